@@ -1,6 +1,6 @@
 # Instanced Rendering demo
 # Demonstrates automatic draw call batching for entities sharing the same mesh + material.
-# 1000 cubes are rendered — entities with the same material are batched into instanced draws.
+# 10,000 cubes are rendered — entities with the same material are batched into instanced draws.
 
 using OpenReality
 
@@ -17,11 +17,12 @@ mat_green = MaterialComponent(color=RGB{Float32}(0.1, 0.8, 0.2), metallic=0.3f0,
 mat_gold  = MaterialComponent(color=RGB{Float32}(1.0, 0.84, 0.0), metallic=0.95f0, roughness=0.1f0)
 materials = [mat_red, mat_blue, mat_green, mat_gold]
 
-# Generate 1000 cubes in a grid, cycling through materials
+# Generate 10,000 cubes in a grid, cycling through materials
 cube_entities = []
-grid_size = 10  # 10x10x10 = 1000
+grid_size = 25  # 25x16x25 = 10,000
+grid_height = 16
 spacing = 2.5
-for ix in 1:grid_size, iy in 1:grid_size, iz in 1:grid_size
+for ix in 1:grid_size, iy in 1:grid_height, iz in 1:grid_size
     x = Float64((ix - grid_size/2) * spacing)
     y = Float64((iy - 1) * spacing + 0.5)
     z = Float64(-(iz - 1) * spacing - 5)
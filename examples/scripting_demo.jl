@@ -59,6 +59,24 @@ function make_spin_script(rotation_speed::Float64=1.0)
     )
 end
 
+# The same bobbing behavior for the web runtime. Closures can't be exported,
+# so scenes passed to `export_scene` carry this source in the ORSB bundle.
+const BOB_WEB_SCRIPT = """
+let time_acc = 0.0;
+
+fn on_update(dt) {
+    time_acc = time_acc + dt;
+    let p = position(self);
+    p.y = base_y + amplitude * sin(speed * time_acc);
+    set_position(self, p);
+}
+
+fn on_collision_enter(other) {
+    log("collectible", self, "hit by", other);
+    despawn(self);
+}
+"""
+
 function collectible(pos::Vec3d, color::RGB{Float32}, entities_to_destroy::Vector{EntityID}, collision_count::Ref{Int}; base_y::Float64=pos[2])
     entity([
         sphere_mesh(radius=0.4f0),
@@ -72,6 +90,8 @@ function collectible(pos::Vec3d, color::RGB{Float32}, entities_to_destroy::Vecto
         ColliderComponent(shape=SphereShape(0.4f0)),
         RigidBodyComponent(body_type=BODY_KINEMATIC),
         make_bobbing_script(base_y),
+        WebScriptComponent(BOB_WEB_SCRIPT; name="bob",
+                           params=Dict("base_y" => base_y, "speed" => 3.0, "amplitude" => 0.5)),
         CollisionCallbackComponent(
             on_collision_enter = (this_eid, other_eid, manifold) -> begin
                 collision_count[] += 1
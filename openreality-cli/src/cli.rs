@@ -45,6 +45,14 @@ pub enum Command {
     },
     /// Run the Julia test suite
     Test,
+    /// Watch an exported .orsb bundle and hot-reload it in connected browsers
+    Serve {
+        /// Path to the .orsb bundle to watch
+        bundle: String,
+        /// WebSocket port
+        #[arg(long, default_value_t = 8765)]
+        port: u16,
+    },
 }
 
 #[derive(Subcommand)]
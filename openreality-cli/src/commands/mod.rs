@@ -2,4 +2,5 @@ pub mod build_cmd;
 pub mod init;
 pub mod new;
 pub mod run_cmd;
+pub mod serve;
 pub mod test_cmd;
@@ -0,0 +1,88 @@
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+use tokio_tungstenite::tungstenite::Message;
+
+/// How often the bundle's modification time is checked.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Watch an exported `.orsb` bundle and push every new version to connected
+/// web runtimes over a WebSocket. Clients receive the current bundle on
+/// connect, then one binary message per change, which they pass to
+/// `App::reload_scene`.
+pub async fn run(bundle: String, port: u16) -> anyhow::Result<()> {
+    let path = PathBuf::from(&bundle);
+    let data = tokio::fs::read(&path)
+        .await
+        .map_err(|e| anyhow::anyhow!("Cannot read {}: {e}", path.display()))?;
+    let (tx, rx) = watch::channel(data);
+
+    let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+    println!("Serving {} on ws://127.0.0.1:{port}", path.display());
+    println!("Re-export the scene to push it to connected browsers (Ctrl+C to stop)");
+
+    tokio::spawn(watch_bundle(path, tx));
+
+    loop {
+        let (stream, addr) = listener.accept().await?;
+        let rx = rx.clone();
+        tokio::spawn(async move {
+            if let Err(e) = serve_client(stream, rx).await {
+                eprintln!("[serve] {addr}: {e}");
+            }
+        });
+    }
+}
+
+async fn watch_bundle(path: PathBuf, tx: watch::Sender<Vec<u8>>) {
+    let modified = |path: &PathBuf| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    let mut last: Option<SystemTime> = modified(&path);
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+        let current = modified(&path);
+        if current.is_none() || current == last {
+            continue;
+        }
+        // The exporter may still be writing; wait until the file stops changing
+        tokio::time::sleep(POLL_INTERVAL).await;
+        if modified(&path) != current {
+            continue;
+        }
+        last = current;
+        match tokio::fs::read(&path).await {
+            Ok(data) => {
+                println!("[serve] {} changed ({} bytes), pushing to {} client(s)", path.display(), data.len(), tx.receiver_count() - 1);
+                let _ = tx.send(data);
+            }
+            Err(e) => eprintln!("[serve] Failed to read {}: {e}", path.display()),
+        }
+    }
+}
+
+async fn serve_client(stream: TcpStream, mut rx: watch::Receiver<Vec<u8>>) -> anyhow::Result<()> {
+    let mut ws = tokio_tungstenite::accept_async(stream).await?;
+    let data = rx.borrow_and_update().clone();
+    ws.send(Message::Binary(data)).await?;
+    loop {
+        tokio::select! {
+            changed = rx.changed() => {
+                if changed.is_err() {
+                    return Ok(());
+                }
+                let data = rx.borrow_and_update().clone();
+                ws.send(Message::Binary(data)).await?;
+            }
+            // Keep reading so pings get their pong and a close is answered;
+            // the stream ends once the closing handshake is done
+            incoming = ws.next() => match incoming {
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e.into()),
+                None => return Ok(()),
+            },
+        }
+    }
+}
@@ -63,6 +63,7 @@ async fn main() -> Result<()> {
             let ctx = project::detect_project_context()?;
             commands::test_cmd::run(ctx).await
         }
+        Some(cli::Command::Serve { bundle, port }) => commands::serve::run(bundle, port).await,
     }
 }
 
@@ -7,9 +7,13 @@ struct PostProcessParams {
     gamma: f32,
     tone_mapping_mode: i32,
     horizontal: i32,
-    _pad1: f32,
-    _pad2: f32,
-    _pad3: f32,
+    chromatic_aberration: f32,
+    vignette_intensity: f32,
+    vignette_radius: f32,
+    vignette_softness: f32,
+    grain_intensity: f32,
+    grain_seed: f32,
+    output_peak: f32,
 };
 
 @group(0) @binding(0) var<uniform> params: PostProcessParams;
@@ -1,4 +1,5 @@
-// Bloom composite + tone mapping + gamma correction.
+// Bloom composite + exposure + tone mapping + gamma correction, with
+// chromatic aberration, vignette and film grain.
 
 struct PostProcessParams {
     bloom_threshold: f32,
@@ -6,9 +7,13 @@ struct PostProcessParams {
     gamma: f32,
     tone_mapping_mode: i32,
     horizontal: i32,
-    _pad1: f32,
-    _pad2: f32,
-    _pad3: f32,
+    chromatic_aberration: f32,
+    vignette_intensity: f32,
+    vignette_radius: f32,
+    vignette_softness: f32,
+    grain_intensity: f32,
+    grain_seed: f32,
+    output_peak: f32,
 };
 
 @group(0) @binding(0) var<uniform> params: PostProcessParams;
@@ -16,6 +21,13 @@ struct PostProcessParams {
 @group(0) @binding(2) var bloom_texture: texture_2d<f32>;
 @group(0) @binding(3) var tex_sampler: sampler;
 
+struct ExposureState {
+    ev100: f32,
+    exposure: f32,
+};
+
+@group(0) @binding(4) var<storage, read> exposure_state: ExposureState;
+
 struct FragmentInput {
     @location(0) uv: vec2<f32>,
 };
@@ -43,27 +55,115 @@ fn uncharted2_tonemap(x: vec3<f32>) -> vec3<f32> {
     return ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F;
 }
 
+// AgX with the default look, after the polynomial fit by Benjamin Wrensch.
+// Returns linear colors like the other operators.
+fn agx_contrast(x: vec3<f32>) -> vec3<f32> {
+    let x2 = x * x;
+    let x4 = x2 * x2;
+    return 15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x - 0.00232;
+}
+
+fn agx(color: vec3<f32>) -> vec3<f32> {
+    let inset = mat3x3<f32>(
+        vec3<f32>(0.842479062253094, 0.0423282422610123, 0.0423756549057051),
+        vec3<f32>(0.0784335999999992, 0.878468636469772, 0.0784336),
+        vec3<f32>(0.0792237451477643, 0.0791661274605434, 0.879142973793104),
+    );
+    let outset = mat3x3<f32>(
+        vec3<f32>(1.19687900512017, -0.0528968517574562, -0.0529716355144438),
+        vec3<f32>(-0.0980208811401368, 1.15190312990417, -0.0980434501171241),
+        vec3<f32>(-0.0990297440797205, -0.0989611768448433, 1.15107367264116),
+    );
+    let min_ev = -12.47393;
+    let max_ev = 4.026069;
+
+    var v = inset * max(color, vec3<f32>(1e-10));
+    v = clamp(log2(v), vec3<f32>(min_ev), vec3<f32>(max_ev));
+    v = agx_contrast((v - min_ev) / (max_ev - min_ev));
+    v = outset * v;
+    return pow(max(v, vec3<f32>(0.0)), vec3<f32>(2.2));
+}
+
+// Khronos PBR Neutral: keeps base colors up to 0.8 unchanged.
+fn pbr_neutral(input: vec3<f32>) -> vec3<f32> {
+    let start_compression = 0.8 - 0.04;
+    let desaturation = 0.15;
+
+    var color = input;
+    let x = min(color.r, min(color.g, color.b));
+    let offset = select(0.04, x - 6.25 * x * x, x < 0.08);
+    color -= offset;
+
+    let peak = max(color.r, max(color.g, color.b));
+    if peak < start_compression {
+        return color;
+    }
+    let d = 1.0 - start_compression;
+    let new_peak = 1.0 - d * d / (peak + d - start_compression);
+    color *= new_peak / peak;
+    let g = 1.0 - 1.0 / (desaturation * (peak - new_peak) + 1.0);
+    return mix(color, vec3<f32>(new_peak), g);
+}
+
+fn grain_hash(p: vec2<f32>) -> f32 {
+    return fract(sin(dot(p, vec2<f32>(12.9898, 78.233))) * 43758.5453);
+}
+
 @fragment
 fn fs_main(in: FragmentInput) -> @location(0) vec4<f32> {
-    var hdr_color = textureSample(scene_texture, tex_sampler, in.uv).rgb;
+    // Chromatic aberration: red and blue pulled apart towards the corners
+    var hdr_color: vec3<f32>;
+    if params.chromatic_aberration > 0.0 {
+        let shift = (in.uv - 0.5) * params.chromatic_aberration;
+        hdr_color = vec3<f32>(
+            textureSample(scene_texture, tex_sampler, clamp(in.uv - shift, vec2<f32>(0.0), vec2<f32>(1.0))).r,
+            textureSample(scene_texture, tex_sampler, in.uv).g,
+            textureSample(scene_texture, tex_sampler, clamp(in.uv + shift, vec2<f32>(0.0), vec2<f32>(1.0))).b,
+        );
+    } else {
+        hdr_color = textureSample(scene_texture, tex_sampler, in.uv).rgb;
+    }
     let bloom = textureSample(bloom_texture, tex_sampler, in.uv).rgb;
 
-    // Add bloom
+    // Add bloom, then expose
     hdr_color += bloom * params.bloom_intensity;
+    hdr_color *= exposure_state.exposure;
 
-    // Tone mapping
+    // Tone mapping onto 0..output_peak: the curves map to 0..1, so on HDR
+    // outputs they run on the scene scaled down to the display's range
+    let peak = params.output_peak;
+    let scaled = hdr_color / peak;
     var mapped: vec3<f32>;
     if params.tone_mapping_mode == 0 {
-        mapped = reinhard(hdr_color);
+        mapped = reinhard(scaled);
     } else if params.tone_mapping_mode == 1 {
-        mapped = aces(hdr_color);
-    } else {
+        mapped = aces(scaled);
+    } else if params.tone_mapping_mode == 2 {
         let W = 11.2;
-        mapped = uncharted2_tonemap(hdr_color * 2.0) / uncharted2_tonemap(vec3<f32>(W));
+        mapped = uncharted2_tonemap(scaled * 2.0) / uncharted2_tonemap(vec3<f32>(W));
+    } else if params.tone_mapping_mode == 3 {
+        mapped = agx(scaled);
+    } else {
+        mapped = pbr_neutral(scaled);
+    }
+    mapped *= peak;
+
+    // Vignette (darkens edges of the screen)
+    if params.vignette_intensity > 0.0 {
+        let dist = length(in.uv * 2.0 - 1.0);
+        let vignette = 1.0 - smoothstep(params.vignette_radius, params.vignette_radius + params.vignette_softness, dist);
+        mapped *= mix(1.0, vignette, params.vignette_intensity);
     }
 
     // Gamma correction
-    mapped = pow(mapped, vec3<f32>(1.0 / params.gamma));
+    mapped = pow(max(mapped, vec3<f32>(0.0)), vec3<f32>(1.0 / params.gamma));
+
+    // Film grain, strongest in the midtones
+    if params.grain_intensity > 0.0 {
+        let noise = grain_hash(in.uv * 1024.0 + params.grain_seed) - 0.5;
+        let luminance = min(dot(mapped, vec3<f32>(0.2126, 0.7152, 0.0722)), 1.0);
+        mapped += vec3<f32>(noise * params.grain_intensity * (1.0 - abs(luminance * 2.0 - 1.0)));
+    }
 
     return vec4<f32>(mapped, 1.0);
 }
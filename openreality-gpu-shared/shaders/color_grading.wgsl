@@ -0,0 +1,63 @@
+// Color grading in display space: a 3D LUT (e.g. a .cube file exported from
+// a grading tool), then brightness, contrast and saturation.
+
+struct ColorGradingParams {
+    brightness: f32,
+    contrast: f32,
+    saturation: f32,
+    lut_strength: f32,
+    lut_domain_min: vec3<f32>,
+    lut_size: f32,
+    lut_domain_max: vec3<f32>,
+    output_max: f32,
+};
+
+@group(0) @binding(0) var<uniform> params: ColorGradingParams;
+@group(0) @binding(1) var input_texture: texture_2d<f32>;
+@group(0) @binding(2) var tex_sampler: sampler;
+// Rgba32Float, which is not filterable everywhere, so filtered by hand
+@group(0) @binding(3) var lut_texture: texture_3d<f32>;
+
+struct FragmentInput {
+    @location(0) uv: vec2<f32>,
+};
+
+fn lut_entry(p: vec3<i32>) -> vec3<f32> {
+    return textureLoad(lut_texture, p, 0).rgb;
+}
+
+// Trilinear lookup of `color`, mapped from the LUT domain onto its entries.
+fn apply_lut(color: vec3<f32>) -> vec3<f32> {
+    let t = clamp((color - params.lut_domain_min) / (params.lut_domain_max - params.lut_domain_min), vec3<f32>(0.0), vec3<f32>(1.0));
+    let p = t * (params.lut_size - 1.0);
+    let p0 = vec3<i32>(floor(p));
+    let p1 = min(p0 + vec3<i32>(1), vec3<i32>(i32(params.lut_size) - 1));
+    let f = p - floor(p);
+
+    let c00 = mix(lut_entry(p0), lut_entry(vec3<i32>(p1.x, p0.y, p0.z)), f.x);
+    let c10 = mix(lut_entry(vec3<i32>(p0.x, p1.y, p0.z)), lut_entry(vec3<i32>(p1.x, p1.y, p0.z)), f.x);
+    let c01 = mix(lut_entry(vec3<i32>(p0.x, p0.y, p1.z)), lut_entry(vec3<i32>(p1.x, p0.y, p1.z)), f.x);
+    let c11 = mix(lut_entry(vec3<i32>(p0.x, p1.y, p1.z)), lut_entry(p1), f.x);
+    return mix(mix(c00, c10, f.y), mix(c01, c11, f.y), f.z);
+}
+
+@fragment
+fn fs_main(in: FragmentInput) -> @location(0) vec4<f32> {
+    var color = textureSample(input_texture, tex_sampler, in.uv).rgb;
+
+    // LUTs cover SDR; HDR highlights above 1 keep their excess on top
+    if params.lut_strength > 0.0 {
+        let sdr = min(color, vec3<f32>(1.0));
+        color = mix(color, apply_lut(sdr) + (color - sdr), params.lut_strength);
+    }
+
+    color += vec3<f32>(params.brightness);
+
+    // Contrast (pivot around mid-gray 0.5)
+    color = (color - 0.5) * params.contrast + 0.5;
+
+    let luminance = dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+    color = mix(vec3<f32>(luminance), color, params.saturation);
+
+    return vec4<f32>(clamp(color, vec3<f32>(0.0), vec3<f32>(params.output_max)), 1.0);
+}
@@ -0,0 +1,158 @@
+// Deferred decals — drawn into the G-Buffer after the geometry pass and
+// before lighting. Each decal rasterizes the back faces of its box (so the
+// camera may be inside it), reconstructs the surface under every pixel from
+// depth and, where that surface lies inside the box, blends the decal over
+// the albedo and normal targets with fixed-function blending:
+//   fs_main       albedo (alpha = coverage, metallic kept) and normal
+//                 (alpha = coverage, roughness kept)
+//   fs_roughness  roughness only: the normal target's alpha blends the
+//                 blend constant (the decal's roughness) by the coverage
+
+const DEPTH_FADE: f32 = 0.05;
+
+struct PerFrame {
+    view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    inv_view_proj: mat4x4<f32>,
+    camera_pos: vec4<f32>,
+    time: f32,
+    _pad1: f32,
+    _pad2: f32,
+    _pad3: f32,
+};
+
+struct Decal {
+    model: mat4x4<f32>,
+    world_to_decal: mat4x4<f32>,
+    tangent: vec4<f32>,
+    bitangent: vec4<f32>,
+    normal: vec4<f32>,
+    color: vec4<f32>,
+    normal_strength: f32,
+    roughness: f32,
+    roughness_opacity: f32,
+    fade_start: f32,
+    fade_end: f32,
+    has_albedo_map: u32,
+    has_normal_map: u32,
+    _pad1: f32,
+};
+
+@group(0) @binding(0) var<uniform> frame: PerFrame;
+@group(1) @binding(0) var<storage, read> decals: array<Decal>;
+// Scene depth as plain floats; naga maps depth textures to shadow samplers on GL
+@group(1) @binding(1) var g_depth: texture_2d<f32>;
+@group(1) @binding(2) var albedo_map: texture_2d<f32>;
+@group(1) @binding(3) var normal_map: texture_2d<f32>;
+@group(1) @binding(4) var decal_sampler: sampler;
+
+// Two outward-facing triangles per face of the unit cube; corner i sits at
+// (i & 1, (i >> 1) & 1, (i >> 2) & 1) - 0.5
+const CUBE_INDICES = array<u32, 36>(
+    0u, 2u, 1u, 1u, 2u, 3u,
+    4u, 5u, 6u, 5u, 7u, 6u,
+    0u, 4u, 2u, 2u, 4u, 6u,
+    1u, 3u, 5u, 3u, 7u, 5u,
+    0u, 1u, 4u, 1u, 5u, 4u,
+    2u, 6u, 3u, 3u, 6u, 7u,
+);
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) @interpolate(flat) decal: u32,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex: u32, @builtin(instance_index) instance: u32) -> VertexOutput {
+    let corner = CUBE_INDICES[vertex];
+    let local = vec3<f32>(f32(corner & 1u), f32((corner >> 1u) & 1u), f32((corner >> 2u) & 1u)) - 0.5;
+    var out: VertexOutput;
+    out.position = frame.projection * frame.view * decals[instance].model * vec4<f32>(local, 1.0);
+    out.decal = instance;
+    return out;
+}
+
+struct DecalOutput {
+    @location(0) albedo_metallic: vec4<f32>,
+    @location(1) normal_roughness: vec4<f32>,
+};
+
+struct DecalSurface {
+    uv: vec2<f32>,
+    // Coverage before the decal's opacities: albedo alpha times the fades
+    mask: f32,
+    geometric_normal: vec3<f32>,
+};
+
+// The decal's image coordinates and coverage on the surface under `frag_coord`.
+// Discards pixels the decal does not reach.
+fn decal_surface(d: Decal, frag_coord: vec4<f32>) -> DecalSurface {
+    let pixel = vec2<i32>(frag_coord.xy);
+    let depth = textureLoad(g_depth, pixel, 0).r;
+    let size = vec2<f32>(textureDimensions(g_depth));
+    let ndc = vec2<f32>(frag_coord.x / size.x * 2.0 - 1.0, 1.0 - frag_coord.y / size.y * 2.0);
+    let world_h = frame.inv_view_proj * vec4<f32>(ndc, depth, 1.0);
+    let world_pos = world_h.xyz / world_h.w;
+
+    // Face normal from the neighbouring pixels, taken before any discard
+    var n = normalize(cross(dpdx(world_pos), dpdy(world_pos)));
+    if dot(n, frame.camera_pos.xyz - world_pos) < 0.0 {
+        n = -n;
+    }
+
+    let local = (d.world_to_decal * vec4<f32>(world_pos, 1.0)).xyz;
+    if depth >= 1.0 || any(abs(local) > vec3<f32>(0.5)) {
+        discard;
+    }
+    let angle = saturate((dot(n, d.normal.xyz) - d.fade_end) / (d.fade_start - d.fade_end));
+    let edge = saturate((0.5 - abs(local.z)) / DEPTH_FADE);
+
+    var out: DecalSurface;
+    out.uv = vec2<f32>(local.x + 0.5, 0.5 - local.y);
+    out.mask = angle * edge;
+    if d.has_albedo_map != 0u {
+        out.mask *= textureSampleLevel(albedo_map, decal_sampler, out.uv, 0.0).a;
+    }
+    out.geometric_normal = n;
+    if out.mask <= 0.0 {
+        discard;
+    }
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> DecalOutput {
+    let d = decals[in.decal];
+    let surface = decal_surface(d, in.position);
+
+    var albedo = d.color.rgb;
+    if d.has_albedo_map != 0u {
+        albedo *= textureSampleLevel(albedo_map, decal_sampler, surface.uv, 0.0).rgb;
+    }
+
+    // Tangent-space normal map on the box's x/y axes, facing its +z
+    var normal = surface.geometric_normal;
+    var normal_weight = 0.0;
+    if d.has_normal_map != 0u {
+        let tangent_normal = textureSampleLevel(normal_map, decal_sampler, surface.uv, 0.0).xyz * 2.0 - 1.0;
+        let TBN = mat3x3<f32>(d.tangent.xyz, d.bitangent.xyz, d.normal.xyz);
+        normal = normalize(TBN * tangent_normal);
+        normal_weight = surface.mask * d.normal_strength;
+    }
+
+    var out: DecalOutput;
+    out.albedo_metallic = vec4<f32>(albedo, surface.mask * d.color.a);
+    out.normal_roughness = vec4<f32>(normal * 0.5 + 0.5, normal_weight);
+    return out;
+}
+
+@fragment
+fn fs_roughness(in: VertexOutput) -> DecalOutput {
+    let d = decals[in.decal];
+    let surface = decal_surface(d, in.position);
+
+    var out: DecalOutput;
+    out.albedo_metallic = vec4<f32>(0.0);
+    out.normal_roughness = vec4<f32>(0.0, 0.0, 0.0, surface.mask * d.roughness_opacity);
+    return out;
+}
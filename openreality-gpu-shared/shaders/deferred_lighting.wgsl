@@ -18,6 +18,46 @@ struct PointLight {
     color: vec4<f32>,
     intensity: f32,
     range: f32,
+    shadow_index: i32,
+    _pad: f32,
+};
+
+struct SpotLight {
+    position: vec4<f32>,
+    direction: vec4<f32>,
+    color: vec4<f32>,
+    intensity: f32,
+    range: f32,
+    inner_cos: f32,
+    outer_cos: f32,
+    shadow_index: i32,
+    _pad1: f32,
+    _pad2: f32,
+    _pad3: f32,
+};
+
+// Rect: position ± axis_x ± axis_y, emitting along cross(axis_x, axis_y).
+// Tube: segment position ± axis_x with thickness `radius`.
+struct AreaLight {
+    position: vec4<f32>,
+    axis_x: vec4<f32>,
+    axis_y: vec4<f32>,
+    color: vec4<f32>,
+    intensity: f32,
+    range: f32,
+    radius: f32,
+    shape: u32,
+};
+
+const AREA_LIGHT_RECT: u32 = 0u;
+
+// Shadow projection of a spot light or one point light cube face, rendered
+// into the `atlas_rect` tile of the shadow atlas.
+struct LocalShadow {
+    view_proj: mat4x4<f32>,
+    atlas_rect: vec4<f32>,
+    texel_size: f32,
+    depth_bias: f32,
     _pad1: f32,
     _pad2: f32,
 };
@@ -32,28 +72,111 @@ struct DirLight {
 };
 
 struct LightData {
-    point_lights: array<PointLight, 16>,
     dir_lights: array<DirLight, 4>,
     num_point_lights: i32,
     num_dir_lights: i32,
     has_ibl: i32,
     ibl_intensity: f32,
+    num_spot_lights: i32,
+    num_area_lights: i32,
+    _pad1: i32,
+    _pad2: i32,
 };
 
+struct ClusterParams {
+    inv_projection: mat4x4<f32>,
+    view: mat4x4<f32>,
+    grid: vec4<u32>,
+    near: f32,
+    far: f32,
+    _pad1: f32,
+    _pad2: f32,
+};
+
+struct CascadeData {
+    view_proj: mat4x4<f32>,
+    split_depth: f32,
+    texel_size: f32,
+    depth_range: f32,
+    _pad: f32,
+};
+
+struct CsmSettings {
+    filter_mode: u32,
+    pcf_samples: u32,
+    pcf_radius: f32,
+    light_size: f32,
+    blend_band: f32,
+    depth_bias: f32,
+    normal_bias: f32,
+    slope_bias: f32,
+    evsm_exponents: vec2<f32>,
+    light_bleed_reduction: f32,
+    stabilize: u32,
+};
+
+struct ShadowUniforms {
+    cascades: array<CascadeData, 4>,
+    num_cascades: i32,
+    _pad1: f32,
+    _pad2: f32,
+    _pad3: f32,
+    settings: CsmSettings,
+};
+
+struct Environment {
+    irradiance_sh: array<vec4<f32>, 9>,
+    sun_direction: vec4<f32>,   // xyz = towards the sun, w = cos of the disk's angular radius
+    sun_radiance: vec4<f32>,
+    has_sky: i32,
+    fog_enabled: i32,
+    fog_near: f32,
+    fog_far: f32,
+    sky_mip_count: f32,
+    _pad1: f32,
+    _pad2: f32,
+    _pad3: f32,
+};
+
+const MAX_LIGHTS_PER_CLUSTER: u32 = 128u;
+
 // Bind group 0: per-frame + G-Buffer textures
 @group(0) @binding(0) var<uniform> frame: PerFrame;
 @group(0) @binding(1) var g_albedo_metallic: texture_2d<f32>;
 @group(0) @binding(2) var g_normal_roughness: texture_2d<f32>;
 @group(0) @binding(3) var g_emissive_ao: texture_2d<f32>;
 @group(0) @binding(4) var g_advanced_material: texture_2d<f32>;
-@group(0) @binding(5) var g_depth: texture_depth_2d;
+@group(0) @binding(5) var g_depth: texture_2d<f32>;
 @group(0) @binding(6) var ssao_texture: texture_2d<f32>;
 @group(0) @binding(7) var ssr_texture: texture_2d<f32>;
 @group(0) @binding(8) var gbuffer_sampler: sampler;
 @group(0) @binding(9) var depth_sampler: sampler;
 
-// Bind group 1: light data
+// Bind group 1: light data, clustered point lights, spot and area lights,
+// local light shadow atlas
 @group(1) @binding(0) var<uniform> lights: LightData;
+@group(1) @binding(1) var<storage, read> point_lights: array<PointLight>;
+@group(1) @binding(2) var<uniform> clusters: ClusterParams;
+@group(1) @binding(3) var<storage, read> cluster_counts: array<u32>;
+@group(1) @binding(4) var<storage, read> cluster_indices: array<u32>;
+@group(1) @binding(5) var<storage, read> spot_lights: array<SpotLight>;
+@group(1) @binding(6) var<storage, read> area_lights: array<AreaLight>;
+@group(1) @binding(7) var shadow_atlas: texture_depth_2d;
+@group(1) @binding(8) var<storage, read> local_shadows: array<LocalShadow>;
+@group(1) @binding(9) var shadow_sampler: sampler_comparison;
+@group(1) @binding(10) var<uniform> shadow: ShadowUniforms;
+@group(1) @binding(11) var csm_depth: texture_depth_2d_array;
+@group(1) @binding(12) var csm_moments: texture_2d_array<f32>;
+@group(1) @binding(13) var csm_sampler: sampler;
+// The cascades again as plain floats for the PCSS blocker search, since GL can
+// only sample depth textures with comparisons
+@group(1) @binding(14) var csm_depth_values: texture_2d_array<f32>;
+
+// Bind group 2: sky cubemap and integrated fog volume
+@group(2) @binding(0) var<uniform> env: Environment;
+@group(2) @binding(1) var sky_cubemap: texture_cube<f32>;
+@group(2) @binding(2) var fog_volume: texture_3d<f32>;
+@group(2) @binding(3) var env_sampler: sampler;
 
 struct FragmentInput {
     @location(0) uv: vec2<f32>,
@@ -65,6 +188,18 @@ fn reconstruct_world_pos(uv: vec2<f32>, depth: f32) -> vec3<f32> {
     return world_pos.xyz / world_pos.w;
 }
 
+// Froxel containing `world_pos`, matching the binning in light_cluster.wgsl
+fn cluster_index(world_pos: vec3<f32>) -> u32 {
+    let view_pos = frame.view * vec4<f32>(world_pos, 1.0);
+    let clip = frame.projection * view_pos;
+    let ndc = clamp(clip.xy / clip.w, vec2<f32>(-1.0), vec2<f32>(1.0));
+    let tile = min(vec2<u32>((ndc * 0.5 + 0.5) * vec2<f32>(clusters.grid.xy)), clusters.grid.xy - 1u);
+    let depth = max(-view_pos.z, clusters.near);
+    let t = log(depth / clusters.near) / log(clusters.far / clusters.near);
+    let slice = min(u32(t * f32(clusters.grid.z)), clusters.grid.z - 1u);
+    return tile.x + clusters.grid.x * (tile.y + clusters.grid.y * slice);
+}
+
 fn distribution_ggx(N: vec3<f32>, H: vec3<f32>, roughness: f32) -> f32 {
     let a = roughness * roughness;
     let a2 = a * a;
@@ -88,16 +223,429 @@ fn fresnel_schlick(cos_theta: f32, F0: vec3<f32>) -> vec3<f32> {
     return F0 + (1.0 - F0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
 }
 
+// Cook-Torrance BRDF (diffuse + specular) for light arriving along L
+fn surface_brdf(N: vec3<f32>, V: vec3<f32>, L: vec3<f32>, albedo: vec3<f32>,
+                metallic: f32, roughness: f32, F0: vec3<f32>) -> vec3<f32> {
+    let H = normalize(V + L);
+    let D = distribution_ggx(N, H, roughness);
+    let G = geometry_smith(N, V, L, roughness);
+    let F = fresnel_schlick(max(dot(H, V), 0.0), F0);
+    let specular = (D * G * F) / (4.0 * max(dot(N, V), 0.0) * max(dot(N, L), 0.0) + 0.0001);
+    let kD = (vec3<f32>(1.0) - F) * (1.0 - metallic);
+    return kD * albedo / PI + specular;
+}
+
+// Visibility (1 = lit) of P from the local light shadow record `index`.
+// Points outside the tile's frustum count as lit.
+fn local_shadow(index: i32, P: vec3<f32>, N: vec3<f32>, light_pos: vec3<f32>) -> f32 {
+    let s = local_shadows[index];
+    let offset = N * s.texel_size * distance(light_pos, P) * 1.5;
+    let clip = s.view_proj * vec4<f32>(P + offset, 1.0);
+    if clip.w <= 0.0 {
+        return 1.0;
+    }
+    let ndc = clip.xyz / clip.w;
+    if any(abs(ndc.xy) > vec2<f32>(1.0)) || ndc.z > 1.0 {
+        return 1.0;
+    }
+    let half_texel = 0.5 / vec2<f32>(textureDimensions(shadow_atlas));
+    let tile_uv = ndc.xy * vec2<f32>(0.5, -0.5) + 0.5;
+    let uv = clamp(s.atlas_rect.xy + tile_uv * s.atlas_rect.zw,
+                   s.atlas_rect.xy + half_texel, s.atlas_rect.xy + s.atlas_rect.zw - half_texel);
+    return textureSampleCompareLevel(shadow_atlas, shadow_sampler, uv, ndc.z - s.depth_bias);
+}
+
+// ---- Cascaded shadow maps ----
+
+const SHADOW_FILTER_HARD: u32 = 0u;
+const SHADOW_FILTER_PCSS: u32 = 2u;
+const SHADOW_FILTER_EVSM: u32 = 3u;
+const MAX_PCF_SAMPLES: u32 = 32u;
+const MAX_PENUMBRA_TEXELS: f32 = 32.0;
+
+// Poisson disk in the unit circle, in best-candidate order so the first
+// pcf_samples taps are spread evenly for any sample count
+const POISSON_DISK = array<vec2<f32>, 32>(
+    vec2<f32>(0.3320, 0.4622), vec2<f32>(-0.6023, -0.7722), vec2<f32>(0.5223, -0.6518), vec2<f32>(-0.8623, 0.4056),
+    vec2<f32>(0.9814, -0.0511), vec2<f32>(-0.2493, 0.9536), vec2<f32>(-0.1495, -0.1520), vec2<f32>(-0.9473, -0.1730),
+    vec2<f32>(-0.0301, -0.9507), vec2<f32>(-0.3158, 0.4051), vec2<f32>(0.4616, -0.1148), vec2<f32>(0.7313, 0.6611),
+    vec2<f32>(-0.2255, -0.5766), vec2<f32>(0.3645, 0.9055), vec2<f32>(-0.6048, 0.0766), vec2<f32>(0.1372, -0.4216),
+    vec2<f32>(0.1309, 0.1296), vec2<f32>(-0.6141, 0.7635), vec2<f32>(0.6704, 0.2169), vec2<f32>(-0.4803, -0.2515),
+    vec2<f32>(0.7497, -0.3609), vec2<f32>(0.0123, 0.6410), vec2<f32>(-0.7114, -0.4896), vec2<f32>(0.2254, -0.6806),
+    vec2<f32>(-0.3354, -0.9047), vec2<f32>(0.3893, -0.9072), vec2<f32>(0.9268, 0.3379), vec2<f32>(0.3988, 0.1864),
+    vec2<f32>(0.0305, 0.9545), vec2<f32>(-0.2923, 0.1112), vec2<f32>(-0.9711, 0.1361), vec2<f32>(-0.5899, 0.3429),
+);
+
+// Poisson tap `i` rotated by `phi`
+fn poisson_tap(i: u32, phi: f32) -> vec2<f32> {
+    let c = cos(phi);
+    let s = sin(phi);
+    let p = POISSON_DISK[i];
+    return vec2<f32>(c * p.x - s * p.y, s * p.x + c * p.y);
+}
+
+// Per-pixel rotation noise, stable under TAA jitter
+fn interleaved_gradient_noise(pixel: vec2<f32>) -> f32 {
+    return fract(52.9829189 * fract(dot(pixel, vec2<f32>(0.06711056, 0.00583715))));
+}
+
+// Shadow map UV and 0..1 depth of P in cascade c
+fn cascade_coords(c: i32, P: vec3<f32>) -> vec3<f32> {
+    let clip = shadow.cascades[c].view_proj * vec4<f32>(P, 1.0);
+    return vec3<f32>(clip.xy * vec2<f32>(0.5, -0.5) + 0.5, clip.z);
+}
+
+// Average of pcf_samples depth comparisons in a disk of `radius` texels
+fn csm_pcf(uv: vec2<f32>, c: i32, depth: f32, radius: f32, phi: f32) -> f32 {
+    let texel = 1.0 / vec2<f32>(textureDimensions(csm_depth));
+    let n = clamp(shadow.settings.pcf_samples, 1u, MAX_PCF_SAMPLES);
+    var lit = 0.0;
+    for (var i = 0u; i < n; i++) {
+        let tap = uv + poisson_tap(i, phi) * radius * texel;
+        lit += textureSampleCompareLevel(csm_depth, shadow_sampler, tap, c, depth);
+    }
+    return lit / f32(n);
+}
+
+// PCSS penumbra width in texels from the average blocker depth around uv,
+// or 0 when nothing occludes the receiver.
+fn csm_penumbra(uv: vec2<f32>, c: i32, depth: f32, phi: f32) -> f32 {
+    let cascade = shadow.cascades[c];
+    let light_size = shadow.settings.light_size;
+    let dims = vec2<f32>(textureDimensions(csm_depth));
+    let search = clamp(light_size * depth * cascade.depth_range / cascade.texel_size, 1.0, MAX_PENUMBRA_TEXELS);
+    let n = clamp(shadow.settings.pcf_samples, 1u, MAX_PCF_SAMPLES);
+    var blocker_sum = 0.0;
+    var blockers = 0.0;
+    for (var i = 0u; i < n; i++) {
+        let tap = (uv + poisson_tap(i, phi) * search / dims) * dims;
+        let texel = clamp(vec2<i32>(tap), vec2<i32>(0), vec2<i32>(dims) - 1);
+        let d = textureLoad(csm_depth_values, texel, c, 0).r;
+        if d < depth {
+            blocker_sum += d;
+            blockers += 1.0;
+        }
+    }
+    if blockers == 0.0 {
+        return 0.0;
+    }
+    let blocker_depth = blocker_sum / blockers;
+    let penumbra = light_size * (depth - blocker_depth) * cascade.depth_range / cascade.texel_size;
+    return clamp(penumbra, 1.0, MAX_PENUMBRA_TEXELS);
+}
+
+// Chebyshev upper bound of the lit fraction for warped depth t, with light
+// bleeding below light_bleed_reduction cut off
+fn chebyshev_upper_bound(moments: vec2<f32>, t: f32) -> f32 {
+    if t <= moments.x {
+        return 1.0;
+    }
+    let variance = max(moments.y - moments.x * moments.x, 1e-5);
+    let delta = t - moments.x;
+    let p = variance / (variance + delta * delta);
+    let r = shadow.settings.light_bleed_reduction;
+    return clamp((p - r) / max(1.0 - r, 1e-4), 0.0, 1.0);
+}
+
+fn csm_evsm(uv: vec2<f32>, c: i32, depth: f32) -> f32 {
+    let moments = textureSampleLevel(csm_moments, csm_sampler, uv, c, 0.0);
+    let k = shadow.settings.evsm_exponents;
+    let d = depth * 2.0 - 1.0;
+    let positive = chebyshev_upper_bound(moments.xy, exp(k.x * d));
+    let negative = chebyshev_upper_bound(moments.zw, -exp(-k.y * d));
+    return min(positive, negative);
+}
+
+// Visibility (1 = lit) of P in cascade c. Points outside the cascade count as lit.
+fn cascade_visibility(c: i32, P: vec3<f32>, N: vec3<f32>, L: vec3<f32>, phi: f32) -> f32 {
+    let cascade = shadow.cascades[c];
+    let s = shadow.settings;
+    let coords = cascade_coords(c, P + N * s.normal_bias * cascade.texel_size);
+    if any(coords.xy < vec2<f32>(0.0)) || any(coords.xy > vec2<f32>(1.0)) || coords.z > 1.0 {
+        return 1.0;
+    }
+
+    if s.filter_mode == SHADOW_FILTER_EVSM {
+        return csm_evsm(coords.xy, c, coords.z);
+    }
+
+    // Depth bias in texels, growing with the tangent of the incidence angle
+    let cos_theta = clamp(dot(N, L), 0.05, 1.0);
+    let tan_theta = min(sqrt(1.0 - cos_theta * cos_theta) / cos_theta, 10.0);
+    let bias = cascade.texel_size * (s.depth_bias + s.slope_bias * tan_theta) / cascade.depth_range;
+    let depth = coords.z - bias;
+
+    if s.filter_mode == SHADOW_FILTER_HARD {
+        return textureSampleCompareLevel(csm_depth, shadow_sampler, coords.xy, c, depth);
+    }
+    if s.filter_mode == SHADOW_FILTER_PCSS {
+        let penumbra = csm_penumbra(coords.xy, c, depth, phi);
+        if penumbra == 0.0 {
+            return 1.0;
+        }
+        return csm_pcf(coords.xy, c, depth, penumbra, phi);
+    }
+    return csm_pcf(coords.xy, c, depth, s.pcf_radius, phi);
+}
+
+// Visibility (1 = lit) of P from the shadowed directional light. The cascade
+// is picked by view depth, and the last blend_band of each cascade fades into
+// the next one (or to lit past the last cascade).
+fn csm_visibility(P: vec3<f32>, N: vec3<f32>, L: vec3<f32>, pixel: vec2<f32>) -> f32 {
+    if shadow.num_cascades == 0 {
+        return 1.0;
+    }
+    let view_depth = -(frame.view * vec4<f32>(P, 1.0)).z;
+    var c = 0;
+    while c < shadow.num_cascades - 1 && view_depth > shadow.cascades[c].split_depth {
+        c++;
+    }
+    let end = shadow.cascades[c].split_depth;
+    if view_depth > end {
+        return 1.0;
+    }
+
+    let phi = interleaved_gradient_noise(pixel) * 6.2831853;
+    let lit = cascade_visibility(c, P, N, L, phi);
+
+    var begin = 0.0;
+    if c > 0 {
+        begin = shadow.cascades[c - 1].split_depth;
+    }
+    let band = (end - begin) * shadow.settings.blend_band;
+    let t = (view_depth - (end - band)) / max(band, 1e-4);
+    if t <= 0.0 {
+        return lit;
+    }
+    var next = 1.0;
+    if c + 1 < shadow.num_cascades {
+        next = cascade_visibility(c + 1, P, N, L, phi);
+    }
+    return mix(lit, next, saturate(t));
+}
+
+// Cube face (+X, -X, +Y, -Y, +Z, -Z) of a point light that sees direction d
+fn cube_face(d: vec3<f32>) -> i32 {
+    let a = abs(d);
+    if a.x >= a.y && a.x >= a.z {
+        return select(1, 0, d.x > 0.0);
+    }
+    if a.y >= a.z {
+        return select(3, 2, d.y > 0.0);
+    }
+    return select(5, 4, d.z > 0.0);
+}
+
+// Smooth window that reaches zero at the light's range
+fn range_falloff(dist: f32, range: f32) -> f32 {
+    let f = clamp(1.0 - pow(dist / max(range, 0.001), 4.0), 0.0, 1.0);
+    return f * f;
+}
+
+fn spot_light_contribution(light: SpotLight, P: vec3<f32>, N: vec3<f32>, V: vec3<f32>,
+                           albedo: vec3<f32>, metallic: f32, roughness: f32, F0: vec3<f32>) -> vec3<f32> {
+    let L_vec = light.position.xyz - P;
+    let dist = length(L_vec);
+    let L = L_vec / max(dist, 0.0001);
+    let cos_angle = dot(-L, normalize(light.direction.xyz));
+    let cone = clamp((cos_angle - light.outer_cos) / max(light.inner_cos - light.outer_cos, 0.0001), 0.0, 1.0);
+    let attenuation = cone * cone * range_falloff(dist, light.range) / (dist * dist + 0.0001);
+    var radiance = light.color.rgb * light.intensity * attenuation;
+    if light.shadow_index >= 0 && dot(radiance, radiance) > 0.0 {
+        radiance *= local_shadow(light.shadow_index, P, N, light.position.xyz);
+    }
+    return surface_brdf(N, V, L, albedo, metallic, roughness, F0) * radiance * max(dot(N, L), 0.0);
+}
+
+// Edge term of the polygon form factor, with the rational fit of
+// theta / sin(theta) from Heitz et al. 2016.
+fn ltc_edge(v1: vec3<f32>, v2: vec3<f32>) -> vec3<f32> {
+    let x = dot(v1, v2);
+    let y = abs(x);
+    let a = 0.8543985 + (0.4965155 + 0.0145206 * y) * y;
+    let b = 3.4175940 + (4.1616724 + y) * y;
+    let v = a / b;
+    let theta_sintheta = select(0.5 * inverseSqrt(max(1.0 - x * x, 1e-7)) - v, v, x > 0.0);
+    return cross(v1, v2) * theta_sintheta;
+}
+
+// Form factor of the quad `corners` seen from P after warping by the linearly
+// transformed cosine `m_inv`. No horizon clipping: quads straddling the
+// shading plane are slightly overestimated.
+fn ltc_evaluate(N: vec3<f32>, V: vec3<f32>, P: vec3<f32>, m_inv: mat3x3<f32>,
+                corners: array<vec3<f32>, 4>) -> f32 {
+    var T1 = V - N * dot(V, N);
+    if dot(T1, T1) < 1e-6 {
+        T1 = cross(N, select(vec3<f32>(1.0, 0.0, 0.0), vec3<f32>(0.0, 1.0, 0.0), abs(N.x) > 0.9));
+    }
+    T1 = normalize(T1);
+    let T2 = cross(N, T1);
+    let to_local = m_inv * transpose(mat3x3<f32>(T1, T2, N));
+
+    var quad = corners;
+    var v: array<vec3<f32>, 4>;
+    for (var i = 0; i < 4; i++) {
+        v[i] = normalize(to_local * (quad[i] - P));
+    }
+    var form_factor = vec3<f32>(0.0);
+    for (var i = 0; i < 4; i++) {
+        form_factor += ltc_edge(v[i], v[(i + 1) % 4]);
+    }
+    return max(form_factor.z, 0.0) / (2.0 * PI);
+}
+
+// Closest point to `p` on the rect center ± ax ± ay
+fn rect_closest_point(center: vec3<f32>, ax: vec3<f32>, ay: vec3<f32>, p: vec3<f32>) -> vec3<f32> {
+    let d = p - center;
+    let u = clamp(dot(d, ax) / max(dot(ax, ax), 1e-8), -1.0, 1.0);
+    let v = clamp(dot(d, ay) / max(dot(ay, ay), 1e-8), -1.0, 1.0);
+    return center + ax * u + ay * v;
+}
+
+// Rect and tube lights. Diffuse irradiance is exact for rects (the LTC
+// integral with an identity matrix, i.e. a clamped cosine) and uses the
+// analytic line integral for tubes. Specular evaluates the BRDF at the point
+// on the light closest to the reflection ray, with roughness widened by the
+// light's apparent size to keep highlights from over-sharpening.
+fn area_light_contribution(light: AreaLight, P: vec3<f32>, N: vec3<f32>, V: vec3<f32>,
+                           albedo: vec3<f32>, metallic: f32, roughness: f32, F0: vec3<f32>) -> vec3<f32> {
+    let center = light.position.xyz;
+    let ax = light.axis_x.xyz;
+    let ay = light.axis_y.xyz;
+    let R = reflect(-V, N);
+
+    var irradiance = 0.0;
+    var nearest = center;
+    var representative = center;
+    var size = light.radius;
+    if light.shape == AREA_LIGHT_RECT {
+        let normal = cross(ax, ay);
+        if dot(P - center, normal) <= 0.0 {
+            return vec3<f32>(0.0);
+        }
+        let corners = array<vec3<f32>, 4>(center - ax - ay, center - ax + ay, center + ax + ay, center + ax - ay);
+        let identity = mat3x3<f32>(vec3<f32>(1.0, 0.0, 0.0), vec3<f32>(0.0, 1.0, 0.0), vec3<f32>(0.0, 0.0, 1.0));
+        irradiance = PI * ltc_evaluate(N, V, P, identity, corners);
+        nearest = rect_closest_point(center, ax, ay, P);
+
+        var hit = nearest;
+        let denom = dot(R, normal);
+        if denom < 0.0 {
+            hit = P + R * (dot(center - P, normal) / denom);
+        }
+        representative = rect_closest_point(center, ax, ay, hit);
+        size = length(ax) + length(ay);
+    } else {
+        let L0 = center - ax - P;
+        let L1 = center + ax - P;
+        let len0 = length(L0);
+        let len1 = length(L1);
+        let NoL = clamp(0.5 * (dot(N, L0) / max(len0, 0.0001) + dot(N, L1) / max(len1, 0.0001)), 0.0, 1.0);
+        irradiance = 2.0 * NoL / (len0 * len1 + dot(L0, L1) + light.radius * light.radius + 0.0001);
+
+        let Ld = L1 - L0;
+        let LdoLd = max(dot(Ld, Ld), 1e-8);
+        nearest = center - ax + Ld * clamp(dot(-L0, Ld) / LdoLd, 0.0, 1.0);
+
+        let RoLd = dot(R, Ld);
+        let t = clamp((dot(R, L0) * RoLd - dot(L0, Ld)) / max(LdoLd - RoLd * RoLd, 1e-8), 0.0, 1.0);
+        var closest = L0 + Ld * t;
+        let to_ray = dot(closest, R) * R - closest;
+        closest += to_ray * clamp(light.radius / max(length(to_ray), 0.0001), 0.0, 1.0);
+        representative = P + closest;
+    }
+
+    let to_light = representative - P;
+    let L = normalize(to_light);
+    let widened = min(roughness + size / (2.0 * max(length(to_light), 0.001)), 1.0);
+    let H = normalize(V + L);
+    let D = distribution_ggx(N, H, widened);
+    let G = geometry_smith(N, V, L, roughness);
+    let F = fresnel_schlick(max(dot(H, V), 0.0), F0);
+    let specular = (D * G * F) / (4.0 * max(dot(N, V), 0.0) * max(dot(N, L), 0.0) + 0.0001);
+    let kD = (vec3<f32>(1.0) - F) * (1.0 - metallic);
+
+    let radiance = light.color.rgb * light.intensity * irradiance * range_falloff(length(nearest - P), light.range);
+    return (kD * albedo / PI + specular) * radiance;
+}
+
+// ---- Sky, IBL and fog ----
+
+// Irradiance from the sky's spherical harmonics (see `atmosphere::sh9_basis`)
+fn sky_irradiance(n: vec3<f32>) -> vec3<f32> {
+    let sh = env.irradiance_sh;
+    return max(sh[0].rgb * 0.282095
+        + sh[1].rgb * 0.488603 * n.y + sh[2].rgb * 0.488603 * n.z + sh[3].rgb * 0.488603 * n.x
+        + sh[4].rgb * 1.092548 * n.x * n.y + sh[5].rgb * 1.092548 * n.y * n.z
+        + sh[6].rgb * 0.315392 * (3.0 * n.z * n.z - 1.0) + sh[7].rgb * 1.092548 * n.x * n.z
+        + sh[8].rgb * 0.546274 * (n.x * n.x - n.y * n.y), vec3<f32>(0.0));
+}
+
+// Analytic fit of the split-sum environment BRDF (Karis 2014)
+fn env_brdf(F0: vec3<f32>, roughness: f32, NdotV: f32) -> vec3<f32> {
+    let r = roughness * vec4<f32>(-1.0, -0.0275, -0.572, 0.022) + vec4<f32>(1.0, 0.0425, 1.04, -0.04);
+    let a004 = min(r.x * r.x, exp2(-9.28 * NdotV)) * r.x + r.y;
+    let ab = vec2<f32>(-1.04, 1.04) * a004 + r.zw;
+    return F0 * ab.x + ab.y;
+}
+
+// Sky and sun disk seen along `dir`
+fn sky_radiance(dir: vec3<f32>) -> vec3<f32> {
+    var radiance = textureSampleLevel(sky_cubemap, env_sampler, dir, 0.0).rgb;
+    let cos_sun = dot(dir, env.sun_direction.xyz);
+    if cos_sun > env.sun_direction.w {
+        // Soft limb so the disk edge does not alias
+        let edge = (cos_sun - env.sun_direction.w) / max(1.0 - env.sun_direction.w, 1e-6);
+        radiance += env.sun_radiance.rgb * smoothstep(0.0, 0.2, edge);
+    }
+    return radiance;
+}
+
+// Diffuse SH irradiance plus specular from the sky's mips, with the part
+// of the specular SSR already reflects left out
+fn sky_ambient(N: vec3<f32>, V: vec3<f32>, albedo: vec3<f32>, metallic: f32, roughness: f32,
+               F0: vec3<f32>, ssr_weight: f32) -> vec3<f32> {
+    let NdotV = max(dot(N, V), 0.0);
+    let F = F0 + (max(vec3<f32>(1.0 - roughness), F0) - F0) * pow(1.0 - NdotV, 5.0);
+    let kD = (vec3<f32>(1.0) - F) * (1.0 - metallic);
+    let diffuse = kD * albedo / PI * sky_irradiance(N);
+    let R = reflect(-V, N);
+    let prefiltered = textureSampleLevel(sky_cubemap, env_sampler, R, roughness * (env.sky_mip_count - 1.0)).rgb;
+    return diffuse + prefiltered * env_brdf(F0, roughness, NdotV) * (1.0 - ssr_weight);
+}
+
+// Fog between the camera and view depth `depth` along the ray through uv:
+// rgb = in-scattered light, a = transmittance
+fn fog_at(uv: vec2<f32>, depth: f32) -> vec4<f32> {
+    let slices = f32(textureDimensions(fog_volume).z);
+    let t = log(max(depth, env.fog_near) / env.fog_near) / log(env.fog_far / env.fog_near);
+    // Texel z holds the fog up to the far end of slice z
+    let w = (clamp(t, 0.0, 1.0) * slices - 0.5) / slices;
+    return textureSampleLevel(fog_volume, env_sampler, vec3<f32>(uv, w), 0.0);
+}
+
+fn apply_fog(color: vec3<f32>, uv: vec2<f32>, depth: f32) -> vec3<f32> {
+    if env.fog_enabled == 0 {
+        return color;
+    }
+    let fog = fog_at(uv, depth);
+    return color * fog.a + fog.rgb;
+}
+
 @fragment
 fn fs_main(in: FragmentInput) -> @location(0) vec4<f32> {
     let albedo_metallic = textureSample(g_albedo_metallic, gbuffer_sampler, in.uv);
     let normal_roughness = textureSample(g_normal_roughness, gbuffer_sampler, in.uv);
     let emissive_ao = textureSample(g_emissive_ao, gbuffer_sampler, in.uv);
-    let depth = textureSample(g_depth, depth_sampler, in.uv);
+    let depth = textureSample(g_depth, depth_sampler, in.uv).r;
 
-    // Skip background pixels
+    // Background: the sky behind the farthest fog, or a flat gray
     if depth >= 1.0 {
-        return vec4<f32>(0.1, 0.1, 0.1, 1.0);
+        if env.has_sky == 0 {
+            return vec4<f32>(apply_fog(vec3<f32>(0.1), in.uv, env.fog_far), 1.0);
+        }
+        let dir = normalize(reconstruct_world_pos(in.uv, 1.0) - frame.camera_pos.xyz);
+        return vec4<f32>(apply_fog(sky_radiance(dir), in.uv, env.fog_far), 1.0);
     }
 
     let albedo = albedo_metallic.rgb;
@@ -111,10 +659,14 @@ fn fs_main(in: FragmentInput) -> @location(0) vec4<f32> {
     let V = normalize(frame.camera_pos.xyz - world_pos);
     let F0 = mix(vec3<f32>(0.04), albedo, metallic);
 
-    // Ambient (modulated by SSAO)
+    // Ambient from the sky, or a constant without one (modulated by SSAO)
     let ssao = textureSample(ssao_texture, gbuffer_sampler, in.uv).r;
-    let ambient = vec3<f32>(0.03) * albedo * ao * ssao;
-    var Lo = ambient;
+    let ssr = textureSample(ssr_texture, gbuffer_sampler, in.uv);
+    var ambient = vec3<f32>(0.03) * albedo;
+    if env.has_sky != 0 {
+        ambient = sky_ambient(N, V, albedo, metallic, roughness, F0, ssr.a * (1.0 - roughness));
+    }
+    var Lo = ambient * ao * ssao;
 
     // Directional lights
     for (var i = 0; i < lights.num_dir_lights; i++) {
@@ -128,20 +680,27 @@ fn fs_main(in: FragmentInput) -> @location(0) vec4<f32> {
 
         let specular = (D * G * F) / (4.0 * max(dot(N, V), 0.0) * NdotL + 0.0001);
         let kD = (vec3<f32>(1.0) - F) * (1.0 - metallic);
-        let radiance = lights.dir_lights[i].color.rgb * lights.dir_lights[i].intensity;
+        var radiance = lights.dir_lights[i].color.rgb * lights.dir_lights[i].intensity;
+        // First directional light casts shadows via CSM
+        if i == 0 {
+            radiance *= csm_visibility(world_pos, N, L, in.uv * vec2<f32>(textureDimensions(g_depth)));
+        }
         Lo += (kD * albedo / PI + specular) * radiance * NdotL;
     }
 
-    // Point lights
-    for (var i = 0; i < lights.num_point_lights; i++) {
-        let light_pos = lights.point_lights[i].position.xyz;
+    // Point lights binned into this pixel's froxel
+    let cluster = cluster_index(world_pos);
+    let cluster_light_count = cluster_counts[cluster];
+    for (var c = 0u; c < cluster_light_count; c++) {
+        let light = point_lights[cluster_indices[cluster * MAX_LIGHTS_PER_CLUSTER + c]];
+        let light_pos = light.position.xyz;
         let L = normalize(light_pos - world_pos);
         let H = normalize(V + L);
         let NdotL = max(dot(N, L), 0.0);
 
         let dist = length(light_pos - world_pos);
         var attenuation = 1.0 / (dist * dist + 0.0001);
-        let range_factor = clamp(1.0 - pow(dist / max(lights.point_lights[i].range, 0.001), 4.0), 0.0, 1.0);
+        let range_factor = clamp(1.0 - pow(dist / max(light.range, 0.001), 4.0), 0.0, 1.0);
         attenuation *= range_factor * range_factor;
 
         let D = distribution_ggx(N, H, roughness);
@@ -150,19 +709,31 @@ fn fs_main(in: FragmentInput) -> @location(0) vec4<f32> {
 
         let specular = (D * G * F) / (4.0 * max(dot(N, V), 0.0) * NdotL + 0.0001);
         let kD = (vec3<f32>(1.0) - F) * (1.0 - metallic);
-        let radiance = lights.point_lights[i].color.rgb * lights.point_lights[i].intensity * attenuation;
+        var radiance = light.color.rgb * light.intensity * attenuation;
+        if light.shadow_index >= 0 && attenuation > 0.0 {
+            let face = cube_face(world_pos - light_pos);
+            radiance *= local_shadow(light.shadow_index + face, world_pos, N, light_pos);
+        }
         Lo += (kD * albedo / PI + specular) * radiance * NdotL;
     }
 
+    // Spot and area lights
+    for (var i = 0; i < lights.num_spot_lights; i++) {
+        Lo += spot_light_contribution(spot_lights[i], world_pos, N, V, albedo, metallic, roughness, F0);
+    }
+    for (var i = 0; i < lights.num_area_lights; i++) {
+        Lo += area_light_contribution(area_lights[i], world_pos, N, V, albedo, metallic, roughness, F0);
+    }
+
     // Emissive
     Lo += emissive;
 
     // SSR contribution
-    let ssr = textureSample(ssr_texture, gbuffer_sampler, in.uv);
     if ssr.a > 0.0 {
         let F = fresnel_schlick(max(dot(N, V), 0.0), F0);
         Lo += ssr.rgb * F * ssr.a * (1.0 - roughness);
     }
 
-    return vec4<f32>(Lo, 1.0);
+    let view_depth = -(frame.view * vec4<f32>(world_pos, 1.0)).z;
+    return vec4<f32>(apply_fog(Lo, in.uv, view_depth), 1.0);
 }
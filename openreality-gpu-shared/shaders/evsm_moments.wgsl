@@ -0,0 +1,60 @@
+// EVSM moments — warps one cascade's depth into exponential moments,
+// averaged over a 3x3 texel box so the lookup can filter them linearly.
+// Output: (exp(k0 d), exp(2 k0 d), -exp(-k1 d), exp(-2 k1 d)) with d in -1..1.
+
+struct CascadeData {
+    view_proj: mat4x4<f32>,
+    split_depth: f32,
+    texel_size: f32,
+    depth_range: f32,
+    _pad: f32,
+};
+
+struct CsmSettings {
+    filter_mode: u32,
+    pcf_samples: u32,
+    pcf_radius: f32,
+    light_size: f32,
+    blend_band: f32,
+    depth_bias: f32,
+    normal_bias: f32,
+    slope_bias: f32,
+    evsm_exponents: vec2<f32>,
+    light_bleed_reduction: f32,
+    stabilize: u32,
+};
+
+struct ShadowUniforms {
+    cascades: array<CascadeData, 4>,
+    num_cascades: i32,
+    _pad1: f32,
+    _pad2: f32,
+    _pad3: f32,
+    settings: CsmSettings,
+};
+
+@group(0) @binding(0) var<uniform> shadow: ShadowUniforms;
+// A depth layer read as plain floats (GL has no non-comparison depth reads)
+@group(0) @binding(1) var cascade_depth: texture_2d<f32>;
+
+struct FragmentInput {
+    @builtin(position) position: vec4<f32>,
+};
+
+@fragment
+fn fs_main(in: FragmentInput) -> @location(0) vec4<f32> {
+    let k = shadow.settings.evsm_exponents;
+    let dims = vec2<i32>(textureDimensions(cascade_depth));
+    let center = vec2<i32>(in.position.xy);
+    var moments = vec4<f32>(0.0);
+    for (var x = -1; x <= 1; x++) {
+        for (var y = -1; y <= 1; y++) {
+            let texel = clamp(center + vec2<i32>(x, y), vec2<i32>(0), dims - 1);
+            let d = textureLoad(cascade_depth, texel, 0).r * 2.0 - 1.0;
+            let positive = exp(k.x * d);
+            let negative = exp(-k.y * d);
+            moments += vec4<f32>(positive, positive * positive, -negative, negative * negative);
+        }
+    }
+    return moments / 9.0;
+}
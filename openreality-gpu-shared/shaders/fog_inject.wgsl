@@ -0,0 +1,316 @@
+// Volumetric fog injection — compute shader.
+// One invocation per froxel: evaluates the medium at the froxel center and
+// the light it scatters towards the camera (directional light with its
+// cascaded shadow, clustered point lights and spot lights with their atlas
+// shadows, sky ambient). Writes rgb = in-scattered radiance per meter,
+// a = extinction, for fog_integrate.wgsl to accumulate.
+
+const PI: f32 = 3.14159265359;
+const MAX_LIGHTS_PER_CLUSTER: u32 = 128u;
+
+struct PerFrame {
+    view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    inv_view_proj: mat4x4<f32>,
+    camera_pos: vec4<f32>,
+    time: f32,
+    _pad1: f32,
+    _pad2: f32,
+    _pad3: f32,
+};
+
+struct FogParams {
+    inv_projection: mat4x4<f32>,
+    inv_view: mat4x4<f32>,
+    grid: vec4<u32>,
+    albedo: vec4<f32>,   // rgb = single scattering albedo, w = ambient intensity
+    near: f32,
+    far: f32,
+    density: f32,
+    height_density: f32,
+    height_falloff: f32,
+    base_height: f32,
+    anisotropy: f32,
+    _pad1: f32,
+};
+
+struct Environment {
+    irradiance_sh: array<vec4<f32>, 9>,
+    sun_direction: vec4<f32>,
+    sun_radiance: vec4<f32>,
+    has_sky: i32,
+    fog_enabled: i32,
+    fog_near: f32,
+    fog_far: f32,
+    sky_mip_count: f32,
+    _pad1: f32,
+    _pad2: f32,
+    _pad3: f32,
+};
+
+struct PointLight {
+    position: vec4<f32>,
+    color: vec4<f32>,
+    intensity: f32,
+    range: f32,
+    shadow_index: i32,
+    _pad: f32,
+};
+
+struct SpotLight {
+    position: vec4<f32>,
+    direction: vec4<f32>,
+    color: vec4<f32>,
+    intensity: f32,
+    range: f32,
+    inner_cos: f32,
+    outer_cos: f32,
+    shadow_index: i32,
+    _pad1: f32,
+    _pad2: f32,
+    _pad3: f32,
+};
+
+struct LocalShadow {
+    view_proj: mat4x4<f32>,
+    atlas_rect: vec4<f32>,
+    texel_size: f32,
+    depth_bias: f32,
+    _pad1: f32,
+    _pad2: f32,
+};
+
+struct DirLight {
+    direction: vec4<f32>,
+    color: vec4<f32>,
+    intensity: f32,
+    _pad1: f32,
+    _pad2: f32,
+    _pad3: f32,
+};
+
+struct LightData {
+    dir_lights: array<DirLight, 4>,
+    num_point_lights: i32,
+    num_dir_lights: i32,
+    has_ibl: i32,
+    ibl_intensity: f32,
+    num_spot_lights: i32,
+    num_area_lights: i32,
+    _pad1: i32,
+    _pad2: i32,
+};
+
+struct ClusterParams {
+    inv_projection: mat4x4<f32>,
+    view: mat4x4<f32>,
+    grid: vec4<u32>,
+    near: f32,
+    far: f32,
+    _pad1: f32,
+    _pad2: f32,
+};
+
+struct CascadeData {
+    view_proj: mat4x4<f32>,
+    split_depth: f32,
+    texel_size: f32,
+    depth_range: f32,
+    _pad: f32,
+};
+
+struct CsmSettings {
+    filter_mode: u32,
+    pcf_samples: u32,
+    pcf_radius: f32,
+    light_size: f32,
+    blend_band: f32,
+    depth_bias: f32,
+    normal_bias: f32,
+    slope_bias: f32,
+    evsm_exponents: vec2<f32>,
+    light_bleed_reduction: f32,
+    stabilize: u32,
+};
+
+struct ShadowUniforms {
+    cascades: array<CascadeData, 4>,
+    num_cascades: i32,
+    _pad1: f32,
+    _pad2: f32,
+    _pad3: f32,
+    settings: CsmSettings,
+};
+
+// Bind group 0: camera, fog parameters, environment and the injected volume
+@group(0) @binding(0) var<uniform> frame: PerFrame;
+@group(0) @binding(1) var<uniform> fog: FogParams;
+@group(0) @binding(2) var<uniform> env: Environment;
+@group(0) @binding(3) var scattering_out: texture_storage_3d<rgba16float, write>;
+
+// Bind group 1: the lighting pass's light data (see deferred_lighting.wgsl)
+@group(1) @binding(0) var<uniform> lights: LightData;
+@group(1) @binding(1) var<storage, read> point_lights: array<PointLight>;
+@group(1) @binding(2) var<uniform> clusters: ClusterParams;
+@group(1) @binding(3) var<storage, read> cluster_counts: array<u32>;
+@group(1) @binding(4) var<storage, read> cluster_indices: array<u32>;
+@group(1) @binding(5) var<storage, read> spot_lights: array<SpotLight>;
+@group(1) @binding(7) var shadow_atlas: texture_depth_2d;
+@group(1) @binding(8) var<storage, read> local_shadows: array<LocalShadow>;
+@group(1) @binding(9) var shadow_sampler: sampler_comparison;
+@group(1) @binding(10) var<uniform> shadow: ShadowUniforms;
+@group(1) @binding(11) var csm_depth: texture_depth_2d_array;
+
+// View depth where slice coordinate `slice` begins, as `atmosphere::fog_slice_depth`
+fn slice_depth(slice: f32) -> f32 {
+    return fog.near * pow(fog.far / fog.near, slice / f32(fog.grid.z));
+}
+
+// Froxel containing `world_pos`, matching the binning in light_cluster.wgsl
+fn cluster_index(world_pos: vec3<f32>) -> u32 {
+    let view_pos = frame.view * vec4<f32>(world_pos, 1.0);
+    let clip = frame.projection * view_pos;
+    let ndc = clamp(clip.xy / clip.w, vec2<f32>(-1.0), vec2<f32>(1.0));
+    let tile = min(vec2<u32>((ndc * 0.5 + 0.5) * vec2<f32>(clusters.grid.xy)), clusters.grid.xy - 1u);
+    let depth = max(-view_pos.z, clusters.near);
+    let t = log(depth / clusters.near) / log(clusters.far / clusters.near);
+    let slice = min(u32(t * f32(clusters.grid.z)), clusters.grid.z - 1u);
+    return tile.x + clusters.grid.x * (tile.y + clusters.grid.y * slice);
+}
+
+fn henyey_greenstein(mu: f32, g: f32) -> f32 {
+    let denom = 1.0 + g * g - 2.0 * g * mu;
+    return (1.0 - g * g) / (4.0 * PI * denom * sqrt(denom));
+}
+
+fn range_falloff(dist: f32, range: f32) -> f32 {
+    let f = clamp(1.0 - pow(dist / max(range, 0.001), 4.0), 0.0, 1.0);
+    return f * f;
+}
+
+fn extinction(world_pos: vec3<f32>) -> f32 {
+    let height = max(world_pos.y - fog.base_height, 0.0);
+    return fog.density + fog.height_density * exp(-fog.height_falloff * height);
+}
+
+// Visibility (1 = lit) of P from the shadowed directional light: one hard
+// comparison in the first cascade that covers it
+fn csm_visibility(P: vec3<f32>) -> f32 {
+    let view_depth = -(frame.view * vec4<f32>(P, 1.0)).z;
+    for (var c = 0; c < shadow.num_cascades; c++) {
+        if view_depth <= shadow.cascades[c].split_depth {
+            let clip = shadow.cascades[c].view_proj * vec4<f32>(P, 1.0);
+            let uv = clip.xy * vec2<f32>(0.5, -0.5) + 0.5;
+            if any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) || clip.z > 1.0 {
+                return 1.0;
+            }
+            return textureSampleCompareLevel(csm_depth, shadow_sampler, uv, c, clip.z);
+        }
+    }
+    return 1.0;
+}
+
+// Visibility (1 = lit) of P from the local light shadow record `index`
+fn local_shadow(index: i32, P: vec3<f32>) -> f32 {
+    let s = local_shadows[index];
+    let clip = s.view_proj * vec4<f32>(P, 1.0);
+    if clip.w <= 0.0 {
+        return 1.0;
+    }
+    let ndc = clip.xyz / clip.w;
+    if any(abs(ndc.xy) > vec2<f32>(1.0)) || ndc.z > 1.0 {
+        return 1.0;
+    }
+    let half_texel = 0.5 / vec2<f32>(textureDimensions(shadow_atlas));
+    let tile_uv = ndc.xy * vec2<f32>(0.5, -0.5) + 0.5;
+    let uv = clamp(s.atlas_rect.xy + tile_uv * s.atlas_rect.zw,
+                   s.atlas_rect.xy + half_texel, s.atlas_rect.xy + s.atlas_rect.zw - half_texel);
+    return textureSampleCompareLevel(shadow_atlas, shadow_sampler, uv, ndc.z - s.depth_bias);
+}
+
+fn cube_face(d: vec3<f32>) -> i32 {
+    let a = abs(d);
+    if a.x >= a.y && a.x >= a.z {
+        return select(1, 0, d.x > 0.0);
+    }
+    if a.y >= a.z {
+        return select(3, 2, d.y > 0.0);
+    }
+    return select(5, 4, d.z > 0.0);
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id >= fog.grid.xyz) {
+        return;
+    }
+
+    // Froxel center; x and y follow texture UVs (y down) so the lighting
+    // pass can look the volume up by screen UV
+    let uv = (vec2<f32>(id.xy) + 0.5) / vec2<f32>(fog.grid.xy);
+    let ndc = vec2<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0);
+    let ray = fog.inv_projection * vec4<f32>(ndc, 1.0, 1.0);
+    let dir = ray.xyz / ray.w;
+    let depth = slice_depth(f32(id.z) + 0.5);
+    let world_pos = (fog.inv_view * vec4<f32>(dir * (depth / -dir.z), 1.0)).xyz;
+    let V = normalize(frame.camera_pos.xyz - world_pos);
+
+    let sigma_t = extinction(world_pos);
+    let g = fog.anisotropy;
+    var radiance = vec3<f32>(0.0);
+
+    // Directional lights; the first one is shadowed by the CSM
+    for (var i = 0; i < lights.num_dir_lights; i++) {
+        let L = normalize(-lights.dir_lights[i].direction.xyz);
+        var light = lights.dir_lights[i].color.rgb * lights.dir_lights[i].intensity;
+        if i == 0 {
+            light *= csm_visibility(world_pos);
+        }
+        radiance += light * henyey_greenstein(dot(-V, L), g);
+    }
+
+    // Point lights binned into this point's light cluster
+    let cluster = cluster_index(world_pos);
+    let count = cluster_counts[cluster];
+    for (var c = 0u; c < count; c++) {
+        let light = point_lights[cluster_indices[cluster * MAX_LIGHTS_PER_CLUSTER + c]];
+        let to_light = light.position.xyz - world_pos;
+        let dist = length(to_light);
+        let attenuation = range_falloff(dist, light.range) / (dist * dist + 0.0001);
+        if attenuation <= 0.0 {
+            continue;
+        }
+        var visibility = 1.0;
+        if light.shadow_index >= 0 {
+            visibility = local_shadow(light.shadow_index + cube_face(-to_light), world_pos);
+        }
+        let L = to_light / max(dist, 0.0001);
+        radiance += light.color.rgb * light.intensity * attenuation * visibility * henyey_greenstein(dot(-V, L), g);
+    }
+
+    for (var i = 0; i < lights.num_spot_lights; i++) {
+        let light = spot_lights[i];
+        let to_light = light.position.xyz - world_pos;
+        let dist = length(to_light);
+        let L = to_light / max(dist, 0.0001);
+        let cos_angle = dot(-L, normalize(light.direction.xyz));
+        let cone = clamp((cos_angle - light.outer_cos) / max(light.inner_cos - light.outer_cos, 0.0001), 0.0, 1.0);
+        let attenuation = cone * cone * range_falloff(dist, light.range) / (dist * dist + 0.0001);
+        if attenuation <= 0.0 {
+            continue;
+        }
+        var visibility = 1.0;
+        if light.shadow_index >= 0 {
+            visibility = local_shadow(light.shadow_index, world_pos);
+        }
+        radiance += light.color.rgb * light.intensity * attenuation * visibility * henyey_greenstein(dot(-V, L), g);
+    }
+
+    // Sky ambient: the average sky radiance (band 0 of its irradiance is pi times it)
+    if env.has_sky != 0 {
+        radiance += env.irradiance_sh[0].rgb * 0.282095 / PI * fog.albedo.w;
+    }
+
+    let scattering = fog.albedo.rgb * sigma_t * radiance;
+    textureStore(scattering_out, id, vec4<f32>(scattering, sigma_t));
+}
@@ -0,0 +1,60 @@
+// Volumetric fog integration — compute shader.
+// One invocation per froxel column: marches the injected scattering and
+// extinction front to back and writes, for each slice, the light scattered
+// towards the camera up to the slice's far end (rgb) and the transmittance
+// to it (a). Each slice is integrated analytically (Hillaire 2015) so thick
+// slices do not gain energy.
+
+struct FogParams {
+    inv_projection: mat4x4<f32>,
+    inv_view: mat4x4<f32>,
+    grid: vec4<u32>,
+    albedo: vec4<f32>,
+    near: f32,
+    far: f32,
+    density: f32,
+    height_density: f32,
+    height_falloff: f32,
+    base_height: f32,
+    anisotropy: f32,
+    _pad1: f32,
+};
+
+@group(0) @binding(0) var<uniform> fog: FogParams;
+@group(0) @binding(1) var scattering_in: texture_3d<f32>;
+@group(0) @binding(2) var integrated_out: texture_storage_3d<rgba16float, write>;
+
+fn slice_depth(slice: f32) -> f32 {
+    return fog.near * pow(fog.far / fog.near, slice / f32(fog.grid.z));
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id.xy >= fog.grid.xy) {
+        return;
+    }
+
+    // Ray length per unit of view depth through this column
+    let uv = (vec2<f32>(id.xy) + 0.5) / vec2<f32>(fog.grid.xy);
+    let ray = fog.inv_projection * vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 1.0, 1.0);
+    let dir = ray.xyz / ray.w;
+    let stretch = length(dir / dir.z);
+
+    var scattered = vec3<f32>(0.0);
+    var transmittance = 1.0;
+    // The camera-side gap before the first slice uses the first slice's medium
+    var begin = 0.0;
+    for (var z = 0u; z < fog.grid.z; z++) {
+        let coord = vec3<u32>(id.xy, z);
+        let medium = textureLoad(scattering_in, coord, 0);
+        let end = slice_depth(f32(z + 1u));
+        let thickness = (end - begin) * stretch;
+        begin = end;
+
+        let sigma_t = max(medium.a, 1e-6);
+        let slice_transmittance = exp(-sigma_t * thickness);
+        scattered += transmittance * (medium.rgb - medium.rgb * slice_transmittance) / sigma_t;
+        transmittance *= slice_transmittance;
+        textureStore(integrated_out, coord, vec4<f32>(scattered, transmittance));
+    }
+}
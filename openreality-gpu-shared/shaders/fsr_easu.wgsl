@@ -0,0 +1,140 @@
+// FSR 1.0 EASU (Edge Adaptive Spatial Upsampling), ported from AMD's
+// ffx_fsr1.h (MIT). Upscales the post-processed scene from the internal
+// render resolution to the output resolution: a 12-tap Lanczos-like kernel
+// stretched along the local edge direction, clamped to the 2x2 neighborhood
+// to avoid ringing. Runs on the gamma encoded image, as FSR expects.
+
+struct UpscaleParams {
+    input_size: vec2<f32>,
+    output_size: vec2<f32>,
+};
+
+@group(0) @binding(0) var<uniform> params: UpscaleParams;
+@group(0) @binding(1) var input_texture: texture_2d<f32>;
+@group(0) @binding(2) var tex_sampler: sampler;
+
+struct FragmentInput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+fn fetch(base: vec2<i32>, offset: vec2<i32>) -> vec3<f32> {
+    let max_coord = vec2<i32>(textureDimensions(input_texture, 0)) - 1;
+    return textureLoad(input_texture, clamp(base + offset, vec2<i32>(0), max_coord), 0).rgb;
+}
+
+fn luma(c: vec3<f32>) -> f32 {
+    return c.g + 0.5 * (c.r + c.b);
+}
+
+// Direction and edge length from the '+' of luma around `lc`, weighted by
+// the bilinear weight `w` of that tap:
+//    a
+//  b c d
+//    e
+fn easu_set(w: f32, la: f32, lb: f32, lc: f32, ld: f32, le: f32) -> vec3<f32> {
+    let dir_x = ld - lb;
+    var len_x = abs(dir_x) / max(max(abs(ld - lc), abs(lc - lb)), 1e-5);
+    len_x = saturate(len_x);
+    let dir_y = le - la;
+    var len_y = abs(dir_y) / max(max(abs(le - lc), abs(lc - la)), 1e-5);
+    len_y = saturate(len_y);
+    return vec3<f32>(dir_x, dir_y, len_x * len_x + len_y * len_y) * w;
+}
+
+// Weight of one tap at `offset` from the sample point: approximate
+// Lanczos-2, rotated along `dir` and stretched by `len2`, windowed by `lob`.
+fn easu_weight(offset: vec2<f32>, dir: vec2<f32>, len2: vec2<f32>, lob: f32, clp: f32) -> f32 {
+    let v = vec2<f32>(offset.x * dir.x + offset.y * dir.y, offset.y * dir.x - offset.x * dir.y) * len2;
+    let d2 = min(dot(v, v), clp);
+    var wb = 2.0 / 5.0 * d2 - 1.0;
+    var wa = lob * d2 - 1.0;
+    wb *= wb;
+    wa *= wa;
+    wb = 25.0 / 16.0 * wb - (25.0 / 16.0 - 1.0);
+    return wb * wa;
+}
+
+@fragment
+fn fs_main(in: FragmentInput) -> @location(0) vec4<f32> {
+    // Sample point in input pixels, relative to the texel center at `fp`
+    let src = in.position.xy * params.input_size / params.output_size - 0.5;
+    let fp = floor(src);
+    let pp = src - fp;
+    let base = vec2<i32>(fp);
+
+    // 12-tap kernel:
+    //    b c
+    //  e f g h
+    //  i j k l
+    //    n o
+    let b = fetch(base, vec2<i32>(0, -1));
+    let c = fetch(base, vec2<i32>(1, -1));
+    let e = fetch(base, vec2<i32>(-1, 0));
+    let f = fetch(base, vec2<i32>(0, 0));
+    let g = fetch(base, vec2<i32>(1, 0));
+    let h = fetch(base, vec2<i32>(2, 0));
+    let i = fetch(base, vec2<i32>(-1, 1));
+    let j = fetch(base, vec2<i32>(0, 1));
+    let k = fetch(base, vec2<i32>(1, 1));
+    let l = fetch(base, vec2<i32>(2, 1));
+    let n = fetch(base, vec2<i32>(0, 2));
+    let o = fetch(base, vec2<i32>(1, 2));
+
+    let bl = luma(b);
+    let cl = luma(c);
+    let el = luma(e);
+    let fl = luma(f);
+    let gl = luma(g);
+    let hl = luma(h);
+    let il = luma(i);
+    let jl = luma(j);
+    let kl = luma(k);
+    let ll = luma(l);
+    let nl = luma(n);
+    let ol = luma(o);
+
+    // Edge direction and length, bilinearly blended from the 4 nearest texels
+    let acc = easu_set((1.0 - pp.x) * (1.0 - pp.y), bl, el, fl, gl, jl)
+        + easu_set(pp.x * (1.0 - pp.y), cl, fl, gl, hl, kl)
+        + easu_set((1.0 - pp.x) * pp.y, fl, il, jl, kl, nl)
+        + easu_set(pp.x * pp.y, gl, jl, kl, ll, ol);
+
+    var dir = acc.xy;
+    let dir_len2 = dot(dir, dir);
+    if dir_len2 < 1.0 / 32768.0 {
+        dir = vec2<f32>(1.0, 0.0);
+    } else {
+        dir *= inverseSqrt(dir_len2);
+    }
+    // {0, 2} to {0, 1}, shaped with a square
+    var len = acc.z * 0.5;
+    len *= len;
+    // Stretch the kernel from 1 on axis aligned edges to sqrt(2) on diagonals
+    let stretch = dot(dir, dir) / max(abs(dir.x), abs(dir.y));
+    let len2 = vec2<f32>(1.0 + (stretch - 1.0) * len, 1.0 - 0.5 * len);
+    // Negative lobe: sharper on edges, softer on flat areas
+    let lob = 0.5 + ((1.0 / 4.0 - 0.04) - 0.5) * len;
+    let clp = 1.0 / lob;
+
+    var color = vec3<f32>(0.0);
+    var weight = 0.0;
+    var offsets = array<vec2<f32>, 12>(
+        vec2<f32>(0.0, -1.0), vec2<f32>(1.0, -1.0),
+        vec2<f32>(-1.0, 0.0), vec2<f32>(0.0, 0.0), vec2<f32>(1.0, 0.0), vec2<f32>(2.0, 0.0),
+        vec2<f32>(-1.0, 1.0), vec2<f32>(0.0, 1.0), vec2<f32>(1.0, 1.0), vec2<f32>(2.0, 1.0),
+        vec2<f32>(0.0, 2.0), vec2<f32>(1.0, 2.0),
+    );
+    var taps = array<vec3<f32>, 12>(b, c, e, f, g, h, i, j, k, l, n, o);
+    for (var t = 0u; t < 12u; t++) {
+        let w = easu_weight(offsets[t] - pp, dir, len2, lob, clp);
+        color += taps[t] * w;
+        weight += w;
+    }
+
+    // Dering: clamp to the 2x2 neighborhood
+    let min4 = min(min(f, g), min(j, k));
+    let max4 = max(max(f, g), max(j, k));
+    let result = clamp(color / weight, min4, max4);
+    return vec4<f32>(result, 1.0);
+}
@@ -0,0 +1,72 @@
+// G-Buffer geometry pass — vertex shader for skinned meshes.
+// Bone matrices are mesh-local (inverse(mesh_world) * bone_world * inverse_bind)
+// and are applied before the model matrix.
+
+struct PerFrame {
+    view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    inv_view_proj: mat4x4<f32>,
+    camera_pos: vec4<f32>,
+    time: f32,
+    _pad1: f32,
+    _pad2: f32,
+    _pad3: f32,
+};
+
+struct PerObject {
+    model: mat4x4<f32>,
+    normal_matrix_col0: vec4<f32>,
+    normal_matrix_col1: vec4<f32>,
+    normal_matrix_col2: vec4<f32>,
+    _pad: vec4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> frame: PerFrame;
+@group(2) @binding(0) var<uniform> object: PerObject;
+@group(3) @binding(0) var<storage, read> bones: array<mat4x4<f32>>;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+    @location(3) bone_weights: vec4<f32>,
+    @location(4) bone_indices: vec4<u32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_pos: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+    @location(3) camera_pos: vec3<f32>,
+};
+
+fn bone(i: u32) -> mat4x4<f32> {
+    return bones[min(i, arrayLength(&bones) - 1u)];
+}
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+
+    let skin = bone(in.bone_indices.x) * in.bone_weights.x
+             + bone(in.bone_indices.y) * in.bone_weights.y
+             + bone(in.bone_indices.z) * in.bone_weights.z
+             + bone(in.bone_indices.w) * in.bone_weights.w;
+    let skin3 = mat3x3<f32>(skin[0].xyz, skin[1].xyz, skin[2].xyz);
+
+    let world_pos = object.model * skin * vec4<f32>(in.position, 1.0);
+    out.world_pos = world_pos.xyz;
+
+    let normal_matrix = mat3x3<f32>(
+        object.normal_matrix_col0.xyz,
+        object.normal_matrix_col1.xyz,
+        object.normal_matrix_col2.xyz,
+    );
+    out.normal = normalize(normal_matrix * skin3 * in.normal);
+    out.uv = in.uv;
+    out.camera_pos = frame.camera_pos.xyz;
+    out.clip_position = frame.projection * frame.view * world_pos;
+
+    return out;
+}
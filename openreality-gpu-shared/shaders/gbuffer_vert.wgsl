@@ -1,4 +1,6 @@
 // G-Buffer geometry pass — vertex shader.
+// Static meshes are drawn instanced; each instance reads its matrices from
+// the per-instance storage buffer.
 
 struct PerFrame {
     view: mat4x4<f32>,
@@ -20,7 +22,7 @@ struct PerObject {
 };
 
 @group(0) @binding(0) var<uniform> frame: PerFrame;
-@group(2) @binding(0) var<uniform> object: PerObject;
+@group(2) @binding(0) var<storage, read> instances: array<PerObject>;
 
 struct VertexInput {
     @location(0) position: vec3<f32>,
@@ -37,8 +39,9 @@ struct VertexOutput {
 };
 
 @vertex
-fn vs_main(in: VertexInput) -> VertexOutput {
+fn vs_main(in: VertexInput, @builtin(instance_index) instance: u32) -> VertexOutput {
     var out: VertexOutput;
+    let object = instances[instance];
 
     let world_pos = object.model * vec4<f32>(in.position, 1.0);
     out.world_pos = world_pos.xyz;
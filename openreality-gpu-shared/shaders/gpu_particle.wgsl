@@ -0,0 +1,143 @@
+// GPU particle rendering — camera-facing quads expanded from the particle
+// storage buffer. One instance per live particle, in sorted order.
+
+struct DrawUniforms {
+    view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    inv_projection: mat4x4<f32>,
+    soft_distance: f32,
+    blend_mode: u32,          // 0 alpha, 1 additive, 2 premultiplied
+    has_texture: u32,
+    _pad1: u32,
+};
+
+struct EmitterParams {
+    velocity_min: vec4<f32>,
+    velocity_max: vec4<f32>,
+    start_color: vec4<f32>,   // rgb + start alpha
+    end_color: vec4<f32>,     // rgb + end alpha
+    shape_params: vec4<f32>,
+    max_particles: u32,
+    emission_rate: f32,
+    lifetime_min: f32,
+    lifetime_max: f32,
+    gravity_modifier: f32,
+    damping: f32,
+    start_size_min: f32,
+    start_size_max: f32,
+    end_size: f32,
+    blend_mode: u32,
+    shape_kind: u32,          // 0 point, 1 sphere, 2 cone, 3 box, 4 mesh
+    mesh_triangle_count: u32,
+    collision_mode: u32,      // 0 none, 1 ground plane, 2 depth buffer
+    plane_height: f32,
+    bounce: f32,
+    friction: f32,
+    soft_distance: f32,
+    sheet_tiles_x: u32,
+    sheet_tiles_y: u32,
+    sheet_frames: u32,
+    sheet_fps: f32,
+    curve_flags: u32,         // bit 0 color, bit 1 size, bit 2 velocity
+    has_texture: u32,
+    _pad1: f32,
+    color_lut: array<vec4<f32>, 8>,
+    size_velocity_lut: array<vec4<f32>, 8>,
+};
+
+struct Particle {
+    position_lifetime: vec4<f32>,
+    velocity_maxlife: vec4<f32>,
+    size: f32,
+    alive: u32,
+    _pad1: u32,
+    _pad2: u32,
+};
+
+// Only the camera matrices are read here; blend mode, soft distance and
+// texture flags are per emitter.
+@group(0) @binding(0) var<uniform> draw: DrawUniforms;
+@group(0) @binding(1) var scene_depth: texture_2d<f32>;
+
+@group(1) @binding(0) var<storage, read> particles: array<Particle>;
+@group(1) @binding(1) var<storage, read> sort_indices: array<u32>;
+@group(1) @binding(2) var<uniform> emitter: EmitterParams;
+@group(1) @binding(3) var sprite_texture: texture_2d<f32>;
+@group(1) @binding(4) var sprite_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) quad_uv: vec2<f32>,
+    @location(1) sheet_uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) view_depth: f32,
+};
+
+fn sample_lut(lut: array<vec4<f32>, 8>, t: f32) -> vec4<f32> {
+    let x = clamp(t, 0.0, 1.0) * 7.0;
+    let i = min(u32(x), 6u);
+    return mix(lut[i], lut[i + 1u], x - f32(i));
+}
+
+// Mirrors `particles::sheet_frame_uv` in openreality-gpu-shared
+fn sheet_uv(uv: vec2<f32>, age: f32, t: f32) -> vec2<f32> {
+    let tiles_x = max(emitter.sheet_tiles_x, 1u);
+    let tiles_y = max(emitter.sheet_tiles_y, 1u);
+    let frames = clamp(emitter.sheet_frames, 1u, tiles_x * tiles_y);
+    var frame: u32;
+    if emitter.sheet_fps > 0.0 {
+        frame = u32(age * emitter.sheet_fps) % frames;
+    } else {
+        frame = min(u32(t * f32(frames)), frames - 1u);
+    }
+    let scale = vec2<f32>(1.0 / f32(tiles_x), 1.0 / f32(tiles_y));
+    let offset = vec2<f32>(f32(frame % tiles_x), f32(frame / tiles_x)) * scale;
+    return offset + uv * scale;
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex: u32, @builtin(instance_index) instance: u32) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-0.5, -0.5), vec2<f32>(0.5, -0.5), vec2<f32>(0.5, 0.5),
+        vec2<f32>(-0.5, -0.5), vec2<f32>(0.5, 0.5), vec2<f32>(-0.5, 0.5),
+    );
+    let p = particles[sort_indices[instance]];
+    let corner = corners[vertex];
+
+    // Lifetime fraction: 0 = born, 1 = dying
+    let max_life = max(p.velocity_maxlife.w, 1e-6);
+    let t = 1.0 - clamp(p.position_lifetime.w / max_life, 0.0, 1.0);
+
+    var size = mix(p.size, emitter.end_size, t);
+    if (emitter.curve_flags & 2u) != 0u {
+        size = p.size * sample_lut(emitter.size_velocity_lut, t).x;
+    }
+    var color = mix(emitter.start_color, emitter.end_color, t);
+    if (emitter.curve_flags & 1u) != 0u {
+        color = sample_lut(emitter.color_lut, t);
+    }
+
+    // Camera right/up are the first two rows of the view rotation
+    let right = vec3<f32>(draw.view[0][0], draw.view[1][0], draw.view[2][0]);
+    let up = vec3<f32>(draw.view[0][1], draw.view[1][1], draw.view[2][1]);
+    let world_pos = p.position_lifetime.xyz + (right * corner.x + up * corner.y) * size;
+    let view_pos = draw.view * vec4<f32>(world_pos, 1.0);
+
+    // Texture rows run top-down, quad corners bottom-up
+    let uv = vec2<f32>(corner.x + 0.5, 0.5 - corner.y);
+
+    var out: VertexOutput;
+    out.quad_uv = uv;
+    out.sheet_uv = sheet_uv(uv, max_life - p.position_lifetime.w, t);
+    out.color = color;
+    out.view_depth = -view_pos.z;
+    out.clip_position = draw.projection * view_pos;
+    return out;
+}
+
+// soft_fade and shade_particle come from particle_shading.wgsl
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return shade_particle(ShadeParams(emitter.soft_distance, emitter.blend_mode, emitter.has_texture), in.color, in.sheet_uv, in.quad_uv, in.clip_position, in.view_depth);
+}
@@ -0,0 +1,65 @@
+// GPU instance culling — tests each instance's bounding sphere against the
+// frustum planes and compacts the survivors of every batch into the range
+// starting at the batch's `first_instance`, counting them into the batch's
+// indirect draw arguments (cleared to zero instances beforehand).
+
+struct PerObject {
+    model: mat4x4<f32>,
+    normal_matrix_col0: vec4<f32>,
+    normal_matrix_col1: vec4<f32>,
+    normal_matrix_col2: vec4<f32>,
+    _pad: vec4<f32>,
+};
+
+struct CullParams {
+    planes: array<vec4<f32>, 6>,
+    instance_count: u32,
+    plane_count: u32,
+    _pad1: u32,
+    _pad2: u32,
+};
+
+struct CullInstance {
+    bounds: vec4<f32>,
+    batch: u32,
+    _pad1: u32,
+    _pad2: u32,
+    _pad3: u32,
+};
+
+struct DrawArgs {
+    index_count: u32,
+    instance_count: atomic<u32>,
+    first_index: u32,
+    base_vertex: i32,
+    first_instance: u32,
+};
+
+@group(0) @binding(0) var<uniform> params: CullParams;
+@group(0) @binding(1) var<storage, read> instances_in: array<PerObject>;
+@group(0) @binding(2) var<storage, read> cull: array<CullInstance>;
+@group(0) @binding(3) var<storage, read_write> instances_out: array<PerObject>;
+@group(0) @binding(4) var<storage, read_write> draws: array<DrawArgs>;
+
+@compute @workgroup_size(64)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if (i >= params.instance_count) {
+        return;
+    }
+
+    let object = instances_in[i];
+    let info = cull[i];
+    let center = (object.model * vec4<f32>(info.bounds.xyz, 1.0)).xyz;
+    let scale = max(length(object.model[0].xyz), max(length(object.model[1].xyz), length(object.model[2].xyz)));
+    let radius = info.bounds.w * scale;
+    for (var p = 0u; p < params.plane_count; p = p + 1u) {
+        let plane = params.planes[p];
+        if (dot(plane.xyz, center) + plane.w < -radius) {
+            return;
+        }
+    }
+
+    let slot = atomicAdd(&draws[info.batch].instance_count, 1u);
+    instances_out[draws[info.batch].first_instance + slot] = object;
+}
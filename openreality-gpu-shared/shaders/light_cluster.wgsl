@@ -0,0 +1,77 @@
+// Light clustering — compute shader.
+// One invocation per froxel: builds the froxel's view-space AABB and lists
+// every point light whose range sphere touches it.
+
+const MAX_LIGHTS_PER_CLUSTER: u32 = 128u;
+
+struct ClusterParams {
+    inv_projection: mat4x4<f32>,
+    view: mat4x4<f32>,
+    grid: vec4<u32>,   // xyz = clusters per axis, w = point light count
+    near: f32,
+    far: f32,
+    _pad1: f32,
+    _pad2: f32,
+};
+
+struct PointLight {
+    position: vec4<f32>,
+    color: vec4<f32>,
+    intensity: f32,
+    range: f32,
+    shadow_index: i32,
+    _pad: f32,
+};
+
+@group(0) @binding(0) var<uniform> params: ClusterParams;
+@group(0) @binding(1) var<storage, read> point_lights: array<PointLight>;
+@group(0) @binding(2) var<storage, read_write> cluster_counts: array<u32>;
+@group(0) @binding(3) var<storage, read_write> cluster_indices: array<u32>;
+
+// View-space depth where slice `slice` begins
+fn slice_depth(slice: u32) -> f32 {
+    return params.near * pow(params.far / params.near, f32(slice) / f32(params.grid.z));
+}
+
+// View-space point at linear `depth` on the ray through NDC `ndc`
+fn ray_point(ndc: vec2<f32>, depth: f32) -> vec3<f32> {
+    let p = params.inv_projection * vec4<f32>(ndc, 1.0, 1.0);
+    let dir = p.xyz / p.w;
+    return dir * (depth / -dir.z);
+}
+
+@compute @workgroup_size(4, 4, 4)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id >= params.grid.xyz) {
+        return;
+    }
+    let cluster = id.x + params.grid.x * (id.y + params.grid.y * id.z);
+
+    let grid = vec2<f32>(params.grid.xy);
+    let ndc_min = vec2<f32>(id.xy) / grid * 2.0 - 1.0;
+    let ndc_max = vec2<f32>(id.xy + 1u) / grid * 2.0 - 1.0;
+    let depth_near = slice_depth(id.z);
+    let depth_far = slice_depth(id.z + 1u);
+
+    var aabb_min = vec3<f32>(1e30);
+    var aabb_max = vec3<f32>(-1e30);
+    for (var c = 0u; c < 8u; c++) {
+        let ndc = vec2<f32>(select(ndc_min.x, ndc_max.x, (c & 1u) != 0u), select(ndc_min.y, ndc_max.y, (c & 2u) != 0u));
+        let p = ray_point(ndc, select(depth_near, depth_far, (c & 4u) != 0u));
+        aabb_min = min(aabb_min, p);
+        aabb_max = max(aabb_max, p);
+    }
+
+    let base = cluster * MAX_LIGHTS_PER_CLUSTER;
+    var count = 0u;
+    for (var i = 0u; i < params.grid.w && count < MAX_LIGHTS_PER_CLUSTER; i++) {
+        let light = point_lights[i];
+        let center = (params.view * vec4<f32>(light.position.xyz, 1.0)).xyz;
+        let d = clamp(center, aabb_min, aabb_max) - center;
+        if dot(d, d) <= light.range * light.range {
+            cluster_indices[base + count] = i;
+            count++;
+        }
+    }
+    cluster_counts[cluster] = count;
+}
@@ -0,0 +1,92 @@
+// Auto exposure: a histogram of the HDR image's log luminance, then its
+// average metered into an EV100 the exposure adapts towards over time.
+// Bin 0 holds (near) black pixels, which do not count towards the average.
+
+struct ExposureParams {
+    min_log_lum: f32,
+    log_lum_range: f32,
+    delta_time: f32,
+    speed_up: f32,
+    speed_down: f32,
+    min_ev100: f32,
+    max_ev100: f32,
+    compensation: f32,
+    pixel_count: u32,
+    snap: u32,
+    _pad1: u32,
+    _pad2: u32,
+};
+
+struct ExposureState {
+    ev100: f32,
+    exposure: f32,
+};
+
+const NUM_BINS: u32 = 256u;
+
+@group(0) @binding(0) var<uniform> params: ExposureParams;
+@group(0) @binding(1) var hdr_texture: texture_2d<f32>;
+@group(0) @binding(2) var<storage, read_write> histogram: array<atomic<u32>, 256>;
+@group(0) @binding(3) var<storage, read_write> state: ExposureState;
+
+var<workgroup> local_bins: array<atomic<u32>, 256>;
+var<workgroup> weighted: array<f32, 256>;
+
+fn luminance_bin(color: vec3<f32>) -> u32 {
+    let luminance = dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+    if luminance < 1e-5 {
+        return 0u;
+    }
+    let t = clamp((log2(luminance) - params.min_log_lum) / params.log_lum_range, 0.0, 1.0);
+    return u32(t * f32(NUM_BINS - 2u)) + 1u;
+}
+
+@compute @workgroup_size(16, 16)
+fn cs_histogram(
+    @builtin(global_invocation_id) gid: vec3<u32>,
+    @builtin(local_invocation_index) index: u32,
+) {
+    atomicStore(&local_bins[index], 0u);
+    workgroupBarrier();
+
+    let dims = textureDimensions(hdr_texture);
+    if gid.x < dims.x && gid.y < dims.y {
+        let color = textureLoad(hdr_texture, vec2<i32>(gid.xy), 0).rgb;
+        atomicAdd(&local_bins[luminance_bin(color)], 1u);
+    }
+    workgroupBarrier();
+
+    atomicAdd(&histogram[index], atomicLoad(&local_bins[index]));
+}
+
+@compute @workgroup_size(256)
+fn cs_average(@builtin(local_invocation_index) index: u32) {
+    // Read and clear the bin for the next frame
+    let count = atomicExchange(&histogram[index], 0u);
+    weighted[index] = f32(count) * f32(index);
+    workgroupBarrier();
+
+    for (var stride = NUM_BINS / 2u; stride > 0u; stride = stride / 2u) {
+        if index < stride {
+            weighted[index] += weighted[index + stride];
+        }
+        workgroupBarrier();
+    }
+
+    if index == 0u {
+        // `count` is the black bin here
+        let lit_pixels = max(f32(params.pixel_count) - f32(count), 1.0);
+        let average_bin = weighted[0] / lit_pixels;
+        let log_lum = (average_bin - 1.0) / f32(NUM_BINS - 2u) * params.log_lum_range + params.min_log_lum;
+        // EV100 that exposes the average luminance as middle gray (K = 12.5)
+        let target_ev = clamp(log_lum + log2(100.0 / 12.5), params.min_ev100, params.max_ev100);
+
+        var ev = target_ev;
+        if params.snap == 0u {
+            let speed = select(params.speed_down, params.speed_up, target_ev > state.ev100);
+            ev = state.ev100 + (target_ev - state.ev100) * (1.0 - exp(-params.delta_time * speed));
+        }
+        state.ev100 = ev;
+        state.exposure = exp2(params.compensation) / (1.2 * exp2(ev));
+    }
+}
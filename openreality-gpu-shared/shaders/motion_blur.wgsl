@@ -14,7 +14,7 @@ struct VelocityParams {
 };
 
 @group(0) @binding(0) var<uniform> velocity_params: VelocityParams;
-@group(0) @binding(1) var depth_texture: texture_depth_2d;
+@group(0) @binding(1) var depth_texture: texture_2d<f32>;
 @group(0) @binding(2) var depth_sampler: sampler;
 
 struct FragmentInput {
@@ -23,10 +23,10 @@ struct FragmentInput {
 
 @fragment
 fn fs_velocity(in: FragmentInput) -> @location(0) vec2<f32> {
-    let depth = textureSample(depth_texture, depth_sampler, in.uv);
+    let depth = textureSample(depth_texture, depth_sampler, in.uv).r;
 
-    // Reconstruct clip-space position
-    let clip_pos = vec4<f32>(in.uv * 2.0 - 1.0, depth * 2.0 - 1.0, 1.0);
+    // Reconstruct clip-space position (the depth buffer holds NDC z)
+    let clip_pos = vec4<f32>(in.uv * 2.0 - 1.0, depth, 1.0);
 
     // Reconstruct world-space position
     var world_pos = velocity_params.inv_view_proj * clip_pos;
@@ -0,0 +1,27 @@
+// MSAA prime — copy the lit image and the G-Buffer depth into the
+// multisampled targets of the forward pass, so transparent geometry blends
+// over the opaque scene and is depth tested against it. Every sample of a
+// pixel gets the single-sampled value.
+
+@group(0) @binding(0) var lit_texture: texture_2d<f32>;
+// Bound as plain floats, which GL can read without a comparison sampler
+@group(0) @binding(1) var depth_texture: texture_2d<f32>;
+
+struct FragmentInput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+struct FragmentOutput {
+    @location(0) color: vec4<f32>,
+    @builtin(frag_depth) depth: f32,
+};
+
+@fragment
+fn fs_main(in: FragmentInput) -> FragmentOutput {
+    let pixel = vec2<i32>(in.position.xy);
+    var out: FragmentOutput;
+    out.color = textureLoad(lit_texture, pixel, 0);
+    out.depth = textureLoad(depth_texture, pixel, 0).r;
+    return out;
+}
@@ -1,12 +1,21 @@
-// Particle rendering — billboard quads with soft circular falloff.
-// Vertex layout: pos3 + uv2 + color4 (interleaved, 9 floats per vertex).
+// Particle rendering — billboard quads with soft circular falloff or a
+// sprite texture. Vertex layout: pos3 + uv2 + color4 (interleaved, 9 floats
+// per vertex); texture-sheet frames are resolved on the CPU into the uv.
 
-struct ParticleUniforms {
+struct DrawUniforms {
     view: mat4x4<f32>,
     projection: mat4x4<f32>,
+    inv_projection: mat4x4<f32>,
+    soft_distance: f32,
+    blend_mode: u32,          // 0 alpha, 1 additive, 2 premultiplied
+    has_texture: u32,
+    _pad1: u32,
 };
 
-@group(0) @binding(0) var<uniform> uniforms: ParticleUniforms;
+@group(0) @binding(0) var<uniform> draw: DrawUniforms;
+@group(0) @binding(1) var scene_depth: texture_2d<f32>;
+@group(0) @binding(2) var sprite_texture: texture_2d<f32>;
+@group(0) @binding(3) var sprite_sampler: sampler;
 
 struct VertexInput {
     @location(0) position: vec3<f32>,
@@ -18,27 +27,23 @@ struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) uv: vec2<f32>,
     @location(1) color: vec4<f32>,
+    @location(2) view_depth: f32,
 };
 
 @vertex
 fn vs_main(in: VertexInput) -> VertexOutput {
+    let view_pos = draw.view * vec4<f32>(in.position, 1.0);
     var out: VertexOutput;
     out.uv = in.uv;
     out.color = in.color;
-    out.clip_position = uniforms.projection * uniforms.view * vec4<f32>(in.position, 1.0);
+    out.view_depth = -view_pos.z;
+    out.clip_position = draw.projection * view_pos;
     return out;
 }
 
+// soft_fade and shade_particle come from particle_shading.wgsl
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    // Soft circular falloff (no texture needed for basic particles)
-    let center = in.uv - vec2<f32>(0.5);
-    let dist = dot(center, center) * 4.0;  // 0 at center, 1 at edges
-    let alpha = 1.0 - smoothstep(0.5, 1.0, dist);
-
-    let color = vec4<f32>(in.color.rgb, in.color.a * alpha);
-    if color.a < 0.01 {
-        discard;
-    }
-    return color;
+    return shade_particle(ShadeParams(draw.soft_distance, draw.blend_mode, draw.has_texture), in.color, in.uv, in.uv, in.clip_position, in.view_depth);
 }
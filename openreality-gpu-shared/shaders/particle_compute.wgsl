@@ -0,0 +1,351 @@
+// GPU particle simulation — emission, integration, back-to-front bitonic sort
+// and indirect draw arguments. Dispatched per emitter in this order:
+// cs_clear, cs_emit, cs_simulate, cs_sort (once per step), cs_write_indirect.
+
+struct EmitterParams {
+    velocity_min: vec4<f32>,
+    velocity_max: vec4<f32>,
+    start_color: vec4<f32>,   // rgb + start alpha
+    end_color: vec4<f32>,     // rgb + end alpha
+    shape_params: vec4<f32>,
+    max_particles: u32,
+    emission_rate: f32,
+    lifetime_min: f32,
+    lifetime_max: f32,
+    gravity_modifier: f32,
+    damping: f32,
+    start_size_min: f32,
+    start_size_max: f32,
+    end_size: f32,
+    blend_mode: u32,
+    shape_kind: u32,          // 0 point, 1 sphere, 2 cone, 3 box, 4 mesh
+    mesh_triangle_count: u32,
+    collision_mode: u32,      // 0 none, 1 ground plane, 2 depth buffer
+    plane_height: f32,
+    bounce: f32,
+    friction: f32,
+    soft_distance: f32,
+    sheet_tiles_x: u32,
+    sheet_tiles_y: u32,
+    sheet_frames: u32,
+    sheet_fps: f32,
+    curve_flags: u32,         // bit 0 color, bit 1 size, bit 2 velocity
+    has_texture: u32,
+    _pad1: f32,
+    color_lut: array<vec4<f32>, 8>,
+    size_velocity_lut: array<vec4<f32>, 8>,
+};
+
+struct SimParams {
+    emitter_transform: mat4x4<f32>,
+    view_proj: mat4x4<f32>,
+    inv_view_proj: mat4x4<f32>,
+    camera_pos: vec4<f32>,
+    screen_size: vec2<f32>,
+    dt: f32,
+    spawn_count: u32,
+    seed: u32,
+    sort_count: u32,
+    _pad1: u32,
+    _pad2: u32,
+};
+
+struct SortParams {
+    j: u32,
+    k: u32,
+    _pad1: u32,
+    _pad2: u32,
+};
+
+struct Particle {
+    position_lifetime: vec4<f32>,   // xyz = position, w = remaining lifetime
+    velocity_maxlife: vec4<f32>,    // xyz = velocity, w = max lifetime
+    size: f32,
+    alive: atomic<u32>,
+    _pad1: u32,
+    _pad2: u32,
+};
+
+struct DrawIndirectArgs {
+    vertex_count: u32,
+    instance_count: u32,
+    first_vertex: u32,
+    first_instance: u32,
+};
+
+@group(0) @binding(0) var<storage, read_write> particles: array<Particle>;
+@group(0) @binding(1) var<storage, read_write> sort_keys: array<f32>;
+@group(0) @binding(2) var<storage, read_write> sort_indices: array<u32>;
+@group(0) @binding(3) var<storage, read_write> alive_count: atomic<u32>;
+@group(0) @binding(4) var<storage, read_write> draw_args: DrawIndirectArgs;
+@group(0) @binding(5) var<uniform> emitter: EmitterParams;
+@group(0) @binding(6) var<uniform> sim: SimParams;
+
+// Three vec4 per triangle; the first vertex's w is the cumulative area (0..1]
+@group(0) @binding(7) var<storage, read> emit_triangles: array<vec4<f32>>;
+
+@group(1) @binding(0) var<uniform> sort: SortParams;
+
+@group(2) @binding(0) var scene_depth: texture_2d<f32>;
+@group(2) @binding(1) var scene_normal: texture_2d<f32>;
+
+const GRAVITY = vec3<f32>(0.0, -9.81, 0.0);
+
+// Emission gives up after this many occupied slots (pool effectively full).
+const MAX_EMIT_PROBES: u32 = 256u;
+
+// PCG hash for GPU RNG
+fn pcg_hash(v: u32) -> u32 {
+    let state = v * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+// Uniform in [0, 1)
+fn rand01(seed: ptr<function, u32>) -> f32 {
+    *seed = pcg_hash(*seed);
+    return f32(*seed >> 8u) / 16777216.0;
+}
+
+fn rand_range(lo: f32, hi: f32, seed: ptr<function, u32>) -> f32 {
+    return lo + rand01(seed) * (hi - lo);
+}
+
+// Piecewise-linear lookup into a baked 8-sample curve
+fn sample_lut(lut: array<vec4<f32>, 8>, t: f32) -> vec4<f32> {
+    let x = clamp(t, 0.0, 1.0) * 7.0;
+    let i = min(u32(x), 6u);
+    return mix(lut[i], lut[i + 1u], x - f32(i));
+}
+
+struct ShapeSample {
+    offset: vec3<f32>,
+    dir: vec3<f32>,
+};
+
+// Mirrors `particles::sample_shape` in openreality-gpu-shared
+fn sample_shape(seed: ptr<function, u32>) -> ShapeSample {
+    let p = emitter.shape_params;
+    switch emitter.shape_kind {
+        case 1u: {
+            let z = 2.0 * rand01(seed) - 1.0;
+            let phi = 6.2831853 * rand01(seed);
+            let r = sqrt(max(1.0 - z * z, 0.0));
+            let dir = vec3<f32>(r * cos(phi), z, r * sin(phi));
+            var dist = p.x;
+            if p.y == 0.0 {
+                dist = p.x * pow(rand01(seed), 1.0 / 3.0);
+            }
+            return ShapeSample(dir * dist, dir);
+        }
+        case 2u: {
+            let cos_theta = 1.0 - rand01(seed) * (1.0 - cos(p.x));
+            let sin_theta = sqrt(max(1.0 - cos_theta * cos_theta, 0.0));
+            let phi = 6.2831853 * rand01(seed);
+            let dir = vec3<f32>(sin_theta * cos(phi), cos_theta, sin_theta * sin(phi));
+            let offset = vec3<f32>(cos(phi), 0.0, sin(phi)) * (p.y * sqrt(rand01(seed)));
+            return ShapeSample(offset, dir);
+        }
+        case 3u: {
+            let u = vec3<f32>(rand01(seed), rand01(seed), rand01(seed));
+            return ShapeSample((u * 2.0 - 1.0) * p.xyz, vec3<f32>(0.0, 1.0, 0.0));
+        }
+        case 4u: {
+            if emitter.mesh_triangle_count == 0u {
+                break;
+            }
+            // First triangle whose cumulative area reaches r
+            let r = rand01(seed);
+            var lo = 0u;
+            var hi = emitter.mesh_triangle_count - 1u;
+            while lo < hi {
+                let mid = (lo + hi) / 2u;
+                if emit_triangles[mid * 3u].w < r {
+                    lo = mid + 1u;
+                } else {
+                    hi = mid;
+                }
+            }
+            let a = emit_triangles[lo * 3u].xyz;
+            let b = emit_triangles[lo * 3u + 1u].xyz;
+            let c = emit_triangles[lo * 3u + 2u].xyz;
+            let s = sqrt(rand01(seed));
+            let v = rand01(seed);
+            let pos = a * (1.0 - s) + b * (s * (1.0 - v)) + c * (s * v);
+            return ShapeSample(pos, normalize(cross(b - a, c - a)));
+        }
+        default: {}
+    }
+    return ShapeSample(vec3<f32>(0.0), vec3<f32>(0.0, 1.0, 0.0));
+}
+
+// Rotate `v` by the shortest arc taking +Y onto `dir` (Rodrigues)
+fn orient_velocity(v: vec3<f32>, dir: vec3<f32>) -> vec3<f32> {
+    let c = dir.y;
+    if c > 0.99999 {
+        return v;
+    }
+    if c < -0.99999 {
+        return vec3<f32>(v.x, -v.y, -v.z);
+    }
+    let k = normalize(vec3<f32>(dir.z, 0.0, -dir.x));
+    let s = sqrt(1.0 - c * c);
+    return v * c + cross(k, v) * s + k * dot(k, v) * (1.0 - c);
+}
+
+@compute @workgroup_size(256)
+fn cs_clear(@builtin(global_invocation_id) gid: vec3<u32>) {
+    let i = gid.x;
+    if i == 0u {
+        atomicStore(&alive_count, 0u);
+    }
+    if i >= sim.sort_count {
+        return;
+    }
+    // Negative keys sort after every live particle
+    sort_keys[i] = -1.0;
+    sort_indices[i] = 0u;
+}
+
+@compute @workgroup_size(64)
+fn cs_emit(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if gid.x >= sim.spawn_count {
+        return;
+    }
+    var seed = sim.seed ^ (gid.x * 1973u + 9277u);
+    let max_p = emitter.max_particles;
+    let start = pcg_hash(seed) % max_p;
+
+    for (var attempt = 0u; attempt < min(max_p, MAX_EMIT_PROBES); attempt++) {
+        let slot = (start + attempt) % max_p;
+        // Claim a dead slot (0 -> 1)
+        if atomicCompareExchangeWeak(&particles[slot].alive, 0u, 1u).exchanged {
+            let lifetime = rand_range(emitter.lifetime_min, emitter.lifetime_max, &seed);
+            let local_velocity = vec3<f32>(
+                rand_range(emitter.velocity_min.x, emitter.velocity_max.x, &seed),
+                rand_range(emitter.velocity_min.y, emitter.velocity_max.y, &seed),
+                rand_range(emitter.velocity_min.z, emitter.velocity_max.z, &seed),
+            );
+            let shape = sample_shape(&seed);
+
+            // Shapes scale with the emitter; velocities only rotate
+            let m = sim.emitter_transform;
+            let rotation = mat3x3<f32>(normalize(m[0].xyz), normalize(m[1].xyz), normalize(m[2].xyz));
+            let position = (m * vec4<f32>(shape.offset, 1.0)).xyz;
+            let velocity = rotation * orient_velocity(local_velocity, shape.dir);
+
+            particles[slot].position_lifetime = vec4<f32>(position, lifetime);
+            particles[slot].velocity_maxlife = vec4<f32>(velocity, lifetime);
+            particles[slot].size = rand_range(emitter.start_size_min, emitter.start_size_max, &seed);
+            return;
+        }
+    }
+}
+
+@compute @workgroup_size(256)
+fn cs_simulate(@builtin(global_invocation_id) gid: vec3<u32>) {
+    let i = gid.x;
+    if i >= emitter.max_particles || atomicLoad(&particles[i].alive) == 0u {
+        return;
+    }
+
+    let lifetime = particles[i].position_lifetime.w - sim.dt;
+    if lifetime <= 0.0 {
+        atomicStore(&particles[i].alive, 0u);
+        return;
+    }
+
+    let max_life = particles[i].velocity_maxlife.w;
+    let t = 1.0 - clamp(lifetime / max(max_life, 1e-6), 0.0, 1.0);
+
+    var vel = particles[i].velocity_maxlife.xyz + GRAVITY * emitter.gravity_modifier * sim.dt;
+    if emitter.damping > 0.0 {
+        vel *= 1.0 - emitter.damping * sim.dt;
+    }
+    // Velocity over lifetime moves the particle without accumulating
+    var step_vel = vel;
+    if (emitter.curve_flags & 4u) != 0u {
+        step_vel += sample_lut(emitter.size_velocity_lut, t).yzw;
+    }
+    var pos = particles[i].position_lifetime.xyz + step_vel * sim.dt;
+
+    if emitter.collision_mode == 1u {
+        if pos.y < emitter.plane_height && vel.y <= 0.0 {
+            pos.y = emitter.plane_height;
+            vel = vec3<f32>(vel.x, -vel.y * emitter.bounce, vel.z);
+            let keep = 1.0 - clamp(emitter.friction, 0.0, 1.0);
+            vel = vec3<f32>(vel.x * keep, vel.y, vel.z * keep);
+        }
+    } else if emitter.collision_mode == 2u {
+        collide_depth(&pos, &vel, particles[i].size);
+    }
+
+    particles[i].position_lifetime = vec4<f32>(pos, lifetime);
+    particles[i].velocity_maxlife = vec4<f32>(vel, particles[i].velocity_maxlife.w);
+
+    // Append to the draw list with squared camera distance as the sort key
+    let n = atomicAdd(&alive_count, 1u);
+    let d = pos - sim.camera_pos.xyz;
+    sort_keys[n] = dot(d, d);
+    sort_indices[n] = i;
+}
+
+// Bounce off the visible scene surface using the G-buffer depth and normals.
+// Particles hidden behind geometry by more than a thin shell are left alone.
+fn collide_depth(pos: ptr<function, vec3<f32>>, vel: ptr<function, vec3<f32>>, size: f32) {
+    let clip = sim.view_proj * vec4<f32>(*pos, 1.0);
+    if clip.w <= 0.0 {
+        return;
+    }
+    let ndc = clip.xyz / clip.w;
+    if abs(ndc.x) >= 1.0 || abs(ndc.y) >= 1.0 {
+        return;
+    }
+    let uv = vec2<f32>(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+    let pixel = vec2<i32>(uv * sim.screen_size);
+    let depth = textureLoad(scene_depth, pixel, 0).r;
+    if ndc.z <= depth {
+        return; // In front of the surface
+    }
+    let world = sim.inv_view_proj * vec4<f32>(ndc.xy, depth, 1.0);
+    let surface = world.xyz / world.w;
+    if distance(*pos, surface) > max(0.25, size * 2.0) {
+        return;
+    }
+    let n = normalize(textureLoad(scene_normal, pixel, 0).xyz * 2.0 - 1.0);
+    let vn = dot(*vel, n);
+    if vn >= 0.0 {
+        return;
+    }
+    let tangent = (*vel - n * vn) * (1.0 - clamp(emitter.friction, 0.0, 1.0));
+    *vel = tangent - n * vn * emitter.bounce;
+    *pos = surface + n * 0.01;
+}
+
+// One bitonic merge step; sorts keys descending (farthest first).
+@compute @workgroup_size(256)
+fn cs_sort(@builtin(global_invocation_id) gid: vec3<u32>) {
+    let i = gid.x;
+    let l = i ^ sort.j;
+    if i >= sim.sort_count || l <= i {
+        return;
+    }
+    let ki = sort_keys[i];
+    let kl = sort_keys[l];
+    let descending = (i & sort.k) == 0u;
+    if (descending && ki < kl) || (!descending && ki > kl) {
+        sort_keys[i] = kl;
+        sort_keys[l] = ki;
+        let tmp = sort_indices[i];
+        sort_indices[i] = sort_indices[l];
+        sort_indices[l] = tmp;
+    }
+}
+
+@compute @workgroup_size(1)
+fn cs_write_indirect() {
+    draw_args.vertex_count = 6u;
+    draw_args.instance_count = atomicLoad(&alive_count);
+    draw_args.first_vertex = 0u;
+    draw_args.first_instance = 0u;
+}
@@ -0,0 +1,45 @@
+// Particle fragment shading shared by particle.wgsl and gpu_particle.wgsl,
+// appended to both in shaders.rs. Expects the including shader to declare
+// `draw.inv_projection`, `scene_depth`, `sprite_texture` and `sprite_sampler`.
+
+// Fade towards opaque geometry so sprites don't clip hard into it.
+// Compares view-space distances; the scene depth is linearised through
+// the inverse projection.
+fn soft_fade(frag_coord: vec4<f32>, view_depth: f32, soft_distance: f32) -> f32 {
+    if soft_distance <= 0.0 {
+        return 1.0;
+    }
+    let depth = textureLoad(scene_depth, vec2<i32>(frag_coord.xy), 0).r;
+    let v = draw.inv_projection * vec4<f32>(0.0, 0.0, depth, 1.0);
+    let scene_view_depth = -v.z / v.w;
+    return clamp((scene_view_depth - view_depth) / soft_distance, 0.0, 1.0);
+}
+
+struct ShadeParams {
+    soft_distance: f32,
+    blend_mode: u32,
+    has_texture: u32,
+};
+
+fn shade_particle(params: ShadeParams, color: vec4<f32>, sheet_uv: vec2<f32>, quad_uv: vec2<f32>, frag_coord: vec4<f32>, view_depth: f32) -> vec4<f32> {
+    var alpha = color.a;
+    var rgb = color.rgb;
+    if params.has_texture != 0u {
+        let texel = textureSample(sprite_texture, sprite_sampler, sheet_uv);
+        rgb *= texel.rgb;
+        alpha *= texel.a;
+    } else {
+        // Soft circular falloff
+        let center = quad_uv - vec2<f32>(0.5);
+        let dist = dot(center, center) * 4.0;  // 0 at center, 1 at edges
+        alpha *= 1.0 - smoothstep(0.5, 1.0, dist);
+    }
+    alpha *= soft_fade(frag_coord, view_depth, params.soft_distance);
+    if alpha < 0.01 {
+        discard;
+    }
+    if params.blend_mode == 2u {
+        return vec4<f32>(rgb * alpha, alpha);
+    }
+    return vec4<f32>(rgb, alpha);
+}
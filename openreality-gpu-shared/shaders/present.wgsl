@@ -1,28 +1,116 @@
-// Final present pass — blit post-processed result to swapchain.
-// Bloom composite already handles tone mapping + gamma correction,
-// so this pass just copies the final result.
+// Final present pass — encode the post-processed result for the swapchain.
+// Bloom composite already handles tone mapping + gamma correction, so this
+// pass undoes the gamma to get linear light relative to paper white, then
+// encodes it for the output (see `display.rs`). Upscaled frames are first
+// sharpened with FSR 1.0 RCAS (Robust Contrast Adaptive Sharpening).
 
-struct PresentParams {
-    bloom_threshold: f32,
-    bloom_intensity: f32,
+struct OutputParams {
+    encoding: u32,
     gamma: f32,
-    tone_mapping_mode: i32,
-    horizontal: i32,
+    paper_white_nits: f32,
+    max_nits: f32,
+    sharpness: f32,
     _pad1: f32,
     _pad2: f32,
     _pad3: f32,
 };
 
-@group(0) @binding(0) var<uniform> params: PresentParams;
+@group(0) @binding(0) var<uniform> params: OutputParams;
 @group(0) @binding(1) var scene_texture: texture_2d<f32>;
 @group(0) @binding(2) var tex_sampler: sampler;
 
 struct FragmentInput {
+    @builtin(position) position: vec4<f32>,
     @location(0) uv: vec2<f32>,
 };
 
+const OUTPUT_SDR_LINEAR: u32 = 0u;
+const OUTPUT_SDR_GAMMA: u32 = 1u;
+const OUTPUT_SCRGB: u32 = 2u;
+
+// Largest negative lobe RCAS uses, from ffx_fsr1.h
+const RCAS_LIMIT: f32 = 0.25 - 1.0 / 16.0;
+
+const SCRGB_WHITE_NITS: f32 = 80.0;
+const PQ_MAX_NITS: f32 = 10000.0;
+
+// BT.709 to BT.2020 primaries. Listed as rows, so `color * m` applies it.
+const REC709_TO_REC2020 = mat3x3<f32>(
+    vec3<f32>(0.6274, 0.3293, 0.0433),
+    vec3<f32>(0.0691, 0.9195, 0.0114),
+    vec3<f32>(0.0164, 0.0880, 0.8956),
+);
+
+fn srgb_encode(c: vec3<f32>) -> vec3<f32> {
+    let low = c * 12.92;
+    let high = 1.055 * pow(c, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, c <= vec3<f32>(0.0031308));
+}
+
+// SMPTE ST 2084 (PQ) of absolute luminance.
+fn pq_encode(nits: vec3<f32>) -> vec3<f32> {
+    let m1 = 0.1593017578125;
+    let m2 = 78.84375;
+    let c1 = 0.8359375;
+    let c2 = 18.8515625;
+    let c3 = 18.6875;
+    let y = pow(clamp(nits / PQ_MAX_NITS, vec3<f32>(0.0), vec3<f32>(1.0)), vec3<f32>(m1));
+    return pow((c1 + c2 * y) / (1.0 + c3 * y), vec3<f32>(m2));
+}
+
+fn load_scene(pixel: vec2<i32>) -> vec3<f32> {
+    let max_coord = vec2<i32>(textureDimensions(scene_texture, 0)) - 1;
+    return textureLoad(scene_texture, clamp(pixel, vec2<i32>(0), max_coord), 0).rgb;
+}
+
+// RCAS: sharpen with a negative lobe on the 4 neighbors, limited so the
+// result stays within [0, peak] of the cross.
+//    b
+//  d e f
+//    h
+fn rcas(pixel: vec2<i32>, peak: f32) -> vec3<f32> {
+    let b = load_scene(pixel + vec2<i32>(0, -1)) / peak;
+    let d = load_scene(pixel + vec2<i32>(-1, 0)) / peak;
+    let e = load_scene(pixel) / peak;
+    let f = load_scene(pixel + vec2<i32>(1, 0)) / peak;
+    let h = load_scene(pixel + vec2<i32>(0, 1)) / peak;
+
+    let mn4 = max(min(min(b, d), min(f, h)), vec3<f32>(0.0));
+    let mx4 = max(max(b, d), max(f, h));
+    let hit_min = min(mn4, e) / (4.0 * mx4 + 1e-5);
+    let hit_max = (1.0 - max(mx4, e)) / min(4.0 * mn4 - 4.0, vec3<f32>(-1e-5));
+    let lobe_rgb = max(-hit_min, hit_max);
+    let lobe = max(-RCAS_LIMIT, min(max(lobe_rgb.r, max(lobe_rgb.g, lobe_rgb.b)), 0.0)) * params.sharpness;
+    return (lobe * (b + d + f + h) + e) / (4.0 * lobe + 1.0) * peak;
+}
+
 @fragment
 fn fs_main(in: FragmentInput) -> @location(0) vec4<f32> {
-    let color = textureSample(scene_texture, tex_sampler, in.uv).rgb;
+    let pixel = vec2<i32>(in.position.xy);
+    var encoded: vec3<f32>;
+    if params.sharpness > 0.0 {
+        // Gamma encoded peak of the output, as in color_grading.wgsl
+        var peak = 1.0;
+        if params.encoding >= OUTPUT_SCRGB {
+            peak = pow(params.max_nits / params.paper_white_nits, 1.0 / params.gamma);
+        }
+        encoded = rcas(pixel, peak);
+    } else {
+        encoded = load_scene(pixel);
+    }
+    let linear = pow(max(encoded, vec3<f32>(0.0)), vec3<f32>(params.gamma));
+
+    var color: vec3<f32>;
+    if params.encoding == OUTPUT_SDR_LINEAR {
+        // sRGB surfaces (and the headless float target) encode on store
+        color = min(linear, vec3<f32>(1.0));
+    } else if params.encoding == OUTPUT_SDR_GAMMA {
+        color = srgb_encode(min(linear, vec3<f32>(1.0)));
+    } else if params.encoding == OUTPUT_SCRGB {
+        color = min(linear * params.paper_white_nits, vec3<f32>(params.max_nits)) / SCRGB_WHITE_NITS;
+    } else {
+        let nits = max(linear * REC709_TO_REC2020, vec3<f32>(0.0)) * params.paper_white_nits;
+        color = pq_encode(min(nits, vec3<f32>(params.max_nits)));
+    }
     return vec4<f32>(color, 1.0);
 }
@@ -0,0 +1,9 @@
+// Shadow atlas tile clear — resets depth to the far plane. Drawn with the
+// viewport and scissor set to one tile, since depth textures can only be
+// cleared whole.
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((vertex << 1u) & 2u), f32(vertex & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 1.0, 1.0);
+}
@@ -1,4 +1,5 @@
 // Shadow depth pass — renders depth only for cascaded shadow maps.
+// Static meshes are drawn instanced from the per-instance storage buffer.
 
 struct PerFrame {
     view: mat4x4<f32>,
@@ -20,9 +21,9 @@ struct PerObject {
 };
 
 @group(0) @binding(0) var<uniform> frame: PerFrame;
-@group(1) @binding(0) var<uniform> object: PerObject;
+@group(1) @binding(0) var<storage, read> instances: array<PerObject>;
 
 @vertex
-fn vs_main(@location(0) position: vec3<f32>) -> @builtin(position) vec4<f32> {
-    return frame.projection * frame.view * object.model * vec4<f32>(position, 1.0);
+fn vs_main(@location(0) position: vec3<f32>, @builtin(instance_index) instance: u32) -> @builtin(position) vec4<f32> {
+    return frame.projection * frame.view * instances[instance].model * vec4<f32>(position, 1.0);
 }
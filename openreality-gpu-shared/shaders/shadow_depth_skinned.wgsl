@@ -0,0 +1,41 @@
+// Shadow depth pass — skinned variant of shadow_depth.wgsl.
+
+struct PerFrame {
+    view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    inv_view_proj: mat4x4<f32>,
+    camera_pos: vec4<f32>,
+    time: f32,
+    _pad1: f32,
+    _pad2: f32,
+    _pad3: f32,
+};
+
+struct PerObject {
+    model: mat4x4<f32>,
+    normal_matrix_col0: vec4<f32>,
+    normal_matrix_col1: vec4<f32>,
+    normal_matrix_col2: vec4<f32>,
+    _pad: vec4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> frame: PerFrame;
+@group(1) @binding(0) var<uniform> object: PerObject;
+@group(2) @binding(0) var<storage, read> bones: array<mat4x4<f32>>;
+
+fn bone(i: u32) -> mat4x4<f32> {
+    return bones[min(i, arrayLength(&bones) - 1u)];
+}
+
+@vertex
+fn vs_main(
+    @location(0) position: vec3<f32>,
+    @location(1) bone_weights: vec4<f32>,
+    @location(2) bone_indices: vec4<u32>,
+) -> @builtin(position) vec4<f32> {
+    let skin = bone(bone_indices.x) * bone_weights.x
+             + bone(bone_indices.y) * bone_weights.y
+             + bone(bone_indices.z) * bone_weights.z
+             + bone(bone_indices.w) * bone_weights.w;
+    return frame.projection * frame.view * object.model * skin * vec4<f32>(position, 1.0);
+}
@@ -14,7 +14,7 @@ struct SSAOParams {
 };
 
 @group(0) @binding(0) var<uniform> params: SSAOParams;
-@group(0) @binding(1) var g_depth: texture_depth_2d;
+@group(0) @binding(1) var g_depth: texture_2d<f32>;
 @group(0) @binding(2) var g_normal_roughness: texture_2d<f32>;
 @group(0) @binding(3) var noise_texture: texture_2d<f32>;
 @group(0) @binding(4) var tex_sampler: sampler;
@@ -26,7 +26,7 @@ struct FragmentInput {
 
 @fragment
 fn fs_main(in: FragmentInput) -> @location(0) f32 {
-    let depth = textureSample(g_depth, depth_sampler, in.uv);
+    let depth = textureSample(g_depth, depth_sampler, in.uv).r;
     if depth >= 1.0 {
         return 1.0;
     }
@@ -53,7 +53,7 @@ fn fs_main(in: FragmentInput) -> @location(0) f32 {
         offset = vec4<f32>(offset.xy / offset.w, offset.zw);
         let sample_uv = offset.xy * 0.5 + 0.5;
 
-        let sample_depth = textureSample(g_depth, depth_sampler, sample_uv);
+        let sample_depth = textureSample(g_depth, depth_sampler, sample_uv).r;
         var sample_view = inv_proj * vec4<f32>(sample_uv * 2.0 - 1.0, sample_depth, 1.0);
         sample_view /= sample_view.w;
 
@@ -15,7 +15,7 @@ struct SSRParams {
 };
 
 @group(0) @binding(0) var<uniform> params: SSRParams;
-@group(0) @binding(1) var g_depth: texture_depth_2d;
+@group(0) @binding(1) var g_depth: texture_2d<f32>;
 @group(0) @binding(2) var g_normal_roughness: texture_2d<f32>;
 @group(0) @binding(3) var lighting_result: texture_2d<f32>;
 @group(0) @binding(4) var tex_sampler: sampler;
@@ -27,7 +27,7 @@ struct FragmentInput {
 
 @fragment
 fn fs_main(in: FragmentInput) -> @location(0) vec4<f32> {
-    let depth = textureSample(g_depth, depth_sampler, in.uv);
+    let depth = textureSample(g_depth, depth_sampler, in.uv).r;
     if depth >= 1.0 {
         return vec4<f32>(0.0);
     }
@@ -64,7 +64,7 @@ fn fs_main(in: FragmentInput) -> @location(0) vec4<f32> {
             break;
         }
 
-        let sample_depth = textureSample(g_depth, depth_sampler, sample_uv);
+        let sample_depth = textureSample(g_depth, depth_sampler, sample_uv).r;
         var sample_view = params.inv_projection * vec4<f32>(sample_uv * 2.0 - 1.0, sample_depth, 1.0);
         sample_view /= sample_view.w;
 
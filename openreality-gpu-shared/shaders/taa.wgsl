@@ -11,7 +11,7 @@ struct TAAParams {
 @group(0) @binding(0) var<uniform> params: TAAParams;
 @group(0) @binding(1) var current_frame: texture_2d<f32>;
 @group(0) @binding(2) var history_frame: texture_2d<f32>;
-@group(0) @binding(3) var depth_texture: texture_depth_2d;
+@group(0) @binding(3) var depth_texture: texture_2d<f32>;
 @group(0) @binding(4) var tex_sampler: sampler;
 @group(0) @binding(5) var depth_sampler: sampler;
 
@@ -0,0 +1,302 @@
+//! Keyframe sampling shared by the native and web runtimes.
+//!
+//! Channel values are stored flat, as exported in ORSB: three components per
+//! key for position/scale and four (w, x, y, z) for rotation. Cubic-spline
+//! channels follow glTF and store an in-tangent, the value and an out-tangent
+//! for every key, so they hold three times as many values.
+
+use glam::{DQuat, DVec3};
+
+use crate::scene_format::InterpolationMode;
+
+/// Number of stored elements per key and component for `interpolation`.
+pub fn values_per_key(interpolation: InterpolationMode) -> usize {
+    match interpolation {
+        InterpolationMode::CubicSpline => 3,
+        InterpolationMode::Step | InterpolationMode::Linear => 1,
+    }
+}
+
+/// Binary search for the keyframe interval containing `time`.
+/// Returns (index0, index1, interpolation_factor) or None.
+pub fn find_keyframe(times: &[f32], time: f32) -> Option<(usize, usize, f32)> {
+    if times.is_empty() {
+        return None;
+    }
+    if times.len() == 1 || time <= times[0] {
+        return Some((0, 0, 0.0));
+    }
+    if time >= times[times.len() - 1] {
+        let last = times.len() - 1;
+        return Some((last, last, 0.0));
+    }
+
+    let mut lo = 0;
+    let mut hi = times.len() - 1;
+    while lo < hi - 1 {
+        let mid = (lo + hi) / 2;
+        if times[mid] <= time {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+
+    let t0 = times[lo];
+    let t1 = times[hi];
+    let factor = if (t1 - t0).abs() < 1e-8 {
+        0.0
+    } else {
+        (time - t0) / (t1 - t0)
+    };
+
+    Some((lo, hi, factor))
+}
+
+/// Sample a position or scale channel at `time`.
+pub fn sample_vec3(
+    interpolation: InterpolationMode,
+    times: &[f32],
+    values: &[f64],
+    time: f32,
+) -> Option<DVec3> {
+    let v = sample::<3>(interpolation, times, values, time, |a, b, t| {
+        let r = DVec3::from_array(a).lerp(DVec3::from_array(b), t);
+        r.to_array()
+    })?;
+    Some(DVec3::from_array(v))
+}
+
+/// Sample a rotation channel at `time`. Values are stored w, x, y, z.
+pub fn sample_quat(
+    interpolation: InterpolationMode,
+    times: &[f32],
+    values: &[f64],
+    time: f32,
+) -> Option<DQuat> {
+    let v = sample::<4>(interpolation, times, values, time, |a, b, t| {
+        let r = slerp(quat_from_wxyz(a), quat_from_wxyz(b), t);
+        [r.w, r.x, r.y, r.z]
+    })?;
+    // Cubic-spline output is not unit length in general.
+    Some(quat_from_wxyz(v).normalize())
+}
+
+/// Sample a morph weight channel holding `count` weights per key.
+pub fn sample_weights(
+    interpolation: InterpolationMode,
+    times: &[f32],
+    values: &[f64],
+    count: usize,
+    time: f32,
+) -> Option<Vec<f64>> {
+    let (i0, i1, t) = find_keyframe(times, time)?;
+    let stride = count * values_per_key(interpolation);
+    if count == 0 || values.len() < times.len() * stride {
+        return None;
+    }
+    let t = t as f64;
+    let dt = (times[i1] - times[i0]) as f64;
+
+    let weights = (0..count)
+        .map(|k| match interpolation {
+            InterpolationMode::Step => values[i0 * stride + k],
+            InterpolationMode::Linear => {
+                let a = values[i0 * stride + k];
+                a + (values[i1 * stride + k] - a) * t
+            }
+            InterpolationMode::CubicSpline => {
+                let v0 = values[i0 * stride + count + k];
+                if i0 == i1 {
+                    return v0;
+                }
+                let b0 = values[i0 * stride + 2 * count + k];
+                let a1 = values[i1 * stride + k];
+                let v1 = values[i1 * stride + count + k];
+                hermite([v0], [b0], [v1], [a1], dt, t)[0]
+            }
+        })
+        .collect();
+    Some(weights)
+}
+
+fn quat_from_wxyz(v: [f64; 4]) -> DQuat {
+    DQuat::from_xyzw(v[1], v[2], v[3], v[0])
+}
+
+fn sample<const N: usize>(
+    interpolation: InterpolationMode,
+    times: &[f32],
+    values: &[f64],
+    time: f32,
+    linear: impl Fn([f64; N], [f64; N], f64) -> [f64; N],
+) -> Option<[f64; N]> {
+    let (i0, i1, t) = find_keyframe(times, time)?;
+    let stride = N * values_per_key(interpolation);
+    if values.len() < times.len() * stride {
+        return None;
+    }
+
+    let read = |offset: usize| -> [f64; N] {
+        let mut out = [0.0; N];
+        out.copy_from_slice(&values[offset..offset + N]);
+        out
+    };
+
+    match interpolation {
+        InterpolationMode::Step => Some(read(i0 * stride)),
+        InterpolationMode::Linear => {
+            Some(linear(read(i0 * stride), read(i1 * stride), t as f64))
+        }
+        InterpolationMode::CubicSpline => {
+            // Layout per key: [in_tangent, value, out_tangent]
+            let v0 = read(i0 * stride + N);
+            if i0 == i1 {
+                return Some(v0);
+            }
+            let b0 = read(i0 * stride + 2 * N);
+            let a1 = read(i1 * stride);
+            let v1 = read(i1 * stride + N);
+            let dt = (times[i1] - times[i0]) as f64;
+            Some(hermite(v0, b0, v1, a1, dt, t as f64))
+        }
+    }
+}
+
+/// Cubic Hermite spline as defined by glTF 2.0, Appendix C. Tangents are
+/// scaled by the keyframe interval `dt`.
+fn hermite<const N: usize>(
+    v0: [f64; N],
+    out_tangent0: [f64; N],
+    v1: [f64; N],
+    in_tangent1: [f64; N],
+    dt: f64,
+    t: f64,
+) -> [f64; N] {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    let h00 = 2.0 * t3 - 3.0 * t2 + 1.0;
+    let h10 = t3 - 2.0 * t2 + t;
+    let h01 = -2.0 * t3 + 3.0 * t2;
+    let h11 = t3 - t2;
+
+    let mut out = [0.0; N];
+    for (i, o) in out.iter_mut().enumerate() {
+        *o = h00 * v0[i] + h10 * dt * out_tangent0[i] + h01 * v1[i] + h11 * dt * in_tangent1[i];
+    }
+    out
+}
+
+/// Shortest-path spherical interpolation between two unit quaternions.
+pub fn slerp(a: DQuat, b: DQuat, t: f64) -> DQuat {
+    let mut dot = a.dot(b);
+
+    // Ensure shortest path
+    let b = if dot < 0.0 {
+        dot = -dot;
+        -b
+    } else {
+        b
+    };
+
+    if dot > 0.9995 {
+        // Very close — use linear interpolation to avoid divide-by-zero
+        return (a + (b - a) * t).normalize();
+    }
+
+    let theta = dot.acos();
+    let sin_theta = theta.sin();
+    let w0 = ((1.0 - t) * theta).sin() / sin_theta;
+    let w1 = (t * theta).sin() / sin_theta;
+
+    a * w0 + b * w1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_keyframe_clamps_and_brackets() {
+        let times = [0.0, 1.0, 3.0];
+        assert_eq!(find_keyframe(&times, -1.0), Some((0, 0, 0.0)));
+        assert_eq!(find_keyframe(&times, 5.0), Some((2, 2, 0.0)));
+        assert_eq!(find_keyframe(&times, 2.0), Some((1, 2, 0.5)));
+        assert_eq!(find_keyframe(&[], 0.0), None);
+    }
+
+    #[test]
+    fn test_linear_and_step_vec3() {
+        let times = [0.0, 2.0];
+        let values = [0.0, 0.0, 0.0, 2.0, 4.0, 6.0];
+        let lin = sample_vec3(InterpolationMode::Linear, &times, &values, 1.0).unwrap();
+        assert!((lin - DVec3::new(1.0, 2.0, 3.0)).length() < 1e-9);
+        let step = sample_vec3(InterpolationMode::Step, &times, &values, 1.9).unwrap();
+        assert_eq!(step, DVec3::ZERO);
+    }
+
+    #[test]
+    fn test_cubic_spline_uses_value_not_tangent() {
+        // Keys at 0 and 1 with values 0 and 10, zero tangents. The tangents
+        // sit before each value, so naive indexing would return garbage.
+        let times = [0.0, 1.0];
+        #[rustfmt::skip]
+        let values = [
+            9.0, 9.0, 9.0,  0.0, 0.0, 0.0,  0.0, 0.0, 0.0,
+            0.0, 0.0, 0.0,  10.0, 10.0, 10.0,  9.0, 9.0, 9.0,
+        ];
+        let mode = InterpolationMode::CubicSpline;
+        assert_eq!(sample_vec3(mode, &times, &values, 0.0).unwrap(), DVec3::ZERO);
+        assert_eq!(sample_vec3(mode, &times, &values, 1.0).unwrap(), DVec3::splat(10.0));
+        // Zero tangents give smoothstep: h01(0.25) = 0.15625
+        let v = sample_vec3(mode, &times, &values, 0.25).unwrap();
+        assert!((v.x - 1.5625).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_cubic_spline_tangents_scaled_by_interval() {
+        // Tangents equal to the slope (5 units/s over 2 s) reproduce a straight line.
+        let times = [0.0, 2.0];
+        #[rustfmt::skip]
+        let values = [
+            5.0, 0.0, 0.0,  0.0, 0.0, 0.0,  5.0, 0.0, 0.0,
+            5.0, 0.0, 0.0,  10.0, 0.0, 0.0,  5.0, 0.0, 0.0,
+        ];
+        for &t in &[0.3_f32, 1.0, 1.7] {
+            let v = sample_vec3(InterpolationMode::CubicSpline, &times, &values, t).unwrap();
+            assert!((v.x - 5.0 * t as f64).abs() < 1e-6, "t={t}: {}", v.x);
+        }
+    }
+
+    #[test]
+    fn test_cubic_spline_quat_is_normalized() {
+        let times = [0.0, 1.0];
+        let half = std::f64::consts::FRAC_1_SQRT_2;
+        #[rustfmt::skip]
+        let values = [
+            0.0, 0.0, 0.0, 0.0,  1.0, 0.0, 0.0, 0.0,   0.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, 0.0,  half, 0.0, half, 0.0, 0.0, 0.0, 0.0, 0.0,
+        ];
+        let q = sample_quat(InterpolationMode::CubicSpline, &times, &values, 0.5).unwrap();
+        assert!((q.length() - 1.0).abs() < 1e-9);
+        assert!(q.y > 0.0 && q.y < half);
+    }
+
+    #[test]
+    fn test_sample_weights() {
+        let times = [0.0, 1.0];
+        let values = [0.0, 1.0, 1.0, 0.0];
+        let w = sample_weights(InterpolationMode::Linear, &times, &values, 2, 0.25).unwrap();
+        assert_eq!(w, vec![0.25, 0.75]);
+        let w = sample_weights(InterpolationMode::Step, &times, &values, 2, 0.9).unwrap();
+        assert_eq!(w, vec![0.0, 1.0]);
+        assert!(sample_weights(InterpolationMode::Linear, &times, &values, 3, 0.5).is_none());
+    }
+
+    #[test]
+    fn test_short_values_rejected() {
+        let times = [0.0, 1.0];
+        let values = [0.0; 6];
+        assert!(sample_vec3(InterpolationMode::CubicSpline, &times, &values, 0.5).is_none());
+    }
+}
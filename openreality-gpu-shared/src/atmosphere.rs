@@ -0,0 +1,491 @@
+//! Sky and participating media. The sky is single-scattered sunlight in a
+//! Rayleigh/Mie/ozone atmosphere, baked on the CPU into a cubemap that is
+//! both drawn as the background and projected to spherical harmonics for
+//! diffuse IBL. Volumetric fog is integrated on the GPU over a froxel grid
+//! (`fog_inject.wgsl`, `fog_integrate.wgsl`); the slice math here mirrors
+//! the shaders.
+
+use bytemuck::{Pod, Zeroable};
+use glam::Vec3;
+
+/// Froxel grid of the fog volume: tiles along x and y, depth slices along z.
+pub const FOG_GRID: [u32; 3] = [160, 90, 64];
+
+/// Ozone layer: a tent of this half width (km) centered at this altitude (km).
+const OZONE_CENTER: f32 = 25.0;
+const OZONE_HALF_WIDTH: f32 = 15.0;
+
+/// Integration steps along view and sun rays when baking the sky.
+const VIEW_STEPS: u32 = 16;
+const SUN_STEPS: u32 = 8;
+
+/// Physically based sky, set through `or_wgpu_set_atmosphere`. Distances
+/// are in kilometers and coefficients per kilometer; the defaults are
+/// Earth's. Sky radiance is per unit of sun illuminance, which comes from
+/// the first directional light.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct AtmosphereSettings {
+    pub rayleigh_scattering: [f32; 3],
+    pub rayleigh_scale_height: f32,
+    pub mie_scattering: f32,
+    pub mie_absorption: f32,
+    pub mie_scale_height: f32,
+    /// Henyey-Greenstein asymmetry of Mie scattering, -1..1.
+    pub mie_anisotropy: f32,
+    pub ozone_absorption: [f32; 3],
+    pub planet_radius: f32,
+    pub atmosphere_height: f32,
+    /// Albedo of the ground below the horizon.
+    pub ground_albedo: f32,
+    /// Angular radius (radians) of the sun disk; 0 hides the disk.
+    pub sun_angular_radius: f32,
+    /// Scale of the sky's radiance, for the background and IBL alike.
+    pub intensity: f32,
+    pub enabled: u32,
+}
+
+impl Default for AtmosphereSettings {
+    fn default() -> Self {
+        Self {
+            rayleigh_scattering: [5.802e-3, 13.558e-3, 33.1e-3],
+            rayleigh_scale_height: 8.0,
+            mie_scattering: 3.996e-3,
+            mie_absorption: 4.4e-3,
+            mie_scale_height: 1.2,
+            mie_anisotropy: 0.8,
+            ozone_absorption: [0.650e-3, 1.881e-3, 0.085e-3],
+            planet_radius: 6360.0,
+            atmosphere_height: 100.0,
+            ground_albedo: 0.3,
+            sun_angular_radius: 0.01,
+            intensity: 1.0,
+            enabled: 0,
+        }
+    }
+}
+
+/// Froxel volumetric fog, set through `or_wgpu_set_fog`. The extinction at
+/// height `y` is `density + height_density * exp(-height_falloff * (y - base_height))`,
+/// with the height term held constant below `base_height`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct FogSettings {
+    /// Single scattering albedo; scattering is `albedo * extinction`.
+    pub albedo: [f32; 3],
+    /// Uniform extinction (1/m).
+    pub density: f32,
+    /// Extinction of the height fog at `base_height` (1/m).
+    pub height_density: f32,
+    pub height_falloff: f32,
+    pub base_height: f32,
+    /// Henyey-Greenstein asymmetry, -1..1; positive scatters forward.
+    pub anisotropy: f32,
+    /// View distance the froxel grid covers; farther surfaces get its fog.
+    pub max_distance: f32,
+    /// Scale of the sky's ambient light scattered by the fog.
+    pub ambient_intensity: f32,
+    pub enabled: u32,
+}
+
+impl Default for FogSettings {
+    fn default() -> Self {
+        Self {
+            albedo: [0.9, 0.9, 0.9],
+            density: 0.01,
+            height_density: 0.05,
+            height_falloff: 0.2,
+            base_height: 0.0,
+            anisotropy: 0.6,
+            max_distance: 100.0,
+            ambient_intensity: 1.0,
+            enabled: 0,
+        }
+    }
+}
+
+impl AtmosphereSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        let coefficients = self.rayleigh_scattering.iter().chain(&self.ozone_absorption);
+        if coefficients.chain([&self.mie_scattering, &self.mie_absorption]).any(|c| *c < 0.0) {
+            return Err("Atmosphere scattering and absorption must be >= 0".into());
+        }
+        if self.rayleigh_scale_height <= 0.0 || self.mie_scale_height <= 0.0 {
+            return Err("Atmosphere scale heights must be > 0".into());
+        }
+        if self.planet_radius <= 0.0 || self.atmosphere_height <= 0.0 {
+            return Err("Planet radius and atmosphere height must be > 0".into());
+        }
+        if !(-1.0 < self.mie_anisotropy && self.mie_anisotropy < 1.0) {
+            return Err(format!("Mie anisotropy must be in (-1, 1), got {}", self.mie_anisotropy));
+        }
+        if !(0.0..=1.0).contains(&self.ground_albedo) {
+            return Err(format!("Ground albedo must be in [0, 1], got {}", self.ground_albedo));
+        }
+        if !(0.0..0.5).contains(&self.sun_angular_radius) || self.intensity < 0.0 {
+            return Err("Sun angular radius must be in [0, 0.5) and intensity >= 0".into());
+        }
+        Ok(())
+    }
+
+    /// Extinction per km at altitude `h` (km).
+    fn extinction(&self, h: f32) -> Vec3 {
+        let (rayleigh, mie, ozone) = self.densities(h);
+        Vec3::from(self.rayleigh_scattering) * rayleigh
+            + Vec3::splat((self.mie_scattering + self.mie_absorption) * mie)
+            + Vec3::from(self.ozone_absorption) * ozone
+    }
+
+    /// Relative Rayleigh, Mie and ozone densities at altitude `h` (km).
+    fn densities(&self, h: f32) -> (f32, f32, f32) {
+        let h = h.max(0.0);
+        let ozone = (1.0 - (h - OZONE_CENTER).abs() / OZONE_HALF_WIDTH).max(0.0);
+        ((-h / self.rayleigh_scale_height).exp(), (-h / self.mie_scale_height).exp(), ozone)
+    }
+
+    fn top_radius(&self) -> f32 {
+        self.planet_radius + self.atmosphere_height
+    }
+
+    /// Transmittance from altitude `h` (km) to space along `dir`; zero when
+    /// the planet is in the way.
+    pub fn transmittance(&self, h: f32, dir: Vec3) -> Vec3 {
+        let origin = Vec3::new(0.0, self.planet_radius + h, 0.0);
+        if ray_sphere(origin, dir, self.planet_radius).is_some_and(|t| t > 0.0) {
+            return Vec3::ZERO;
+        }
+        let Some(length) = ray_sphere(origin, dir, self.top_radius()) else {
+            return Vec3::ONE;
+        };
+        let step = length / SUN_STEPS as f32;
+        let depth = (0..SUN_STEPS).fold(Vec3::ZERO, |depth, i| {
+            let p = origin + dir * ((i as f32 + 0.5) * step);
+            depth + self.extinction(p.length() - self.planet_radius) * step
+        });
+        (-depth).exp()
+    }
+
+    /// Radiance seen from the ground along `view_dir` for a sun of unit
+    /// illuminance in direction `sun_dir` (both unit vectors, y up): single
+    /// scattering along the view ray plus the lit ground below the horizon.
+    /// The sun disk itself is left to the shaders.
+    pub fn sky_radiance(&self, view_dir: Vec3, sun_dir: Vec3) -> Vec3 {
+        // Just above the ground, so horizontal rays leave the planet
+        let origin = Vec3::new(0.0, self.planet_radius + 1e-3, 0.0);
+        let ground = ray_sphere(origin, view_dir, self.planet_radius).filter(|t| *t > 0.0);
+        let length = ground.or_else(|| ray_sphere(origin, view_dir, self.top_radius())).unwrap_or(0.0);
+
+        let mu = view_dir.dot(sun_dir);
+        let rayleigh_phase = 3.0 / (16.0 * std::f32::consts::PI) * (1.0 + mu * mu);
+        let mie_phase = henyey_greenstein(mu, self.mie_anisotropy);
+
+        let step = length / VIEW_STEPS as f32;
+        let mut optical_depth = Vec3::ZERO;
+        let mut radiance = Vec3::ZERO;
+        for i in 0..VIEW_STEPS {
+            let p = origin + view_dir * ((i as f32 + 0.5) * step);
+            let h = p.length() - self.planet_radius;
+            let (rayleigh, mie, _) = self.densities(h);
+            let half_step = self.extinction(h) * (0.5 * step);
+            optical_depth += half_step;
+            let scattering = Vec3::from(self.rayleigh_scattering) * rayleigh * rayleigh_phase
+                + Vec3::splat(self.mie_scattering * mie * mie_phase);
+            let sun = self.transmittance(h, sun_dir);
+            radiance += (-optical_depth).exp() * sun * scattering * step;
+            optical_depth += half_step;
+        }
+
+        if ground.is_some() {
+            let sun = self.transmittance(0.0, sun_dir) * sun_dir.y.max(0.0);
+            radiance += (-optical_depth).exp() * sun * (self.ground_albedo / std::f32::consts::PI);
+        }
+        radiance * self.intensity
+    }
+}
+
+impl FogSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.albedo.iter().any(|a| !(0.0..=1.0).contains(a)) {
+            return Err("Fog albedo must be in [0, 1]".into());
+        }
+        if self.density < 0.0 || self.height_density < 0.0 || self.height_falloff < 0.0 {
+            return Err("Fog densities and height falloff must be >= 0".into());
+        }
+        if !(-1.0 < self.anisotropy && self.anisotropy < 1.0) {
+            return Err(format!("Fog anisotropy must be in (-1, 1), got {}", self.anisotropy));
+        }
+        if self.max_distance <= 0.0 || self.ambient_intensity < 0.0 {
+            return Err("Fog max distance must be > 0 and ambient intensity >= 0".into());
+        }
+        Ok(())
+    }
+
+    /// Extinction (1/m) at world height `y`, as `fog_inject.wgsl` computes it.
+    pub fn extinction(&self, y: f32) -> f32 {
+        self.density + self.height_density * (-self.height_falloff * (y - self.base_height).max(0.0)).exp()
+    }
+}
+
+/// Henyey-Greenstein phase function for the cosine `mu` between the view
+/// and light directions.
+pub fn henyey_greenstein(mu: f32, g: f32) -> f32 {
+    let denom = 1.0 + g * g - 2.0 * g * mu;
+    (1.0 - g * g) / (4.0 * std::f32::consts::PI * denom * denom.sqrt())
+}
+
+/// Distance along unit `dir` from `origin` to where it leaves the sphere of
+/// `radius` around the origin, or the nearer hit when starting outside it.
+fn ray_sphere(origin: Vec3, dir: Vec3, radius: f32) -> Option<f32> {
+    let b = origin.dot(dir);
+    let c = origin.length_squared() - radius * radius;
+    let disc = b * b - c;
+    if disc < 0.0 {
+        return None;
+    }
+    let root = disc.sqrt();
+    if c > 0.0 {
+        (-b - root >= 0.0).then_some(-b - root)
+    } else {
+        Some(-b + root)
+    }
+}
+
+/// View depth where froxel slice coordinate `slice` (0..`slices`, may be
+/// fractional) begins; slices are spaced exponentially over `near..far`.
+pub fn fog_slice_depth(slice: f32, near: f32, far: f32, slices: u32) -> f32 {
+    near * (far / near).powf(slice / slices as f32)
+}
+
+/// Inverse of `fog_slice_depth`, clamped to `0..slices`.
+pub fn fog_depth_slice(depth: f32, near: f32, far: f32, slices: u32) -> f32 {
+    let t = (depth.max(near) / near).ln() / (far / near).ln();
+    (t * slices as f32).clamp(0.0, slices as f32)
+}
+
+/// World-space direction through texel `(x, y)` of cubemap face `face`
+/// (+X, -X, +Y, -Y, +Z, -Z).
+pub fn cubemap_direction(face: u32, x: u32, y: u32, size: u32) -> Vec3 {
+    let u = (x as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+    let v = (y as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+    let dir = match face {
+        0 => Vec3::new(1.0, -v, -u),
+        1 => Vec3::new(-1.0, -v, u),
+        2 => Vec3::new(u, 1.0, v),
+        3 => Vec3::new(u, -1.0, -v),
+        4 => Vec3::new(u, -v, 1.0),
+        _ => Vec3::new(-u, -v, -1.0),
+    };
+    dir.normalize()
+}
+
+/// Solid angle of texel `(x, y)` on a cubemap face of `size`² texels.
+pub fn cubemap_texel_solid_angle(x: u32, y: u32, size: u32) -> f32 {
+    let u = (x as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+    let v = (y as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+    let texel = 2.0 / size as f32;
+    texel * texel / (1.0 + u * u + v * v).powf(1.5)
+}
+
+/// Sky radiance for every texel of a `size`² cubemap, face after face,
+/// for a sun of `sun_illuminance` shining from `sun_dir`.
+pub fn bake_sky_cubemap(settings: &AtmosphereSettings, sun_dir: Vec3, sun_illuminance: Vec3, size: u32) -> Vec<Vec3> {
+    let sun_dir = sun_dir.normalize();
+    (0..6)
+        .flat_map(|face| (0..size).flat_map(move |y| (0..size).map(move |x| (face, x, y))))
+        .map(|(face, x, y)| settings.sky_radiance(cubemap_direction(face, x, y, size), sun_dir) * sun_illuminance)
+        .collect()
+}
+
+/// Next mip of a cubemap baked by `bake_sky_cubemap`: each face halved with
+/// a 2x2 box filter.
+pub fn downsample_cubemap(texels: &[Vec3], size: u32) -> Vec<Vec3> {
+    let half = (size / 2).max(1);
+    let at = |face: u32, x: u32, y: u32| texels[((face * size + y.min(size - 1)) * size + x.min(size - 1)) as usize];
+    (0..6)
+        .flat_map(|face| (0..half).flat_map(move |y| (0..half).map(move |x| (face, x, y))))
+        .map(|(face, x, y)| {
+            let (x, y) = (x * 2, y * 2);
+            (at(face, x, y) + at(face, x + 1, y) + at(face, x, y + 1) + at(face, x + 1, y + 1)) * 0.25
+        })
+        .collect()
+}
+
+/// Irradiance as 9 spherical harmonics coefficients (bands 0-2), already
+/// convolved with the clamped cosine: `E(n) = sum(coeffs[i] * Y_i(n))`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IrradianceSH {
+    pub coeffs: [Vec3; 9],
+}
+
+/// Real spherical harmonics basis, bands 0-2, for unit `n`.
+pub fn sh9_basis(n: Vec3) -> [f32; 9] {
+    [
+        0.282_095,
+        0.488_603 * n.y,
+        0.488_603 * n.z,
+        0.488_603 * n.x,
+        1.092_548 * n.x * n.y,
+        1.092_548 * n.y * n.z,
+        0.315_392 * (3.0 * n.z * n.z - 1.0),
+        1.092_548 * n.x * n.z,
+        0.546_274 * (n.x * n.x - n.y * n.y),
+    ]
+}
+
+impl IrradianceSH {
+    /// Project the radiance of a `size`² cubemap baked by `bake_sky_cubemap`.
+    pub fn from_cubemap(texels: &[Vec3], size: u32) -> Self {
+        let mut coeffs = [Vec3::ZERO; 9];
+        for face in 0..6 {
+            for y in 0..size {
+                for x in 0..size {
+                    let radiance = texels[((face * size + y) * size + x) as usize];
+                    let weight = cubemap_texel_solid_angle(x, y, size);
+                    for (c, basis) in coeffs.iter_mut().zip(sh9_basis(cubemap_direction(face, x, y, size))) {
+                        *c += radiance * (basis * weight);
+                    }
+                }
+            }
+        }
+        // Clamped cosine convolution per band
+        let bands = [std::f32::consts::PI, 2.0 * std::f32::consts::PI / 3.0, std::f32::consts::PI / 4.0];
+        for (i, c) in coeffs.iter_mut().enumerate() {
+            *c *= bands[match i { 0 => 0, 1..=3 => 1, _ => 2 }];
+        }
+        Self { coeffs }
+    }
+
+    /// Irradiance arriving at a surface with unit normal `n`.
+    pub fn irradiance(&self, n: Vec3) -> Vec3 {
+        self.coeffs.iter().zip(sh9_basis(n)).map(|(c, b)| *c * b).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blueness(c: Vec3) -> f32 {
+        c.z / (c.x + c.y + c.z)
+    }
+
+    #[test]
+    fn test_sky_is_blue_above_and_brighter_near_horizon() {
+        let settings = AtmosphereSettings::default();
+        let sun = Vec3::new(0.0, 0.8, 0.6).normalize();
+        let zenith = settings.sky_radiance(Vec3::Y, sun);
+        let horizon = settings.sky_radiance(Vec3::new(-1.0, 0.05, 0.0).normalize(), sun);
+        assert!(zenith.z > zenith.x && zenith.z > zenith.y, "zenith {zenith}");
+        assert!(blueness(zenith) > blueness(horizon));
+        assert!(horizon.length() > zenith.length());
+    }
+
+    #[test]
+    fn test_sunset_reddens_the_sun() {
+        let settings = AtmosphereSettings::default();
+        let noon = settings.transmittance(0.0, Vec3::Y);
+        let sunset = settings.transmittance(0.0, Vec3::new(1.0, 0.02, 0.0).normalize());
+        assert!(noon.x > 0.8 && noon.z > 0.6, "noon {noon}");
+        assert!(sunset.x / sunset.z > noon.x / noon.z * 10.0);
+        // Below the horizon the planet blocks the sun
+        assert_eq!(settings.transmittance(0.0, Vec3::new(1.0, -0.2, 0.0).normalize()), Vec3::ZERO);
+    }
+
+    #[test]
+    fn test_forward_mie_scattering_brightens_around_sun() {
+        let settings = AtmosphereSettings::default();
+        let sun = Vec3::new(0.0, 0.3, 1.0).normalize();
+        let toward = settings.sky_radiance(Vec3::new(0.05, 0.3, 1.0).normalize(), sun);
+        let away = settings.sky_radiance(Vec3::new(0.0, 0.3, -1.0).normalize(), sun);
+        assert!(toward.length() > away.length() * 1.5, "{toward} {away}");
+    }
+
+    #[test]
+    fn test_settings_validate() {
+        assert!(AtmosphereSettings::default().validate().is_ok());
+        assert!(FogSettings::default().validate().is_ok());
+        let bad_g = AtmosphereSettings { mie_anisotropy: 1.0, ..Default::default() };
+        assert!(bad_g.validate().is_err());
+        let bad_height = AtmosphereSettings { rayleigh_scale_height: 0.0, ..Default::default() };
+        assert!(bad_height.validate().is_err());
+        let bad_density = FogSettings { density: -0.1, ..Default::default() };
+        assert!(bad_density.validate().is_err());
+        let bad_albedo = FogSettings { albedo: [1.5, 0.5, 0.5], ..Default::default() };
+        assert!(bad_albedo.validate().is_err());
+    }
+
+    #[test]
+    fn test_fog_extinction() {
+        let fog = FogSettings { density: 0.01, height_density: 0.1, height_falloff: 0.5, base_height: 2.0, ..Default::default() };
+        assert!((fog.extinction(2.0) - 0.11).abs() < 1e-6);
+        assert_eq!(fog.extinction(-5.0), fog.extinction(2.0));
+        assert!((fog.extinction(4.0) - (0.01 + 0.1 * (-1.0f32).exp())).abs() < 1e-6);
+        assert!(fog.extinction(100.0) - 0.01 < 1e-6);
+    }
+
+    #[test]
+    fn test_henyey_greenstein_normalized() {
+        for g in [-0.5, 0.0, 0.8] {
+            let n = 2000;
+            let integral: f32 = (0..n)
+                .map(|i| {
+                    let mu = -1.0 + (i as f32 + 0.5) * 2.0 / n as f32;
+                    henyey_greenstein(mu, g) * 2.0 * std::f32::consts::PI * 2.0 / n as f32
+                })
+                .sum();
+            assert!((integral - 1.0).abs() < 1e-2, "g {g}: {integral}");
+        }
+    }
+
+    #[test]
+    fn test_fog_slice_roundtrip() {
+        let (near, far, slices) = (0.5, 100.0, FOG_GRID[2]);
+        assert!((fog_slice_depth(0.0, near, far, slices) - near).abs() < 1e-5);
+        assert!((fog_slice_depth(slices as f32, near, far, slices) - far).abs() < 1e-3);
+        for slice in [0.0, 3.5, 17.0, 63.9] {
+            let depth = fog_slice_depth(slice, near, far, slices);
+            assert!((fog_depth_slice(depth, near, far, slices) - slice).abs() < 1e-3);
+        }
+        assert_eq!(fog_depth_slice(0.01, near, far, slices), 0.0);
+        assert_eq!(fog_depth_slice(1000.0, near, far, slices), slices as f32);
+    }
+
+    #[test]
+    fn test_cubemap_solid_angles_cover_sphere() {
+        let size = 16;
+        let total: f32 = (0..size)
+            .flat_map(|y| (0..size).map(move |x| cubemap_texel_solid_angle(x, y, size)))
+            .sum::<f32>()
+            * 6.0;
+        assert!((total - 4.0 * std::f32::consts::PI).abs() < 0.05, "{total}");
+        assert!((cubemap_direction(2, 8, 8, size).y - 1.0).abs() < 0.01);
+        assert!((cubemap_direction(5, 8, 8, size).z + 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_irradiance_sh() {
+        // Uniform radiance L gives irradiance pi * L for any normal
+        let size = 16;
+        let uniform = vec![Vec3::new(1.0, 0.5, 0.25); (6 * size * size) as usize];
+        let sh = IrradianceSH::from_cubemap(&uniform, size);
+        for n in [Vec3::X, Vec3::NEG_Y, Vec3::new(1.0, 1.0, 1.0).normalize()] {
+            let e = sh.irradiance(n);
+            assert!((e - Vec3::new(1.0, 0.5, 0.25) * std::f32::consts::PI).abs().max_element() < 0.02, "{e}");
+        }
+
+        // Upward normals see the blue sky, downward ones the sunlit ground
+        let sky = bake_sky_cubemap(&AtmosphereSettings::default(), Vec3::Y, Vec3::ONE, size);
+        let sh = IrradianceSH::from_cubemap(&sky, size);
+        assert!(blueness(sh.irradiance(Vec3::Y)) > blueness(sh.irradiance(Vec3::NEG_Y)));
+    }
+
+    #[test]
+    fn test_downsample_cubemap() {
+        let size = 4;
+        let texels: Vec<Vec3> = (0..6 * size * size).map(|i| Vec3::splat((i % 2) as f32)).collect();
+        let mip = downsample_cubemap(&texels, size);
+        assert_eq!(mip.len(), (6 * 2 * 2) as usize);
+        assert!(mip.iter().all(|t| (*t - Vec3::splat(0.5)).length() < 1e-6));
+        assert_eq!(downsample_cubemap(&mip[..6], 1).len(), 6);
+    }
+}
@@ -0,0 +1,67 @@
+//! Grouping of draw items into instanced batches.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// A run of instances drawn with one call.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InstanceBatch {
+    /// Position of the batch's first instance in the instance order.
+    pub first_instance: u32,
+    pub instance_count: u32,
+    /// Index of the first item with this key; its mesh and material stand for the batch.
+    pub representative: usize,
+}
+
+/// Group items that share a key (e.g. mesh and material) into batches.
+/// Returns the batches, in order of each key's first appearance, and the item
+/// indices reordered so every batch's instances are contiguous.
+pub fn group_instances<K: Eq + Hash>(keys: impl IntoIterator<Item = K>) -> (Vec<InstanceBatch>, Vec<usize>) {
+    let mut batch_of: HashMap<K, usize> = HashMap::new();
+    let mut members: Vec<Vec<usize>> = Vec::new();
+    for (i, key) in keys.into_iter().enumerate() {
+        let b = *batch_of.entry(key).or_insert_with(|| {
+            members.push(Vec::new());
+            members.len() - 1
+        });
+        members[b].push(i);
+    }
+
+    let mut batches = Vec::with_capacity(members.len());
+    let mut order = Vec::new();
+    for items in members {
+        batches.push(InstanceBatch {
+            first_instance: order.len() as u32,
+            instance_count: items.len() as u32,
+            representative: items[0],
+        });
+        order.extend(items);
+    }
+    (batches, order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_instances() {
+        let (batches, order) = group_instances(["cube", "sphere", "cube", "cube", "plane", "sphere"]);
+        assert_eq!(
+            batches,
+            vec![
+                InstanceBatch { first_instance: 0, instance_count: 3, representative: 0 },
+                InstanceBatch { first_instance: 3, instance_count: 2, representative: 1 },
+                InstanceBatch { first_instance: 5, instance_count: 1, representative: 4 },
+            ]
+        );
+        assert_eq!(order, vec![0, 2, 3, 1, 5, 4]);
+    }
+
+    #[test]
+    fn test_group_instances_empty() {
+        let (batches, order) = group_instances(Vec::<u64>::new());
+        assert!(batches.is_empty());
+        assert!(order.is_empty());
+    }
+}
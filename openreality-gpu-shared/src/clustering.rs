@@ -0,0 +1,79 @@
+//! Clustered light binning. The view frustum is split into a grid of froxels
+//! (screen tiles × exponential depth slices); `light_cluster.wgsl` lists the
+//! point lights touching each froxel, so shading only visits nearby lights.
+//! The slice math here mirrors the shaders.
+
+use glam::Mat4;
+
+use crate::uniforms::ClusterParams;
+
+/// Froxel grid: tiles along x and y, depth slices along z.
+pub const CLUSTER_GRID: [u32; 3] = [16, 9, 24];
+
+/// Lights stored per froxel; further lights touching it are dropped.
+pub const MAX_LIGHTS_PER_CLUSTER: u32 = 128;
+
+/// Total number of froxels.
+pub const fn cluster_count() -> u32 {
+    CLUSTER_GRID[0] * CLUSTER_GRID[1] * CLUSTER_GRID[2]
+}
+
+/// Near and far distances of an OpenGL-style perspective projection.
+pub fn projection_depth_range(projection: &Mat4) -> (f32, f32) {
+    let a = projection.z_axis.z;
+    let b = projection.w_axis.z;
+    (b / (a - 1.0), b / (a + 1.0))
+}
+
+/// Slice holding view-space `depth`; depths outside `near..far` are clamped
+/// to the first or last slice.
+pub fn depth_slice(depth: f32, near: f32, far: f32, slices: u32) -> u32 {
+    let t = (depth.max(near) / near).ln() / (far / near).ln();
+    ((t * slices as f32) as u32).min(slices - 1)
+}
+
+/// View-space depth where slice `slice` begins.
+pub fn slice_depth(slice: u32, near: f32, far: f32, slices: u32) -> f32 {
+    near * (far / near).powf(slice as f32 / slices as f32)
+}
+
+/// Clustering parameters for a camera and `point_light_count` lights.
+pub fn cluster_params(view: &Mat4, projection: &Mat4, point_light_count: u32) -> ClusterParams {
+    let (near, far) = projection_depth_range(projection);
+    ClusterParams {
+        inv_projection: projection.inverse().to_cols_array_2d(),
+        view: view.to_cols_array_2d(),
+        grid: [CLUSTER_GRID[0], CLUSTER_GRID[1], CLUSTER_GRID[2], point_light_count],
+        near,
+        far,
+        _pad1: 0.0,
+        _pad2: 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_projection_depth_range() {
+        let proj = Mat4::perspective_rh_gl(1.0, 16.0 / 9.0, 0.1, 500.0);
+        let (near, far) = projection_depth_range(&proj);
+        assert!((near - 0.1).abs() < 1e-4);
+        assert!((far - 500.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_depth_slices() {
+        let slices = CLUSTER_GRID[2];
+        assert_eq!(depth_slice(0.1, 0.1, 500.0, slices), 0);
+        assert_eq!(depth_slice(0.01, 0.1, 500.0, slices), 0);
+        assert_eq!(depth_slice(500.0, 0.1, 500.0, slices), slices - 1);
+        assert_eq!(depth_slice(9000.0, 0.1, 500.0, slices), slices - 1);
+        // Every slice's start depth maps back into that slice
+        for s in 0..slices {
+            let d = slice_depth(s, 0.1, 500.0, slices) * 1.001;
+            assert_eq!(depth_slice(d, 0.1, 500.0, slices), s);
+        }
+    }
+}
@@ -0,0 +1,279 @@
+//! World-space collider shapes with overlap and ray queries.
+//!
+//! ORSB colliders are stored in entity space; [`WorldShape::from_collider`]
+//! places one with the entity's world matrix. Boxes stay axis-aligned (as in
+//! the Julia `AABBShape`), so a rotated box becomes its world-space bounds.
+
+use glam::{Mat4, Vec3};
+
+use crate::scene_format::ColliderParsed;
+
+/// Collider shape placed in world space.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WorldShape {
+    Box { center: Vec3, half_extents: Vec3 },
+    Sphere { center: Vec3, radius: f32 },
+    /// Segment `a`–`b` swept by `radius`.
+    Capsule { a: Vec3, b: Vec3, radius: f32 },
+}
+
+/// Result of a ray query.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+    pub distance: f32,
+    pub point: Vec3,
+    pub normal: Vec3,
+}
+
+fn closest_on_segment(a: Vec3, b: Vec3, p: Vec3) -> Vec3 {
+    let ab = b - a;
+    let len_sq = ab.length_squared();
+    if len_sq < 1e-12 {
+        return a;
+    }
+    a + ab * ((p - a).dot(ab) / len_sq).clamp(0.0, 1.0)
+}
+
+/// Squared distance between segments `p0`–`p1` and `q0`–`q1`.
+fn segment_distance_sq(p0: Vec3, p1: Vec3, q0: Vec3, q1: Vec3) -> f32 {
+    let d1 = p1 - p0;
+    let d2 = q1 - q0;
+    let r = p0 - q0;
+    let a = d1.length_squared();
+    let e = d2.length_squared();
+    let f = d2.dot(r);
+
+    let (s, t) = if a < 1e-12 && e < 1e-12 {
+        (0.0, 0.0)
+    } else if a < 1e-12 {
+        (0.0, (f / e).clamp(0.0, 1.0))
+    } else {
+        let c = d1.dot(r);
+        if e < 1e-12 {
+            ((-c / a).clamp(0.0, 1.0), 0.0)
+        } else {
+            let b = d1.dot(d2);
+            let denom = a * e - b * b;
+            let mut s = if denom > 1e-12 { ((b * f - c * e) / denom).clamp(0.0, 1.0) } else { 0.0 };
+            let mut t = (b * s + f) / e;
+            if t < 0.0 {
+                t = 0.0;
+                s = (-c / a).clamp(0.0, 1.0);
+            } else if t > 1.0 {
+                t = 1.0;
+                s = ((b - c) / a).clamp(0.0, 1.0);
+            }
+            (s, t)
+        }
+    };
+    ((p0 + d1 * s) - (q0 + d2 * t)).length_squared()
+}
+
+fn box_distance_sq(center: Vec3, half_extents: Vec3, p: Vec3) -> f32 {
+    let closest = p.clamp(center - half_extents, center + half_extents);
+    (p - closest).length_squared()
+}
+
+/// Squared distance from a segment to a box. The distance along the segment
+/// is convex, so a ternary search converges on the minimum.
+fn segment_box_distance_sq(a: Vec3, b: Vec3, center: Vec3, half_extents: Vec3) -> f32 {
+    let (mut lo, mut hi) = (0.0f32, 1.0f32);
+    let at = |t: f32| box_distance_sq(center, half_extents, a.lerp(b, t));
+    for _ in 0..40 {
+        let m1 = lo + (hi - lo) / 3.0;
+        let m2 = hi - (hi - lo) / 3.0;
+        if at(m1) < at(m2) {
+            hi = m2;
+        } else {
+            lo = m1;
+        }
+    }
+    at(0.5 * (lo + hi)).min(at(0.0)).min(at(1.0))
+}
+
+impl WorldShape {
+    /// Place an ORSB collider (0 = box, 1 = sphere, 2 = Y-axis capsule) with
+    /// its entity's world matrix. Radii scale with the largest axis scale.
+    pub fn from_collider(collider: &ColliderParsed, world: &Mat4) -> Self {
+        let [d0, d1, d2] = collider.shape_data;
+        let offset = Vec3::from_array(collider.offset);
+        let center = world.transform_point3(offset);
+        let (x, y, z) = (world.x_axis.truncate(), world.y_axis.truncate(), world.z_axis.truncate());
+        let max_scale = x.length().max(y.length()).max(z.length());
+
+        match collider.shape_type {
+            1 => WorldShape::Sphere { center, radius: d0 * max_scale },
+            2 => WorldShape::Capsule {
+                a: world.transform_point3(offset - Vec3::Y * d1),
+                b: world.transform_point3(offset + Vec3::Y * d1),
+                radius: d0 * max_scale,
+            },
+            _ => WorldShape::Box {
+                center,
+                half_extents: x.abs() * d0 + y.abs() * d1 + z.abs() * d2,
+            },
+        }
+    }
+
+    /// World-space bounds as (min, max).
+    pub fn aabb(&self) -> (Vec3, Vec3) {
+        match *self {
+            WorldShape::Box { center, half_extents } => (center - half_extents, center + half_extents),
+            WorldShape::Sphere { center, radius } => (center - Vec3::splat(radius), center + Vec3::splat(radius)),
+            WorldShape::Capsule { a, b, radius } => (a.min(b) - Vec3::splat(radius), a.max(b) + Vec3::splat(radius)),
+        }
+    }
+
+    /// True if the two shapes touch or intersect.
+    pub fn overlaps(&self, other: &WorldShape) -> bool {
+        use WorldShape::*;
+        match (*self, *other) {
+            (Box { center: c0, half_extents: h0 }, Box { center: c1, half_extents: h1 }) => {
+                let d = (c0 - c1).abs();
+                d.x <= h0.x + h1.x && d.y <= h0.y + h1.y && d.z <= h0.z + h1.z
+            }
+            (Sphere { center: c0, radius: r0 }, Sphere { center: c1, radius: r1 }) => {
+                (c0 - c1).length_squared() <= (r0 + r1) * (r0 + r1)
+            }
+            (Box { center, half_extents }, Sphere { center: p, radius })
+            | (Sphere { center: p, radius }, Box { center, half_extents }) => {
+                box_distance_sq(center, half_extents, p) <= radius * radius
+            }
+            (Capsule { a, b, radius }, Sphere { center, radius: r })
+            | (Sphere { center, radius: r }, Capsule { a, b, radius }) => {
+                (center - closest_on_segment(a, b, center)).length_squared() <= (radius + r) * (radius + r)
+            }
+            (Capsule { a: a0, b: b0, radius: r0 }, Capsule { a: a1, b: b1, radius: r1 }) => {
+                segment_distance_sq(a0, b0, a1, b1) <= (r0 + r1) * (r0 + r1)
+            }
+            (Capsule { a, b, radius }, Box { center, half_extents })
+            | (Box { center, half_extents }, Capsule { a, b, radius }) => {
+                segment_box_distance_sq(a, b, center, half_extents) <= radius * radius
+            }
+        }
+    }
+
+    /// First hit along the ray `origin + t * dir` (`dir` normalized) with
+    /// `t` in [0, `max_distance`]. A ray starting inside the shape hits at 0.
+    pub fn raycast(&self, origin: Vec3, dir: Vec3, max_distance: f32) -> Option<RayHit> {
+        let hit = |distance: f32, normal: Vec3| RayHit { distance, point: origin + dir * distance, normal };
+        match *self {
+            WorldShape::Sphere { center, radius } => {
+                let m = origin - center;
+                let c = m.length_squared() - radius * radius;
+                if c <= 0.0 {
+                    return Some(hit(0.0, -dir));
+                }
+                let b = m.dot(dir);
+                let disc = b * b - c;
+                if b > 0.0 || disc < 0.0 {
+                    return None;
+                }
+                let t = -b - disc.sqrt();
+                (t <= max_distance).then(|| hit(t, (origin + dir * t - center) / radius))
+            }
+            WorldShape::Box { center, half_extents } => {
+                let (min, max) = (center - half_extents, center + half_extents);
+                let (mut t_near, mut t_far) = (0.0f32, max_distance);
+                let mut normal = -dir;
+                for axis in 0..3 {
+                    let (o, d) = (origin[axis], dir[axis]);
+                    if d.abs() < 1e-12 {
+                        if o < min[axis] || o > max[axis] {
+                            return None;
+                        }
+                        continue;
+                    }
+                    let (mut t0, mut t1) = ((min[axis] - o) / d, (max[axis] - o) / d);
+                    let mut sign = -1.0;
+                    if t0 > t1 {
+                        std::mem::swap(&mut t0, &mut t1);
+                        sign = 1.0;
+                    }
+                    if t0 > t_near {
+                        t_near = t0;
+                        normal = Vec3::ZERO;
+                        normal[axis] = sign;
+                    }
+                    t_far = t_far.min(t1);
+                    if t_near > t_far {
+                        return None;
+                    }
+                }
+                Some(hit(t_near, normal))
+            }
+            WorldShape::Capsule { a, b, radius } => {
+                // Sphere-trace the capsule's exact distance field
+                let mut t = 0.0;
+                for _ in 0..64 {
+                    let p = origin + dir * t;
+                    let axis_point = closest_on_segment(a, b, p);
+                    let d = (p - axis_point).length() - radius;
+                    if d < 1e-4 {
+                        let normal = (p - axis_point).try_normalize().unwrap_or(-dir);
+                        return Some(hit(t, if t == 0.0 { -dir } else { normal }));
+                    }
+                    t += d;
+                    if t > max_distance {
+                        return None;
+                    }
+                }
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collider(shape_type: u8, shape_data: [f32; 3]) -> ColliderParsed {
+        ColliderParsed { shape_type, shape_data, offset: [0.0; 3], is_trigger: false }
+    }
+
+    #[test]
+    fn test_from_collider_applies_world_transform() {
+        let world = Mat4::from_scale_rotation_translation(Vec3::splat(2.0), glam::Quat::IDENTITY, Vec3::X * 5.0);
+        let sphere = WorldShape::from_collider(&collider(1, [0.5, 0.0, 0.0]), &world);
+        assert_eq!(sphere, WorldShape::Sphere { center: Vec3::X * 5.0, radius: 1.0 });
+
+        let rotated = Mat4::from_rotation_z(std::f32::consts::FRAC_PI_2);
+        let WorldShape::Box { half_extents, .. } = WorldShape::from_collider(&collider(0, [2.0, 1.0, 1.0]), &rotated) else {
+            panic!("expected a box");
+        };
+        assert!((half_extents - Vec3::new(1.0, 2.0, 1.0)).length() < 1e-5);
+    }
+
+    #[test]
+    fn test_overlaps() {
+        let unit_box = WorldShape::Box { center: Vec3::ZERO, half_extents: Vec3::ONE };
+        let near = WorldShape::Sphere { center: Vec3::new(1.5, 0.0, 0.0), radius: 0.6 };
+        let far = WorldShape::Sphere { center: Vec3::new(3.0, 0.0, 0.0), radius: 0.6 };
+        assert!(unit_box.overlaps(&near) && near.overlaps(&unit_box));
+        assert!(!unit_box.overlaps(&far));
+
+        let capsule = WorldShape::Capsule { a: Vec3::new(2.0, -5.0, 0.0), b: Vec3::new(2.0, 5.0, 0.0), radius: 1.1 };
+        assert!(capsule.overlaps(&unit_box));
+        assert!(capsule.overlaps(&far));
+        let crossing = WorldShape::Capsule { a: Vec3::new(-5.0, 0.0, 0.5), b: Vec3::new(5.0, 0.0, 0.5), radius: 0.1 };
+        assert!(capsule.overlaps(&crossing));
+    }
+
+    #[test]
+    fn test_raycasts() {
+        let origin = Vec3::new(-10.0, 0.0, 0.0);
+        let shapes = [
+            WorldShape::Box { center: Vec3::ZERO, half_extents: Vec3::ONE },
+            WorldShape::Sphere { center: Vec3::ZERO, radius: 1.0 },
+            WorldShape::Capsule { a: -Vec3::Y, b: Vec3::Y, radius: 1.0 },
+        ];
+        for shape in shapes {
+            let hit = shape.raycast(origin, Vec3::X, 100.0).unwrap();
+            assert!((hit.distance - 9.0).abs() < 1e-3, "{shape:?}: {hit:?}");
+            assert!((hit.normal - -Vec3::X).length() < 1e-3, "{shape:?}: {hit:?}");
+            assert!(shape.raycast(origin, Vec3::X, 5.0).is_none());
+            assert!(shape.raycast(origin, Vec3::Y, 100.0).is_none());
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! 3D color lookup tables for the color grading pass, parsed from the
+//! Adobe/Resolve `.cube` format that grading tools export. Entries are
+//! stored red-fastest, which is also the texel order of a 3D texture indexed
+//! by (r, g, b), so the data uploads as is.
+
+/// Smallest and largest edge length a `.cube` 3D LUT may declare.
+pub const MIN_LUT_SIZE: u32 = 2;
+pub const MAX_LUT_SIZE: u32 = 256;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CubeLut {
+    pub title: Option<String>,
+    /// Entries per axis.
+    pub size: u32,
+    /// Input colors mapped to the first and last entry of each axis.
+    pub domain_min: [f32; 3],
+    pub domain_max: [f32; 3],
+    /// `size³` output colors, red fastest, then green, then blue.
+    pub data: Vec<[f32; 3]>,
+}
+
+impl CubeLut {
+    /// LUT that maps every color to itself. Two entries per axis are enough,
+    /// since trilinear filtering reproduces its input exactly.
+    pub fn identity(size: u32) -> Self {
+        let size = size.clamp(MIN_LUT_SIZE, MAX_LUT_SIZE);
+        let step = 1.0 / (size - 1) as f32;
+        let mut data = Vec::with_capacity((size * size * size) as usize);
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    data.push([r as f32 * step, g as f32 * step, b as f32 * step]);
+                }
+            }
+        }
+        Self { title: None, size, domain_min: [0.0; 3], domain_max: [1.0; 3], data }
+    }
+
+    /// Parse the text of a `.cube` file. Only 3D LUTs are supported.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut title = None;
+        let mut size = None;
+        let mut domain_min = [0.0; 3];
+        let mut domain_max = [1.0; 3];
+        let mut data = Vec::new();
+
+        for (index, raw) in text.lines().enumerate() {
+            let line = raw.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line_no = index + 1;
+            let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let rest = rest.trim();
+            match keyword {
+                "TITLE" => title = Some(rest.trim_matches('"').to_string()),
+                "LUT_3D_SIZE" => {
+                    let n: u32 = rest.parse().map_err(|_| format!("line {line_no}: bad LUT_3D_SIZE '{rest}'"))?;
+                    if !(MIN_LUT_SIZE..=MAX_LUT_SIZE).contains(&n) {
+                        return Err(format!("line {line_no}: LUT_3D_SIZE must be {MIN_LUT_SIZE}..={MAX_LUT_SIZE}, got {n}"));
+                    }
+                    size = Some(n);
+                }
+                "LUT_1D_SIZE" => return Err(format!("line {line_no}: 1D LUTs are not supported")),
+                "DOMAIN_MIN" => domain_min = parse_triple(rest, line_no)?,
+                "DOMAIN_MAX" => domain_max = parse_triple(rest, line_no)?,
+                // Resolve's single-range form of DOMAIN_MIN/MAX
+                "LUT_3D_INPUT_RANGE" => {
+                    let range = parse_floats(rest, line_no)?;
+                    let [lo, hi] = range[..] else {
+                        return Err(format!("line {line_no}: LUT_3D_INPUT_RANGE needs 2 values"));
+                    };
+                    domain_min = [lo; 3];
+                    domain_max = [hi; 3];
+                }
+                _ if keyword.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '+' || c == '.') => {
+                    data.push(parse_triple(line, line_no)?);
+                }
+                // Unknown keywords are tool-specific metadata
+                _ => {}
+            }
+        }
+
+        let size = size.ok_or("missing LUT_3D_SIZE")?;
+        let expected = (size * size * size) as usize;
+        if data.len() != expected {
+            return Err(format!("LUT_3D_SIZE {size} needs {expected} entries, found {}", data.len()));
+        }
+        if (0..3).any(|i| domain_min[i] >= domain_max[i]) {
+            return Err(format!("empty domain {domain_min:?}..{domain_max:?}"));
+        }
+        Ok(Self { title, size, domain_min, domain_max, data })
+    }
+
+    /// Entries as RGBA texels (alpha 1) for an `Rgba32Float` 3D texture.
+    pub fn to_rgba32f(&self) -> Vec<f32> {
+        self.data.iter().flat_map(|&[r, g, b]| [r, g, b, 1.0]).collect()
+    }
+}
+
+fn parse_floats(text: &str, line_no: usize) -> Result<Vec<f32>, String> {
+    text.split_whitespace()
+        .map(|v| v.parse::<f32>().map_err(|_| format!("line {line_no}: bad number '{v}'")))
+        .collect()
+}
+
+fn parse_triple(text: &str, line_no: usize) -> Result<[f32; 3], String> {
+    let values = parse_floats(text, line_no)?;
+    <[f32; 3]>::try_from(values).map_err(|_| format!("line {line_no}: expected 3 values"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cube() {
+        let text = "# Exported by a grading tool\n\
+                    TITLE \"Warm\"\n\
+                    LUT_3D_SIZE 2\n\
+                    DOMAIN_MIN 0 0 0\n\
+                    DOMAIN_MAX 1 1 1\n\
+                    \n\
+                    0 0 0\n1 0 0\n0 1 0\n1 1 0\n\
+                    0 0 1\n1 0 1\n0 1 1\n1.0 0.9 0.8\n";
+        let lut = CubeLut::parse(text).unwrap();
+        assert_eq!(lut.title.as_deref(), Some("Warm"));
+        assert_eq!(lut.size, 2);
+        assert_eq!(lut.data.len(), 8);
+        // Red varies fastest
+        assert_eq!(lut.data[1], [1.0, 0.0, 0.0]);
+        assert_eq!(lut.data[7], [1.0, 0.9, 0.8]);
+        assert_eq!(lut.to_rgba32f()[28..], [1.0, 0.9, 0.8, 1.0]);
+
+        let identity = CubeLut::identity(2);
+        assert_eq!(identity.data[..7], lut.data[..7]);
+    }
+
+    #[test]
+    fn test_parse_input_range() {
+        let mut text = String::from("LUT_3D_SIZE 2\nLUT_3D_INPUT_RANGE -0.5 1.5\n");
+        for _ in 0..8 {
+            text.push_str("0.5 0.5 0.5\n");
+        }
+        let lut = CubeLut::parse(&text).unwrap();
+        assert_eq!(lut.domain_min, [-0.5; 3]);
+        assert_eq!(lut.domain_max, [1.5; 3]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(CubeLut::parse("0 0 0\n").is_err());
+        assert!(CubeLut::parse("LUT_1D_SIZE 1024\n").is_err());
+        assert!(CubeLut::parse("LUT_3D_SIZE 1\n0 0 0\n").is_err());
+        assert!(CubeLut::parse("LUT_3D_SIZE 2\n0 0 0\n").is_err());
+        assert!(CubeLut::parse("LUT_3D_SIZE 2\n0 0 x\n").is_err());
+    }
+
+    #[test]
+    fn test_identity() {
+        let lut = CubeLut::identity(17);
+        assert_eq!(lut.data.len(), 17 * 17 * 17);
+        assert_eq!(lut.data[0], [0.0; 3]);
+        assert_eq!(lut.data[16], [1.0, 0.0, 0.0]);
+        assert_eq!(*lut.data.last().unwrap(), [1.0; 3]);
+    }
+}
@@ -0,0 +1,148 @@
+//! Cascaded shadow map fitting for directional lights. Each cascade covers a
+//! depth slice of the camera frustum with an orthographic light projection
+//! (0..1 depth) sized to the slice's bounding sphere, so its scale does not
+//! change as the camera turns. Stabilized cascades are also snapped to whole
+//! texels, so static shadows do not shimmer as the camera moves.
+
+use glam::{Mat4, Vec3, Vec4};
+
+/// Distance toward the light covered beyond each cascade's bounding sphere,
+/// in radii, so casters outside the view still throw shadows into it.
+pub const CASTER_MARGIN: f32 = 2.0;
+
+/// Light projection of one cascade.
+#[derive(Clone, Copy, Debug)]
+pub struct CascadeFit {
+    pub view_proj: Mat4,
+    /// World-space size of one shadow map texel.
+    pub texel_size: f32,
+    /// World-space distance covered by the 0..1 depth range.
+    pub depth_range: f32,
+}
+
+/// Split distances of the Practical Split Scheme, blending uniform
+/// (`lambda` = 0) and logarithmic (`lambda` = 1) splits. Returns
+/// `num_cascades + 1` distances from `near` to `far`, like Julia's
+/// `compute_cascade_splits`.
+pub fn cascade_splits(near: f32, far: f32, num_cascades: usize, lambda: f32) -> Vec<f32> {
+    let n = num_cascades.max(1);
+    (0..=n)
+        .map(|i| {
+            let t = i as f32 / n as f32;
+            let linear = near + (far - near) * t;
+            let log = near * (far / near).powf(t);
+            lambda * log + (1.0 - lambda) * linear
+        })
+        .collect()
+}
+
+/// World-space corners of the camera frustum between view distances `near`
+/// and `far`: the four near corners, then the four far corners.
+pub fn frustum_slice_corners(view: &Mat4, projection: &Mat4, near: f32, far: f32) -> [Vec3; 8] {
+    let inv_projection = projection.inverse();
+    let inv_view = view.inverse();
+    let mut corners = [Vec3::ZERO; 8];
+    for (i, (x, y)) in [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)].into_iter().enumerate() {
+        // Far plane is z = 1 for both OpenGL and 0..1 depth
+        let p = inv_projection * Vec4::new(x, y, 1.0, 1.0);
+        let ray = p.truncate() / p.w;
+        let ray = ray / -ray.z;
+        corners[i] = inv_view.transform_point3(ray * near);
+        corners[i + 4] = inv_view.transform_point3(ray * far);
+    }
+    corners
+}
+
+/// Orthographic light projection covering `corners`, seen along `light_dir`
+/// by a `resolution`² shadow map.
+pub fn fit_cascade(corners: &[Vec3; 8], light_dir: Vec3, resolution: u32, stabilize: bool) -> CascadeFit {
+    let center = corners.iter().copied().sum::<Vec3>() / 8.0;
+    let mut radius = corners.iter().map(|c| c.distance(center)).fold(0.0, f32::max).max(1e-3);
+    if stabilize {
+        // Quantize so float noise in the corners cannot change the scale
+        radius = (radius * 16.0).ceil() / 16.0;
+    }
+
+    let dir = light_dir.normalize_or(Vec3::NEG_Y);
+    let up = if dir.y.abs() > 0.99 { Vec3::Z } else { Vec3::Y };
+    let back = radius * (1.0 + CASTER_MARGIN);
+    let view = Mat4::look_to_rh(center - dir * back, dir, up);
+    let depth_range = back + radius;
+    let mut projection = Mat4::orthographic_rh(-radius, radius, -radius, radius, 0.0, depth_range);
+
+    if stabilize {
+        // Move the projection so the world origin lands on a texel corner
+        let half_res = resolution as f32 * 0.5;
+        let origin = (projection * view).w_axis.truncate().truncate() * half_res;
+        let offset = (origin.round() - origin) / half_res;
+        projection.w_axis.x += offset.x;
+        projection.w_axis.y += offset.y;
+    }
+
+    CascadeFit {
+        view_proj: projection * view,
+        texel_size: 2.0 * radius / resolution as f32,
+        depth_range,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn camera(eye: Vec3, yaw: f32) -> (Mat4, Mat4) {
+        let forward = Vec3::new(yaw.sin(), -0.2, -yaw.cos());
+        let view = Mat4::look_to_rh(eye, forward, Vec3::Y);
+        let projection = Mat4::perspective_rh_gl(1.0, 16.0 / 9.0, 0.1, 500.0);
+        (view, projection)
+    }
+
+    #[test]
+    fn test_cascade_splits() {
+        let splits = cascade_splits(0.1, 500.0, 4, 0.5);
+        assert_eq!(splits.len(), 5);
+        assert!((splits[0] - 0.1).abs() < 1e-5);
+        assert!((splits[4] - 500.0).abs() < 1e-2);
+        assert!(splits.windows(2).all(|w| w[0] < w[1]));
+
+        let uniform = cascade_splits(1.0, 101.0, 4, 0.0);
+        assert!((uniform[2] - 51.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_cascade_contains_slice() {
+        let (view, projection) = camera(Vec3::new(3.0, 2.0, 5.0), 0.7);
+        let corners = frustum_slice_corners(&view, &projection, 2.0, 20.0);
+        let depth = |c: Vec3| (view * c.extend(1.0)).z;
+        assert!((depth(corners[0]) + 2.0).abs() < 1e-3);
+        assert!((depth(corners[7]) + 20.0).abs() < 1e-2);
+
+        let fit = fit_cascade(&corners, Vec3::new(0.3, -1.0, 0.2), 2048, true);
+        for c in corners {
+            let p = fit.view_proj.project_point3(c);
+            assert!(p.x.abs() <= 1.0 && p.y.abs() <= 1.0, "{p:?}");
+            assert!((0.0..=1.0).contains(&p.z), "{p:?}");
+        }
+    }
+
+    #[test]
+    fn test_stabilized_cascade_is_texel_snapped() {
+        let light = Vec3::new(-0.4, -1.0, 0.1);
+        let res = 1024;
+        let fits: Vec<CascadeFit> = [(0.0, 0.0), (0.013, 0.0), (0.5, 0.9)]
+            .iter()
+            .map(|&(dx, yaw)| {
+                let (view, projection) = camera(Vec3::new(dx, 1.0, 0.0), yaw);
+                fit_cascade(&frustum_slice_corners(&view, &projection, 0.1, 15.0), light, res, true)
+            })
+            .collect();
+
+        // Same texel size under camera rotation and translation
+        assert!(fits.iter().all(|f| (f.texel_size - fits[0].texel_size).abs() < 1e-6));
+        // World origin on a texel corner
+        for fit in &fits {
+            let origin = fit.view_proj.w_axis.truncate().truncate() * (res as f32 * 0.5);
+            assert!((origin - origin.round()).abs().max_element() < 1e-2, "{origin:?}");
+        }
+    }
+}
@@ -0,0 +1,212 @@
+//! Deferred decals: oriented boxes that project a texture onto the G-buffer
+//! before lighting (`decal.wgsl`). A decal is the unit cube [-0.5, 0.5]³
+//! under its transform, projected along its local -z axis; the image's u
+//! runs along +x and v along -y. The projection here mirrors the shader.
+
+use bytemuck::{Pod, Zeroable};
+use glam::{Mat4, Vec2, Vec3, Vec4Swizzles};
+
+use crate::math::BoundingSphere;
+use crate::uniforms::DecalData;
+
+/// Depth (in box units) over which a decal fades out towards its front and
+/// back faces.
+pub const DEPTH_FADE: f32 = 0.05;
+
+/// Fraction of `DecalParams::angle_fade` at which the angle fade starts.
+const ANGLE_FADE_START: f32 = 0.75;
+
+/// Surface settings of a decal, set through `or_wgpu_add_decal` and
+/// `or_wgpu_update_decal`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct DecalParams {
+    /// rgb tints the albedo map; a is the decal's coverage of the surface albedo.
+    pub color: [f32; 4],
+    /// Coverage of the surface normal by the decal's normal map.
+    pub normal_strength: f32,
+    pub roughness: f32,
+    /// Coverage of the surface roughness by `roughness`.
+    pub roughness_opacity: f32,
+    /// Largest angle (radians) between the surface normal and the decal's
+    /// +z axis at which the decal still shows; it fades out towards it.
+    pub angle_fade: f32,
+}
+
+impl Default for DecalParams {
+    fn default() -> Self {
+        Self {
+            color: [1.0; 4],
+            normal_strength: 1.0,
+            roughness: 0.5,
+            roughness_opacity: 0.0,
+            angle_fade: std::f32::consts::FRAC_PI_3,
+        }
+    }
+}
+
+impl DecalParams {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.color[..3].iter().any(|c| *c < 0.0) || !(0.0..=1.0).contains(&self.color[3]) {
+            return Err("Decal color must be >= 0 with opacity in [0, 1]".into());
+        }
+        let unit = [self.normal_strength, self.roughness, self.roughness_opacity];
+        if unit.iter().any(|v| !(0.0..=1.0).contains(v)) {
+            return Err("Decal normal strength, roughness and roughness opacity must be in [0, 1]".into());
+        }
+        if !(self.angle_fade > 0.0 && self.angle_fade <= std::f32::consts::FRAC_PI_2) {
+            return Err(format!("Decal angle fade must be in (0, pi/2], got {}", self.angle_fade));
+        }
+        Ok(())
+    }
+}
+
+/// Reject transforms that flatten the box, which could not be inverted.
+pub fn validate_decal_transform(model: &Mat4) -> Result<(), String> {
+    let det = model.determinant();
+    if !det.is_finite() || det.abs() < 1e-12 {
+        return Err("Decal transform must be invertible".into());
+    }
+    Ok(())
+}
+
+/// World-space sphere around the decal box, for frustum culling.
+pub fn decal_bounds(model: &Mat4) -> BoundingSphere {
+    let axes = [model.x_axis, model.y_axis, model.z_axis].map(|a| a.xyz().length_squared());
+    BoundingSphere {
+        center: model.w_axis.xyz(),
+        radius: 0.5 * axes.iter().sum::<f32>().sqrt(),
+    }
+}
+
+impl DecalData {
+    /// GPU data of the decal `model` (a valid transform, see
+    /// `validate_decal_transform`) with `params`.
+    pub fn new(model: Mat4, params: &DecalParams, has_albedo_map: bool, has_normal_map: bool) -> Self {
+        let axis = |a: glam::Vec4| a.xyz().normalize_or_zero().extend(0.0).to_array();
+        Self {
+            model: model.to_cols_array_2d(),
+            world_to_decal: model.inverse().to_cols_array_2d(),
+            tangent: axis(model.x_axis),
+            bitangent: axis(model.y_axis),
+            normal: axis(model.z_axis),
+            color: params.color,
+            normal_strength: params.normal_strength,
+            roughness: params.roughness,
+            roughness_opacity: params.roughness_opacity,
+            fade_start: (params.angle_fade * ANGLE_FADE_START).cos(),
+            fade_end: params.angle_fade.cos(),
+            has_albedo_map: has_albedo_map as u32,
+            has_normal_map: has_normal_map as u32,
+            _pad1: 0.0,
+        }
+    }
+
+    /// Image coordinates and fade of the decal at world point `p` on a
+    /// surface with unit normal `n`; `None` where the decal does not reach.
+    pub fn project(&self, p: Vec3, n: Vec3) -> Option<(Vec2, f32)> {
+        let local = Mat4::from_cols_array_2d(&self.world_to_decal).transform_point3(p);
+        if local.abs().max_element() > 0.5 {
+            return None;
+        }
+        let facing = n.dot(Vec3::from_slice(&self.normal[..3]));
+        let angle = ((facing - self.fade_end) / (self.fade_start - self.fade_end)).clamp(0.0, 1.0);
+        let depth = ((0.5 - local.z.abs()) / DEPTH_FADE).clamp(0.0, 1.0);
+        let fade = angle * depth;
+        (fade > 0.0).then(|| (Vec2::new(local.x + 0.5, 0.5 - local.y), fade))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Quat;
+
+    #[test]
+    fn test_params_validation() {
+        assert!(DecalParams::default().validate().is_ok());
+        let invalid = [
+            DecalParams { color: [1.0, 1.0, 1.0, 1.5], ..Default::default() },
+            DecalParams { roughness: -0.1, ..Default::default() },
+            DecalParams { roughness_opacity: 2.0, ..Default::default() },
+            DecalParams { angle_fade: 0.0, ..Default::default() },
+            DecalParams { angle_fade: 2.0, ..Default::default() },
+        ];
+        for params in invalid {
+            assert!(params.validate().is_err(), "{params:?}");
+        }
+        assert!(validate_decal_transform(&Mat4::IDENTITY).is_ok());
+        assert!(validate_decal_transform(&Mat4::from_scale(Vec3::new(1.0, 1.0, 0.0))).is_err());
+    }
+
+    #[test]
+    fn test_project_uv() {
+        let decal = DecalData::new(Mat4::IDENTITY, &DecalParams::default(), false, false);
+        let (uv, fade) = decal.project(Vec3::ZERO, Vec3::Z).unwrap();
+        assert!((uv - Vec2::splat(0.5)).length() < 1e-6);
+        assert_eq!(fade, 1.0);
+
+        // u along +x, v down from +y
+        let (uv, _) = decal.project(Vec3::new(-0.45, 0.45, 0.0), Vec3::Z).unwrap();
+        assert!((uv - Vec2::new(0.05, 0.05)).length() < 1e-5);
+
+        assert!(decal.project(Vec3::new(0.6, 0.0, 0.0), Vec3::Z).is_none());
+        assert!(decal.project(Vec3::new(0.0, 0.0, -0.6), Vec3::Z).is_none());
+    }
+
+    #[test]
+    fn test_project_transformed() {
+        // A 2x1 decal on a wall facing +x, 0.2 deep
+        let model = Mat4::from_scale_rotation_translation(
+            Vec3::new(2.0, 1.0, 0.2),
+            Quat::from_rotation_y(std::f32::consts::FRAC_PI_2),
+            Vec3::new(5.0, 1.0, 0.0),
+        );
+        let decal = DecalData::new(model, &DecalParams::default(), true, false);
+        assert!((Vec3::from_slice(&decal.normal[..3]) - Vec3::X).length() < 1e-5);
+
+        // Local +x maps to world -z
+        let (uv, fade) = decal.project(Vec3::new(5.0, 1.0, -0.5), Vec3::X).unwrap();
+        assert!((uv - Vec2::new(0.75, 0.5)).length() < 1e-5);
+        assert_eq!(fade, 1.0);
+        assert!(decal.project(Vec3::new(5.0, 1.0, -1.1), Vec3::X).is_none());
+        assert!(decal.project(Vec3::new(5.2, 1.0, 0.0), Vec3::X).is_none());
+    }
+
+    #[test]
+    fn test_fades() {
+        let params = DecalParams { angle_fade: 1.0, ..Default::default() };
+        let decal = DecalData::new(Mat4::IDENTITY, &params, false, false);
+        let tilted = |angle: f32| Vec3::new(angle.sin(), 0.0, angle.cos());
+
+        // Full up to 3/4 of the angle, gone past it; back-facing surfaces get nothing
+        assert_eq!(decal.project(Vec3::ZERO, tilted(0.7)).unwrap().1, 1.0);
+        let partial = decal.project(Vec3::ZERO, tilted(0.9)).unwrap().1;
+        assert!(partial > 0.0 && partial < 1.0);
+        assert!(decal.project(Vec3::ZERO, tilted(1.1)).is_none());
+        assert!(decal.project(Vec3::ZERO, -Vec3::Z).is_none());
+
+        // Fades towards the front and back faces
+        let near_face = decal.project(Vec3::new(0.0, 0.0, 0.49), Vec3::Z).unwrap().1;
+        assert!((near_face - 0.2).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_bounds_contain_box() {
+        let model = Mat4::from_scale_rotation_translation(
+            Vec3::new(3.0, 0.5, 1.0),
+            Quat::from_rotation_z(0.4),
+            Vec3::new(1.0, 2.0, 3.0),
+        );
+        let bounds = decal_bounds(&model);
+        for i in 0..8 {
+            let corner = Vec3::new(
+                if i & 1 == 0 { -0.5 } else { 0.5 },
+                if i & 2 == 0 { -0.5 } else { 0.5 },
+                if i & 4 == 0 { -0.5 } else { 0.5 },
+            );
+            let p = model.transform_point3(corner);
+            assert!(p.distance(bounds.center) <= bounds.radius + 1e-4);
+        }
+    }
+}
@@ -0,0 +1,228 @@
+//! Display output: which swapchain format to present to, and how the present
+//! pass encodes the image for it. The post-process chain ends in colors
+//! relative to paper white (gamma encoded with the tone mapping gamma); SDR
+//! outputs clip them at 1, HDR outputs show them up to the display's peak.
+
+/// Requested display modes, matching Julia's `DisplayMode`.
+pub const DISPLAY_SDR: u32 = 0;
+/// Extended linear sRGB in a half float surface (1.0 = 80 nits).
+pub const DISPLAY_HDR_SCRGB: u32 = 1;
+/// Rec.2020 primaries with the PQ curve in a 10-bit surface.
+pub const DISPLAY_HDR10: u32 = 2;
+
+/// Encodings of the present pass (`OutputParams::encoding`).
+/// Linear 0..1, for sRGB surfaces (which encode on store) and float targets.
+pub const OUTPUT_SDR_LINEAR: u32 = 0;
+/// Gamma encoded 0..1, for UNORM surfaces.
+pub const OUTPUT_SDR_GAMMA: u32 = 1;
+pub const OUTPUT_SCRGB: u32 = 2;
+pub const OUTPUT_HDR10: u32 = 3;
+
+/// Luminance of scRGB 1.0.
+pub const SCRGB_WHITE_NITS: f32 = 80.0;
+/// Luminance of PQ 1.0.
+pub const PQ_MAX_NITS: f32 = 10000.0;
+
+/// What the present pass can do with a surface format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SurfaceFormatKind {
+    /// 8-bit sRGB (`Bgra8UnormSrgb`, `Rgba8UnormSrgb`).
+    Srgb8,
+    /// 8-bit UNORM.
+    Unorm8,
+    /// `Rgba16Float`.
+    Float16,
+    /// `Rgb10a2Unorm`.
+    Rgb10a2,
+    Other,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DisplaySettings {
+    /// Requested `DISPLAY_*` mode; the surface may fall back to SDR.
+    pub mode: u32,
+    /// Luminance of a tone mapped 1.0 (diffuse white) on HDR outputs.
+    pub paper_white_nits: f32,
+    /// Peak luminance of the display.
+    pub max_nits: f32,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        Self { mode: DISPLAY_SDR, paper_white_nits: 200.0, max_nits: 1000.0 }
+    }
+}
+
+impl DisplaySettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.mode > DISPLAY_HDR10 {
+            return Err(format!("Unknown display mode {}", self.mode));
+        }
+        if self.paper_white_nits <= 0.0 {
+            return Err(format!("Paper white must be > 0 nits, got {}", self.paper_white_nits));
+        }
+        if self.max_nits < self.paper_white_nits || self.max_nits > PQ_MAX_NITS {
+            return Err(format!(
+                "Max luminance must be between paper white and {PQ_MAX_NITS} nits, got {}",
+                self.max_nits
+            ));
+        }
+        Ok(())
+    }
+
+    /// Brightest tone mapped value relative to paper white: 1 for SDR
+    /// encodings, `max_nits / paper_white_nits` for HDR ones.
+    pub fn output_peak(&self, encoding: u32) -> f32 {
+        if is_hdr_output(encoding) {
+            self.max_nits / self.paper_white_nits
+        } else {
+            1.0
+        }
+    }
+}
+
+pub fn is_hdr_output(encoding: u32) -> bool {
+    matches!(encoding, OUTPUT_SCRGB | OUTPUT_HDR10)
+}
+
+/// Pick a surface format for `mode` from the surface's `formats`: the
+/// index and the present encoding for it. HDR modes fall back to the other
+/// HDR format, then to SDR, when the surface does not offer theirs.
+pub fn choose_surface_format(formats: &[SurfaceFormatKind], mode: u32) -> (usize, u32) {
+    use SurfaceFormatKind::*;
+
+    let find = |kind: SurfaceFormatKind| formats.iter().position(|&f| f == kind);
+    let hdr_preference: &[(SurfaceFormatKind, u32)] = match mode {
+        DISPLAY_HDR_SCRGB => &[(Float16, OUTPUT_SCRGB), (Rgb10a2, OUTPUT_HDR10)],
+        DISPLAY_HDR10 => &[(Rgb10a2, OUTPUT_HDR10), (Float16, OUTPUT_SCRGB)],
+        _ => &[],
+    };
+    let sdr_preference = [(Srgb8, OUTPUT_SDR_LINEAR), (Unorm8, OUTPUT_SDR_GAMMA), (Float16, OUTPUT_SDR_LINEAR)];
+    hdr_preference
+        .iter()
+        .chain(&sdr_preference)
+        .find_map(|&(kind, encoding)| find(kind).map(|i| (i, encoding)))
+        .unwrap_or((0, OUTPUT_SDR_GAMMA))
+}
+
+/// SMPTE ST 2084 (PQ) encoding of an absolute luminance. Mirrors `pq_encode`
+/// in `present.wgsl`.
+pub fn pq_encode(nits: f32) -> f32 {
+    const M1: f32 = 0.159_301_76;
+    const M2: f32 = 78.84375;
+    const C1: f32 = 0.835_937_5;
+    const C2: f32 = 18.851_563;
+    const C3: f32 = 18.6875;
+
+    let y = (nits / PQ_MAX_NITS).clamp(0.0, 1.0).powf(M1);
+    ((C1 + C2 * y) / (1.0 + C3 * y)).powf(M2)
+}
+
+/// Decode an IEEE 754 half float, e.g. a texel read back from an
+/// `Rgba16Float` target.
+pub fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1F) as i32;
+    let mantissa = (bits & 0x3FF) as f32;
+    match exponent {
+        0 => sign * mantissa * 2f32.powi(-24),
+        0x1F if mantissa == 0.0 => sign * f32::INFINITY,
+        0x1F => f32::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
+
+/// Encode `value` as an IEEE 754 half float, rounding to nearest; values
+/// past the half range become infinity. Used to upload `Rgba16Float` texels.
+pub fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xFF) as i32;
+    let mantissa = bits & 0x7F_FFFF;
+    if exponent == 0xFF {
+        return sign | 0x7C00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+    let half_exponent = exponent - 127 + 15;
+    if half_exponent >= 0x1F {
+        return sign | 0x7C00;
+    }
+    if half_exponent <= 0 {
+        if half_exponent < -10 {
+            return sign;
+        }
+        // Subnormal: shift the implicit leading bit into the mantissa
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - half_exponent) as u32;
+        let rounded = (mantissa + (1 << (shift - 1))) >> shift;
+        return sign | rounded as u16;
+    }
+    let rounded = ((half_exponent as u32) << 10 | mantissa >> 13) + ((mantissa >> 12) & 1);
+    sign | rounded.min(0x7C00) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use SurfaceFormatKind::*;
+
+    #[test]
+    fn test_choose_surface_format() {
+        let formats = [Unorm8, Srgb8, Float16, Rgb10a2];
+        assert_eq!(choose_surface_format(&formats, DISPLAY_SDR), (1, OUTPUT_SDR_LINEAR));
+        assert_eq!(choose_surface_format(&formats, DISPLAY_HDR_SCRGB), (2, OUTPUT_SCRGB));
+        assert_eq!(choose_surface_format(&formats, DISPLAY_HDR10), (3, OUTPUT_HDR10));
+
+        // No HDR formats: fall back to SDR
+        assert_eq!(choose_surface_format(&[Unorm8, Srgb8], DISPLAY_HDR10), (1, OUTPUT_SDR_LINEAR));
+        assert_eq!(choose_surface_format(&[Unorm8], DISPLAY_SDR), (0, OUTPUT_SDR_GAMMA));
+        // Only the other HDR format
+        assert_eq!(choose_surface_format(&[Srgb8, Float16], DISPLAY_HDR10), (1, OUTPUT_SCRGB));
+        assert_eq!(choose_surface_format(&[Other], DISPLAY_SDR), (0, OUTPUT_SDR_GAMMA));
+    }
+
+    #[test]
+    fn test_display_settings() {
+        let settings = DisplaySettings { mode: DISPLAY_HDR_SCRGB, paper_white_nits: 250.0, max_nits: 1000.0 };
+        assert!(settings.validate().is_ok());
+        assert_eq!(settings.output_peak(OUTPUT_SCRGB), 4.0);
+        assert_eq!(settings.output_peak(OUTPUT_SDR_LINEAR), 1.0);
+        assert!(DisplaySettings { max_nits: 100.0, ..settings }.validate().is_err());
+        assert!(DisplaySettings { mode: 3, ..settings }.validate().is_err());
+        assert!(DisplaySettings::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_pq_encode() {
+        assert!(pq_encode(0.0) < 1e-6);
+        assert!((pq_encode(PQ_MAX_NITS) - 1.0).abs() < 1e-5);
+        // Reference PQ code values: 100 nits ≈ 0.508, 1000 nits ≈ 0.752
+        assert!((pq_encode(100.0) - 0.508).abs() < 1e-3);
+        assert!((pq_encode(1000.0) - 0.752).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_f16_to_f32() {
+        assert_eq!(f16_to_f32(0x3C00), 1.0);
+        assert_eq!(f16_to_f32(0xC000), -2.0);
+        assert_eq!(f16_to_f32(0x3555), 0.333_251_95);
+        assert_eq!(f16_to_f32(0x7BFF), 65504.0);
+        assert_eq!(f16_to_f32(0x0001), 2f32.powi(-24));
+        assert_eq!(f16_to_f32(0x7C00), f32::INFINITY);
+        assert!(f16_to_f32(0x7E00).is_nan());
+    }
+
+    #[test]
+    fn test_f32_to_f16() {
+        assert_eq!(f32_to_f16(1.0), 0x3C00);
+        assert_eq!(f32_to_f16(-2.0), 0xC000);
+        assert_eq!(f32_to_f16(65504.0), 0x7BFF);
+        assert_eq!(f32_to_f16(1e6), 0x7C00);
+        assert_eq!(f32_to_f16(2f32.powi(-24)), 0x0001);
+        assert_eq!(f32_to_f16(0.0), 0);
+        assert!(f16_to_f32(f32_to_f16(f32::NAN)).is_nan());
+        for value in [0.1, 0.333, 4.5678, 1234.5, 6.1e-5] {
+            let back = f16_to_f32(f32_to_f16(value));
+            assert!((back - value).abs() <= value * 1e-3, "{value} -> {back}");
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! Entity hierarchy ordering shared by the runtimes.
+//!
+//! ORSB stores entities in the exporter's order, which is not guaranteed to put
+//! parents before children. [`HierarchyOrder`] is a depth-first pre-order of the
+//! parent links in which every subtree is one contiguous run, so a dirty entity
+//! and all of its descendants can be recomputed as a single slice.
+
+/// Depth-first pre-order of an entity hierarchy.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HierarchyOrder {
+    /// Entity indices, parents before children.
+    pub order: Vec<usize>,
+    /// `subtree_end[k]` is the exclusive end (in `order`) of the subtree rooted at `order[k]`.
+    pub subtree_end: Vec<usize>,
+}
+
+impl HierarchyOrder {
+    /// Order the entities described by `parents` (`parents[i]` = parent of
+    /// entity `i`). Out-of-range parents are treated as roots; cycles are an error.
+    /// Roots and siblings keep their original relative order.
+    pub fn build(parents: &[Option<usize>]) -> Result<Self, String> {
+        let n = parents.len();
+        let parent_of = |i: usize| parents[i].filter(|&p| p < n);
+
+        // Children lists in CSR form, preserving entity order among siblings
+        let mut child_start = vec![0usize; n + 1];
+        for i in 0..n {
+            if let Some(p) = parent_of(i) {
+                child_start[p + 1] += 1;
+            }
+        }
+        for i in 0..n {
+            child_start[i + 1] += child_start[i];
+        }
+        let mut fill = child_start.clone();
+        let mut children = vec![0usize; child_start[n]];
+        for i in 0..n {
+            if let Some(p) = parent_of(i) {
+                children[fill[p]] = i;
+                fill[p] += 1;
+            }
+        }
+
+        let mut order = Vec::with_capacity(n);
+        let mut subtree_end = vec![0usize; n];
+        // (position in `order`, next child to visit)
+        let mut stack: Vec<(usize, usize)> = Vec::new();
+        for root in (0..n).filter(|&i| parent_of(i).is_none()) {
+            order.push(root);
+            stack.push((order.len() - 1, child_start[root]));
+            while let Some(top) = stack.last_mut() {
+                let (pos, next) = *top;
+                if next < child_start[order[pos] + 1] {
+                    top.1 += 1;
+                    let child = children[next];
+                    order.push(child);
+                    stack.push((order.len() - 1, child_start[child]));
+                } else {
+                    subtree_end[pos] = order.len();
+                    stack.pop();
+                }
+            }
+        }
+
+        // Anything not reached from a root sits on a parent cycle
+        if order.len() != n {
+            let mut reached = vec![false; n];
+            for &i in &order {
+                reached[i] = true;
+            }
+            let cyclic = reached.iter().position(|r| !r).unwrap();
+            return Err(format!("Entity hierarchy has a parent cycle through entity {cyclic}"));
+        }
+
+        Ok(Self { order, subtree_end })
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+}
+
+/// True if making `parent` the parent of `child` would create a cycle,
+/// i.e. `parent` is `child` or one of its descendants.
+pub fn would_create_cycle(parents: &[Option<usize>], child: usize, parent: usize) -> bool {
+    let mut cursor = Some(parent);
+    // Bounded walk so a pre-existing cycle cannot loop forever
+    for _ in 0..=parents.len() {
+        match cursor {
+            Some(c) if c == child => return true,
+            Some(c) => cursor = parents.get(c).copied().flatten(),
+            None => return false,
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_child_stored_before_parent() {
+        // 0 is a child of 2, 1 is a child of 0, 2 is a root
+        let h = HierarchyOrder::build(&[Some(2), Some(0), None]).unwrap();
+        assert_eq!(h.order, vec![2, 0, 1]);
+        assert_eq!(h.subtree_end, vec![3, 3, 3]);
+    }
+
+    #[test]
+    fn test_subtrees_are_contiguous() {
+        // Two roots: 0 -> {1 -> {3}, 2}, 4 -> {5}
+        let parents = [None, Some(0), Some(0), Some(1), None, Some(4)];
+        let h = HierarchyOrder::build(&parents).unwrap();
+        assert_eq!(h.order, vec![0, 1, 3, 2, 4, 5]);
+        assert_eq!(h.subtree_end, vec![4, 3, 3, 4, 6, 6]);
+        for (k, &i) in h.order.iter().enumerate() {
+            if let Some(p) = parents[i] {
+                assert!(h.order[..k].contains(&p));
+            }
+        }
+    }
+
+    #[test]
+    fn test_out_of_range_parent_is_root() {
+        let h = HierarchyOrder::build(&[Some(7), Some(0)]).unwrap();
+        assert_eq!(h.order, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_cycle_is_rejected() {
+        assert!(HierarchyOrder::build(&[Some(1), Some(0), None]).is_err());
+        assert!(HierarchyOrder::build(&[Some(0)]).is_err());
+    }
+
+    #[test]
+    fn test_would_create_cycle() {
+        let parents = [None, Some(0), Some(1)];
+        assert!(would_create_cycle(&parents, 0, 2));
+        assert!(would_create_cycle(&parents, 1, 1));
+        assert!(!would_create_cycle(&parents, 2, 0));
+    }
+}
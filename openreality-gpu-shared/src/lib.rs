@@ -1,4 +1,22 @@
 pub mod uniforms;
 pub mod shaders;
 pub mod math;
+pub mod lod;
+pub mod batching;
+pub mod clustering;
+pub mod csm;
+pub mod shadow_atlas;
+pub mod post_process;
+pub mod atmosphere;
+pub mod decal;
+pub mod color_lut;
+pub mod display;
+pub mod resolution;
 pub mod scene_format;
+pub mod animation;
+pub mod morph;
+pub mod particles;
+pub mod rng;
+pub mod hierarchy;
+pub mod collision;
+pub mod script;
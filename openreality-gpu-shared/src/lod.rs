@@ -0,0 +1,47 @@
+//! Distance-based LOD selection, mirroring Julia's `select_lod_level`.
+
+/// Pick the LOD level (0 = finest) for an object `distance` away. `max_distances`
+/// are the levels' thresholds in ascending order; the last level is used past
+/// its threshold. Going back to a finer level than `prev_level` requires the
+/// distance to drop below that level's threshold divided by `hysteresis`, so
+/// objects near a boundary do not flicker between levels.
+pub fn select_lod_level(max_distances: &[f32], hysteresis: f32, distance: f32, prev_level: usize) -> usize {
+    let n = max_distances.len();
+    if n <= 1 {
+        return 0;
+    }
+    let mut level = max_distances.iter().position(|&d| distance <= d).unwrap_or(n - 1);
+    if prev_level > level && hysteresis > 0.0 && distance > max_distances[level] / hysteresis {
+        level = prev_level;
+    }
+    level.min(n - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEVELS: [f32; 3] = [15.0, 30.0, 60.0];
+
+    #[test]
+    fn test_select_by_distance() {
+        assert_eq!(select_lod_level(&LEVELS, 1.0, 5.0, 0), 0);
+        assert_eq!(select_lod_level(&LEVELS, 1.0, 20.0, 0), 1);
+        assert_eq!(select_lod_level(&LEVELS, 1.0, 45.0, 0), 2);
+        // Past the last threshold stays on the coarsest level
+        assert_eq!(select_lod_level(&LEVELS, 1.0, 500.0, 0), 2);
+        assert_eq!(select_lod_level(&[10.0], 1.0, 500.0, 0), 0);
+        assert_eq!(select_lod_level(&[], 1.0, 5.0, 3), 0);
+    }
+
+    #[test]
+    fn test_hysteresis() {
+        // Moving in from level 1: 14 is within 15 but not within 15 / 1.1
+        assert_eq!(select_lod_level(&LEVELS, 1.1, 14.0, 1), 1);
+        assert_eq!(select_lod_level(&LEVELS, 1.1, 13.0, 1), 0);
+        // Moving out is never delayed
+        assert_eq!(select_lod_level(&LEVELS, 1.1, 16.0, 0), 1);
+        // A stale level past the end is clamped
+        assert_eq!(select_lod_level(&LEVELS, 1.1, 14.0, 7), 2);
+    }
+}
@@ -31,8 +31,10 @@ pub fn extract_frustum_planes(vp: &Mat4) -> [[f32; 4]; 6] {
     planes
 }
 
-/// Test if a bounding sphere is inside or intersects the frustum.
-pub fn sphere_in_frustum(planes: &[[f32; 4]; 6], center: Vec3, radius: f32) -> bool {
+/// Test if a bounding sphere is inside or intersects the frustum. Any subset
+/// of the planes may be passed, e.g. only the side planes of a shadow cascade
+/// so casters between the light and the cascade are kept.
+pub fn sphere_in_frustum(planes: &[[f32; 4]], center: Vec3, radius: f32) -> bool {
     for plane in planes {
         let dist = plane[0] * center.x + plane[1] * center.y + plane[2] * center.z + plane[3];
         if dist < -radius {
@@ -42,6 +44,32 @@ pub fn sphere_in_frustum(planes: &[[f32; 4]; 6], center: Vec3, radius: f32) -> b
     true
 }
 
+/// Bounding sphere used for frustum culling and LOD distances.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    /// Sphere around the AABB center of flat xyz `positions` reaching the
+    /// farthest vertex, matching Julia's `bounding_sphere_from_mesh`.
+    pub fn from_positions(positions: &[f32]) -> Self {
+        let points = || positions.chunks_exact(3).map(Vec3::from_slice);
+        let Some(first) = points().next() else { return Self::default() };
+        let (min, max) = points().fold((first, first), |(lo, hi), p| (lo.min(p), hi.max(p)));
+        let center = (min + max) * 0.5;
+        let radius = points().map(|p| p.distance_squared(center)).fold(0.0, f32::max).sqrt();
+        Self { center, radius }
+    }
+
+    /// World-space sphere under `model`; the radius grows with the largest axis scale.
+    pub fn transformed(&self, model: &Mat4) -> Self {
+        let scale = model.x_axis.truncate().length().max(model.y_axis.truncate().length()).max(model.z_axis.truncate().length());
+        Self { center: model.transform_point3(self.center), radius: self.radius * scale }
+    }
+}
+
 /// Compute cascade split distances using PSSM (Practical Split Scheme Method).
 pub fn compute_cascade_splits(near: f32, far: f32, num_cascades: usize, lambda: f32) -> Vec<f32> {
     let mut splits = Vec::with_capacity(num_cascades + 1);
@@ -71,3 +99,36 @@ pub fn geometry_schlick_ggx(n_dot_v: f32, roughness: f32) -> f32 {
     let k = (r * r) / 8.0;
     n_dot_v / (n_dot_v * (1.0 - k) + k)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bounding_sphere_from_positions() {
+        let bs = BoundingSphere::from_positions(&[-1.0, 0.0, 0.0, 3.0, 0.0, 0.0, 1.0, 2.0, 0.0]);
+        assert_eq!(bs.center, Vec3::new(1.0, 1.0, 0.0));
+        assert!((bs.radius - 5.0f32.sqrt()).abs() < 1e-6);
+        assert_eq!(BoundingSphere::from_positions(&[]), BoundingSphere::default());
+    }
+
+    #[test]
+    fn test_bounding_sphere_transformed() {
+        let bs = BoundingSphere { center: Vec3::X, radius: 1.0 };
+        let model = Mat4::from_scale_rotation_translation(Vec3::new(1.0, 3.0, 2.0), glam::Quat::IDENTITY, Vec3::Z);
+        let world = bs.transformed(&model);
+        assert_eq!(world.center, Vec3::new(1.0, 0.0, 1.0));
+        assert_eq!(world.radius, 3.0);
+    }
+
+    #[test]
+    fn test_sphere_in_frustum() {
+        let vp = Mat4::perspective_rh(1.0, 1.0, 0.1, 100.0) * Mat4::look_at_rh(Vec3::ZERO, -Vec3::Z, Vec3::Y);
+        let planes = extract_frustum_planes(&vp);
+        assert!(sphere_in_frustum(&planes, Vec3::new(0.0, 0.0, -10.0), 1.0));
+        assert!(!sphere_in_frustum(&planes, Vec3::new(0.0, 0.0, 10.0), 1.0));
+        assert!(!sphere_in_frustum(&planes, Vec3::new(0.0, 0.0, -200.0), 1.0));
+        // Straddling the left plane still counts
+        assert!(sphere_in_frustum(&planes, Vec3::new(-5.6, 0.0, -10.0), 1.0));
+    }
+}
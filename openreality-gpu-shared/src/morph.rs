@@ -0,0 +1,96 @@
+//! Morph target (blend shape) application shared by the native and web runtimes.
+//!
+//! Both runtimes deform on the CPU with [`apply_morph_targets`] and upload the
+//! result, so a given set of weights produces identical vertices everywhere.
+
+use crate::scene_format::MorphTargetParsed;
+
+/// Weights below this are treated as zero.
+const WEIGHT_EPSILON: f32 = 1e-5;
+
+/// Write `base + Σ weight_i * delta_i` into `out_positions`/`out_normals`.
+///
+/// Normals are renormalized after blending. Targets without normal deltas
+/// leave normals untouched. Extra weights or targets are ignored.
+pub fn apply_morph_targets(
+    base_positions: &[f32],
+    base_normals: &[f32],
+    targets: &[MorphTargetParsed],
+    weights: &[f32],
+    out_positions: &mut Vec<f32>,
+    out_normals: &mut Vec<f32>,
+) {
+    out_positions.clear();
+    out_positions.extend_from_slice(base_positions);
+    out_normals.clear();
+    out_normals.extend_from_slice(base_normals);
+
+    let mut normals_changed = false;
+    for (target, &w) in targets.iter().zip(weights) {
+        if w.abs() < WEIGHT_EPSILON {
+            continue;
+        }
+        for (p, d) in out_positions.iter_mut().zip(&target.position_deltas) {
+            *p += w * d;
+        }
+        if !target.normal_deltas.is_empty() {
+            for (n, d) in out_normals.iter_mut().zip(&target.normal_deltas) {
+                *n += w * d;
+            }
+            normals_changed = true;
+        }
+    }
+
+    if normals_changed {
+        for n in out_normals.chunks_exact_mut(3) {
+            let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+            if len > 1e-8 {
+                n[0] /= len;
+                n[1] /= len;
+                n[2] /= len;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(position_deltas: Vec<f32>, normal_deltas: Vec<f32>) -> MorphTargetParsed {
+        MorphTargetParsed { position_deltas, normal_deltas }
+    }
+
+    #[test]
+    fn test_weighted_position_deltas() {
+        let base = [0.0, 0.0, 0.0, 1.0, 1.0, 1.0];
+        let normals = [0.0, 1.0, 0.0, 0.0, 1.0, 0.0];
+        let targets = [
+            target(vec![1.0, 0.0, 0.0, 0.0, 0.0, 0.0], vec![]),
+            target(vec![0.0, 2.0, 0.0, 0.0, 0.0, 2.0], vec![]),
+        ];
+        let (mut pos, mut nrm) = (Vec::new(), Vec::new());
+        apply_morph_targets(&base, &normals, &targets, &[0.5, 0.25], &mut pos, &mut nrm);
+        assert_eq!(pos, vec![0.5, 0.5, 0.0, 1.0, 1.0, 1.5]);
+        assert_eq!(nrm, normals.to_vec());
+    }
+
+    #[test]
+    fn test_zero_weights_return_base() {
+        let base = [1.0, 2.0, 3.0];
+        let targets = [target(vec![5.0, 5.0, 5.0], vec![1.0, 0.0, 0.0])];
+        let (mut pos, mut nrm) = (Vec::new(), Vec::new());
+        apply_morph_targets(&base, &[0.0, 0.0, 1.0], &targets, &[0.0], &mut pos, &mut nrm);
+        assert_eq!(pos, base.to_vec());
+        assert_eq!(nrm, vec![0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_normals_renormalized() {
+        let targets = [target(vec![0.0; 3], vec![1.0, -1.0, 0.0])];
+        let (mut pos, mut nrm) = (Vec::new(), Vec::new());
+        apply_morph_targets(&[0.0; 3], &[0.0, 1.0, 0.0], &targets, &[1.0], &mut pos, &mut nrm);
+        assert!((nrm[0] - 1.0).abs() < 1e-6);
+        assert!(nrm[1].abs() < 1e-6);
+    }
+}
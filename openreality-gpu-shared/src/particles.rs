@@ -0,0 +1,327 @@
+//! Particle emitter behaviour shared by the native and web runtimes.
+//!
+//! The web runtime simulates with these functions directly; the wgpu compute
+//! path bakes curves with [`bake_curve`] and mirrors the shape sampling in
+//! `particle_compute.wgsl`. Random numbers come from a caller-supplied source
+//! returning values in [0, 1).
+
+use glam::{Quat, Vec3};
+
+use crate::scene_format::{EmitterShape, ParticleCurve, ParticleEmitterParsed, TextureSheetParsed};
+
+/// Samples per baked curve in the GPU emitter parameters.
+pub const CURVE_LUT_SIZE: usize = 8;
+
+/// Sample an `N`-component curve at normalized age `t`, linearly between keys
+/// and clamped at both ends. Returns `None` for empty or malformed curves.
+pub fn sample_curve<const N: usize>(curve: &ParticleCurve, t: f32) -> Option<[f32; N]> {
+    let keys = curve.times.len();
+    if keys == 0 || curve.values.len() < keys * N {
+        return None;
+    }
+    let read = |k: usize| -> [f32; N] {
+        let mut out = [0.0; N];
+        out.copy_from_slice(&curve.values[k * N..k * N + N]);
+        out
+    };
+
+    let hi = curve.times.partition_point(|&k| k <= t);
+    if hi == 0 {
+        return Some(read(0));
+    }
+    if hi == keys {
+        return Some(read(keys - 1));
+    }
+    let (t0, t1) = (curve.times[hi - 1], curve.times[hi]);
+    let f = if t1 - t0 > 1e-6 { (t - t0) / (t1 - t0) } else { 0.0 };
+    let (a, b) = (read(hi - 1), read(hi));
+    let mut out = [0.0; N];
+    for i in 0..N {
+        out[i] = a[i] + (b[i] - a[i]) * f;
+    }
+    Some(out)
+}
+
+/// Bake a curve into [`CURVE_LUT_SIZE`] evenly spaced samples over [0, 1].
+/// Empty curves bake to `fallback`.
+pub fn bake_curve<const N: usize>(curve: &ParticleCurve, fallback: [f32; N]) -> [[f32; N]; CURVE_LUT_SIZE] {
+    let mut lut = [fallback; CURVE_LUT_SIZE];
+    for (i, slot) in lut.iter_mut().enumerate() {
+        let t = i as f32 / (CURVE_LUT_SIZE - 1) as f32;
+        if let Some(v) = sample_curve::<N>(curve, t) {
+            *slot = v;
+        }
+    }
+    lut
+}
+
+/// RGBA of a particle at normalized age `t`.
+pub fn color_at(e: &ParticleEmitterParsed, t: f32) -> [f32; 4] {
+    sample_curve::<4>(&e.color_curve, t).unwrap_or_else(|| {
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        [
+            lerp(e.start_color[0], e.end_color[0]),
+            lerp(e.start_color[1], e.end_color[1]),
+            lerp(e.start_color[2], e.end_color[2]),
+            lerp(e.start_alpha, e.end_alpha),
+        ]
+    })
+}
+
+/// Size of a particle born with `start_size` at normalized age `t`.
+pub fn size_at(e: &ParticleEmitterParsed, start_size: f32, t: f32) -> f32 {
+    match sample_curve::<1>(&e.size_curve, t) {
+        Some([m]) => start_size * m,
+        None => start_size + (e.end_size - start_size) * t,
+    }
+}
+
+/// Extra world-space velocity from the velocity-over-lifetime curve.
+pub fn velocity_offset_at(e: &ParticleEmitterParsed, t: f32) -> Vec3 {
+    sample_curve::<3>(&e.velocity_curve, t).map_or(Vec3::ZERO, Vec3::from_array)
+}
+
+/// Area-weighted random points on a triangle mesh.
+#[derive(Clone, Debug)]
+pub struct MeshSurfaceSampler {
+    triangles: Vec<[Vec3; 3]>,
+    /// Normalized cumulative area, one entry per triangle (last = 1).
+    cdf: Vec<f32>,
+}
+
+impl MeshSurfaceSampler {
+    /// Build from flat xyz positions and triangle indices. Returns `None` if
+    /// the mesh has no triangles with area.
+    pub fn new(positions: &[f32], indices: &[u32]) -> Option<Self> {
+        let vertex = |i: u32| -> Option<Vec3> {
+            let i = i as usize * 3;
+            positions.get(i..i + 3).map(Vec3::from_slice)
+        };
+        let mut triangles = Vec::with_capacity(indices.len() / 3);
+        let mut cdf = Vec::with_capacity(indices.len() / 3);
+        let mut total = 0.0;
+        for tri in indices.chunks_exact(3) {
+            let (Some(a), Some(b), Some(c)) = (vertex(tri[0]), vertex(tri[1]), vertex(tri[2])) else { continue };
+            let area = 0.5 * (b - a).cross(c - a).length();
+            if area <= 0.0 {
+                continue;
+            }
+            total += area;
+            triangles.push([a, b, c]);
+            cdf.push(total);
+        }
+        if triangles.is_empty() {
+            return None;
+        }
+        for v in &mut cdf {
+            *v /= total;
+        }
+        Some(Self { triangles, cdf })
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    /// Pick a point and its face normal.
+    pub fn sample(&self, rng: &mut impl FnMut() -> f32) -> (Vec3, Vec3) {
+        let r = rng();
+        let tri = self.cdf.partition_point(|&c| c < r).min(self.triangles.len() - 1);
+        let [a, b, c] = self.triangles[tri];
+        // Uniform barycentrics (Osada et al.)
+        let s = rng().sqrt();
+        let v = rng();
+        let p = a * (1.0 - s) + b * (s * (1.0 - v)) + c * (s * v);
+        (p, (b - a).cross(c - a).normalize_or(Vec3::Y))
+    }
+
+    /// Pack for GPU upload: three `[x, y, z, w]` per triangle, with the
+    /// triangle's cumulative area in the first vertex's `w`.
+    pub fn gpu_triangles(&self) -> Vec<[f32; 4]> {
+        let mut out = Vec::with_capacity(self.triangles.len() * 3);
+        for (tri, &cdf) in self.triangles.iter().zip(&self.cdf) {
+            out.push([tri[0].x, tri[0].y, tri[0].z, cdf]);
+            out.push([tri[1].x, tri[1].y, tri[1].z, 0.0]);
+            out.push([tri[2].x, tri[2].y, tri[2].z, 0.0]);
+        }
+        out
+    }
+}
+
+/// Sample a spawn offset and emission direction in emitter space.
+/// `Mesh` shapes need `mesh`; without it they emit from the origin.
+pub fn sample_shape(
+    shape: &EmitterShape,
+    mesh: Option<&MeshSurfaceSampler>,
+    rng: &mut impl FnMut() -> f32,
+) -> (Vec3, Vec3) {
+    use std::f32::consts::TAU;
+    match *shape {
+        EmitterShape::Point => (Vec3::ZERO, Vec3::Y),
+        EmitterShape::Sphere { radius, surface_only } => {
+            let z = 2.0 * rng() - 1.0;
+            let phi = TAU * rng();
+            let r = (1.0 - z * z).max(0.0).sqrt();
+            let dir = Vec3::new(r * phi.cos(), z, r * phi.sin());
+            let dist = if surface_only { radius } else { radius * rng().cbrt() };
+            (dir * dist, dir)
+        }
+        EmitterShape::Cone { angle, radius } => {
+            let cos_theta = 1.0 - rng() * (1.0 - angle.cos());
+            let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+            let phi = TAU * rng();
+            let (s, c) = phi.sin_cos();
+            let dir = Vec3::new(sin_theta * c, cos_theta, sin_theta * s);
+            // Start on the base disc, spreading outward along the same azimuth
+            let offset = Vec3::new(c, 0.0, s) * (radius * rng().sqrt());
+            (offset, dir)
+        }
+        EmitterShape::Box { half_extents } => {
+            let h = Vec3::from_array(half_extents);
+            let offset = Vec3::new(2.0 * rng() - 1.0, 2.0 * rng() - 1.0, 2.0 * rng() - 1.0) * h;
+            (offset, Vec3::Y)
+        }
+        EmitterShape::Mesh { .. } => mesh.map_or((Vec3::ZERO, Vec3::Y), |m| m.sample(rng)),
+    }
+}
+
+/// Rotate a velocity sampled in the emission frame (+Y forward) onto `dir`.
+pub fn orient_velocity(local_velocity: Vec3, dir: Vec3) -> Vec3 {
+    Quat::from_rotation_arc(Vec3::Y, dir) * local_velocity
+}
+
+/// Atlas frame for a particle `age` seconds old at normalized age `t`.
+/// Returns the UV offset and scale to apply to the quad's [0, 1] UVs.
+pub fn sheet_frame_uv(sheet: &TextureSheetParsed, age: f32, t: f32) -> ([f32; 2], [f32; 2]) {
+    let tiles_x = sheet.tiles_x.max(1) as u32;
+    let tiles_y = sheet.tiles_y.max(1) as u32;
+    let frames = (sheet.frame_count as u32).clamp(1, tiles_x * tiles_y);
+    let frame = if sheet.fps > 0.0 {
+        (age * sheet.fps) as u32 % frames
+    } else {
+        ((t * frames as f32) as u32).min(frames - 1)
+    };
+    let scale = [1.0 / tiles_x as f32, 1.0 / tiles_y as f32];
+    let offset = [(frame % tiles_x) as f32 * scale[0], (frame / tiles_x) as f32 * scale[1]];
+    (offset, scale)
+}
+
+/// Bounce a particle off a horizontal plane. Returns true on contact.
+pub fn collide_ground_plane(position: &mut Vec3, velocity: &mut Vec3, e: &ParticleEmitterParsed) -> bool {
+    if position.y >= e.plane_height || velocity.y > 0.0 {
+        return false;
+    }
+    position.y = e.plane_height;
+    velocity.y = -velocity.y * e.bounce;
+    let keep = 1.0 - e.friction.clamp(0.0, 1.0);
+    velocity.x *= keep;
+    velocity.z *= keep;
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic generator for sampling tests.
+    fn lcg(seed: u32) -> impl FnMut() -> f32 {
+        let mut state = seed;
+        move || {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (state >> 8) as f32 / (1u32 << 24) as f32
+        }
+    }
+
+    #[test]
+    fn test_sample_curve_clamps_and_lerps() {
+        let curve = ParticleCurve { times: vec![0.2, 0.6], values: vec![1.0, 3.0] };
+        assert_eq!(sample_curve::<1>(&curve, 0.0), Some([1.0]));
+        assert_eq!(sample_curve::<1>(&curve, 1.0), Some([3.0]));
+        assert!((sample_curve::<1>(&curve, 0.4).unwrap()[0] - 2.0).abs() < 1e-6);
+        assert_eq!(sample_curve::<1>(&ParticleCurve::default(), 0.5), None);
+        assert_eq!(sample_curve::<3>(&curve, 0.5), None);
+    }
+
+    #[test]
+    fn test_bake_curve_endpoints_and_fallback() {
+        let curve = ParticleCurve { times: vec![0.0, 1.0], values: vec![0.0, 7.0] };
+        let lut = bake_curve::<1>(&curve, [1.0]);
+        assert_eq!(lut[0], [0.0]);
+        assert_eq!(lut[CURVE_LUT_SIZE - 1], [7.0]);
+        assert_eq!(bake_curve::<1>(&ParticleCurve::default(), [1.0]), [[1.0]; CURVE_LUT_SIZE]);
+    }
+
+    #[test]
+    fn test_color_and_size_fall_back_to_lerp() {
+        let e = ParticleEmitterParsed { start_size_min: 2.0, end_size: 0.0, ..Default::default() };
+        assert_eq!(color_at(&e, 0.5)[3], 0.5);
+        assert_eq!(size_at(&e, 2.0, 0.25), 1.5);
+        let e = ParticleEmitterParsed {
+            size_curve: ParticleCurve { times: vec![0.0], values: vec![3.0] },
+            ..e
+        };
+        assert_eq!(size_at(&e, 2.0, 0.25), 6.0);
+    }
+
+    #[test]
+    fn test_shapes_stay_in_bounds() {
+        let mut rng = lcg(7);
+        for _ in 0..200 {
+            let (p, d) = sample_shape(&EmitterShape::Sphere { radius: 2.0, surface_only: true }, None, &mut rng);
+            assert!((p.length() - 2.0).abs() < 1e-4);
+            assert!((d.length() - 1.0).abs() < 1e-4);
+
+            let angle = 0.4;
+            let (p, d) = sample_shape(&EmitterShape::Cone { angle, radius: 0.5 }, None, &mut rng);
+            assert!(d.angle_between(Vec3::Y) <= angle + 1e-4);
+            assert!(p.y == 0.0 && p.length() <= 0.5 + 1e-6);
+
+            let (p, _) = sample_shape(&EmitterShape::Box { half_extents: [1.0, 2.0, 3.0] }, None, &mut rng);
+            assert!(p.x.abs() <= 1.0 && p.y.abs() <= 2.0 && p.z.abs() <= 3.0);
+        }
+    }
+
+    #[test]
+    fn test_mesh_sampler_uses_area() {
+        // A 1x1 quad in the XZ plane plus a degenerate triangle
+        let positions = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0, 1.0];
+        let indices = [0, 3, 2, 0, 2, 1, 0, 0, 1];
+        let sampler = MeshSurfaceSampler::new(&positions, &indices).unwrap();
+        assert_eq!(sampler.triangle_count(), 2);
+        assert_eq!(sampler.gpu_triangles()[3][3], 1.0);
+        let mut rng = lcg(3);
+        for _ in 0..100 {
+            let (p, n) = sampler.sample(&mut rng);
+            assert!(p.y == 0.0 && (0.0..=1.0).contains(&p.x) && (0.0..=1.0).contains(&p.z));
+            assert!((n - Vec3::Y).length() < 1e-5);
+        }
+        assert!(MeshSurfaceSampler::new(&positions, &[0, 0, 1]).is_none());
+    }
+
+    #[test]
+    fn test_orient_velocity() {
+        let v = orient_velocity(Vec3::new(0.0, 2.0, 0.0), Vec3::X);
+        assert!((v - Vec3::new(2.0, 0.0, 0.0)).length() < 1e-5);
+    }
+
+    #[test]
+    fn test_sheet_frames() {
+        let sheet = TextureSheetParsed { texture: None, tiles_x: 4, tiles_y: 2, frame_count: 8, fps: 0.0 };
+        assert_eq!(sheet_frame_uv(&sheet, 0.0, 0.0), ([0.0, 0.0], [0.25, 0.5]));
+        assert_eq!(sheet_frame_uv(&sheet, 0.0, 0.7).0, [0.25, 0.5]);
+        assert_eq!(sheet_frame_uv(&sheet, 0.0, 1.0).0, [0.75, 0.5]);
+        let looping = TextureSheetParsed { fps: 10.0, ..sheet };
+        assert_eq!(sheet_frame_uv(&looping, 0.85, 0.0).0, [0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_ground_plane_bounce() {
+        let e = ParticleEmitterParsed { plane_height: 0.0, bounce: 0.5, friction: 0.5, ..Default::default() };
+        let mut p = Vec3::new(0.0, -0.1, 0.0);
+        let mut v = Vec3::new(2.0, -4.0, 0.0);
+        assert!(collide_ground_plane(&mut p, &mut v, &e));
+        assert_eq!(p.y, 0.0);
+        assert_eq!(v, Vec3::new(1.0, 2.0, 0.0));
+        assert!(!collide_ground_plane(&mut p, &mut v, &e));
+    }
+}
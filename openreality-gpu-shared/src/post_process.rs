@@ -0,0 +1,514 @@
+//! Post-process stack: which screen-space effects run, in what order, and
+//! with which settings. SSAO and SSR are inputs of the lighting pass, so they
+//! run before it wherever they sit in the order. The other effects form a
+//! chain over the lit HDR image: the HDR effects (TAA, bloom, DOF, motion
+//! blur) in stack order, then tone mapping, then the display-space effects
+//! (color grading, FXAA) in stack order.
+//!
+//! Exposure, chromatic aberration, vignette and film grain are not stack
+//! effects: they belong to the tone mapping pass, and are off at their
+//! default settings.
+
+use crate::rng::Rng;
+
+/// Number of effects in a stack.
+pub const NUM_POST_EFFECTS: usize = 8;
+
+/// Tone mapping operators, matching Julia's `ToneMappingMode`.
+pub const TONEMAP_REINHARD: u32 = 0;
+pub const TONEMAP_ACES: u32 = 1;
+pub const TONEMAP_UNCHARTED2: u32 = 2;
+pub const TONEMAP_AGX: u32 = 3;
+pub const TONEMAP_PBR_NEUTRAL: u32 = 4;
+
+/// Exposure modes, matching Julia's `ExposureMode`. Fixed scales the image
+/// by `2^compensation` only; manual and auto expose for an EV100 (set, or
+/// metered from the luminance histogram) like a physical camera.
+pub const EXPOSURE_FIXED: u32 = 0;
+pub const EXPOSURE_MANUAL: u32 = 1;
+pub const EXPOSURE_AUTO: u32 = 2;
+
+/// Bins of the auto exposure luminance histogram (one workgroup's threads).
+pub const HISTOGRAM_BINS: u32 = 256;
+
+/// Largest SSAO kernel the shader reads.
+pub const MAX_SSAO_KERNEL: u32 = 64;
+/// Largest number of motion blur taps per pixel.
+pub const MAX_MOTION_BLUR_SAMPLES: u32 = 32;
+
+/// Effects of the stack; the discriminant is the id used over FFI.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PostEffect {
+    Ssao = 0,
+    Ssr = 1,
+    Taa = 2,
+    Bloom = 3,
+    Dof = 4,
+    MotionBlur = 5,
+    ColorGrading = 6,
+    Fxaa = 7,
+}
+
+impl PostEffect {
+    /// Every effect, in id (and default) order.
+    pub const ALL: [PostEffect; NUM_POST_EFFECTS] = [
+        PostEffect::Ssao,
+        PostEffect::Ssr,
+        PostEffect::Taa,
+        PostEffect::Bloom,
+        PostEffect::Dof,
+        PostEffect::MotionBlur,
+        PostEffect::ColorGrading,
+        PostEffect::Fxaa,
+    ];
+
+    pub fn from_id(id: u32) -> Option<Self> {
+        Self::ALL.get(id as usize).copied()
+    }
+
+    /// Effects computed from the G-Buffer for the lighting pass.
+    pub fn is_lighting_input(self) -> bool {
+        matches!(self, PostEffect::Ssao | PostEffect::Ssr)
+    }
+
+    /// Effects that work on tone-mapped colors.
+    pub fn is_display_space(self) -> bool {
+        matches!(self, PostEffect::ColorGrading | PostEffect::Fxaa)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BloomSettings {
+    pub threshold: f32,
+    pub intensity: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ToneMapSettings {
+    pub mode: u32,
+    pub gamma: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SsaoSettings {
+    pub radius: f32,
+    pub bias: f32,
+    pub power: f32,
+    pub kernel_size: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SsrSettings {
+    pub max_steps: u32,
+    pub max_distance: f32,
+    pub thickness: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TaaSettings {
+    /// History weight, 0..1.
+    pub feedback: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DofSettings {
+    pub focus_distance: f32,
+    pub focus_range: f32,
+    pub bokeh_radius: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MotionBlurSettings {
+    pub intensity: f32,
+    pub samples: u32,
+    /// Longest blur in pixels.
+    pub max_velocity: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorGradingSettings {
+    pub brightness: f32,
+    pub contrast: f32,
+    pub saturation: f32,
+    /// Blend between the ungraded and the 3D LUT graded color, 0..1.
+    pub lut_strength: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExposureSettings {
+    pub mode: u32,
+    /// Exposure value at ISO 100 of `EXPOSURE_MANUAL`.
+    pub ev100: f32,
+    /// Stops added on top of the mode's exposure.
+    pub compensation: f32,
+    /// EV100 range auto exposure is metered and clamped to.
+    pub min_ev100: f32,
+    pub max_ev100: f32,
+    /// Adaptation rates (1/s) towards a brighter and a darker scene.
+    pub speed_up: f32,
+    pub speed_down: f32,
+}
+
+/// Lens and film effects of the tone mapping pass; zero intensity is off.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LensSettings {
+    /// Red/blue channel offset at the screen corners, in UV units.
+    pub chromatic_aberration: f32,
+    pub vignette_intensity: f32,
+    pub vignette_radius: f32,
+    pub vignette_softness: f32,
+    pub grain_intensity: f32,
+}
+
+impl BloomSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.threshold < 0.0 || self.intensity < 0.0 {
+            return Err("Bloom threshold and intensity must be >= 0".into());
+        }
+        Ok(())
+    }
+}
+
+impl ToneMapSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.mode > TONEMAP_PBR_NEUTRAL {
+            return Err(format!("Unknown tone mapping mode {}", self.mode));
+        }
+        if self.gamma <= 0.0 {
+            return Err(format!("Gamma must be > 0, got {}", self.gamma));
+        }
+        Ok(())
+    }
+}
+
+impl SsaoSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=MAX_SSAO_KERNEL).contains(&self.kernel_size) {
+            return Err(format!("SSAO kernel size must be 1..={MAX_SSAO_KERNEL}, got {}", self.kernel_size));
+        }
+        if self.radius <= 0.0 {
+            return Err(format!("SSAO radius must be > 0, got {}", self.radius));
+        }
+        Ok(())
+    }
+}
+
+impl SsrSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_steps == 0 || self.max_distance <= 0.0 || self.thickness <= 0.0 {
+            return Err("SSR steps, distance and thickness must be > 0".into());
+        }
+        Ok(())
+    }
+}
+
+impl TaaSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..1.0).contains(&self.feedback) {
+            return Err(format!("TAA feedback must be in [0, 1), got {}", self.feedback));
+        }
+        Ok(())
+    }
+}
+
+impl DofSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.focus_range <= 0.0 || self.focus_distance < 0.0 || self.bokeh_radius < 0.0 {
+            return Err("DOF focus range must be > 0, focus distance and bokeh radius >= 0".into());
+        }
+        Ok(())
+    }
+}
+
+impl MotionBlurSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(2..=MAX_MOTION_BLUR_SAMPLES).contains(&self.samples) {
+            return Err(format!("Motion blur samples must be 2..={MAX_MOTION_BLUR_SAMPLES}, got {}", self.samples));
+        }
+        if self.intensity < 0.0 || self.max_velocity < 0.0 {
+            return Err("Motion blur intensity and max velocity must be >= 0".into());
+        }
+        Ok(())
+    }
+}
+
+impl ColorGradingSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.contrast < 0.0 || self.saturation < 0.0 {
+            return Err("Color grading contrast and saturation must be >= 0".into());
+        }
+        if !(0.0..=1.0).contains(&self.lut_strength) {
+            return Err(format!("LUT strength must be in [0, 1], got {}", self.lut_strength));
+        }
+        Ok(())
+    }
+}
+
+impl ExposureSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.mode > EXPOSURE_AUTO {
+            return Err(format!("Unknown exposure mode {}", self.mode));
+        }
+        if self.min_ev100 >= self.max_ev100 {
+            return Err(format!("Exposure EV100 range {}..{} is empty", self.min_ev100, self.max_ev100));
+        }
+        if self.speed_up <= 0.0 || self.speed_down <= 0.0 {
+            return Err("Exposure adaptation speeds must be > 0".into());
+        }
+        Ok(())
+    }
+
+    /// Linear scale of the scene color for an EV100 of `ev100` (ignored by
+    /// `EXPOSURE_FIXED`; auto exposure passes its adapted EV100).
+    pub fn exposure(&self, ev100: f32) -> f32 {
+        let stops = 2f32.powf(self.compensation);
+        if self.mode == EXPOSURE_FIXED {
+            stops
+        } else {
+            stops * ev100_to_exposure(ev100)
+        }
+    }
+}
+
+impl LensSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.chromatic_aberration < 0.0 || self.vignette_intensity < 0.0 || self.grain_intensity < 0.0 {
+            return Err("Chromatic aberration, vignette and grain intensities must be >= 0".into());
+        }
+        if self.vignette_radius < 0.0 || self.vignette_softness <= 0.0 {
+            return Err("Vignette radius must be >= 0 and softness > 0".into());
+        }
+        Ok(())
+    }
+}
+
+/// Scale that maps the luminance which saturates a sensor at `ev100` to 1
+/// (the saturation-based speed model, lens attenuation 0.65, K = 12.5).
+pub fn ev100_to_exposure(ev100: f32) -> f32 {
+    1.0 / (1.2 * 2f32.powf(ev100))
+}
+
+/// EV100 that exposes an average scene luminance (cd/m²) as middle gray.
+pub fn ev100_from_luminance(avg_luminance: f32) -> f32 {
+    (avg_luminance.max(1e-6) * 100.0 / 12.5).log2()
+}
+
+/// Move `current` towards `target` EV100 over `dt` seconds, adapting at
+/// `speed_up` when the scene got brighter and `speed_down` when it got darker.
+/// Mirrors the adaptation in `luminance_histogram.wgsl`.
+pub fn adapt_ev100(current: f32, target: f32, dt: f32, speed_up: f32, speed_down: f32) -> f32 {
+    let speed = if target > current { speed_up } else { speed_down };
+    current + (target - current) * (1.0 - (-dt * speed).exp())
+}
+
+/// Effect order, enabled flags and settings of one renderer's post-processing.
+#[derive(Clone, Debug)]
+pub struct PostProcessStack {
+    order: [PostEffect; NUM_POST_EFFECTS],
+    enabled: [bool; NUM_POST_EFFECTS],
+    pub bloom: BloomSettings,
+    pub tone_map: ToneMapSettings,
+    pub ssao: SsaoSettings,
+    pub ssr: SsrSettings,
+    pub taa: TaaSettings,
+    pub dof: DofSettings,
+    pub motion_blur: MotionBlurSettings,
+    pub color_grading: ColorGradingSettings,
+    pub exposure: ExposureSettings,
+    pub lens: LensSettings,
+}
+
+impl Default for PostProcessStack {
+    /// SSAO, SSR, bloom and FXAA on, like the fixed chain this replaces.
+    fn default() -> Self {
+        let mut enabled = [false; NUM_POST_EFFECTS];
+        for effect in [PostEffect::Ssao, PostEffect::Ssr, PostEffect::Bloom, PostEffect::Fxaa] {
+            enabled[effect as usize] = true;
+        }
+        Self {
+            order: PostEffect::ALL,
+            enabled,
+            bloom: BloomSettings { threshold: 1.0, intensity: 0.3 },
+            tone_map: ToneMapSettings { mode: TONEMAP_REINHARD, gamma: 2.2 },
+            ssao: SsaoSettings { radius: 0.5, bias: 0.025, power: 2.0, kernel_size: 32 },
+            ssr: SsrSettings { max_steps: 64, max_distance: 50.0, thickness: 0.1 },
+            taa: TaaSettings { feedback: 0.9 },
+            dof: DofSettings { focus_distance: 10.0, focus_range: 5.0, bokeh_radius: 3.0 },
+            motion_blur: MotionBlurSettings { intensity: 1.0, samples: 8, max_velocity: 40.0 },
+            color_grading: ColorGradingSettings { brightness: 0.0, contrast: 1.0, saturation: 1.0, lut_strength: 1.0 },
+            exposure: ExposureSettings {
+                mode: EXPOSURE_FIXED,
+                ev100: 0.0,
+                compensation: 0.0,
+                min_ev100: -4.0,
+                max_ev100: 16.0,
+                speed_up: 3.0,
+                speed_down: 1.0,
+            },
+            lens: LensSettings {
+                chromatic_aberration: 0.0,
+                vignette_intensity: 0.0,
+                vignette_radius: 0.8,
+                vignette_softness: 0.5,
+                grain_intensity: 0.0,
+            },
+        }
+    }
+}
+
+impl PostProcessStack {
+    pub fn is_enabled(&self, effect: PostEffect) -> bool {
+        self.enabled[effect as usize]
+    }
+
+    pub fn set_enabled(&mut self, effect: PostEffect, enabled: bool) {
+        self.enabled[effect as usize] = enabled;
+    }
+
+    pub fn order(&self) -> &[PostEffect] {
+        &self.order
+    }
+
+    /// Reorder the stack. `ids` must name every effect exactly once.
+    pub fn set_order(&mut self, ids: &[u32]) -> Result<(), String> {
+        if ids.len() != NUM_POST_EFFECTS {
+            return Err(format!("Effect order must list all {NUM_POST_EFFECTS} effects, got {}", ids.len()));
+        }
+        let mut order = PostEffect::ALL;
+        let mut seen = [false; NUM_POST_EFFECTS];
+        for (slot, &id) in order.iter_mut().zip(ids) {
+            let effect = PostEffect::from_id(id).ok_or_else(|| format!("Unknown post effect {id}"))?;
+            if std::mem::replace(&mut seen[id as usize], true) {
+                return Err(format!("Post effect {id} listed twice"));
+            }
+            *slot = effect;
+        }
+        self.order = order;
+        Ok(())
+    }
+
+    /// Enabled HDR effects, in the order they run before tone mapping.
+    pub fn hdr_chain(&self) -> impl Iterator<Item = PostEffect> + '_ {
+        self.chain(false)
+    }
+
+    /// Enabled display-space effects, in the order they run after tone mapping.
+    pub fn display_chain(&self) -> impl Iterator<Item = PostEffect> + '_ {
+        self.chain(true)
+    }
+
+    fn chain(&self, display_space: bool) -> impl Iterator<Item = PostEffect> + '_ {
+        self.order.iter().copied().filter(move |&e| {
+            self.is_enabled(e) && !e.is_lighting_input() && e.is_display_space() == display_space
+        })
+    }
+}
+
+/// `size` SSAO sample offsets in the +Z unit hemisphere, denser near the
+/// center, padded to `MAX_SSAO_KERNEL` entries. The same seed always gives
+/// the same kernel, so AO does not flicker between frames.
+pub fn ssao_kernel(size: u32, seed: u64) -> [[f32; 4]; MAX_SSAO_KERNEL as usize] {
+    let mut rng = Rng::new(seed);
+    let mut kernel = [[0.0; 4]; MAX_SSAO_KERNEL as usize];
+    let n = size.clamp(1, MAX_SSAO_KERNEL);
+    for (i, sample) in kernel.iter_mut().take(n as usize).enumerate() {
+        let phi = rng.next_f32() * std::f32::consts::TAU;
+        let z = rng.next_f32();
+        let r = (1.0 - z * z).sqrt();
+        let t = (i + 1) as f32 / n as f32;
+        let scale = 0.1 + t * t * 0.9;
+        *sample = [r * phi.cos() * scale, r * phi.sin() * scale, z * scale, 0.0];
+    }
+    kernel
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_chain() {
+        let stack = PostProcessStack::default();
+        assert_eq!(stack.hdr_chain().collect::<Vec<_>>(), vec![PostEffect::Bloom]);
+        assert_eq!(stack.display_chain().collect::<Vec<_>>(), vec![PostEffect::Fxaa]);
+        assert!(stack.is_enabled(PostEffect::Ssao) && !stack.is_enabled(PostEffect::Taa));
+    }
+
+    #[test]
+    fn test_reorder_chain() {
+        let mut stack = PostProcessStack::default();
+        for effect in [PostEffect::Taa, PostEffect::Dof, PostEffect::MotionBlur, PostEffect::ColorGrading] {
+            stack.set_enabled(effect, true);
+        }
+        stack.set_enabled(PostEffect::Bloom, false);
+        stack.set_order(&[7, 5, 0, 4, 3, 1, 2, 6]).unwrap();
+
+        use PostEffect::*;
+        assert_eq!(stack.hdr_chain().collect::<Vec<_>>(), vec![MotionBlur, Dof, Taa]);
+        assert_eq!(stack.display_chain().collect::<Vec<_>>(), vec![Fxaa, ColorGrading]);
+        assert_eq!(stack.order()[0], Fxaa);
+    }
+
+    #[test]
+    fn test_set_order_rejects_bad_lists() {
+        let mut stack = PostProcessStack::default();
+        assert!(stack.set_order(&[0, 1, 2]).is_err());
+        assert!(stack.set_order(&[0, 1, 2, 3, 4, 5, 6, 6]).is_err());
+        assert!(stack.set_order(&[0, 1, 2, 3, 4, 5, 6, 8]).is_err());
+        // A failed reorder keeps the previous order
+        assert_eq!(stack.order(), &PostEffect::ALL);
+    }
+
+    #[test]
+    fn test_settings_validation() {
+        let stack = PostProcessStack::default();
+        assert!(stack.ssao.validate().is_ok() && stack.motion_blur.validate().is_ok());
+        assert!(ToneMapSettings { mode: TONEMAP_PBR_NEUTRAL, gamma: 2.2 }.validate().is_ok());
+        assert!(ToneMapSettings { mode: 5, gamma: 2.2 }.validate().is_err());
+        assert!(stack.exposure.validate().is_ok() && stack.lens.validate().is_ok());
+        assert!(ExposureSettings { min_ev100: 20.0, ..stack.exposure }.validate().is_err());
+        assert!(ColorGradingSettings { lut_strength: 1.5, ..stack.color_grading }.validate().is_err());
+        assert!(SsaoSettings { kernel_size: 65, ..stack.ssao }.validate().is_err());
+        assert!(TaaSettings { feedback: 1.0 }.validate().is_err());
+        assert!(DofSettings { focus_range: 0.0, ..stack.dof }.validate().is_err());
+    }
+
+    #[test]
+    fn test_exposure() {
+        let mut exposure = PostProcessStack::default().exposure;
+        assert_eq!(exposure.exposure(10.0), 1.0);
+        exposure.compensation = 1.0;
+        assert_eq!(exposure.exposure(10.0), 2.0);
+
+        exposure.mode = EXPOSURE_MANUAL;
+        assert!((exposure.exposure(0.0) - 2.0 / 1.2).abs() < 1e-6);
+        // One stop up halves the exposure
+        assert!((exposure.exposure(1.0) * 2.0 - exposure.exposure(0.0)).abs() < 1e-6);
+
+        // Metering a luminance and exposing for it maps that luminance to about middle gray
+        assert!((ev100_to_exposure(ev100_from_luminance(5.0)) * 5.0 - 0.104).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_adapt_ev100() {
+        assert_eq!(adapt_ev100(2.0, 2.0, 0.016, 3.0, 1.0), 2.0);
+        let up = adapt_ev100(0.0, 10.0, 0.1, 3.0, 1.0);
+        let down = adapt_ev100(10.0, 0.0, 0.1, 3.0, 1.0);
+        assert!(up > 0.0 && up < 10.0 && down < 10.0 && down > 0.0);
+        // Brightening adapts faster than darkening with these rates
+        assert!(up > 10.0 - down);
+        assert!((adapt_ev100(0.0, 10.0, 100.0, 3.0, 1.0) - 10.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_ssao_kernel() {
+        let kernel = ssao_kernel(16, 7);
+        assert_eq!(kernel, ssao_kernel(16, 7));
+        for s in &kernel[..16] {
+            let len = (s[0] * s[0] + s[1] * s[1] + s[2] * s[2]).sqrt();
+            assert!(s[2] >= 0.0 && len <= 1.0 + 1e-5, "{s:?}");
+        }
+        assert!(kernel[16..].iter().all(|s| *s == [0.0; 4]));
+    }
+}
@@ -0,0 +1,202 @@
+//! Internal render resolution and multisampling. The scene renders at
+//! `scale` times the output size and the present pass upscales it (FSR1
+//! EASU, then RCAS sharpening). With dynamic resolution the scale follows
+//! the measured GPU frame time towards a target.
+
+pub const MIN_RENDER_SCALE: f32 = 0.25;
+pub const MAX_RENDER_SCALE: f32 = 1.0;
+/// Dynamic resolution changes the scale in steps of this size, so small
+/// frame time changes do not reallocate the render targets.
+pub const RENDER_SCALE_STEP: f32 = 0.05;
+
+/// Frames to wait after a scale change before measuring again.
+const COOLDOWN_FRAMES: u32 = 30;
+/// Frame time headroom (target / measured) inside which the scale is kept.
+const HEADROOM_LOW: f32 = 0.95;
+const HEADROOM_HIGH: f32 = 1.15;
+/// Weight of the newest sample in the frame time average.
+const EMA_WEIGHT: f32 = 0.1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ResolutionSettings {
+    /// Render scale while dynamic resolution is off.
+    pub scale: f32,
+    pub dynamic: bool,
+    /// GPU frame time dynamic resolution aims for.
+    pub target_frame_ms: f32,
+    pub min_scale: f32,
+    pub max_scale: f32,
+    /// RCAS sharpening of upscaled frames, 0 (off) to 1.
+    pub sharpness: f32,
+    /// Samples of the forward (transparent) and UI passes: 1, 2, 4 or 8.
+    pub msaa_samples: u32,
+}
+
+impl Default for ResolutionSettings {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            dynamic: false,
+            target_frame_ms: 16.6,
+            min_scale: 0.5,
+            max_scale: 1.0,
+            sharpness: 0.8,
+            msaa_samples: 1,
+        }
+    }
+}
+
+impl ResolutionSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        let in_range = |s: f32| (MIN_RENDER_SCALE..=MAX_RENDER_SCALE).contains(&s);
+        if !in_range(self.min_scale) || !in_range(self.max_scale) || self.min_scale > self.max_scale {
+            return Err(format!(
+                "Render scale range must lie within [{MIN_RENDER_SCALE}, {MAX_RENDER_SCALE}], got [{}, {}]",
+                self.min_scale, self.max_scale
+            ));
+        }
+        if !in_range(self.scale) {
+            return Err(format!(
+                "Render scale must be between {MIN_RENDER_SCALE} and {MAX_RENDER_SCALE}, got {}",
+                self.scale
+            ));
+        }
+        if !(self.target_frame_ms.is_finite() && self.target_frame_ms > 0.0) {
+            return Err(format!("Target frame time must be a finite time > 0 ms, got {}", self.target_frame_ms));
+        }
+        if !(0.0..=1.0).contains(&self.sharpness) {
+            return Err(format!("Sharpness must be between 0 and 1, got {}", self.sharpness));
+        }
+        if !matches!(self.msaa_samples, 1 | 2 | 4 | 8) {
+            return Err(format!("MSAA sample count must be 1, 2, 4 or 8, got {}", self.msaa_samples));
+        }
+        Ok(())
+    }
+}
+
+/// Internal extent for an output of `width` x `height` at `scale`.
+pub fn scaled_extent(width: u32, height: u32, scale: f32) -> (u32, u32) {
+    let scale_dim = |d: u32| ((d as f32 * scale).round() as u32).clamp(1, d.max(1));
+    (scale_dim(width), scale_dim(height))
+}
+
+/// Dynamic resolution controller. GPU cost is taken to scale with the pixel
+/// count, so the scale moves by the square root of the frame time headroom.
+#[derive(Clone, Debug, Default)]
+pub struct DynamicResolution {
+    /// Smoothed GPU frame time, `None` until the first sample.
+    average_ms: Option<f32>,
+    cooldown: u32,
+}
+
+impl DynamicResolution {
+    /// Forget the frame time history, e.g. after the settings changed.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn average_ms(&self) -> Option<f32> {
+        self.average_ms
+    }
+
+    /// Feed one GPU frame time measured at render scale `scale`. Returns the
+    /// new scale when it should change.
+    pub fn update(&mut self, gpu_ms: f32, scale: f32, settings: &ResolutionSettings) -> Option<f32> {
+        if !settings.dynamic || !gpu_ms.is_finite() || gpu_ms <= 0.0 {
+            return None;
+        }
+        let average = match self.average_ms {
+            Some(average) => average * (1.0 - EMA_WEIGHT) + gpu_ms * EMA_WEIGHT,
+            None => gpu_ms,
+        };
+        self.average_ms = Some(average);
+        if self.cooldown > 0 {
+            self.cooldown -= 1;
+            return None;
+        }
+
+        let headroom = settings.target_frame_ms / average;
+        if (HEADROOM_LOW..=HEADROOM_HIGH).contains(&headroom) {
+            return None;
+        }
+        let wanted = scale * headroom.sqrt();
+        let new_scale = ((wanted / RENDER_SCALE_STEP).round() * RENDER_SCALE_STEP)
+            .clamp(settings.min_scale, settings.max_scale);
+        if (new_scale - scale).abs() < RENDER_SCALE_STEP * 0.5 {
+            return None;
+        }
+
+        // Expect the cost to follow the pixel count until measured again
+        self.average_ms = Some(average * (new_scale / scale).powi(2));
+        self.cooldown = COOLDOWN_FRAMES;
+        Some(new_scale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dynamic_settings() -> ResolutionSettings {
+        ResolutionSettings { dynamic: true, target_frame_ms: 10.0, min_scale: 0.5, max_scale: 1.0, ..Default::default() }
+    }
+
+    #[test]
+    fn test_resolution_settings_validate() {
+        assert!(ResolutionSettings::default().validate().is_ok());
+        assert!(ResolutionSettings { scale: 0.1, ..Default::default() }.validate().is_err());
+        assert!(ResolutionSettings { min_scale: 0.9, max_scale: 0.6, ..Default::default() }.validate().is_err());
+        assert!(ResolutionSettings { msaa_samples: 3, ..Default::default() }.validate().is_err());
+        assert!(ResolutionSettings { sharpness: 1.5, ..Default::default() }.validate().is_err());
+        assert!(ResolutionSettings { target_frame_ms: 0.0, ..Default::default() }.validate().is_err());
+        assert!(ResolutionSettings { target_frame_ms: f32::NAN, ..Default::default() }.validate().is_err());
+        assert!(ResolutionSettings { target_frame_ms: f32::INFINITY, ..Default::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_scaled_extent() {
+        assert_eq!(scaled_extent(1920, 1080, 1.0), (1920, 1080));
+        assert_eq!(scaled_extent(1920, 1080, 0.5), (960, 540));
+        assert_eq!(scaled_extent(1280, 720, 0.75), (960, 540));
+        assert_eq!(scaled_extent(3, 1, 0.25), (1, 1));
+    }
+
+    #[test]
+    fn test_dynamic_resolution_drops_when_over_budget() {
+        let settings = dynamic_settings();
+        let mut controller = DynamicResolution::default();
+        // 20 ms at scale 1 against a 10 ms target: sqrt(0.5) ≈ 0.707 -> 0.7
+        let new_scale = controller.update(20.0, 1.0, &settings).unwrap();
+        assert!((new_scale - 0.7).abs() < 1e-4);
+        // Waits for the cooldown before changing again
+        for _ in 0..COOLDOWN_FRAMES {
+            assert_eq!(controller.update(20.0, new_scale, &settings), None);
+        }
+        // Clamped to the minimum scale
+        let mut controller = DynamicResolution::default();
+        assert_eq!(controller.update(100.0, 1.0, &settings), Some(0.5));
+    }
+
+    #[test]
+    fn test_dynamic_resolution_holds_and_recovers() {
+        let settings = dynamic_settings();
+        let mut controller = DynamicResolution::default();
+        // Within the deadband
+        assert_eq!(controller.update(9.5, 0.8, &settings), None);
+        assert_eq!(controller.update(10.3, 0.8, &settings), None);
+
+        // Plenty of headroom raises the scale, up to the maximum
+        let mut controller = DynamicResolution::default();
+        let new_scale = controller.update(5.0, 0.6, &settings).unwrap();
+        assert!((new_scale - 0.85).abs() < 1e-4);
+        let mut controller = DynamicResolution::default();
+        assert_eq!(controller.update(1.0, 0.9, &settings), Some(1.0));
+        let mut controller = DynamicResolution::default();
+        assert_eq!(controller.update(1.0, 1.0, &settings), None);
+
+        // Disabled: never changes
+        let mut controller = DynamicResolution::default();
+        let fixed = ResolutionSettings { dynamic: false, ..settings };
+        assert_eq!(controller.update(100.0, 1.0, &fixed), None);
+    }
+}
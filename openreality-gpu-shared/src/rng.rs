@@ -0,0 +1,186 @@
+//! Deterministic, seedable random numbers shared by the runtimes.
+//!
+//! Every system draws from its own [`Rng`] stream, derived from one session
+//! seed and a stream key, so adding an emitter never shifts the numbers another
+//! system sees. [`RngStreams`] owns the streams and can snapshot and restore
+//! them, which is what replays and lockstep sessions need for identical effects.
+
+use std::collections::BTreeMap;
+
+/// SplitMix64 generator. The whole state is one `u64`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+const GOLDEN_GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+
+fn mix64(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Independent stream `stream` of the session seeded with `seed`.
+    pub fn for_stream(seed: u64, stream: u64) -> Self {
+        Self { state: mix64(seed ^ mix64(stream.wrapping_add(GOLDEN_GAMMA))) }
+    }
+
+    /// Resume a generator from a value returned by [`Rng::state`].
+    pub fn from_state(state: u64) -> Self {
+        Self { state }
+    }
+
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(GOLDEN_GAMMA);
+        mix64(self.state)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// Uniform in [0, 1).
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 * (1.0 / (1u32 << 24) as f32)
+    }
+
+    /// Uniform in [lo, hi).
+    pub fn range(&mut self, lo: f32, hi: f32) -> f32 {
+        lo + (hi - lo) * self.next_f32()
+    }
+}
+
+/// Per-system random streams for one session, keyed by an arbitrary `u64`.
+/// Streams are created on first use from the session seed.
+#[derive(Debug, Clone)]
+pub struct RngStreams {
+    seed: u64,
+    streams: BTreeMap<u64, Rng>,
+}
+
+/// Size of the snapshot header: seed (u64) + stream count (u32).
+const SNAPSHOT_HEADER: usize = 12;
+
+impl RngStreams {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, streams: BTreeMap::new() }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Restart every stream from a new session seed.
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.streams.clear();
+    }
+
+    /// The generator for `key`, created on first use.
+    pub fn stream(&mut self, key: u64) -> &mut Rng {
+        let seed = self.seed;
+        self.streams.entry(key).or_insert_with(|| Rng::for_stream(seed, key))
+    }
+
+    /// Serialize the seed and every stream's state (little-endian):
+    /// seed u64, count u32, then count × (key u64, state u64).
+    pub fn snapshot(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(SNAPSHOT_HEADER + self.streams.len() * 16);
+        out.extend_from_slice(&self.seed.to_le_bytes());
+        out.extend_from_slice(&(self.streams.len() as u32).to_le_bytes());
+        for (key, rng) in &self.streams {
+            out.extend_from_slice(&key.to_le_bytes());
+            out.extend_from_slice(&rng.state.to_le_bytes());
+        }
+        out
+    }
+
+    /// Replace all streams with a [`RngStreams::snapshot`]. Streams created
+    /// after the snapshot was taken start over from the seed.
+    pub fn restore(&mut self, data: &[u8]) -> Result<(), String> {
+        let u64_at = |o: usize| u64::from_le_bytes(data[o..o + 8].try_into().unwrap());
+        if data.len() < SNAPSHOT_HEADER {
+            return Err("RNG snapshot too short".into());
+        }
+        let count = u32::from_le_bytes(data[8..12].try_into().unwrap()) as usize;
+        if count.checked_mul(16).and_then(|n| n.checked_add(SNAPSHOT_HEADER)) != Some(data.len()) {
+            return Err(format!("RNG snapshot size mismatch: {} streams in {} bytes", count, data.len()));
+        }
+        self.seed = u64_at(0);
+        self.streams = (0..count)
+            .map(|i| {
+                let o = SNAPSHOT_HEADER + i * 16;
+                (u64_at(o), Rng::from_state(u64_at(o + 8)))
+            })
+            .collect();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_sequence() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+    }
+
+    #[test]
+    fn test_f32_in_unit_interval() {
+        let mut rng = Rng::new(7);
+        for _ in 0..10_000 {
+            let x = rng.next_f32();
+            assert!((0.0..1.0).contains(&x));
+            let r = rng.range(-2.0, 3.0);
+            assert!((-2.0..3.0).contains(&r));
+        }
+    }
+
+    #[test]
+    fn test_streams_are_independent() {
+        let mut streams = RngStreams::new(99);
+        let first = streams.stream(1).next_u64();
+
+        // Drawing from another stream doesn't disturb stream 1
+        let mut other = RngStreams::new(99);
+        other.stream(5).next_u64();
+        assert_eq!(other.stream(1).next_u64(), first);
+        assert_ne!(Rng::for_stream(99, 1), Rng::for_stream(99, 2));
+    }
+
+    #[test]
+    fn test_snapshot_restore_round_trip() {
+        let mut streams = RngStreams::new(12345);
+        streams.stream(0).next_u64();
+        streams.stream(1 << 63).next_f32();
+        let snapshot = streams.snapshot();
+
+        let expected: Vec<u64> = (0..4).map(|_| streams.stream(0).next_u64()).collect();
+        streams.stream(3).next_u64();
+
+        let mut restored = RngStreams::new(0);
+        restored.restore(&snapshot).unwrap();
+        assert_eq!(restored.seed(), 12345);
+        let replayed: Vec<u64> = (0..4).map(|_| restored.stream(0).next_u64()).collect();
+        assert_eq!(replayed, expected);
+
+        assert!(restored.restore(&snapshot[..snapshot.len() - 1]).is_err());
+        assert!(restored.restore(&[0; 4]).is_err());
+    }
+}
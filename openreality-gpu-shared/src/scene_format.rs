@@ -1,11 +1,19 @@
-/// ORSB (OpenReality Scene Bundle) binary format definitions.
-///
-/// The format is designed for zero-copy loading in WASM and efficient
-/// streaming from Julia's scene export.
+//! ORSB (OpenReality Scene Bundle) binary format definitions.
+//!
+//! The format is designed for zero-copy loading in WASM and efficient
+//! streaming from Julia's scene export.
+
+use crate::atmosphere::{AtmosphereSettings, FogSettings};
+use crate::decal::DecalParams;
 
 /// Magic bytes at the start of every .orsb file.
 pub const ORSB_MAGIC: [u8; 4] = *b"ORSB";
-pub const ORSB_VERSION: u32 = 1;
+pub const ORSB_VERSION: u32 = 3;
+/// Oldest format version the parser still accepts.
+///
+/// Version 1 entity records only carry mesh/material indices (28 bytes) and
+/// camera records have no `active` flag.
+pub const ORSB_MIN_VERSION: u32 = 1;
 
 /// File header (32 bytes).
 #[repr(C)]
@@ -38,6 +46,7 @@ pub enum SectionType {
     Skeletons = 11,
     Particles = 12,
     PhysicsConfig = 13,
+    AnimStateMachines = 14,
 }
 
 /// Table of contents entry.
@@ -49,6 +58,10 @@ pub struct TocEntry {
     pub size: u64,
 }
 
+/// Flags of the environment section: which settings follow.
+pub const ENVIRONMENT_ATMOSPHERE: u32 = 1 << 0;
+pub const ENVIRONMENT_FOG: u32 = 1 << 1;
+
 /// Component mask bitfield — indicates which components an entity has.
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Default)]
@@ -69,6 +82,8 @@ impl ComponentMask {
     pub const AUDIO_SOURCE: u64 = 1 << 11;
     pub const AUDIO_LISTENER: u64 = 1 << 12;
     pub const IBL: u64 = 1 << 13;
+    pub const SPOT_LIGHT: u64 = 1 << 14;
+    pub const AREA_LIGHT: u64 = 1 << 15;
 
     pub fn has(&self, flag: u64) -> bool {
         self.0 & flag != 0
@@ -216,6 +231,8 @@ pub enum TargetProperty {
     Position = 0,
     Rotation = 1,
     Scale = 2,
+    /// Morph target weights of the entity's mesh, one value per target.
+    MorphWeights = 3,
 }
 
 /// Physics world configuration.
@@ -247,13 +264,58 @@ pub struct DirLightParsed {
     pub intensity: f32,
 }
 
+/// Parsed spot light from the lights section (version 3+).
+#[derive(Clone, Debug)]
+pub struct SpotLightParsed {
+    pub position: [f32; 3],
+    pub direction: [f32; 3],
+    pub color: [f32; 3],
+    pub intensity: f32,
+    pub range: f32,
+    /// Cone half-angles in radians; full intensity inside `inner_angle`,
+    /// fading to zero at `outer_angle`.
+    pub inner_angle: f32,
+    pub outer_angle: f32,
+    pub casts_shadow: bool,
+}
+
+/// Area light shape.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AreaLightShape {
+    Rect = 0,
+    Tube = 1,
+}
+
+/// Parsed area light from the lights section (version 3+).
+///
+/// A rect is `width` x `height`, spanned by `tangent` and
+/// `cross(direction, tangent)`, and emits along `direction`. A tube is
+/// `width` long along `tangent` with thickness `radius`.
+#[derive(Clone, Debug)]
+pub struct AreaLightParsed {
+    pub shape: AreaLightShape,
+    pub position: [f32; 3],
+    pub direction: [f32; 3],
+    pub tangent: [f32; 3],
+    pub color: [f32; 3],
+    pub intensity: f32,
+    pub range: f32,
+    pub width: f32,
+    pub height: f32,
+    pub radius: f32,
+}
+
 /// Parsed camera from the cameras section.
 #[derive(Clone, Copy, Debug)]
 pub struct CameraParsed {
+    /// Vertical field of view in degrees.
     pub fov: f32,
     pub near: f32,
     pub far: f32,
     pub aspect: f32,
+    /// Whether this camera should be used as the initial view (always true for v1 files).
+    pub active: bool,
 }
 
 /// Parsed collider from the colliders section.
@@ -293,6 +355,293 @@ pub struct AnimationParsed {
     pub speed: f32,
 }
 
+/// Type of a state machine parameter.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnimParamKind {
+    Float = 0,
+    Bool = 1,
+    Trigger = 2,
+}
+
+/// How an animation layer combines with the layers below it.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LayerBlendMode {
+    /// Replace the underlying pose, scaled by layer weight.
+    Override = 0,
+    /// Add the difference between the sampled pose and the clip's first frame.
+    Additive = 1,
+}
+
+/// Comparison applied by a transition condition.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConditionOp {
+    Greater = 0,
+    Less = 1,
+    Equals = 2,
+    NotEquals = 3,
+    True = 4,
+    False = 5,
+    /// Passes while the trigger is set; firing the transition consumes it.
+    Trigger = 6,
+}
+
+/// Parsed state machine parameter with its default value (bools/triggers are 0 or 1).
+#[derive(Clone, Debug)]
+pub struct AnimParameterParsed {
+    pub name: String,
+    pub kind: AnimParamKind,
+    pub default: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct AnimConditionParsed {
+    pub parameter: u16,
+    pub op: ConditionOp,
+    pub threshold: f32,
+}
+
+/// Parsed state — plays one clip of the driven animation.
+#[derive(Clone, Debug)]
+pub struct AnimStateParsed {
+    pub name: String,
+    pub clip: u32,
+    pub speed: f32,
+    pub looping: bool,
+}
+
+/// Parsed transition. `from` is `None` for any-state transitions.
+#[derive(Clone, Debug)]
+pub struct AnimTransitionParsed {
+    pub from: Option<u16>,
+    pub to: u16,
+    /// Cross-fade duration in seconds.
+    pub duration: f32,
+    /// Normalized source-state time required before leaving, if any.
+    pub exit_time: Option<f32>,
+    pub conditions: Vec<AnimConditionParsed>,
+}
+
+/// Parsed state machine layer.
+#[derive(Clone, Debug)]
+pub struct AnimLayerParsed {
+    pub name: String,
+    pub weight: f32,
+    pub blend_mode: LayerBlendMode,
+    /// Entity indices this layer may affect. Empty means all.
+    pub mask: Vec<u32>,
+    pub states: Vec<AnimStateParsed>,
+    pub default_state: u16,
+    pub transitions: Vec<AnimTransitionParsed>,
+}
+
+/// Parsed animation state machine, driving `animations[animation_index]`.
+#[derive(Clone, Debug)]
+pub struct AnimStateMachineParsed {
+    pub animation_index: u32,
+    pub parameters: Vec<AnimParameterParsed>,
+    pub layers: Vec<AnimLayerParsed>,
+}
+
+/// How particles are composited over the scene.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ParticleBlendMode {
+    #[default]
+    Alpha = 0,
+    Additive = 1,
+    /// Color is already multiplied by alpha (`One, OneMinusSrcAlpha`).
+    Premultiplied = 2,
+}
+
+/// Volume or surface new particles are spawned from, in emitter space.
+/// The emission direction is +Y for `Point`/`Box`, radial for `Sphere`,
+/// within the cone for `Cone` and the triangle normal for `Mesh`.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum EmitterShape {
+    #[default]
+    Point,
+    Sphere { radius: f32, surface_only: bool },
+    /// `angle` is the half-angle in radians; particles start on a disc of `radius`.
+    Cone { angle: f32, radius: f32 },
+    Box { half_extents: [f32; 3] },
+    /// Surface of `meshes[mesh]`, area-weighted.
+    Mesh { mesh: u32 },
+}
+
+/// What particles collide with.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ParticleCollisionMode {
+    #[default]
+    None = 0,
+    /// Horizontal plane at `plane_height` (world space).
+    GroundPlane = 1,
+    /// Scene depth buffer. Only the wgpu compute path can evaluate this; CPU
+    /// simulation (Julia and the web runtime) lets these particles pass through.
+    DepthBuffer = 2,
+}
+
+/// Keyframed value over normalized particle age (0 = birth, 1 = death).
+/// `values` holds `times.len()` keys of a fixed component count.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParticleCurve {
+    pub times: Vec<f32>,
+    pub values: Vec<f32>,
+}
+
+impl ParticleCurve {
+    pub fn is_empty(&self) -> bool {
+        self.times.is_empty()
+    }
+}
+
+/// Flipbook animation over a texture atlas of `tiles_x` × `tiles_y` frames.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextureSheetParsed {
+    /// Index into `textures`, if any.
+    pub texture: Option<u32>,
+    pub tiles_x: u16,
+    pub tiles_y: u16,
+    pub frame_count: u16,
+    /// Frames per second; 0 plays the sheet once over the particle lifetime.
+    pub fps: f32,
+}
+
+/// Parsed particle emitter, matching Julia's `ParticleSystemComponent`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParticleEmitterParsed {
+    pub max_particles: u32,
+    pub emission_rate: f32,
+    pub burst_count: u32,
+    pub lifetime_min: f32,
+    pub lifetime_max: f32,
+    /// Initial velocity range in the shape's emission frame (+Y = emission direction).
+    pub velocity_min: [f32; 3],
+    pub velocity_max: [f32; 3],
+    pub gravity_modifier: f32,
+    pub damping: f32,
+    pub start_size_min: f32,
+    pub start_size_max: f32,
+    pub end_size: f32,
+    pub start_color: [f32; 3],
+    pub end_color: [f32; 3],
+    pub start_alpha: f32,
+    pub end_alpha: f32,
+    pub blend_mode: ParticleBlendMode,
+    pub shape: EmitterShape,
+    /// RGBA over lifetime; replaces the start/end color lerp when present.
+    pub color_curve: ParticleCurve,
+    /// Multiplier on the start size; replaces the lerp to `end_size` when present.
+    pub size_curve: ParticleCurve,
+    /// World-space velocity added over lifetime.
+    pub velocity_curve: ParticleCurve,
+    pub texture_sheet: Option<TextureSheetParsed>,
+    /// View-space distance over which particles fade into geometry (0 = hard edges).
+    pub soft_distance: f32,
+    pub collision: ParticleCollisionMode,
+    pub plane_height: f32,
+    /// Fraction of normal velocity kept after a collision.
+    pub bounce: f32,
+    /// Fraction of tangential velocity removed by a collision.
+    pub friction: f32,
+}
+
+impl Default for ParticleEmitterParsed {
+    fn default() -> Self {
+        Self {
+            max_particles: 256,
+            emission_rate: 20.0,
+            burst_count: 0,
+            lifetime_min: 1.0,
+            lifetime_max: 2.0,
+            velocity_min: [-0.5, 1.0, -0.5],
+            velocity_max: [0.5, 3.0, 0.5],
+            gravity_modifier: 1.0,
+            damping: 0.0,
+            start_size_min: 0.1,
+            start_size_max: 0.3,
+            end_size: 0.0,
+            start_color: [1.0; 3],
+            end_color: [1.0; 3],
+            start_alpha: 1.0,
+            end_alpha: 0.0,
+            blend_mode: ParticleBlendMode::Alpha,
+            shape: EmitterShape::Point,
+            color_curve: ParticleCurve::default(),
+            size_curve: ParticleCurve::default(),
+            velocity_curve: ParticleCurve::default(),
+            texture_sheet: None,
+            soft_distance: 0.0,
+            collision: ParticleCollisionMode::None,
+            plane_height: 0.0,
+            bounce: 0.5,
+            friction: 0.1,
+        }
+    }
+}
+
+/// Source of an embedded gameplay script (see the `script` module).
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScriptParsed {
+    pub name: String,
+    pub source: String,
+}
+
+/// A script attached to an entity. `params` become globals of its instance.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScriptAttachmentParsed {
+    pub entity_index: usize,
+    /// Index into `scripts`.
+    pub script_index: usize,
+    pub params: Vec<(String, f64)>,
+}
+
+/// Entity sub-graph stored for runtime instantiation instead of being part
+/// of the scene. Its entities are the contiguous range
+/// `first_entity..first_entity + entity_count`, root first.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrefabParsed {
+    pub name: String,
+    pub first_entity: usize,
+    pub entity_count: usize,
+}
+
+/// One level of an LOD chain: the mesh to draw up to `max_distance` from the camera.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LodLevelParsed {
+    pub mesh_index: usize,
+    pub max_distance: f32,
+}
+
+/// LOD chain of an entity, levels sorted finest to coarsest.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LodParsed {
+    pub entity_index: usize,
+    pub hysteresis: f32,
+    pub levels: Vec<LodLevelParsed>,
+}
+
+/// Decal projected by an entity's transform, matching Julia's `DecalComponent`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DecalParsed {
+    pub entity_index: usize,
+    /// Indices into `textures`, if any.
+    pub albedo_texture: Option<u32>,
+    pub normal_texture: Option<u32>,
+    pub params: DecalParams,
+}
+
+/// Parsed morph target (blend shape): per-vertex deltas added to the base mesh.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MorphTargetParsed {
+    pub position_deltas: Vec<f32>,
+    /// Empty when the target does not affect normals.
+    pub normal_deltas: Vec<f32>,
+}
+
 /// Parsed mesh data.
 #[derive(Clone, Debug)]
 pub struct MeshParsed {
@@ -302,6 +651,7 @@ pub struct MeshParsed {
     pub indices: Vec<u32>,
     pub bone_weights: Option<Vec<f32>>,
     pub bone_indices: Option<Vec<u16>>,
+    pub morph_targets: Vec<MorphTargetParsed>,
 }
 
 /// Parsed texture data.
@@ -323,17 +673,42 @@ pub struct ParsedScene {
     pub component_masks: Vec<ComponentMask>,
     pub mesh_indices: Vec<Option<usize>>,
     pub material_indices: Vec<Option<usize>>,
+    /// Index into `cameras`.
+    pub camera_indices: Vec<Option<usize>>,
+    /// Index into `point_lights`, `dir_lights`, `spot_lights` or `area_lights`,
+    /// depending on the component mask.
+    pub light_indices: Vec<Option<usize>>,
+    pub collider_indices: Vec<Option<usize>>,
+    pub rigidbody_indices: Vec<Option<usize>>,
+    pub animation_indices: Vec<Option<usize>>,
+    pub skeleton_indices: Vec<Option<usize>>,
+    pub particle_indices: Vec<Option<usize>>,
     pub transforms: Vec<TransformData>,
     pub meshes: Vec<MeshParsed>,
     pub materials: Vec<MaterialData>,
     pub textures: Vec<TextureParsed>,
     pub point_lights: Vec<PointLightParsed>,
     pub dir_lights: Vec<DirLightParsed>,
+    pub spot_lights: Vec<SpotLightParsed>,
+    pub area_lights: Vec<AreaLightParsed>,
     pub cameras: Vec<CameraParsed>,
     pub colliders: Vec<ColliderParsed>,
     pub rigidbodies: Vec<RigidBodyData>,
     pub animations: Vec<AnimationParsed>,
     pub physics_config: Option<PhysicsConfigData>,
+    pub state_machines: Vec<AnimStateMachineParsed>,
+    /// Indexed by `particle_indices`.
+    pub particle_emitters: Vec<ParticleEmitterParsed>,
+    /// Per-entity names; empty for unnamed entities.
+    pub entity_names: Vec<String>,
+    pub scripts: Vec<ScriptParsed>,
+    pub script_attachments: Vec<ScriptAttachmentParsed>,
+    pub prefabs: Vec<PrefabParsed>,
+    pub lods: Vec<LodParsed>,
+    pub atmosphere: Option<AtmosphereSettings>,
+    pub fog: Option<FogSettings>,
+    /// In draw order.
+    pub decals: Vec<DecalParsed>,
 }
 
 // ── Cursor-based binary reader helpers ──
@@ -437,17 +812,22 @@ impl<'a> Cursor<'a> {
     }
 }
 
+/// Convert a serialized u32 index (u32::MAX = none) into an optional index.
+fn optional_index(idx: u32) -> Option<usize> {
+    if idx == u32::MAX { None } else { Some(idx as usize) }
+}
+
 /// Parse an ORSB header from raw bytes.
 pub fn parse_header(data: &[u8]) -> Option<OrsbHeader> {
     if data.len() < 32 {
         return None;
     }
-    if &data[0..4] != &ORSB_MAGIC {
+    if data[0..4] != ORSB_MAGIC {
         return None;
     }
 
     let version = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
-    if version != ORSB_VERSION {
+    if !(ORSB_MIN_VERSION..=ORSB_VERSION).contains(&version) {
         return None;
     }
 
@@ -474,12 +854,19 @@ pub fn parse_orsb(data: &[u8]) -> Result<ParsedScene, String> {
     let mut c = Cursor::new(data);
     c.skip(32); // past header
 
-    // ── Entity graph (28 bytes per entity) ──
+    // ── Entity graph (28 bytes per entity in v1, 56 bytes in v2) ──
     let mut entity_ids = Vec::with_capacity(num_entities);
     let mut parent_indices = Vec::with_capacity(num_entities);
     let mut component_masks = Vec::with_capacity(num_entities);
     let mut mesh_indices = Vec::with_capacity(num_entities);
     let mut material_indices = Vec::with_capacity(num_entities);
+    let mut camera_indices = Vec::with_capacity(num_entities);
+    let mut light_indices = Vec::with_capacity(num_entities);
+    let mut collider_indices = Vec::with_capacity(num_entities);
+    let mut rigidbody_indices = Vec::with_capacity(num_entities);
+    let mut animation_indices = Vec::with_capacity(num_entities);
+    let mut skeleton_indices = Vec::with_capacity(num_entities);
+    let mut particle_indices = Vec::with_capacity(num_entities);
 
     for _ in 0..num_entities {
         let eid = c.read_u64().ok_or("Truncated entity graph")?;
@@ -489,10 +876,28 @@ pub fn parse_orsb(data: &[u8]) -> Result<ParsedScene, String> {
         let mat_idx = c.read_u32().ok_or("Truncated entity graph")?;
 
         entity_ids.push(eid);
-        parent_indices.push(if parent == u32::MAX { None } else { Some(parent as usize) });
+        parent_indices.push(optional_index(parent));
         component_masks.push(ComponentMask(mask));
-        mesh_indices.push(if mesh_idx == u32::MAX { None } else { Some(mesh_idx as usize) });
-        material_indices.push(if mat_idx == u32::MAX { None } else { Some(mat_idx as usize) });
+        mesh_indices.push(optional_index(mesh_idx));
+        material_indices.push(optional_index(mat_idx));
+
+        if header.version >= 2 {
+            camera_indices.push(optional_index(c.read_u32().ok_or("Truncated entity graph")?));
+            light_indices.push(optional_index(c.read_u32().ok_or("Truncated entity graph")?));
+            collider_indices.push(optional_index(c.read_u32().ok_or("Truncated entity graph")?));
+            rigidbody_indices.push(optional_index(c.read_u32().ok_or("Truncated entity graph")?));
+            animation_indices.push(optional_index(c.read_u32().ok_or("Truncated entity graph")?));
+            skeleton_indices.push(optional_index(c.read_u32().ok_or("Truncated entity graph")?));
+            particle_indices.push(optional_index(c.read_u32().ok_or("Truncated entity graph")?));
+        } else {
+            camera_indices.push(None);
+            light_indices.push(None);
+            collider_indices.push(None);
+            rigidbody_indices.push(None);
+            animation_indices.push(None);
+            skeleton_indices.push(None);
+            particle_indices.push(None);
+        }
     }
 
     // ── Transforms (80 bytes per entity) ──
@@ -521,7 +926,8 @@ pub fn parse_orsb(data: &[u8]) -> Result<ParsedScene, String> {
         let nv = c.read_u32().ok_or("Truncated mesh header")? as usize;
         let ni = c.read_u32().ok_or("Truncated mesh header")? as usize;
         let has_bones = c.read_u32().ok_or("Truncated mesh header")? != 0;
-        c.skip(4); // padding
+        // Formerly padding, always written as 0 before morph targets existed
+        let num_morph_targets = c.read_u32().ok_or("Truncated mesh header")? as usize;
 
         let mut positions = Vec::with_capacity(nv * 3);
         for _ in 0..nv * 3 {
@@ -557,7 +963,25 @@ pub fn parse_orsb(data: &[u8]) -> Result<ParsedScene, String> {
             (None, None)
         };
 
-        meshes.push(MeshParsed { positions, normals, uvs, indices, bone_weights, bone_indices });
+        // Morph targets: position deltas then normal deltas (has_normals u8 first)
+        let mut morph_targets = Vec::with_capacity(num_morph_targets);
+        for _ in 0..num_morph_targets {
+            let has_normals = c.read_u8().ok_or("Truncated morph target")? != 0;
+            let mut position_deltas = Vec::with_capacity(nv * 3);
+            for _ in 0..nv * 3 {
+                position_deltas.push(c.read_f32().ok_or("Truncated morph target")?);
+            }
+            let mut normal_deltas = Vec::new();
+            if has_normals {
+                normal_deltas.reserve(nv * 3);
+                for _ in 0..nv * 3 {
+                    normal_deltas.push(c.read_f32().ok_or("Truncated morph target")?);
+                }
+            }
+            morph_targets.push(MorphTargetParsed { position_deltas, normal_deltas });
+        }
+
+        meshes.push(MeshParsed { positions, normals, uvs, indices, bone_weights, bone_indices, morph_targets });
     }
 
     // ── Materials (96 bytes each) ──
@@ -633,6 +1057,8 @@ pub fn parse_orsb(data: &[u8]) -> Result<ParsedScene, String> {
     // ── Lights ──
     let mut point_lights = Vec::new();
     let mut dir_lights = Vec::new();
+    let mut spot_lights = Vec::new();
+    let mut area_lights = Vec::new();
     if c.remaining() >= 4 {
         let n_point = c.read_u32().unwrap() as usize;
         for _ in 0..n_point {
@@ -654,19 +1080,68 @@ pub fn parse_orsb(data: &[u8]) -> Result<ParsedScene, String> {
                 dir_lights.push(DirLightParsed { direction, color, intensity });
             }
         }
+        if header.version >= 3 && c.remaining() >= 4 {
+            let n_spot = c.read_u32().unwrap() as usize;
+            for _ in 0..n_spot {
+                if c.remaining() < 56 { break; }
+                let position = [c.read_f32().unwrap(), c.read_f32().unwrap(), c.read_f32().unwrap()];
+                let direction = [c.read_f32().unwrap(), c.read_f32().unwrap(), c.read_f32().unwrap()];
+                let color = [c.read_f32().unwrap(), c.read_f32().unwrap(), c.read_f32().unwrap()];
+                let intensity = c.read_f32().unwrap();
+                let range = c.read_f32().unwrap();
+                let inner_angle = c.read_f32().unwrap();
+                let outer_angle = c.read_f32().unwrap();
+                let casts_shadow = c.read_u8().unwrap() != 0;
+                c.skip(3); // padding
+                spot_lights.push(SpotLightParsed {
+                    position, direction, color, intensity, range, inner_angle, outer_angle, casts_shadow,
+                });
+            }
+        }
+        if header.version >= 3 && c.remaining() >= 4 {
+            let n_area = c.read_u32().unwrap() as usize;
+            for _ in 0..n_area {
+                if c.remaining() < 72 { break; }
+                let shape = match c.read_u8().unwrap() {
+                    1 => AreaLightShape::Tube,
+                    _ => AreaLightShape::Rect,
+                };
+                c.skip(3); // padding
+                let position = [c.read_f32().unwrap(), c.read_f32().unwrap(), c.read_f32().unwrap()];
+                let direction = [c.read_f32().unwrap(), c.read_f32().unwrap(), c.read_f32().unwrap()];
+                let tangent = [c.read_f32().unwrap(), c.read_f32().unwrap(), c.read_f32().unwrap()];
+                let color = [c.read_f32().unwrap(), c.read_f32().unwrap(), c.read_f32().unwrap()];
+                let intensity = c.read_f32().unwrap();
+                let range = c.read_f32().unwrap();
+                let width = c.read_f32().unwrap();
+                let height = c.read_f32().unwrap();
+                let radius = c.read_f32().unwrap();
+                area_lights.push(AreaLightParsed {
+                    shape, position, direction, tangent, color, intensity, range, width, height, radius,
+                });
+            }
+        }
     }
 
     // ── Cameras ──
     let mut cameras = Vec::new();
     if c.remaining() >= 4 {
         let n_cam = c.read_u32().unwrap() as usize;
+        let cam_size = if header.version >= 2 { 20 } else { 16 };
         for _ in 0..n_cam {
-            if c.remaining() < 16 { break; }
+            if c.remaining() < cam_size { break; }
             let fov = c.read_f32().unwrap();
             let near = c.read_f32().unwrap();
             let far = c.read_f32().unwrap();
             let aspect = c.read_f32().unwrap();
-            cameras.push(CameraParsed { fov, near, far, aspect });
+            let active = if header.version >= 2 {
+                let active = c.read_u8().unwrap() != 0;
+                c.skip(3); // padding
+                active
+            } else {
+                true
+            };
+            cameras.push(CameraParsed { fov, near, far, aspect, active });
         }
     }
 
@@ -730,6 +1205,7 @@ pub fn parse_orsb(data: &[u8]) -> Result<ParsedScene, String> {
                     let target_property = match prop_byte {
                         0 => TargetProperty::Position,
                         1 => TargetProperty::Rotation,
+                        3 => TargetProperty::MorphWeights,
                         _ => TargetProperty::Scale,
                     };
                     let interpolation = match interp_byte {
@@ -738,12 +1214,20 @@ pub fn parse_orsb(data: &[u8]) -> Result<ParsedScene, String> {
                         _ => InterpolationMode::CubicSpline,
                     };
 
+                    // Weight channels carry their target count before the keys
+                    let components = match target_property {
+                        TargetProperty::Rotation => 4,
+                        TargetProperty::MorphWeights => c.read_u32().ok_or("Truncated channel")? as usize,
+                        TargetProperty::Position | TargetProperty::Scale => 3,
+                    };
+
                     let mut times = Vec::with_capacity(keyframe_count);
                     for _ in 0..keyframe_count {
                         times.push(c.read_f32().ok_or("Truncated keyframe times")?);
                     }
 
-                    let vals_per_key = if target_property == TargetProperty::Rotation { 4 } else { 3 };
+                    // Cubic splines carry in-tangent, value and out-tangent per key
+                    let vals_per_key = components * crate::animation::values_per_key(interpolation);
                     let mut values = Vec::with_capacity(keyframe_count * vals_per_key);
                     for _ in 0..keyframe_count * vals_per_key {
                         values.push(c.read_f64().ok_or("Truncated keyframe values")?);
@@ -784,6 +1268,132 @@ pub fn parse_orsb(data: &[u8]) -> Result<ParsedScene, String> {
         None
     };
 
+    // ── Animation state machines ──
+    let mut state_machines = Vec::new();
+    if c.remaining() >= 4 {
+        let n = c.read_u32().unwrap_or(0) as usize;
+        for _ in 0..n {
+            state_machines.push(parse_state_machine(&mut c).ok_or("Truncated animation state machine")?);
+        }
+    }
+
+    // ── Particle emitters ──
+    let mut particle_emitters = Vec::new();
+    if c.remaining() >= 4 {
+        let n = c.read_u32().unwrap_or(0) as usize;
+        for _ in 0..n {
+            particle_emitters.push(parse_particle_emitter(&mut c).ok_or("Truncated particle emitter")?);
+        }
+    }
+
+    // ── Entity names: (entity index, name) for named entities only ──
+    let mut entity_names = vec![String::new(); num_entities];
+    if c.remaining() >= 4 {
+        let n = c.read_u32().unwrap_or(0) as usize;
+        for _ in 0..n {
+            let index = c.read_u32().ok_or("Truncated entity name")? as usize;
+            let name = read_name(&mut c).ok_or("Truncated entity name")?;
+            if let Some(slot) = entity_names.get_mut(index) {
+                *slot = name;
+            }
+        }
+    }
+
+    // ── Scripts: deduplicated sources, then per-entity attachments ──
+    let mut scripts = Vec::new();
+    let mut script_attachments = Vec::new();
+    if c.remaining() >= 4 {
+        let n = c.read_u32().unwrap_or(0) as usize;
+        for _ in 0..n {
+            scripts.push(parse_script(&mut c).ok_or("Truncated script")?);
+        }
+        let n = c.read_u32().ok_or("Truncated script attachments")? as usize;
+        for _ in 0..n {
+            let attachment = parse_script_attachment(&mut c).ok_or("Truncated script attachment")?;
+            if attachment.entity_index >= num_entities {
+                return Err(format!("Script attachment entity {} out of range", attachment.entity_index));
+            }
+            if attachment.script_index >= scripts.len() {
+                return Err(format!("Script attachment script {} out of range", attachment.script_index));
+            }
+            script_attachments.push(attachment);
+        }
+    }
+
+    // ── Prefabs: named entity ranges held back for runtime instantiation ──
+    let mut prefabs = Vec::new();
+    if c.remaining() >= 4 {
+        let n = c.read_u32().unwrap_or(0) as usize;
+        for _ in 0..n {
+            let name = read_name(&mut c).ok_or("Truncated prefab")?;
+            let first_entity = c.read_u32().ok_or("Truncated prefab")? as usize;
+            let entity_count = c.read_u32().ok_or("Truncated prefab")? as usize;
+            let end = first_entity.checked_add(entity_count);
+            if entity_count == 0 || end.is_none_or(|end| end > num_entities) {
+                return Err(format!("Prefab '{name}' entity range out of bounds"));
+            }
+            prefabs.push(PrefabParsed { name, first_entity, entity_count });
+        }
+    }
+
+    // ── LOD chains: per-entity meshes by camera distance ──
+    let mut lods = Vec::new();
+    if c.remaining() >= 4 {
+        let n = c.read_u32().unwrap_or(0) as usize;
+        for _ in 0..n {
+            let entity_index = c.read_u32().ok_or("Truncated LOD chain")? as usize;
+            let hysteresis = c.read_f32().ok_or("Truncated LOD chain")?;
+            let num_levels = c.read_u16().ok_or("Truncated LOD chain")? as usize;
+            let mut levels = Vec::with_capacity(num_levels);
+            for _ in 0..num_levels {
+                let mesh_index = c.read_u32().ok_or("Truncated LOD level")? as usize;
+                let max_distance = c.read_f32().ok_or("Truncated LOD level")?;
+                if mesh_index >= header.num_meshes as usize {
+                    return Err(format!("LOD level mesh {mesh_index} out of range"));
+                }
+                levels.push(LodLevelParsed { mesh_index, max_distance });
+            }
+            if entity_index >= num_entities {
+                return Err(format!("LOD chain entity {entity_index} out of range"));
+            }
+            lods.push(LodParsed { entity_index, hysteresis, levels });
+        }
+    }
+
+    // ── Environment: sky atmosphere and volumetric fog ──
+    let (mut atmosphere, mut fog) = (None, None);
+    if c.remaining() >= 4 {
+        let flags = c.read_u32().unwrap_or(0);
+        if flags & ENVIRONMENT_ATMOSPHERE != 0 {
+            let settings = parse_atmosphere(&mut c).ok_or("Truncated atmosphere settings")?;
+            settings.validate()?;
+            atmosphere = Some(settings);
+        }
+        if flags & ENVIRONMENT_FOG != 0 {
+            let settings = parse_fog(&mut c).ok_or("Truncated fog settings")?;
+            settings.validate()?;
+            fog = Some(settings);
+        }
+    }
+
+    // ── Decals: boxes projected onto the G-buffer by entity transforms ──
+    let mut decals = Vec::new();
+    if c.remaining() >= 4 {
+        let n = c.read_u32().unwrap_or(0) as usize;
+        for _ in 0..n {
+            let decal = parse_decal(&mut c).ok_or("Truncated decal")?;
+            if decal.entity_index >= num_entities {
+                return Err(format!("Decal entity {} out of range", decal.entity_index));
+            }
+            let textures = [decal.albedo_texture, decal.normal_texture];
+            if textures.into_iter().flatten().any(|t| t >= header.num_textures) {
+                return Err("Decal texture out of range".into());
+            }
+            decal.params.validate()?;
+            decals.push(decal);
+        }
+    }
+
     Ok(ParsedScene {
         header,
         entity_ids,
@@ -791,17 +1401,298 @@ pub fn parse_orsb(data: &[u8]) -> Result<ParsedScene, String> {
         component_masks,
         mesh_indices,
         material_indices,
+        camera_indices,
+        light_indices,
+        collider_indices,
+        rigidbody_indices,
+        animation_indices,
+        skeleton_indices,
+        particle_indices,
         transforms,
         meshes,
         materials,
         textures,
         point_lights,
         dir_lights,
+        spot_lights,
+        area_lights,
         cameras,
         colliders,
         rigidbodies,
         animations,
         physics_config,
+        state_machines,
+        particle_emitters,
+        entity_names,
+        scripts,
+        script_attachments,
+        prefabs,
+        lods,
+        atmosphere,
+        fog,
+        decals,
+    })
+}
+
+fn read_name(c: &mut Cursor) -> Option<String> {
+    let len = c.read_u16()? as usize;
+    Some(String::from_utf8_lossy(c.read_bytes(len)?).to_string())
+}
+
+fn parse_script(c: &mut Cursor) -> Option<ScriptParsed> {
+    let name = read_name(c)?;
+    let len = c.read_u32()? as usize;
+    let source = String::from_utf8_lossy(c.read_bytes(len)?).to_string();
+    Some(ScriptParsed { name, source })
+}
+
+fn parse_script_attachment(c: &mut Cursor) -> Option<ScriptAttachmentParsed> {
+    let entity_index = c.read_u32()? as usize;
+    let script_index = c.read_u32()? as usize;
+    let num_params = c.read_u16()? as usize;
+    let mut params = Vec::with_capacity(num_params);
+    for _ in 0..num_params {
+        params.push((read_name(c)?, c.read_f64()?));
+    }
+    Some(ScriptAttachmentParsed { entity_index, script_index, params })
+}
+
+fn parse_state_machine(c: &mut Cursor) -> Option<AnimStateMachineParsed> {
+    let animation_index = c.read_u32()?;
+
+    let num_params = c.read_u16()? as usize;
+    let mut parameters = Vec::with_capacity(num_params);
+    for _ in 0..num_params {
+        let name = read_name(c)?;
+        let kind = match c.read_u8()? {
+            0 => AnimParamKind::Float,
+            1 => AnimParamKind::Bool,
+            _ => AnimParamKind::Trigger,
+        };
+        let default = c.read_f32()?;
+        parameters.push(AnimParameterParsed { name, kind, default });
+    }
+
+    let num_layers = c.read_u16()? as usize;
+    let mut layers = Vec::with_capacity(num_layers);
+    for _ in 0..num_layers {
+        let name = read_name(c)?;
+        let weight = c.read_f32()?;
+        let blend_mode = if c.read_u8()? == 1 { LayerBlendMode::Additive } else { LayerBlendMode::Override };
+
+        let mask_len = c.read_u32()? as usize;
+        let mut mask = Vec::with_capacity(mask_len);
+        for _ in 0..mask_len {
+            mask.push(c.read_u32()?);
+        }
+
+        let num_states = c.read_u16()? as usize;
+        let mut states = Vec::with_capacity(num_states);
+        for _ in 0..num_states {
+            let name = read_name(c)?;
+            let clip = c.read_u32()?;
+            let speed = c.read_f32()?;
+            let looping = c.read_u8()? != 0;
+            states.push(AnimStateParsed { name, clip, speed, looping });
+        }
+        let default_state = c.read_u16()?;
+
+        let num_transitions = c.read_u16()? as usize;
+        let mut transitions = Vec::with_capacity(num_transitions);
+        for _ in 0..num_transitions {
+            let from = c.read_u16()?;
+            let to = c.read_u16()?;
+            let duration = c.read_f32()?;
+            let exit_time = c.read_f32()?;
+            let num_conditions = c.read_u16()? as usize;
+            let mut conditions = Vec::with_capacity(num_conditions);
+            for _ in 0..num_conditions {
+                let parameter = c.read_u16()?;
+                let op = match c.read_u8()? {
+                    0 => ConditionOp::Greater,
+                    1 => ConditionOp::Less,
+                    2 => ConditionOp::Equals,
+                    3 => ConditionOp::NotEquals,
+                    4 => ConditionOp::True,
+                    5 => ConditionOp::False,
+                    _ => ConditionOp::Trigger,
+                };
+                let threshold = c.read_f32()?;
+                conditions.push(AnimConditionParsed { parameter, op, threshold });
+            }
+            transitions.push(AnimTransitionParsed {
+                from: (from != u16::MAX).then_some(from),
+                to,
+                duration,
+                exit_time: (exit_time >= 0.0).then_some(exit_time),
+                conditions,
+            });
+        }
+
+        layers.push(AnimLayerParsed { name, weight, blend_mode, mask, states, default_state, transitions });
+    }
+
+    Some(AnimStateMachineParsed { animation_index, parameters, layers })
+}
+
+fn read_vec3(c: &mut Cursor) -> Option<[f32; 3]> {
+    Some([c.read_f32()?, c.read_f32()?, c.read_f32()?])
+}
+
+fn parse_atmosphere(c: &mut Cursor) -> Option<AtmosphereSettings> {
+    Some(AtmosphereSettings {
+        rayleigh_scattering: read_vec3(c)?,
+        rayleigh_scale_height: c.read_f32()?,
+        mie_scattering: c.read_f32()?,
+        mie_absorption: c.read_f32()?,
+        mie_scale_height: c.read_f32()?,
+        mie_anisotropy: c.read_f32()?,
+        ozone_absorption: read_vec3(c)?,
+        planet_radius: c.read_f32()?,
+        atmosphere_height: c.read_f32()?,
+        ground_albedo: c.read_f32()?,
+        sun_angular_radius: c.read_f32()?,
+        intensity: c.read_f32()?,
+        enabled: c.read_u32()?,
+    })
+}
+
+fn parse_fog(c: &mut Cursor) -> Option<FogSettings> {
+    Some(FogSettings {
+        albedo: read_vec3(c)?,
+        density: c.read_f32()?,
+        height_density: c.read_f32()?,
+        height_falloff: c.read_f32()?,
+        base_height: c.read_f32()?,
+        anisotropy: c.read_f32()?,
+        max_distance: c.read_f32()?,
+        ambient_intensity: c.read_f32()?,
+        enabled: c.read_u32()?,
+    })
+}
+
+fn parse_decal(c: &mut Cursor) -> Option<DecalParsed> {
+    let entity_index = c.read_u32()? as usize;
+    let albedo_texture = c.read_i32()?;
+    let normal_texture = c.read_i32()?;
+    Some(DecalParsed {
+        entity_index,
+        albedo_texture: (albedo_texture >= 0).then_some(albedo_texture as u32),
+        normal_texture: (normal_texture >= 0).then_some(normal_texture as u32),
+        params: DecalParams {
+            color: [c.read_f32()?, c.read_f32()?, c.read_f32()?, c.read_f32()?],
+            normal_strength: c.read_f32()?,
+            roughness: c.read_f32()?,
+            roughness_opacity: c.read_f32()?,
+            angle_fade: c.read_f32()?,
+        },
+    })
+}
+
+fn read_particle_curve(c: &mut Cursor, components: usize) -> Option<ParticleCurve> {
+    let n = c.read_u16()? as usize;
+    let mut curve = ParticleCurve { times: Vec::with_capacity(n), values: Vec::with_capacity(n * components) };
+    for _ in 0..n {
+        curve.times.push(c.read_f32()?);
+        for _ in 0..components {
+            curve.values.push(c.read_f32()?);
+        }
+    }
+    Some(curve)
+}
+
+fn parse_particle_emitter(c: &mut Cursor) -> Option<ParticleEmitterParsed> {
+    let max_particles = c.read_u32()?;
+    let emission_rate = c.read_f32()?;
+    let burst_count = c.read_u32()?;
+    let lifetime_min = c.read_f32()?;
+    let lifetime_max = c.read_f32()?;
+    let velocity_min = read_vec3(c)?;
+    let velocity_max = read_vec3(c)?;
+    let gravity_modifier = c.read_f32()?;
+    let damping = c.read_f32()?;
+    let start_size_min = c.read_f32()?;
+    let start_size_max = c.read_f32()?;
+    let end_size = c.read_f32()?;
+    let start_color = read_vec3(c)?;
+    let start_alpha = c.read_f32()?;
+    let end_color = read_vec3(c)?;
+    let end_alpha = c.read_f32()?;
+
+    let blend_mode = match c.read_u8()? {
+        1 => ParticleBlendMode::Additive,
+        2 => ParticleBlendMode::Premultiplied,
+        _ => ParticleBlendMode::Alpha,
+    };
+    let shape_kind = c.read_u8()?;
+    let collision = match c.read_u8()? {
+        1 => ParticleCollisionMode::GroundPlane,
+        2 => ParticleCollisionMode::DepthBuffer,
+        _ => ParticleCollisionMode::None,
+    };
+    let shape_flags = c.read_u8()?;
+    let shape_params = [c.read_f32()?, c.read_f32()?, c.read_f32()?, c.read_f32()?];
+    let shape_mesh = c.read_u32()?;
+    let shape = match shape_kind {
+        1 => EmitterShape::Sphere { radius: shape_params[0], surface_only: shape_flags & 1 != 0 },
+        2 => EmitterShape::Cone { angle: shape_params[0], radius: shape_params[1] },
+        3 => EmitterShape::Box { half_extents: [shape_params[0], shape_params[1], shape_params[2]] },
+        // A mesh emitter whose mesh was not exported degrades to a point
+        4 if shape_mesh != u32::MAX => EmitterShape::Mesh { mesh: shape_mesh },
+        _ => EmitterShape::Point,
+    };
+
+    let color_curve = read_particle_curve(c, 4)?;
+    let size_curve = read_particle_curve(c, 1)?;
+    let velocity_curve = read_particle_curve(c, 3)?;
+
+    let sheet_texture = c.read_i32()?;
+    let tiles_x = c.read_u16()?;
+    let tiles_y = c.read_u16()?;
+    let frame_count = c.read_u16()?;
+    c.skip(2); // padding
+    let fps = c.read_f32()?;
+    let texture_sheet = (sheet_texture >= 0 || tiles_x as u32 * tiles_y as u32 > 1).then(|| TextureSheetParsed {
+        texture: (sheet_texture >= 0).then_some(sheet_texture as u32),
+        tiles_x: tiles_x.max(1),
+        tiles_y: tiles_y.max(1),
+        frame_count: frame_count.max(1),
+        fps,
+    });
+
+    let soft_distance = c.read_f32()?;
+    let plane_height = c.read_f32()?;
+    let bounce = c.read_f32()?;
+    let friction = c.read_f32()?;
+
+    Some(ParticleEmitterParsed {
+        max_particles,
+        emission_rate,
+        burst_count,
+        lifetime_min,
+        lifetime_max,
+        velocity_min,
+        velocity_max,
+        gravity_modifier,
+        damping,
+        start_size_min,
+        start_size_max,
+        end_size,
+        start_color,
+        end_color,
+        start_alpha,
+        end_alpha,
+        blend_mode,
+        shape,
+        color_curve,
+        size_curve,
+        velocity_curve,
+        texture_sheet,
+        soft_distance,
+        collision,
+        plane_height,
+        bounce,
+        friction,
     })
 }
 
@@ -809,11 +1700,21 @@ pub fn parse_orsb(data: &[u8]) -> Result<ParsedScene, String> {
 mod tests {
     use super::*;
 
-    /// Build a minimal valid ORSB binary with the given counts.
+    /// Build a minimal valid version 1 ORSB binary with the given counts.
     fn build_header(num_entities: u32, num_meshes: u32, num_textures: u32, num_materials: u32) -> Vec<u8> {
+        build_header_version(1, num_entities, num_meshes, num_textures, num_materials)
+    }
+
+    fn build_header_version(
+        version: u32,
+        num_entities: u32,
+        num_meshes: u32,
+        num_textures: u32,
+        num_materials: u32,
+    ) -> Vec<u8> {
         let mut buf = Vec::new();
         buf.extend_from_slice(b"ORSB");
-        buf.extend_from_slice(&1u32.to_le_bytes());   // version
+        buf.extend_from_slice(&version.to_le_bytes());
         buf.extend_from_slice(&0u32.to_le_bytes());    // flags
         buf.extend_from_slice(&num_entities.to_le_bytes());
         buf.extend_from_slice(&num_meshes.to_le_bytes());
@@ -831,6 +1732,17 @@ mod tests {
         buf.extend_from_slice(&mat.to_le_bytes());
     }
 
+    /// Write a version 2 entity record: v1 fields followed by camera, light,
+    /// collider, rigidbody, animation, skeleton and particle indices.
+    fn write_entity_v2(buf: &mut Vec<u8>, id: u64, parent: u32, mask: u64, indices: [u32; 9]) {
+        buf.extend_from_slice(&id.to_le_bytes());
+        buf.extend_from_slice(&parent.to_le_bytes());
+        buf.extend_from_slice(&mask.to_le_bytes());
+        for idx in indices {
+            buf.extend_from_slice(&idx.to_le_bytes());
+        }
+    }
+
     fn write_transform(buf: &mut Vec<u8>, px: f64, py: f64, pz: f64) {
         // position
         buf.extend_from_slice(&px.to_le_bytes());
@@ -945,7 +1857,7 @@ mod tests {
         data.extend_from_slice(&3u32.to_le_bytes());
         data.extend_from_slice(&3u32.to_le_bytes());
         data.extend_from_slice(&0u32.to_le_bytes()); // no bones
-        data.extend_from_slice(&0u32.to_le_bytes()); // padding
+        data.extend_from_slice(&0u32.to_le_bytes()); // no morph targets
 
         // Positions (3 verts * 3 floats)
         for v in &[0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.5, 1.0, 0.0] {
@@ -1029,4 +1941,532 @@ mod tests {
         assert_eq!(scene.dir_lights[0].direction[1], -1.0);
         assert_eq!(scene.dir_lights[0].intensity, 5.0);
     }
+
+    #[test]
+    fn test_parse_orsb_v3_spot_and_area_lights() {
+        let mut data = build_header_version(3, 0, 0, 0, 0);
+        data.extend_from_slice(&0u32.to_le_bytes()); // 0 point lights
+        data.extend_from_slice(&0u32.to_le_bytes()); // 0 dir lights
+        // 1 spot light: position, direction, color, intensity, range, inner, outer
+        data.extend_from_slice(&1u32.to_le_bytes());
+        for v in [0.0f32, 4.0, 0.0, 0.0, -1.0, 0.0, 1.0, 0.9, 0.8, 20.0, 15.0, 0.3, 0.5] {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        data.extend_from_slice(&[1u8, 0, 0, 0]); // casts shadow + padding
+        // 1 tube light: position, direction, tangent, color, intensity, range, width, height, radius
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&[1u8, 0, 0, 0]);
+        for v in [1.0f32, 2.0, 3.0, 0.0, -1.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0, 8.0, 10.0, 2.0, 0.0, 0.05] {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        // 1 camera follows the lights section
+        data.extend_from_slice(&1u32.to_le_bytes());
+        for v in [60.0f32, 0.1, 100.0, 1.0] {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        data.extend_from_slice(&[1u8, 0, 0, 0]);
+
+        let scene = parse_orsb(&data).unwrap();
+        assert_eq!(scene.spot_lights.len(), 1);
+        let spot = &scene.spot_lights[0];
+        assert_eq!(spot.position, [0.0, 4.0, 0.0]);
+        assert_eq!(spot.direction, [0.0, -1.0, 0.0]);
+        assert_eq!(spot.inner_angle, 0.3);
+        assert_eq!(spot.outer_angle, 0.5);
+        assert!(spot.casts_shadow);
+        assert_eq!(scene.area_lights.len(), 1);
+        let area = &scene.area_lights[0];
+        assert_eq!(area.shape, AreaLightShape::Tube);
+        assert_eq!(area.tangent, [1.0, 0.0, 0.0]);
+        assert_eq!(area.width, 2.0);
+        assert_eq!(area.radius, 0.05);
+        assert_eq!(scene.cameras.len(), 1);
+        assert_eq!(scene.cameras[0].fov, 60.0);
+    }
+
+    #[test]
+    fn test_parse_orsb_v2_component_indices() {
+        const NONE: u32 = u32::MAX;
+        let mut data = build_header_version(2, 2, 0, 0, 0);
+        // Camera entity
+        write_entity_v2(
+            &mut data, 1, NONE, ComponentMask::TRANSFORM | ComponentMask::CAMERA,
+            [NONE, NONE, 0, NONE, NONE, NONE, NONE, NONE, NONE],
+        );
+        // Point light with a collider, rigidbody and particle emitter
+        write_entity_v2(
+            &mut data, 2, 0,
+            ComponentMask::TRANSFORM | ComponentMask::POINT_LIGHT | ComponentMask::COLLIDER
+                | ComponentMask::RIGIDBODY | ComponentMask::PARTICLE,
+            [NONE, NONE, NONE, 0, 0, 0, NONE, NONE, 3],
+        );
+        write_transform(&mut data, 0.0, 1.0, 5.0);
+        write_transform(&mut data, 0.0, 0.0, 0.0);
+        data.extend_from_slice(&0u32.to_le_bytes()); // 0 point lights
+        data.extend_from_slice(&0u32.to_le_bytes()); // 0 dir lights
+        // 1 camera (v2 records carry an active flag)
+        data.extend_from_slice(&1u32.to_le_bytes());
+        for v in [75.0f32, 0.5, 500.0, 1.5] {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        data.extend_from_slice(&[0u8, 0, 0, 0]); // inactive + padding
+        data.extend_from_slice(&0u32.to_le_bytes()); // 0 colliders
+        data.extend_from_slice(&0u32.to_le_bytes()); // 0 rigidbodies
+        data.extend_from_slice(&0u32.to_le_bytes()); // 0 animations
+
+        let scene = parse_orsb(&data).unwrap();
+        assert_eq!(scene.camera_indices, vec![Some(0), None]);
+        assert_eq!(scene.light_indices, vec![None, Some(0)]);
+        assert_eq!(scene.collider_indices, vec![None, Some(0)]);
+        assert_eq!(scene.rigidbody_indices, vec![None, Some(0)]);
+        assert_eq!(scene.animation_indices, vec![None, None]);
+        assert_eq!(scene.skeleton_indices, vec![None, None]);
+        assert_eq!(scene.particle_indices, vec![None, Some(3)]);
+        assert_eq!(scene.parent_indices[1], Some(0));
+        assert_eq!(scene.transforms[0].position, [0.0, 1.0, 5.0]);
+        assert_eq!(scene.cameras.len(), 1);
+        assert_eq!(scene.cameras[0].fov, 75.0);
+        assert!(!scene.cameras[0].active);
+    }
+
+    #[test]
+    fn test_parse_orsb_v1_has_no_component_indices() {
+        let mut data = build_header(1, 0, 0, 0);
+        write_entity(&mut data, 7, u32::MAX, ComponentMask::TRANSFORM | ComponentMask::CAMERA, u32::MAX, u32::MAX);
+        write_transform(&mut data, 0.0, 0.0, 0.0);
+        data.extend_from_slice(&0u32.to_le_bytes()); // 0 point lights
+        data.extend_from_slice(&0u32.to_le_bytes()); // 0 dir lights
+        data.extend_from_slice(&1u32.to_le_bytes()); // 1 camera (16 bytes in v1)
+        for v in [60.0f32, 0.1, 1000.0, 1.0] {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        data.extend_from_slice(&0u32.to_le_bytes()); // 0 colliders
+        data.extend_from_slice(&0u32.to_le_bytes()); // 0 rigidbodies
+        data.extend_from_slice(&0u32.to_le_bytes()); // 0 animations
+
+        let scene = parse_orsb(&data).unwrap();
+        assert_eq!(scene.camera_indices, vec![None]);
+        assert_eq!(scene.cameras.len(), 1);
+        assert!(scene.cameras[0].active);
+    }
+
+    #[test]
+    fn test_parse_orsb_cubic_spline_channel() {
+        let mut data = build_header(1, 0, 0, 0);
+        write_entity(&mut data, 1, u32::MAX, ComponentMask::TRANSFORM | ComponentMask::ANIMATION, u32::MAX, u32::MAX);
+        write_transform(&mut data, 0.0, 0.0, 0.0);
+        for _ in 0..5 {
+            data.extend_from_slice(&0u32.to_le_bytes()); // lights, cameras, colliders, rigidbodies
+        }
+
+        // One animation, one clip, one cubic position channel with 2 keys
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&4u16.to_le_bytes());
+        data.extend_from_slice(b"move");
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&1.0f32.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes()); // target entity
+        data.push(0); // position
+        data.push(2); // cubic spline
+        data.extend_from_slice(&2u32.to_le_bytes());
+        data.extend_from_slice(&0.0f32.to_le_bytes());
+        data.extend_from_slice(&1.0f32.to_le_bytes());
+        for i in 0..18 {
+            data.extend_from_slice(&(i as f64).to_le_bytes());
+        }
+        data.extend_from_slice(&0i32.to_le_bytes());
+        data.push(1);
+        data.push(1);
+        data.extend_from_slice(&1.0f32.to_le_bytes());
+
+        // Physics config must still line up after the tangents
+        for g in [0.0f64, -9.81, 0.0, 1.0 / 60.0] {
+            data.extend_from_slice(&g.to_le_bytes());
+        }
+        data.extend_from_slice(&4u32.to_le_bytes());
+        data.extend_from_slice(&10u32.to_le_bytes());
+        data.extend_from_slice(&0.2f32.to_le_bytes());
+        data.extend_from_slice(&0.01f32.to_le_bytes());
+
+        let scene = parse_orsb(&data).unwrap();
+        let channel = &scene.animations[0].clips[0].channels[0];
+        assert_eq!(channel.interpolation, InterpolationMode::CubicSpline);
+        assert_eq!(channel.values.len(), 18);
+        assert_eq!(channel.values[17], 17.0);
+        let physics = scene.physics_config.unwrap();
+        assert_eq!(physics.gravity[1], -9.81);
+        assert_eq!(physics.solver_iterations, 10);
+    }
+
+    fn write_name(buf: &mut Vec<u8>, name: &str) {
+        buf.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        buf.extend_from_slice(name.as_bytes());
+    }
+
+    #[test]
+    fn test_parse_orsb_state_machine() {
+        let mut data = build_header(0, 0, 0, 0);
+        write_empty_trailing(&mut data);
+        data.extend_from_slice(&[0u8; 48]); // physics config
+
+        data.extend_from_slice(&1u32.to_le_bytes()); // 1 state machine
+        data.extend_from_slice(&0u32.to_le_bytes()); // animation index
+        data.extend_from_slice(&1u16.to_le_bytes()); // 1 parameter
+        write_name(&mut data, "speed");
+        data.push(0);
+        data.extend_from_slice(&0.0f32.to_le_bytes());
+
+        data.extend_from_slice(&1u16.to_le_bytes()); // 1 layer
+        write_name(&mut data, "upper");
+        data.extend_from_slice(&0.5f32.to_le_bytes());
+        data.push(1); // additive
+        data.extend_from_slice(&2u32.to_le_bytes());
+        data.extend_from_slice(&3u32.to_le_bytes());
+        data.extend_from_slice(&4u32.to_le_bytes());
+        data.extend_from_slice(&2u16.to_le_bytes()); // 2 states
+        for (name, clip) in [("idle", 0u32), ("walk", 1)] {
+            write_name(&mut data, name);
+            data.extend_from_slice(&clip.to_le_bytes());
+            data.extend_from_slice(&1.0f32.to_le_bytes());
+            data.push(1);
+        }
+        data.extend_from_slice(&0u16.to_le_bytes()); // default state
+        data.extend_from_slice(&1u16.to_le_bytes()); // 1 transition
+        data.extend_from_slice(&u16::MAX.to_le_bytes()); // any state
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&0.25f32.to_le_bytes());
+        data.extend_from_slice(&(-1.0f32).to_le_bytes());
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes());
+        data.push(0); // greater
+        data.extend_from_slice(&0.1f32.to_le_bytes());
+
+        let scene = parse_orsb(&data).unwrap();
+        assert_eq!(scene.state_machines.len(), 1);
+        let sm = &scene.state_machines[0];
+        assert_eq!(sm.parameters[0].name, "speed");
+        let layer = &sm.layers[0];
+        assert_eq!(layer.blend_mode, LayerBlendMode::Additive);
+        assert_eq!(layer.mask, vec![3, 4]);
+        assert_eq!(layer.states[1].name, "walk");
+        let tr = &layer.transitions[0];
+        assert_eq!(tr.from, None);
+        assert_eq!(tr.to, 1);
+        assert_eq!(tr.exit_time, None);
+        assert_eq!(tr.conditions[0].op, ConditionOp::Greater);
+    }
+
+    #[test]
+    fn test_parse_orsb_without_state_machines() {
+        let mut data = build_header(0, 0, 0, 0);
+        write_empty_trailing(&mut data);
+        data.extend_from_slice(&[0u8; 48]);
+        let scene = parse_orsb(&data).unwrap();
+        assert!(scene.state_machines.is_empty());
+    }
+
+    #[test]
+    fn test_parse_orsb_morph_targets_and_weight_channel() {
+        let mut data = build_header(1, 1, 0, 0);
+        write_entity(&mut data, 1, u32::MAX, ComponentMask::TRANSFORM | ComponentMask::MESH | ComponentMask::ANIMATION, 0, u32::MAX);
+        write_transform(&mut data, 0.0, 0.0, 0.0);
+
+        // Mesh: 1 vertex, 0 indices, no bones, 2 morph targets
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&2u32.to_le_bytes());
+        for _ in 0..8 {
+            data.extend_from_slice(&0.0f32.to_le_bytes()); // position, normal, uv
+        }
+        data.push(1); // target 0 has normals
+        for v in [1.0f32, 0.0, 0.0, 0.0, 1.0, 0.0] {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        data.push(0); // target 1 positions only
+        for v in [0.0f32, 2.0, 0.0] {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+
+        for _ in 0..5 {
+            data.extend_from_slice(&0u32.to_le_bytes()); // lights, cameras, colliders, rigidbodies
+        }
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&4u16.to_le_bytes());
+        data.extend_from_slice(b"face");
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&1.0f32.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.push(3); // morph weights
+        data.push(1); // linear
+        data.extend_from_slice(&2u32.to_le_bytes()); // 2 keys
+        data.extend_from_slice(&2u32.to_le_bytes()); // 2 weights per key
+        data.extend_from_slice(&0.0f32.to_le_bytes());
+        data.extend_from_slice(&1.0f32.to_le_bytes());
+        for w in [0.0f64, 0.0, 1.0, 0.5] {
+            data.extend_from_slice(&w.to_le_bytes());
+        }
+        data.extend_from_slice(&0i32.to_le_bytes());
+        data.push(1);
+        data.push(1);
+        data.extend_from_slice(&1.0f32.to_le_bytes());
+
+        let scene = parse_orsb(&data).unwrap();
+        let targets = &scene.meshes[0].morph_targets;
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[0].normal_deltas, vec![0.0, 1.0, 0.0]);
+        assert!(targets[1].normal_deltas.is_empty());
+        assert_eq!(targets[1].position_deltas, vec![0.0, 2.0, 0.0]);
+
+        let channel = &scene.animations[0].clips[0].channels[0];
+        assert_eq!(channel.target_property, TargetProperty::MorphWeights);
+        assert_eq!(channel.values, vec![0.0, 0.0, 1.0, 0.5]);
+    }
+
+    #[test]
+    fn test_parse_orsb_particle_emitter() {
+        let mut data = build_header(0, 0, 0, 0);
+        write_empty_trailing(&mut data);
+        data.extend_from_slice(&[0u8; 48]); // physics config
+        data.extend_from_slice(&0u32.to_le_bytes()); // no state machines
+
+        let f = |buf: &mut Vec<u8>, vals: &[f32]| {
+            for v in vals {
+                buf.extend_from_slice(&v.to_le_bytes());
+            }
+        };
+        data.extend_from_slice(&1u32.to_le_bytes()); // 1 emitter
+        data.extend_from_slice(&500u32.to_le_bytes());
+        f(&mut data, &[40.0]);
+        data.extend_from_slice(&10u32.to_le_bytes());
+        f(&mut data, &[1.0, 2.0, 0.0, 1.0, 0.0, 0.0, 2.0, 0.0, 1.0, 0.0, 0.1, 0.2, 0.0]);
+        f(&mut data, &[1.0, 0.5, 0.0, 1.0, 0.2, 0.2, 0.2, 0.0]);
+        data.extend_from_slice(&[1, 2, 1, 0]); // additive, cone, ground plane, flags
+        f(&mut data, &[0.3, 0.5, 0.0, 0.0]);
+        data.extend_from_slice(&u32::MAX.to_le_bytes());
+        data.extend_from_slice(&2u16.to_le_bytes()); // color curve: 2 keys
+        f(&mut data, &[0.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0]);
+        data.extend_from_slice(&1u16.to_le_bytes()); // size curve: 1 key
+        f(&mut data, &[0.5, 2.0]);
+        data.extend_from_slice(&0u16.to_le_bytes()); // no velocity curve
+        data.extend_from_slice(&3i32.to_le_bytes()); // sheet texture
+        for v in [4u16, 2, 8, 0] {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        f(&mut data, &[0.0, 0.25, -1.0, 0.6, 0.2]);
+
+        let scene = parse_orsb(&data).unwrap();
+        assert_eq!(scene.particle_emitters.len(), 1);
+        let e = &scene.particle_emitters[0];
+        assert_eq!(e.max_particles, 500);
+        assert_eq!(e.burst_count, 10);
+        assert_eq!(e.blend_mode, ParticleBlendMode::Additive);
+        assert_eq!(e.shape, EmitterShape::Cone { angle: 0.3, radius: 0.5 });
+        assert_eq!(e.collision, ParticleCollisionMode::GroundPlane);
+        assert_eq!(e.color_curve.times, vec![0.0, 1.0]);
+        assert_eq!(e.color_curve.values.len(), 8);
+        assert_eq!(e.size_curve.values, vec![2.0]);
+        assert!(e.velocity_curve.is_empty());
+        let sheet = e.texture_sheet.unwrap();
+        assert_eq!((sheet.texture, sheet.tiles_x, sheet.tiles_y, sheet.frame_count), (Some(3), 4, 2, 8));
+        assert_eq!(e.soft_distance, 0.25);
+        assert_eq!((e.plane_height, e.bounce, e.friction), (-1.0, 0.6, 0.2));
+    }
+
+    #[test]
+    fn test_parse_orsb_entity_names() {
+        let mut data = build_header(2, 0, 0, 0);
+        write_entity(&mut data, 10, u32::MAX, 1, u32::MAX, u32::MAX);
+        write_entity(&mut data, 11, u32::MAX, 1, u32::MAX, u32::MAX);
+        write_transform(&mut data, 0.0, 0.0, 0.0);
+        write_transform(&mut data, 0.0, 0.0, 0.0);
+        write_empty_trailing(&mut data);
+        data.extend_from_slice(&[0u8; 48]); // physics config
+        data.extend_from_slice(&0u32.to_le_bytes()); // no state machines
+        data.extend_from_slice(&0u32.to_le_bytes()); // no particle emitters
+        data.extend_from_slice(&1u32.to_le_bytes()); // 1 named entity
+        data.extend_from_slice(&1u32.to_le_bytes());
+        write_name(&mut data, "door");
+
+        let scene = parse_orsb(&data).unwrap();
+        assert_eq!(scene.entity_names, vec![String::new(), "door".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_orsb_scripts() {
+        let build = |attachments: &[(u32, u32)]| {
+            let mut data = build_header(2, 0, 0, 0);
+            write_entity(&mut data, 10, u32::MAX, 1, u32::MAX, u32::MAX);
+            write_entity(&mut data, 11, u32::MAX, 1, u32::MAX, u32::MAX);
+            write_transform(&mut data, 0.0, 0.0, 0.0);
+            write_transform(&mut data, 0.0, 0.0, 0.0);
+            write_empty_trailing(&mut data);
+            data.extend_from_slice(&[0u8; 48]); // physics config
+            data.extend_from_slice(&0u32.to_le_bytes()); // no state machines
+            data.extend_from_slice(&0u32.to_le_bytes()); // no particle emitters
+            data.extend_from_slice(&0u32.to_le_bytes()); // no named entities
+            data.extend_from_slice(&1u32.to_le_bytes()); // 1 script
+            write_name(&mut data, "bob");
+            let source = "fn on_update(dt) { }";
+            data.extend_from_slice(&(source.len() as u32).to_le_bytes());
+            data.extend_from_slice(source.as_bytes());
+            data.extend_from_slice(&(attachments.len() as u32).to_le_bytes());
+            for &(entity, script) in attachments {
+                data.extend_from_slice(&entity.to_le_bytes());
+                data.extend_from_slice(&script.to_le_bytes());
+                data.extend_from_slice(&1u16.to_le_bytes());
+                write_name(&mut data, "speed");
+                data.extend_from_slice(&2.5f64.to_le_bytes());
+            }
+            data
+        };
+
+        let scene = parse_orsb(&build(&[(1, 0)])).unwrap();
+        assert_eq!(scene.scripts, vec![ScriptParsed { name: "bob".into(), source: "fn on_update(dt) { }".into() }]);
+        assert_eq!(
+            scene.script_attachments,
+            vec![ScriptAttachmentParsed { entity_index: 1, script_index: 0, params: vec![("speed".into(), 2.5)] }]
+        );
+
+        // Attachments to missing scripts or entities are rejected
+        assert!(parse_orsb(&build(&[(1, 0), (1, 5)])).unwrap_err().contains("script 5 out of range"));
+        assert!(parse_orsb(&build(&[(2, 0)])).unwrap_err().contains("entity 2 out of range"));
+    }
+
+    #[test]
+    fn test_parse_orsb_prefabs() {
+        let mut data = build_header(3, 0, 0, 0);
+        write_entity(&mut data, 10, u32::MAX, 1, u32::MAX, u32::MAX);
+        write_entity(&mut data, 11, u32::MAX, 1, u32::MAX, u32::MAX);
+        write_entity(&mut data, 12, 1, 1, u32::MAX, u32::MAX);
+        for _ in 0..3 {
+            write_transform(&mut data, 0.0, 0.0, 0.0);
+        }
+        write_empty_trailing(&mut data);
+        data.extend_from_slice(&[0u8; 48]); // physics config
+        for _ in 0..5 {
+            data.extend_from_slice(&0u32.to_le_bytes()); // state machines .. script attachments
+        }
+        data.extend_from_slice(&1u32.to_le_bytes()); // 1 prefab
+        write_name(&mut data, "bullet");
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&2u32.to_le_bytes());
+
+        let scene = parse_orsb(&data).unwrap();
+        assert_eq!(scene.prefabs, vec![PrefabParsed { name: "bullet".into(), first_entity: 1, entity_count: 2 }]);
+
+        // Ranges past the entity table are rejected
+        let n = data.len();
+        data[n - 4..].copy_from_slice(&3u32.to_le_bytes());
+        assert!(parse_orsb(&data).is_err());
+        // Including ones whose end overflows
+        data[n - 8..n - 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        data[n - 4..].copy_from_slice(&2u32.to_le_bytes());
+        assert!(parse_orsb(&data).unwrap_err().contains("out of bounds"));
+    }
+
+    #[test]
+    fn test_parse_orsb_lods() {
+        let mut data = build_header(1, 2, 0, 0);
+        write_entity(&mut data, 1, u32::MAX, ComponentMask::TRANSFORM | ComponentMask::MESH, 0, u32::MAX);
+        write_transform(&mut data, 0.0, 0.0, 0.0);
+        for _ in 0..2 {
+            data.extend_from_slice(&[0u8; 16]); // empty mesh: no verts, indices, bones or morphs
+        }
+        write_empty_trailing(&mut data);
+        data.extend_from_slice(&[0u8; 48]); // physics config
+        for _ in 0..6 {
+            data.extend_from_slice(&0u32.to_le_bytes()); // state machines .. prefabs
+        }
+        data.extend_from_slice(&1u32.to_le_bytes()); // 1 LOD chain
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&1.1f32.to_le_bytes());
+        data.extend_from_slice(&2u16.to_le_bytes());
+        for (mesh, distance) in [(0u32, 15.0f32), (1, 30.0)] {
+            data.extend_from_slice(&mesh.to_le_bytes());
+            data.extend_from_slice(&distance.to_le_bytes());
+        }
+
+        let scene = parse_orsb(&data).unwrap();
+        assert_eq!(
+            scene.lods,
+            vec![LodParsed {
+                entity_index: 0,
+                hysteresis: 1.1,
+                levels: vec![
+                    LodLevelParsed { mesh_index: 0, max_distance: 15.0 },
+                    LodLevelParsed { mesh_index: 1, max_distance: 30.0 },
+                ],
+            }]
+        );
+
+        // Level meshes must exist
+        let n = data.len();
+        data[n - 8..n - 4].copy_from_slice(&2u32.to_le_bytes());
+        assert!(parse_orsb(&data).is_err());
+    }
+
+    #[test]
+    fn test_parse_orsb_environment() {
+        let mut data = build_header(0, 0, 0, 0);
+        write_empty_trailing(&mut data);
+        data.extend_from_slice(&[0u8; 48]); // physics config
+        for _ in 0..7 {
+            data.extend_from_slice(&0u32.to_le_bytes()); // state machines .. LOD chains
+        }
+        let atmosphere = AtmosphereSettings { intensity: 2.0, enabled: 1, ..Default::default() };
+        let fog = FogSettings { density: 0.02, enabled: 1, ..Default::default() };
+        data.extend_from_slice(&(ENVIRONMENT_ATMOSPHERE | ENVIRONMENT_FOG).to_le_bytes());
+        data.extend_from_slice(bytemuck::bytes_of(&atmosphere));
+        data.extend_from_slice(bytemuck::bytes_of(&fog));
+
+        let scene = parse_orsb(&data).unwrap();
+        assert_eq!(scene.atmosphere, Some(atmosphere));
+        assert_eq!(scene.fog, Some(fog));
+
+        // Invalid settings are rejected
+        let n = data.len();
+        data[n - 12..n - 8].copy_from_slice(&(-1.0f32).to_le_bytes()); // max distance
+        assert!(parse_orsb(&data).is_err());
+
+        // Truncated settings are rejected
+        data.truncate(n - 4);
+        assert!(parse_orsb(&data).is_err());
+    }
+
+    #[test]
+    fn test_parse_orsb_decals() {
+        let mut data = build_header(1, 0, 0, 0);
+        write_entity(&mut data, 1, u32::MAX, ComponentMask::TRANSFORM, u32::MAX, u32::MAX);
+        write_transform(&mut data, 0.0, 0.0, 0.0);
+        write_empty_trailing(&mut data);
+        data.extend_from_slice(&[0u8; 48]); // physics config
+        for _ in 0..8 {
+            data.extend_from_slice(&0u32.to_le_bytes()); // state machines .. environment
+        }
+        let params = DecalParams { roughness: 0.9, roughness_opacity: 0.5, ..Default::default() };
+        data.extend_from_slice(&1u32.to_le_bytes()); // 1 decal
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&(-1i32).to_le_bytes());
+        data.extend_from_slice(&(-1i32).to_le_bytes());
+        data.extend_from_slice(bytemuck::bytes_of(&params));
+
+        let scene = parse_orsb(&data).unwrap();
+        assert_eq!(
+            scene.decals,
+            vec![DecalParsed { entity_index: 0, albedo_texture: None, normal_texture: None, params }]
+        );
+
+        // Invalid settings are rejected
+        let n = data.len();
+        data[n - 4..].copy_from_slice(&0.0f32.to_le_bytes()); // angle fade
+        assert!(parse_orsb(&data).is_err());
+
+        // Textures must exist
+        data[n - 4..].copy_from_slice(&1.0f32.to_le_bytes());
+        data[n - 36..n - 32].copy_from_slice(&0i32.to_le_bytes()); // albedo
+        assert!(parse_orsb(&data).is_err());
+    }
 }
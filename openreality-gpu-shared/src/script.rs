@@ -0,0 +1,1009 @@
+//! Small embedded scripting language for game logic shipped inside ORSB bundles.
+//!
+//! Scripts are plain source text, compiled once at load and interpreted per
+//! entity. The syntax is a minimal Rhai/Lua-style language:
+//!
+//! ```text
+//! let time_acc = 0.0;                 // instance state, set up once
+//!
+//! fn on_update(dt) {
+//!     time_acc = time_acc + dt;
+//!     let p = position(self);
+//!     p.y = base_y + amplitude * sin(speed * time_acc);
+//!     set_position(self, p);
+//! }
+//!
+//! fn on_collision_enter(other) {
+//!     if other != nil { despawn(self); }
+//! }
+//! ```
+//!
+//! Values are `nil`, booleans, numbers (f64), strings, `vec3`s and entity
+//! handles. Everything the script can touch in the world goes through a
+//! [`ScriptHost`]; the interpreter itself only knows math builtins. Each call
+//! runs under a step budget so a runaway loop cannot hang the frame.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use glam::DVec3;
+
+/// Maximum statements and calls evaluated by one top-level invocation.
+pub const STEP_BUDGET: u32 = 100_000;
+
+/// Maximum nesting of script function calls.
+const MAX_CALL_DEPTH: usize = 64;
+
+/// Maximum nesting of statements and unary/parenthesized expressions in source.
+const MAX_PARSE_NESTING: usize = 64;
+
+/// Maximum nesting of expressions and blocks during evaluation, across calls.
+const MAX_EVAL_NESTING: usize = 256;
+
+/// A script value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Nil,
+    Bool(bool),
+    Num(f64),
+    Vec3(DVec3),
+    Str(Rc<str>),
+    Entity(u64),
+}
+
+impl Value {
+    pub fn truthy(&self) -> bool {
+        !matches!(self, Value::Nil | Value::Bool(false))
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Nil => "nil",
+            Value::Bool(_) => "bool",
+            Value::Num(_) => "number",
+            Value::Vec3(_) => "vec3",
+            Value::Str(_) => "string",
+            Value::Entity(_) => "entity",
+        }
+    }
+
+    pub fn as_num(&self) -> Result<f64, String> {
+        match self {
+            Value::Num(n) => Ok(*n),
+            other => Err(format!("expected number, got {}", other.type_name())),
+        }
+    }
+
+    pub fn as_vec3(&self) -> Result<DVec3, String> {
+        match self {
+            Value::Vec3(v) => Ok(*v),
+            other => Err(format!("expected vec3, got {}", other.type_name())),
+        }
+    }
+
+    pub fn as_entity(&self) -> Result<u64, String> {
+        match self {
+            Value::Entity(e) => Ok(*e),
+            other => Err(format!("expected entity, got {}", other.type_name())),
+        }
+    }
+
+    pub fn as_str(&self) -> Result<&str, String> {
+        match self {
+            Value::Str(s) => Ok(s),
+            other => Err(format!("expected string, got {}", other.type_name())),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Nil => write!(f, "nil"),
+            Value::Bool(b) => write!(f, "{b}"),
+            Value::Num(n) => write!(f, "{n}"),
+            Value::Vec3(v) => write!(f, "vec3({}, {}, {})", v.x, v.y, v.z),
+            Value::Str(s) => write!(f, "{s}"),
+            Value::Entity(e) => write!(f, "entity({e})"),
+        }
+    }
+}
+
+/// Bindings from scripts to the world (entities, input, physics, ...).
+pub trait ScriptHost {
+    /// Call host function `name`. Returns `None` if the host has no such function.
+    fn call(&mut self, name: &str, args: &[Value]) -> Option<Result<Value, String>>;
+}
+
+// ── Lexer ──
+
+#[derive(Clone, Debug, PartialEq)]
+enum Tok {
+    Num(f64),
+    Str(Rc<str>),
+    Ident(String),
+    Let,
+    Fn,
+    If,
+    Else,
+    While,
+    Return,
+    True,
+    False,
+    Nil,
+    Sym(&'static str),
+    Eof,
+}
+
+const SYMBOLS: [&str; 22] = [
+    "==", "!=", "<=", ">=", "&&", "||", "(", ")", "{", "}", ",", ";", ".", "=", "<", ">", "+", "-", "*", "/",
+    "%", "!",
+];
+
+fn lex(source: &str) -> Result<Vec<(Tok, u32)>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let mut line = 1u32;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\n' {
+            line += 1;
+            i += 1;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit())) {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let n = text.parse().map_err(|_| format!("line {line}: bad number '{text}'"))?;
+            tokens.push((Tok::Num(n), line));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            let tok = match word.as_str() {
+                "let" => Tok::Let,
+                "fn" => Tok::Fn,
+                "if" => Tok::If,
+                "else" => Tok::Else,
+                "while" => Tok::While,
+                "return" => Tok::Return,
+                "true" => Tok::True,
+                "false" => Tok::False,
+                "nil" => Tok::Nil,
+                _ => Tok::Ident(word),
+            };
+            tokens.push((tok, line));
+        } else if c == '"' {
+            let mut s = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None | Some('\n') => return Err(format!("line {line}: unterminated string")),
+                    Some('"') => break,
+                    Some('\\') => {
+                        s.push(match chars.get(i + 1) {
+                            Some('n') => '\n',
+                            Some('t') => '\t',
+                            Some(&other) => other,
+                            None => return Err(format!("line {line}: unterminated string")),
+                        });
+                        i += 2;
+                    }
+                    Some(&ch) => {
+                        s.push(ch);
+                        i += 1;
+                    }
+                }
+            }
+            i += 1;
+            tokens.push((Tok::Str(s.into()), line));
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let sym = SYMBOLS
+                .iter()
+                .find(|s| rest.starts_with(*s))
+                .ok_or_else(|| format!("line {line}: unexpected character '{c}'"))?;
+            i += sym.len();
+            tokens.push((Tok::Sym(sym), line));
+        }
+    }
+    tokens.push((Tok::Eof, line));
+    Ok(tokens)
+}
+
+// ── AST ──
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug)]
+enum Expr {
+    Lit(Value),
+    Var(String),
+    Field(Box<Expr>, usize),
+    Neg(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+#[derive(Debug)]
+enum StmtKind {
+    Let(String, Expr),
+    Assign(String, Option<usize>, Expr),
+    If(Expr, Vec<Stmt>, Vec<Stmt>),
+    While(Expr, Vec<Stmt>),
+    Return(Option<Expr>),
+    Expr(Expr),
+}
+
+#[derive(Debug)]
+struct Stmt {
+    line: u32,
+    kind: StmtKind,
+}
+
+#[derive(Debug)]
+struct Function {
+    params: Vec<String>,
+    body: Vec<Stmt>,
+}
+
+/// A compiled script, shared by every entity running it.
+#[derive(Debug)]
+pub struct Script {
+    init: Vec<Stmt>,
+    functions: HashMap<String, Rc<Function>>,
+}
+
+// ── Parser ──
+
+struct Parser {
+    tokens: Vec<(Tok, u32)>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Tok {
+        &self.tokens[self.pos].0
+    }
+
+    fn line(&self) -> u32 {
+        self.tokens[self.pos].1
+    }
+
+    fn next(&mut self) -> Tok {
+        let tok = self.tokens[self.pos].0.clone();
+        if self.pos + 1 < self.tokens.len() {
+            self.pos += 1;
+        }
+        tok
+    }
+
+    fn error<T>(&self, msg: &str) -> Result<T, String> {
+        Err(format!("line {}: {msg}", self.line()))
+    }
+
+    /// Run `f` one nesting level deeper, failing past `MAX_PARSE_NESTING`.
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, String>) -> Result<T, String> {
+        if self.depth >= MAX_PARSE_NESTING {
+            return self.error("nested too deeply");
+        }
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn eat(&mut self, sym: &str) -> bool {
+        if matches!(self.peek(), Tok::Sym(s) if *s == sym) {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, sym: &str) -> Result<(), String> {
+        if self.eat(sym) {
+            Ok(())
+        } else {
+            self.error(&format!("expected '{sym}'"))
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, String> {
+        match self.next() {
+            Tok::Ident(name) => Ok(name),
+            _ => {
+                self.pos -= 1;
+                self.error("expected a name")
+            }
+        }
+    }
+
+    fn block(&mut self) -> Result<Vec<Stmt>, String> {
+        self.expect("{")?;
+        let mut body = Vec::new();
+        while !self.eat("}") {
+            if *self.peek() == Tok::Eof {
+                return self.error("expected '}'");
+            }
+            body.push(self.statement()?);
+        }
+        Ok(body)
+    }
+
+    fn statement(&mut self) -> Result<Stmt, String> {
+        self.nested(Self::statement_inner)
+    }
+
+    fn statement_inner(&mut self) -> Result<Stmt, String> {
+        let line = self.line();
+        let kind = match self.peek() {
+            Tok::Let => {
+                self.next();
+                let name = self.ident()?;
+                self.expect("=")?;
+                let value = self.expression()?;
+                self.expect(";")?;
+                StmtKind::Let(name, value)
+            }
+            Tok::If => {
+                self.next();
+                let cond = self.expression()?;
+                let then = self.block()?;
+                let otherwise = if matches!(self.peek(), Tok::Else) {
+                    self.next();
+                    if matches!(self.peek(), Tok::If) {
+                        vec![self.statement()?]
+                    } else {
+                        self.block()?
+                    }
+                } else {
+                    Vec::new()
+                };
+                StmtKind::If(cond, then, otherwise)
+            }
+            Tok::While => {
+                self.next();
+                let cond = self.expression()?;
+                StmtKind::While(cond, self.block()?)
+            }
+            Tok::Return => {
+                self.next();
+                let value = if self.eat(";") {
+                    None
+                } else {
+                    let v = self.expression()?;
+                    self.expect(";")?;
+                    Some(v)
+                };
+                StmtKind::Return(value)
+            }
+            _ => {
+                let expr = self.expression()?;
+                let kind = if self.eat("=") {
+                    let value = self.expression()?;
+                    match expr {
+                        Expr::Var(name) => StmtKind::Assign(name, None, value),
+                        Expr::Field(target, axis) => match *target {
+                            Expr::Var(name) => StmtKind::Assign(name, Some(axis), value),
+                            _ => return Err(format!("line {line}: can only assign fields of variables")),
+                        },
+                        _ => return Err(format!("line {line}: invalid assignment target")),
+                    }
+                } else {
+                    StmtKind::Expr(expr)
+                };
+                self.expect(";")?;
+                kind
+            }
+        };
+        Ok(Stmt { line, kind })
+    }
+
+    fn expression(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.and()?;
+        while self.eat("||") {
+            lhs = Expr::Or(Box::new(lhs), Box::new(self.and()?));
+        }
+        Ok(lhs)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.binary(0)?;
+        while self.eat("&&") {
+            lhs = Expr::And(Box::new(lhs), Box::new(self.binary(0)?));
+        }
+        Ok(lhs)
+    }
+
+    /// Precedence climbing over equality < comparison < term < factor.
+    fn binary(&mut self, level: usize) -> Result<Expr, String> {
+        const LEVELS: [&[(&str, BinOp)]; 4] = [
+            &[("==", BinOp::Eq), ("!=", BinOp::Ne)],
+            &[("<", BinOp::Lt), ("<=", BinOp::Le), (">", BinOp::Gt), (">=", BinOp::Ge)],
+            &[("+", BinOp::Add), ("-", BinOp::Sub)],
+            &[("*", BinOp::Mul), ("/", BinOp::Div), ("%", BinOp::Rem)],
+        ];
+        if level == LEVELS.len() {
+            return self.unary();
+        }
+        let mut lhs = self.binary(level + 1)?;
+        'outer: loop {
+            for &(sym, op) in LEVELS[level] {
+                if self.eat(sym) {
+                    lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.binary(level + 1)?));
+                    continue 'outer;
+                }
+            }
+            return Ok(lhs);
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        self.nested(|p| {
+            if p.eat("-") {
+                Ok(Expr::Neg(Box::new(p.unary()?)))
+            } else if p.eat("!") {
+                Ok(Expr::Not(Box::new(p.unary()?)))
+            } else {
+                p.postfix()
+            }
+        })
+    }
+
+    fn postfix(&mut self) -> Result<Expr, String> {
+        let mut expr = self.primary()?;
+        while self.eat(".") {
+            let axis = match self.ident()?.as_str() {
+                "x" => 0,
+                "y" => 1,
+                "z" => 2,
+                other => return self.error(&format!("unknown field '{other}' (expected x, y or z)")),
+            };
+            expr = Expr::Field(Box::new(expr), axis);
+        }
+        Ok(expr)
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Tok::Num(n) => Ok(Expr::Lit(Value::Num(n))),
+            Tok::Str(s) => Ok(Expr::Lit(Value::Str(s))),
+            Tok::True => Ok(Expr::Lit(Value::Bool(true))),
+            Tok::False => Ok(Expr::Lit(Value::Bool(false))),
+            Tok::Nil => Ok(Expr::Lit(Value::Nil)),
+            Tok::Ident(name) => {
+                if self.eat("(") {
+                    let mut args = Vec::new();
+                    if !self.eat(")") {
+                        loop {
+                            args.push(self.expression()?);
+                            if self.eat(")") {
+                                break;
+                            }
+                            self.expect(",")?;
+                        }
+                    }
+                    Ok(Expr::Call(name, args))
+                } else {
+                    Ok(Expr::Var(name))
+                }
+            }
+            Tok::Sym("(") => {
+                let inner = self.expression()?;
+                self.expect(")")?;
+                Ok(inner)
+            }
+            _ => {
+                self.pos -= 1;
+                self.error("expected an expression")
+            }
+        }
+    }
+}
+
+/// Compile script source. Top-level `let`s and statements become the
+/// per-instance initializer; `fn` items become callable functions.
+pub fn compile(source: &str) -> Result<Script, String> {
+    let mut parser = Parser { tokens: lex(source)?, pos: 0, depth: 0 };
+    let mut init = Vec::new();
+    let mut functions = HashMap::new();
+    while *parser.peek() != Tok::Eof {
+        if matches!(parser.peek(), Tok::Fn) {
+            parser.next();
+            let name = parser.ident()?;
+            parser.expect("(")?;
+            let mut params = Vec::new();
+            if !parser.eat(")") {
+                loop {
+                    params.push(parser.ident()?);
+                    if parser.eat(")") {
+                        break;
+                    }
+                    parser.expect(",")?;
+                }
+            }
+            let body = parser.block()?;
+            if functions.insert(name.clone(), Rc::new(Function { params, body })).is_some() {
+                return Err(format!("function '{name}' defined twice"));
+            }
+        } else {
+            init.push(parser.statement()?);
+        }
+    }
+    Ok(Script { init, functions })
+}
+
+// ── Interpreter ──
+
+enum Flow {
+    Normal,
+    Return(Value),
+}
+
+struct Interp<'a> {
+    script: &'a Script,
+    globals: &'a mut HashMap<String, Value>,
+    host: &'a mut dyn ScriptHost,
+    steps: u32,
+    depth: usize,
+    nesting: usize,
+    /// Set once an error has been tagged with the line of the innermost
+    /// statement, so enclosing statements don't prefix theirs too.
+    located: bool,
+}
+
+fn arith(op: BinOp, a: &Value, b: &Value) -> Result<Value, String> {
+    use Value::*;
+    let v = match (op, a, b) {
+        (BinOp::Eq, a, b) => Bool(a == b),
+        (BinOp::Ne, a, b) => Bool(a != b),
+        (BinOp::Add, Num(x), Num(y)) => Num(x + y),
+        (BinOp::Sub, Num(x), Num(y)) => Num(x - y),
+        (BinOp::Mul, Num(x), Num(y)) => Num(x * y),
+        (BinOp::Div, Num(x), Num(y)) => Num(x / y),
+        (BinOp::Rem, Num(x), Num(y)) => Num(x.rem_euclid(*y)),
+        (BinOp::Lt, Num(x), Num(y)) => Bool(x < y),
+        (BinOp::Le, Num(x), Num(y)) => Bool(x <= y),
+        (BinOp::Gt, Num(x), Num(y)) => Bool(x > y),
+        (BinOp::Ge, Num(x), Num(y)) => Bool(x >= y),
+        (BinOp::Add, Vec3(x), Vec3(y)) => Vec3(*x + *y),
+        (BinOp::Sub, Vec3(x), Vec3(y)) => Vec3(*x - *y),
+        (BinOp::Mul, Vec3(x), Vec3(y)) => Vec3(*x * *y),
+        (BinOp::Mul, Vec3(v), Num(s)) | (BinOp::Mul, Num(s), Vec3(v)) => Vec3(*v * *s),
+        (BinOp::Div, Vec3(v), Num(s)) => Vec3(*v / *s),
+        (BinOp::Add, Str(s), other) => Str(format!("{s}{other}").into()),
+        (BinOp::Add, other, Str(s)) => Str(format!("{other}{s}").into()),
+        _ => return Err(format!("cannot apply {op:?} to {} and {}", a.type_name(), b.type_name())),
+    };
+    Ok(v)
+}
+
+fn builtin(name: &str, args: &[Value]) -> Option<Result<Value, String>> {
+    let num = |i: usize| args.get(i).ok_or("missing argument".to_string()).and_then(Value::as_num);
+    let vec = |i: usize| args.get(i).ok_or("missing argument".to_string()).and_then(Value::as_vec3);
+    let unary = |f: fn(f64) -> f64| num(0).map(|x| Value::Num(f(x)));
+    let result = match name {
+        "vec3" => (|| Ok(Value::Vec3(DVec3::new(num(0)?, num(1)?, num(2)?))))(),
+        "sin" => unary(f64::sin),
+        "cos" => unary(f64::cos),
+        "tan" => unary(f64::tan),
+        "sqrt" => unary(f64::sqrt),
+        "abs" => unary(f64::abs),
+        "floor" => unary(f64::floor),
+        "ceil" => unary(f64::ceil),
+        "atan2" => (|| Ok(Value::Num(num(0)?.atan2(num(1)?))))(),
+        "pow" => (|| Ok(Value::Num(num(0)?.powf(num(1)?))))(),
+        "min" => (|| Ok(Value::Num(num(0)?.min(num(1)?))))(),
+        "max" => (|| Ok(Value::Num(num(0)?.max(num(1)?))))(),
+        "clamp" => (|| {
+            // max/min rather than f64::clamp, which panics on NaN bounds
+            let lo = num(1)?;
+            Ok(Value::Num(num(0)?.max(lo).min(num(2)?.max(lo))))
+        })(),
+        "lerp" => (|| {
+            let t = num(2)?;
+            match (&args[0], &args[1]) {
+                (Value::Vec3(a), Value::Vec3(b)) => Ok(Value::Vec3(a.lerp(*b, t))),
+                _ => Ok(Value::Num(num(0)? + (num(1)? - num(0)?) * t)),
+            }
+        })(),
+        "length" => vec(0).map(|v| Value::Num(v.length())),
+        "normalize" => vec(0).map(|v| Value::Vec3(v.normalize_or_zero())),
+        "dot" => (|| Ok(Value::Num(vec(0)?.dot(vec(1)?))))(),
+        "cross" => (|| Ok(Value::Vec3(vec(0)?.cross(vec(1)?))))(),
+        "distance" => (|| Ok(Value::Num(vec(0)?.distance(vec(1)?))))(),
+        _ => return None,
+    };
+    Some(result)
+}
+
+impl Interp<'_> {
+    fn tick(&mut self) -> Result<(), String> {
+        self.steps += 1;
+        if self.steps > STEP_BUDGET {
+            Err("step budget exceeded (infinite loop?)".into())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Run `f` one nesting level deeper, failing past `MAX_EVAL_NESTING`.
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, String>) -> Result<T, String> {
+        if self.nesting >= MAX_EVAL_NESTING {
+            return Err("nested too deeply".into());
+        }
+        self.nesting += 1;
+        let result = f(self);
+        self.nesting -= 1;
+        result
+    }
+
+    fn lookup(&self, scopes: &[HashMap<String, Value>], name: &str) -> Result<Value, String> {
+        scopes
+            .iter()
+            .rev()
+            .find_map(|s| s.get(name))
+            .or_else(|| self.globals.get(name))
+            .cloned()
+            .ok_or_else(|| format!("undefined variable '{name}'"))
+    }
+
+    fn slot<'s>(&'s mut self, scopes: &'s mut [HashMap<String, Value>], name: &str) -> Result<&'s mut Value, String> {
+        if let Some(scope) = scopes.iter_mut().rev().find(|s| s.contains_key(name)) {
+            return Ok(scope.get_mut(name).unwrap());
+        }
+        self.globals
+            .get_mut(name)
+            .ok_or_else(|| format!("assignment to undefined variable '{name}' (declare it with let)"))
+    }
+
+    fn eval(&mut self, expr: &Expr, scopes: &mut Vec<HashMap<String, Value>>) -> Result<Value, String> {
+        self.nested(|interp| interp.eval_inner(expr, scopes))
+    }
+
+    fn eval_inner(&mut self, expr: &Expr, scopes: &mut Vec<HashMap<String, Value>>) -> Result<Value, String> {
+        match expr {
+            Expr::Lit(v) => Ok(v.clone()),
+            Expr::Var(name) => self.lookup(scopes, name),
+            Expr::Field(target, axis) => {
+                let v = self.eval(target, scopes)?.as_vec3()?;
+                Ok(Value::Num(v[*axis]))
+            }
+            Expr::Neg(inner) => match self.eval(inner, scopes)? {
+                Value::Num(n) => Ok(Value::Num(-n)),
+                Value::Vec3(v) => Ok(Value::Vec3(-v)),
+                other => Err(format!("cannot negate {}", other.type_name())),
+            },
+            Expr::Not(inner) => Ok(Value::Bool(!self.eval(inner, scopes)?.truthy())),
+            Expr::And(a, b) => {
+                let lhs = self.eval(a, scopes)?;
+                if lhs.truthy() { self.eval(b, scopes) } else { Ok(lhs) }
+            }
+            Expr::Or(a, b) => {
+                let lhs = self.eval(a, scopes)?;
+                if lhs.truthy() { Ok(lhs) } else { self.eval(b, scopes) }
+            }
+            Expr::Binary(op, a, b) => {
+                let lhs = self.eval(a, scopes)?;
+                let rhs = self.eval(b, scopes)?;
+                arith(*op, &lhs, &rhs)
+            }
+            Expr::Call(name, arg_exprs) => {
+                self.tick()?;
+                let mut args = Vec::with_capacity(arg_exprs.len());
+                for a in arg_exprs {
+                    args.push(self.eval(a, scopes)?);
+                }
+                if let Some(f) = self.script.functions.get(name).cloned() {
+                    return self.call_function(name, &f, args);
+                }
+                if let Some(result) = builtin(name, &args) {
+                    return result.map_err(|e| format!("{name}: {e}"));
+                }
+                match self.host.call(name, &args) {
+                    Some(result) => result.map_err(|e| format!("{name}: {e}")),
+                    None => Err(format!("unknown function '{name}'")),
+                }
+            }
+        }
+    }
+
+    fn exec_block(&mut self, body: &[Stmt], scopes: &mut Vec<HashMap<String, Value>>) -> Result<Flow, String> {
+        self.nested(|interp| interp.exec_block_inner(body, scopes))
+    }
+
+    fn exec_block_inner(&mut self, body: &[Stmt], scopes: &mut Vec<HashMap<String, Value>>) -> Result<Flow, String> {
+        scopes.push(HashMap::new());
+        let result = body.iter().try_fold(Flow::Normal, |_, stmt| match self.exec(stmt, scopes)? {
+            Flow::Normal => Ok(Flow::Normal),
+            ret => Err(Ok(ret)),
+        });
+        scopes.pop();
+        match result {
+            Ok(flow) => Ok(flow),
+            Err(Ok(ret)) => Ok(ret),
+            Err(Err(e)) => Err(e),
+        }
+    }
+
+    fn exec(&mut self, stmt: &Stmt, scopes: &mut Vec<HashMap<String, Value>>) -> Result<Flow, Result<Flow, String>> {
+        self.exec_inner(stmt, scopes).map_err(|e| {
+            if self.located {
+                Err(e)
+            } else {
+                self.located = true;
+                Err(format!("line {}: {e}", stmt.line))
+            }
+        })
+    }
+
+    fn exec_inner(&mut self, stmt: &Stmt, scopes: &mut Vec<HashMap<String, Value>>) -> Result<Flow, String> {
+        self.tick()?;
+        match &stmt.kind {
+            StmtKind::Let(name, expr) => {
+                let value = self.eval(expr, scopes)?;
+                match scopes.last_mut() {
+                    Some(scope) => scope.insert(name.clone(), value),
+                    None => self.globals.insert(name.clone(), value),
+                };
+            }
+            StmtKind::Assign(name, axis, expr) => {
+                let value = self.eval(expr, scopes)?;
+                let slot = self.slot(scopes, name)?;
+                match axis {
+                    None => *slot = value,
+                    Some(axis) => match slot {
+                        Value::Vec3(v) => v[*axis] = value.as_num()?,
+                        other => return Err(format!("'{name}' is a {}, not a vec3", other.type_name())),
+                    },
+                }
+            }
+            StmtKind::If(cond, then, otherwise) => {
+                let branch = if self.eval(cond, scopes)?.truthy() { then } else { otherwise };
+                return self.exec_block(branch, scopes);
+            }
+            StmtKind::While(cond, body) => {
+                while self.eval(cond, scopes)?.truthy() {
+                    self.tick()?;
+                    if let Flow::Return(v) = self.exec_block(body, scopes)? {
+                        return Ok(Flow::Return(v));
+                    }
+                }
+            }
+            StmtKind::Return(expr) => {
+                let value = match expr {
+                    Some(e) => self.eval(e, scopes)?,
+                    None => Value::Nil,
+                };
+                return Ok(Flow::Return(value));
+            }
+            StmtKind::Expr(expr) => {
+                self.eval(expr, scopes)?;
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    fn call_function(&mut self, name: &str, f: &Function, args: Vec<Value>) -> Result<Value, String> {
+        if self.depth >= MAX_CALL_DEPTH {
+            return Err("call stack overflow".into());
+        }
+        // Missing arguments are nil, extra ones are dropped
+        let mut frame = HashMap::new();
+        let mut args = args.into_iter();
+        for p in &f.params {
+            frame.insert(p.clone(), args.next().unwrap_or(Value::Nil));
+        }
+        self.depth += 1;
+        let mut scopes = vec![frame];
+        let result = self.exec_block(&f.body, &mut scopes);
+        self.depth -= 1;
+        match result.map_err(|e| format!("in {name}: {e}"))? {
+            Flow::Return(v) => Ok(v),
+            Flow::Normal => Ok(Value::Nil),
+        }
+    }
+}
+
+/// One entity's running copy of a script, with its own global state.
+#[derive(Debug)]
+pub struct ScriptInstance {
+    script: Rc<Script>,
+    globals: HashMap<String, Value>,
+}
+
+impl ScriptInstance {
+    /// Create an instance: `self` is bound to `entity`, `params` become
+    /// globals, then the script's top-level statements run once.
+    pub fn new(
+        script: Rc<Script>,
+        entity: u64,
+        params: &[(String, f64)],
+        host: &mut dyn ScriptHost,
+    ) -> Result<Self, String> {
+        let mut globals: HashMap<String, Value> =
+            params.iter().map(|(k, v)| (k.clone(), Value::Num(*v))).collect();
+        globals.insert("self".into(), Value::Entity(entity));
+        let mut interp = Interp { script: &script, globals: &mut globals, host, steps: 0, depth: 0, nesting: 0, located: false };
+        let mut scopes = Vec::new();
+        for stmt in &script.init {
+            if let Err(Err(e)) = interp.exec(stmt, &mut scopes) {
+                return Err(e);
+            }
+        }
+        Ok(Self { script, globals })
+    }
+
+    pub fn has_function(&self, name: &str) -> bool {
+        self.script.functions.contains_key(name)
+    }
+
+    /// Call script function `name`; missing functions are a no-op returning nil.
+    pub fn call(&mut self, name: &str, args: Vec<Value>, host: &mut dyn ScriptHost) -> Result<Value, String> {
+        let Some(f) = self.script.functions.get(name).cloned() else { return Ok(Value::Nil) };
+        let script = self.script.clone();
+        let mut interp = Interp { script: &script, globals: &mut self.globals, host, steps: 0, depth: 0, nesting: 0, located: false };
+        interp.call_function(name, &f, args)
+    }
+
+    pub fn global(&self, name: &str) -> Option<&Value> {
+        self.globals.get(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Host with a movable position per entity and a log.
+    #[derive(Default)]
+    struct TestHost {
+        positions: HashMap<u64, DVec3>,
+        log: Vec<String>,
+    }
+
+    impl ScriptHost for TestHost {
+        fn call(&mut self, name: &str, args: &[Value]) -> Option<Result<Value, String>> {
+            Some(match name {
+                "position" => args[0].as_entity().map(|e| Value::Vec3(self.positions.get(&e).copied().unwrap_or_default())),
+                "set_position" => (|| {
+                    self.positions.insert(args[0].as_entity()?, args[1].as_vec3()?);
+                    Ok(Value::Nil)
+                })(),
+                "log" => {
+                    self.log.push(args.iter().map(|a| a.to_string()).collect::<Vec<_>>().join(" "));
+                    Ok(Value::Nil)
+                }
+                _ => return None,
+            })
+        }
+    }
+
+    fn instance(source: &str, host: &mut TestHost) -> ScriptInstance {
+        ScriptInstance::new(Rc::new(compile(source).unwrap()), 7, &[("speed".into(), 2.0)], host).unwrap()
+    }
+
+    #[test]
+    fn test_bobbing_script_moves_entity() {
+        let mut host = TestHost::default();
+        let mut inst = instance(
+            "let t = 0.0;
+             fn on_update(dt) {
+                 t = t + dt;
+                 let p = position(self);
+                 p.y = 1.0 + sin(speed * t) * 0.5;
+                 set_position(self, p);
+             }",
+            &mut host,
+        );
+        inst.call("on_update", vec![Value::Num(0.25)], &mut host).unwrap();
+        let y = host.positions[&7].y;
+        assert!((y - (1.0 + (0.5f64).sin() * 0.5)).abs() < 1e-12);
+        assert_eq!(inst.global("t"), Some(&Value::Num(0.25)));
+    }
+
+    #[test]
+    fn test_control_flow_and_functions() {
+        let mut host = TestHost::default();
+        let mut inst = instance(
+            "fn fact(n) { if n <= 1 { return 1; } return n * fact(n - 1); }
+             fn count() { let i = 0; let sum = 0; while i < 5 { i = i + 1; if i % 2 == 0 { sum = sum + i; } } return sum; }
+             fn logic() { return (1 < 2 && !false) || nil; }
+             fn greet() { log(\"hello \" + 3, vec3(1, 2, 3).z); }",
+            &mut host,
+        );
+        assert_eq!(inst.call("fact", vec![Value::Num(5.0)], &mut host), Ok(Value::Num(120.0)));
+        assert_eq!(inst.call("count", vec![], &mut host), Ok(Value::Num(6.0)));
+        assert_eq!(inst.call("logic", vec![], &mut host), Ok(Value::Bool(true)));
+        assert_eq!(inst.call("missing", vec![], &mut host), Ok(Value::Nil));
+        inst.call("greet", vec![], &mut host).unwrap();
+        assert_eq!(host.log, vec!["hello 3 3".to_string()]);
+    }
+
+    #[test]
+    fn test_errors_report_lines() {
+        let err = compile("let x = 1;\nlet y = ;").unwrap_err();
+        assert!(err.starts_with("line 2"), "{err}");
+
+        let mut host = TestHost::default();
+        let mut inst = instance("fn bad() {\n  undefined_thing();\n}", &mut host);
+        let err = inst.call("bad", vec![], &mut host).unwrap_err();
+        assert!(err.contains("line 2") && err.contains("unknown function"), "{err}");
+
+        // Only the innermost statement's line is reported
+        let mut inst = instance("fn nested() {\n  if true {\n    while true {\n      undefined_thing();\n    }\n  }\n}", &mut host);
+        let err = inst.call("nested", vec![], &mut host).unwrap_err();
+        assert_eq!(err, "in nested: line 4: unknown function 'undefined_thing'");
+    }
+
+    #[test]
+    fn test_clamp_with_nan_bounds() {
+        let mut host = TestHost::default();
+        let mut inst = instance(
+            "fn lo() { return clamp(5, 0 / 0, 1); }
+             fn hi() { return clamp(5, 0, 0 / 0); }
+             fn ok() { return clamp(5, 0, 1); }",
+            &mut host,
+        );
+        assert_eq!(inst.call("lo", vec![], &mut host), Ok(Value::Num(1.0)));
+        assert_eq!(inst.call("hi", vec![], &mut host), Ok(Value::Num(0.0)));
+        assert_eq!(inst.call("ok", vec![], &mut host), Ok(Value::Num(1.0)));
+    }
+
+    #[test]
+    fn test_deep_nesting_is_an_error() {
+        let parens = format!("let x = {}1{};", "(".repeat(10_000), ")".repeat(10_000));
+        assert!(compile(&parens).unwrap_err().contains("nested too deeply"));
+        let negations = format!("let x = {}1;", "-".repeat(10_000));
+        assert!(compile(&negations).unwrap_err().contains("nested too deeply"));
+        let blocks = format!("fn f() {{ {}{} }}", "if true { ".repeat(10_000), "}".repeat(10_000));
+        assert!(compile(&blocks).unwrap_err().contains("nested too deeply"));
+
+        // Recursion through nested blocks hits the evaluator's limit
+        let mut host = TestHost::default();
+        let mut inst = instance(
+            "fn f(n) { if true { if true { if true { if true { return f(n + 1); } } } } }",
+            &mut host,
+        );
+        let err = inst.call("f", vec![Value::Num(0.0)], &mut host).unwrap_err();
+        assert!(err.contains("nested too deeply"), "{err}");
+    }
+
+    #[test]
+    fn test_step_budget_stops_infinite_loop() {
+        let mut host = TestHost::default();
+        let mut inst = instance("fn spin() { while true { } }", &mut host);
+        let err = inst.call("spin", vec![], &mut host).unwrap_err();
+        assert!(err.contains("step budget"), "{err}");
+    }
+}
@@ -1,11 +1,17 @@
-/// Embedded WGSL shader source strings for the WebGPU rendering pipeline.
-/// These are shared between the native FFI backend and the WASM web runtime.
+//! Embedded WGSL shader source strings for the WebGPU rendering pipeline.
+//! These are shared between the native FFI backend and the WASM web runtime.
 
 pub const FULLSCREEN_QUAD_VERT: &str = include_str!("../shaders/fullscreen_quad.wgsl");
 pub const GBUFFER_VERT: &str = include_str!("../shaders/gbuffer_vert.wgsl");
+pub const GBUFFER_SKINNED_VERT: &str = include_str!("../shaders/gbuffer_skinned_vert.wgsl");
 pub const GBUFFER_FRAG: &str = include_str!("../shaders/gbuffer_frag.wgsl");
 pub const DEFERRED_LIGHTING_FRAG: &str = include_str!("../shaders/deferred_lighting.wgsl");
 pub const SHADOW_DEPTH_VERT: &str = include_str!("../shaders/shadow_depth.wgsl");
+pub const SHADOW_DEPTH_SKINNED_VERT: &str = include_str!("../shaders/shadow_depth_skinned.wgsl");
+pub const SHADOW_CLEAR_VERT: &str = include_str!("../shaders/shadow_clear.wgsl");
+pub const EVSM_MOMENTS_FRAG: &str = include_str!("../shaders/evsm_moments.wgsl");
+pub const INSTANCE_CULL_SHADER: &str = include_str!("../shaders/instance_cull.wgsl");
+pub const LIGHT_CLUSTER_SHADER: &str = include_str!("../shaders/light_cluster.wgsl");
 pub const SSAO_FRAG: &str = include_str!("../shaders/ssao.wgsl");
 pub const SSAO_BLUR_FRAG: &str = include_str!("../shaders/ssao_blur.wgsl");
 pub const SSR_FRAG: &str = include_str!("../shaders/ssr.wgsl");
@@ -15,9 +21,33 @@ pub const BLOOM_BLUR_FRAG: &str = include_str!("../shaders/bloom_blur.wgsl");
 pub const BLOOM_COMPOSITE_FRAG: &str = include_str!("../shaders/bloom_composite.wgsl");
 pub const FXAA_FRAG: &str = include_str!("../shaders/fxaa.wgsl");
 pub const PRESENT_FRAG: &str = include_str!("../shaders/present.wgsl");
-pub const PARTICLE_SHADER: &str = include_str!("../shaders/particle.wgsl");
+pub const PARTICLE_SHADER: &str =
+    concat!(include_str!("../shaders/particle.wgsl"), include_str!("../shaders/particle_shading.wgsl"));
+pub const PARTICLE_COMPUTE_SHADER: &str = include_str!("../shaders/particle_compute.wgsl");
+pub const GPU_PARTICLE_SHADER: &str =
+    concat!(include_str!("../shaders/gpu_particle.wgsl"), include_str!("../shaders/particle_shading.wgsl"));
 pub const UI_SHADER: &str = include_str!("../shaders/ui.wgsl");
 pub const TERRAIN_GBUFFER_SHADER: &str = include_str!("../shaders/terrain_gbuffer.wgsl");
 pub const FORWARD_PBR_SHADER: &str = include_str!("../shaders/forward_pbr.wgsl");
 pub const DOF_SHADER: &str = include_str!("../shaders/dof.wgsl");
 pub const MOTION_BLUR_SHADER: &str = include_str!("../shaders/motion_blur.wgsl");
+pub const COLOR_GRADING_FRAG: &str = include_str!("../shaders/color_grading.wgsl");
+pub const LUMINANCE_HISTOGRAM_SHADER: &str = include_str!("../shaders/luminance_histogram.wgsl");
+pub const FSR_EASU_FRAG: &str = include_str!("../shaders/fsr_easu.wgsl");
+pub const MSAA_PRIME_FRAG: &str = include_str!("../shaders/msaa_prime.wgsl");
+pub const FOG_INJECT_SHADER: &str = include_str!("../shaders/fog_inject.wgsl");
+pub const FOG_INTEGRATE_SHADER: &str = include_str!("../shaders/fog_integrate.wgsl");
+pub const DECAL_SHADER: &str = include_str!("../shaders/decal.wgsl");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_particle_shaders_share_shading() {
+        for source in [PARTICLE_SHADER, GPU_PARTICLE_SHADER] {
+            assert_eq!(source.matches("fn shade_particle(").count(), 1);
+            assert_eq!(source.matches("fn soft_fade(").count(), 1);
+        }
+    }
+}
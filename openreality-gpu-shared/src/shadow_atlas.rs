@@ -0,0 +1,236 @@
+//! Shadow atlas for point and spot lights. Every shadowed light gets square
+//! tiles of one depth texture: one for a spot light, six (one per cube face)
+//! for a point light. Tile resolution follows how much of the screen the
+//! light's range covers, and the least covered lights lose resolution first
+//! when the atlas runs out of room.
+
+use glam::{Mat4, Vec3};
+
+use crate::uniforms::LocalShadowData;
+
+/// Light kinds accepted by `or_wgpu_set_shadow_casters`.
+pub const LIGHT_KIND_POINT: u32 = 0;
+pub const LIGHT_KIND_SPOT: u32 = 1;
+
+/// Cube faces of a point light shadow, in record order.
+pub const CUBE_FACES: usize = 6;
+
+/// Near plane of local light shadow projections.
+pub const SHADOW_NEAR: f32 = 0.05;
+
+/// Atlas size and tile resolution limits in texels, all powers of two.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AtlasConfig {
+    pub size: u32,
+    pub min_tile: u32,
+    pub max_tile: u32,
+}
+
+/// A light asking for shadow tiles.
+#[derive(Clone, Copy, Debug)]
+pub struct ShadowRequest {
+    /// Fraction of the screen covered by the light's range, 0..1.
+    pub coverage: f32,
+    /// `CUBE_FACES` for point lights, 1 for spot lights.
+    pub faces: u32,
+}
+
+/// Square region of the atlas, in texels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AtlasTile {
+    pub x: u32,
+    pub y: u32,
+    pub size: u32,
+}
+
+impl AtlasTile {
+    /// Offset and size of the tile in atlas UVs.
+    pub fn uv_rect(&self, atlas_size: u32) -> [f32; 4] {
+        let s = atlas_size as f32;
+        [self.x as f32 / s, self.y as f32 / s, self.size as f32 / s, self.size as f32 / s]
+    }
+}
+
+/// Tile resolution of each request: its coverage of `max_tile`, rounded up
+/// to a power of two, then halved for the least covered lights until all
+/// tiles fit. Lights that still do not fit at `min_tile` get 0 (no shadow).
+pub fn budget_resolutions(requests: &[ShadowRequest], config: &AtlasConfig) -> Vec<u32> {
+    let mut res: Vec<u32> = requests
+        .iter()
+        .map(|r| {
+            let wanted = (r.coverage.clamp(0.0, 1.0) * config.max_tile as f32).ceil() as u32;
+            wanted.next_power_of_two().clamp(config.min_tile, config.max_tile)
+        })
+        .collect();
+
+    let mut order: Vec<usize> = (0..requests.len()).collect();
+    order.sort_by(|&a, &b| requests[a].coverage.total_cmp(&requests[b].coverage));
+
+    let capacity = config.size as u64 * config.size as u64;
+    let tile_area = |i: usize, size: u32| requests[i].faces as u64 * size as u64 * size as u64;
+    let mut used: u64 = (0..requests.len()).map(|i| tile_area(i, res[i])).sum();
+
+    // One halving step per light per round, least covered first
+    while used > capacity {
+        let mut shrunk = false;
+        for &i in &order {
+            if used <= capacity {
+                break;
+            }
+            if res[i] > config.min_tile {
+                used -= tile_area(i, res[i]) - tile_area(i, res[i] / 2);
+                res[i] /= 2;
+                shrunk = true;
+            }
+        }
+        if !shrunk {
+            break;
+        }
+    }
+    for &i in &order {
+        if used <= capacity {
+            break;
+        }
+        used -= tile_area(i, res[i]);
+        res[i] = 0;
+    }
+    res
+}
+
+/// Budget and place the tiles of every request. Returns each request's
+/// tiles in face order, or none when it was dropped.
+///
+/// Tiles are placed largest first along a Z-order curve: with power-of-two
+/// sizes in descending order every tile lands aligned to its own size, so
+/// tiles never overlap and fill the atlas without gaps.
+pub fn allocate_tiles(requests: &[ShadowRequest], config: &AtlasConfig) -> Vec<Vec<AtlasTile>> {
+    let res = budget_resolutions(requests, config);
+    let mut items: Vec<usize> = (0..requests.len())
+        .filter(|&i| res[i] > 0)
+        .flat_map(|i| std::iter::repeat_n(i, requests[i].faces as usize))
+        .collect();
+    items.sort_by(|&a, &b| res[b].cmp(&res[a]));
+
+    let mut tiles = vec![Vec::new(); requests.len()];
+    let mut cursor: u64 = 0;
+    for i in items {
+        let size = res[i];
+        let area = size as u64 * size as u64;
+        let (bx, by) = morton_decode(cursor / area);
+        tiles[i].push(AtlasTile { x: bx * size, y: by * size, size });
+        cursor += area;
+    }
+    tiles
+}
+
+/// Split a Z-order index into its x (even bits) and y (odd bits) coordinates.
+fn morton_decode(code: u64) -> (u32, u32) {
+    fn compact(mut v: u64) -> u32 {
+        v &= 0x5555_5555_5555_5555;
+        v = (v | (v >> 1)) & 0x3333_3333_3333_3333;
+        v = (v | (v >> 2)) & 0x0F0F_0F0F_0F0F_0F0F;
+        v = (v | (v >> 4)) & 0x00FF_00FF_00FF_00FF;
+        v = (v | (v >> 8)) & 0x0000_FFFF_0000_FFFF;
+        v = (v | (v >> 16)) & 0x0000_0000_FFFF_FFFF;
+        v as u32
+    }
+    (compact(code), compact(code >> 1))
+}
+
+/// View-projection of cube face `face` (+X, -X, +Y, -Y, +Z, -Z) of a point
+/// light, with 0..1 depth.
+pub fn cube_face_view_proj(position: Vec3, range: f32, face: usize) -> Mat4 {
+    let (forward, up) = match face {
+        0 => (Vec3::X, Vec3::NEG_Y),
+        1 => (Vec3::NEG_X, Vec3::NEG_Y),
+        2 => (Vec3::Y, Vec3::Z),
+        3 => (Vec3::NEG_Y, Vec3::NEG_Z),
+        4 => (Vec3::Z, Vec3::NEG_Y),
+        _ => (Vec3::NEG_Z, Vec3::NEG_Y),
+    };
+    let proj = Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1.0, SHADOW_NEAR, range.max(SHADOW_NEAR * 2.0));
+    proj * Mat4::look_to_rh(position, forward, up)
+}
+
+/// View-projection of a spot light's cone, with 0..1 depth.
+pub fn spot_view_proj(position: Vec3, direction: Vec3, outer_cos: f32, range: f32) -> Mat4 {
+    let fov = (2.0 * outer_cos.clamp(-1.0, 1.0).acos()).clamp(0.01, 3.0);
+    let forward = direction.normalize_or(Vec3::NEG_Y);
+    let up = if forward.y.abs() > 0.99 { Vec3::Z } else { Vec3::Y };
+    let proj = Mat4::perspective_rh(fov, 1.0, SHADOW_NEAR, range.max(SHADOW_NEAR * 2.0));
+    proj * Mat4::look_to_rh(position, forward, up)
+}
+
+/// Shader record of one tile rendered with `view_proj`, whose projection has
+/// the given vertical field of view.
+pub fn local_shadow_data(view_proj: Mat4, fov: f32, tile: &AtlasTile, atlas_size: u32) -> LocalShadowData {
+    LocalShadowData {
+        view_proj: view_proj.to_cols_array_2d(),
+        atlas_rect: tile.uv_rect(atlas_size),
+        texel_size: 2.0 * (fov * 0.5).tan() / tile.size as f32,
+        depth_bias: 0.0005,
+        _pad1: 0.0,
+        _pad2: 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: AtlasConfig = AtlasConfig { size: 2048, min_tile: 64, max_tile: 1024 };
+
+    fn overlaps(a: &AtlasTile, b: &AtlasTile) -> bool {
+        a.x < b.x + b.size && b.x < a.x + a.size && a.y < b.y + b.size && b.y < a.y + a.size
+    }
+
+    #[test]
+    fn test_tiles_fit_without_overlap() {
+        let requests: Vec<ShadowRequest> = (0..12)
+            .map(|i| ShadowRequest { coverage: 1.0 - i as f32 * 0.08, faces: if i % 2 == 0 { 6 } else { 1 } })
+            .collect();
+        let tiles: Vec<AtlasTile> = allocate_tiles(&requests, &CONFIG).into_iter().flatten().collect();
+        assert!(!tiles.is_empty());
+        for (i, a) in tiles.iter().enumerate() {
+            assert!(a.x + a.size <= CONFIG.size && a.y + a.size <= CONFIG.size);
+            assert_eq!(a.x % a.size, 0);
+            assert_eq!(a.y % a.size, 0);
+            for b in &tiles[i + 1..] {
+                assert!(!overlaps(a, b), "{a:?} overlaps {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_budget_shrinks_least_covered_first() {
+        let requests = [
+            ShadowRequest { coverage: 1.0, faces: 1 },
+            ShadowRequest { coverage: 0.9, faces: 6 },
+            ShadowRequest { coverage: 0.1, faces: 6 },
+        ];
+        let res = budget_resolutions(&requests, &CONFIG);
+        assert_eq!(res[0], 1024);
+        assert!(res[2] <= res[1]);
+        let used: u64 = requests.iter().zip(&res).map(|(r, &s)| r.faces as u64 * (s as u64).pow(2)).sum();
+        assert!(used <= (CONFIG.size as u64).pow(2));
+
+        // Far more lights than the atlas holds at min_tile: the least covered are dropped
+        let crowd = vec![ShadowRequest { coverage: 0.5, faces: 6 }; 200];
+        let res = budget_resolutions(&crowd, &CONFIG);
+        assert!(res.contains(&0));
+        assert!(res.iter().all(|&s| s == 0 || s == CONFIG.min_tile));
+        let tiles = allocate_tiles(&crowd, &CONFIG);
+        assert!(tiles.iter().all(|t| t.is_empty() || t.len() == CUBE_FACES));
+    }
+
+    #[test]
+    fn test_cube_faces_cover_their_axis() {
+        let light = Vec3::new(1.0, 2.0, 3.0);
+        let axes = [Vec3::X, Vec3::NEG_X, Vec3::Y, Vec3::NEG_Y, Vec3::Z, Vec3::NEG_Z];
+        for (face, axis) in axes.iter().enumerate() {
+            let p = cube_face_view_proj(light, 10.0, face).project_point3(light + *axis * 5.0 + Vec3::splat(0.5));
+            assert!(p.x.abs() <= 1.0 && p.y.abs() <= 1.0, "face {face}: {p:?}");
+            assert!((0.0..=1.0).contains(&p.z));
+        }
+    }
+}
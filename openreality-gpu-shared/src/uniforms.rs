@@ -52,6 +52,41 @@ pub struct PerObjectUniforms {
     pub _pad: [f32; 4],
 }
 
+/// Inputs of the GPU instance culling pass (`instance_cull.wgsl`).
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct CullParams {
+    /// Frustum planes (xyz normal, w distance); only the first `plane_count` are tested.
+    pub planes: [[f32; 4]; 6],
+    pub instance_count: u32,
+    pub plane_count: u32,
+    pub _pad1: u32,
+    pub _pad2: u32,
+}
+
+/// Per-instance culling data: local bounding sphere and the batch it draws with.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct CullInstance {
+    /// Center xyz and radius in mesh space.
+    pub bounds: [f32; 4],
+    pub batch: u32,
+    pub _pad1: u32,
+    pub _pad2: u32,
+    pub _pad3: u32,
+}
+
+/// Arguments of one `draw_indexed_indirect` call, filled in by the culling pass.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct DrawIndexedIndirectArgs {
+    pub index_count: u32,
+    pub instance_count: u32,
+    pub first_index: u32,
+    pub base_vertex: i32,
+    pub first_instance: u32,
+}
+
 /// Point light data.
 #[repr(C)]
 #[derive(Clone, Copy, Pod, Zeroable)]
@@ -60,10 +95,70 @@ pub struct PointLightData {
     pub color: [f32; 4],
     pub intensity: f32,
     pub range: f32,
+    /// First of six `LocalShadowData` records (cube faces), or -1.
+    pub shadow_index: i32,
+    pub _pad: f32,
+}
+
+/// Spot light data. `inner_cos`/`outer_cos` are the cosines of the cone
+/// half-angles; intensity fades smoothly between them.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct SpotLightData {
+    pub position: [f32; 4],
+    pub direction: [f32; 4],
+    pub color: [f32; 4],
+    pub intensity: f32,
+    pub range: f32,
+    pub inner_cos: f32,
+    pub outer_cos: f32,
+    /// `LocalShadowData` record of this light, or -1.
+    pub shadow_index: i32,
+    pub _pad1: f32,
+    pub _pad2: f32,
+    pub _pad3: f32,
+}
+
+/// Shadow projection of one spot light or point light cube face, sampled
+/// from its tile of the shadow atlas.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct LocalShadowData {
+    pub view_proj: [[f32; 4]; 4],
+    /// Tile offset (xy) and size (zw) in atlas UVs.
+    pub atlas_rect: [f32; 4],
+    /// World-space size of one texel at unit distance from the light; scales
+    /// the normal offset applied before the depth comparison.
+    pub texel_size: f32,
+    pub depth_bias: f32,
     pub _pad1: f32,
     pub _pad2: f32,
 }
 
+/// Area light shape stored in `AreaLightData::shape`.
+pub const AREA_LIGHT_RECT: u32 = 0;
+pub const AREA_LIGHT_TUBE: u32 = 1;
+
+/// Rectangular or tube area light data.
+///
+/// A rect spans `position ± axis_x ± axis_y` and emits along
+/// `cross(axis_x, axis_y)`; `color * intensity` is its surface radiance.
+/// A tube runs from `position - axis_x` to `position + axis_x` with
+/// thickness `radius`; `color * intensity` is its total intensity, like a
+/// point light's.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct AreaLightData {
+    pub position: [f32; 4],
+    pub axis_x: [f32; 4],
+    pub axis_y: [f32; 4],
+    pub color: [f32; 4],
+    pub intensity: f32,
+    pub range: f32,
+    pub radius: f32,
+    pub shape: u32,
+}
+
 /// Directional light data.
 #[repr(C)]
 #[derive(Clone, Copy, Pod, Zeroable)]
@@ -77,15 +172,36 @@ pub struct DirLightData {
 }
 
 /// Light uniform buffer — matches GPU bind group 1, binding 0 in lighting pass.
+/// Point, spot and area lights live in separate storage buffers holding
+/// `num_point_lights`, `num_spot_lights` and `num_area_lights` entries.
 #[repr(C)]
 #[derive(Clone, Copy, Pod, Zeroable)]
 pub struct LightUniforms {
-    pub point_lights: [PointLightData; 16],
     pub dir_lights: [DirLightData; 4],
     pub num_point_lights: i32,
     pub num_dir_lights: i32,
     pub has_ibl: i32,
     pub ibl_intensity: f32,
+    pub num_spot_lights: i32,
+    pub num_area_lights: i32,
+    pub _pad1: i32,
+    pub _pad2: i32,
+}
+
+/// Light clustering parameters — shared by `light_cluster.wgsl` and the
+/// lighting shaders that read the per-cluster light lists.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct ClusterParams {
+    pub inv_projection: [[f32; 4]; 4],
+    pub view: [[f32; 4]; 4],
+    /// Clusters along x, y and z, then the point light count.
+    pub grid: [u32; 4],
+    /// View-space depth range split into exponential slices.
+    pub near: f32,
+    pub far: f32,
+    pub _pad1: f32,
+    pub _pad2: f32,
 }
 
 /// SSAO parameters.
@@ -132,7 +248,8 @@ pub struct TAAParams {
     pub screen_height: f32,
 }
 
-/// Post-processing parameters.
+/// Post-processing parameters of the bloom and tone mapping passes. The
+/// exposure comes from the `ExposureState` buffer instead.
 #[repr(C)]
 #[derive(Clone, Copy, Pod, Zeroable)]
 pub struct PostProcessParams {
@@ -141,20 +258,206 @@ pub struct PostProcessParams {
     pub gamma: f32,
     pub tone_mapping_mode: i32,
     pub horizontal: i32,
+    pub chromatic_aberration: f32,
+    pub vignette_intensity: f32,
+    pub vignette_radius: f32,
+    pub vignette_softness: f32,
+    pub grain_intensity: f32,
+    /// Changes every frame so the grain does not freeze.
+    pub grain_seed: f32,
+    /// Brightest tone mapped value relative to paper white; 1 on SDR outputs.
+    pub output_peak: f32,
+}
+
+/// Auto exposure parameters of `luminance_histogram.wgsl`.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct ExposureParams {
+    /// log2 luminance of the first non-black histogram bin, and the range of all of them.
+    pub min_log_lum: f32,
+    pub log_lum_range: f32,
+    pub delta_time: f32,
+    pub speed_up: f32,
+    pub speed_down: f32,
+    pub min_ev100: f32,
+    pub max_ev100: f32,
+    pub compensation: f32,
+    pub pixel_count: u32,
+    /// Nonzero to jump to the metered EV100 instead of adapting towards it.
+    pub snap: u32,
+    pub _pad1: u32,
+    pub _pad2: u32,
+}
+
+/// Exposure the tone mapping pass scales the scene by; written by the CPU,
+/// or by the auto exposure pass.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct ExposureState {
+    pub ev100: f32,
+    pub exposure: f32,
+}
+
+/// DOF circle of confusion parameters.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct DOFCoCParams {
+    pub focus_distance: f32,
+    pub focus_range: f32,
+    pub near_plane: f32,
+    pub far_plane: f32,
+}
+
+/// DOF separable blur parameters.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct DOFBlurParams {
+    pub horizontal: i32,
+    pub bokeh_radius: f32,
+    pub _pad1: f32,
+    pub _pad2: f32,
+}
+
+/// Motion blur velocity buffer parameters.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct VelocityParams {
+    pub inv_view_proj: [[f32; 4]; 4],
+    pub prev_view_proj: [[f32; 4]; 4],
+    pub max_velocity: f32,
     pub _pad1: f32,
     pub _pad2: f32,
     pub _pad3: f32,
 }
 
+/// Motion blur parameters.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct MotionBlurParams {
+    pub samples: i32,
+    pub intensity: f32,
+    pub _pad1: f32,
+    pub _pad2: f32,
+}
+
+/// Color grading parameters.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct ColorGradingParams {
+    pub brightness: f32,
+    pub contrast: f32,
+    pub saturation: f32,
+    /// 0 skips the 3D LUT.
+    pub lut_strength: f32,
+    pub lut_domain_min: [f32; 3],
+    pub lut_size: f32,
+    pub lut_domain_max: [f32; 3],
+    /// Largest gamma encoded value the output shows; 1 on SDR outputs.
+    pub output_max: f32,
+}
+
+/// Parameters of the present pass, which encodes the post-processed image
+/// for the surface (`display::OUTPUT_*`).
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct OutputParams {
+    pub encoding: u32,
+    /// Tone mapping gamma the post-process chain encoded with.
+    pub gamma: f32,
+    pub paper_white_nits: f32,
+    pub max_nits: f32,
+    /// RCAS sharpening of the scene, 0 (off) to 1; used on upscaled frames.
+    pub sharpness: f32,
+    pub _pad1: f32,
+    pub _pad2: f32,
+    pub _pad3: f32,
+}
+
+/// Parameters of the EASU upscale pass (`fsr_easu.wgsl`), in pixels.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct UpscaleParams {
+    pub input_size: [f32; 2],
+    pub output_size: [f32; 2],
+}
+
+/// UI overlay projection and texture mode (`ui.wgsl`).
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct UIUniforms {
+    /// Orthographic projection with a top-left origin, in pixels.
+    pub projection: [[f32; 4]; 4],
+    pub has_texture: i32,
+    pub is_font: i32,
+    pub _pad1: i32,
+    pub _pad2: i32,
+}
+
 /// Shadow cascade data.
 #[repr(C)]
 #[derive(Clone, Copy, Pod, Zeroable)]
 pub struct CascadeData {
     pub light_view_proj: [[f32; 4]; 4],
+    /// View-space distance where this cascade ends.
     pub split_depth: f32,
-    pub _pad1: f32,
-    pub _pad2: f32,
-    pub _pad3: f32,
+    /// World-space size of one shadow map texel.
+    pub texel_size: f32,
+    /// World-space distance covered by the cascade's 0..1 depth range.
+    pub depth_range: f32,
+    pub _pad: f32,
+}
+
+/// CSM filters selected by `CsmSettings::filter_mode`.
+pub const SHADOW_FILTER_HARD: u32 = 0;
+pub const SHADOW_FILTER_PCF: u32 = 1;
+pub const SHADOW_FILTER_PCSS: u32 = 2;
+pub const SHADOW_FILTER_EVSM: u32 = 3;
+
+/// Most Poisson taps a PCF or PCSS lookup takes.
+pub const MAX_PCF_SAMPLES: u32 = 32;
+
+/// Filtering and bias settings of the cascaded shadow maps, set through
+/// `or_wgpu_set_csm_settings`. Biases and radii are in shadow map texels.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct CsmSettings {
+    pub filter_mode: u32,
+    /// Poisson taps per PCF/PCSS lookup, 1..=`MAX_PCF_SAMPLES`.
+    pub pcf_samples: u32,
+    pub pcf_radius: f32,
+    /// PCSS: penumbra width per world unit between blocker and receiver.
+    pub light_size: f32,
+    /// Fraction of each cascade's view-depth span cross-faded into the next.
+    pub blend_band: f32,
+    pub depth_bias: f32,
+    /// Receiver offset along its normal.
+    pub normal_bias: f32,
+    /// Extra depth bias scaled by the tangent of the light's incidence angle.
+    pub slope_bias: f32,
+    /// EVSM positive and negative warp exponents.
+    pub evsm_exponents: [f32; 2],
+    /// EVSM: visibility below this fraction is cut to zero against light bleeding.
+    pub light_bleed_reduction: f32,
+    /// Snap cascades to whole texels so they do not shimmer as the camera moves (0/1).
+    pub stabilize: u32,
+}
+
+impl Default for CsmSettings {
+    fn default() -> Self {
+        Self {
+            filter_mode: SHADOW_FILTER_PCF,
+            pcf_samples: 16,
+            pcf_radius: 1.5,
+            light_size: 0.02,
+            blend_band: 0.1,
+            depth_bias: 1.0,
+            normal_bias: 1.0,
+            slope_bias: 1.0,
+            evsm_exponents: [5.0, 5.0],
+            light_bleed_reduction: 0.2,
+            stabilize: 1,
+        }
+    }
 }
 
 /// Shadow uniform buffer.
@@ -163,7 +466,166 @@ pub struct CascadeData {
 pub struct ShadowUniforms {
     pub cascades: [CascadeData; 4],
     pub num_cascades: i32,
-    pub shadow_bias: f32,
     pub _pad1: f32,
     pub _pad2: f32,
+    pub _pad3: f32,
+    pub settings: CsmSettings,
+}
+
+/// Sky, IBL and fog lookup inputs of the lighting and forward passes.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct EnvironmentUniforms {
+    /// Irradiance SH of the sky (`atmosphere::IrradianceSH`), rgb per coefficient.
+    pub irradiance_sh: [[f32; 4]; 9],
+    /// xyz = unit direction towards the sun, w = cosine of the disk's angular radius.
+    pub sun_direction: [f32; 4],
+    /// rgb = radiance of the sun disk.
+    pub sun_radiance: [f32; 4],
+    pub has_sky: i32,
+    pub fog_enabled: i32,
+    /// View depths the fog volume's slices span.
+    pub fog_near: f32,
+    pub fog_far: f32,
+    pub sky_mip_count: f32,
+    pub _pad1: f32,
+    pub _pad2: f32,
+    pub _pad3: f32,
+}
+
+/// Froxel grid and medium of the fog inject and integrate passes.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct FogUniforms {
+    pub inv_projection: [[f32; 4]; 4],
+    pub inv_view: [[f32; 4]; 4],
+    /// xyz = froxel grid size.
+    pub grid: [u32; 4],
+    /// rgb = single scattering albedo, w = ambient intensity.
+    pub albedo: [f32; 4],
+    pub near: f32,
+    pub far: f32,
+    pub density: f32,
+    pub height_density: f32,
+    pub height_falloff: f32,
+    pub base_height: f32,
+    pub anisotropy: f32,
+    pub _pad1: f32,
+}
+
+/// One deferred decal (`decal.wgsl`), built by `DecalData::new`. The decal is
+/// the unit box under `model`, projected along `-normal`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct DecalData {
+    pub model: [[f32; 4]; 4],
+    pub world_to_decal: [[f32; 4]; 4],
+    /// World directions of the box's x, y and z axes (unit length).
+    pub tangent: [f32; 4],
+    pub bitangent: [f32; 4],
+    pub normal: [f32; 4],
+    /// rgb = albedo tint, a = albedo opacity.
+    pub color: [f32; 4],
+    pub normal_strength: f32,
+    pub roughness: f32,
+    pub roughness_opacity: f32,
+    /// Cosines between the surface normal and `normal` at which the angle
+    /// fade starts and where the decal vanishes.
+    pub fade_start: f32,
+    pub fade_end: f32,
+    pub has_albedo_map: u32,
+    pub has_normal_map: u32,
+    pub _pad1: f32,
+}
+
+/// GPU particle emitter descriptor. Uploaded when an emitter is created or
+/// reconfigured; mirrors `ParticleSystemComponent` with curves baked to
+/// `particles::CURVE_LUT_SIZE` samples.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct ParticleEmitterParams {
+    pub velocity_min: [f32; 4],
+    pub velocity_max: [f32; 4],
+    /// rgb + start alpha
+    pub start_color: [f32; 4],
+    /// rgb + end alpha
+    pub end_color: [f32; 4],
+    /// Sphere: radius, surface-only (0/1); cone: half-angle, radius; box: half extents.
+    pub shape_params: [f32; 4],
+    pub max_particles: u32,
+    pub emission_rate: f32,
+    pub lifetime_min: f32,
+    pub lifetime_max: f32,
+    pub gravity_modifier: f32,
+    pub damping: f32,
+    pub start_size_min: f32,
+    pub start_size_max: f32,
+    pub end_size: f32,
+    /// 0 = alpha, 1 = additive, 2 = premultiplied
+    pub blend_mode: u32,
+    /// 0 = point, 1 = sphere, 2 = cone, 3 = box, 4 = mesh surface
+    pub shape_kind: u32,
+    /// Set by the backend from the uploaded emission mesh.
+    pub mesh_triangle_count: u32,
+    /// 0 = none, 1 = ground plane, 2 = depth buffer
+    pub collision_mode: u32,
+    pub plane_height: f32,
+    pub bounce: f32,
+    pub friction: f32,
+    pub soft_distance: f32,
+    pub sheet_tiles_x: u32,
+    pub sheet_tiles_y: u32,
+    pub sheet_frames: u32,
+    /// Frames per second; 0 plays the sheet once over the lifetime.
+    pub sheet_fps: f32,
+    /// Bit 0: color curve, bit 1: size curve, bit 2: velocity curve.
+    pub curve_flags: u32,
+    /// Set by the backend when a sprite texture is bound.
+    pub has_texture: u32,
+    pub _pad1: f32,
+    pub color_lut: [[f32; 4]; 8],
+    /// x = size multiplier, yzw = velocity offset
+    pub size_velocity_lut: [[f32; 4]; 8],
+}
+
+/// Per-frame simulation inputs for one GPU particle emitter.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct ParticleSimParams {
+    pub emitter_transform: [[f32; 4]; 4],
+    pub view_proj: [[f32; 4]; 4],
+    pub inv_view_proj: [[f32; 4]; 4],
+    pub camera_pos: [f32; 4],
+    pub screen_size: [f32; 2],
+    pub dt: f32,
+    pub spawn_count: u32,
+    pub seed: u32,
+    /// Power-of-two length of the sort key range.
+    pub sort_count: u32,
+    pub _pad1: u32,
+    pub _pad2: u32,
+}
+
+/// Camera and per-draw settings for particle rendering.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct ParticleDrawUniforms {
+    pub view: [[f32; 4]; 4],
+    pub projection: [[f32; 4]; 4],
+    pub inv_projection: [[f32; 4]; 4],
+    /// CPU particle pass only; GPU emitters carry these in their params.
+    pub soft_distance: f32,
+    pub blend_mode: u32,
+    pub has_texture: u32,
+    pub _pad1: u32,
+}
+
+/// One bitonic sort step (bound with a dynamic offset per dispatch).
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct ParticleSortParams {
+    pub j: u32,
+    pub k: u32,
+    pub _pad1: u32,
+    pub _pad2: u32,
 }
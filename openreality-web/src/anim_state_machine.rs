@@ -0,0 +1,297 @@
+use openreality_gpu_shared::scene_format::{
+    AnimConditionParsed, AnimLayerParsed, AnimParamKind, AnimParameterParsed, AnimStateMachineParsed,
+    AnimTransitionParsed, ConditionOp,
+};
+
+use crate::scene::{AnimationLayer, AnimationState, ClipPlayback, LoadedScene};
+
+/// Per-layer runtime state of a state machine.
+#[derive(Clone)]
+pub struct StateMachineLayer {
+    pub def: AnimLayerParsed,
+    pub current_state: usize,
+    /// Seconds spent in the current state (scaled by state speed).
+    pub state_time: f32,
+}
+
+/// Runtime instance of an ORSB animation state machine. Drives the layers of
+/// `animations[animation_index]` by cross-fading between states.
+#[derive(Clone)]
+pub struct AnimStateMachine {
+    pub animation_index: usize,
+    pub parameters: Vec<AnimParameterParsed>,
+    /// Current parameter values (bools and triggers are 0.0 or 1.0).
+    pub values: Vec<f32>,
+    pub layers: Vec<StateMachineLayer>,
+}
+
+impl AnimStateMachine {
+    /// Build the instance and configure `anim`'s layers to match, starting
+    /// every layer in its default state.
+    pub fn new(def: AnimStateMachineParsed, anim: &mut AnimationState, num_entities: usize) -> Self {
+        let values = def.parameters.iter().map(|p| p.default).collect();
+        let layers: Vec<StateMachineLayer> = def
+            .layers
+            .into_iter()
+            .map(|layer| {
+                let current_state = (layer.default_state as usize).min(layer.states.len().saturating_sub(1));
+                StateMachineLayer { def: layer, current_state, state_time: 0.0 }
+            })
+            .collect();
+
+        anim.layers.truncate(layers.len().max(1));
+        for (i, layer) in layers.iter().enumerate() {
+            let mask = (!layer.def.mask.is_empty()).then(|| {
+                let mut flags = vec![false; num_entities];
+                for &e in &layer.def.mask {
+                    if let Some(f) = flags.get_mut(e as usize) {
+                        *f = true;
+                    }
+                }
+                flags
+            });
+            let mut anim_layer = AnimationLayer::new(&layer.def.name, layer.def.blend_mode, layer.def.weight, mask);
+            if let Some(state) = layer.def.states.get(layer.current_state) {
+                anim_layer.tracks.push(ClipPlayback {
+                    speed: state.speed,
+                    ..ClipPlayback::new(state.clip as usize, state.looping)
+                });
+            }
+            if i < anim.layers.len() {
+                anim.layers[i] = anim_layer;
+            } else {
+                anim.layers.push(anim_layer);
+            }
+        }
+        if !layers.is_empty() {
+            anim.playing = true;
+        }
+
+        Self {
+            animation_index: def.animation_index as usize,
+            parameters: def.parameters,
+            values,
+            layers,
+        }
+    }
+
+    fn parameter(&self, name: &str) -> Option<usize> {
+        self.parameters.iter().position(|p| p.name == name)
+    }
+
+    /// Set a float parameter. Returns false if no such parameter exists.
+    pub fn set_float(&mut self, name: &str, value: f32) -> bool {
+        match self.parameter(name) {
+            Some(i) => {
+                self.values[i] = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn set_bool(&mut self, name: &str, value: bool) -> bool {
+        self.set_float(name, if value { 1.0 } else { 0.0 })
+    }
+
+    /// Set a trigger; it stays set until a transition consumes it.
+    pub fn set_trigger(&mut self, name: &str) -> bool {
+        match self.parameter(name) {
+            Some(i) if self.parameters[i].kind == AnimParamKind::Trigger => {
+                self.values[i] = 1.0;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn condition_holds(&self, cond: &AnimConditionParsed) -> bool {
+        let Some(&v) = self.values.get(cond.parameter as usize) else { return false };
+        match cond.op {
+            ConditionOp::Greater => v > cond.threshold,
+            ConditionOp::Less => v < cond.threshold,
+            ConditionOp::Equals => v == cond.threshold,
+            ConditionOp::NotEquals => v != cond.threshold,
+            ConditionOp::True | ConditionOp::Trigger => v != 0.0,
+            ConditionOp::False => v == 0.0,
+        }
+    }
+
+    fn transition_ready(&self, layer: &StateMachineLayer, tr: &AnimTransitionParsed, anim: &AnimationState) -> bool {
+        match tr.from {
+            Some(from) if from as usize != layer.current_state => return false,
+            // Any-state transitions never re-enter the state they target
+            None if tr.to as usize == layer.current_state => return false,
+            _ => {}
+        }
+        if let Some(exit_time) = tr.exit_time {
+            let clip = layer.def.states.get(layer.current_state).map(|s| s.clip as usize);
+            let duration = clip.and_then(|c| anim.clips.get(c)).map_or(0.0, |c| c.duration);
+            if duration > 0.0 && layer.state_time / duration < exit_time {
+                return false;
+            }
+        }
+        tr.conditions.iter().all(|c| self.condition_holds(c))
+    }
+
+    /// Advance state timers and fire at most one transition per layer.
+    pub fn update(&mut self, anim: &mut AnimationState, dt: f32) {
+        for li in 0..self.layers.len() {
+            let layer = &self.layers[li];
+            let speed = layer.def.states.get(layer.current_state).map_or(1.0, |s| s.speed);
+            self.layers[li].state_time += dt * anim.speed * speed.abs();
+
+            let layer = &self.layers[li];
+            let fired = layer
+                .def
+                .transitions
+                .iter()
+                .find(|tr| self.transition_ready(layer, tr, anim))
+                .cloned();
+            let Some(tr) = fired else { continue };
+            let Some(state) = self.layers[li].def.states.get(tr.to as usize).cloned() else { continue };
+
+            for cond in &tr.conditions {
+                if cond.op == ConditionOp::Trigger {
+                    if let Some(v) = self.values.get_mut(cond.parameter as usize) {
+                        *v = 0.0;
+                    }
+                }
+            }
+
+            let playback = ClipPlayback { speed: state.speed, ..ClipPlayback::new(state.clip as usize, state.looping) };
+            anim.cross_fade_playback(li, playback, tr.duration);
+            self.layers[li].current_state = tr.to as usize;
+            self.layers[li].state_time = 0.0;
+        }
+    }
+}
+
+/// Tick every state machine. Runs before `update_animations` so transitions
+/// fired this frame are sampled immediately.
+pub fn update_state_machines(scene: &mut LoadedScene, dt: f32) {
+    let LoadedScene { state_machines, animations, .. } = scene;
+    for sm in state_machines.iter_mut() {
+        if let Some(anim) = animations.get_mut(sm.animation_index) {
+            sm.update(anim, dt);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::AnimationClip;
+    use openreality_gpu_shared::scene_format::{AnimStateParsed, LayerBlendMode};
+
+    fn state(name: &str, clip: u32, looping: bool) -> AnimStateParsed {
+        AnimStateParsed { name: name.to_string(), clip, speed: 1.0, looping }
+    }
+
+    fn when(parameter: u16, op: ConditionOp, threshold: f32) -> AnimConditionParsed {
+        AnimConditionParsed { parameter, op, threshold }
+    }
+
+    /// idle -> run while speed > 0.5, any state -> jump on the trigger, and
+    /// jump -> idle once its clip has played through.
+    fn locomotion() -> (AnimStateMachine, AnimationState) {
+        let clip = |name: &str| AnimationClip { name: name.to_string(), duration: 1.0, channels: Vec::new() };
+        let mut anim = AnimationState {
+            clips: vec![clip("idle"), clip("run"), clip("jump")],
+            layers: Vec::new(),
+            playing: false,
+            speed: 1.0,
+        };
+        let transition = |from, to, duration, exit_time, conditions| AnimTransitionParsed {
+            from,
+            to,
+            duration,
+            exit_time,
+            conditions,
+        };
+        let def = AnimStateMachineParsed {
+            animation_index: 0,
+            parameters: vec![
+                AnimParameterParsed { name: "speed".to_string(), kind: AnimParamKind::Float, default: 0.0 },
+                AnimParameterParsed { name: "jump".to_string(), kind: AnimParamKind::Trigger, default: 0.0 },
+            ],
+            layers: vec![AnimLayerParsed {
+                name: "base".to_string(),
+                weight: 1.0,
+                blend_mode: LayerBlendMode::Override,
+                mask: Vec::new(),
+                states: vec![state("idle", 0, true), state("run", 1, true), state("jump", 2, false)],
+                default_state: 0,
+                transitions: vec![
+                    transition(Some(0), 1, 0.2, None, vec![when(0, ConditionOp::Greater, 0.5)]),
+                    transition(None, 2, 0.1, None, vec![when(1, ConditionOp::Trigger, 0.0)]),
+                    transition(Some(2), 0, 0.0, Some(1.0), Vec::new()),
+                ],
+            }],
+        };
+        let sm = AnimStateMachine::new(def, &mut anim, 1);
+        (sm, anim)
+    }
+
+    fn playing_clip(anim: &AnimationState) -> usize {
+        anim.layers[0].tracks[0].clip
+    }
+
+    #[test]
+    fn test_starts_in_default_state() {
+        let (sm, anim) = locomotion();
+        assert_eq!(sm.layers[0].current_state, 0);
+        assert_eq!(playing_clip(&anim), 0);
+        assert!(anim.playing);
+    }
+
+    #[test]
+    fn test_transition_fires_when_condition_holds() {
+        let (mut sm, mut anim) = locomotion();
+        sm.update(&mut anim, 0.1);
+        assert_eq!(sm.layers[0].current_state, 0);
+
+        assert!(sm.set_float("speed", 1.0));
+        sm.update(&mut anim, 0.1);
+        assert_eq!(sm.layers[0].current_state, 1);
+        assert_eq!(sm.layers[0].state_time, 0.0);
+        assert_eq!(playing_clip(&anim), 1);
+        let fade = anim.layers[0].fade.as_ref().unwrap();
+        assert_eq!(fade.duration, 0.2);
+        assert_eq!(fade.from[0].clip, 0);
+    }
+
+    #[test]
+    fn test_trigger_is_consumed_by_its_transition() {
+        let (mut sm, mut anim) = locomotion();
+        assert!(sm.set_trigger("jump"));
+        sm.update(&mut anim, 0.1);
+        assert_eq!(sm.layers[0].current_state, 2);
+        assert_eq!(sm.values[1], 0.0);
+
+        // Any-state transitions don't re-enter their own target
+        sm.update(&mut anim, 0.1);
+        assert_eq!(sm.layers[0].current_state, 2);
+    }
+
+    #[test]
+    fn test_exit_time_holds_state_until_clip_ends() {
+        let (mut sm, mut anim) = locomotion();
+        sm.set_trigger("jump");
+        sm.update(&mut anim, 0.0);
+        sm.update(&mut anim, 0.6);
+        assert_eq!(sm.layers[0].current_state, 2);
+        sm.update(&mut anim, 0.6);
+        assert_eq!(sm.layers[0].current_state, 0);
+        assert!(anim.layers[0].fade.is_none());
+    }
+
+    #[test]
+    fn test_parameter_setters_check_name_and_kind() {
+        let (mut sm, _) = locomotion();
+        assert!(!sm.set_float("missing", 1.0));
+        assert!(!sm.set_trigger("speed"));
+        assert!(sm.set_bool("speed", true));
+        assert_eq!(sm.values[0], 1.0);
+    }
+}
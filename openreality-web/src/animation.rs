@@ -1,179 +1,489 @@
-use glam::{DVec3, DQuat};
+use std::collections::HashMap;
 
-use crate::scene::LoadedScene;
-use openreality_gpu_shared::scene_format::{InterpolationMode, TargetProperty};
+use glam::{DQuat, DVec3, DVec4};
 
-/// Update all animation playback states and apply interpolated values to transforms.
-pub fn update_animations(scene: &mut LoadedScene, dt: f32) {
-    for anim_state in &mut scene.animations {
-        if !anim_state.playing {
-            continue;
+use crate::events::GameEvent;
+use crate::scene::{AnimationClip, AnimationLayer, AnimationState, ClipPlayback, CrossFade, Entity, LoadedScene};
+use openreality_gpu_shared::animation::{sample_quat, sample_vec3, sample_weights, slerp, values_per_key};
+use openreality_gpu_shared::scene_format::{LayerBlendMode, TargetProperty};
+
+impl ClipPlayback {
+    pub fn new(clip: usize, looping: bool) -> Self {
+        Self { clip, time: 0.0, speed: 1.0, looping, weight: 1.0 }
+    }
+
+    /// Advance playback by `dt`. Returns true once a non-looping clip has ended.
+    fn advance(&mut self, duration: f32, dt: f32) -> bool {
+        if duration <= 0.0 {
+            self.time = 0.0;
+            return !self.looping;
+        }
+        self.time += dt * self.speed;
+        if self.looping {
+            self.time = self.time.rem_euclid(duration);
+            false
+        } else {
+            self.time = self.time.clamp(0.0, duration);
+            (self.speed >= 0.0 && self.time >= duration) || (self.speed < 0.0 && self.time <= 0.0)
         }
+    }
+}
 
-        let clip_idx = anim_state.active_clip;
-        if clip_idx < 0 || clip_idx as usize >= anim_state.clips.len() {
-            continue;
+impl AnimationLayer {
+    pub fn new(name: &str, blend_mode: LayerBlendMode, weight: f32, mask: Option<Vec<bool>>) -> Self {
+        Self { name: name.to_string(), weight, blend_mode, mask, tracks: Vec::new(), fade: None }
+    }
+
+    fn affects(&self, entity_index: usize) -> bool {
+        match &self.mask {
+            Some(mask) => mask.get(entity_index).copied().unwrap_or(false),
+            None => true,
         }
+    }
+}
 
-        // Advance time
-        anim_state.current_time += dt * anim_state.speed;
+impl AnimationState {
+    pub fn find_clip(&self, name: &str) -> Option<usize> {
+        self.clips.iter().position(|c| c.name == name)
+    }
 
-        let duration = anim_state.clips[clip_idx as usize].duration;
-        if anim_state.current_time > duration {
-            if anim_state.looping {
-                anim_state.current_time %= duration;
-            } else {
-                anim_state.current_time = duration;
-                anim_state.playing = false;
-            }
+    /// Immediately play `clip` on the base layer, replacing any blend.
+    pub fn play(&mut self, clip: usize, looping: bool) {
+        if let Some(base) = self.layers.first_mut() {
+            base.tracks = vec![ClipPlayback::new(clip, looping)];
+            base.fade = None;
+            self.playing = true;
         }
+    }
 
-        let time = anim_state.current_time;
+    /// Fade from whatever `layer` is playing to `clip` over `duration` seconds.
+    pub fn cross_fade(&mut self, layer: usize, clip: usize, duration: f32, looping: bool) {
+        self.cross_fade_playback(layer, ClipPlayback::new(clip, looping), duration);
+    }
 
-        // Apply each channel
-        let clip = &anim_state.clips[clip_idx as usize];
-        for channel in &clip.channels {
-            let target_idx = channel.target_entity_index;
-            if target_idx >= scene.entities.len() {
-                continue;
-            }
+    pub(crate) fn cross_fade_playback(&mut self, layer: usize, playback: ClipPlayback, duration: f32) {
+        let Some(l) = self.layers.get_mut(layer) else { return };
+        let from = std::mem::replace(&mut l.tracks, vec![playback]);
+        l.fade = if duration > 0.0 && !from.is_empty() {
+            Some(CrossFade { from, elapsed: 0.0, duration })
+        } else {
+            None
+        };
+        self.playing = true;
+    }
 
-            // Find bounding keyframes via binary search
-            let key_idx = find_keyframe_index(&channel.times, time);
-            if key_idx.is_none() {
-                continue;
+    /// Play several clips on `layer` at once with relative weights.
+    /// Clips that were already playing keep their time so the blend stays in sync.
+    pub fn set_blend(&mut self, layer: usize, clips: &[(usize, f32)], looping: bool) {
+        let Some(l) = self.layers.get_mut(layer) else { return };
+        let tracks = clips
+            .iter()
+            .map(|&(clip, weight)| {
+                let mut track = l
+                    .tracks
+                    .iter()
+                    .find(|t| t.clip == clip)
+                    .cloned()
+                    .unwrap_or_else(|| ClipPlayback::new(clip, looping));
+                track.weight = weight;
+                track
+            })
+            .collect();
+        l.tracks = tracks;
+        self.playing = true;
+    }
+
+    /// Add a layer on top of the existing ones and return its index.
+    pub fn add_layer(&mut self, name: &str, blend_mode: LayerBlendMode, weight: f32, mask: Option<Vec<bool>>) -> usize {
+        self.layers.push(AnimationLayer::new(name, blend_mode, weight, mask));
+        self.layers.len() - 1
+    }
+}
+
+/// Properties produced by sampling; missing properties are left untouched.
+#[derive(Clone, Default)]
+struct PoseSample {
+    position: Option<DVec3>,
+    rotation: Option<DQuat>,
+    scale: Option<DVec3>,
+    weights: Option<Vec<f64>>,
+}
+
+type Pose = HashMap<usize, PoseSample>;
+
+/// Weighted running sums for blending any number of clips in one pass.
+#[derive(Default)]
+struct PoseAccumulator {
+    position: (DVec3, f64),
+    rotation: (Option<DQuat>, DVec4, f64),
+    scale: (DVec3, f64),
+    weights: (Vec<f64>, f64),
+}
+
+impl PoseAccumulator {
+    fn add_rotation(&mut self, q: DQuat, w: f64) {
+        // Keep every contribution in the hemisphere of the first so they don't cancel
+        let reference = *self.rotation.0.get_or_insert(q);
+        let q = if reference.dot(q) < 0.0 { -q } else { q };
+        self.rotation.1 += DVec4::from(q) * w;
+        self.rotation.2 += w;
+    }
+
+    fn finish(&self) -> PoseSample {
+        PoseSample {
+            position: (self.position.1 > 0.0).then(|| self.position.0 / self.position.1),
+            rotation: (self.rotation.2 > 0.0).then(|| DQuat::from_vec4(self.rotation.1).normalize()),
+            scale: (self.scale.1 > 0.0).then(|| self.scale.0 / self.scale.1),
+            weights: (self.weights.1 > 0.0).then(|| self.weights.0.iter().map(|w| w / self.weights.1).collect()),
+        }
+    }
+}
+
+fn accumulate_clip(acc: &mut HashMap<usize, PoseAccumulator>, clip: &AnimationClip, time: f32, weight: f64) {
+    if weight <= 0.0 {
+        return;
+    }
+    for channel in &clip.channels {
+        let slot = acc.entry(channel.target_entity_index).or_default();
+        match channel.target_property {
+            TargetProperty::Position => {
+                if let Some(v) = sample_vec3(channel.interpolation, &channel.times, &channel.values, time) {
+                    slot.position.0 += v * weight;
+                    slot.position.1 += weight;
+                }
             }
-            let (i0, i1, t) = key_idx.unwrap();
-
-            match channel.target_property {
-                TargetProperty::Position => {
-                    let v0 = get_vec3(&channel.values, i0);
-                    let v1 = get_vec3(&channel.values, i1);
-                    let interpolated = match channel.interpolation {
-                        InterpolationMode::Step => v0,
-                        InterpolationMode::Linear | InterpolationMode::CubicSpline => {
-                            lerp_vec3(v0, v1, t as f64)
-                        }
-                    };
-                    scene.entities[target_idx].transform.position = interpolated;
-                    scene.entities[target_idx].transform.dirty = true;
+            TargetProperty::Rotation => {
+                if let Some(q) = sample_quat(channel.interpolation, &channel.times, &channel.values, time) {
+                    slot.add_rotation(q, weight);
                 }
-                TargetProperty::Rotation => {
-                    let q0 = get_quat(&channel.values, i0);
-                    let q1 = get_quat(&channel.values, i1);
-                    let interpolated = match channel.interpolation {
-                        InterpolationMode::Step => q0,
-                        InterpolationMode::Linear | InterpolationMode::CubicSpline => {
-                            slerp_quat(q0, q1, t as f64)
-                        }
-                    };
-                    scene.entities[target_idx].transform.rotation = interpolated;
-                    scene.entities[target_idx].transform.dirty = true;
+            }
+            TargetProperty::Scale => {
+                if let Some(v) = sample_vec3(channel.interpolation, &channel.times, &channel.values, time) {
+                    slot.scale.0 += v * weight;
+                    slot.scale.1 += weight;
                 }
-                TargetProperty::Scale => {
-                    let v0 = get_vec3(&channel.values, i0);
-                    let v1 = get_vec3(&channel.values, i1);
-                    let interpolated = match channel.interpolation {
-                        InterpolationMode::Step => v0,
-                        InterpolationMode::Linear | InterpolationMode::CubicSpline => {
-                            lerp_vec3(v0, v1, t as f64)
-                        }
-                    };
-                    scene.entities[target_idx].transform.scale = interpolated;
-                    scene.entities[target_idx].transform.dirty = true;
+            }
+            TargetProperty::MorphWeights => {
+                let per_key = channel.times.len() * values_per_key(channel.interpolation);
+                let count = channel.values.len().checked_div(per_key).unwrap_or(0);
+                if let Some(w) = sample_weights(channel.interpolation, &channel.times, &channel.values, count, time) {
+                    let sum = &mut slot.weights.0;
+                    if sum.len() < w.len() {
+                        sum.resize(w.len(), 0.0);
+                    }
+                    for (acc, v) in sum.iter_mut().zip(&w) {
+                        *acc += v * weight;
+                    }
+                    slot.weights.1 += weight;
                 }
             }
         }
     }
 }
 
-/// Binary search for the keyframe interval containing `time`.
-/// Returns (index0, index1, interpolation_factor) or None.
-fn find_keyframe_index(times: &[f32], time: f32) -> Option<(usize, usize, f32)> {
-    if times.is_empty() {
-        return None;
-    }
-    if times.len() == 1 {
-        return Some((0, 0, 0.0));
+/// Weighted blend of `tracks`. `at_start` samples every clip at its first frame
+/// (the reference pose for additive layers).
+fn sample_tracks(clips: &[AnimationClip], tracks: &[ClipPlayback], at_start: bool) -> Pose {
+    let mut acc = HashMap::new();
+    for track in tracks {
+        if let Some(clip) = clips.get(track.clip) {
+            let time = if at_start { 0.0 } else { track.time };
+            accumulate_clip(&mut acc, clip, time, track.weight as f64);
+        }
     }
-    if time <= times[0] {
-        return Some((0, 0, 0.0));
+    acc.into_iter().map(|(e, a)| (e, a.finish())).collect()
+}
+
+fn lerp_weights(a: &[f64], b: &[f64], t: f64) -> Vec<f64> {
+    let n = a.len().max(b.len());
+    (0..n)
+        .map(|i| {
+            let a = a.get(i).copied().unwrap_or(0.0);
+            let b = b.get(i).copied().unwrap_or(0.0);
+            a + (b - a) * t
+        })
+        .collect()
+}
+
+fn mix_sample(a: PoseSample, b: PoseSample, t: f64) -> PoseSample {
+    fn mix<T>(a: Option<T>, b: Option<T>, f: impl Fn(T, T) -> T) -> Option<T> {
+        match (a, b) {
+            (Some(a), Some(b)) => Some(f(a, b)),
+            (a, b) => b.or(a),
+        }
     }
-    if time >= times[times.len() - 1] {
-        let last = times.len() - 1;
-        return Some((last, last, 0.0));
+    PoseSample {
+        position: mix(a.position, b.position, |a, b| a.lerp(b, t)),
+        rotation: mix(a.rotation, b.rotation, |a, b| slerp(a, b, t)),
+        scale: mix(a.scale, b.scale, |a, b| a.lerp(b, t)),
+        weights: mix(a.weights, b.weights, |a, b| lerp_weights(&a, &b, t)),
     }
+}
 
-    // Binary search
-    let mut lo = 0;
-    let mut hi = times.len() - 1;
-    while lo < hi - 1 {
-        let mid = (lo + hi) / 2;
-        if times[mid] <= time {
-            lo = mid;
-        } else {
-            hi = mid;
-        }
+fn sample_layer(clips: &[AnimationClip], layer: &AnimationLayer, at_start: bool) -> Pose {
+    let to = sample_tracks(clips, &layer.tracks, at_start);
+    let Some(fade) = &layer.fade else { return to };
+
+    let t = (fade.elapsed / fade.duration).clamp(0.0, 1.0) as f64;
+    let mut pose = sample_tracks(clips, &fade.from, at_start);
+    for (e, to_sample) in to {
+        let from_sample = pose.remove(&e).unwrap_or_default();
+        pose.insert(e, mix_sample(from_sample, to_sample, t));
     }
+    // Properties the incoming clips don't animate hold the outgoing value until the fade ends
+    pose
+}
 
-    let t0 = times[lo];
-    let t1 = times[hi];
-    let factor = if (t1 - t0).abs() < 1e-8 {
-        0.0
-    } else {
-        (time - t0) / (t1 - t0)
-    };
+/// Current value of an entity property: already-blended output, else the entity's own state.
+fn base_sample(result: &Pose, entities: &[Entity], e: usize) -> PoseSample {
+    let entity = &entities[e];
+    let t = &entity.transform;
+    let current = result.get(&e).cloned().unwrap_or_default();
+    let morph_weights = || entity.morph.as_ref().map(|m| m.weights.iter().map(|&w| w as f64).collect());
+    PoseSample {
+        position: current.position.or(Some(t.position)),
+        rotation: current.rotation.or(Some(t.rotation)),
+        scale: current.scale.or(Some(t.scale)),
+        weights: current.weights.or_else(morph_weights),
+    }
+}
 
-    Some((lo, hi, factor))
+fn apply_override(result: &mut Pose, entities: &[Entity], layer: &AnimationLayer, pose: Pose) {
+    let w = layer.weight.clamp(0.0, 1.0) as f64;
+    for (e, sample) in pose {
+        if e >= entities.len() || !layer.affects(e) {
+            continue;
+        }
+        let base = base_sample(result, entities, e);
+        let out = result.entry(e).or_default();
+        if let Some(p) = sample.position {
+            out.position = Some(base.position.unwrap().lerp(p, w));
+        }
+        if let Some(q) = sample.rotation {
+            out.rotation = Some(slerp(base.rotation.unwrap(), q, w));
+        }
+        if let Some(s) = sample.scale {
+            out.scale = Some(base.scale.unwrap().lerp(s, w));
+        }
+        if let Some(mw) = sample.weights {
+            out.weights = Some(lerp_weights(&base.weights.unwrap_or_default(), &mw, w));
+        }
+    }
 }
 
-fn get_vec3(values: &[f64], index: usize) -> DVec3 {
-    let i = index * 3;
-    DVec3::new(values[i], values[i + 1], values[i + 2])
+fn apply_additive(result: &mut Pose, entities: &[Entity], layer: &AnimationLayer, pose: Pose, reference: Pose) {
+    let w = layer.weight as f64;
+    for (e, sample) in pose {
+        if e >= entities.len() || !layer.affects(e) {
+            continue;
+        }
+        let reference = reference.get(&e).cloned().unwrap_or_default();
+        let base = base_sample(result, entities, e);
+        let out = result.entry(e).or_default();
+        if let (Some(p), Some(r)) = (sample.position, reference.position) {
+            out.position = Some(base.position.unwrap() + (p - r) * w);
+        }
+        if let (Some(q), Some(r)) = (sample.rotation, reference.rotation) {
+            let delta = slerp(DQuat::IDENTITY, (q * r.inverse()).normalize(), w);
+            out.rotation = Some((delta * base.rotation.unwrap()).normalize());
+        }
+        if let (Some(s), Some(r)) = (sample.scale, reference.scale) {
+            let ratio = s / r.max(DVec3::splat(1e-8));
+            out.scale = Some(base.scale.unwrap() * (DVec3::ONE + (ratio - DVec3::ONE) * w));
+        }
+        if let (Some(mw), Some(r)) = (sample.weights, reference.weights) {
+            let mut out_weights = base.weights.unwrap_or_default();
+            out_weights.resize(out_weights.len().max(mw.len()), 0.0);
+            for (i, o) in out_weights.iter_mut().enumerate() {
+                *o += (mw.get(i).unwrap_or(&0.0) - r.get(i).unwrap_or(&0.0)) * w;
+            }
+            out.weights = Some(out_weights);
+        }
+    }
 }
 
-fn get_quat(values: &[f64], index: usize) -> DQuat {
-    let i = index * 4;
-    // ORSB stores w, x, y, z
-    DQuat::from_xyzw(values[i + 1], values[i + 2], values[i + 3], values[i])
+/// Advance all layers of an animation. Returns true while anything is still playing.
+fn advance_layers(anim: &mut AnimationState, dt: f32) -> bool {
+    let clips = &anim.clips;
+    let duration = |clip: usize| clips.get(clip).map_or(0.0, |c| c.duration);
+    let mut active = false;
+
+    for layer in &mut anim.layers {
+        for track in &mut layer.tracks {
+            active |= !track.advance(duration(track.clip), dt);
+        }
+        if let Some(fade) = &mut layer.fade {
+            for track in &mut fade.from {
+                track.advance(duration(track.clip), dt);
+            }
+            fade.elapsed += dt;
+            if fade.elapsed >= fade.duration {
+                layer.fade = None;
+            } else {
+                active = true;
+            }
+        }
+    }
+    active
 }
 
-fn lerp_vec3(a: DVec3, b: DVec3, t: f64) -> DVec3 {
-    a + (b - a) * t
+/// Update all animation playback states and apply blended values to transforms
+/// and morph weights.
+pub fn update_animations(scene: &mut LoadedScene, dt: f32) {
+    let LoadedScene { animations, entities, events, .. } = scene;
+
+    for (anim_index, anim) in animations.iter_mut().enumerate() {
+        if !anim.playing {
+            continue;
+        }
+
+        let still_playing = advance_layers(anim, dt * anim.speed);
+
+        let mut result = Pose::new();
+        for layer in &anim.layers {
+            if layer.weight <= 0.0 || (layer.tracks.is_empty() && layer.fade.is_none()) {
+                continue;
+            }
+            let pose = sample_layer(&anim.clips, layer, false);
+            match layer.blend_mode {
+                LayerBlendMode::Override => apply_override(&mut result, entities, layer, pose),
+                LayerBlendMode::Additive => {
+                    let reference = sample_layer(&anim.clips, layer, true);
+                    apply_additive(&mut result, entities, layer, pose, reference);
+                }
+            }
+        }
+
+        for (e, sample) in result {
+            let entity = &mut entities[e];
+            let transform = &mut entity.transform;
+            if let Some(p) = sample.position {
+                transform.position = p;
+                transform.dirty = true;
+            }
+            if let Some(q) = sample.rotation {
+                transform.rotation = q;
+                transform.dirty = true;
+            }
+            if let Some(s) = sample.scale {
+                transform.scale = s;
+                transform.dirty = true;
+            }
+            if let (Some(w), Some(morph)) = (sample.weights, entity.morph.as_mut()) {
+                for (dst, src) in morph.weights.iter_mut().zip(w) {
+                    *dst = src as f32;
+                }
+                morph.dirty = true;
+            }
+        }
+
+        // Non-looping clips stop playback once they reach the end
+        if !still_playing {
+            anim.playing = false;
+            let clip = anim.layers.first().and_then(|l| l.tracks.first()).and_then(|t| anim.clips.get(t.clip));
+            let owner = entities.iter().find(|e| e.alive && e.animation_index == Some(anim_index));
+            if let (Some(clip), Some(owner)) = (clip, owner) {
+                events.push(GameEvent::AnimationFinished { entity: owner.id, clip: clip.name.clone() });
+            }
+        }
+    }
 }
 
-fn slerp_quat(a: DQuat, b: DQuat, t: f64) -> DQuat {
-    // glam's DQuat doesn't have slerp, so implement manually
-    let mut dot = a.x * b.x + a.y * b.y + a.z * b.z + a.w * b.w;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::EventKind;
+    use crate::scene::AnimationChannel;
+    use openreality_gpu_shared::scene_format::InterpolationMode;
 
-    // Ensure shortest path
-    let b = if dot < 0.0 {
-        dot = -dot;
-        DQuat::from_xyzw(-b.x, -b.y, -b.z, -b.w)
-    } else {
-        b
-    };
+    /// One-second clip moving `entity` linearly from `from` to `to`.
+    fn slide(name: &str, entity: usize, from: [f64; 3], to: [f64; 3]) -> AnimationClip {
+        AnimationClip {
+            name: name.to_string(),
+            duration: 1.0,
+            channels: vec![AnimationChannel {
+                target_entity_index: entity,
+                target_property: TargetProperty::Position,
+                interpolation: InterpolationMode::Linear,
+                times: vec![0.0, 1.0],
+                values: from.into_iter().chain(to).collect(),
+            }],
+        }
+    }
+
+    fn scene_with(entities: usize, clips: Vec<AnimationClip>) -> LoadedScene {
+        let mut scene = LoadedScene::empty();
+        for i in 0..entities {
+            scene.spawn_entity(&format!("e{i}"), None);
+        }
+        let base = AnimationLayer::new("base", LayerBlendMode::Override, 1.0, None);
+        scene.insert_animation(AnimationState { clips, layers: vec![base], playing: false, speed: 1.0 });
+        scene
+    }
+
+    fn position(scene: &LoadedScene, entity: usize) -> DVec3 {
+        scene.entities[entity].transform.position
+    }
+
+    #[test]
+    fn test_cross_fade_weights_follow_elapsed_time() {
+        let mut scene = scene_with(1, vec![slide("a", 0, [0.0; 3], [0.0; 3]), slide("b", 0, [10.0, 0.0, 0.0], [10.0, 0.0, 0.0])]);
+        scene.animations[0].play(0, true);
+        update_animations(&mut scene, 0.0);
+        assert_eq!(position(&scene, 0), DVec3::ZERO);
 
-    if dot > 0.9995 {
-        // Very close — use linear interpolation to avoid divide-by-zero
-        let result = DQuat::from_xyzw(
-            a.x + (b.x - a.x) * t,
-            a.y + (b.y - a.y) * t,
-            a.z + (b.z - a.z) * t,
-            a.w + (b.w - a.w) * t,
-        );
-        return result.normalize();
+        scene.animations[0].cross_fade(0, 1, 1.0, true);
+        for expected in [2.5, 5.0, 7.5] {
+            update_animations(&mut scene, 0.25);
+            assert!((position(&scene, 0).x - expected).abs() < 1e-9);
+        }
+        update_animations(&mut scene, 0.25);
+        assert_eq!(position(&scene, 0).x, 10.0);
+        assert!(scene.animations[0].layers[0].fade.is_none());
+    }
+
+    #[test]
+    fn test_additive_layer_adds_offset_from_first_frame() {
+        let clips = vec![slide("stand", 0, [1.0, 0.0, 0.0], [1.0, 0.0, 0.0]), slide("bob", 0, [0.0; 3], [0.0, 2.0, 0.0])];
+        let mut scene = scene_with(1, clips);
+        let anim = &mut scene.animations[0];
+        anim.play(0, true);
+        let bob = anim.add_layer("bob", LayerBlendMode::Additive, 0.5, None);
+        anim.set_blend(bob, &[(1, 1.0)], true);
+
+        update_animations(&mut scene, 0.5);
+        // The layer samples y = 1 against a reference of y = 0, scaled by its weight
+        assert!(position(&scene, 0).abs_diff_eq(DVec3::new(1.0, 0.5, 0.0), 1e-9));
     }
 
-    let theta = dot.acos();
-    let sin_theta = theta.sin();
-    let w0 = ((1.0 - t) * theta).sin() / sin_theta;
-    let w1 = (t * theta).sin() / sin_theta;
+    #[test]
+    fn test_masked_layer_only_affects_masked_entities() {
+        let mut both = slide("idle", 0, [1.0, 0.0, 0.0], [1.0, 0.0, 0.0]);
+        both.channels.extend(slide("", 1, [1.0, 0.0, 0.0], [1.0, 0.0, 0.0]).channels);
+        let mut wave = slide("wave", 0, [5.0, 0.0, 0.0], [5.0, 0.0, 0.0]);
+        wave.channels.extend(slide("", 1, [5.0, 0.0, 0.0], [5.0, 0.0, 0.0]).channels);
+        let mut scene = scene_with(2, vec![both, wave]);
+        let anim = &mut scene.animations[0];
+        anim.play(0, true);
+        let arm = anim.add_layer("arm", LayerBlendMode::Override, 1.0, Some(vec![false, true]));
+        anim.set_blend(arm, &[(1, 1.0)], true);
 
-    DQuat::from_xyzw(
-        a.x * w0 + b.x * w1,
-        a.y * w0 + b.y * w1,
-        a.z * w0 + b.z * w1,
-        a.w * w0 + b.w * w1,
-    )
+        update_animations(&mut scene, 0.1);
+        assert_eq!(position(&scene, 0).x, 1.0);
+        assert_eq!(position(&scene, 1).x, 5.0);
+    }
+
+    #[test]
+    fn test_finished_clip_stops_and_reports() {
+        let mut scene = scene_with(1, vec![slide("once", 0, [0.0; 3], [4.0, 0.0, 0.0])]);
+        scene.entities[0].animation_index = Some(0);
+        scene.events.subscribe(EventKind::AnimationFinished);
+        scene.animations[0].play(0, false);
+        update_animations(&mut scene, 0.5);
+        assert!(scene.animations[0].playing);
+        update_animations(&mut scene, 0.75);
+        assert!(!scene.animations[0].playing);
+        assert_eq!(position(&scene, 0).x, 4.0);
+        assert!(scene.events.drain().iter().any(|e| matches!(e, GameEvent::AnimationFinished { clip, .. } if clip == "once")));
+    }
 }
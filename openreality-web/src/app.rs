@@ -1,17 +1,66 @@
+use glam::{DQuat, DVec3, Vec3};
+use openreality_gpu_shared::decal::validate_decal_transform;
+use openreality_gpu_shared::rng::RngStreams;
+use openreality_gpu_shared::scene_format::{DecalParsed, LayerBlendMode};
+use openreality_gpu_shared::uniforms::{DecalData, PerFrameUniforms};
 use wasm_bindgen::prelude::*;
 use web_sys::HtmlCanvasElement;
 
-use crate::scene::LoadedScene;
+use crate::scene::{AnimationState, Entity, LoadedScene, MaterialInfo, SkeletonData, TransformState};
 use crate::input::InputState;
+use crate::camera::{CameraSystem, DebugCameraMode};
 use crate::animation;
+use crate::anim_state_machine::{self, AnimStateMachine};
 use crate::transform;
 use crate::skinning;
+use crate::morph;
+use crate::particles;
+use crate::collision;
+use crate::culling;
+use crate::events::{EventKind, GameEvent};
+use crate::scripting::ScriptRuntime;
+use crate::reload;
+use crate::prefab::TransformOverride;
+
+/// Session seed used until `set_random_seed` is called.
+const DEFAULT_SEED: u64 = 12345;
+
+/// Closest collider hit by `App::raycast`.
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+pub struct RaycastHit {
+    pub entity_id: u64,
+    pub distance: f32,
+    point: [f32; 3],
+    normal: [f32; 3],
+}
+
+#[wasm_bindgen]
+impl RaycastHit {
+    /// World-space hit point (xyz).
+    #[wasm_bindgen(getter)]
+    pub fn point(&self) -> Vec<f32> {
+        self.point.to_vec()
+    }
+
+    /// Surface normal at the hit point (xyz).
+    #[wasm_bindgen(getter)]
+    pub fn normal(&self) -> Vec<f32> {
+        self.normal.to_vec()
+    }
+}
 
 /// Main application state for the WASM runtime.
 #[wasm_bindgen]
 pub struct App {
     scene: LoadedScene,
     input: InputState,
+    camera: CameraSystem,
+    frame_uniforms: PerFrameUniforms,
+    /// Session seed and per-system random streams (gameplay, particle emitters).
+    random: RngStreams,
+    /// Game logic scripts shipped inside the bundle.
+    scripts: ScriptRuntime,
     last_time: f64,
     canvas: HtmlCanvasElement,
     // Renderer will be added in Phase 6
@@ -30,7 +79,7 @@ impl App {
             .map_err(|_| "Element is not a canvas")?;
 
         // Parse ORSB scene
-        let scene = LoadedScene::from_orsb(scene_data)
+        let mut scene = LoadedScene::from_orsb(scene_data)
             .map_err(|e| JsValue::from_str(&format!("Failed to load scene: {e}")))?;
 
         log::info!(
@@ -40,9 +89,18 @@ impl App {
             scene.num_textures(),
         );
 
+        let camera = CameraSystem::new(&scene, canvas.width(), canvas.height());
+        let input = InputState::new();
+        let mut random = RngStreams::new(DEFAULT_SEED);
+        let scripts = ScriptRuntime::new(&mut scene, &input, &mut random);
+
         Ok(App {
             scene,
-            input: InputState::new(),
+            input,
+            camera,
+            frame_uniforms: bytemuck::Zeroable::zeroed(),
+            random,
+            scripts,
             last_time: 0.0,
             canvas,
         })
@@ -57,14 +115,634 @@ impl App {
         };
         self.last_time = time;
 
+        // Pick up canvas size changes made outside `resize()` (e.g. CSS layout)
+        self.camera.resize(self.canvas.width(), self.canvas.height());
+
         // Update systems
-        self.input.update();
+        self.scripts.update(&mut self.scene, &self.input, &mut self.random, dt as f32);
+        self.fix_active_camera();
+        anim_state_machine::update_state_machines(&mut self.scene, dt as f32);
         animation::update_animations(&mut self.scene, dt as f32);
         transform::compute_world_transforms(&mut self.scene);
+        collision::update_collisions(&mut self.scene);
+        morph::update_morph_targets(&mut self.scene);
         skinning::update_skinned_meshes(&mut self.scene);
+        self.camera.update(&self.scene, &self.input, dt as f32);
+        culling::update_visibility(&mut self.scene, &self.camera.view_projection(), self.camera.position);
+        self.frame_uniforms = self.camera.per_frame_uniforms((time / 1000.0) as f32);
+        particles::update_particles(&mut self.scene, &mut self.random, dt as f32, &self.camera.view, self.camera.position);
 
         // Rendering will be done here in Phase 6
-        // For now, just tick the systems
+
+        // Clear per-frame input deltas once every system has seen them
+        self.input.update();
+        self.scene.recycle_slots();
+    }
+
+    /// Swap in a re-exported bundle without recreating the app. Entities are
+    /// matched by id: the camera, animation playback, live particles and event
+    /// subscriptions carry over, while authored data comes from the new bundle.
+    /// Bundle scripts restart. On error the current scene is left untouched.
+    pub fn reload_scene(&mut self, scene_data: &[u8]) -> Result<(), JsValue> {
+        let mut scene = LoadedScene::from_orsb(scene_data)
+            .map_err(|e| JsValue::from_str(&format!("Failed to reload scene: {e}")))?;
+        let report = reload::carry_over(&mut self.scene, &mut scene);
+        reload::carry_camera(&mut self.camera, &self.scene, &scene);
+
+        self.scene = scene;
+        self.scripts = ScriptRuntime::new(&mut self.scene, &self.input, &mut self.random);
+        log::info!(
+            "Reloaded scene: {} entities kept, {} added, {} removed",
+            report.kept_entities,
+            report.added_entities,
+            report.removed_entities,
+        );
+        Ok(())
+    }
+
+    /// This frame's `PerFrameUniforms` as raw bytes (bind group 0, binding 0).
+    pub fn frame_uniforms(&self) -> Vec<u8> {
+        bytemuck::bytes_of(&self.frame_uniforms).to_vec()
+    }
+
+    /// The scene's `AtmosphereSettings` as raw bytes, if it has a sky.
+    pub fn atmosphere_settings(&self) -> Option<Vec<u8>> {
+        self.scene.atmosphere.as_ref().map(|a| bytemuck::bytes_of(a).to_vec())
+    }
+
+    /// The scene's `FogSettings` as raw bytes, if it has volumetric fog.
+    pub fn fog_settings(&self) -> Option<Vec<u8>> {
+        self.scene.fog.as_ref().map(|f| bytemuck::bytes_of(f).to_vec())
+    }
+
+    /// Packed `DecalData` (bind group 1, binding 0 of the decal pass) of this
+    /// frame's decals, in draw order.
+    pub fn decals(&self) -> Vec<u8> {
+        let data: Vec<DecalData> = self
+            .live_decals()
+            .map(|(e, d)| {
+                DecalData::new(e.world_transform, &d.params, d.albedo_texture.is_some(), d.normal_texture.is_some())
+            })
+            .collect();
+        bytemuck::cast_slice(&data).to_vec()
+    }
+
+    /// Albedo and normal texture index of each of `decals()`, -1 for none.
+    pub fn decal_textures(&self) -> Vec<i32> {
+        let index = |t: Option<u32>| t.map_or(-1, |t| t as i32);
+        self.live_decals()
+            .flat_map(|(_, d)| [index(d.albedo_texture), index(d.normal_texture)])
+            .collect()
+    }
+
+    /// Column-major view-projection matrix of the active camera.
+    pub fn view_projection(&self) -> Vec<f32> {
+        self.camera.view_projection().to_cols_array().to_vec()
+    }
+
+    /// Ids of the entities to draw this frame: live mesh entities inside the
+    /// camera frustum.
+    pub fn visible_entities(&self) -> Vec<u64> {
+        self.scene.draw_list.iter().map(|d| self.scene.entities[d.entity].id).collect()
+    }
+
+    /// Scene mesh index to draw for each of `visible_entities()`, after LOD selection.
+    pub fn visible_meshes(&self) -> Vec<u32> {
+        self.scene.draw_list.iter().map(|d| d.mesh as u32).collect()
+    }
+
+    /// Set a float (or bool as 0/1) parameter on the state machine driving
+    /// this entity's animation. Returns false if not found.
+    pub fn set_anim_param(&mut self, entity_id: u64, name: &str, value: f32) -> bool {
+        self.state_machine_mut(entity_id).is_some_and(|sm| sm.set_float(name, value))
+    }
+
+    pub fn set_anim_bool(&mut self, entity_id: u64, name: &str, value: bool) -> bool {
+        self.state_machine_mut(entity_id).is_some_and(|sm| sm.set_bool(name, value))
+    }
+
+    /// Fire a trigger parameter on the entity's animation state machine.
+    pub fn set_anim_trigger(&mut self, entity_id: u64, name: &str) -> bool {
+        self.state_machine_mut(entity_id).is_some_and(|sm| sm.set_trigger(name))
+    }
+
+    /// Cross-fade the entity's base animation layer to the clip named `clip`.
+    pub fn cross_fade(&mut self, entity_id: u64, clip: &str, duration: f32, looping: bool) -> bool {
+        let Some(anim) = self.animation_mut(entity_id) else { return false };
+        match anim.find_clip(clip) {
+            Some(c) => {
+                anim.cross_fade(0, c, duration, looping);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Play the clip named `clip` on the entity's base layer, without fading.
+    pub fn play_animation(&mut self, entity_id: u64, clip: &str, looping: bool) -> bool {
+        let Some(anim) = self.animation_mut(entity_id) else { return false };
+        match anim.find_clip(clip) {
+            Some(c) => {
+                anim.play(c, looping);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Blend several clips on one layer with the given relative weights.
+    /// `clips` and `weights` must be the same length.
+    pub fn set_anim_blend(&mut self, entity_id: u64, layer: usize, clips: Vec<String>, weights: Vec<f32>) -> bool {
+        if clips.len() != weights.len() {
+            return false;
+        }
+        let Some(anim) = self.animation_mut(entity_id) else { return false };
+        let blend: Option<Vec<(usize, f32)>> = clips
+            .iter()
+            .zip(weights)
+            .map(|(name, w)| anim.find_clip(name).map(|c| (c, w)))
+            .collect();
+        match blend {
+            Some(blend) if layer < anim.layers.len() => {
+                anim.set_blend(layer, &blend, true);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Add an animation layer. `mask` lists the entity ids it may affect
+    /// (empty = all). Returns the layer index, or -1 if the entity has no animation.
+    pub fn add_anim_layer(&mut self, entity_id: u64, name: &str, additive: bool, weight: f32, mask: Vec<u64>) -> i32 {
+        let mask = (!mask.is_empty()).then(|| {
+            let mut flags = vec![false; self.scene.entities.len()];
+            for id in mask {
+                if let Some(i) = self.scene.find_entity(id) {
+                    flags[i] = true;
+                }
+            }
+            flags
+        });
+        let mode = if additive { LayerBlendMode::Additive } else { LayerBlendMode::Override };
+        match self.animation_mut(entity_id) {
+            Some(anim) => anim.add_layer(name, mode, weight, mask) as i32,
+            None => -1,
+        }
+    }
+
+    /// Set the weight of the named animation layer.
+    pub fn set_anim_layer_weight(&mut self, entity_id: u64, layer: &str, weight: f32) -> bool {
+        let Some(anim) = self.animation_mut(entity_id) else { return false };
+        match anim.layers.iter_mut().find(|l| l.name == layer) {
+            Some(l) => {
+                l.weight = weight;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Override the morph target weights of an entity's mesh.
+    pub fn set_morph_weights(&mut self, entity_id: u64, weights: Vec<f32>) -> bool {
+        let Some(idx) = self.scene.find_entity(entity_id) else { return false };
+        let Some(morph) = self.scene.entities[idx].morph.as_mut() else { return false };
+        for (dst, src) in morph.weights.iter_mut().zip(weights) {
+            *dst = src;
+        }
+        morph.dirty = true;
+        true
+    }
+
+    /// Deformed vertex positions of a morphed entity (xyz per vertex), before skinning.
+    pub fn morphed_positions(&self, entity_id: u64) -> Option<Vec<f32>> {
+        let idx = self.scene.find_entity(entity_id)?;
+        self.scene.entities[idx].morph.as_ref().map(|m| m.positions.clone())
+    }
+
+    /// Deformed vertex normals of a morphed entity (xyz per vertex), before skinning.
+    pub fn morphed_normals(&self, entity_id: u64) -> Option<Vec<f32>> {
+        let idx = self.scene.find_entity(entity_id)?;
+        self.scene.entities[idx].morph.as_ref().map(|m| m.normals.clone())
+    }
+
+    /// Mesh-space vertex positions of a skinned entity, morph targets included.
+    pub fn skinned_positions(&self, entity_id: u64) -> Option<Vec<f32>> {
+        self.skinned(entity_id).map(|s| s.skinned_positions.clone())
+    }
+
+    /// Mesh-space vertex normals of a skinned entity, morph targets included.
+    pub fn skinned_normals(&self, entity_id: u64) -> Option<Vec<f32>> {
+        self.skinned(entity_id).map(|s| s.skinned_normals.clone())
+    }
+
+    /// Reparent an entity (`parent_id` None = make it a root). With
+    /// `keep_world` it stays where it is; otherwise it moves with its new parent.
+    /// Returns false for unknown ids or if the link would form a cycle.
+    pub fn set_parent(&mut self, entity_id: u64, parent_id: Option<u64>, keep_world: bool) -> bool {
+        let Some(child) = self.scene.find_entity(entity_id) else { return false };
+        let parent = match parent_id {
+            Some(id) => match self.scene.find_entity(id) {
+                Some(p) => Some(p),
+                None => return false,
+            },
+            None => None,
+        };
+        match transform::set_parent(&mut self.scene, child, parent, keep_world) {
+            Ok(()) => true,
+            Err(e) => {
+                log::warn!("{e}");
+                false
+            }
+        }
+    }
+
+    /// Column-major world matrix of an entity.
+    pub fn world_transform(&mut self, entity_id: u64) -> Option<Vec<f32>> {
+        let idx = self.scene.find_entity(entity_id)?;
+        transform::compute_world_transforms(&mut self.scene);
+        Some(self.scene.entities[idx].world_transform.to_cols_array().to_vec())
+    }
+
+    /// Convert a point in the entity's local space to world space (xyz).
+    pub fn local_to_world(&mut self, entity_id: u64, x: f32, y: f32, z: f32) -> Option<Vec<f32>> {
+        let idx = self.scene.find_entity(entity_id)?;
+        transform::compute_world_transforms(&mut self.scene);
+        let p = transform::local_to_world_point(&self.scene, idx, Vec3::new(x, y, z));
+        Some(p.to_array().to_vec())
+    }
+
+    /// Convert a world-space point into the entity's local space (xyz).
+    pub fn world_to_local(&mut self, entity_id: u64, x: f32, y: f32, z: f32) -> Option<Vec<f32>> {
+        let idx = self.scene.find_entity(entity_id)?;
+        transform::compute_world_transforms(&mut self.scene);
+        let p = transform::world_to_local_point(&self.scene, idx, Vec3::new(x, y, z));
+        Some(p.to_array().to_vec())
+    }
+
+    /// Move an entity to a world-space position, whatever its parent.
+    pub fn set_world_position(&mut self, entity_id: u64, x: f32, y: f32, z: f32) -> bool {
+        let Some(idx) = self.scene.find_entity(entity_id) else { return false };
+        transform::compute_world_transforms(&mut self.scene);
+        transform::set_world_position(&mut self.scene, idx, Vec3::new(x, y, z));
+        true
+    }
+
+    // ── Entities ──
+
+    /// Id of the first live entity named `name`.
+    pub fn find_entity(&self, name: &str) -> Option<u64> {
+        self.scene.find_entity_by_name(name).map(|i| self.scene.entities[i].id)
+    }
+
+    pub fn entity_exists(&self, entity_id: u64) -> bool {
+        self.scene.find_entity(entity_id).is_some()
+    }
+
+    pub fn entity_name(&self, entity_id: u64) -> Option<String> {
+        self.scene.find_entity(entity_id).map(|i| self.scene.entities[i].name.clone())
+    }
+
+    /// Ids of every live entity, in scene order.
+    pub fn entity_ids(&self) -> Vec<u64> {
+        self.scene.entities.iter().filter(|e| e.alive).map(|e| e.id).collect()
+    }
+
+    pub fn parent(&self, entity_id: u64) -> Option<u64> {
+        let idx = self.scene.find_entity(entity_id)?;
+        self.scene.entities[idx].parent_index.map(|p| self.scene.entities[p].id)
+    }
+
+    /// Spawn an empty entity at the origin of its parent. Returns its id, or
+    /// None if `parent_id` is unknown.
+    pub fn spawn(&mut self, name: &str, parent_id: Option<u64>) -> Option<u64> {
+        let parent = match parent_id {
+            Some(id) => Some(self.scene.find_entity(id)?),
+            None => None,
+        };
+        let idx = self.scene.spawn_entity(name, parent);
+        Some(self.scene.entities[idx].id)
+    }
+
+    /// Names of the bundle's prefabs.
+    pub fn prefab_names(&self) -> Vec<String> {
+        self.scene.prefabs.iter().map(|p| p.name.clone()).collect()
+    }
+
+    /// Instantiate a prefab under `parent_id`. `position` and `scale` are
+    /// `[x, y, z]`, `rotation` is `[x, y, z, w]`; omitted ones keep the
+    /// prefab's values. Returns the root's id, or None if the prefab or
+    /// parent is unknown or an array has the wrong length.
+    pub fn instantiate(
+        &mut self,
+        prefab: &str,
+        parent_id: Option<u64>,
+        position: Option<Vec<f32>>,
+        rotation: Option<Vec<f32>>,
+        scale: Option<Vec<f32>>,
+    ) -> Option<u64> {
+        let index = self.scene.find_prefab(prefab)?;
+        let parent = match parent_id {
+            Some(id) => Some(self.scene.find_entity(id)?),
+            None => None,
+        };
+        let vec3 = |v: Option<Vec<f32>>| match v.as_deref() {
+            Some(&[x, y, z]) => Ok(Some(DVec3::new(x as f64, y as f64, z as f64))),
+            Some(_) => Err(()),
+            None => Ok(None),
+        };
+        let rotation = match rotation.as_deref() {
+            Some(&[x, y, z, w]) => Some(DQuat::from_xyzw(x as f64, y as f64, z as f64, w as f64).normalize()),
+            Some(_) => return None,
+            None => None,
+        };
+        let overrides = TransformOverride { position: vec3(position).ok()?, rotation, scale: vec3(scale).ok()? };
+        let idx = self.scene.instantiate_prefab(index, parent, overrides);
+        Some(self.scene.entities[idx].id)
+    }
+
+    /// Despawn an entity and its descendants. Returns false if it doesn't exist.
+    pub fn despawn(&mut self, entity_id: u64) -> bool {
+        let Some(idx) = self.scene.find_entity(entity_id) else { return false };
+        self.scene.despawn_entity(idx);
+        self.fix_active_camera();
+        true
+    }
+
+    /// Attach a scene mesh and material (by index) to an entity; None detaches.
+    pub fn set_mesh(&mut self, entity_id: u64, mesh: Option<u32>, material: Option<u32>) -> bool {
+        let Some(idx) = self.scene.find_entity(entity_id) else { return false };
+        let mesh = mesh.map(|m| m as usize);
+        let material = material.map(|m| m as usize);
+        if mesh.is_some_and(|m| m >= self.scene.meshes.len())
+            || material.is_some_and(|m| m >= self.scene.materials.len())
+        {
+            return false;
+        }
+        let entity = &mut self.scene.entities[idx];
+        entity.mesh_index = mesh;
+        entity.material_index = material;
+        true
+    }
+
+    // ── Transforms (local, relative to the parent) ──
+
+    pub fn position(&self, entity_id: u64) -> Option<Vec<f32>> {
+        let t = &self.scene.entities[self.scene.find_entity(entity_id)?].transform;
+        Some(t.position.as_vec3().to_array().to_vec())
+    }
+
+    pub fn set_position(&mut self, entity_id: u64, x: f32, y: f32, z: f32) -> bool {
+        self.edit_transform(entity_id, |t| t.position = DVec3::new(x as f64, y as f64, z as f64))
+    }
+
+    /// Rotation quaternion (xyzw).
+    pub fn rotation(&self, entity_id: u64) -> Option<Vec<f32>> {
+        let t = &self.scene.entities[self.scene.find_entity(entity_id)?].transform;
+        Some(t.rotation.as_quat().to_array().to_vec())
+    }
+
+    pub fn set_rotation(&mut self, entity_id: u64, x: f32, y: f32, z: f32, w: f32) -> bool {
+        let q = DQuat::from_xyzw(x as f64, y as f64, z as f64, w as f64).normalize();
+        self.edit_transform(entity_id, |t| t.rotation = q)
+    }
+
+    pub fn scale(&self, entity_id: u64) -> Option<Vec<f32>> {
+        let t = &self.scene.entities[self.scene.find_entity(entity_id)?].transform;
+        Some(t.scale.as_vec3().to_array().to_vec())
+    }
+
+    pub fn set_scale(&mut self, entity_id: u64, x: f32, y: f32, z: f32) -> bool {
+        self.edit_transform(entity_id, |t| t.scale = DVec3::new(x as f64, y as f64, z as f64))
+    }
+
+    // ── Materials ──
+
+    /// The entity's material as `[r, g, b, a, metallic, roughness, emissive r, g, b, opacity]`.
+    pub fn material(&self, entity_id: u64) -> Option<Vec<f32>> {
+        let idx = self.scene.find_entity(entity_id)?;
+        let m = self.scene.materials.get(self.scene.entities[idx].material_index?)?;
+        let mut out = m.color.to_vec();
+        out.extend_from_slice(&[m.metallic, m.roughness]);
+        out.extend_from_slice(&m.emissive);
+        out.push(m.opacity);
+        Some(out)
+    }
+
+    pub fn set_material_color(&mut self, entity_id: u64, r: f32, g: f32, b: f32, a: f32) -> bool {
+        self.edit_material(entity_id, |m| m.color = [r, g, b, a])
+    }
+
+    pub fn set_material_pbr(&mut self, entity_id: u64, metallic: f32, roughness: f32) -> bool {
+        self.edit_material(entity_id, |m| {
+            m.metallic = metallic;
+            m.roughness = roughness;
+        })
+    }
+
+    pub fn set_material_emissive(&mut self, entity_id: u64, r: f32, g: f32, b: f32) -> bool {
+        self.edit_material(entity_id, |m| m.emissive = [r, g, b])
+    }
+
+    pub fn set_material_opacity(&mut self, entity_id: u64, opacity: f32) -> bool {
+        self.edit_material(entity_id, |m| m.opacity = opacity)
+    }
+
+    // ── Animation playback ──
+
+    /// Stop all layers of the entity's animation and clear its clips.
+    pub fn stop_animation(&mut self, entity_id: u64) -> bool {
+        let Some(anim) = self.animation_mut(entity_id) else { return false };
+        for layer in &mut anim.layers {
+            layer.tracks.clear();
+            layer.fade = None;
+        }
+        anim.playing = false;
+        true
+    }
+
+    /// Pause or resume the entity's animation without resetting clip times.
+    pub fn set_animation_paused(&mut self, entity_id: u64, paused: bool) -> bool {
+        let Some(anim) = self.animation_mut(entity_id) else { return false };
+        anim.playing = !paused;
+        true
+    }
+
+    pub fn set_animation_speed(&mut self, entity_id: u64, speed: f32) -> bool {
+        let Some(anim) = self.animation_mut(entity_id) else { return false };
+        anim.speed = speed;
+        true
+    }
+
+    // ── Queries and events ──
+
+    /// Closest collider hit by a ray from `origin` (xyz) along `dir` (xyz).
+    pub fn raycast(&mut self, origin: &[f32], dir: &[f32], max_distance: f32, include_triggers: bool) -> Option<RaycastHit> {
+        if origin.len() < 3 || dir.len() < 3 {
+            return None;
+        }
+        transform::compute_world_transforms(&mut self.scene);
+        let (idx, hit) = collision::raycast(
+            &self.scene,
+            Vec3::from_slice(origin),
+            Vec3::from_slice(dir),
+            max_distance,
+            include_triggers,
+        )?;
+        Some(RaycastHit {
+            entity_id: self.scene.entities[idx].id,
+            distance: hit.distance,
+            point: hit.point.to_array(),
+            normal: hit.normal.to_array(),
+        })
+    }
+
+    /// Start queuing events of `kind` ("collision_enter", "collision_exit",
+    /// "animation_finished"). Returns false for unknown kinds.
+    pub fn subscribe(&mut self, kind: &str) -> bool {
+        match EventKind::from_name(kind) {
+            Some(k) => {
+                self.scene.events.subscribe(k);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stop queuing events of `kind` and drop any already queued.
+    pub fn unsubscribe(&mut self, kind: &str) -> bool {
+        match EventKind::from_name(kind) {
+            Some(k) => {
+                self.scene.events.unsubscribe(k);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Take the events raised since the last call, oldest first, as objects
+    /// `{ type, entity, other }` (collisions) or `{ type, entity, clip }`.
+    /// Call after `frame()`; handlers may then call back into the app freely.
+    pub fn poll_events(&mut self) -> js_sys::Array {
+        let out = js_sys::Array::new();
+        for event in self.scene.events.drain() {
+            let obj = js_sys::Object::new();
+            let set = |key: &str, value: JsValue| {
+                let _ = js_sys::Reflect::set(&obj, &JsValue::from_str(key), &value);
+            };
+            set("type", JsValue::from_str(event.kind().name()));
+            match event {
+                GameEvent::CollisionEnter { entity, other } | GameEvent::CollisionExit { entity, other } => {
+                    set("entity", JsValue::from(entity));
+                    set("other", JsValue::from(other));
+                }
+                GameEvent::AnimationFinished { entity, clip } => {
+                    set("entity", JsValue::from(entity));
+                    set("clip", JsValue::from_str(&clip));
+                }
+            }
+            out.push(&obj);
+        }
+        out
+    }
+
+    /// Restart every random stream from `seed`. Sessions started with the same
+    /// seed and inputs produce identical particle effects and gameplay rolls.
+    pub fn set_random_seed(&mut self, seed: u64) {
+        self.random.reseed(seed);
+    }
+
+    pub fn random_seed(&self) -> u64 {
+        self.random.seed()
+    }
+
+    /// Uniform float in [0, 1) from gameplay stream `stream`. Use separate
+    /// streams for unrelated systems so one never shifts the other's sequence.
+    pub fn random(&mut self, stream: u32) -> f32 {
+        self.random.stream(stream as u64).next_f32()
+    }
+
+    /// Uniform float in [lo, hi) from gameplay stream `stream`.
+    pub fn random_range(&mut self, stream: u32, lo: f32, hi: f32) -> f32 {
+        self.random.stream(stream as u64).range(lo, hi)
+    }
+
+    /// Snapshot the seed and the state of every random stream.
+    pub fn save_random_state(&self) -> Vec<u8> {
+        self.random.snapshot()
+    }
+
+    /// Restore a snapshot from `save_random_state`. Returns false if it is malformed.
+    pub fn restore_random_state(&mut self, data: &[u8]) -> bool {
+        match self.random.restore(data) {
+            Ok(()) => true,
+            Err(e) => {
+                log::warn!("{e}");
+                false
+            }
+        }
+    }
+
+    /// Resize the canvas backing store (call on window resize).
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.canvas.set_width(width);
+        self.canvas.set_height(height);
+        self.camera.resize(width, height);
+    }
+
+    pub fn on_key_down(&mut self, key_code: u8) {
+        self.input.set_key(key_code, true);
+    }
+
+    pub fn on_key_up(&mut self, key_code: u8) {
+        self.input.set_key(key_code, false);
+    }
+
+    /// Pointer move in canvas pixels, with movement since the last event.
+    pub fn on_mouse_move(&mut self, x: f64, y: f64, dx: f64, dy: f64) {
+        self.input.move_mouse(x, y, dx, dy);
+    }
+
+    /// `button` follows `MouseEvent.button` (0 = left, 1 = middle, 2 = right).
+    pub fn on_mouse_button(&mut self, button: u8, pressed: bool) {
+        self.input.set_mouse_button(button as usize, pressed);
+    }
+
+    pub fn on_wheel(&mut self, delta_y: f64) {
+        self.input.scroll(delta_y);
+    }
+
+    /// Make the camera on the entity with `entity_id` active. Returns false if
+    /// no such entity exists or it has no camera.
+    pub fn set_active_camera(&mut self, entity_id: u64) -> bool {
+        match self.scene.find_entity(entity_id) {
+            Some(idx) => self.camera.set_active_entity(&self.scene, idx),
+            None => false,
+        }
+    }
+
+    /// Cycle to the next scene camera. Returns its entity id.
+    pub fn next_camera(&mut self) -> Option<u64> {
+        let idx = self.camera.next_camera(&self.scene)?;
+        Some(self.scene.entities[idx].id)
+    }
+
+    /// Entity id of the active scene camera (None while the debug camera is in use).
+    pub fn active_camera_id(&self) -> Option<u64> {
+        if self.camera.debug_enabled {
+            return None;
+        }
+        let idx = self.camera.active_entity?;
+        self.scene.entities.get(idx).filter(|e| e.alive).map(|e| e.id)
+    }
+
+    /// Switch to the debug camera: fly mode if `fly`, otherwise orbit.
+    pub fn enable_debug_camera(&mut self, fly: bool) {
+        let mode = if fly { DebugCameraMode::Fly } else { DebugCameraMode::Orbit };
+        self.camera.enable_debug(mode);
+    }
+
+    pub fn disable_debug_camera(&mut self) {
+        self.camera.disable_debug();
     }
 
     /// Get the canvas width.
@@ -77,3 +755,73 @@ impl App {
         self.canvas.height()
     }
 }
+
+impl App {
+    /// Switch cameras if the active one was despawned.
+    fn fix_active_camera(&mut self) {
+        if self.camera.active_entity.is_some_and(|a| !self.scene.entities.get(a).is_some_and(|e| e.alive)) {
+            self.camera.active_entity = None;
+            self.camera.next_camera(&self.scene);
+        }
+    }
+
+    /// Live decals in draw order, skipping entities whose world transform
+    /// flattens the box.
+    fn live_decals(&self) -> impl Iterator<Item = (&Entity, &DecalParsed)> {
+        self.scene.entities.iter().filter(|e| e.alive).filter_map(|e| {
+            let decal = &self.scene.decals[e.decal_index?];
+            validate_decal_transform(&e.world_transform).is_ok().then_some((e, decal))
+        })
+    }
+
+    fn animation_mut(&mut self, entity_id: u64) -> Option<&mut AnimationState> {
+        let idx = self.scene.find_entity(entity_id)?;
+        let anim = self.scene.entities[idx].animation_index?;
+        self.scene.animations.get_mut(anim)
+    }
+
+    /// The skeleton deforming the entity's mesh, if its mesh is skinned.
+    fn skinned(&self, entity_id: u64) -> Option<&SkeletonData> {
+        let idx = self.scene.find_entity(entity_id)?;
+        self.scene.skeletons.iter().find(|s| s.entity_index == idx && !s.skinned_positions.is_empty())
+    }
+
+    fn edit_transform(&mut self, entity_id: u64, edit: impl FnOnce(&mut TransformState)) -> bool {
+        let Some(idx) = self.scene.find_entity(entity_id) else { return false };
+        let t = &mut self.scene.entities[idx].transform;
+        edit(t);
+        t.dirty = true;
+        true
+    }
+
+    /// Edit the entity's material, first giving it a private copy if other
+    /// entities share it so the change stays local.
+    fn edit_material(&mut self, entity_id: u64, edit: impl FnOnce(&mut MaterialInfo)) -> bool {
+        let Some(idx) = self.scene.find_entity(entity_id) else { return false };
+        let Some(mat) = self.scene.entities[idx].material_index.filter(|&m| m < self.scene.materials.len()) else {
+            return false;
+        };
+        let shared = self
+            .scene
+            .entities
+            .iter()
+            .enumerate()
+            .any(|(i, e)| i != idx && e.alive && e.material_index == Some(mat));
+        let mat = if shared {
+            self.scene.materials.push(self.scene.materials[mat].clone());
+            let copy = self.scene.materials.len() - 1;
+            self.scene.entities[idx].material_index = Some(copy);
+            copy
+        } else {
+            mat
+        };
+        edit(&mut self.scene.materials[mat]);
+        true
+    }
+
+    fn state_machine_mut(&mut self, entity_id: u64) -> Option<&mut AnimStateMachine> {
+        let idx = self.scene.find_entity(entity_id)?;
+        let anim = self.scene.entities[idx].animation_index?;
+        self.scene.state_machines.iter_mut().find(|sm| sm.animation_index == anim)
+    }
+}
@@ -0,0 +1,289 @@
+use glam::{Mat4, Vec3};
+use openreality_gpu_shared::uniforms::PerFrameUniforms;
+
+use crate::input::InputState;
+use crate::scene::LoadedScene;
+
+// Browser `KeyboardEvent.keyCode` values used by the debug camera.
+const KEY_W: u8 = 87;
+const KEY_A: u8 = 65;
+const KEY_S: u8 = 83;
+const KEY_D: u8 = 68;
+const KEY_Q: u8 = 81;
+const KEY_E: u8 = 69;
+const KEY_SHIFT: u8 = 16;
+
+const MOUSE_LEFT: usize = 0;
+const MOUSE_RIGHT: usize = 2;
+
+const MAX_PITCH: f32 = 89.0 * std::f32::consts::PI / 180.0;
+
+/// Built-in debug camera behaviour.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DebugCameraMode {
+    /// Left-drag orbits around `target`, right-drag pans, wheel zooms.
+    Orbit,
+    /// WASD + Q/E moves, left-drag looks around, Shift speeds up.
+    Fly,
+}
+
+/// Free camera that is independent of the scene's camera entities.
+pub struct DebugCamera {
+    pub mode: DebugCameraMode,
+    pub position: Vec3,
+    pub target: Vec3,
+    /// Rotation around +Y in radians (0 = looking down -Z).
+    pub yaw: f32,
+    /// Elevation in radians, clamped to ±89°.
+    pub pitch: f32,
+    /// Orbit distance from `target`.
+    pub distance: f32,
+    /// Vertical field of view in degrees.
+    pub fov: f32,
+    pub near: f32,
+    pub far: f32,
+    pub move_speed: f32,
+    pub look_sensitivity: f32,
+    pub zoom_sensitivity: f32,
+}
+
+impl DebugCamera {
+    pub fn new() -> Self {
+        let mut cam = Self {
+            mode: DebugCameraMode::Orbit,
+            position: Vec3::ZERO,
+            target: Vec3::ZERO,
+            yaw: 0.0,
+            pitch: -0.3,
+            distance: 10.0,
+            fov: 60.0,
+            near: 0.1,
+            far: 1000.0,
+            move_speed: 5.0,
+            look_sensitivity: 0.005,
+            zoom_sensitivity: 0.001,
+        };
+        cam.position = cam.target - cam.forward() * cam.distance;
+        cam
+    }
+
+    /// Unit look direction derived from yaw/pitch.
+    pub fn forward(&self) -> Vec3 {
+        Vec3::new(
+            -self.yaw.sin() * self.pitch.cos(),
+            self.pitch.sin(),
+            -self.yaw.cos() * self.pitch.cos(),
+        )
+    }
+
+    /// Place the debug camera at an existing viewpoint so switching does not jump.
+    pub fn look_from(&mut self, position: Vec3, forward: Vec3) {
+        let f = forward.normalize_or(Vec3::NEG_Z);
+        self.position = position;
+        self.yaw = (-f.x).atan2(-f.z);
+        self.pitch = f.y.clamp(-1.0, 1.0).asin().clamp(-MAX_PITCH, MAX_PITCH);
+        self.target = position + self.forward() * self.distance;
+    }
+
+    /// Apply this frame's mouse/keyboard input.
+    pub fn update(&mut self, input: &InputState, dt: f32) {
+        let dx = input.mouse_dx as f32;
+        let dy = input.mouse_dy as f32;
+
+        if input.is_mouse_down(MOUSE_LEFT) {
+            self.yaw -= dx * self.look_sensitivity;
+            self.pitch = (self.pitch - dy * self.look_sensitivity).clamp(-MAX_PITCH, MAX_PITCH);
+        }
+
+        let forward = self.forward();
+        let right = forward.cross(Vec3::Y).normalize_or(Vec3::X);
+        let up = right.cross(forward);
+
+        match self.mode {
+            DebugCameraMode::Orbit => {
+                if input.is_mouse_down(MOUSE_RIGHT) {
+                    let pan_scale = self.distance * self.look_sensitivity * 0.5;
+                    self.target += (-right * dx + up * dy) * pan_scale;
+                }
+                let zoom = 1.0 + input.wheel_delta as f32 * self.zoom_sensitivity;
+                self.distance = (self.distance * zoom.max(0.1)).clamp(self.near * 2.0, self.far * 0.5);
+                self.position = self.target - forward * self.distance;
+            }
+            DebugCameraMode::Fly => {
+                let mut dir = Vec3::ZERO;
+                if input.is_key_down(KEY_W) { dir += forward; }
+                if input.is_key_down(KEY_S) { dir -= forward; }
+                if input.is_key_down(KEY_D) { dir += right; }
+                if input.is_key_down(KEY_A) { dir -= right; }
+                if input.is_key_down(KEY_E) { dir += Vec3::Y; }
+                if input.is_key_down(KEY_Q) { dir -= Vec3::Y; }
+                let boost = if input.is_key_down(KEY_SHIFT) { 4.0 } else { 1.0 };
+                self.position += dir.normalize_or_zero() * self.move_speed * boost * dt;
+                self.target = self.position + forward * self.distance;
+            }
+        }
+    }
+
+    pub fn view_matrix(&self) -> Mat4 {
+        Mat4::look_at_rh(self.position, self.position + self.forward(), Vec3::Y)
+    }
+}
+
+impl Default for DebugCamera {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Resolves the active camera (a scene entity or the debug camera) into
+/// per-frame view/projection matrices.
+pub struct CameraSystem {
+    /// Entity index of the active scene camera, if any.
+    pub active_entity: Option<usize>,
+    pub debug: DebugCamera,
+    pub debug_enabled: bool,
+    pub viewport_width: u32,
+    pub viewport_height: u32,
+    pub view: Mat4,
+    pub projection: Mat4,
+    pub position: Vec3,
+}
+
+impl CameraSystem {
+    /// Pick the first camera flagged active (or the first camera at all).
+    pub fn new(scene: &LoadedScene, width: u32, height: u32) -> Self {
+        let cameras = scene.camera_entities();
+        let active_entity = cameras
+            .iter()
+            .copied()
+            .find(|&i| {
+                let camera = scene.entities.get(i).and_then(|e| scene.cameras.get(e.camera_index?));
+                camera.is_some_and(|c| c.active)
+            })
+            .or_else(|| cameras.first().copied());
+
+        Self {
+            active_entity,
+            debug: DebugCamera::new(),
+            debug_enabled: false,
+            viewport_width: width,
+            viewport_height: height,
+            view: Mat4::IDENTITY,
+            projection: Mat4::IDENTITY,
+            position: Vec3::ZERO,
+        }
+    }
+
+    /// Update the viewport size. Returns true if it changed.
+    pub fn resize(&mut self, width: u32, height: u32) -> bool {
+        if width == self.viewport_width && height == self.viewport_height {
+            return false;
+        }
+        self.viewport_width = width;
+        self.viewport_height = height;
+        true
+    }
+
+    /// Switch to the camera attached to `entity_index`. Returns false if the
+    /// entity has no camera.
+    pub fn set_active_entity(&mut self, scene: &LoadedScene, entity_index: usize) -> bool {
+        let has_camera = scene
+            .entities
+            .get(entity_index)
+            .and_then(|e| e.camera_index)
+            .is_some_and(|c| c < scene.cameras.len());
+        if has_camera {
+            self.active_entity = Some(entity_index);
+            self.debug_enabled = false;
+        }
+        has_camera
+    }
+
+    /// Advance to the next camera entity in scene order (wrapping around).
+    pub fn next_camera(&mut self, scene: &LoadedScene) -> Option<usize> {
+        let cameras = scene.camera_entities();
+        let next = match self.active_entity.and_then(|a| cameras.iter().position(|&c| c == a)) {
+            Some(pos) => cameras.get((pos + 1) % cameras.len()).copied(),
+            None => cameras.first().copied(),
+        };
+        if let Some(i) = next {
+            self.active_entity = Some(i);
+            self.debug_enabled = false;
+        }
+        next
+    }
+
+    /// Switch to the debug camera, starting from the current viewpoint.
+    pub fn enable_debug(&mut self, mode: DebugCameraMode) {
+        if !self.debug_enabled {
+            let forward = self.view.inverse().transform_vector3(Vec3::NEG_Z);
+            self.debug.look_from(self.position, forward);
+        }
+        self.debug.mode = mode;
+        self.debug_enabled = true;
+    }
+
+    pub fn disable_debug(&mut self) {
+        self.debug_enabled = false;
+    }
+
+    fn viewport_aspect(&self) -> Option<f32> {
+        if self.viewport_width > 0 && self.viewport_height > 0 {
+            Some(self.viewport_width as f32 / self.viewport_height as f32)
+        } else {
+            None
+        }
+    }
+
+    /// Recompute view/projection for this frame. Must run after world transforms.
+    pub fn update(&mut self, scene: &LoadedScene, input: &InputState, dt: f32) {
+        let entity_camera = if self.debug_enabled {
+            None
+        } else {
+            self.active_entity.and_then(|i| {
+                let entity = scene.entities.get(i)?;
+                let camera = scene.cameras.get(entity.camera_index?)?;
+                Some((entity, camera))
+            })
+        };
+
+        match entity_camera {
+            Some((entity, camera)) => {
+                let (_, rotation, translation) = entity.world_transform.to_scale_rotation_translation();
+                let aspect = self.viewport_aspect().unwrap_or(camera.aspect);
+                // Drop scale so a scaled parent doesn't distort the view.
+                self.view = Mat4::from_rotation_translation(rotation, translation).inverse();
+                self.projection = Mat4::perspective_rh(camera.fov.to_radians(), aspect, camera.near, camera.far);
+                self.position = translation;
+            }
+            None => {
+                // No usable scene camera — fall back to the debug camera.
+                self.debug.update(input, dt);
+                let aspect = self.viewport_aspect().unwrap_or(1.0);
+                self.view = self.debug.view_matrix();
+                self.projection =
+                    Mat4::perspective_rh(self.debug.fov.to_radians(), aspect, self.debug.near, self.debug.far);
+                self.position = self.debug.position;
+            }
+        }
+    }
+
+    pub fn view_projection(&self) -> Mat4 {
+        self.projection * self.view
+    }
+
+    /// Pack the current matrices into the shared per-frame uniform layout.
+    pub fn per_frame_uniforms(&self, time: f32) -> PerFrameUniforms {
+        PerFrameUniforms {
+            view: self.view.to_cols_array_2d(),
+            projection: self.projection.to_cols_array_2d(),
+            inv_view_proj: self.view_projection().inverse().to_cols_array_2d(),
+            camera_pos: [self.position.x, self.position.y, self.position.z, 1.0],
+            time,
+            _pad1: 0.0,
+            _pad2: 0.0,
+            _pad3: 0.0,
+            _alignment_pad: [0.0; 8],
+        }
+    }
+}
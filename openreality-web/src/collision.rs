@@ -0,0 +1,82 @@
+use std::collections::BTreeSet;
+
+use glam::Vec3;
+use openreality_gpu_shared::collision::{RayHit, WorldShape};
+
+use crate::events::GameEvent;
+use crate::scene::LoadedScene;
+
+/// World-space collider of one entity.
+struct Placed {
+    entity: usize,
+    shape: WorldShape,
+    min: Vec3,
+    max: Vec3,
+}
+
+fn placed_colliders(scene: &LoadedScene, include_triggers: bool) -> Vec<Placed> {
+    scene
+        .entities
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| e.alive)
+        .filter_map(|(i, e)| {
+            let collider = scene.colliders.get(e.collider_index?)?;
+            if collider.is_trigger && !include_triggers {
+                return None;
+            }
+            let shape = WorldShape::from_collider(collider, &e.world_transform);
+            let (min, max) = shape.aabb();
+            Some(Placed { entity: i, shape, min, max })
+        })
+        .collect()
+}
+
+/// Detect overlapping colliders and raise enter/exit events for pairs that
+/// started or stopped touching since the last call. There is no collision
+/// response here; overlaps come from animation and script-driven movement.
+/// Must run after world transforms.
+pub fn update_collisions(scene: &mut LoadedScene) {
+    let mut placed = placed_colliders(scene, true);
+
+    // Sweep and prune along X
+    placed.sort_by(|a, b| a.min.x.total_cmp(&b.min.x));
+    let mut contacts = BTreeSet::new();
+    for (i, a) in placed.iter().enumerate() {
+        for b in &placed[i + 1..] {
+            if b.min.x > a.max.x {
+                break;
+            }
+            let bounds_overlap = a.min.y <= b.max.y && b.min.y <= a.max.y && a.min.z <= b.max.z && b.min.z <= a.max.z;
+            if bounds_overlap && a.shape.overlaps(&b.shape) {
+                contacts.insert((a.entity.min(b.entity), a.entity.max(b.entity)));
+            }
+        }
+    }
+
+    let LoadedScene { entities, contacts: previous, events, .. } = scene;
+    for &(a, b) in contacts.difference(previous) {
+        events.push(GameEvent::CollisionEnter { entity: entities[a].id, other: entities[b].id });
+    }
+    for &(a, b) in previous.difference(&contacts) {
+        events.push(GameEvent::CollisionExit { entity: entities[a].id, other: entities[b].id });
+    }
+    *previous = contacts;
+}
+
+/// Closest collider hit by the ray `origin + t * dir` within `max_distance`.
+/// Returns the entity index and hit. Trigger volumes are skipped unless
+/// `include_triggers`.
+pub fn raycast(
+    scene: &LoadedScene,
+    origin: Vec3,
+    dir: Vec3,
+    max_distance: f32,
+    include_triggers: bool,
+) -> Option<(usize, RayHit)> {
+    let dir = dir.try_normalize()?;
+    placed_colliders(scene, include_triggers)
+        .into_iter()
+        .filter_map(|p| p.shape.raycast(origin, dir, max_distance).map(|hit| (p.entity, hit)))
+        .min_by(|a, b| a.1.distance.total_cmp(&b.1.distance))
+}
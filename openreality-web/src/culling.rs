@@ -0,0 +1,72 @@
+use glam::{Mat4, Vec3};
+use openreality_gpu_shared::lod::select_lod_level;
+use openreality_gpu_shared::math::{extract_frustum_planes, sphere_in_frustum};
+
+use crate::scene::LoadedScene;
+
+/// Which level of `LoadedScene::lod_chains[chain]` an entity drew last frame.
+#[derive(Clone, Copy)]
+pub struct LodState {
+    pub chain: usize,
+    pub level: usize,
+}
+
+/// An entity drawn this frame and the mesh it is drawn with.
+#[derive(Clone, Copy)]
+pub struct DrawItem {
+    pub entity: usize,
+    pub mesh: usize,
+}
+
+/// Rebuild `scene.draw_list` from the live mesh entities whose bounds touch
+/// the camera frustum, swapping in the LOD level for their camera distance.
+/// Skinned entities are always drawn, since their poses can leave the
+/// bind-pose bounds. Needs up-to-date world transforms.
+pub fn update_visibility(scene: &mut LoadedScene, view_proj: &Mat4, camera_pos: Vec3) {
+    let planes = extract_frustum_planes(view_proj);
+    scene.draw_list.clear();
+    for (i, entity) in scene.entities.iter_mut().enumerate() {
+        let Some(mesh) = entity.mesh_index.filter(|_| entity.alive) else { continue };
+        let Some(bounds) = scene.mesh_bounds.get(mesh) else { continue };
+        let world = bounds.transformed(&entity.world_transform);
+        if entity.skeleton_index.is_none() && !sphere_in_frustum(&planes, world.center, world.radius) {
+            continue;
+        }
+
+        let mut mesh = mesh;
+        if let Some(lod) = &mut entity.lod {
+            let chain = &scene.lod_chains[lod.chain];
+            if !chain.levels.is_empty() {
+                let distances: Vec<f32> = chain.levels.iter().map(|l| l.max_distance).collect();
+                lod.level = select_lod_level(&distances, chain.hysteresis, world.center.distance(camera_pos), lod.level);
+                mesh = chain.levels[lod.level].mesh_index;
+            }
+        }
+        scene.draw_list.push(DrawItem { entity: i, mesh });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openreality_gpu_shared::math::BoundingSphere;
+
+    #[test]
+    fn test_skinned_entities_are_not_culled() {
+        let mut scene = LoadedScene::empty();
+        scene.mesh_bounds.push(BoundingSphere { center: Vec3::ZERO, radius: 1.0 });
+        // Both entities sit behind the camera
+        for skeleton in [None, Some(0)] {
+            let e = scene.spawn_entity("mesh", None);
+            scene.entities[e].mesh_index = Some(0);
+            scene.entities[e].skeleton_index = skeleton;
+            scene.entities[e].world_transform = Mat4::from_translation(Vec3::new(0.0, 0.0, 10.0));
+        }
+        let view = Mat4::look_at_rh(Vec3::ZERO, Vec3::NEG_Z, Vec3::Y);
+        let view_proj = Mat4::perspective_rh(1.0, 1.0, 0.1, 100.0) * view;
+
+        update_visibility(&mut scene, &view_proj, Vec3::ZERO);
+        let drawn: Vec<usize> = scene.draw_list.iter().map(|d| d.entity).collect();
+        assert_eq!(drawn, vec![1]);
+    }
+}
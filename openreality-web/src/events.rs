@@ -0,0 +1,99 @@
+/// Kinds of gameplay events scripts can subscribe to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventKind {
+    CollisionEnter,
+    CollisionExit,
+    AnimationFinished,
+}
+
+impl EventKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "collision_enter" => Some(EventKind::CollisionEnter),
+            "collision_exit" => Some(EventKind::CollisionExit),
+            "animation_finished" => Some(EventKind::AnimationFinished),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EventKind::CollisionEnter => "collision_enter",
+            EventKind::CollisionExit => "collision_exit",
+            EventKind::AnimationFinished => "animation_finished",
+        }
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// A gameplay event, referring to entities by id.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    CollisionEnter { entity: u64, other: u64 },
+    CollisionExit { entity: u64, other: u64 },
+    /// A non-looping clip on the entity's base layer reached its end.
+    AnimationFinished { entity: u64, clip: String },
+}
+
+impl GameEvent {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            GameEvent::CollisionEnter { .. } => EventKind::CollisionEnter,
+            GameEvent::CollisionExit { .. } => EventKind::CollisionExit,
+            GameEvent::AnimationFinished { .. } => EventKind::AnimationFinished,
+        }
+    }
+}
+
+/// Events raised during a frame, queued until scripts drain them.
+/// Only subscribed kinds are kept, so unobserved events cost nothing.
+/// JavaScript and bundle scripts subscribe and drain independently.
+#[derive(Default)]
+pub struct EventQueue {
+    subscribed: u8,
+    pending: Vec<GameEvent>,
+    script_subscribed: u8,
+    script_pending: Vec<GameEvent>,
+}
+
+impl EventQueue {
+    pub fn subscribe(&mut self, kind: EventKind) {
+        self.subscribed |= kind.bit();
+    }
+
+    pub fn unsubscribe(&mut self, kind: EventKind) {
+        self.subscribed &= !kind.bit();
+        self.pending.retain(|e| e.kind() != kind);
+    }
+
+    pub fn is_subscribed(&self, kind: EventKind) -> bool {
+        self.subscribed & kind.bit() != 0
+    }
+
+    /// Queue events of `kind` for bundle scripts.
+    pub fn subscribe_scripts(&mut self, kind: EventKind) {
+        self.script_subscribed |= kind.bit();
+    }
+
+    pub fn push(&mut self, event: GameEvent) {
+        if self.script_subscribed & event.kind().bit() != 0 {
+            self.script_pending.push(event.clone());
+        }
+        if self.is_subscribed(event.kind()) {
+            self.pending.push(event);
+        }
+    }
+
+    /// Take every queued event, oldest first.
+    pub fn drain(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.pending)
+    }
+
+    /// Take every event queued for bundle scripts, oldest first.
+    pub fn drain_scripts(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.script_pending)
+    }
+}
@@ -6,6 +6,8 @@ pub struct InputState {
     pub mouse_dx: f64,
     pub mouse_dy: f64,
     pub mouse_buttons: [bool; 3],
+    /// Accumulated wheel scroll since the last frame (positive = away from the user).
+    pub wheel_delta: f64,
 }
 
 impl InputState {
@@ -17,16 +19,45 @@ impl InputState {
             mouse_dx: 0.0,
             mouse_dy: 0.0,
             mouse_buttons: [false; 3],
+            wheel_delta: 0.0,
         }
     }
 
-    /// Reset per-frame deltas.
+    /// Reset per-frame deltas. Called at the end of a frame, after all systems
+    /// have consumed the input gathered since the previous frame.
     pub fn update(&mut self) {
         self.mouse_dx = 0.0;
         self.mouse_dy = 0.0;
+        self.wheel_delta = 0.0;
     }
 
     pub fn is_key_down(&self, key_code: u8) -> bool {
         self.keys_down[key_code as usize]
     }
+
+    pub fn is_mouse_down(&self, button: usize) -> bool {
+        self.mouse_buttons.get(button).copied().unwrap_or(false)
+    }
+
+    pub fn set_key(&mut self, key_code: u8, down: bool) {
+        self.keys_down[key_code as usize] = down;
+    }
+
+    /// Record a pointer move. Deltas accumulate until the next `update()`.
+    pub fn move_mouse(&mut self, x: f64, y: f64, dx: f64, dy: f64) {
+        self.mouse_x = x;
+        self.mouse_y = y;
+        self.mouse_dx += dx;
+        self.mouse_dy += dy;
+    }
+
+    pub fn set_mouse_button(&mut self, button: usize, down: bool) {
+        if let Some(b) = self.mouse_buttons.get_mut(button) {
+            *b = down;
+        }
+    }
+
+    pub fn scroll(&mut self, delta: f64) {
+        self.wheel_delta += delta;
+    }
 }
@@ -8,9 +8,18 @@ mod app;
 mod scene;
 mod transform;
 mod animation;
+mod anim_state_machine;
 mod skinning;
+mod morph;
 mod particles;
+mod collision;
+mod events;
+mod scripting;
+mod reload;
+mod prefab;
+mod culling;
 mod input;
+mod camera;
 
 use wasm_bindgen::prelude::*;
 
@@ -0,0 +1,87 @@
+use openreality_gpu_shared::morph::apply_morph_targets;
+
+use crate::scene::LoadedScene;
+
+/// Recompute deformed vertices for every entity whose morph weights changed.
+pub fn update_morph_targets(scene: &mut LoadedScene) {
+    let LoadedScene { entities, meshes, .. } = scene;
+    for entity in entities.iter_mut() {
+        let Some(morph) = entity.morph.as_mut().filter(|m| m.dirty) else { continue };
+        let Some(mesh) = entity.mesh_index.and_then(|m| meshes.get(m)) else { continue };
+        apply_morph_targets(
+            &mesh.positions,
+            &mesh.normals,
+            &mesh.morph_targets,
+            &morph.weights,
+            &mut morph.positions,
+            &mut morph.normals,
+        );
+        morph.dirty = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::{MeshData, MorphState};
+    use openreality_gpu_shared::scene_format::MorphTargetParsed;
+
+    fn morphed_scene() -> (LoadedScene, usize) {
+        let mesh = MeshData {
+            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+            normals: vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0],
+            uvs: Vec::new(),
+            indices: Vec::new(),
+            bone_weights: None,
+            bone_indices: None,
+            morph_targets: vec![
+                MorphTargetParsed { position_deltas: vec![0.0, 2.0, 0.0, 0.0, 2.0, 0.0], normal_deltas: Vec::new() },
+                MorphTargetParsed {
+                    position_deltas: vec![0.0, 0.0, 1.0, 0.0, 0.0, 0.0],
+                    normal_deltas: vec![1.0, 0.0, -1.0, 0.0, 0.0, 0.0],
+                },
+            ],
+        };
+        let mut scene = LoadedScene::empty();
+        let e = scene.spawn_entity("face", None);
+        scene.entities[e].morph = MorphState::for_mesh(&mesh);
+        scene.entities[e].mesh_index = Some(scene.meshes.len());
+        scene.meshes.push(mesh);
+        (scene, e)
+    }
+
+    #[test]
+    fn test_matches_the_wgpu_backend_blend() {
+        let (mut scene, e) = morphed_scene();
+        let weights = [0.5, 1.0];
+        let morph = scene.entities[e].morph.as_mut().unwrap();
+        morph.weights.copy_from_slice(&weights);
+        morph.dirty = true;
+        update_morph_targets(&mut scene);
+
+        // The wgpu backend uploads exactly this in `set_morph_weights`
+        let mesh = &scene.meshes[0];
+        let (mut positions, mut normals) = (Vec::new(), Vec::new());
+        apply_morph_targets(&mesh.positions, &mesh.normals, &mesh.morph_targets, &weights, &mut positions, &mut normals);
+        let morph = scene.entities[e].morph.as_ref().unwrap();
+        assert_eq!(morph.positions, positions);
+        assert_eq!(morph.normals, normals);
+
+        assert_eq!(morph.positions, vec![0.0, 1.0, 1.0, 1.0, 1.0, 0.0]);
+        assert_eq!(morph.normals, vec![1.0, 0.0, 0.0, 0.0, 0.0, 1.0]);
+        assert!(!morph.dirty);
+    }
+
+    #[test]
+    fn test_only_dirty_morphs_are_recomputed() {
+        let (mut scene, e) = morphed_scene();
+        let morph = scene.entities[e].morph.as_mut().unwrap();
+        morph.weights[0] = 1.0;
+        update_morph_targets(&mut scene);
+        assert_eq!(scene.entities[e].morph.as_ref().unwrap().positions, scene.meshes[0].positions);
+
+        scene.entities[e].morph.as_mut().unwrap().dirty = true;
+        update_morph_targets(&mut scene);
+        assert_eq!(scene.entities[e].morph.as_ref().unwrap().positions, vec![0.0, 2.0, 0.0, 1.0, 2.0, 0.0]);
+    }
+}
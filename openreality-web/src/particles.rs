@@ -1,49 +1,12 @@
-use glam::Vec3;
-
-/// Configuration for a particle emitter (matches Julia's ParticleSystemComponent).
-pub struct ParticleConfig {
-    pub max_particles: usize,
-    pub emission_rate: f32,
-    pub burst_count: i32,
-    pub lifetime_min: f32,
-    pub lifetime_max: f32,
-    pub velocity_min: Vec3,
-    pub velocity_max: Vec3,
-    pub gravity_modifier: f32,
-    pub damping: f32,
-    pub start_size_min: f32,
-    pub start_size_max: f32,
-    pub end_size: f32,
-    pub start_color: [f32; 3],
-    pub end_color: [f32; 3],
-    pub start_alpha: f32,
-    pub end_alpha: f32,
-    pub additive: bool,
-}
+use glam::{Mat4, Vec3};
+use openreality_gpu_shared::particles::{
+    collide_ground_plane, color_at, orient_velocity, sample_shape, sheet_frame_uv, size_at,
+    velocity_offset_at, MeshSurfaceSampler,
+};
+use openreality_gpu_shared::rng::{Rng, RngStreams};
+use openreality_gpu_shared::scene_format::{EmitterShape, ParticleCollisionMode, ParticleEmitterParsed};
 
-impl Default for ParticleConfig {
-    fn default() -> Self {
-        Self {
-            max_particles: 256,
-            emission_rate: 20.0,
-            burst_count: 0,
-            lifetime_min: 1.0,
-            lifetime_max: 2.0,
-            velocity_min: Vec3::new(-0.5, 1.0, -0.5),
-            velocity_max: Vec3::new(0.5, 3.0, 0.5),
-            gravity_modifier: 1.0,
-            damping: 0.0,
-            start_size_min: 0.1,
-            start_size_max: 0.3,
-            end_size: 0.0,
-            start_color: [1.0, 1.0, 1.0],
-            end_color: [1.0, 1.0, 1.0],
-            start_alpha: 1.0,
-            end_alpha: 0.0,
-            additive: false,
-        }
-    }
-}
+use crate::scene::LoadedScene;
 
 struct Particle {
     position: Vec3,
@@ -54,12 +17,43 @@ struct Particle {
     alive: bool,
 }
 
+impl Particle {
+    const DEAD: Particle = Particle {
+        position: Vec3::ZERO,
+        velocity: Vec3::ZERO,
+        lifetime: 0.0,
+        max_lifetime: 1.0,
+        size: 0.0,
+        alive: false,
+    };
+
+    /// Normalized age: 0 = just born, 1 = dying.
+    fn t(&self) -> f32 {
+        1.0 - (self.lifetime / self.max_lifetime).clamp(0.0, 1.0)
+    }
+}
+
 const GRAVITY: Vec3 = Vec3::new(0.0, -9.81, 0.0);
 
+/// Emitter random streams are keyed by entity id with the top bit set, which
+/// keeps them clear of the gameplay streams (`App::random`, u32 keys).
+const PARTICLE_STREAM_BASE: u64 = 1 << 63;
+
+/// Camera position and billboard axes, in world space.
+pub struct BillboardCamera {
+    pub position: Vec3,
+    pub right: Vec3,
+    pub up: Vec3,
+}
+
 /// A particle pool for one emitter. Manages particle simulation and billboard vertex generation.
 pub struct ParticlePool {
     particles: Vec<Particle>,
     emit_accumulator: f32,
+    /// One-shot burst still to be emitted (consumed on the next update).
+    pub pending_burst: u32,
+    /// Emission surface for `EmitterShape::Mesh` emitters.
+    pub mesh_surface: Option<MeshSurfaceSampler>,
     /// Flat vertex data: 6 vertices per particle, 9 floats per vertex (pos3 + uv2 + rgba4).
     pub vertex_data: Vec<f32>,
     pub vertex_count: usize,
@@ -69,19 +63,12 @@ pub struct ParticlePool {
 impl ParticlePool {
     pub fn new(max_particles: usize) -> Self {
         let mut particles = Vec::with_capacity(max_particles);
-        for _ in 0..max_particles {
-            particles.push(Particle {
-                position: Vec3::ZERO,
-                velocity: Vec3::ZERO,
-                lifetime: 0.0,
-                max_lifetime: 1.0,
-                size: 0.0,
-                alive: false,
-            });
-        }
+        particles.resize_with(max_particles, || Particle::DEAD);
         Self {
             particles,
             emit_accumulator: 0.0,
+            pending_burst: 0,
+            mesh_surface: None,
             vertex_data: vec![0.0; max_particles * 6 * 9],
             vertex_count: 0,
             alive_count: 0,
@@ -91,40 +78,36 @@ impl ParticlePool {
     /// Resize the pool if max_particles changed.
     pub fn resize(&mut self, max_particles: usize) {
         if self.particles.len() != max_particles {
-            self.particles.resize_with(max_particles, || Particle {
-                position: Vec3::ZERO,
-                velocity: Vec3::ZERO,
-                lifetime: 0.0,
-                max_lifetime: 1.0,
-                size: 0.0,
-                alive: false,
-            });
+            self.particles.resize_with(max_particles, || Particle::DEAD);
             self.vertex_data.resize(max_particles * 6 * 9, 0.0);
         }
     }
 
-    /// Emit a single particle at the given origin.
-    fn emit(&mut self, origin: Vec3, config: &ParticleConfig) -> bool {
-        for p in &mut self.particles {
-            if !p.alive {
-                p.position = origin;
-                p.velocity = Vec3::new(
-                    rand_range(config.velocity_min.x, config.velocity_max.x),
-                    rand_range(config.velocity_min.y, config.velocity_max.y),
-                    rand_range(config.velocity_min.z, config.velocity_max.z),
-                );
-                p.max_lifetime = rand_range(config.lifetime_min, config.lifetime_max);
-                p.lifetime = p.max_lifetime;
-                p.size = rand_range(config.start_size_min, config.start_size_max);
-                p.alive = true;
-                return true;
-            }
-        }
-        false
+    /// Emit a single particle from the emitter's shape, placed by `world`.
+    fn emit(&mut self, world: &Mat4, config: &ParticleEmitterParsed, rng: &mut Rng) -> bool {
+        let Some(p) = self.particles.iter_mut().find(|p| !p.alive) else { return false };
+
+        let (offset, dir) = sample_shape(&config.shape, self.mesh_surface.as_ref(), &mut || rng.next_f32());
+        let (lo, hi) = (Vec3::from_array(config.velocity_min), Vec3::from_array(config.velocity_max));
+        let local_velocity = Vec3::new(
+            rng.range(lo.x, hi.x),
+            rng.range(lo.y, hi.y),
+            rng.range(lo.z, hi.z),
+        );
+
+        // The shape is scaled with the entity; velocities are only rotated.
+        let (_, rotation, _) = world.to_scale_rotation_translation();
+        p.position = world.transform_point3(offset);
+        p.velocity = rotation * orient_velocity(local_velocity, dir);
+        p.max_lifetime = rng.range(config.lifetime_min, config.lifetime_max);
+        p.lifetime = p.max_lifetime;
+        p.size = rng.range(config.start_size_min, config.start_size_max);
+        p.alive = true;
+        true
     }
 
     /// Simulate physics for all alive particles.
-    fn simulate(&mut self, dt: f32, gravity_modifier: f32, damping: f32) {
+    fn simulate(&mut self, dt: f32, config: &ParticleEmitterParsed) {
         let mut alive = 0;
         for p in &mut self.particles {
             if !p.alive {
@@ -135,9 +118,13 @@ impl ParticlePool {
                 p.alive = false;
                 continue;
             }
-            p.velocity += GRAVITY * gravity_modifier * dt;
-            p.velocity *= 1.0 - damping * dt;
-            p.position += p.velocity * dt;
+            p.velocity += GRAVITY * config.gravity_modifier * dt;
+            p.velocity *= 1.0 - config.damping * dt;
+            p.position += (p.velocity + velocity_offset_at(config, p.t())) * dt;
+            // Depth-buffer collisions need the GPU path; the CPU only knows the ground plane.
+            if config.collision == ParticleCollisionMode::GroundPlane {
+                collide_ground_plane(&mut p.position, &mut p.velocity, config);
+            }
             alive += 1;
         }
         self.alive_count = alive;
@@ -168,7 +155,7 @@ impl ParticlePool {
     /// Build billboard vertex data for rendering.
     fn build_billboards(
         &mut self,
-        config: &ParticleConfig,
+        config: &ParticleEmitterParsed,
         cam_right: Vec3,
         cam_up: Vec3,
     ) {
@@ -180,14 +167,16 @@ impl ParticlePool {
                 continue;
             }
 
-            let t = 1.0 - (p.lifetime / p.max_lifetime).clamp(0.0, 1.0);
-            let size = lerp(p.size, config.end_size, t);
-            let half = size * 0.5;
+            let t = p.t();
+            let half = size_at(config, p.size, t) * 0.5;
+            let [r, g, b, a] = color_at(config, t);
 
-            let r = lerp(config.start_color[0], config.end_color[0], t);
-            let g = lerp(config.start_color[1], config.end_color[1], t);
-            let b = lerp(config.start_color[2], config.end_color[2], t);
-            let a = lerp(config.start_alpha, config.end_alpha, t);
+            // Sheet tiles run top-to-bottom, so v = 0 is the top edge of the quad
+            let ([u0, v0], [su, sv]) = match &config.texture_sheet {
+                Some(sheet) => sheet_frame_uv(sheet, p.max_lifetime - p.lifetime, t),
+                None => ([0.0, 0.0], [1.0, 1.0]),
+            };
+            let (u1, v1) = (u0 + su, v0 + sv);
 
             let right = cam_right * half;
             let up = cam_up * half;
@@ -198,8 +187,8 @@ impl ParticlePool {
             let tl = p.position - right + up;
 
             // Two triangles: BL, BR, TR and BL, TR, TL
-            let corners = [(bl, 0.0, 0.0), (br, 1.0, 0.0), (tr, 1.0, 1.0),
-                           (bl, 0.0, 0.0), (tr, 1.0, 1.0), (tl, 0.0, 1.0)];
+            let corners = [(bl, u0, v1), (br, u1, v1), (tr, u1, v0),
+                           (bl, u0, v1), (tr, u1, v0), (tl, u0, v0)];
 
             for (pos, u, v) in corners {
                 if offset + 9 <= self.vertex_data.len() {
@@ -221,55 +210,78 @@ impl ParticlePool {
     }
 
     /// Full per-frame update: emit, simulate, sort, build billboards.
+    /// All randomness comes from `rng`, so equal seeds replay identically.
     pub fn update(
         &mut self,
         dt: f32,
-        origin: Vec3,
-        config: &mut ParticleConfig,
-        cam_pos: Vec3,
-        cam_right: Vec3,
-        cam_up: Vec3,
+        world: &Mat4,
+        config: &ParticleEmitterParsed,
+        rng: &mut Rng,
+        camera: &BillboardCamera,
     ) {
-        self.resize(config.max_particles);
+        self.resize(config.max_particles as usize);
 
         // Burst emission
-        if config.burst_count > 0 {
-            for _ in 0..config.burst_count {
-                if !self.emit(origin, config) { break; }
-            }
-            config.burst_count = 0;
+        for _ in 0..self.pending_burst {
+            if !self.emit(world, config, rng) { break; }
         }
+        self.pending_burst = 0;
 
         // Continuous emission
         self.emit_accumulator += config.emission_rate * dt;
         while self.emit_accumulator >= 1.0 {
-            if !self.emit(origin, config) { break; }
+            if !self.emit(world, config, rng) { break; }
             self.emit_accumulator -= 1.0;
         }
 
-        self.simulate(dt, config.gravity_modifier, config.damping);
-        self.sort_back_to_front(cam_pos);
-        self.build_billboards(config, cam_right, cam_up);
+        self.simulate(dt, config);
+        self.sort_back_to_front(camera.position);
+        self.build_billboards(config, camera.right, camera.up);
     }
 }
 
-fn lerp(a: f32, b: f32, t: f32) -> f32 {
-    a + (b - a) * t
+/// Runtime state of one entity's particle emitter.
+pub struct ParticleSystemState {
+    pub entity_index: usize,
+    /// Index into `LoadedScene::particle_emitters`.
+    pub emitter_index: usize,
+    pub pool: ParticlePool,
 }
 
-/// Simple deterministic-ish pseudo-random for WASM (no std rand).
-/// Uses a global xorshift state.
-static mut RAND_STATE: u32 = 12345;
-
-fn rand_f32() -> f32 {
-    unsafe {
-        RAND_STATE ^= RAND_STATE << 13;
-        RAND_STATE ^= RAND_STATE >> 17;
-        RAND_STATE ^= RAND_STATE << 5;
-        (RAND_STATE as f32) / (u32::MAX as f32)
+impl ParticleSystemState {
+    pub fn new(entity_index: usize, emitter_index: usize, config: &ParticleEmitterParsed) -> Self {
+        let mut pool = ParticlePool::new(config.max_particles as usize);
+        pool.pending_burst = config.burst_count;
+        Self { entity_index, emitter_index, pool }
     }
 }
 
-fn rand_range(lo: f32, hi: f32) -> f32 {
-    lo + (hi - lo) * rand_f32()
+/// Advance every particle emitter and rebuild its billboards for the camera
+/// described by `view` (world-to-view) and `cam_pos`. Each emitter draws from
+/// its own stream in `rng`. Must run after world transforms.
+pub fn update_particles(scene: &mut LoadedScene, rng: &mut RngStreams, dt: f32, view: &Mat4, cam_pos: Vec3) {
+    let inv_view = view.inverse();
+    let camera = BillboardCamera {
+        position: cam_pos,
+        right: inv_view.x_axis.truncate().normalize_or_zero(),
+        up: inv_view.y_axis.truncate().normalize_or_zero(),
+    };
+
+    let LoadedScene { entities, meshes, particle_emitters, particle_systems, .. } = scene;
+    for system in particle_systems.iter_mut() {
+        let Some(config) = particle_emitters.get(system.emitter_index) else { continue };
+        let Some(entity) = entities.get(system.entity_index).filter(|e| e.alive) else { continue };
+
+        // The mesh emission surface is built on first use
+        if let EmitterShape::Mesh { mesh } = config.shape {
+            if system.pool.mesh_surface.is_none() {
+                system.pool.mesh_surface = meshes
+                    .get(mesh as usize)
+                    .and_then(|m| MeshSurfaceSampler::new(&m.positions, &m.indices));
+            }
+        }
+
+        let stream = rng.stream(PARTICLE_STREAM_BASE | entity.id);
+        system.pool.update(dt, &entity.world_transform, config, stream, &camera);
+    }
 }
@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+
+use glam::{DQuat, DVec3};
+use openreality_gpu_shared::scene_format::{PrefabParsed, ScriptAttachmentParsed};
+
+use crate::anim_state_machine::AnimStateMachine;
+use crate::particles::ParticleSystemState;
+use crate::scene::{AnimationState, Entity, LoadedScene, MorphState, SkeletonData};
+
+/// Entity sub-graph that can be instantiated at runtime. Indices inside a
+/// prefab (parents, animation targets, layer masks, skeleton bones, script
+/// attachments) are positions in `entities`, so a template does not depend on
+/// scene slots.
+pub struct Prefab {
+    pub name: String,
+    /// Template entities, root first.
+    entities: Vec<Entity>,
+    /// Animation of the template entity at each position, with its state machine.
+    animations: Vec<(usize, AnimationState, Option<AnimStateMachine>)>,
+    /// Skeletons whose mesh and bones all lie inside the template.
+    skeletons: Vec<SkeletonData>,
+    scripts: Vec<ScriptAttachmentParsed>,
+}
+
+/// Transform applied to a prefab instance's root; `None` keeps the template value.
+#[derive(Clone, Copy, Default)]
+pub struct TransformOverride {
+    pub position: Option<DVec3>,
+    pub rotation: Option<DQuat>,
+    pub scale: Option<DVec3>,
+}
+
+/// Move the entities of every parsed prefab out of the scene into templates.
+/// Their slots are freed for runtime spawns.
+pub fn extract_prefabs(scene: &mut LoadedScene, parsed: &[PrefabParsed]) {
+    for p in parsed {
+        let slots: Vec<usize> = (p.first_entity..p.first_entity + p.entity_count).collect();
+        let local: HashMap<usize, usize> = slots.iter().enumerate().map(|(l, &s)| (s, l)).collect();
+
+        let mut entities = Vec::with_capacity(slots.len());
+        let mut animations = Vec::new();
+        for (l, &slot) in slots.iter().enumerate() {
+            let mut entity = scene.entities[slot].clone();
+            entity.parent_index = entity.parent_index.and_then(|p| local.get(&p).copied());
+            if let Some(a) = entity.animation_index.take() {
+                let mut anim = scene.animations[a].clone();
+                remap_animation(&mut anim, |i| local.get(&i).copied());
+                let sm = scene.state_machines.iter().find(|sm| sm.animation_index == a).cloned();
+                animations.push((l, anim, sm));
+            }
+            entities.push(entity);
+        }
+        let skeletons = scene
+            .skeletons
+            .iter()
+            .filter_map(|s| remap_skeleton(s, |i| local.get(&i).copied()))
+            .collect();
+        let scripts = scene
+            .script_attachments
+            .iter()
+            .filter_map(|a| Some(ScriptAttachmentParsed { entity_index: *local.get(&a.entity_index)?, ..a.clone() }))
+            .collect();
+        scene.script_attachments.retain(|a| !local.contains_key(&a.entity_index));
+
+        scene.prefabs.push(Prefab { name: p.name.clone(), entities, animations, skeletons, scripts });
+        // The root's subtree covers the whole range unless the range is malformed
+        for &slot in &slots {
+            if scene.entities[slot].alive {
+                scene.despawn_entity(slot);
+            }
+        }
+    }
+    scene.recycle_slots();
+}
+
+/// Point animation channels and layer masks through `map`; channels whose
+/// target it does not cover are dropped.
+fn remap_animation(anim: &mut AnimationState, map: impl Fn(usize) -> Option<usize>) {
+    for clip in &mut anim.clips {
+        clip.channels.retain_mut(|c| match map(c.target_entity_index) {
+            Some(i) => {
+                c.target_entity_index = i;
+                true
+            }
+            None => false,
+        });
+    }
+    for layer in &mut anim.layers {
+        if let Some(mask) = &mut layer.mask {
+            let mut remapped = Vec::new();
+            for (i, _) in mask.iter().enumerate().filter(|(_, &on)| on) {
+                if let Some(j) = map(i) {
+                    if remapped.len() <= j {
+                        remapped.resize(j + 1, false);
+                    }
+                    remapped[j] = true;
+                }
+            }
+            *mask = remapped;
+        }
+    }
+}
+
+/// Copy of `skeleton` with its mesh and bones pointed through `map`, or
+/// `None` if `map` does not cover all of them.
+fn remap_skeleton(skeleton: &SkeletonData, map: impl Fn(usize) -> Option<usize>) -> Option<SkeletonData> {
+    Some(SkeletonData {
+        entity_index: map(skeleton.entity_index)?,
+        bone_entity_indices: skeleton.bone_entity_indices.iter().map(|&b| map(b)).collect::<Option<_>>()?,
+        ..skeleton.clone()
+    })
+}
+
+impl LoadedScene {
+    pub fn find_prefab(&self, name: &str) -> Option<usize> {
+        self.prefabs.iter().position(|p| p.name == name)
+    }
+
+    /// Spawn a copy of prefab `prefab` under `parent` and return its root's
+    /// entity index. Every instance gets fresh ids, its own animation,
+    /// skeleton and particle state, and its own script instances.
+    pub fn instantiate_prefab(&mut self, prefab: usize, parent: Option<usize>, overrides: TransformOverride) -> usize {
+        let template = &self.prefabs[prefab];
+        let mut entities = template.entities.clone();
+        let animations = template.animations.clone();
+        let scripts = template.scripts.clone();
+
+        let root = &mut entities[0].transform;
+        root.position = overrides.position.unwrap_or(root.position);
+        root.rotation = overrides.rotation.unwrap_or(root.rotation);
+        root.scale = overrides.scale.unwrap_or(root.scale);
+
+        // Template entities are ordered parent-first, so parents are placed before children
+        let mut slots = Vec::with_capacity(entities.len());
+        for (l, mut entity) in entities.into_iter().enumerate() {
+            entity.parent_index = match entity.parent_index {
+                Some(p) => Some(slots[p]),
+                None if l == 0 => parent,
+                None => None,
+            };
+            entity.morph = entity.mesh_index.and_then(|m| self.meshes.get(m)).and_then(MorphState::for_mesh);
+            let slot = self.insert_entity(entity);
+            if let Some(emitter) = self.entities[slot].particle_index {
+                if let Some(config) = self.particle_emitters.get(emitter) {
+                    self.particle_systems.push(ParticleSystemState::new(slot, emitter, config));
+                }
+            }
+            slots.push(slot);
+        }
+
+        for (l, mut anim, sm) in animations {
+            remap_animation(&mut anim, |i| slots.get(i).copied());
+            let index = self.insert_animation(anim);
+            self.entities[slots[l]].animation_index = Some(index);
+            if let Some(mut sm) = sm {
+                sm.animation_index = index;
+                self.state_machines.push(sm);
+            }
+        }
+
+        let skeletons: Vec<SkeletonData> = self.prefabs[prefab]
+            .skeletons
+            .iter()
+            .filter_map(|s| remap_skeleton(s, |i| slots.get(i).copied()))
+            .collect();
+        self.skeletons.extend(skeletons);
+        self.pending_scripts
+            .extend(scripts.into_iter().map(|a| ScriptAttachmentParsed { entity_index: slots[a.entity_index], ..a }));
+        slots[0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use glam::Mat4;
+
+    use super::*;
+
+    /// A scene holding a two-entity "gun" prefab whose child is animated and
+    /// whose root runs a script.
+    fn scene_with_prefab() -> LoadedScene {
+        let mut scene = LoadedScene::empty();
+        let root = scene.spawn_entity("gun", None);
+        let child = scene.spawn_entity("muzzle", Some(root));
+        let anim = scene.insert_animation(AnimationState { clips: Vec::new(), layers: Vec::new(), playing: true, speed: 1.0 });
+        scene.entities[child].animation_index = Some(anim);
+        scene.script_attachments.push(ScriptAttachmentParsed { entity_index: root, script_index: 0, params: Vec::new() });
+        extract_prefabs(&mut scene, &[PrefabParsed { name: "gun".into(), first_entity: 0, entity_count: 2 }]);
+        scene
+    }
+
+    #[test]
+    fn test_extract_frees_template_slots() {
+        let scene = scene_with_prefab();
+        assert_eq!(scene.find_prefab("gun"), Some(0));
+        assert!(scene.entities.iter().all(|e| !e.alive));
+        assert!(scene.script_attachments.is_empty());
+        assert_eq!(scene.find_entity_by_name("gun"), None);
+    }
+
+    #[test]
+    fn test_instances_get_fresh_slots_ids_and_state() {
+        let mut scene = scene_with_prefab();
+        let at = DVec3::new(1.0, 2.0, 3.0);
+        let a = scene.instantiate_prefab(0, None, TransformOverride { position: Some(at), ..Default::default() });
+        let b = scene.instantiate_prefab(0, Some(a), TransformOverride::default());
+
+        let a_child = scene.entities.iter().position(|e| e.alive && e.parent_index == Some(a) && e.name == "muzzle").unwrap();
+        let b_child = scene.entities.iter().position(|e| e.alive && e.parent_index == Some(b)).unwrap();
+        assert_eq!(scene.entities[a].transform.position, at);
+        assert_eq!(scene.entities[b].transform.position, DVec3::ZERO);
+        assert_eq!(scene.entities[b].parent_index, Some(a));
+
+        // The template's freed slots and animation are reused by the first instance
+        let mut slots = vec![a, a_child, b, b_child];
+        slots.sort();
+        assert_eq!(slots, vec![0, 1, 2, 3]);
+        let ids: BTreeSet<u64> = slots.iter().map(|&i| scene.entities[i].id).collect();
+        assert_eq!(ids.len(), 4);
+        let anims = (scene.entities[a_child].animation_index.unwrap(), scene.entities[b_child].animation_index.unwrap());
+        assert_eq!(anims, (0, 1));
+        assert_eq!(scene.animations.len(), 2);
+
+        let scripted: Vec<usize> = scene.pending_scripts.iter().map(|s| s.entity_index).collect();
+        assert_eq!(scripted, vec![a, b]);
+
+        // Despawning an instance frees its animation for the next one
+        scene.despawn_entity(b);
+        scene.recycle_slots();
+        let c = scene.instantiate_prefab(0, None, TransformOverride::default());
+        let c_child = scene.entities.iter().position(|e| e.alive && e.parent_index == Some(c)).unwrap();
+        assert_eq!(scene.entities[c_child].animation_index, Some(1));
+        assert_eq!(scene.animations.len(), 2);
+    }
+
+    #[test]
+    fn test_instances_get_their_own_skeletons() {
+        let mut scene = LoadedScene::empty();
+        let outside = scene.spawn_entity("outside", None);
+        let body = scene.spawn_entity("body", None);
+        let bone = scene.spawn_entity("bone", Some(body));
+        let skeleton = SkeletonData {
+            entity_index: body,
+            bone_entity_indices: vec![body, bone],
+            inverse_bind_matrices: vec![Mat4::IDENTITY; 2],
+            bone_matrices: Vec::new(),
+            skinned_positions: Vec::new(),
+            skinned_normals: Vec::new(),
+        };
+        // A skeleton posed by an entity outside the prefab cannot be carried along
+        let borrowed = SkeletonData { bone_entity_indices: vec![outside], ..skeleton.clone() };
+        scene.skeletons = vec![skeleton, borrowed];
+        extract_prefabs(&mut scene, &[PrefabParsed { name: "body".into(), first_entity: 1, entity_count: 2 }]);
+        assert!(scene.skeletons.is_empty());
+
+        let a = scene.instantiate_prefab(0, None, TransformOverride::default());
+        let b = scene.instantiate_prefab(0, None, TransformOverride::default());
+        let bone_of = |root| scene.entities.iter().position(|e| e.alive && e.parent_index == Some(root)).unwrap();
+        let posed: Vec<(usize, Vec<usize>)> =
+            scene.skeletons.iter().map(|s| (s.entity_index, s.bone_entity_indices.clone())).collect();
+        assert_eq!(posed, vec![(a, vec![a, bone_of(a)]), (b, vec![b, bone_of(b)])]);
+    }
+}
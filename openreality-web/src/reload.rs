@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+
+use crate::camera::CameraSystem;
+use crate::scene::LoadedScene;
+
+/// What a hot reload kept from the previous scene.
+pub struct ReloadReport {
+    pub kept_entities: usize,
+    pub added_entities: usize,
+    pub removed_entities: usize,
+}
+
+/// Carry runtime state from `old` into the freshly loaded `new`, matching
+/// entities by id. Authored data (transforms, materials, lights, ...) comes
+/// from `new`; animation playback, state machine parameters, live particles,
+/// morph weights, contacts and event subscriptions come from `old` wherever
+/// the entity still has a compatible component. Entities spawned at runtime
+/// are dropped.
+pub fn carry_over(old: &mut LoadedScene, new: &mut LoadedScene) -> ReloadReport {
+    let old_by_id: HashMap<u64, usize> =
+        old.entities.iter().enumerate().filter(|(_, e)| e.alive).map(|(i, e)| (e.id, i)).collect();
+    // new entity index -> old entity index
+    let matches: Vec<Option<usize>> = new.entities.iter().map(|e| old_by_id.get(&e.id).copied()).collect();
+    let kept = matches.iter().flatten().count();
+    let mut to_new = vec![None; old.entities.len()];
+    for (n, o) in matches.iter().enumerate() {
+        if let Some(o) = *o {
+            to_new[o] = Some(n);
+        }
+    }
+
+    for (n, &o) in matches.iter().enumerate() {
+        let Some(o) = o else { continue };
+        carry_animation(old, new, o, n);
+        carry_particles(old, new, o, n);
+        if let (Some(old_morph), Some(new_morph)) = (&old.entities[o].morph, &mut new.entities[n].morph) {
+            if old_morph.weights.len() == new_morph.weights.len() {
+                new_morph.weights.clone_from(&old_morph.weights);
+                new_morph.dirty = true;
+            }
+        }
+    }
+
+    new.contacts = old
+        .contacts
+        .iter()
+        .filter_map(|&(a, b)| {
+            let (a, b) = (to_new[a]?, to_new[b]?);
+            Some((a.min(b), a.max(b)))
+        })
+        .collect();
+    new.events = std::mem::take(&mut old.events);
+    new.next_entity_id = new.next_entity_id.max(old.next_entity_id);
+
+    ReloadReport {
+        kept_entities: kept,
+        added_entities: new.entities.len() - kept,
+        removed_entities: old_by_id.len() - kept,
+    }
+}
+
+/// Keep the active camera on the same entity if it is still in `new`,
+/// otherwise fall back to the camera `new` would start with.
+pub fn carry_camera(camera: &mut CameraSystem, old: &LoadedScene, new: &LoadedScene) {
+    let active_id = camera.active_entity.map(|i| old.entities[i].id);
+    let fallback = CameraSystem::new(new, camera.viewport_width, camera.viewport_height).active_entity;
+    camera.active_entity = active_id.and_then(|id| new.find_entity(id)).or(fallback);
+}
+
+/// Keep clip playback when the entity still has the same clips, and state
+/// machine progress when its parameters and layers are unchanged.
+fn carry_animation(old: &mut LoadedScene, new: &mut LoadedScene, o: usize, n: usize) {
+    let (Some(oa), Some(na)) = (old.entities[o].animation_index, new.entities[n].animation_index) else { return };
+    let (Some(old_anim), Some(new_anim)) = (old.animations.get_mut(oa), new.animations.get_mut(na)) else { return };
+    let same_clips = old_anim.clips.len() == new_anim.clips.len()
+        && old_anim.clips.iter().zip(&new_anim.clips).all(|(a, b)| a.name == b.name);
+    if !same_clips {
+        return;
+    }
+    new_anim.playing = old_anim.playing;
+    new_anim.speed = old_anim.speed;
+    for layer in &mut new_anim.layers {
+        if let Some(prev) = old_anim.layers.iter_mut().find(|l| l.name == layer.name) {
+            layer.weight = prev.weight;
+            layer.tracks = std::mem::take(&mut prev.tracks);
+            layer.fade = prev.fade.take();
+        }
+    }
+
+    let old_sm = old.state_machines.iter_mut().find(|sm| sm.animation_index == oa);
+    let new_sm = new.state_machines.iter_mut().find(|sm| sm.animation_index == na);
+    let (Some(old_sm), Some(new_sm)) = (old_sm, new_sm) else { return };
+    let same_params = old_sm.parameters.len() == new_sm.parameters.len()
+        && old_sm.parameters.iter().zip(&new_sm.parameters).all(|(a, b)| a.name == b.name);
+    if same_params {
+        new_sm.values.clone_from(&old_sm.values);
+    }
+    for layer in &mut new_sm.layers {
+        let prev = old_sm.layers.iter().find(|l| l.def.name == layer.def.name);
+        if let Some(prev) = prev.filter(|p| p.current_state < layer.def.states.len()) {
+            layer.current_state = prev.current_state;
+            layer.state_time = prev.state_time;
+        }
+    }
+}
+
+/// Keep live particles when the emitter settings are unchanged.
+fn carry_particles(old: &mut LoadedScene, new: &mut LoadedScene, o: usize, n: usize) {
+    let Some(old_sys) = old.particle_systems.iter_mut().find(|s| s.entity_index == o) else { return };
+    let Some(new_sys) = new.particle_systems.iter_mut().find(|s| s.entity_index == n) else { return };
+    if old.particle_emitters.get(old_sys.emitter_index) == new.particle_emitters.get(new_sys.emitter_index) {
+        std::mem::swap(&mut old_sys.pool, &mut new_sys.pool);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::AnimationState;
+
+    /// A version 2 bundle of root entities `(id, has camera)`.
+    fn bundle(entities: &[(u64, bool)]) -> Vec<u8> {
+        const NONE: u32 = u32::MAX;
+        let cameras = entities.iter().filter(|(_, cam)| *cam).count() as u32;
+        let mut data = Vec::new();
+        data.extend_from_slice(b"ORSB");
+        for v in [2, 0, entities.len() as u32, 0, 0, 0, 0] {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        let mut camera = 0;
+        for &(id, has_camera) in entities {
+            data.extend_from_slice(&id.to_le_bytes());
+            data.extend_from_slice(&NONE.to_le_bytes());
+            data.extend_from_slice(&1u64.to_le_bytes()); // transform
+            let camera_index = if has_camera { camera += 1; camera - 1 } else { NONE };
+            for idx in [NONE, NONE, camera_index, NONE, NONE, NONE, NONE, NONE, NONE] {
+                data.extend_from_slice(&idx.to_le_bytes());
+            }
+        }
+        for _ in entities {
+            for v in [0.0f64, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0] {
+                data.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+        data.extend_from_slice(&0u32.to_le_bytes()); // point lights
+        data.extend_from_slice(&0u32.to_le_bytes()); // dir lights
+        data.extend_from_slice(&cameras.to_le_bytes());
+        for _ in 0..cameras {
+            for v in [60.0f32, 0.1, 100.0, 1.0] {
+                data.extend_from_slice(&v.to_le_bytes());
+            }
+            data.extend_from_slice(&[0u8; 4]); // not flagged active
+        }
+        data.extend_from_slice(&0u32.to_le_bytes()); // colliders
+        data.extend_from_slice(&0u32.to_le_bytes()); // rigidbodies
+        data.extend_from_slice(&0u32.to_le_bytes()); // animations
+        data
+    }
+
+    fn animation(playing: bool, speed: f32) -> AnimationState {
+        AnimationState { clips: Vec::new(), layers: Vec::new(), playing, speed }
+    }
+
+    #[test]
+    fn test_carry_over_matches_entities_by_id() {
+        let mut old = LoadedScene::from_orsb(&bundle(&[(1, true), (2, true), (3, false)])).unwrap();
+        // Entity 5 is new; entity 1 is gone and 2 moved to the end
+        let mut new = LoadedScene::from_orsb(&bundle(&[(5, true), (3, false), (2, true)])).unwrap();
+
+        old.animations.push(animation(true, 2.0));
+        old.entities[2].animation_index = Some(0);
+        new.animations.push(animation(false, 1.0));
+        new.entities[1].animation_index = Some(0);
+        old.contacts.insert((0, 2));
+        old.contacts.insert((1, 2));
+        // Spawned at runtime as entity 4, so not part of the new bundle
+        let spawned = old.spawn_entity("bullet", None);
+
+        let mut camera = CameraSystem::new(&old, 800, 600);
+        assert!(camera.set_active_entity(&old, 1));
+
+        let report = carry_over(&mut old, &mut new);
+        carry_camera(&mut camera, &old, &new);
+
+        assert_eq!((report.kept_entities, report.added_entities, report.removed_entities), (2, 1, 2));
+        // The camera stays on entity 2, now at index 2
+        assert_eq!(camera.active_entity, Some(2));
+        // Playback follows entity 3 to its new slot
+        assert!(new.animations[0].playing);
+        assert_eq!(new.animations[0].speed, 2.0);
+        // Only the contact between surviving entities 2 and 3 is kept
+        assert_eq!(new.contacts.iter().copied().collect::<Vec<_>>(), vec![(1, 2)]);
+        // Ids handed out at runtime are not reused
+        assert!(new.next_entity_id > old.entities[spawned].id);
+    }
+
+    #[test]
+    fn test_carry_camera_falls_back_when_entity_is_removed() {
+        let old = LoadedScene::from_orsb(&bundle(&[(1, true), (2, true)])).unwrap();
+        let new = LoadedScene::from_orsb(&bundle(&[(2, false), (3, true)])).unwrap();
+        let mut camera = CameraSystem::new(&old, 800, 600);
+        assert_eq!(camera.active_entity, Some(0));
+
+        carry_camera(&mut camera, &old, &new);
+        assert_eq!(camera.active_entity, Some(1));
+    }
+}
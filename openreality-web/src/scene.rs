@@ -1,18 +1,88 @@
+use std::collections::{BTreeSet, HashMap};
+
+use openreality_gpu_shared::atmosphere::{AtmosphereSettings, FogSettings};
 use openreality_gpu_shared::scene_format::*;
 use glam::{DVec3, DQuat, Mat4};
 
+use openreality_gpu_shared::hierarchy::HierarchyOrder;
+use openreality_gpu_shared::math::BoundingSphere;
+
+use crate::anim_state_machine::AnimStateMachine;
+use crate::events::EventQueue;
+use crate::particles::ParticleSystemState;
+use crate::events::GameEvent;
+use crate::prefab::{self, Prefab};
+use crate::culling::{DrawItem, LodState};
+
+/// Stable reference to an entity slot. Slots are recycled after despawn, so
+/// an index alone may later name a different entity; the generation tells
+/// the two apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EntityHandle {
+    pub index: usize,
+    pub generation: u32,
+}
+
 /// A loaded entity with component data.
+#[derive(Clone)]
 pub struct Entity {
     pub id: u64,
+    /// Exported `NameComponent` name; empty if unnamed.
+    pub name: String,
+    /// False once despawned. The slot is recycled at the end of the frame.
+    pub alive: bool,
+    /// Bumped every time the slot is reused.
+    pub generation: u32,
     pub parent_index: Option<usize>,
     pub transform: TransformState,
     pub world_transform: Mat4,
     pub mesh_index: Option<usize>,
     pub material_index: Option<usize>,
+    pub camera_index: Option<usize>,
+    /// Index into `point_lights`, `dir_lights`, `spot_lights` or
+    /// `area_lights`, depending on `mask`.
+    pub light_index: Option<usize>,
+    pub collider_index: Option<usize>,
+    pub rigidbody_index: Option<usize>,
+    pub animation_index: Option<usize>,
+    pub skeleton_index: Option<usize>,
+    pub particle_index: Option<usize>,
     pub mask: ComponentMask,
+    /// Present when the entity's mesh has morph targets.
+    pub morph: Option<MorphState>,
+    /// Present when the entity switches meshes by camera distance.
+    pub lod: Option<LodState>,
+    /// Index into `LoadedScene::decals` when the entity projects a decal.
+    pub decal_index: Option<usize>,
+}
+
+/// Per-entity morph target weights and the deformed vertices they produce.
+#[derive(Clone)]
+pub struct MorphState {
+    pub weights: Vec<f32>,
+    pub positions: Vec<f32>,
+    pub normals: Vec<f32>,
+    /// Set when `weights` changed and the vertices need recomputing.
+    pub dirty: bool,
+}
+
+impl MorphState {
+    /// Undeformed state for `mesh`, or `None` if it has no morph targets.
+    pub fn for_mesh(mesh: &MeshData) -> Option<Self> {
+        if mesh.morph_targets.is_empty() {
+            return None;
+        }
+        Some(Self {
+            weights: vec![0.0; mesh.morph_targets.len()],
+            positions: mesh.positions.clone(),
+            normals: mesh.normals.clone(),
+            dirty: false,
+        })
+    }
 }
 
 /// Runtime transform state (mutable, used for animation).
+#[derive(Clone)]
 pub struct TransformState {
     pub position: DVec3,
     pub rotation: DQuat,
@@ -21,6 +91,7 @@ pub struct TransformState {
 }
 
 /// Loaded mesh data (ready for GPU upload).
+#[derive(PartialEq)]
 pub struct MeshData {
     pub positions: Vec<f32>,
     pub normals: Vec<f32>,
@@ -28,9 +99,11 @@ pub struct MeshData {
     pub indices: Vec<u32>,
     pub bone_weights: Option<Vec<f32>>,
     pub bone_indices: Option<Vec<u16>>,
+    pub morph_targets: Vec<MorphTargetParsed>,
 }
 
 /// Loaded material data.
+#[derive(Clone)]
 pub struct MaterialInfo {
     pub color: [f32; 4],
     pub metallic: f32,
@@ -44,6 +117,7 @@ pub struct MaterialInfo {
 }
 
 /// Loaded texture data.
+#[derive(PartialEq)]
 pub struct TextureData {
     pub width: u32,
     pub height: u32,
@@ -53,12 +127,14 @@ pub struct TextureData {
 }
 
 /// Animation clip for runtime playback.
+#[derive(Clone)]
 pub struct AnimationClip {
     pub name: String,
     pub duration: f32,
     pub channels: Vec<AnimationChannel>,
 }
 
+#[derive(Clone)]
 pub struct AnimationChannel {
     pub target_entity_index: usize,
     pub target_property: TargetProperty,
@@ -67,17 +143,50 @@ pub struct AnimationChannel {
     pub values: Vec<f64>,
 }
 
+/// One clip playing on an animation layer.
+#[derive(Clone, Debug)]
+pub struct ClipPlayback {
+    pub clip: usize,
+    pub time: f32,
+    pub speed: f32,
+    pub looping: bool,
+    /// Relative weight among the layer's tracks.
+    pub weight: f32,
+}
+
+/// Cross-fade in progress: `from` fades out while the layer's tracks fade in.
+#[derive(Clone, Debug)]
+pub struct CrossFade {
+    pub from: Vec<ClipPlayback>,
+    pub elapsed: f32,
+    pub duration: f32,
+}
+
+/// A layer of weighted clips combined over the layers below it.
+#[derive(Clone)]
+pub struct AnimationLayer {
+    pub name: String,
+    pub weight: f32,
+    pub blend_mode: LayerBlendMode,
+    /// Per-entity flags restricting which entities (bones) this layer affects.
+    /// `None` affects every entity.
+    pub mask: Option<Vec<bool>>,
+    pub tracks: Vec<ClipPlayback>,
+    pub fade: Option<CrossFade>,
+}
+
 /// Animation playback state for an entity.
+#[derive(Clone)]
 pub struct AnimationState {
     pub clips: Vec<AnimationClip>,
-    pub active_clip: i32,
-    pub current_time: f32,
+    /// Layers evaluated bottom to top; `layers[0]` is the base layer.
+    pub layers: Vec<AnimationLayer>,
     pub playing: bool,
-    pub looping: bool,
     pub speed: f32,
 }
 
 /// Skeleton runtime data.
+#[derive(Clone)]
 pub struct SkeletonData {
     /// Index of the entity this skeleton is attached to.
     pub entity_index: usize,
@@ -87,6 +196,10 @@ pub struct SkeletonData {
     pub inverse_bind_matrices: Vec<Mat4>,
     /// Computed bone matrices (updated per frame by skinning system).
     pub bone_matrices: Vec<Mat4>,
+    /// Mesh-space vertices after morphing and skinning (xyz per vertex).
+    /// Empty when the mesh carries no bone weights.
+    pub skinned_positions: Vec<f32>,
+    pub skinned_normals: Vec<f32>,
 }
 
 /// Point light data for runtime.
@@ -104,32 +217,101 @@ pub struct DirLight {
     pub intensity: f32,
 }
 
+/// Spot light data for runtime. Cone angles are half-angles in radians.
+pub struct SpotLight {
+    pub position: [f32; 3],
+    pub direction: [f32; 3],
+    pub color: [f32; 3],
+    pub intensity: f32,
+    pub range: f32,
+    pub inner_angle: f32,
+    pub outer_angle: f32,
+    pub casts_shadow: bool,
+}
+
+/// Rect or tube area light data for runtime.
+pub struct AreaLight {
+    pub shape: AreaLightShape,
+    pub position: [f32; 3],
+    pub direction: [f32; 3],
+    pub tangent: [f32; 3],
+    pub color: [f32; 3],
+    pub intensity: f32,
+    pub range: f32,
+    pub width: f32,
+    pub height: f32,
+    pub radius: f32,
+}
+
 /// Camera data for runtime.
 pub struct Camera {
+    /// Vertical field of view in degrees.
     pub fov: f32,
     pub near: f32,
     pub far: f32,
     pub aspect: f32,
+    pub active: bool,
 }
 
 /// Complete loaded scene.
 pub struct LoadedScene {
     pub entities: Vec<Entity>,
     pub meshes: Vec<MeshData>,
+    /// Local-space bounds of each mesh, for culling and LOD distances.
+    pub mesh_bounds: Vec<BoundingSphere>,
+    pub lod_chains: Vec<LodParsed>,
+    /// Entities that passed frustum culling this frame, with their LOD mesh.
+    pub draw_list: Vec<DrawItem>,
     pub materials: Vec<MaterialInfo>,
     pub textures: Vec<TextureData>,
     pub animations: Vec<AnimationState>,
     pub skeletons: Vec<SkeletonData>,
     pub point_lights: Vec<PointLight>,
     pub dir_lights: Vec<DirLight>,
+    pub spot_lights: Vec<SpotLight>,
+    pub area_lights: Vec<AreaLight>,
     pub cameras: Vec<Camera>,
     pub physics_config: Option<PhysicsConfigData>,
+    /// Sky and volumetric fog settings, for the renderer.
+    pub atmosphere: Option<AtmosphereSettings>,
+    pub fog: Option<FogSettings>,
+    /// Decal settings, projected by their entities' world transforms.
+    pub decals: Vec<DecalParsed>,
+    pub state_machines: Vec<AnimStateMachine>,
+    /// Entities in parent-before-child order, used by the transform system.
+    pub hierarchy: HierarchyOrder,
+    /// Set when parent links changed and `hierarchy` must be rebuilt.
+    pub hierarchy_dirty: bool,
+    pub particle_emitters: Vec<ParticleEmitterParsed>,
+    /// One per entity with a particle emitter.
+    pub particle_systems: Vec<ParticleSystemState>,
+    pub colliders: Vec<ColliderParsed>,
+    /// Entity index pairs (lower first) whose colliders overlapped last frame.
+    pub contacts: BTreeSet<(usize, usize)>,
+    pub events: EventQueue,
+    /// Id handed to the next spawned entity.
+    pub next_entity_id: u64,
+    /// Embedded script sources and which entities run them.
+    pub scripts: Vec<ScriptParsed>,
+    pub script_attachments: Vec<ScriptAttachmentParsed>,
+    /// Attachments for entities instantiated from prefabs, waiting for the
+    /// script runtime to pick them up.
+    pub pending_scripts: Vec<ScriptAttachmentParsed>,
+    pub prefabs: Vec<Prefab>,
+    /// Live entity index by id.
+    id_index: HashMap<u64, usize>,
+    /// Slots despawned this frame, recycled by `recycle_slots`.
+    dead_slots: Vec<usize>,
+    /// Slots ready to be reused by spawns.
+    free_slots: Vec<usize>,
+    /// Animation slots of despawned entities, reused by prefab instances.
+    free_animations: Vec<usize>,
 }
 
 impl LoadedScene {
     /// Parse an ORSB binary file into a LoadedScene.
     pub fn from_orsb(data: &[u8]) -> Result<Self, String> {
-        let parsed = parse_orsb(data)?;
+        let mut parsed = parse_orsb(data)?;
 
         // Build entities
         let num_entities = parsed.entity_ids.len();
@@ -138,7 +320,10 @@ impl LoadedScene {
             let t = &parsed.transforms[i];
             entities.push(Entity {
                 id: parsed.entity_ids[i],
-                parent_index: parsed.parent_indices[i],
+                name: std::mem::take(&mut parsed.entity_names[i]),
+                alive: true,
+                generation: 0,
+                parent_index: parsed.parent_indices[i].filter(|&p| p < num_entities),
                 transform: TransformState {
                     position: DVec3::new(t.position[0], t.position[1], t.position[2]),
                     rotation: DQuat::from_xyzw(t.rotation[1], t.rotation[2], t.rotation[3], t.rotation[0]),
@@ -148,20 +333,48 @@ impl LoadedScene {
                 world_transform: Mat4::IDENTITY,
                 mesh_index: parsed.mesh_indices[i],
                 material_index: parsed.material_indices[i],
+                camera_index: parsed.camera_indices[i],
+                light_index: parsed.light_indices[i],
+                collider_index: parsed.collider_indices[i],
+                rigidbody_index: parsed.rigidbody_indices[i],
+                animation_index: parsed.animation_indices[i],
+                skeleton_index: parsed.skeleton_indices[i],
+                particle_index: parsed.particle_indices[i],
                 mask: parsed.component_masks[i],
+                morph: None,
+                lod: None,
+                decal_index: None,
             });
         }
 
+        // Parents may be stored after their children; establish a safe order
+        let parents: Vec<Option<usize>> = entities.iter().map(|e| e.parent_index).collect();
+        let hierarchy = HierarchyOrder::build(&parents)?;
+
         // Build meshes
-        let meshes = parsed.meshes.into_iter().map(|m| MeshData {
+        let meshes: Vec<MeshData> = parsed.meshes.into_iter().map(|m| MeshData {
             positions: m.positions,
             normals: m.normals,
             uvs: m.uvs,
             indices: m.indices,
             bone_weights: m.bone_weights,
             bone_indices: m.bone_indices,
+            morph_targets: m.morph_targets,
         }).collect();
 
+        let mesh_bounds = meshes.iter().map(|m| BoundingSphere::from_positions(&m.positions)).collect();
+        for (chain, lod) in parsed.lods.iter().enumerate() {
+            entities[lod.entity_index].lod = Some(LodState { chain, level: 0 });
+        }
+        for (i, decal) in parsed.decals.iter().enumerate() {
+            entities[decal.entity_index].decal_index = Some(i);
+        }
+
+        // Morphed entities get their own copy of the vertices to deform
+        for entity in &mut entities {
+            entity.morph = entity.mesh_index.and_then(|m| meshes.get(m)).and_then(MorphState::for_mesh);
+        }
+
         // Build materials
         let materials = parsed.materials.into_iter().map(|m| MaterialInfo {
             color: m.color,
@@ -206,17 +419,42 @@ impl LoadedScene {
             intensity: l.intensity,
         }).collect();
 
+        let spot_lights = parsed.spot_lights.into_iter().map(|l| SpotLight {
+            position: l.position,
+            direction: l.direction,
+            color: l.color,
+            intensity: l.intensity,
+            range: l.range,
+            inner_angle: l.inner_angle,
+            outer_angle: l.outer_angle,
+            casts_shadow: l.casts_shadow,
+        }).collect();
+
+        let area_lights = parsed.area_lights.into_iter().map(|l| AreaLight {
+            shape: l.shape,
+            position: l.position,
+            direction: l.direction,
+            tangent: l.tangent,
+            color: l.color,
+            intensity: l.intensity,
+            range: l.range,
+            width: l.width,
+            height: l.height,
+            radius: l.radius,
+        }).collect();
+
         // Build cameras
         let cameras = parsed.cameras.into_iter().map(|c| Camera {
             fov: c.fov,
             near: c.near,
             far: c.far,
             aspect: c.aspect,
+            active: c.active,
         }).collect();
 
         // Build animations
-        let animations = parsed.animations.into_iter().map(|a| AnimationState {
-            clips: a.clips.into_iter().map(|clip| AnimationClip {
+        let mut animations: Vec<AnimationState> = parsed.animations.into_iter().map(|a| {
+            let clips: Vec<AnimationClip> = a.clips.into_iter().map(|clip| AnimationClip {
                 name: clip.name,
                 duration: clip.duration,
                 channels: clip.channels.into_iter().map(|ch| AnimationChannel {
@@ -226,28 +464,252 @@ impl LoadedScene {
                     times: ch.times,
                     values: ch.values,
                 }).collect(),
-            }).collect(),
-            active_clip: a.active_clip,
-            current_time: 0.0,
-            playing: a.playing,
-            looping: a.looping,
-            speed: a.speed,
+            }).collect();
+
+            let mut base = AnimationLayer::new("base", LayerBlendMode::Override, 1.0, None);
+            if a.active_clip >= 0 && (a.active_clip as usize) < clips.len() {
+                base.tracks.push(ClipPlayback::new(a.active_clip as usize, a.looping));
+            }
+
+            AnimationState {
+                clips,
+                layers: vec![base],
+                playing: a.playing,
+                speed: a.speed,
+            }
+        }).collect();
+
+        // Attach state machines to the animations they drive
+        let state_machines = parsed.state_machines.into_iter().filter_map(|sm| {
+            let anim = animations.get_mut(sm.animation_index as usize)?;
+            Some(AnimStateMachine::new(sm, anim, num_entities))
+        }).collect();
+
+        // One simulated pool per emitting entity
+        let particle_emitters = parsed.particle_emitters;
+        let particle_systems = entities.iter().enumerate().filter_map(|(i, e)| {
+            let emitter = e.particle_index?;
+            Some(ParticleSystemState::new(i, emitter, particle_emitters.get(emitter)?))
         }).collect();
 
-        Ok(LoadedScene {
+        let id_index = entities.iter().enumerate().map(|(i, e)| (e.id, i)).collect();
+        let mut scene = LoadedScene {
             entities,
             meshes,
+            mesh_bounds,
+            lod_chains: parsed.lods,
+            draw_list: Vec::new(),
             materials,
             textures,
             animations,
             skeletons: Vec::new(), // Skeleton section not yet exported by Julia
             point_lights,
             dir_lights,
+            spot_lights,
+            area_lights,
             cameras,
             physics_config: parsed.physics_config,
+            atmosphere: parsed.atmosphere,
+            fog: parsed.fog,
+            decals: parsed.decals,
+            state_machines,
+            hierarchy,
+            hierarchy_dirty: false,
+            particle_emitters,
+            particle_systems,
+            colliders: parsed.colliders,
+            contacts: BTreeSet::new(),
+            events: EventQueue::default(),
+            next_entity_id: parsed.entity_ids.iter().max().map_or(1, |&id| id + 1),
+            scripts: parsed.scripts,
+            script_attachments: parsed.script_attachments,
+            pending_scripts: Vec::new(),
+            prefabs: Vec::new(),
+            id_index,
+            dead_slots: Vec::new(),
+            free_slots: Vec::new(),
+            free_animations: Vec::new(),
+        };
+        prefab::extract_prefabs(&mut scene, &parsed.prefabs);
+        Ok(scene)
+    }
+
+    /// Indices of all entities linked to a camera, in entity order.
+    pub fn camera_entities(&self) -> Vec<usize> {
+        self.entities
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.alive && e.camera_index.is_some_and(|c| c < self.cameras.len()))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Find a live entity by its exported entity ID.
+    pub fn find_entity(&self, id: u64) -> Option<usize> {
+        self.id_index.get(&id).copied()
+    }
+
+    /// Find the first live entity with the given name.
+    pub fn find_entity_by_name(&self, name: &str) -> Option<usize> {
+        self.entities.iter().position(|e| e.alive && e.name == name)
+    }
+
+    pub fn handle(&self, index: usize) -> EntityHandle {
+        EntityHandle { index, generation: self.entities[index].generation }
+    }
+
+    /// Index of the entity `handle` refers to, if it is still alive.
+    pub fn resolve(&self, handle: EntityHandle) -> Option<usize> {
+        self.entities
+            .get(handle.index)
+            .filter(|e| e.alive && e.generation == handle.generation)
+            .map(|_| handle.index)
+    }
+
+    /// Add an empty entity at the origin and return its index.
+    pub fn spawn_entity(&mut self, name: &str, parent_index: Option<usize>) -> usize {
+        self.insert_entity(Entity {
+            id: 0,
+            name: name.to_string(),
+            alive: true,
+            generation: 0,
+            parent_index,
+            transform: TransformState {
+                position: DVec3::ZERO,
+                rotation: DQuat::IDENTITY,
+                scale: DVec3::ONE,
+                dirty: true,
+            },
+            world_transform: Mat4::IDENTITY,
+            mesh_index: None,
+            material_index: None,
+            camera_index: None,
+            light_index: None,
+            collider_index: None,
+            rigidbody_index: None,
+            animation_index: None,
+            skeleton_index: None,
+            particle_index: None,
+            mask: ComponentMask(ComponentMask::TRANSFORM),
+            morph: None,
+            lod: None,
+            decal_index: None,
         })
     }
 
+    /// Store `entity` under a fresh id, reusing a recycled slot if there is one.
+    pub(crate) fn insert_entity(&mut self, mut entity: Entity) -> usize {
+        entity.id = self.next_entity_id;
+        entity.alive = true;
+        entity.transform.dirty = true;
+        self.next_entity_id += 1;
+        let index = match self.free_slots.pop() {
+            Some(slot) => {
+                entity.generation = self.entities[slot].generation.wrapping_add(1);
+                self.entities[slot] = entity;
+                slot
+            }
+            None => {
+                entity.generation = 0;
+                self.entities.push(entity);
+                self.entities.len() - 1
+            }
+        };
+        self.id_index.insert(self.entities[index].id, index);
+        self.hierarchy_dirty = true;
+        index
+    }
+
+    /// Store `anim` in a freed animation slot if there is one and return its index.
+    pub(crate) fn insert_animation(&mut self, anim: AnimationState) -> usize {
+        match self.free_animations.pop() {
+            Some(slot) => {
+                self.animations[slot] = anim;
+                slot
+            }
+            None => {
+                self.animations.push(anim);
+                self.animations.len() - 1
+            }
+        }
+    }
+
+    /// Despawn an entity and all of its descendants. Collisions they were part
+    /// of end, and anything that referred to their slots is detached, so the
+    /// slots can be recycled safely.
+    pub fn despawn_entity(&mut self, index: usize) {
+        let n = self.entities.len();
+        let descends = |mut i: usize| {
+            for _ in 0..n {
+                if i == index {
+                    return true;
+                }
+                match self.entities[i].parent_index {
+                    Some(p) => i = p,
+                    None => return false,
+                }
+            }
+            false
+        };
+        let doomed: BTreeSet<usize> = (0..n).filter(|&i| self.entities[i].alive && descends(i)).collect();
+        if doomed.is_empty() {
+            return;
+        }
+
+        let mut dead_animations = BTreeSet::new();
+        for &i in &doomed {
+            let entity = &mut self.entities[i];
+            entity.alive = false;
+            self.id_index.remove(&entity.id);
+            if let Some(a) = entity.animation_index.take() {
+                dead_animations.insert(a);
+            }
+        }
+        // Leave freed animation slots empty and stopped until they are reused
+        for &a in &dead_animations {
+            if let Some(anim) = self.animations.get_mut(a) {
+                anim.playing = false;
+                anim.clips.clear();
+                anim.layers.clear();
+                self.free_animations.push(a);
+            }
+        }
+        self.state_machines.retain(|sm| !dead_animations.contains(&sm.animation_index));
+
+        let (ended, kept): (BTreeSet<_>, BTreeSet<_>) =
+            self.contacts.iter().partition(|(a, b)| doomed.contains(a) || doomed.contains(b));
+        for (a, b) in ended {
+            self.events.push(GameEvent::CollisionExit { entity: self.entities[a].id, other: self.entities[b].id });
+        }
+        self.contacts = kept;
+
+        for anim in &mut self.animations {
+            for clip in &mut anim.clips {
+                clip.channels.retain(|c| !doomed.contains(&c.target_entity_index));
+            }
+            for mask in anim.layers.iter_mut().filter_map(|l| l.mask.as_mut()) {
+                for &i in &doomed {
+                    if let Some(on) = mask.get_mut(i) {
+                        *on = false;
+                    }
+                }
+            }
+        }
+        self.particle_systems.retain(|s| !doomed.contains(&s.entity_index));
+        // Drop skeletons that lost a bone rather than let the slot's next occupant pose it
+        self.skeletons.retain(|s| {
+            !doomed.contains(&s.entity_index) && !s.bone_entity_indices.iter().any(|b| doomed.contains(b))
+        });
+        self.pending_scripts.retain(|a| !doomed.contains(&a.entity_index));
+        self.dead_slots.extend(doomed);
+    }
+
+    /// Make slots despawned since the last call available for reuse. Runs at
+    /// the end of a frame so indices held during the frame stay unambiguous.
+    pub fn recycle_slots(&mut self) {
+        self.free_slots.append(&mut self.dead_slots);
+    }
+
     pub fn num_entities(&self) -> usize {
         self.entities.len()
     }
@@ -260,3 +722,118 @@ impl LoadedScene {
         self.textures.len()
     }
 }
+
+#[cfg(test)]
+impl LoadedScene {
+    /// A scene with no entities or resources.
+    pub(crate) fn empty() -> Self {
+        let mut data = b"ORSB".to_vec();
+        // Version 1 header with no entities, then empty lights .. animations
+        for v in [1u32, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0] {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        Self::from_orsb(&data).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn animation() -> AnimationState {
+        AnimationState { clips: Vec::new(), layers: Vec::new(), playing: true, speed: 1.0 }
+    }
+
+    #[test]
+    fn test_handles_go_stale_when_slots_are_reused() {
+        let mut scene = LoadedScene::empty();
+        let a = scene.spawn_entity("a", None);
+        let handle = scene.handle(a);
+        let id = scene.entities[a].id;
+        assert_eq!(scene.resolve(handle), Some(a));
+
+        scene.despawn_entity(a);
+        assert_eq!(scene.resolve(handle), None);
+        assert_eq!(scene.find_entity(id), None);
+
+        // The slot is only reused after the end-of-frame recycle
+        let b = scene.spawn_entity("b", None);
+        assert_ne!(b, a);
+        scene.recycle_slots();
+        let c = scene.spawn_entity("c", None);
+        assert_eq!(c, a);
+        assert_eq!(scene.entities[c].generation, 1);
+        assert_eq!(scene.resolve(handle), None);
+        assert_eq!(scene.resolve(scene.handle(c)), Some(c));
+        assert!(scene.entities[c].id > scene.entities[b].id);
+        assert_eq!(scene.num_entities(), 2);
+    }
+
+    #[test]
+    fn test_despawn_removes_descendants() {
+        let mut scene = LoadedScene::empty();
+        let root = scene.spawn_entity("root", None);
+        let child = scene.spawn_entity("child", Some(root));
+        let grandchild = scene.spawn_entity("grandchild", Some(child));
+        let other = scene.spawn_entity("other", None);
+        scene.contacts.insert((grandchild, other));
+
+        scene.despawn_entity(root);
+        let alive: Vec<bool> = scene.entities.iter().map(|e| e.alive).collect();
+        assert_eq!(alive, vec![false, false, false, true]);
+        assert!(scene.contacts.is_empty());
+    }
+
+    #[test]
+    fn test_despawned_bones_are_not_reused_by_respawns() {
+        let mut scene = LoadedScene::empty();
+        let mesh = scene.spawn_entity("body", None);
+        let hip = scene.spawn_entity("hip", Some(mesh));
+        let hand = scene.spawn_entity("hand", Some(hip));
+        let skeleton = SkeletonData {
+            entity_index: mesh,
+            bone_entity_indices: vec![hip, hand],
+            inverse_bind_matrices: vec![Mat4::IDENTITY; 2],
+            bone_matrices: Vec::new(),
+            skinned_positions: Vec::new(),
+            skinned_normals: Vec::new(),
+        };
+        scene.skeletons = vec![skeleton.clone(), SkeletonData { bone_entity_indices: vec![hip], ..skeleton }];
+        let mut anim = animation();
+        let mut arm = AnimationLayer::new("arm", LayerBlendMode::Override, 1.0, Some(vec![false, true, true]));
+        arm.tracks.push(ClipPlayback::new(0, true));
+        anim.layers.push(arm);
+        let a = scene.insert_animation(anim);
+        scene.entities[mesh].animation_index = Some(a);
+
+        scene.despawn_entity(hand);
+        scene.recycle_slots();
+        let prop = scene.spawn_entity("prop", None);
+        assert_eq!(prop, hand);
+
+        // Only the skeleton that never used the hand survives, and the layer no longer drives its slot
+        assert_eq!(scene.skeletons.len(), 1);
+        assert_eq!(scene.skeletons[0].bone_entity_indices, vec![hip]);
+        assert_eq!(scene.animations[a].layers[0].mask, Some(vec![false, true, false]));
+
+        scene.despawn_entity(mesh);
+        assert!(scene.skeletons.is_empty());
+        assert!(scene.entities[prop].alive);
+    }
+
+    #[test]
+    fn test_animation_slots_are_reused() {
+        let mut scene = LoadedScene::empty();
+        for i in 0..10 {
+            let e = scene.spawn_entity("animated", None);
+            let anim = scene.insert_animation(animation());
+            scene.entities[e].animation_index = Some(anim);
+            assert_eq!(anim, 0, "spawn {i}");
+            scene.despawn_entity(e);
+            assert!(!scene.animations[anim].playing);
+            scene.recycle_slots();
+        }
+        assert_eq!(scene.animations.len(), 1);
+        assert_eq!(scene.entities.len(), 1);
+    }
+}
@@ -0,0 +1,460 @@
+use std::rc::Rc;
+
+use glam::{DQuat, DVec3, Vec3};
+use openreality_gpu_shared::collision::RayHit;
+use openreality_gpu_shared::rng::RngStreams;
+use openreality_gpu_shared::scene_format::ScriptAttachmentParsed;
+use openreality_gpu_shared::script::{self, ScriptHost, ScriptInstance, Value};
+
+use crate::collision;
+use crate::events::{EventKind, GameEvent};
+use crate::input::InputState;
+use crate::prefab::TransformOverride;
+use crate::scene::{EntityHandle, LoadedScene, TransformState};
+use crate::transform;
+
+/// Errors a script instance may raise before it is disabled, matching
+/// Julia's default `SCRIPT_ERROR_BUDGET`.
+const SCRIPT_ERROR_BUDGET: u32 = 5;
+
+/// Random stream shared by every bundle script.
+const SCRIPT_STREAM: u64 = 1 << 62;
+
+/// One entity's running script.
+struct EntityScript {
+    entity: EntityHandle,
+    /// Id of `entity`, kept for `on_destroy` after its slot is recycled.
+    id: u64,
+    instance: ScriptInstance,
+    started: bool,
+    errors: u32,
+}
+
+/// Bundle scripts of a loaded scene, run once per frame.
+#[derive(Default)]
+pub struct ScriptRuntime {
+    /// Compiled scene scripts by index; `None` if compilation failed.
+    compiled: Vec<Option<Rc<script::Script>>>,
+    scripts: Vec<EntityScript>,
+    /// Seconds since the runtime started, exposed as `time()`.
+    elapsed: f64,
+}
+
+/// The world as seen by a script call.
+struct World<'a> {
+    scene: &'a mut LoadedScene,
+    input: &'a InputState,
+    random: &'a mut RngStreams,
+    elapsed: f64,
+}
+
+impl ScriptRuntime {
+    /// Compile the scene's scripts and instantiate them on their entities.
+    /// Scripts that fail to compile or initialize are logged and skipped.
+    pub fn new(scene: &mut LoadedScene, input: &InputState, random: &mut RngStreams) -> Self {
+        let compiled = scene
+            .scripts
+            .iter()
+            .map(|s| match script::compile(&s.source) {
+                Ok(compiled) => Some(Rc::new(compiled)),
+                Err(e) => {
+                    log::warn!("Script '{}' failed to compile: {e}", s.name);
+                    None
+                }
+            })
+            .collect();
+
+        let attachments = scene.script_attachments.clone();
+        let mut runtime = Self { compiled, scripts: Vec::new(), elapsed: 0.0 };
+        let mut world = World { scene, input, random, elapsed: 0.0 };
+        runtime.attach(&mut world, attachments);
+        runtime
+    }
+
+    /// Instantiate `attachments` and subscribe to the events their handlers need.
+    fn attach(&mut self, world: &mut World, attachments: Vec<ScriptAttachmentParsed>) {
+        for a in attachments {
+            let Some(compiled) = self.compiled.get(a.script_index).cloned().flatten() else { continue };
+            let entity = world.scene.handle(a.entity_index);
+            let id = world.scene.entities[a.entity_index].id;
+            match ScriptInstance::new(compiled, id, &a.params, world) {
+                Ok(instance) => self.scripts.push(EntityScript { entity, id, instance, started: false, errors: 0 }),
+                Err(e) => log::warn!("Script '{}' on entity {id} failed to start: {e}", world.scene.scripts[a.script_index].name),
+            }
+        }
+
+        for (kind, handler) in [
+            (EventKind::CollisionEnter, "on_collision_enter"),
+            (EventKind::CollisionExit, "on_collision_exit"),
+            (EventKind::AnimationFinished, "on_animation_finished"),
+        ] {
+            if self.scripts.iter().any(|s| s.instance.has_function(handler)) {
+                world.scene.events.subscribe_scripts(kind);
+            }
+        }
+    }
+
+    /// Start scripts of newly instantiated prefabs, deliver last frame's
+    /// events, then run `on_start` (first frame only) and `on_update(dt)` on
+    /// every live scripted entity. Scripts whose entity was despawned get
+    /// `on_destroy` and are dropped.
+    pub fn update(&mut self, scene: &mut LoadedScene, input: &InputState, random: &mut RngStreams, dt: f32) {
+        if self.scripts.is_empty() && scene.pending_scripts.is_empty() {
+            return;
+        }
+        self.elapsed += dt as f64;
+        let pending = std::mem::take(&mut scene.pending_scripts);
+        let mut world = World { scene, input, random, elapsed: self.elapsed };
+        self.attach(&mut world, pending);
+
+        for event in world.scene.events.drain_scripts() {
+            match event {
+                GameEvent::CollisionEnter { entity, other } => {
+                    self.dispatch(&mut world, entity, "on_collision_enter", vec![Value::Entity(other)]);
+                    self.dispatch(&mut world, other, "on_collision_enter", vec![Value::Entity(entity)]);
+                }
+                GameEvent::CollisionExit { entity, other } => {
+                    self.dispatch(&mut world, entity, "on_collision_exit", vec![Value::Entity(other)]);
+                    self.dispatch(&mut world, other, "on_collision_exit", vec![Value::Entity(entity)]);
+                }
+                GameEvent::AnimationFinished { entity, clip } => {
+                    self.dispatch(&mut world, entity, "on_animation_finished", vec![Value::Str(clip.into())]);
+                }
+            }
+        }
+
+        // Scripts spawned or despawned mid-loop are handled next frame
+        for i in 0..self.scripts.len() {
+            if !self.scripts[i].started {
+                self.scripts[i].started = true;
+                self.run(i, &mut world, "on_start", Vec::new());
+            }
+            self.run(i, &mut world, "on_update", vec![Value::Num(dt as f64)]);
+        }
+
+        let mut i = 0;
+        while i < self.scripts.len() {
+            if world.scene.resolve(self.scripts[i].entity).is_some() {
+                i += 1;
+            } else {
+                self.run(i, &mut world, "on_destroy", Vec::new());
+                self.scripts.swap_remove(i);
+            }
+        }
+    }
+
+    fn dispatch(&mut self, world: &mut World, entity_id: u64, name: &str, args: Vec<Value>) {
+        for i in 0..self.scripts.len() {
+            if self.scripts[i].id == entity_id {
+                self.run(i, world, name, args.clone());
+            }
+        }
+    }
+
+    /// Call `name` on script `i` unless its entity is gone or it has used up
+    /// its error budget.
+    fn run(&mut self, i: usize, world: &mut World, name: &str, args: Vec<Value>) {
+        let s = &mut self.scripts[i];
+        let dead = world.scene.resolve(s.entity).is_none() && name != "on_destroy";
+        if dead || s.errors >= SCRIPT_ERROR_BUDGET || !s.instance.has_function(name) {
+            return;
+        }
+        if let Err(e) = s.instance.call(name, args, world) {
+            s.errors += 1;
+            let id = s.id;
+            if s.errors >= SCRIPT_ERROR_BUDGET {
+                log::warn!("Script on entity {id} disabled after {} errors: {e}", s.errors);
+            } else {
+                log::warn!("Script {name} error on entity {id}: {e}");
+            }
+        }
+    }
+}
+
+fn arg(args: &[Value], i: usize) -> Result<&Value, String> {
+    args.get(i).ok_or_else(|| format!("missing argument {}", i + 1))
+}
+
+fn vec3(v: Vec3) -> Value {
+    Value::Vec3(v.as_dvec3())
+}
+
+impl World<'_> {
+    /// Index of the live entity passed as argument `i`.
+    fn entity(&self, args: &[Value], i: usize) -> Result<usize, String> {
+        let id = arg(args, i)?.as_entity()?;
+        self.scene.find_entity(id).ok_or_else(|| format!("entity {id} does not exist"))
+    }
+
+    fn edit_transform(&mut self, args: &[Value], edit: impl FnOnce(&mut TransformState)) -> Result<Value, String> {
+        let idx = self.entity(args, 0)?;
+        let t = &mut self.scene.entities[idx].transform;
+        edit(t);
+        t.dirty = true;
+        Ok(Value::Nil)
+    }
+
+    /// `raycast(origin, dir, max_distance)`, skipping triggers.
+    fn raycast(&mut self, args: &[Value]) -> Result<Option<(usize, RayHit)>, String> {
+        let origin = arg(args, 0)?.as_vec3()?.as_vec3();
+        let dir = arg(args, 1)?.as_vec3()?.as_vec3();
+        let max_distance = arg(args, 2)?.as_num()? as f32;
+        transform::compute_world_transforms(self.scene);
+        Ok(collision::raycast(self.scene, origin, dir, max_distance, false))
+    }
+}
+
+impl ScriptHost for World<'_> {
+    fn call(&mut self, name: &str, args: &[Value]) -> Option<Result<Value, String>> {
+        let result = match name {
+            // ── Entities ──
+            "find" => arg(args, 0).and_then(Value::as_str).map(|n| match self.scene.find_entity_by_name(n) {
+                Some(i) => Value::Entity(self.scene.entities[i].id),
+                None => Value::Nil,
+            }),
+            "exists" => arg(args, 0).and_then(Value::as_entity).map(|id| Value::Bool(self.scene.find_entity(id).is_some())),
+            "name" => self.entity(args, 0).map(|i| Value::Str(self.scene.entities[i].name.as_str().into())),
+            "parent" => self.entity(args, 0).map(|i| match self.scene.entities[i].parent_index {
+                Some(p) => Value::Entity(self.scene.entities[p].id),
+                None => Value::Nil,
+            }),
+            "spawn" => arg(args, 0).and_then(Value::as_str).map(|n| {
+                let idx = self.scene.spawn_entity(n, None);
+                Value::Entity(self.scene.entities[idx].id)
+            }),
+            // instantiate(prefab, position?, parent?)
+            "instantiate" => (|| {
+                let name = arg(args, 0)?.as_str()?;
+                let prefab = self.scene.find_prefab(name).ok_or_else(|| format!("no prefab named '{name}'"))?;
+                let position = match args.get(1) {
+                    Some(Value::Nil) | None => None,
+                    Some(v) => Some(v.as_vec3()?),
+                };
+                let parent = match args.get(2) {
+                    Some(Value::Nil) | None => None,
+                    Some(_) => Some(self.entity(args, 2)?),
+                };
+                let overrides = TransformOverride { position, ..Default::default() };
+                let idx = self.scene.instantiate_prefab(prefab, parent, overrides);
+                Ok(Value::Entity(self.scene.entities[idx].id))
+            })(),
+            "despawn" => self.entity(args, 0).map(|i| {
+                self.scene.despawn_entity(i);
+                Value::Nil
+            }),
+
+            // ── Transforms (local, relative to the parent) ──
+            "position" => self.entity(args, 0).map(|i| Value::Vec3(self.scene.entities[i].transform.position)),
+            "set_position" => (|| {
+                let p = arg(args, 1)?.as_vec3()?;
+                self.edit_transform(args, |t| t.position = p)
+            })(),
+            "scale" => self.entity(args, 0).map(|i| Value::Vec3(self.scene.entities[i].transform.scale)),
+            "set_scale" => (|| {
+                let s = arg(args, 1)?.as_vec3()?;
+                self.edit_transform(args, |t| t.scale = s)
+            })(),
+            // rotate(e, axis, angle): turn about a local axis, like `rotation * delta` in Julia
+            "rotate" => (|| {
+                let axis = arg(args, 1)?.as_vec3()?.normalize_or_zero();
+                let angle = arg(args, 2)?.as_num()?;
+                if axis == DVec3::ZERO {
+                    return Err("rotation axis is zero".to_string());
+                }
+                self.edit_transform(args, |t| t.rotation = (t.rotation * DQuat::from_axis_angle(axis, angle)).normalize())
+            })(),
+            "world_position" => self.entity(args, 0).map(|i| {
+                transform::compute_world_transforms(self.scene);
+                vec3(self.scene.entities[i].world_transform.w_axis.truncate())
+            }),
+            "set_world_position" => (|| {
+                let idx = self.entity(args, 0)?;
+                let p = arg(args, 1)?.as_vec3()?.as_vec3();
+                transform::compute_world_transforms(self.scene);
+                transform::set_world_position(self.scene, idx, p);
+                Ok(Value::Nil)
+            })(),
+
+            // ── Animation ──
+            "play_animation" => (|| {
+                let idx = self.entity(args, 0)?;
+                let clip = arg(args, 1)?.as_str()?;
+                let looping = args.get(2).is_some_and(Value::truthy);
+                let anim = self.scene.entities[idx]
+                    .animation_index
+                    .and_then(|a| self.scene.animations.get_mut(a))
+                    .ok_or("entity has no animation")?;
+                let c = anim.find_clip(clip).ok_or_else(|| format!("no clip named '{clip}'"))?;
+                anim.play(c, looping);
+                Ok(Value::Nil)
+            })(),
+
+            // ── Input ──
+            "key_down" => arg(args, 0).and_then(Value::as_num).map(|k| Value::Bool(self.input.is_key_down(k as u8))),
+            "mouse_down" => arg(args, 0).and_then(Value::as_num).map(|b| Value::Bool(self.input.is_mouse_down(b as usize))),
+            "mouse_position" => Ok(Value::Vec3(DVec3::new(self.input.mouse_x, self.input.mouse_y, 0.0))),
+            "mouse_delta" => Ok(Value::Vec3(DVec3::new(self.input.mouse_dx, self.input.mouse_dy, 0.0))),
+
+            // ── Physics ──
+            "raycast" => self.raycast(args).map(|hit| match hit {
+                Some((idx, _)) => Value::Entity(self.scene.entities[idx].id),
+                None => Value::Nil,
+            }),
+            "raycast_point" => self.raycast(args).map(|hit| match hit {
+                Some((_, hit)) => vec3(hit.point),
+                None => Value::Nil,
+            }),
+            "touching" => (|| {
+                let a = self.entity(args, 0)?;
+                let b = self.entity(args, 1)?;
+                Ok(Value::Bool(self.scene.contacts.contains(&(a.min(b), a.max(b)))))
+            })(),
+
+            // ── Misc ──
+            "time" => Ok(Value::Num(self.elapsed)),
+            "random" => Ok(Value::Num(self.random.stream(SCRIPT_STREAM).next_f32() as f64)),
+            "random_range" => (|| {
+                let lo = arg(args, 0)?.as_num()? as f32;
+                let hi = arg(args, 1)?.as_num()? as f32;
+                Ok(Value::Num(self.random.stream(SCRIPT_STREAM).range(lo, hi) as f64))
+            })(),
+            "log" => {
+                let line: Vec<String> = args.iter().map(Value::to_string).collect();
+                log::info!("[script] {}", line.join(" "));
+                Ok(Value::Nil)
+            }
+            _ => return None,
+        };
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openreality_gpu_shared::scene_format::{ColliderParsed, ScriptParsed};
+
+    /// Attach `source` to each of `entities` and start the runtime.
+    fn attach(scene: &mut LoadedScene, source: &str, entities: &[usize]) -> ScriptRuntime {
+        scene.scripts.push(ScriptParsed { name: "test".into(), source: source.into() });
+        for &entity_index in entities {
+            scene.script_attachments.push(ScriptAttachmentParsed { entity_index, script_index: 0, params: Vec::new() });
+        }
+        ScriptRuntime::new(scene, &InputState::new(), &mut RngStreams::new(1))
+    }
+
+    fn frame(runtime: &mut ScriptRuntime, scene: &mut LoadedScene) {
+        runtime.update(scene, &InputState::new(), &mut RngStreams::new(1), 0.5);
+    }
+
+    fn global(runtime: &ScriptRuntime, script: usize, name: &str) -> Value {
+        runtime.scripts[script].instance.global(name).cloned().unwrap()
+    }
+
+    #[test]
+    fn test_lifecycle_hooks_run_in_order() {
+        let mut scene = LoadedScene::empty();
+        let player = scene.spawn_entity("player", None);
+        let mut runtime = attach(
+            &mut scene,
+            "let trace = \"\";
+             fn on_start() { trace = trace + \"start \"; }
+             fn on_update(dt) { trace = trace + \"update \"; }
+             fn on_destroy() { spawn(trace + \"destroy\"); }",
+            &[player],
+        );
+        assert_eq!(global(&runtime, 0, "trace"), Value::Str("".into()));
+
+        frame(&mut runtime, &mut scene);
+        frame(&mut runtime, &mut scene);
+        assert_eq!(global(&runtime, 0, "trace"), Value::Str("start update update ".into()));
+
+        // A despawned entity gets no more updates, only on_destroy
+        scene.despawn_entity(player);
+        frame(&mut runtime, &mut scene);
+        assert!(runtime.scripts.is_empty());
+        assert!(scene.find_entity_by_name("start update update destroy").is_some());
+    }
+
+    #[test]
+    fn test_error_budget_disables_script() {
+        let mut scene = LoadedScene::empty();
+        let e = scene.spawn_entity("broken", None);
+        let mut runtime = attach(
+            &mut scene,
+            "let calls = 0;
+             fn on_update(dt) { calls = calls + 1; undefined_thing(); }",
+            &[e],
+        );
+        for _ in 0..SCRIPT_ERROR_BUDGET + 3 {
+            frame(&mut runtime, &mut scene);
+        }
+        assert_eq!(runtime.scripts[0].errors, SCRIPT_ERROR_BUDGET);
+        assert_eq!(global(&runtime, 0, "calls"), Value::Num(SCRIPT_ERROR_BUDGET as f64));
+    }
+
+    #[test]
+    fn test_events_reach_both_entities_handlers() {
+        let mut scene = LoadedScene::empty();
+        let a = scene.spawn_entity("a", None);
+        let b = scene.spawn_entity("b", None);
+        let mut runtime = attach(
+            &mut scene,
+            "let hits = 0;
+             let last = nil;
+             let clip = nil;
+             fn on_collision_enter(other) { hits = hits + 1; last = other; }
+             fn on_animation_finished(name) { clip = name; }",
+            &[a, b],
+        );
+        let (a_id, b_id) = (scene.entities[a].id, scene.entities[b].id);
+        scene.events.push(GameEvent::CollisionEnter { entity: a_id, other: b_id });
+        scene.events.push(GameEvent::CollisionExit { entity: a_id, other: b_id });
+        scene.events.push(GameEvent::AnimationFinished { entity: b_id, clip: "wave".into() });
+        frame(&mut runtime, &mut scene);
+
+        assert_eq!(global(&runtime, 0, "hits"), Value::Num(1.0));
+        assert_eq!(global(&runtime, 0, "last"), Value::Entity(b_id));
+        assert_eq!(global(&runtime, 1, "last"), Value::Entity(a_id));
+        assert_eq!(global(&runtime, 0, "clip"), Value::Nil);
+        assert_eq!(global(&runtime, 1, "clip"), Value::Str("wave".into()));
+        // No script handles exits, so they are never queued for scripts
+        assert!(scene.events.drain_scripts().is_empty());
+    }
+
+    #[test]
+    fn test_world_bindings() {
+        let mut scene = LoadedScene::empty();
+        let player = scene.spawn_entity("player", None);
+        let target = scene.spawn_entity("target", None);
+        let doomed = scene.spawn_entity("doomed", None);
+        scene.colliders.push(ColliderParsed { shape_type: 1, shape_data: [1.0, 0.0, 0.0], offset: [0.0; 3], is_trigger: false });
+        scene.entities[target].collider_index = Some(0);
+        let mut runtime = attach(
+            &mut scene,
+            "let hit = nil;
+             let hit_point = nil;
+             let spawned = nil;
+             let missing = 0;
+             fn on_start() {
+                 set_position(find(\"target\"), vec3(0, 0, 5));
+                 spawned = spawn(\"bullet\");
+                 despawn(find(\"doomed\"));
+                 missing = find(\"nobody\");
+                 hit = raycast(vec3(0, 0, 0), vec3(0, 0, 1), 100);
+                 hit_point = raycast_point(vec3(0, 0, 0), vec3(0, 0, 1), 100);
+             }",
+            &[player],
+        );
+        frame(&mut runtime, &mut scene);
+
+        assert_eq!(scene.entities[target].transform.position, DVec3::new(0.0, 0.0, 5.0));
+        assert_eq!(global(&runtime, 0, "hit"), Value::Entity(scene.entities[target].id));
+        assert_eq!(global(&runtime, 0, "hit_point"), Value::Vec3(DVec3::new(0.0, 0.0, 4.0)));
+        assert_eq!(global(&runtime, 0, "missing"), Value::Nil);
+        let Value::Entity(spawned) = global(&runtime, 0, "spawned") else { panic!("spawn returned no entity") };
+        assert_eq!(scene.entities[scene.find_entity(spawned).unwrap()].name, "bullet");
+        assert!(!scene.entities[doomed].alive);
+        assert_eq!(runtime.scripts[0].errors, 0);
+    }
+}
@@ -1,4 +1,4 @@
-use glam::Mat4;
+use glam::{Mat3, Mat4, Vec3};
 
 use crate::scene::LoadedScene;
 
@@ -8,30 +8,150 @@ const MAX_BONES: usize = 128;
 ///
 /// For each entity with skeleton data, computes final bone matrices:
 ///   bone_matrix[i] = inverse(mesh_world) * bone_world * inverse_bind_matrix
+///
+/// Meshes with bone weights are then skinned on the CPU. Morph targets are
+/// applied first, as in glTF, so this must run after `update_morph_targets`.
 pub fn update_skinned_meshes(scene: &mut LoadedScene) {
-    for skel_idx in 0..scene.skeletons.len() {
-        let entity_idx = scene.skeletons[skel_idx].entity_index;
-        if entity_idx >= scene.entities.len() {
-            continue;
-        }
-
-        let mesh_world = scene.entities[entity_idx].world_transform;
-        let inv_mesh_world = mesh_world.inverse();
+    let LoadedScene { skeletons, entities, meshes, .. } = scene;
+    for skeleton in skeletons.iter_mut() {
+        let Some(entity) = entities.get(skeleton.entity_index) else { continue };
+        let inv_mesh_world = entity.world_transform.inverse();
 
-        let bone_count = scene.skeletons[skel_idx].bone_entity_indices.len().min(MAX_BONES);
-        scene.skeletons[skel_idx].bone_matrices.resize(bone_count, Mat4::IDENTITY);
+        let bone_count = skeleton.bone_entity_indices.len().min(MAX_BONES);
+        skeleton.bone_matrices.resize(bone_count, Mat4::IDENTITY);
 
         for i in 0..bone_count {
-            let bone_entity_idx = scene.skeletons[skel_idx].bone_entity_indices[i];
-            if bone_entity_idx >= scene.entities.len() {
-                scene.skeletons[skel_idx].bone_matrices[i] = Mat4::IDENTITY;
+            let bone_entity_idx = skeleton.bone_entity_indices[i];
+            if bone_entity_idx >= entities.len() {
+                skeleton.bone_matrices[i] = Mat4::IDENTITY;
                 continue;
             }
 
-            let bone_world = scene.entities[bone_entity_idx].world_transform;
-            let inv_bind = scene.skeletons[skel_idx].inverse_bind_matrices[i];
+            let bone_world = entities[bone_entity_idx].world_transform;
+            let inv_bind = skeleton.inverse_bind_matrices[i];
+
+            skeleton.bone_matrices[i] = inv_mesh_world * bone_world * inv_bind;
+        }
 
-            scene.skeletons[skel_idx].bone_matrices[i] = inv_mesh_world * bone_world * inv_bind;
+        skeleton.skinned_positions.clear();
+        skeleton.skinned_normals.clear();
+        let Some(mesh) = entity.mesh_index.and_then(|m| meshes.get(m)) else { continue };
+        let (Some(weights), Some(indices)) = (&mesh.bone_weights, &mesh.bone_indices) else { continue };
+        let (positions, normals) = match &entity.morph {
+            Some(morph) => (&morph.positions, &morph.normals),
+            None => (&mesh.positions, &mesh.normals),
+        };
+        skin_vertices(
+            positions,
+            normals,
+            weights,
+            indices,
+            &skeleton.bone_matrices,
+            &mut skeleton.skinned_positions,
+            &mut skeleton.skinned_normals,
+        );
+    }
+}
+
+/// Linear blend skinning with the same arithmetic as the skinned vertex
+/// shaders: four weighted bone matrices per vertex, bone indices clamped to
+/// the last bone, and normals transformed by the upper 3x3 then renormalized.
+fn skin_vertices(
+    positions: &[f32],
+    normals: &[f32],
+    weights: &[f32],
+    indices: &[u16],
+    bones: &[Mat4],
+    out_positions: &mut Vec<f32>,
+    out_normals: &mut Vec<f32>,
+) {
+    let Some(last_bone) = bones.len().checked_sub(1) else {
+        out_positions.extend_from_slice(positions);
+        out_normals.extend_from_slice(normals);
+        return;
+    };
+    for (v, p) in positions.chunks_exact(3).enumerate() {
+        let mut skin = Mat4::ZERO;
+        for k in v * 4..v * 4 + 4 {
+            let w = weights.get(k).copied().unwrap_or(0.0);
+            let bone = indices.get(k).map_or(0, |&i| (i as usize).min(last_bone));
+            skin += bones[bone] * w;
+        }
+        let p = skin * Vec3::from_slice(p).extend(1.0);
+        out_positions.extend_from_slice(&p.truncate().to_array());
+        if let Some(n) = normals.get(v * 3..v * 3 + 3) {
+            let n = (Mat3::from_mat4(skin) * Vec3::from_slice(n)).normalize_or_zero();
+            out_normals.extend_from_slice(&n.to_array());
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::{DQuat, DVec3};
+    use crate::morph::update_morph_targets;
+    use crate::scene::{MeshData, MorphState, SkeletonData};
+    use crate::transform::compute_world_transforms;
+    use openreality_gpu_shared::scene_format::MorphTargetParsed;
+
+    #[test]
+    fn test_skinning_deforms_the_morphed_vertices() {
+        let mesh = MeshData {
+            positions: vec![0.0, 0.0, 0.0],
+            normals: vec![0.0, 0.0, 1.0],
+            uvs: Vec::new(),
+            indices: Vec::new(),
+            bone_weights: Some(vec![1.0, 0.0, 0.0, 0.0]),
+            // Out-of-range indices clamp to the last bone, as in the shaders
+            bone_indices: Some(vec![0, 7, 0, 0]),
+            morph_targets: vec![MorphTargetParsed { position_deltas: vec![1.0, 0.0, 0.0], normal_deltas: Vec::new() }],
+        };
+        let mut scene = LoadedScene::empty();
+        let body = scene.spawn_entity("body", None);
+        let bone = scene.spawn_entity("bone", Some(body));
+        scene.entities[body].morph = MorphState::for_mesh(&mesh);
+        scene.entities[body].mesh_index = Some(0);
+        scene.meshes.push(mesh);
+        let t = &mut scene.entities[bone].transform;
+        t.position = DVec3::new(0.0, 2.0, 0.0);
+        t.rotation = DQuat::from_rotation_y(std::f64::consts::FRAC_PI_2);
+        scene.skeletons.push(SkeletonData {
+            entity_index: body,
+            bone_entity_indices: vec![bone],
+            inverse_bind_matrices: vec![Mat4::IDENTITY],
+            bone_matrices: Vec::new(),
+            skinned_positions: Vec::new(),
+            skinned_normals: Vec::new(),
+        });
+
+        let morph = scene.entities[body].morph.as_mut().unwrap();
+        morph.weights[0] = 1.0;
+        morph.dirty = true;
+        compute_world_transforms(&mut scene);
+        update_morph_targets(&mut scene);
+        update_skinned_meshes(&mut scene);
+
+        // Morphed to (1, 0, 0), then turned a quarter about Y and raised by the bone
+        let skeleton = &scene.skeletons[0];
+        assert!(Vec3::from_slice(&skeleton.skinned_positions).abs_diff_eq(Vec3::new(0.0, 2.0, -1.0), 1e-6));
+        assert!(Vec3::from_slice(&skeleton.skinned_normals).abs_diff_eq(Vec3::X, 1e-6));
+    }
+
+    #[test]
+    fn test_meshes_without_bone_weights_are_not_skinned() {
+        let mut scene = LoadedScene::empty();
+        let body = scene.spawn_entity("body", None);
+        scene.skeletons.push(SkeletonData {
+            entity_index: body,
+            bone_entity_indices: vec![body],
+            inverse_bind_matrices: vec![Mat4::IDENTITY],
+            bone_matrices: Vec::new(),
+            skinned_positions: vec![1.0, 2.0, 3.0],
+            skinned_normals: Vec::new(),
+        });
+        update_skinned_meshes(&mut scene);
+        assert_eq!(scene.skeletons[0].bone_matrices, vec![Mat4::IDENTITY]);
+        assert!(scene.skeletons[0].skinned_positions.is_empty());
+    }
+}
@@ -1,33 +1,108 @@
 use glam::{DVec3, DQuat, Mat4, Vec3, Quat};
+use openreality_gpu_shared::hierarchy::{would_create_cycle, HierarchyOrder};
 
 use crate::scene::LoadedScene;
 
-/// Compute world transform matrices for all entities in the scene.
-/// Traverses from root entities down, composing parent * local transforms.
+/// Bring world transform matrices up to date.
+///
+/// Walks the scene's hierarchy order (parents before children) and recomputes
+/// only the subtrees under entities whose transform is dirty; clean subtrees
+/// are skipped without touching their matrices.
 pub fn compute_world_transforms(scene: &mut LoadedScene) {
-    let n = scene.entities.len();
+    if scene.hierarchy_dirty {
+        rebuild_hierarchy(scene);
+    }
+
+    let LoadedScene { entities, hierarchy, .. } = scene;
+    let mut k = 0;
+    while k < hierarchy.len() {
+        if !entities[hierarchy.order[k]].transform.dirty {
+            k += 1;
+            continue;
+        }
 
-    // Process entities in order (parents before children since ORSB is DFS-ordered)
-    for i in 0..n {
-        let local = compose_local_transform(
-            &scene.entities[i].transform.position,
-            &scene.entities[i].transform.rotation,
-            &scene.entities[i].transform.scale,
-        );
+        // A dirty entity invalidates its whole subtree, which is contiguous
+        let end = hierarchy.subtree_end[k];
+        for &i in &hierarchy.order[k..end] {
+            let t = &entities[i].transform;
+            let local = compose_local_transform(&t.position, &t.rotation, &t.scale);
+            let world = match entities[i].parent_index {
+                Some(p) => entities[p].world_transform * local,
+                None => local,
+            };
+            entities[i].world_transform = world;
+            entities[i].transform.dirty = false;
+        }
+        k = end;
+    }
+}
 
-        let world = if let Some(parent_idx) = scene.entities[i].parent_index {
-            if parent_idx < n {
-                scene.entities[parent_idx].world_transform * local
-            } else {
-                local
-            }
-        } else {
-            local
-        };
+/// Recompute the hierarchy order after parent links changed. Links are
+/// validated when they are made, so this cannot fail.
+fn rebuild_hierarchy(scene: &mut LoadedScene) {
+    let parents: Vec<Option<usize>> = scene.entities.iter().map(|e| e.parent_index).collect();
+    scene.hierarchy = HierarchyOrder::build(&parents).expect("parent cycle slipped past set_parent");
+    scene.hierarchy_dirty = false;
+}
 
-        scene.entities[i].world_transform = world;
-        scene.entities[i].transform.dirty = false;
+/// Move `child` under `parent` (`None` = make it a root).
+///
+/// With `keep_world` the local transform is rewritten so the entity stays where
+/// it is in the world; otherwise its local transform is kept and it moves with
+/// the new parent. Shear from non-uniformly scaled parents cannot be expressed
+/// in a TRS transform and is dropped.
+pub fn set_parent(scene: &mut LoadedScene, child: usize, parent: Option<usize>, keep_world: bool) -> Result<(), String> {
+    let n = scene.entities.len();
+    if child >= n || parent.is_some_and(|p| p >= n) {
+        return Err("Entity index out of range".into());
+    }
+    if let Some(p) = parent {
+        let parents: Vec<Option<usize>> = scene.entities.iter().map(|e| e.parent_index).collect();
+        if would_create_cycle(&parents, child, p) {
+            return Err("Cannot parent an entity to itself or one of its descendants".into());
+        }
     }
+
+    if keep_world {
+        compute_world_transforms(scene);
+        let parent_world = parent.map_or(Mat4::IDENTITY, |p| scene.entities[p].world_transform);
+        let local = parent_world.inverse() * scene.entities[child].world_transform;
+        let (scale, rotation, translation) = local.to_scale_rotation_translation();
+        let t = &mut scene.entities[child].transform;
+        t.position = translation.as_dvec3();
+        t.rotation = rotation.as_dquat();
+        t.scale = scale.as_dvec3();
+    }
+
+    scene.entities[child].parent_index = parent;
+    scene.entities[child].transform.dirty = true;
+    scene.hierarchy_dirty = true;
+    Ok(())
+}
+
+/// World matrix of `entity`'s parent (identity for roots).
+pub fn parent_world_transform(scene: &LoadedScene, entity: usize) -> Mat4 {
+    scene.entities[entity]
+        .parent_index
+        .map_or(Mat4::IDENTITY, |p| scene.entities[p].world_transform)
+}
+
+/// Transform a point from `entity`'s local space into world space.
+pub fn local_to_world_point(scene: &LoadedScene, entity: usize, point: Vec3) -> Vec3 {
+    scene.entities[entity].world_transform.transform_point3(point)
+}
+
+/// Transform a world-space point into `entity`'s local space.
+pub fn world_to_local_point(scene: &LoadedScene, entity: usize, point: Vec3) -> Vec3 {
+    scene.entities[entity].world_transform.inverse().transform_point3(point)
+}
+
+/// Place `entity` at a world-space position by rewriting its local position.
+pub fn set_world_position(scene: &mut LoadedScene, entity: usize, position: Vec3) {
+    let local = parent_world_transform(scene, entity).inverse().transform_point3(position);
+    let t = &mut scene.entities[entity].transform;
+    t.position = local.as_dvec3();
+    t.dirty = true;
 }
 
 /// Compose a local transform matrix from position, rotation, and scale.
@@ -43,3 +118,111 @@ fn compose_local_transform(position: &DVec3, rotation: &DQuat, scale: &DVec3) ->
 
     Mat4::from_scale_rotation_translation(scl, rot, pos)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn place(scene: &mut LoadedScene, entity: usize, position: DVec3, rotation: DQuat, scale: f64) {
+        let t = &mut scene.entities[entity].transform;
+        t.position = position;
+        t.rotation = rotation;
+        t.scale = DVec3::splat(scale);
+        t.dirty = true;
+    }
+
+    fn translation(scene: &LoadedScene, entity: usize) -> Vec3 {
+        scene.entities[entity].world_transform.w_axis.truncate()
+    }
+
+    #[test]
+    fn test_clean_subtrees_are_not_recomputed() {
+        let mut scene = LoadedScene::empty();
+        let a = scene.spawn_entity("a", None);
+        let a_child = scene.spawn_entity("a_child", Some(a));
+        let b = scene.spawn_entity("b", None);
+        compute_world_transforms(&mut scene);
+
+        // A stale matrix in a clean subtree survives, so it was not touched
+        let marker = Mat4::from_translation(Vec3::splat(42.0));
+        scene.entities[a_child].world_transform = marker;
+        place(&mut scene, b, DVec3::X, DQuat::IDENTITY, 1.0);
+        compute_world_transforms(&mut scene);
+        assert_eq!(scene.entities[a_child].world_transform, marker);
+        assert_eq!(translation(&scene, b), Vec3::X);
+
+        scene.entities[a].transform.dirty = true;
+        compute_world_transforms(&mut scene);
+        assert_eq!(scene.entities[a_child].world_transform, Mat4::IDENTITY);
+    }
+
+    #[test]
+    fn test_parent_stored_after_child_propagates() {
+        let mut scene = LoadedScene::empty();
+        let child = scene.spawn_entity("child", None);
+        let parent = scene.spawn_entity("parent", None);
+        assert!(parent > child);
+        set_parent(&mut scene, child, Some(parent), false).unwrap();
+        place(&mut scene, child, DVec3::Y, DQuat::IDENTITY, 1.0);
+        compute_world_transforms(&mut scene);
+
+        place(&mut scene, parent, DVec3::new(0.0, 0.0, 5.0), DQuat::IDENTITY, 2.0);
+        compute_world_transforms(&mut scene);
+        assert_eq!(translation(&scene, child), Vec3::new(0.0, 2.0, 5.0));
+        assert!(!scene.entities[child].transform.dirty);
+    }
+
+    #[test]
+    fn test_set_parent_keeps_world_transform() {
+        let mut scene = LoadedScene::empty();
+        let parent = scene.spawn_entity("parent", None);
+        let child = scene.spawn_entity("child", None);
+        place(&mut scene, parent, DVec3::new(1.0, 2.0, 3.0), DQuat::from_rotation_y(0.7), 2.0);
+        place(&mut scene, child, DVec3::new(-4.0, 0.5, 1.0), DQuat::from_rotation_x(0.3), 0.5);
+        compute_world_transforms(&mut scene);
+        let before = scene.entities[child].world_transform;
+
+        set_parent(&mut scene, child, Some(parent), true).unwrap();
+        compute_world_transforms(&mut scene);
+        assert!(scene.entities[child].world_transform.abs_diff_eq(before, 1e-5));
+        assert!((scene.entities[child].transform.scale - DVec3::splat(0.25)).length() < 1e-5);
+
+        // Without keep_world the local transform is kept and the child moves
+        set_parent(&mut scene, child, None, false).unwrap();
+        compute_world_transforms(&mut scene);
+        assert!(!scene.entities[child].world_transform.abs_diff_eq(before, 1e-3));
+    }
+
+    #[test]
+    fn test_set_parent_rejects_cycles() {
+        let mut scene = LoadedScene::empty();
+        let root = scene.spawn_entity("root", None);
+        let child = scene.spawn_entity("child", Some(root));
+        let grandchild = scene.spawn_entity("grandchild", Some(child));
+
+        assert!(set_parent(&mut scene, root, Some(grandchild), false).is_err());
+        assert!(set_parent(&mut scene, child, Some(child), false).is_err());
+        assert!(set_parent(&mut scene, child, Some(99), false).is_err());
+        assert_eq!(scene.entities[root].parent_index, None);
+        assert_eq!(scene.entities[child].parent_index, Some(root));
+        compute_world_transforms(&mut scene);
+    }
+
+    #[test]
+    fn test_world_local_points_round_trip() {
+        let mut scene = LoadedScene::empty();
+        let parent = scene.spawn_entity("parent", None);
+        let child = scene.spawn_entity("child", Some(parent));
+        place(&mut scene, parent, DVec3::new(0.0, 1.0, 0.0), DQuat::from_rotation_z(1.2), 3.0);
+        place(&mut scene, child, DVec3::X, DQuat::IDENTITY, 1.0);
+        compute_world_transforms(&mut scene);
+
+        let p = Vec3::new(0.5, -1.0, 2.0);
+        let world = local_to_world_point(&scene, child, p);
+        assert!(world_to_local_point(&scene, child, world).abs_diff_eq(p, 1e-5));
+
+        set_world_position(&mut scene, child, Vec3::new(7.0, 8.0, 9.0));
+        compute_world_transforms(&mut scene);
+        assert!(translation(&scene, child).abs_diff_eq(Vec3::new(7.0, 8.0, 9.0), 1e-5));
+    }
+}
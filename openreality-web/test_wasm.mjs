@@ -66,8 +66,11 @@ console.log(`  ✓ Animations: ${numAnimations}`);
 // ── 3. Parse entity graph ──
 console.log("[3/3] Parsing entity graph...");
 
+// v2 entity records append camera/light/collider/rigidbody/animation/skeleton/particle indices
+const entityStride = version >= 2 ? 56 : 28;
 let offset = 32; // past header
-for (let i = 0; i < numEntities && offset + 28 <= orsbData.length; i++) {
+for (let i = 0; i < numEntities && offset + entityStride <= orsbData.length; i++) {
+    const recordStart = offset;
     const entityId = view.getBigUint64(offset, true); offset += 8;
     const parentIdx = view.getUint32(offset, true); offset += 4;
     const maskLow = view.getUint32(offset, true);
@@ -75,6 +78,7 @@ for (let i = 0; i < numEntities && offset + 28 <= orsbData.length; i++) {
     offset += 8;
     const meshIdx = view.getUint32(offset, true); offset += 4;
     const matIdx = view.getUint32(offset, true); offset += 4;
+    offset = recordStart + entityStride;
 
     const components = [];
     if (maskLow & (1 << 0)) components.push('Transform');
@@ -93,7 +97,7 @@ for (let i = 0; i < numEntities && offset + 28 <= orsbData.length; i++) {
 // Parse transforms section to verify positions
 console.log();
 console.log("  Transform positions:");
-const transformOffset = 32 + numEntities * 28; // after entity graph
+const transformOffset = 32 + numEntities * entityStride; // after entity graph
 for (let i = 0; i < numEntities && transformOffset + i * 80 + 24 <= orsbData.length; i++) {
     const base = transformOffset + i * 80;
     const px = view.getFloat64(base, true);
@@ -1,5 +1,26 @@
+use crate::gpu_particles::{GPUParticleEmitter, GPUParticlePipelines, ParticleCamera};
 use crate::handle::HandleStore;
-use crate::render_targets;
+use crate::csm::CascadedShadowMap;
+use crate::environment::Environment;
+use crate::exposure::AutoExposure;
+use crate::frame_timer::FrameTimer;
+use crate::local_shadows::ShadowAtlas;
+use crate::instancing::{InstanceBufferPool, InstanceBuffers};
+use crate::output::{self, OutputTarget};
+use crate::post_process::ColorLut;
+use openreality_gpu_shared::clustering;
+use openreality_gpu_shared::decal::{self, DecalParams};
+use openreality_gpu_shared::display::{self, DisplaySettings, DISPLAY_SDR};
+use openreality_gpu_shared::math::BoundingSphere;
+use openreality_gpu_shared::morph::apply_morph_targets;
+use openreality_gpu_shared::particles::MeshSurfaceSampler;
+use openreality_gpu_shared::post_process::PostProcessStack;
+use openreality_gpu_shared::resolution::{self, DynamicResolution, ResolutionSettings};
+use openreality_gpu_shared::scene_format::MorphTargetParsed;
+use openreality_gpu_shared::uniforms::{
+    AreaLightData, ClusterParams, ParticleEmitterParams, PointLightData, SpotLightData,
+};
+use crate::render_targets::{self, MsaaTargets};
 
 /// GPU mesh with vertex and index buffers.
 pub struct GPUMesh {
@@ -8,6 +29,44 @@ pub struct GPUMesh {
     pub uv_buffer: wgpu::Buffer,
     pub index_buffer: wgpu::Buffer,
     pub index_count: u32,
+    pub vertex_count: u32,
+    /// Base vertices and deltas, kept on the CPU when the mesh has morph targets.
+    pub morph: Option<MorphData>,
+    /// Bone streams and palette, present for meshes uploaded with `upload_skinned_mesh`.
+    pub skin: Option<SkinData>,
+    /// Local-space bounds used for culling. Skinned meshes keep their bind-pose
+    /// bounds, which animated poses can leave, so they are never culled.
+    pub bounds: BoundingSphere,
+}
+
+impl GPUMesh {
+    /// World-space bounds of an instance drawn with `model`, or `None` if the
+    /// mesh is skinned and must not be culled.
+    pub fn cull_bounds(&self, model: &glam::Mat4) -> Option<BoundingSphere> {
+        self.skin.is_none().then(|| self.bounds.transformed(model))
+    }
+}
+
+/// GPU skinning resources for a mesh. The palette holds mesh-local bone
+/// matrices and is read by the skinned G-Buffer/shadow vertex stages.
+pub struct SkinData {
+    pub bone_weight_buffer: wgpu::Buffer,
+    pub bone_index_buffer: wgpu::Buffer,
+    pub bone_buffer: wgpu::Buffer,
+    pub bone_bind_group: wgpu::BindGroup,
+    /// Number of matrices `bone_buffer` can hold.
+    pub bone_capacity: u32,
+}
+
+/// CPU-side morph target data for a mesh. Weights are applied with the shared
+/// `apply_morph_targets` and the result is written into the vertex buffers.
+pub struct MorphData {
+    pub base_positions: Vec<f32>,
+    pub base_normals: Vec<f32>,
+    pub targets: Vec<MorphTargetParsed>,
+    pub weights: Vec<f32>,
+    scratch_positions: Vec<f32>,
+    scratch_normals: Vec<f32>,
 }
 
 /// GPU texture with associated view and sampler.
@@ -20,6 +79,15 @@ pub struct GPUTexture {
     pub channels: u32,
 }
 
+/// Decal box projected onto the G-Buffer. Texture handles of 0 (or of
+/// destroyed textures) draw without that map.
+pub struct Decal {
+    pub transform: glam::Mat4,
+    pub params: DecalParams,
+    pub albedo_texture: u64,
+    pub normal_texture: u64,
+}
+
 /// Render target (framebuffer equivalent).
 pub struct RenderTarget {
     pub color_texture: wgpu::Texture,
@@ -51,26 +119,6 @@ pub struct GBuffer {
     pub height: u32,
 }
 
-/// Cascaded shadow map.
-pub struct CascadedShadowMap {
-    pub depth_textures: Vec<wgpu::Texture>,
-    pub depth_views: Vec<wgpu::TextureView>,
-    pub sampler: wgpu::Sampler,
-    pub num_cascades: u32,
-    pub resolution: u32,
-}
-
-/// Post-processing pipeline state.
-pub struct PostProcessPipeline {
-    pub bloom_extract_pipeline: wgpu::RenderPipeline,
-    pub bloom_blur_pipeline: wgpu::RenderPipeline,
-    pub bloom_composite_pipeline: wgpu::RenderPipeline,
-    pub fxaa_pipeline: Option<wgpu::RenderPipeline>,
-    pub bloom_targets: Vec<RenderTarget>,
-    pub params_buffer: wgpu::Buffer,
-    pub params_bind_group_layout: wgpu::BindGroupLayout,
-}
-
 /// SSAO pass state.
 pub struct SSAOPass {
     pub pipeline: wgpu::RenderPipeline,
@@ -105,11 +153,29 @@ pub struct TAAPass {
 pub struct DeferredPipeline {
     // Render pipelines
     pub gbuffer_pipeline: wgpu::RenderPipeline,
+    pub gbuffer_skinned_pipeline: wgpu::RenderPipeline,
+    /// Decals over the G-Buffer albedo and normals, then over its roughness.
+    pub decal_pipeline: wgpu::RenderPipeline,
+    pub decal_roughness_pipeline: wgpu::RenderPipeline,
     pub lighting_pipeline: wgpu::RenderPipeline,
     pub shadow_pipeline: wgpu::RenderPipeline,
+    pub shadow_skinned_pipeline: wgpu::RenderPipeline,
+    pub shadow_clear_pipeline: wgpu::RenderPipeline,
+    pub evsm_moments_pipeline: wgpu::RenderPipeline,
+    /// Copies the lit image and depth into `msaa.scene`; `None` at 1 sample.
+    pub msaa_prime_pipeline: Option<wgpu::RenderPipeline>,
+    /// FSR EASU upscale of the post-processed image to the output size.
+    pub upscale_pipeline: wgpu::RenderPipeline,
+    pub instance_cull_pipeline: wgpu::ComputePipeline,
+    pub light_cluster_pipeline: wgpu::ComputePipeline,
+    /// Volumetric fog: light the froxels, then integrate them front to back.
+    pub fog_inject_pipeline: wgpu::ComputePipeline,
+    pub fog_integrate_pipeline: wgpu::ComputePipeline,
     pub forward_pipeline: wgpu::RenderPipeline,
+    pub forward_skinned_pipeline: wgpu::RenderPipeline,
     pub present_pipeline: wgpu::RenderPipeline,
-    pub particle_pipeline: wgpu::RenderPipeline,
+    /// CPU-streamed particle pipelines, indexed by blend mode.
+    pub particle_pipelines: [wgpu::RenderPipeline; 3],
     pub ui_pipeline: wgpu::RenderPipeline,
     pub terrain_pipeline: wgpu::RenderPipeline,
 
@@ -122,6 +188,12 @@ pub struct DeferredPipeline {
     pub bloom_blur_pipeline: wgpu::RenderPipeline,
     pub bloom_composite_pipeline: wgpu::RenderPipeline,
     pub fxaa_pipeline: wgpu::RenderPipeline,
+    pub dof_coc_pipeline: wgpu::RenderPipeline,
+    pub dof_blur_pipeline: wgpu::RenderPipeline,
+    pub dof_composite_pipeline: wgpu::RenderPipeline,
+    pub velocity_pipeline: wgpu::RenderPipeline,
+    pub motion_blur_pipeline: wgpu::RenderPipeline,
+    pub color_grading_pipeline: wgpu::RenderPipeline,
 
     // Render targets
     pub gbuffer: GBuffer,
@@ -130,13 +202,18 @@ pub struct DeferredPipeline {
     pub ssr_target: RenderTarget,
     pub taa_targets: render_targets::TAATargets,
     pub bloom_targets: render_targets::BloomTargets,
-    pub dof_targets: Option<render_targets::DOFTargets>,
-    pub mblur_targets: Option<render_targets::MotionBlurTargets>,
+    pub dof_targets: render_targets::DOFTargets,
+    pub mblur_targets: render_targets::MotionBlurTargets,
 
     // Post-process intermediate targets (for ping-pong)
     pub pp_target_a: RenderTarget,
     pub pp_target_b: RenderTarget,
 
+    /// Upscaled image at the output size; `None` at render scale 1.
+    pub upscale_target: Option<RenderTarget>,
+    /// Forward and present + UI multisampling; `None` at 1 sample.
+    pub msaa: Option<MsaaTargets>,
+
     // Default resources
     pub default_texture: wgpu::Texture,
     pub default_texture_view: wgpu::TextureView,
@@ -147,7 +224,16 @@ pub struct DeferredPipeline {
     // Bind group layouts
     pub lighting_bgl: wgpu::BindGroupLayout,
     pub light_data_bgl: wgpu::BindGroupLayout,
+    pub environment_bgl: wgpu::BindGroupLayout,
+    pub fog_inject_bgl: wgpu::BindGroupLayout,
+    pub fog_integrate_bgl: wgpu::BindGroupLayout,
     pub per_object_bgl: wgpu::BindGroupLayout,
+    /// Per-object uniform plus bone palette, for skinned forward draws.
+    pub skinned_object_bgl: wgpu::BindGroupLayout,
+    pub decal_bgl: wgpu::BindGroupLayout,
+    pub instance_bgl: wgpu::BindGroupLayout,
+    pub instance_cull_bgl: wgpu::BindGroupLayout,
+    pub light_cluster_bgl: wgpu::BindGroupLayout,
     pub particle_bgl: wgpu::BindGroupLayout,
     pub ui_bgl: wgpu::BindGroupLayout,
     pub terrain_bgl: wgpu::BindGroupLayout,
@@ -163,12 +249,27 @@ pub struct DeferredPipeline {
     pub bloom_blur_bgl: wgpu::BindGroupLayout,
     pub bloom_composite_bgl: wgpu::BindGroupLayout,
     pub fxaa_bgl: wgpu::BindGroupLayout,
+    /// Uniform + depth + sampler, shared by the DOF CoC and velocity passes.
+    pub depth_effect_bgl: wgpu::BindGroupLayout,
+    pub dof_blur_bgl: wgpu::BindGroupLayout,
+    pub dof_composite_bgl: wgpu::BindGroupLayout,
+    pub motion_blur_bgl: wgpu::BindGroupLayout,
+    pub color_grading_bgl: wgpu::BindGroupLayout,
+    pub evsm_moments_bgl: wgpu::BindGroupLayout,
+    pub msaa_prime_bgl: wgpu::BindGroupLayout,
+    pub upscale_bgl: wgpu::BindGroupLayout,
 
     // Uniform buffers for effects
     pub ssao_params_buffer: wgpu::Buffer,
     pub ssr_params_buffer: wgpu::Buffer,
     pub taa_params_buffer: wgpu::Buffer,
     pub pp_params_buffer: wgpu::Buffer,
+    /// `OutputParams` of the present pass.
+    pub output_params_buffer: wgpu::Buffer,
+    /// `UpscaleParams` of the upscale pass.
+    pub upscale_params_buffer: wgpu::Buffer,
+    /// 256-byte slots for the other post-process passes (see `post_process::SLOT_*`).
+    pub effect_params_buffer: wgpu::Buffer,
     pub shadow_uniform_buffer: wgpu::Buffer,
     pub particle_uniform_buffer: wgpu::Buffer,
     pub ui_uniform_buffer: wgpu::Buffer,
@@ -177,15 +278,137 @@ pub struct DeferredPipeline {
     // Samplers
     pub depth_sampler: wgpu::Sampler,
     pub shadow_comparison_sampler: wgpu::Sampler,
+    /// Linear clamp sampler of the EVSM moments.
+    pub csm_moments_sampler: wgpu::Sampler,
+    /// Linear clamp sampler of the decal textures.
+    pub decal_sampler: wgpu::Sampler,
 
     // Dynamic vertex buffers for streaming data
     pub particle_vbo: wgpu::Buffer,
     pub particle_vbo_size: u64,
     pub ui_vbo: wgpu::Buffer,
     pub ui_vbo_size: u64,
+    /// Vertices uploaded by the last UI pass, drawn by the next present.
+    pub ui_vertex_count: u32,
 
     // TAA state
     pub taa_first_frame: bool,
+    /// Camera view-projection of the last post-process pass, for TAA and motion blur.
+    pub prev_view_proj: Option<glam::Mat4>,
+    /// Post-process passes run so far; seeds the film grain.
+    pub frame_index: u32,
+
+    /// Exposure of the tone mapping pass, metered here in auto mode.
+    pub exposure: AutoExposure,
+}
+
+/// Draws kept and culled by the last G-Buffer and shadow passes (shadow counts
+/// summed over cascades).
+#[derive(Clone, Copy, Default)]
+pub struct CullStats {
+    pub gbuffer_drawn: u32,
+    pub gbuffer_culled: u32,
+    pub shadow_drawn: u32,
+    pub shadow_culled: u32,
+}
+
+/// Storage buffer of light records, grown to the next power of two on upload.
+pub struct LightBuffer {
+    pub buffer: wgpu::Buffer,
+    /// Records `buffer` can hold.
+    pub capacity: u32,
+    pub count: u32,
+    label: &'static str,
+    stride: u64,
+}
+
+impl LightBuffer {
+    pub fn new<T: bytemuck::Pod>(device: &wgpu::Device, label: &'static str) -> Self {
+        let stride = std::mem::size_of::<T>() as u64;
+        Self {
+            buffer: Self::create(device, label, stride),
+            capacity: 1,
+            count: 0,
+            label,
+            stride,
+        }
+    }
+
+    fn create(device: &wgpu::Device, label: &str, size: u64) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Upload `lights`, reallocating the buffer when they no longer fit.
+    pub fn upload<T: bytemuck::Pod>(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, lights: &[T]) {
+        let count = lights.len() as u32;
+        if count > self.capacity {
+            self.capacity = count.next_power_of_two();
+            self.buffer = Self::create(device, self.label, self.capacity as u64 * self.stride);
+        }
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(lights));
+        self.count = count;
+    }
+}
+
+/// Point lights and their per-froxel light lists, rebuilt each lighting pass
+/// by `light_cluster.wgsl`, plus the spot and area lights every pixel loops over.
+pub struct LightClusters {
+    pub point_lights: LightBuffer,
+    pub spot_lights: LightBuffer,
+    pub area_lights: LightBuffer,
+    /// Last uploaded point and spot lights; the local shadow pass fills in
+    /// their `shadow_index` and uploads them again.
+    pub point_light_data: Vec<PointLightData>,
+    pub spot_light_data: Vec<SpotLightData>,
+    pub params_buffer: wgpu::Buffer,
+    pub counts_buffer: wgpu::Buffer,
+    pub indices_buffer: wgpu::Buffer,
+}
+
+impl LightClusters {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let clusters = clustering::cluster_count() as u64;
+        Self {
+            point_lights: LightBuffer::new::<PointLightData>(device, "Point Lights"),
+            spot_lights: LightBuffer::new::<SpotLightData>(device, "Spot Lights"),
+            area_lights: LightBuffer::new::<AreaLightData>(device, "Area Lights"),
+            point_light_data: Vec::new(),
+            spot_light_data: Vec::new(),
+            params_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Cluster Params"),
+                size: std::mem::size_of::<ClusterParams>() as u64,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            counts_buffer: LightBuffer::create(device, "Cluster Light Counts", clusters * 4),
+            indices_buffer: LightBuffer::create(
+                device,
+                "Cluster Light Indices",
+                clusters * clustering::MAX_LIGHTS_PER_CLUSTER as u64 * 4,
+            ),
+        }
+    }
+}
+
+fn create_instance() -> wgpu::Instance {
+    wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::all(),
+        ..Default::default()
+    })
+}
+
+fn request_adapter(instance: &wgpu::Instance, surface: Option<&wgpu::Surface<'static>>) -> Result<wgpu::Adapter, String> {
+    pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::HighPerformance,
+        compatible_surface: surface,
+        force_fallback_adapter: false,
+    }))
+    .ok_or_else(|| "Failed to find suitable GPU adapter".into())
 }
 
 /// Main backend state — owns all wgpu resources.
@@ -194,10 +417,24 @@ pub struct WGPUBackendState {
     pub adapter: wgpu::Adapter,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
-    pub surface: wgpu::Surface<'static>,
+    /// Swapchain surface, or the headless target.
+    pub output: OutputTarget,
+    /// Configuration of the surface; only its format and size apply to the
+    /// headless target.
     pub surface_config: wgpu::SurfaceConfiguration,
+    /// Requested display mode and luminance range.
+    pub display: DisplaySettings,
+    /// `display::OUTPUT_*` the present pass encodes with for the current format.
+    pub output_encoding: u32,
+    /// Output size; the deferred targets are `render_extent()`.
     pub width: u32,
     pub height: u32,
+    /// Render scale, dynamic resolution and MSAA settings.
+    pub resolution: ResolutionSettings,
+    /// Render scale in use; follows the frame time with dynamic resolution.
+    pub render_scale: f32,
+    pub dynamic_resolution: DynamicResolution,
+    pub frame_timer: FrameTimer,
 
     // Resource stores (Julia holds opaque u64 handles into these)
     pub meshes: HandleStore<GPUMesh>,
@@ -207,29 +444,69 @@ pub struct WGPUBackendState {
     // Deferred pipeline resources
     pub gbuffer: Option<GBuffer>,
     pub lighting_target: Option<RenderTarget>,
-    pub csm: Option<CascadedShadowMap>,
+    /// Directional light cascades; a placeholder until created.
+    pub csm: CascadedShadowMap,
+    /// Point and spot light shadows; a 1x1 placeholder until created.
+    pub shadow_atlas: ShadowAtlas,
+    /// Sky and volumetric fog; kept across deferred pipeline rebuilds.
+    pub environment: Environment,
 
     // Screen-space effects
     pub ssao: Option<SSAOPass>,
     pub ssr: Option<SSRPass>,
     pub taa: Option<TAAPass>,
-    pub post_process: Option<PostProcessPipeline>,
+    /// Effect order, enabled flags and settings; kept across deferred pipeline rebuilds.
+    pub post_process: PostProcessStack,
+    /// 3D LUT of the color grading pass.
+    pub color_lut: ColorLut,
 
     // Shared GPU resources
     pub per_frame_buffer: wgpu::Buffer,
     pub per_frame_bind_group_layout: wgpu::BindGroupLayout,
-    pub per_object_buffer: wgpu::Buffer,
     pub material_bind_group_layout: wgpu::BindGroupLayout,
+    pub skin_bind_group_layout: wgpu::BindGroupLayout,
     pub light_buffer: wgpu::Buffer,
+    pub light_clusters: LightClusters,
     pub default_sampler: wgpu::Sampler,
+    /// Instancing buffers of the G-buffer pass, shadow cascades and shadow atlas tiles.
+    pub gbuffer_instances: InstanceBuffers,
+    pub cascade_instances: InstanceBufferPool,
+    pub local_shadow_instances: InstanceBufferPool,
 
     // Deferred rendering pipeline (created on demand)
     pub deferred: Option<DeferredPipeline>,
 
+    // GPU-simulated particle emitters (pipelines created with the first emitter)
+    pub particle_emitters: HandleStore<GPUParticleEmitter>,
+    pub gpu_particles: Option<GPUParticlePipelines>,
+
+    /// Decals, drawn in handle (creation) order after the G-Buffer pass.
+    pub decals: HandleStore<Decal>,
+
+    // Culling: camera frustum from the last `begin_frame`, counts from the last passes
+    pub frame_frustum: Option<[[f32; 4]; 6]>,
+    /// Camera view and projection from the last `begin_frame`, for light clustering.
+    pub frame_camera: Option<(glam::Mat4, glam::Mat4)>,
+    pub cull_stats: CullStats,
+    /// Cull instances in a compute pass and draw them indirectly, instead of
+    /// culling on the CPU. Needs `INDIRECT_FIRST_INSTANCE`.
+    pub gpu_culling: bool,
+
     // Error state
     pub last_error: Option<String>,
 }
 
+/// Bytes per bone in the palette: one column-major `mat4x4<f32>`.
+const BONE_MATRIX_SIZE: u64 = 64;
+
+/// The whole matrices of a flat bone palette; a trailing partial matrix is ignored.
+fn bone_palette(matrices: &[f32]) -> Result<&[f32], String> {
+    match matrices.len() / 16 {
+        0 => Err("Bone palette is empty".into()),
+        bones => Ok(&matrices[..bones * 16]),
+    }
+}
+
 impl WGPUBackendState {
     /// Create a new backend state from a raw window handle.
     pub fn new(
@@ -237,28 +514,43 @@ impl WGPUBackendState {
         width: u32,
         height: u32,
     ) -> Result<Self, String> {
-        use openreality_gpu_shared::uniforms::*;
-
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::all(),
-            ..Default::default()
-        });
-
+        let instance = create_instance();
         let surface = instance
             .create_surface(window)
             .map_err(|e| format!("Failed to create surface: {e}"))?;
+        let adapter = request_adapter(&instance, Some(&surface))?;
+        let formats = surface.get_capabilities(&adapter).formats;
+        Self::with_output(instance, adapter, Some((surface, formats)), width, height)
+    }
+
+    /// Create a backend state without a window, presenting to an offscreen
+    /// `Rgba16Float` target that `output::read_headless_target` reads back.
+    pub fn new_headless(width: u32, height: u32) -> Result<Self, String> {
+        let instance = create_instance();
+        let adapter = request_adapter(&instance, None)?;
+        Self::with_output(instance, adapter, None, width, height)
+    }
 
-        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
-            power_preference: wgpu::PowerPreference::HighPerformance,
-            compatible_surface: Some(&surface),
-            force_fallback_adapter: false,
-        }))
-        .ok_or("Failed to find suitable GPU adapter")?;
+    fn with_output(
+        instance: wgpu::Instance,
+        adapter: wgpu::Adapter,
+        surface: Option<(wgpu::Surface<'static>, Vec<wgpu::TextureFormat>)>,
+        width: u32,
+        height: u32,
+    ) -> Result<Self, String> {
+        use openreality_gpu_shared::uniforms::*;
 
         let (device, queue) = pollster::block_on(adapter.request_device(
             &wgpu::DeviceDescriptor {
                 label: Some("OpenReality WebGPU Device"),
-                required_features: wgpu::Features::empty(),
+                // Indirect first instance lets GPU-culled draws start at a batch's
+                // instance range; the others are for MSAA sample counts beyond
+                // 1 and 4 and GPU frame timing. All are optional.
+                required_features: adapter.features()
+                    & (wgpu::Features::INDIRECT_FIRST_INSTANCE
+                        | wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+                        | wgpu::Features::TIMESTAMP_QUERY
+                        | wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS),
                 required_limits: wgpu::Limits::default(),
                 memory_hints: wgpu::MemoryHints::default(),
             },
@@ -266,25 +558,30 @@ impl WGPUBackendState {
         ))
         .map_err(|e| format!("Failed to create device: {e}"))?;
 
-        let surface_caps = surface.get_capabilities(&adapter);
-        let surface_format = surface_caps
-            .formats
-            .iter()
-            .find(|f| f.is_srgb())
-            .copied()
-            .unwrap_or(surface_caps.formats[0]);
-
-        let surface_config = wgpu::SurfaceConfiguration {
+        let mut surface_config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: surface_format,
+            format: output::HEADLESS_FORMAT,
             width,
             height,
             present_mode: wgpu::PresentMode::Fifo,
-            alpha_mode: surface_caps.alpha_modes[0],
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
             view_formats: vec![],
             desired_maximum_frame_latency: 2,
         };
-        surface.configure(&device, &surface_config);
+        let (output, output_encoding) = match surface {
+            Some((surface, formats)) => {
+                let kinds: Vec<_> = formats.iter().map(|&f| output::format_kind(f)).collect();
+                let (index, encoding) = display::choose_surface_format(&kinds, DISPLAY_SDR);
+                surface_config.format = formats[index];
+                surface_config.alpha_mode = surface.get_capabilities(&adapter).alpha_modes[0];
+                surface.configure(&device, &surface_config);
+                (OutputTarget::Surface { surface, formats }, encoding)
+            }
+            None => (
+                OutputTarget::Headless(output::create_headless_target(&device, width, height)),
+                output::headless_encoding(DISPLAY_SDR),
+            ),
+        };
 
         // Create per-frame uniform buffer
         let per_frame_buffer = device.create_buffer(&wgpu::BufferDescriptor {
@@ -309,14 +606,6 @@ impl WGPUBackendState {
                 }],
             });
 
-        // Per-object uniform buffer
-        let per_object_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Per-Object Uniforms"),
-            size: std::mem::size_of::<PerObjectUniforms>() as u64,
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-
         // Material bind group layout
         let material_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -404,6 +693,9 @@ impl WGPUBackendState {
                 ],
             });
 
+        // Bone palette layout (needed at upload time, before the deferred pipeline exists)
+        let skin_bind_group_layout = crate::pipeline::create_skin_bind_group_layout(&device);
+
         // Light uniform buffer
         let light_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Light Uniforms"),
@@ -412,6 +704,13 @@ impl WGPUBackendState {
             mapped_at_creation: false,
         });
 
+        let light_clusters = LightClusters::new(&device);
+        let shadow_atlas = ShadowAtlas::placeholder(&device);
+        let csm = CascadedShadowMap::placeholder(&device);
+        let environment = Environment::new(&device);
+        let color_lut = ColorLut::identity(&device, &queue);
+        let frame_timer = FrameTimer::new(&device, &queue);
+
         // Default sampler
         let default_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             label: Some("Default Sampler"),
@@ -435,27 +734,47 @@ impl WGPUBackendState {
             adapter,
             device,
             queue,
-            surface,
+            output,
             surface_config,
+            display: DisplaySettings::default(),
+            output_encoding,
             width,
             height,
+            resolution: ResolutionSettings::default(),
+            render_scale: 1.0,
+            dynamic_resolution: DynamicResolution::default(),
+            frame_timer,
             meshes: HandleStore::new(),
             textures: HandleStore::new(),
             framebuffers: HandleStore::new(),
             gbuffer: None,
             lighting_target: None,
-            csm: None,
+            csm,
+            shadow_atlas,
+            environment,
             ssao: None,
             ssr: None,
             taa: None,
-            post_process: None,
+            post_process: PostProcessStack::default(),
+            color_lut,
             per_frame_buffer,
             per_frame_bind_group_layout,
-            per_object_buffer,
             material_bind_group_layout,
+            skin_bind_group_layout,
             light_buffer,
+            light_clusters,
             default_sampler,
+            gbuffer_instances: InstanceBuffers::default(),
+            cascade_instances: InstanceBufferPool::default(),
+            local_shadow_instances: InstanceBufferPool::default(),
             deferred: None,
+            particle_emitters: HandleStore::new(),
+            gpu_particles: None,
+            decals: HandleStore::new(),
+            frame_frustum: None,
+            frame_camera: None,
+            cull_stats: CullStats::default(),
+            gpu_culling: false,
             last_error: None,
         })
     }
@@ -467,20 +786,43 @@ impl WGPUBackendState {
             self.height = height;
             self.surface_config.width = width;
             self.surface_config.height = height;
-            self.surface.configure(&self.device, &self.surface_config);
+            match &mut self.output {
+                OutputTarget::Surface { surface, .. } => surface.configure(&self.device, &self.surface_config),
+                OutputTarget::Headless(target) => *target = output::create_headless_target(&self.device, width, height),
+            }
+        }
+    }
+
+    /// Switch the output to `settings.mode`, reconfiguring the surface with
+    /// the best format it offers for it. Returns the `display::OUTPUT_*`
+    /// encoding in use, which is SDR when the surface has no HDR format.
+    pub fn set_display_output(&mut self, settings: DisplaySettings) -> Result<u32, String> {
+        settings.validate()?;
+        let (encoding, format_changed) = match &self.output {
+            OutputTarget::Surface { surface, formats } => {
+                let kinds: Vec<_> = formats.iter().map(|&f| output::format_kind(f)).collect();
+                let (index, encoding) = display::choose_surface_format(&kinds, settings.mode);
+                let format_changed = formats[index] != self.surface_config.format;
+                if format_changed {
+                    self.surface_config.format = formats[index];
+                    surface.configure(&self.device, &self.surface_config);
+                    log::info!("Display output switched to {:?}", formats[index]);
+                }
+                (encoding, format_changed)
+            }
+            OutputTarget::Headless(_) => (output::headless_encoding(settings.mode), false),
+        };
+        if format_changed {
+            self.rebuild_sampled_resources();
         }
+        self.display = settings;
+        self.output_encoding = encoding;
+        Ok(encoding)
     }
 
     /// Render a frame that just clears to a color (bootstrap pass).
     pub fn render_clear(&mut self, r: f64, g: f64, b: f64) -> Result<(), String> {
-        let output = self
-            .surface
-            .get_current_texture()
-            .map_err(|e| format!("Surface texture error: {e}"))?;
-
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+        let frame = self.output.acquire()?;
 
         let mut encoder = self
             .device
@@ -492,7 +834,7 @@ impl WGPUBackendState {
             let _render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Clear Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
+                    view: &frame.view,
                     resolve_target: None,
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Clear(wgpu::Color {
@@ -510,7 +852,7 @@ impl WGPUBackendState {
         }
 
         self.queue.submit(std::iter::once(encoder.finish()));
-        output.present();
+        frame.present();
 
         Ok(())
     }
@@ -530,7 +872,8 @@ impl WGPUBackendState {
             .create_buffer_init(&wgpu::util::BufferInitDescriptor {
                 label: Some("Vertex Position Buffer"),
                 contents: bytemuck::cast_slice(positions),
-                usage: wgpu::BufferUsages::VERTEX,
+                // COPY_DST so morph targets can rewrite the vertices in place
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
             });
 
         let normal_buffer = self
@@ -538,7 +881,7 @@ impl WGPUBackendState {
             .create_buffer_init(&wgpu::util::BufferInitDescriptor {
                 label: Some("Vertex Normal Buffer"),
                 contents: bytemuck::cast_slice(normals),
-                usage: wgpu::BufferUsages::VERTEX,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
             });
 
         let uv_buffer = self
@@ -563,11 +906,150 @@ impl WGPUBackendState {
             uv_buffer,
             index_buffer,
             index_count: indices.len() as u32,
+            vertex_count: (positions.len() / 3) as u32,
+            morph: None,
+            skin: None,
+            bounds: BoundingSphere::from_positions(positions),
         };
 
         self.meshes.insert(mesh)
     }
 
+    /// Upload a mesh with per-vertex bone weights (4 f32) and bone indices
+    /// (4 u16). The bone palette starts as a single identity matrix.
+    pub fn upload_skinned_mesh(
+        &mut self,
+        positions: &[f32],
+        normals: &[f32],
+        uvs: &[f32],
+        bone_weights: &[f32],
+        bone_indices: &[u16],
+        indices: &[u32],
+    ) -> u64 {
+        use wgpu::util::DeviceExt;
+
+        let bone_weight_buffer = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Bone Weight Buffer"),
+                contents: bytemuck::cast_slice(bone_weights),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+
+        let bone_index_buffer = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Bone Index Buffer"),
+                contents: bytemuck::cast_slice(bone_indices),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+
+        let identity = glam::Mat4::IDENTITY.to_cols_array();
+        let (bone_buffer, bone_bind_group) = self.create_bone_palette(1);
+        self.queue.write_buffer(&bone_buffer, 0, bytemuck::cast_slice(&identity));
+
+        let handle = self.upload_mesh(positions, normals, uvs, indices);
+        if let Some(mesh) = self.meshes.get_mut(handle) {
+            mesh.skin = Some(SkinData {
+                bone_weight_buffer,
+                bone_index_buffer,
+                bone_buffer,
+                bone_bind_group,
+                bone_capacity: 1,
+            });
+        }
+        handle
+    }
+
+    /// Upload this frame's bone palette (`bone_count` column-major mat4s) for a
+    /// skinned mesh. The storage buffer grows as needed.
+    pub fn update_bone_matrices(&mut self, mesh: u64, matrices: &[f32]) -> Result<(), String> {
+        let palette = bone_palette(matrices)?;
+        let bone_count = (palette.len() / 16) as u32;
+        let capacity = match self.meshes.get(mesh) {
+            Some(m) => m.skin.as_ref().ok_or("Mesh is not skinned")?.bone_capacity,
+            None => return Err("Invalid mesh handle".into()),
+        };
+        if bone_count > capacity {
+            let (buffer, bind_group) = self.create_bone_palette(bone_count);
+            if let Some(skin) = self.meshes.get_mut(mesh).and_then(|m| m.skin.as_mut()) {
+                skin.bone_buffer = buffer;
+                skin.bone_bind_group = bind_group;
+                skin.bone_capacity = bone_count;
+            }
+        }
+        let skin = self.meshes.get(mesh).and_then(|m| m.skin.as_ref()).ok_or("Mesh is not skinned")?;
+        self.queue.write_buffer(&skin.bone_buffer, 0, bytemuck::cast_slice(palette));
+        Ok(())
+    }
+
+    fn create_bone_palette(&self, bone_count: u32) -> (wgpu::Buffer, wgpu::BindGroup) {
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Bone Matrix Buffer"),
+            size: bone_count as u64 * BONE_MATRIX_SIZE,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Skin BG"),
+            layout: &self.skin_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+        (buffer, bind_group)
+    }
+
+    /// Attach morph targets to an uploaded mesh. `base_positions`/`base_normals`
+    /// must be the data the mesh was uploaded with. All weights start at zero.
+    pub fn set_morph_targets(
+        &mut self,
+        mesh: u64,
+        base_positions: &[f32],
+        base_normals: &[f32],
+        targets: Vec<MorphTargetParsed>,
+    ) -> Result<(), String> {
+        let gpu_mesh = self.meshes.get_mut(mesh).ok_or("Invalid mesh handle")?;
+        let n = gpu_mesh.vertex_count as usize * 3;
+        if base_positions.len() != n || base_normals.len() != n {
+            return Err("Morph base vertex count does not match mesh".into());
+        }
+        if targets.iter().any(|t| t.position_deltas.len() != n || (!t.normal_deltas.is_empty() && t.normal_deltas.len() != n)) {
+            return Err("Morph target vertex count does not match mesh".into());
+        }
+        gpu_mesh.morph = Some(MorphData {
+            base_positions: base_positions.to_vec(),
+            base_normals: base_normals.to_vec(),
+            weights: vec![0.0; targets.len()],
+            targets,
+            scratch_positions: Vec::new(),
+            scratch_normals: Vec::new(),
+        });
+        Ok(())
+    }
+
+    /// Update morph weights and rewrite the mesh's position/normal buffers.
+    pub fn set_morph_weights(&mut self, mesh: u64, weights: &[f32]) -> Result<(), String> {
+        let gpu_mesh = self.meshes.get_mut(mesh).ok_or("Invalid mesh handle")?;
+        let morph = gpu_mesh.morph.as_mut().ok_or("Mesh has no morph targets")?;
+        for (dst, &src) in morph.weights.iter_mut().zip(weights) {
+            *dst = src;
+        }
+        apply_morph_targets(
+            &morph.base_positions,
+            &morph.base_normals,
+            &morph.targets,
+            &morph.weights,
+            &mut morph.scratch_positions,
+            &mut morph.scratch_normals,
+        );
+        self.queue.write_buffer(&gpu_mesh.vertex_buffer, 0, bytemuck::cast_slice(&morph.scratch_positions));
+        self.queue.write_buffer(&gpu_mesh.normal_buffer, 0, bytemuck::cast_slice(&morph.scratch_normals));
+        gpu_mesh.bounds = BoundingSphere::from_positions(&morph.scratch_positions);
+        Ok(())
+    }
+
     /// Upload texture data to GPU.
     pub fn upload_texture(
         &mut self,
@@ -664,6 +1146,102 @@ impl WGPUBackendState {
         self.textures.remove(handle);
     }
 
+    /// Create a GPU particle emitter. Returns the emitter handle.
+    pub fn create_particle_emitter(&mut self, params: ParticleEmitterParams) -> Result<u64, String> {
+        let pipelines = self
+            .gpu_particles
+            .get_or_insert_with(|| GPUParticlePipelines::new(&self.device, &self.queue));
+        let emitter = GPUParticleEmitter::new(&self.device, &self.queue, pipelines, params)?;
+        Ok(self.particle_emitters.insert(emitter))
+    }
+
+    /// Replace an emitter's parameters. Changing `max_particles` reallocates
+    /// the particle storage and drops its live particles.
+    pub fn update_particle_emitter(&mut self, handle: u64, params: ParticleEmitterParams) -> Result<(), String> {
+        let pipelines = self.gpu_particles.as_ref().ok_or("GPU particle pipelines not created")?;
+        let emitter = self.particle_emitters.get_mut(handle).ok_or("Invalid particle emitter handle")?;
+        let sprite = self.textures.get(emitter.texture).map(|t| &t.view);
+        emitter.set_params(&self.device, &self.queue, pipelines, params, sprite)
+    }
+
+    /// Emit from the surface of a triangle mesh (used by `EmitterShape::Mesh`).
+    pub fn set_particle_emitter_mesh(&mut self, handle: u64, positions: &[f32], indices: &[u32]) -> Result<(), String> {
+        let pipelines = self.gpu_particles.as_ref().ok_or("GPU particle pipelines not created")?;
+        let emitter = self.particle_emitters.get_mut(handle).ok_or("Invalid particle emitter handle")?;
+        let sampler = MeshSurfaceSampler::new(positions, indices).ok_or("Emission mesh has no surface area")?;
+        emitter.set_mesh_triangles(&self.device, &self.queue, pipelines, &sampler.gpu_triangles());
+        Ok(())
+    }
+
+    /// Attach a sprite texture to an emitter. Handle 0 detaches it.
+    pub fn set_particle_emitter_texture(&mut self, handle: u64, texture: u64) -> Result<(), String> {
+        let pipelines = self.gpu_particles.as_ref().ok_or("GPU particle pipelines not created")?;
+        let emitter = self.particle_emitters.get_mut(handle).ok_or("Invalid particle emitter handle")?;
+        let view = if texture == 0 {
+            None
+        } else {
+            Some(&self.textures.get(texture).ok_or("Invalid texture handle")?.view)
+        };
+        emitter.set_texture(&self.device, &self.queue, pipelines, texture, view);
+        Ok(())
+    }
+
+    /// Destroy a GPU particle emitter by handle.
+    pub fn destroy_particle_emitter(&mut self, handle: u64) {
+        self.particle_emitters.remove(handle);
+    }
+
+    /// Place a decal box. Returns the decal handle.
+    pub fn add_decal(&mut self, decal: Decal) -> Result<u64, String> {
+        self.validate_decal(&decal)?;
+        Ok(self.decals.insert(decal))
+    }
+
+    /// Move a decal or replace its settings and textures.
+    pub fn update_decal(&mut self, handle: u64, decal: Decal) -> Result<(), String> {
+        self.validate_decal(&decal)?;
+        let slot = self.decals.get_mut(handle).ok_or("Invalid decal handle")?;
+        *slot = decal;
+        Ok(())
+    }
+
+    /// Remove a decal by handle.
+    pub fn remove_decal(&mut self, handle: u64) {
+        self.decals.remove(handle);
+    }
+
+    fn validate_decal(&self, decal: &Decal) -> Result<(), String> {
+        decal.params.validate()?;
+        decal::validate_decal_transform(&decal.transform)?;
+        for texture in [decal.albedo_texture, decal.normal_texture] {
+            if texture != 0 && self.textures.get(texture).is_none() {
+                return Err("Invalid texture handle".into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Run one simulation step for every emitter. Needs the deferred pipeline,
+    /// whose G-buffer feeds depth-buffer collisions; without it nothing runs.
+    pub fn simulate_particles(&mut self, dt: f32, view: glam::Mat4, proj: glam::Mat4) {
+        let Some(pipelines) = self.gpu_particles.as_ref() else { return };
+        let Some(dp) = self.deferred.as_ref() else { return };
+
+        let camera = ParticleCamera {
+            view_proj: proj * view,
+            position: view.inverse().w_axis.truncate(),
+            screen_size: [dp.gbuffer.width as f32, dp.gbuffer.height as f32],
+        };
+        let scene_bg = pipelines.scene_bind_group(&self.device, &dp.gbuffer.depth_view, &dp.gbuffer.normal_roughness_view);
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Particle Simulation Encoder"),
+        });
+        for (_, emitter) in self.particle_emitters.iter_mut() {
+            emitter.record_simulation(&mut encoder, &self.queue, pipelines, &scene_bg, dt, &camera);
+        }
+        self.queue.submit(std::iter::once(encoder.finish()));
+    }
+
     /// Create the full deferred rendering pipeline (all pipelines and targets).
     pub fn create_deferred_pipeline(&mut self) -> Result<(), String> {
         use crate::pipeline;
@@ -671,9 +1249,9 @@ impl WGPUBackendState {
 
         let device = &self.device;
         let queue = &self.queue;
-        let w = self.width;
-        let h = self.height;
+        let (w, h) = self.render_extent();
         let surface_format = self.surface_config.format;
+        let samples = self.resolution.msaa_samples;
 
         // Per-object bind group layout
         let per_object_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -690,9 +1268,18 @@ impl WGPUBackendState {
             }],
         });
 
+        let skinned_object_bgl = pipeline::create_skinned_object_bind_group_layout(device);
+        let instance_bgl = pipeline::create_instance_bind_group_layout(device);
+        let instance_cull_bgl = pipeline::create_instance_cull_bind_group_layout(device);
+        let light_cluster_bgl = pipeline::create_light_cluster_bind_group_layout(device);
+        let decal_bgl = pipeline::create_decal_bind_group_layout(device);
+
         // Create all bind group layouts
         let lighting_bgl = pipeline::create_lighting_bind_group_layout(device);
         let light_data_bgl = pipeline::create_light_data_bind_group_layout(device);
+        let environment_bgl = pipeline::create_environment_bind_group_layout(device);
+        let fog_inject_bgl = pipeline::create_fog_inject_bind_group_layout(device);
+        let fog_integrate_bgl = pipeline::create_fog_integrate_bind_group_layout(device);
         let particle_bgl = pipeline::create_particle_bgl(device);
         let ui_bgl = pipeline::create_ui_bgl(device);
         let terrain_bgl = pipeline::create_terrain_bgl(device);
@@ -706,30 +1293,83 @@ impl WGPUBackendState {
         let taa_bgl = pipeline::create_taa_bind_group_layout(device);
         let bloom_extract_bgl = pipeline::create_effect_bind_group_layout(device, "Bloom Extract BGL", 1, false);
         let bloom_blur_bgl = pipeline::create_effect_bind_group_layout(device, "Bloom Blur BGL", 1, false);
-        let bloom_composite_bgl = pipeline::create_effect_bind_group_layout(device, "Bloom Composite BGL", 2, false);
+        let bloom_composite_bgl = pipeline::create_tone_map_bind_group_layout(device);
         let fxaa_bgl = pipeline::create_fxaa_bind_group_layout(device);
+        let depth_effect_bgl = pipeline::create_depth_effect_bind_group_layout(device, "Depth Effect BGL");
+        let dof_blur_bgl = pipeline::create_effect_bind_group_layout(device, "DOF Blur BGL", 2, false);
+        let dof_composite_bgl = pipeline::create_dof_composite_bind_group_layout(device);
+        let motion_blur_bgl = pipeline::create_effect_bind_group_layout(device, "Motion Blur BGL", 2, false);
+        let color_grading_bgl = pipeline::create_color_grading_bind_group_layout(device);
+        let evsm_moments_bgl = pipeline::create_evsm_moments_bind_group_layout(device);
+        let msaa_prime_bgl = pipeline::create_msaa_prime_bgl(device);
+        let upscale_bgl = pipeline::create_effect_bind_group_layout(device, "Upscale BGL", 1, false);
 
         // Create render pipelines (with logging to diagnose driver crashes)
         log::info!("Creating G-Buffer pipeline...");
         let gbuffer_pipeline = pipeline::create_gbuffer_pipeline(
+            device,
+            &self.per_frame_bind_group_layout,
+            &self.material_bind_group_layout,
+            &instance_bgl,
+        );
+
+        log::info!("Creating skinned G-Buffer pipeline...");
+        let gbuffer_skinned_pipeline = pipeline::create_skinned_gbuffer_pipeline(
             device,
             &self.per_frame_bind_group_layout,
             &self.material_bind_group_layout,
             &per_object_bgl,
+            &self.skin_bind_group_layout,
         );
 
+        log::info!("Creating decal pipelines...");
+        let decal_pipeline =
+            pipeline::create_decal_pipeline(device, &self.per_frame_bind_group_layout, &decal_bgl, false);
+        let decal_roughness_pipeline =
+            pipeline::create_decal_pipeline(device, &self.per_frame_bind_group_layout, &decal_bgl, true);
+
         log::info!("Creating shadow pipeline...");
         let shadow_pipeline = pipeline::create_shadow_pipeline(
+            device,
+            &self.per_frame_bind_group_layout,
+            &instance_bgl,
+        );
+
+        log::info!("Creating skinned shadow pipeline...");
+        let shadow_skinned_pipeline = pipeline::create_skinned_shadow_pipeline(
             device,
             &self.per_frame_bind_group_layout,
             &per_object_bgl,
+            &self.skin_bind_group_layout,
         );
+        let shadow_clear_pipeline = pipeline::create_shadow_clear_pipeline(device);
+
+        log::info!("Creating instance cull pipeline...");
+        let instance_cull_pipeline = pipeline::create_instance_cull_pipeline(device, &instance_cull_bgl);
+
+        log::info!("Creating light cluster pipeline...");
+        let light_cluster_pipeline = pipeline::create_light_cluster_pipeline(device, &light_cluster_bgl);
 
         log::info!("Creating lighting pipeline...");
         let lighting_pipeline = pipeline::create_lighting_pipeline(
             device,
             &lighting_bgl,
             &light_data_bgl,
+            &environment_bgl,
+        );
+
+        log::info!("Creating fog pipelines...");
+        let fog_inject_pipeline = pipeline::create_fog_pipeline(
+            device,
+            "Fog Inject Pipeline",
+            shaders::FOG_INJECT_SHADER,
+            &[&fog_inject_bgl, &light_data_bgl],
+        );
+        let fog_integrate_pipeline = pipeline::create_fog_pipeline(
+            device,
+            "Fog Integrate Pipeline",
+            shaders::FOG_INTEGRATE_SHADER,
+            &[&fog_integrate_bgl],
         );
 
         log::info!("Creating forward pipeline...");
@@ -739,19 +1379,31 @@ impl WGPUBackendState {
             &self.material_bind_group_layout,
             &per_object_bgl,
             &forward_light_shadow_bgl,
+            samples,
         );
+        let forward_skinned_pipeline = pipeline::create_skinned_forward_pipeline(
+            device,
+            &self.per_frame_bind_group_layout,
+            &self.material_bind_group_layout,
+            &skinned_object_bgl,
+            &forward_light_shadow_bgl,
+            samples,
+        );
+        let msaa_prime_pipeline =
+            (samples > 1).then(|| pipeline::create_msaa_prime_pipeline(device, &msaa_prime_bgl, samples));
 
         log::info!("Creating present pipeline...");
         let present_pipeline = pipeline::create_present_pipeline(
             device,
             &present_bgl,
             surface_format,
+            samples,
         );
 
         log::info!("Creating particle pipeline...");
-        let particle_pipeline = pipeline::create_particle_pipeline(device, &particle_bgl, surface_format);
+        let particle_pipelines = [0, 1, 2].map(|mode| pipeline::create_particle_pipeline(device, &particle_bgl, mode));
         log::info!("Creating UI pipeline...");
-        let ui_pipeline = pipeline::create_ui_pipeline(device, &ui_bgl, surface_format);
+        let ui_pipeline = pipeline::create_ui_pipeline(device, &ui_bgl, surface_format, samples);
         log::info!("Creating terrain pipeline...");
         let terrain_pipeline = pipeline::create_terrain_pipeline(device, &self.per_frame_bind_group_layout, &terrain_bgl);
 
@@ -789,6 +1441,31 @@ impl WGPUBackendState {
         let fxaa_pipeline = pipeline::create_fullscreen_effect_pipeline(
             device, "FXAA Pipeline", shaders::FXAA_FRAG, "fs_main", &fxaa_bgl, render_targets::HDR_FORMAT,
         );
+        log::info!("Creating DOF and motion blur pipelines...");
+        let dof_coc_pipeline = pipeline::create_fullscreen_effect_pipeline(
+            device, "DOF CoC Pipeline", shaders::DOF_SHADER, "fs_coc", &depth_effect_bgl, render_targets::R16_FORMAT,
+        );
+        let dof_blur_pipeline = pipeline::create_fullscreen_effect_pipeline(
+            device, "DOF Blur Pipeline", shaders::DOF_SHADER, "fs_blur", &dof_blur_bgl, render_targets::HDR_FORMAT,
+        );
+        let dof_composite_pipeline = pipeline::create_fullscreen_effect_pipeline(
+            device, "DOF Composite Pipeline", shaders::DOF_SHADER, "fs_composite", &dof_composite_bgl, render_targets::HDR_FORMAT,
+        );
+        let velocity_pipeline = pipeline::create_fullscreen_effect_pipeline(
+            device, "Velocity Pipeline", shaders::MOTION_BLUR_SHADER, "fs_velocity", &depth_effect_bgl, render_targets::RG16_FORMAT,
+        );
+        let motion_blur_pipeline = pipeline::create_fullscreen_effect_pipeline(
+            device, "Motion Blur Pipeline", shaders::MOTION_BLUR_SHADER, "fs_blur", &motion_blur_bgl, render_targets::HDR_FORMAT,
+        );
+        let color_grading_pipeline = pipeline::create_fullscreen_effect_pipeline(
+            device, "Color Grading Pipeline", shaders::COLOR_GRADING_FRAG, "fs_main", &color_grading_bgl, render_targets::HDR_FORMAT,
+        );
+        let evsm_moments_pipeline = pipeline::create_fullscreen_effect_pipeline(
+            device, "EVSM Moments Pipeline", shaders::EVSM_MOMENTS_FRAG, "fs_main", &evsm_moments_bgl, crate::csm::MOMENTS_FORMAT,
+        );
+        let upscale_pipeline = pipeline::create_fullscreen_effect_pipeline(
+            device, "FSR EASU Pipeline", shaders::FSR_EASU_FRAG, "fs_main", &upscale_bgl, render_targets::HDR_FORMAT,
+        );
         log::info!("All pipelines created successfully.");
 
         // Create render targets
@@ -798,8 +1475,12 @@ impl WGPUBackendState {
         let ssr_target = render_targets::create_ssr_target(device, w, h);
         let taa_targets = render_targets::create_taa_targets(device, w, h);
         let bloom_targets = render_targets::create_bloom_targets(device, w, h);
+        let dof_targets = render_targets::create_dof_targets(device, w, h);
+        let mblur_targets = render_targets::create_motion_blur_targets(device, w, h);
         let pp_target_a = render_targets::create_hdr_target(device, w, h, "PP Target A", false);
         let pp_target_b = render_targets::create_hdr_target(device, w, h, "PP Target B", false);
+        let upscale_target = self.create_upscale_target();
+        let msaa = self.create_msaa_targets();
 
         // Default resources
         let (default_texture, default_texture_view) = render_targets::create_default_texture(device, queue);
@@ -821,6 +1502,18 @@ impl WGPUBackendState {
             min_filter: wgpu::FilterMode::Linear,
             ..Default::default()
         });
+        let csm_moments_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("CSM Moments Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let decal_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Decal Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
 
         // Uniform buffers for effects
         let ssao_params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
@@ -847,6 +1540,24 @@ impl WGPUBackendState {
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
+        let output_params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Output Params"),
+            size: std::mem::size_of::<OutputParams>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let upscale_params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Upscale Params"),
+            size: std::mem::size_of::<UpscaleParams>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let effect_params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Effect Params"),
+            size: crate::post_process::SLOT_SIZE * crate::post_process::NUM_SLOTS,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
         let shadow_uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Shadow Uniforms"),
             size: std::mem::size_of::<ShadowUniforms>() as u64,
@@ -857,7 +1568,7 @@ impl WGPUBackendState {
         // Particle/UI uniform buffers (view/proj for particles, projection for UI)
         let particle_uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Particle Uniforms"),
-            size: 128, // 2 * mat4x4<f32>
+            size: std::mem::size_of::<ParticleDrawUniforms>() as u64,
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
@@ -893,11 +1604,24 @@ impl WGPUBackendState {
 
         self.deferred = Some(DeferredPipeline {
             gbuffer_pipeline,
+            gbuffer_skinned_pipeline,
+            decal_pipeline,
+            decal_roughness_pipeline,
             lighting_pipeline,
             shadow_pipeline,
+            shadow_skinned_pipeline,
+            shadow_clear_pipeline,
+            evsm_moments_pipeline,
+            msaa_prime_pipeline,
+            upscale_pipeline,
+            instance_cull_pipeline,
+            light_cluster_pipeline,
+            fog_inject_pipeline,
+            fog_integrate_pipeline,
             forward_pipeline,
+            forward_skinned_pipeline,
             present_pipeline,
-            particle_pipeline,
+            particle_pipelines,
             ui_pipeline,
             terrain_pipeline,
             ssao_pipeline,
@@ -908,16 +1632,24 @@ impl WGPUBackendState {
             bloom_blur_pipeline,
             bloom_composite_pipeline,
             fxaa_pipeline,
+            dof_coc_pipeline,
+            dof_blur_pipeline,
+            dof_composite_pipeline,
+            velocity_pipeline,
+            motion_blur_pipeline,
+            color_grading_pipeline,
             gbuffer,
             lighting_target,
             ssao_targets,
             ssr_target,
             taa_targets,
             bloom_targets,
-            dof_targets: None,
-            mblur_targets: None,
+            dof_targets,
+            mblur_targets,
             pp_target_a,
             pp_target_b,
+            upscale_target,
+            msaa,
             default_texture,
             default_texture_view,
             ssao_noise_texture,
@@ -925,7 +1657,15 @@ impl WGPUBackendState {
             fullscreen_quad_vbo,
             lighting_bgl,
             light_data_bgl,
+            environment_bgl,
+            fog_inject_bgl,
+            fog_integrate_bgl,
             per_object_bgl,
+            skinned_object_bgl,
+            decal_bgl,
+            instance_bgl,
+            instance_cull_bgl,
+            light_cluster_bgl,
             particle_bgl,
             ui_bgl,
             terrain_bgl,
@@ -939,29 +1679,50 @@ impl WGPUBackendState {
             bloom_blur_bgl,
             bloom_composite_bgl,
             fxaa_bgl,
+            depth_effect_bgl,
+            dof_blur_bgl,
+            dof_composite_bgl,
+            motion_blur_bgl,
+            color_grading_bgl,
+            evsm_moments_bgl,
+            msaa_prime_bgl,
+            upscale_bgl,
             ssao_params_buffer,
             ssr_params_buffer,
             taa_params_buffer,
             pp_params_buffer,
+            output_params_buffer,
+            upscale_params_buffer,
+            effect_params_buffer,
             shadow_uniform_buffer,
             particle_uniform_buffer,
             ui_uniform_buffer,
             terrain_params_buffer,
             depth_sampler,
             shadow_comparison_sampler,
+            csm_moments_sampler,
+            decal_sampler,
             particle_vbo,
             particle_vbo_size: initial_particle_vbo_size,
             ui_vbo,
             ui_vbo_size: initial_ui_vbo_size,
+            ui_vertex_count: 0,
             taa_first_frame: true,
+            prev_view_proj: None,
+            frame_index: 0,
+            exposure: AutoExposure::new(device),
         });
 
-        log::info!("Deferred pipeline created ({}x{})", w, h);
+        log::info!("Deferred pipeline created ({}x{}, {}x MSAA)", w, h, samples);
         Ok(())
     }
 
-    /// Resize deferred pipeline render targets (called on window resize).
-    pub fn resize_deferred_pipeline(&mut self, width: u32, height: u32) {
+    /// Resize deferred pipeline render targets to the render extent (called
+    /// on window resize and render scale changes).
+    pub fn resize_deferred_pipeline(&mut self) {
+        let (width, height) = self.render_extent();
+        let upscale_target = self.create_upscale_target();
+        let msaa = self.create_msaa_targets();
         if let Some(ref mut dp) = self.deferred {
             let device = &self.device;
 
@@ -971,11 +1732,160 @@ impl WGPUBackendState {
             dp.ssr_target = render_targets::create_ssr_target(device, width, height);
             dp.taa_targets = render_targets::create_taa_targets(device, width, height);
             dp.bloom_targets = render_targets::create_bloom_targets(device, width, height);
+            dp.dof_targets = render_targets::create_dof_targets(device, width, height);
+            dp.mblur_targets = render_targets::create_motion_blur_targets(device, width, height);
             dp.pp_target_a = render_targets::create_hdr_target(device, width, height, "PP Target A", false);
             dp.pp_target_b = render_targets::create_hdr_target(device, width, height, "PP Target B", false);
+            dp.upscale_target = upscale_target;
+            dp.msaa = msaa;
             dp.taa_first_frame = true;
 
             log::info!("Deferred pipeline resized to {}x{}", width, height);
         }
     }
+
+    /// Size of the deferred targets: the output size at the render scale.
+    pub fn render_extent(&self) -> (u32, u32) {
+        resolution::scaled_extent(self.width, self.height, self.render_scale)
+    }
+
+    fn create_upscale_target(&self) -> Option<RenderTarget> {
+        (self.render_extent() != (self.width, self.height)).then(|| {
+            render_targets::create_hdr_target(&self.device, self.width, self.height, "Upscale Target", false)
+        })
+    }
+
+    fn create_msaa_targets(&self) -> Option<MsaaTargets> {
+        let samples = self.resolution.msaa_samples;
+        (samples > 1).then(|| {
+            render_targets::create_msaa_targets(
+                &self.device,
+                self.render_extent(),
+                (self.width, self.height),
+                self.surface_config.format,
+                samples,
+            )
+        })
+    }
+
+    /// Check that the forward targets and the output format support
+    /// `samples` per pixel. Without adapter specific format features only
+    /// the sample counts WebGPU guarantees (1 and 4) are allowed.
+    pub fn check_msaa_samples(&self, samples: u32) -> Result<(), String> {
+        let formats = [render_targets::HDR_FORMAT, render_targets::DEPTH_FORMAT, self.surface_config.format];
+        let supported = if self.device.features().contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES) {
+            formats
+                .iter()
+                .all(|&f| self.adapter.get_texture_format_features(f).flags.sample_count_supported(samples))
+        } else {
+            matches!(samples, 1 | 4)
+        };
+        if supported {
+            Ok(())
+        } else {
+            Err(format!("{samples}x MSAA is not supported by this device"))
+        }
+    }
+
+    /// Apply render scale, dynamic resolution and MSAA settings.
+    pub fn set_resolution(&mut self, settings: ResolutionSettings) -> Result<(), String> {
+        settings.validate()?;
+        self.check_msaa_samples(settings.msaa_samples)?;
+        let samples_changed = settings.msaa_samples != self.resolution.msaa_samples;
+        self.resolution = settings;
+        self.dynamic_resolution.reset();
+        self.frame_timer.reset();
+        // Dynamic resolution starts from the scale in use
+        let scale = if settings.dynamic {
+            self.render_scale.clamp(settings.min_scale, settings.max_scale)
+        } else {
+            settings.scale
+        };
+        self.set_render_scale(scale);
+        if samples_changed {
+            self.rebuild_sampled_resources();
+        }
+        Ok(())
+    }
+
+    fn set_render_scale(&mut self, scale: f32) {
+        if scale != self.render_scale {
+            self.render_scale = scale;
+            self.resize_deferred_pipeline();
+        }
+    }
+
+    /// Feed the frame timer to the dynamic resolution controller, resizing
+    /// the deferred targets when it changes the render scale. Call before
+    /// the frame's first pass.
+    pub fn update_dynamic_resolution(&mut self) {
+        if !self.resolution.dynamic {
+            return;
+        }
+        let Some(frame_ms) = self.frame_timer.begin_frame(&self.device, &self.queue) else { return };
+        if let Some(scale) = self.dynamic_resolution.update(frame_ms, self.render_scale, &self.resolution) {
+            log::info!("Dynamic resolution: render scale {:.2} -> {:.2} ({frame_ms:.1} ms)", self.render_scale, scale);
+            self.set_render_scale(scale);
+        }
+    }
+
+    /// Recreate what depends on the output format or the MSAA sample count:
+    /// the forward, present and UI pipelines and the multisampled targets.
+    /// Falls back to 1 sample when the output format does not support the
+    /// current count.
+    fn rebuild_sampled_resources(&mut self) {
+        use crate::pipeline;
+
+        if let Err(e) = self.check_msaa_samples(self.resolution.msaa_samples) {
+            log::warn!("{e}; disabling MSAA");
+            self.resolution.msaa_samples = 1;
+        }
+        let samples = self.resolution.msaa_samples;
+        let format = self.surface_config.format;
+        let msaa = self.create_msaa_targets();
+        let Some(dp) = self.deferred.as_mut() else { return };
+        let device = &self.device;
+
+        dp.forward_pipeline = pipeline::create_forward_pipeline(
+            device,
+            &self.per_frame_bind_group_layout,
+            &self.material_bind_group_layout,
+            &dp.per_object_bgl,
+            &dp.forward_light_shadow_bgl,
+            samples,
+        );
+        dp.forward_skinned_pipeline = pipeline::create_skinned_forward_pipeline(
+            device,
+            &self.per_frame_bind_group_layout,
+            &self.material_bind_group_layout,
+            &dp.skinned_object_bgl,
+            &dp.forward_light_shadow_bgl,
+            samples,
+        );
+        dp.msaa_prime_pipeline =
+            (samples > 1).then(|| pipeline::create_msaa_prime_pipeline(device, &dp.msaa_prime_bgl, samples));
+        dp.present_pipeline = pipeline::create_present_pipeline(device, &dp.present_bgl, format, samples);
+        dp.ui_pipeline = pipeline::create_ui_pipeline(device, &dp.ui_bgl, format, samples);
+        dp.msaa = msaa;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bone_palette_packs_whole_column_major_matrices() {
+        let translate = glam::Mat4::from_translation(glam::Vec3::new(2.0, 3.0, 4.0));
+        let mut matrices = [glam::Mat4::IDENTITY.to_cols_array(), translate.to_cols_array()].concat();
+        matrices.extend([9.0; 5]);
+
+        let palette = bone_palette(&matrices).unwrap();
+        assert_eq!(palette.len(), 32);
+        assert_eq!(palette[28..32], [2.0, 3.0, 4.0, 1.0]);
+        assert_eq!(std::mem::size_of_val(palette) as u64, 2 * BONE_MATRIX_SIZE);
+        assert_eq!(std::mem::size_of::<[[f32; 4]; 4]>() as u64, BONE_MATRIX_SIZE);
+
+        assert!(bone_palette(&[1.0; 15]).is_err());
+    }
 }
@@ -0,0 +1,330 @@
+//! Cascaded shadow maps of the primary directional light. Cascades are fitted
+//! to the frame camera by `csm::fit_cascade` and rendered into the layers of
+//! one depth array; with EVSM filtering each layer is then warped into a
+//! moments array the lighting shaders filter linearly.
+
+use glam::{Mat4, Vec3};
+use openreality_gpu_shared::batching::group_instances;
+use openreality_gpu_shared::csm;
+use openreality_gpu_shared::math::{extract_frustum_planes, sphere_in_frustum};
+use openreality_gpu_shared::uniforms::{
+    CascadeData, CsmSettings, PerFrameUniforms, PerObjectUniforms, ShadowUniforms, MAX_PCF_SAMPLES, SHADOW_FILTER_EVSM,
+};
+
+use crate::backend::{GPUMesh, WGPUBackendState};
+use crate::instancing::{GpuCull, Instances};
+use crate::passes::shadow::{render_shadow_depth, ShadowCasters, ShadowTarget};
+use crate::render_targets::DEPTH_FORMAT;
+
+/// Most cascades `ShadowUniforms` holds.
+pub const MAX_CASCADES: u32 = 4;
+
+/// Format of the EVSM moments array.
+pub const MOMENTS_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// Blend of uniform and logarithmic cascade splits, as in Julia's
+/// `compute_cascade_splits`.
+pub const SPLIT_LAMBDA: f32 = 0.5;
+
+/// Largest EVSM exponent whose squared moments still fit in a half float.
+pub const MAX_EVSM_EXPONENT: f32 = 5.5;
+
+/// Cascade depth layers, EVSM moments and the settings they are filtered with.
+pub struct CascadedShadowMap {
+    /// One view per cascade, rendered into by the shadow pass.
+    pub layer_views: Vec<wgpu::TextureView>,
+    /// All cascades, as sampled by the lighting shaders.
+    pub depth_view: wgpu::TextureView,
+    /// EVSM moments of each cascade; a 1x1 placeholder until EVSM is selected.
+    pub moment_layer_views: Vec<wgpu::TextureView>,
+    pub moments_view: wgpu::TextureView,
+    /// 0 for the placeholder bound until `or_wgpu_create_csm`.
+    pub num_cascades: u32,
+    pub resolution: u32,
+    /// View distances the cascades are split between.
+    pub near: f32,
+    pub far: f32,
+    pub settings: CsmSettings,
+}
+
+impl CascadedShadowMap {
+    pub fn placeholder(device: &wgpu::Device) -> Self {
+        Self::new(device, 0, 1, 0.1, 100.0)
+    }
+
+    /// `num_cascades` layers of `resolution`² texels splitting `near..far`.
+    pub fn new(device: &wgpu::Device, num_cascades: u32, resolution: u32, near: f32, far: f32) -> Self {
+        let (layer_views, depth_view) = array_texture(device, "CSM Depth", DEPTH_FORMAT, resolution, num_cascades.max(1));
+        let (moment_layer_views, moments_view) = array_texture(device, "CSM Moments", MOMENTS_FORMAT, 1, 1);
+        Self {
+            layer_views,
+            depth_view,
+            moment_layer_views,
+            moments_view,
+            num_cascades,
+            resolution,
+            near,
+            far,
+            settings: CsmSettings::default(),
+        }
+    }
+
+    /// Clamp and store `settings`, creating the moments array the first time
+    /// EVSM is selected.
+    pub fn set_settings(&mut self, device: &wgpu::Device, mut settings: CsmSettings) {
+        settings.pcf_samples = settings.pcf_samples.clamp(1, MAX_PCF_SAMPLES);
+        settings.blend_band = settings.blend_band.clamp(0.0, 1.0);
+        settings.light_bleed_reduction = settings.light_bleed_reduction.clamp(0.0, 0.99);
+        for k in &mut settings.evsm_exponents {
+            *k = k.clamp(0.0, MAX_EVSM_EXPONENT);
+        }
+        self.settings = settings;
+
+        if settings.filter_mode == SHADOW_FILTER_EVSM && self.moment_layer_views.len() != self.layer_views.len() {
+            let layers = self.layer_views.len() as u32;
+            (self.moment_layer_views, self.moments_view) =
+                array_texture(device, "CSM Moments", MOMENTS_FORMAT, self.resolution, layers);
+        }
+    }
+}
+
+/// 2D array texture with a view per layer and one of the whole array. At
+/// least two layers are allocated: GL infers a texture's view dimension from
+/// its layer count, so a single layer would not bind as an array.
+fn array_texture(
+    device: &wgpu::Device,
+    label: &str,
+    format: wgpu::TextureFormat,
+    resolution: u32,
+    layers: u32,
+) -> (Vec<wgpu::TextureView>, wgpu::TextureView) {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d {
+            width: resolution,
+            height: resolution,
+            depth_or_array_layers: layers.max(2),
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    let layer_views = (0..layers)
+        .map(|layer| {
+            texture.create_view(&wgpu::TextureViewDescriptor {
+                label: Some(label),
+                dimension: Some(wgpu::TextureViewDimension::D2),
+                base_array_layer: layer,
+                array_layer_count: Some(1),
+                ..Default::default()
+            })
+        })
+        .collect();
+    let array_view = texture.create_view(&wgpu::TextureViewDescriptor {
+        label: Some(label),
+        dimension: Some(wgpu::TextureViewDimension::D2Array),
+        ..Default::default()
+    });
+    (layer_views, array_view)
+}
+
+/// Fit up to `num_cascades` cascades to the frame camera for a light shining
+/// along `light_dir`, upload `ShadowUniforms` and render every cascade's
+/// casters. With no cascades the uniforms still go up, turning shadows off.
+pub fn render_cascaded_shadows(
+    state: &mut WGPUBackendState,
+    mesh_handles: &[u64],
+    model_data: &[f32],
+    light_dir: Vec3,
+    num_cascades: u32,
+) -> Result<(), String> {
+    let dp = state.deferred.as_ref().ok_or("Deferred pipeline not created")?;
+    let map = &state.csm;
+    if map.num_cascades == 0 {
+        return Err("CSM not created".into());
+    }
+    let (view, projection) = state.frame_camera.ok_or("No camera set for this frame")?;
+
+    let n = num_cascades.min(map.num_cascades) as usize;
+    let splits = csm::cascade_splits(map.near, map.far, n, SPLIT_LAMBDA);
+    let mut uniforms: ShadowUniforms = bytemuck::Zeroable::zeroed();
+    uniforms.num_cascades = n as i32;
+    uniforms.settings = map.settings;
+    let mut view_projs = Vec::with_capacity(n);
+    for c in 0..n {
+        let corners = csm::frustum_slice_corners(&view, &projection, splits[c], splits[c + 1]);
+        let fit = csm::fit_cascade(&corners, light_dir, map.resolution, map.settings.stabilize != 0);
+        uniforms.cascades[c] = CascadeData {
+            light_view_proj: fit.view_proj.to_cols_array_2d(),
+            split_depth: splits[c + 1],
+            texel_size: fit.texel_size,
+            depth_range: fit.depth_range,
+            _pad: 0.0,
+        };
+        view_projs.push(fit.view_proj);
+    }
+    state.queue.write_buffer(&dp.shadow_uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
+
+    state.cull_stats.shadow_drawn = 0;
+    state.cull_stats.shadow_culled = 0;
+    if n == 0 {
+        return Ok(());
+    }
+
+    // One per-frame slot per cascade: queue writes are staged, so cascades
+    // cannot share a buffer region within one submission.
+    let slot = std::mem::size_of::<PerFrameUniforms>() as u64;
+    let frames: Vec<PerFrameUniforms> = view_projs
+        .iter()
+        .map(|view_proj| PerFrameUniforms {
+            view: Mat4::IDENTITY.to_cols_array_2d(),
+            projection: view_proj.to_cols_array_2d(),
+            inv_view_proj: [[0.0; 4]; 4],
+            camera_pos: [0.0; 4],
+            time: 0.0,
+            _pad1: 0.0,
+            _pad2: 0.0,
+            _pad3: 0.0,
+            _alignment_pad: [0.0; 8],
+        })
+        .collect();
+    let frame_buffer = state.device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Shadow Cascade Per-Frame"),
+        size: slot * frames.len() as u64,
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    state.queue.write_buffer(&frame_buffer, 0, bytemuck::cast_slice(&frames));
+
+    let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Shadow Encoder"),
+    });
+
+    for (c, view_proj) in view_projs.iter().enumerate() {
+        let per_frame_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Shadow Per-Frame BG"),
+            layout: &state.per_frame_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &frame_buffer,
+                    offset: c as u64 * slot,
+                    size: wgpu::BufferSize::new(slot),
+                }),
+            }],
+        });
+
+        // Collect entities for this cascade. Only the side planes cull, so
+        // casters between the light and the cascade still throw shadows.
+        let planes = extract_frustum_planes(view_proj);
+        let mut static_handles = Vec::new();
+        let mut static_meshes = Vec::new();
+        let mut static_objects = Vec::new();
+        let mut skinned_meshes = Vec::new();
+        for (i, &mesh_handle) in mesh_handles.iter().enumerate() {
+            let (Some(mesh), Some(cols)) = (state.meshes.get(mesh_handle), model_data.get(i * 16..i * 16 + 16)) else {
+                continue;
+            };
+            let model = Mat4::from_cols_slice(cols);
+            let skinned = mesh.skin.is_some();
+            // The GPU culls static instances itself; skinned meshes are never culled
+            if !state.gpu_culling {
+                if let Some(bounds) = mesh.cull_bounds(&model) {
+                    if !sphere_in_frustum(&planes[..4], bounds.center, bounds.radius) {
+                        state.cull_stats.shadow_culled += 1;
+                        continue;
+                    }
+                }
+            }
+            if skinned {
+                skinned_meshes.push((mesh_handle, mesh, model.to_cols_array_2d()));
+            } else {
+                static_handles.push(mesh_handle);
+                static_meshes.push(mesh);
+                static_objects.push(PerObjectUniforms {
+                    model: model.to_cols_array_2d(),
+                    normal_matrix_col0: [0.0; 4], // Not needed for depth-only
+                    normal_matrix_col1: [0.0; 4],
+                    normal_matrix_col2: [0.0; 4],
+                    _pad: [0.0; 4],
+                });
+            }
+        }
+        state.cull_stats.shadow_drawn += (static_objects.len() + skinned_meshes.len()) as u32;
+
+        let (batches, order) = group_instances(static_handles);
+        let instances: Vec<_> = order.iter().map(|&i| static_objects[i]).collect();
+        let bounds: Vec<_> = order.iter().map(|&i| static_meshes[i].bounds).collect();
+        let batch_meshes: Vec<&GPUMesh> = batches.iter().map(|b| static_meshes[b.representative]).collect();
+        let index_counts: Vec<u32> = batch_meshes.iter().map(|m| m.index_count).collect();
+        let gpu_cull = state.gpu_culling.then(|| GpuCull {
+            pipeline: &dp.instance_cull_pipeline,
+            bind_group_layout: &dp.instance_cull_bgl,
+            planes: &planes[..4],
+            bounds: &bounds,
+            index_counts: &index_counts,
+        });
+        let instance_draws = state.cascade_instances.get(c).prepare(
+            &state.device,
+            &state.queue,
+            &mut encoder,
+            &dp.instance_bgl,
+            Instances { objects: &instances, batches },
+            gpu_cull,
+        );
+
+        let label = format!("Shadow Cascade {c}");
+        render_shadow_depth(
+            &mut encoder,
+            &ShadowTarget {
+                label: &label,
+                view: &map.layer_views[c],
+                tile: None,
+                per_frame_bg: &per_frame_bg,
+            },
+            dp,
+            &ShadowCasters {
+                batches: &batch_meshes,
+                instances: &instance_draws,
+                skinned: &skinned_meshes,
+            },
+            &state.device,
+            &state.queue,
+        );
+    }
+
+    if map.settings.filter_mode == SHADOW_FILTER_EVSM {
+        for c in 0..n {
+            let bind_group = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("EVSM Moments BG"),
+                layout: &dp.evsm_moments_bgl,
+                entries: &[
+                    wgpu::BindGroupEntry { binding: 0, resource: dp.shadow_uniform_buffer.as_entire_binding() },
+                    wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&map.layer_views[c]) },
+                ],
+            });
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("EVSM Moments"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &map.moment_layer_views[c],
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                ..Default::default()
+            });
+            pass.set_pipeline(&dp.evsm_moments_pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
+    }
+
+    state.queue.submit(std::iter::once(encoder.finish()));
+    Ok(())
+}
@@ -0,0 +1,229 @@
+//! Sky and volumetric fog. The sky is baked from the atmosphere settings
+//! and the sun (the first directional light) whenever either changes, and
+//! feeds the background and IBL of the lighting and forward passes. Fog is
+//! injected into and integrated through a froxel volume each frame by the
+//! lighting pass; both passes then look it up per pixel.
+
+use glam::{Mat4, Vec3};
+use openreality_gpu_shared::atmosphere::{AtmosphereSettings, FogSettings, IrradianceSH, FOG_GRID};
+use openreality_gpu_shared::clustering::projection_depth_range;
+use openreality_gpu_shared::uniforms::{DirLightData, EnvironmentUniforms, FogUniforms};
+
+use crate::ibl;
+
+/// Face size of the baked sky cubemap.
+pub const SKY_CUBEMAP_SIZE: u32 = 64;
+
+/// Format of the fog scattering and integrated volumes.
+pub const FOG_VOLUME_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// Fog compute workgroup edge (`cs_main` of both fog passes is 8x8x1).
+pub const FOG_TILE: u32 = 8;
+
+/// Sun used for the sky when the scene has no directional light.
+const DEFAULT_SUN_DIRECTION: Vec3 = Vec3::new(0.3, 0.8, 0.5);
+
+/// Sun motion (cosine of the angle) and relative illuminance change below
+/// which the baked sky is kept.
+const REBAKE_COS: f32 = 0.999_96;
+const REBAKE_ILLUMINANCE: f32 = 0.01;
+
+/// Cap of the sun disk's radiance, leaving half-float headroom in the HDR
+/// target for lights added on top of it.
+const MAX_SUN_RADIANCE: f32 = 32768.0;
+
+/// Sky cubemap, fog volumes and the uniforms the passes read them with.
+pub struct Environment {
+    pub atmosphere: AtmosphereSettings,
+    pub fog: FogSettings,
+    /// Direction towards and illuminance of the sun, from the last light upload.
+    pub sun: Option<(Vec3, Vec3)>,
+    /// Sun the sky was last baked for; `None` when it needs baking.
+    baked_sun: Option<(Vec3, Vec3)>,
+    irradiance: IrradianceSH,
+    sky_mip_count: u32,
+    pub sky_view: wgpu::TextureView,
+    /// In-scattering and extinction per froxel, written by `fog_inject.wgsl`.
+    pub scattering_view: wgpu::TextureView,
+    /// Scattering and transmittance up to each froxel, written by `fog_integrate.wgsl`.
+    pub fog_volume_view: wgpu::TextureView,
+    /// `EnvironmentUniforms`.
+    pub uniform_buffer: wgpu::Buffer,
+    /// `FogUniforms`.
+    pub fog_buffer: wgpu::Buffer,
+    /// Linear clamp sampler of the sky and the fog volume.
+    pub sampler: wgpu::Sampler,
+}
+
+impl Environment {
+    /// No sky and no fog: 1x1 placeholders until they are enabled.
+    pub fn new(device: &wgpu::Device) -> Self {
+        let sky_view = device
+            .create_texture(&wgpu::TextureDescriptor {
+                label: Some("Sky Placeholder"),
+                size: wgpu::Extent3d { width: 1, height: 1, depth_or_array_layers: 6 },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba16Float,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            })
+            .create_view(&wgpu::TextureViewDescriptor {
+                dimension: Some(wgpu::TextureViewDimension::Cube),
+                ..Default::default()
+            });
+        let uniform = |label, size| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        };
+        Self {
+            atmosphere: AtmosphereSettings::default(),
+            fog: FogSettings::default(),
+            sun: None,
+            baked_sun: None,
+            irradiance: IrradianceSH { coeffs: [Vec3::ZERO; 9] },
+            sky_mip_count: 1,
+            sky_view,
+            scattering_view: fog_volume(device, "Fog Scattering Placeholder", [1, 1, 1]),
+            fog_volume_view: fog_volume(device, "Fog Volume Placeholder", [1, 1, 1]),
+            uniform_buffer: uniform("Environment Uniforms", std::mem::size_of::<EnvironmentUniforms>() as u64),
+            fog_buffer: uniform("Fog Uniforms", std::mem::size_of::<FogUniforms>() as u64),
+            sampler: device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("Environment Sampler"),
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                mipmap_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            }),
+        }
+    }
+
+    pub fn set_atmosphere(&mut self, settings: AtmosphereSettings) {
+        self.atmosphere = settings;
+        self.baked_sun = None;
+    }
+
+    /// Store `settings`, creating the froxel volumes the first time fog is enabled.
+    pub fn set_fog(&mut self, device: &wgpu::Device, settings: FogSettings) {
+        if settings.enabled != 0 && self.fog.enabled == 0 {
+            self.scattering_view = fog_volume(device, "Fog Scattering", FOG_GRID);
+            self.fog_volume_view = fog_volume(device, "Fog Volume", FOG_GRID);
+        }
+        self.fog = settings;
+    }
+
+    /// Take the sun from the first of `num_dir_lights` directional lights.
+    pub fn set_sun(&mut self, dir_lights: &[DirLightData; 4], num_dir_lights: i32) {
+        self.sun = (num_dir_lights > 0).then(|| {
+            let light = &dir_lights[0];
+            let direction = -Vec3::from_slice(&light.direction[..3]).normalize_or(Vec3::NEG_Y);
+            (direction, Vec3::from_slice(&light.color[..3]) * light.intensity)
+        });
+    }
+
+    pub fn has_sky(&self) -> bool {
+        self.atmosphere.enabled != 0
+    }
+
+    pub fn has_fog(&self) -> bool {
+        self.fog.enabled != 0
+    }
+
+    /// Rebake the sky if its settings or the sun changed, and write this
+    /// frame's uniforms for the camera of `or_wgpu_begin_frame`.
+    pub fn update(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, camera: Option<&(Mat4, Mat4)>) {
+        let (sun_dir, sun_illuminance) = self.sun.unwrap_or((DEFAULT_SUN_DIRECTION.normalize(), Vec3::ONE));
+        if self.has_sky() && !self.baked_sun.is_some_and(|baked| same_sun(baked, (sun_dir, sun_illuminance))) {
+            let (_, view, irradiance) = ibl::generate_procedural_sky_cubemap(
+                device,
+                queue,
+                SKY_CUBEMAP_SIZE,
+                &self.atmosphere,
+                sun_dir,
+                sun_illuminance,
+            );
+            self.sky_view = view;
+            self.irradiance = irradiance;
+            self.sky_mip_count = SKY_CUBEMAP_SIZE.ilog2() + 1;
+            self.baked_sun = Some((sun_dir, sun_illuminance));
+        }
+
+        // Fog spans the camera's near plane to its max distance
+        let (near, far) = match camera {
+            Some((_, projection)) => {
+                let near = projection_depth_range(projection).0.max(0.01);
+                (near, self.fog.max_distance.max(near * 2.0))
+            }
+            None => (0.1, self.fog.max_distance.max(0.2)),
+        };
+
+        // The disk's radiance spreads the sun's illuminance over its solid angle
+        let radius = self.atmosphere.sun_angular_radius;
+        let disk_radiance = if radius > 0.0 {
+            sun_illuminance * self.atmosphere.transmittance(0.0, sun_dir) * self.atmosphere.intensity
+                / (std::f32::consts::PI * radius * radius)
+        } else {
+            Vec3::ZERO
+        };
+        let disk_radiance = disk_radiance.min(Vec3::splat(MAX_SUN_RADIANCE));
+        let uniforms = EnvironmentUniforms {
+            irradiance_sh: self.irradiance.coeffs.map(|c| c.extend(0.0).to_array()),
+            sun_direction: sun_dir.extend(radius.cos()).to_array(),
+            sun_radiance: disk_radiance.extend(0.0).to_array(),
+            has_sky: self.has_sky() as i32,
+            fog_enabled: (self.has_fog() && camera.is_some()) as i32,
+            fog_near: near,
+            fog_far: far,
+            sky_mip_count: self.sky_mip_count as f32,
+            _pad1: 0.0,
+            _pad2: 0.0,
+            _pad3: 0.0,
+        };
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
+
+        if let Some((view, projection)) = camera.filter(|_| self.has_fog()) {
+            let fog = &self.fog;
+            let [r, g, b] = fog.albedo;
+            let params = FogUniforms {
+                inv_projection: projection.inverse().to_cols_array_2d(),
+                inv_view: view.inverse().to_cols_array_2d(),
+                grid: [FOG_GRID[0], FOG_GRID[1], FOG_GRID[2], 0],
+                albedo: [r, g, b, fog.ambient_intensity],
+                near,
+                far,
+                density: fog.density,
+                height_density: fog.height_density,
+                height_falloff: fog.height_falloff,
+                base_height: fog.base_height,
+                anisotropy: fog.anisotropy,
+                _pad1: 0.0,
+            };
+            queue.write_buffer(&self.fog_buffer, 0, bytemuck::bytes_of(&params));
+        }
+    }
+}
+
+fn same_sun(a: (Vec3, Vec3), b: (Vec3, Vec3)) -> bool {
+    let brightness = a.1.max_element().max(b.1.max_element()).max(1e-6);
+    a.0.dot(b.0) >= REBAKE_COS && (a.1 - b.1).abs().max_element() <= brightness * REBAKE_ILLUMINANCE
+}
+
+fn fog_volume(device: &wgpu::Device, label: &str, size: [u32; 3]) -> wgpu::TextureView {
+    device
+        .create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d { width: size[0], height: size[1], depth_or_array_layers: size[2] },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D3,
+            format: FOG_VOLUME_FORMAT,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::STORAGE_BINDING,
+            view_formats: &[],
+        })
+        .create_view(&wgpu::TextureViewDescriptor::default())
+}
@@ -0,0 +1,129 @@
+//! Exposure of the tone mapping pass. Fixed and manual exposure are written
+//! from the CPU; auto exposure meters the lit HDR image with the
+//! `luminance_histogram.wgsl` passes and adapts on the GPU, so the exposure
+//! never round-trips through the CPU.
+
+use std::time::Instant;
+
+use openreality_gpu_shared::post_process::{ExposureSettings, EXPOSURE_AUTO, HISTOGRAM_BINS};
+use openreality_gpu_shared::uniforms::{ExposureParams, ExposureState};
+
+use crate::pipeline;
+
+/// log2 of the luminance EV100 0 exposes as middle gray (log2(12.5 / 100)).
+const EV100_LOG_LUM_OFFSET: f32 = -3.0;
+
+/// Histogram workgroup edge (`cs_histogram` is 16x16).
+const HISTOGRAM_TILE: u32 = 16;
+
+pub struct AutoExposure {
+    pub bgl: wgpu::BindGroupLayout,
+    pub histogram_pipeline: wgpu::ComputePipeline,
+    pub average_pipeline: wgpu::ComputePipeline,
+    pub params_buffer: wgpu::Buffer,
+    pub histogram_buffer: wgpu::Buffer,
+    /// `ExposureState`, read by the tone mapping pass.
+    pub state_buffer: wgpu::Buffer,
+    /// Whether the last update metered the image; the first metered frame
+    /// jumps to its EV100 instead of adapting from a stale one.
+    metering: bool,
+    last_update: Option<Instant>,
+}
+
+impl AutoExposure {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let bgl = pipeline::create_exposure_bind_group_layout(device);
+        let storage = |label, size| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        };
+        Self {
+            histogram_pipeline: pipeline::create_exposure_pipeline(device, &bgl, "cs_histogram"),
+            average_pipeline: pipeline::create_exposure_pipeline(device, &bgl, "cs_average"),
+            params_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Exposure Params"),
+                size: std::mem::size_of::<ExposureParams>() as u64,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            // Zero-initialized, as `cs_average` expects; it clears the bins after reading them
+            histogram_buffer: storage("Luminance Histogram", HISTOGRAM_BINS as u64 * 4),
+            state_buffer: storage("Exposure State", std::mem::size_of::<ExposureState>() as u64),
+            bgl,
+            metering: false,
+            last_update: None,
+        }
+    }
+
+    /// Update the exposure for this frame, metering `hdr_view` (`size`
+    /// pixels) in auto mode.
+    pub fn update(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        hdr_view: &wgpu::TextureView,
+        size: (u32, u32),
+        settings: &ExposureSettings,
+    ) {
+        let now = Instant::now();
+        let delta_time = self.last_update.map_or(0.0, |t| now.duration_since(t).as_secs_f32());
+        self.last_update = Some(now);
+
+        if settings.mode != EXPOSURE_AUTO {
+            let state = ExposureState { ev100: settings.ev100, exposure: settings.exposure(settings.ev100) };
+            queue.write_buffer(&self.state_buffer, 0, bytemuck::bytes_of(&state));
+            self.metering = false;
+            return;
+        }
+
+        let (width, height) = size;
+        let min_log_lum = settings.min_ev100 + EV100_LOG_LUM_OFFSET;
+        let params = ExposureParams {
+            min_log_lum,
+            log_lum_range: settings.max_ev100 - settings.min_ev100,
+            delta_time,
+            speed_up: settings.speed_up,
+            speed_down: settings.speed_down,
+            min_ev100: settings.min_ev100,
+            max_ev100: settings.max_ev100,
+            compensation: settings.compensation,
+            pixel_count: width * height,
+            snap: !self.metering as u32,
+            _pad1: 0,
+            _pad2: 0,
+        };
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::bytes_of(&params));
+        self.metering = true;
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Exposure BG"),
+            layout: &self.bgl,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: self.params_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(hdr_view) },
+                wgpu::BindGroupEntry { binding: 2, resource: self.histogram_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 3, resource: self.state_buffer.as_entire_binding() },
+            ],
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Auto Exposure Encoder"),
+        });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Auto Exposure"),
+                timestamp_writes: None,
+            });
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.set_pipeline(&self.histogram_pipeline);
+            pass.dispatch_workgroups(width.div_ceil(HISTOGRAM_TILE), height.div_ceil(HISTOGRAM_TILE), 1);
+            pass.set_pipeline(&self.average_pipeline);
+            pass.dispatch_workgroups(1, 1, 1);
+        }
+        queue.submit(std::iter::once(encoder.finish()));
+    }
+}
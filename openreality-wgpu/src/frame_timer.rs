@@ -0,0 +1,125 @@
+//! Frame time measurement for dynamic resolution. With timestamp queries a
+//! frame is timed on the GPU from `begin_frame` to the present submission
+//! (which includes the gaps while the CPU records passes); one frame is in
+//! flight at a time and read back a few frames later. Without them it falls
+//! back to the CPU interval between frames, which can only be trusted when
+//! the GPU is the bottleneck.
+
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum QueryState {
+    Idle,
+    /// Start timestamp written; waiting for the present pass.
+    Recording,
+    /// End timestamp and resolve recorded; waiting for the submission.
+    Resolving,
+    /// Readback buffer mapping requested.
+    Reading,
+}
+
+struct Timestamps {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    /// Nanoseconds per timestamp tick.
+    period_ns: f32,
+    /// Outcome of mapping `readback_buffer`, set by the map callback.
+    map_result: Arc<Mutex<Option<Result<(), wgpu::BufferAsyncError>>>>,
+    state: QueryState,
+}
+
+pub struct FrameTimer {
+    timestamps: Option<Timestamps>,
+    last_frame: Option<Instant>,
+}
+
+impl FrameTimer {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let needed = wgpu::Features::TIMESTAMP_QUERY | wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS;
+        let timestamps = device.features().contains(needed).then(|| Timestamps {
+            query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("Frame Timestamps"),
+                ty: wgpu::QueryType::Timestamp,
+                count: 2,
+            }),
+            resolve_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Frame Timestamp Resolve"),
+                size: 16,
+                usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+            readback_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Frame Timestamp Readback"),
+                size: 16,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }),
+            period_ns: queue.get_timestamp_period(),
+            map_result: Arc::new(Mutex::new(None)),
+            state: QueryState::Idle,
+        });
+        Self { timestamps, last_frame: None }
+    }
+
+    /// Drop the CPU frame interval, e.g. after frames were not timed.
+    pub fn reset(&mut self) {
+        self.last_frame = None;
+    }
+
+    /// Start timing a frame. Returns the time of an earlier frame in
+    /// milliseconds when one has been read back.
+    pub fn begin_frame(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> Option<f32> {
+        let Some(ts) = self.timestamps.as_mut() else {
+            let now = Instant::now();
+            let interval = self.last_frame.map(|last| now.duration_since(last).as_secs_f32() * 1000.0);
+            self.last_frame = Some(now);
+            return interval;
+        };
+
+        let mut frame_ms = None;
+        if ts.state == QueryState::Reading {
+            device.poll(wgpu::Maintain::Poll);
+            match ts.map_result.lock().unwrap().take() {
+                Some(Ok(())) => {
+                    let ticks: [u64; 2] = bytemuck::pod_read_unaligned(&ts.readback_buffer.slice(..).get_mapped_range());
+                    ts.readback_buffer.unmap();
+                    ts.state = QueryState::Idle;
+                    frame_ms = Some(ticks[1].saturating_sub(ticks[0]) as f32 * ts.period_ns / 1.0e6);
+                }
+                Some(Err(_)) => ts.state = QueryState::Idle,
+                None => {}
+            }
+        }
+        // A frame that never reached present is timed again
+        if matches!(ts.state, QueryState::Idle | QueryState::Recording) {
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Frame Timer Begin"),
+            });
+            encoder.write_timestamp(&ts.query_set, 0);
+            queue.submit(std::iter::once(encoder.finish()));
+            ts.state = QueryState::Recording;
+        }
+        frame_ms
+    }
+
+    /// Record the end of the frame into the present encoder.
+    pub fn end_frame(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let Some(ts) = self.timestamps.as_mut().filter(|ts| ts.state == QueryState::Recording) else { return };
+        encoder.write_timestamp(&ts.query_set, 1);
+        encoder.resolve_query_set(&ts.query_set, 0..2, &ts.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(&ts.resolve_buffer, 0, &ts.readback_buffer, 0, 16);
+        ts.state = QueryState::Resolving;
+    }
+
+    /// Call after submitting the encoder passed to `end_frame`.
+    pub fn frame_submitted(&mut self) {
+        let Some(ts) = self.timestamps.as_mut().filter(|ts| ts.state == QueryState::Resolving) else { return };
+        let map_result = ts.map_result.clone();
+        ts.readback_buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            *map_result.lock().unwrap() = Some(result);
+        });
+        ts.state = QueryState::Reading;
+    }
+}
@@ -0,0 +1,756 @@
+//! Compute-shader particle emitters.
+//! Particle state lives entirely on the device: spawning, integration, the
+//! back-to-front bitonic sort and the indirect draw arguments are produced by
+//! `particle_compute.wgsl`. The CPU only advances emission counters.
+
+use crate::render_targets::{self, DEPTH_FORMAT, DEPTH_VALUES, HDR_FORMAT};
+use openreality_gpu_shared::scene_format::ParticleBlendMode;
+use openreality_gpu_shared::shaders;
+use openreality_gpu_shared::uniforms::{
+    ParticleDrawUniforms, ParticleEmitterParams, ParticleSimParams, ParticleSortParams,
+};
+
+/// Bytes per particle in the storage buffer (see `Particle` in particle_compute.wgsl).
+const PARTICLE_STRIDE: u64 = 48;
+
+/// Pipelines and layouts shared by all emitters. Created with the first emitter.
+pub struct GPUParticlePipelines {
+    pub sim_bgl: wgpu::BindGroupLayout,
+    pub sort_bgl: wgpu::BindGroupLayout,
+    pub scene_bgl: wgpu::BindGroupLayout,
+    pub draw_bgl: wgpu::BindGroupLayout,
+    pub render_bgl: wgpu::BindGroupLayout,
+    pub clear_pipeline: wgpu::ComputePipeline,
+    pub emit_pipeline: wgpu::ComputePipeline,
+    pub simulate_pipeline: wgpu::ComputePipeline,
+    pub sort_pipeline: wgpu::ComputePipeline,
+    pub indirect_pipeline: wgpu::ComputePipeline,
+    /// Render pipelines indexed by blend mode (alpha, additive, premultiplied).
+    pub render_pipelines: [wgpu::RenderPipeline; 3],
+    pub draw_buffer: wgpu::Buffer,
+    /// Bound when an emitter has no sprite texture (the view keeps its texture alive).
+    pub white_view: wgpu::TextureView,
+    pub sprite_sampler: wgpu::Sampler,
+    /// Byte stride between sort steps in an emitter's sort params buffer.
+    pub sort_params_stride: u64,
+}
+
+/// Storage sized by `max_particles`; reallocated when the capacity changes.
+struct ParticleStorage {
+    particles: wgpu::Buffer,
+    sort_keys: wgpu::Buffer,
+    sort_indices: wgpu::Buffer,
+    counter: wgpu::Buffer,
+    indirect: wgpu::Buffer,
+    sort_params: wgpu::Buffer,
+    /// Power-of-two sort range (>= max_particles).
+    sort_count: u32,
+    sort_steps: u32,
+}
+
+/// One GPU-simulated emitter. Addressed from Julia by handle.
+pub struct GPUParticleEmitter {
+    pub params: ParticleEmitterParams,
+    /// Column-major emitter world transform.
+    pub transform: [f32; 16],
+    pub active: bool,
+    pub emit_accumulator: f32,
+    pub pending_burst: u32,
+    pub frame: u32,
+    /// Sprite texture handle (0 = none).
+    pub texture: u64,
+    storage: ParticleStorage,
+    params_buffer: wgpu::Buffer,
+    sim_buffer: wgpu::Buffer,
+    /// Mesh-surface emission triangles (see `MeshSurfaceSampler::gpu_triangles`).
+    triangle_buffer: wgpu::Buffer,
+    sim_bind_group: wgpu::BindGroup,
+    sort_bind_group: wgpu::BindGroup,
+    render_bind_group: wgpu::BindGroup,
+}
+
+fn storage_entry(binding: u32, visibility: wgpu::ShaderStages, read_only: bool) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+fn uniform_entry(binding: u32, visibility: wgpu::ShaderStages, has_dynamic_offset: bool) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+fn texture_entry(binding: u32, visibility: wgpu::ShaderStages, sample_type: wgpu::TextureSampleType) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility,
+        ty: wgpu::BindingType::Texture {
+            sample_type,
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+        },
+        count: None,
+    }
+}
+
+impl GPUParticlePipelines {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let compute = wgpu::ShaderStages::COMPUTE;
+        let sim_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Particle Sim BGL"),
+            entries: &[
+                storage_entry(0, compute, false), // particles
+                storage_entry(1, compute, false), // sort keys
+                storage_entry(2, compute, false), // sort indices
+                storage_entry(3, compute, false), // alive counter
+                storage_entry(4, compute, false), // indirect draw args
+                uniform_entry(5, compute, false), // emitter params
+                uniform_entry(6, compute, false), // sim params
+                storage_entry(7, compute, true),  // mesh emission triangles
+            ],
+        });
+        let sort_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Particle Sort BGL"),
+            entries: &[uniform_entry(0, compute, true)],
+        });
+        // G-buffer depth + normals for depth-buffer collisions
+        let scene_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Particle Scene BGL"),
+            entries: &[
+                texture_entry(0, compute, DEPTH_VALUES),
+                texture_entry(1, compute, wgpu::TextureSampleType::Float { filterable: false }),
+            ],
+        });
+        let fragment = wgpu::ShaderStages::FRAGMENT;
+        let draw_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Particle Draw BGL"),
+            entries: &[
+                uniform_entry(0, wgpu::ShaderStages::VERTEX | fragment, false),
+                texture_entry(1, fragment, DEPTH_VALUES), // soft particles
+            ],
+        });
+        let render_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Particle Render BGL"),
+            entries: &[
+                storage_entry(0, wgpu::ShaderStages::VERTEX, true),
+                storage_entry(1, wgpu::ShaderStages::VERTEX, true),
+                uniform_entry(2, wgpu::ShaderStages::VERTEX | fragment, false),
+                texture_entry(3, fragment, wgpu::TextureSampleType::Float { filterable: true }),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: fragment,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let compute_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Particle Compute"),
+            source: wgpu::ShaderSource::Wgsl(shaders::PARTICLE_COMPUTE_SHADER.into()),
+        });
+        let compute_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Particle Compute Layout"),
+            bind_group_layouts: &[&sim_bgl, &sort_bgl, &scene_bgl],
+            push_constant_ranges: &[],
+        });
+        let compute_pipeline = |entry_point: &str| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&compute_layout),
+                module: &compute_module,
+                entry_point: Some(entry_point),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                cache: None,
+            })
+        };
+
+        let render_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("GPU Particle Shader"),
+            source: wgpu::ShaderSource::Wgsl(shaders::GPU_PARTICLE_SHADER.into()),
+        });
+        let render_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("GPU Particle Pipeline Layout"),
+            bind_group_layouts: &[&draw_bgl, &render_bgl],
+            push_constant_ranges: &[],
+        });
+        let render_pipeline = |blend_mode: u32| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("GPU Particle Pipeline"),
+                layout: Some(&render_layout),
+                vertex: wgpu::VertexState {
+                    module: &render_module,
+                    entry_point: Some("vs_main"),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    buffers: &[], // Quads are expanded from vertex_index
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &render_module,
+                    entry_point: Some("fs_main"),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: HDR_FORMAT,
+                        blend: Some(crate::pipeline::particle_blend_state(blend_mode)),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    cull_mode: None,
+                    ..Default::default()
+                },
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: DEPTH_FORMAT,
+                    depth_write_enabled: false,
+                    depth_compare: wgpu::CompareFunction::Less,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+                cache: None,
+            })
+        };
+        let draw_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("GPU Particle Draw Uniforms"),
+            size: std::mem::size_of::<ParticleDrawUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let (_, white_view) = render_targets::create_default_texture(device, queue);
+        let sprite_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Particle Sprite Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let alignment = device.limits().min_uniform_buffer_offset_alignment as u64;
+        let sort_params_stride = (std::mem::size_of::<ParticleSortParams>() as u64).div_ceil(alignment) * alignment;
+
+        Self {
+            clear_pipeline: compute_pipeline("cs_clear"),
+            emit_pipeline: compute_pipeline("cs_emit"),
+            simulate_pipeline: compute_pipeline("cs_simulate"),
+            sort_pipeline: compute_pipeline("cs_sort"),
+            indirect_pipeline: compute_pipeline("cs_write_indirect"),
+            render_pipelines: [0, 1, 2].map(render_pipeline),
+            sim_bgl,
+            sort_bgl,
+            scene_bgl,
+            draw_bgl,
+            render_bgl,
+            draw_buffer,
+            white_view,
+            sprite_sampler,
+            sort_params_stride,
+        }
+    }
+
+    /// Bind the G-buffer depth and normals read by depth-buffer collisions.
+    pub fn scene_bind_group(
+        &self,
+        device: &wgpu::Device,
+        depth_view: &wgpu::TextureView,
+        normal_view: &wgpu::TextureView,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Particle Scene BG"),
+            layout: &self.scene_bgl,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(depth_view) },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(normal_view) },
+            ],
+        })
+    }
+}
+
+impl ParticleStorage {
+    fn new(device: &wgpu::Device, queue: &wgpu::Queue, pipelines: &GPUParticlePipelines, max_particles: u32) -> Self {
+        use wgpu::util::DeviceExt;
+
+        // Bitonic sorting needs a power-of-two key range
+        let sort_count = max_particles.next_power_of_two();
+        let storage = |label: &str, size: u64| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        };
+        // Zero-initialized, so every particle starts dead
+        let particles = storage("Particle Buffer", max_particles as u64 * PARTICLE_STRIDE);
+        let sort_keys = storage("Particle Sort Keys", sort_count as u64 * 4);
+        let sort_indices = storage("Particle Sort Indices", sort_count as u64 * 4);
+        let counter = storage("Particle Alive Counter", 4);
+        let indirect = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Particle Indirect Args"),
+            size: std::mem::size_of::<wgpu::util::DrawIndirectArgs>() as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::INDIRECT,
+            mapped_at_creation: false,
+        });
+
+        let steps = bitonic_steps(sort_count);
+        let sort_steps = steps.len() as u32;
+        let sort_data = pack_sort_params(&steps, pipelines.sort_params_stride as usize);
+        let sort_params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Particle Sort Params"),
+            contents: &sort_data,
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        // Draw nothing until the first simulation step writes real arguments
+        queue.write_buffer(&counter, 0, bytemuck::bytes_of(&0u32));
+
+        Self { particles, sort_keys, sort_indices, counter, indirect, sort_params, sort_count, sort_steps }
+    }
+}
+
+impl GPUParticleEmitter {
+    /// Allocate device buffers for `params.max_particles` particles (all dead).
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        pipelines: &GPUParticlePipelines,
+        mut params: ParticleEmitterParams,
+    ) -> Result<Self, String> {
+        use wgpu::util::DeviceExt;
+
+        if params.max_particles == 0 {
+            return Err("Particle emitter needs max_particles > 0".into());
+        }
+        // Mesh and texture bindings are attached separately
+        params.mesh_triangle_count = 0;
+        params.has_texture = 0;
+
+        let storage = ParticleStorage::new(device, queue, pipelines, params.max_particles);
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Particle Emitter Params"),
+            contents: bytemuck::bytes_of(&params),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let sim_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Particle Sim Params"),
+            size: std::mem::size_of::<ParticleSimParams>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let triangle_buffer = create_triangle_buffer(device, &[]);
+
+        let sim_bind_group = create_sim_bind_group(device, pipelines, &storage, &params_buffer, &sim_buffer, &triangle_buffer);
+        let sort_bind_group = create_sort_bind_group(device, pipelines, &storage);
+        let render_bind_group = create_render_bind_group(device, pipelines, &storage, &params_buffer, &pipelines.white_view);
+
+        Ok(Self {
+            params,
+            transform: glam::Mat4::IDENTITY.to_cols_array(),
+            active: true,
+            emit_accumulator: 0.0,
+            pending_burst: 0,
+            frame: 0,
+            texture: 0,
+            storage,
+            params_buffer,
+            sim_buffer,
+            triangle_buffer,
+            sim_bind_group,
+            sort_bind_group,
+            render_bind_group,
+        })
+    }
+
+    /// Replace the emitter settings. A new `max_particles` reallocates (and
+    /// clears) the particle storage; otherwise live particles are kept.
+    /// `sprite_view` is the currently attached texture, if any.
+    pub fn set_params(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        pipelines: &GPUParticlePipelines,
+        mut params: ParticleEmitterParams,
+        sprite_view: Option<&wgpu::TextureView>,
+    ) -> Result<(), String> {
+        if params.max_particles == 0 {
+            return Err("Particle emitter needs max_particles > 0".into());
+        }
+        params.mesh_triangle_count = self.params.mesh_triangle_count;
+        params.has_texture = self.params.has_texture;
+
+        if params.max_particles != self.params.max_particles {
+            self.storage = ParticleStorage::new(device, queue, pipelines, params.max_particles);
+            self.sim_bind_group = create_sim_bind_group(
+                device, pipelines, &self.storage, &self.params_buffer, &self.sim_buffer, &self.triangle_buffer,
+            );
+            self.sort_bind_group = create_sort_bind_group(device, pipelines, &self.storage);
+            let sprite = sprite_view.unwrap_or(&pipelines.white_view);
+            self.render_bind_group = create_render_bind_group(device, pipelines, &self.storage, &self.params_buffer, sprite);
+        }
+        self.params = params;
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::bytes_of(&self.params));
+        Ok(())
+    }
+
+    /// Upload mesh-surface emission triangles (three vec4 per triangle).
+    pub fn set_mesh_triangles(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        pipelines: &GPUParticlePipelines,
+        triangles: &[[f32; 4]],
+    ) {
+        self.triangle_buffer = create_triangle_buffer(device, triangles);
+        self.sim_bind_group = create_sim_bind_group(
+            device, pipelines, &self.storage, &self.params_buffer, &self.sim_buffer, &self.triangle_buffer,
+        );
+        self.params.mesh_triangle_count = (triangles.len() / 3) as u32;
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::bytes_of(&self.params));
+    }
+
+    /// Attach a sprite texture (`None` restores the procedural round sprite).
+    pub fn set_texture(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        pipelines: &GPUParticlePipelines,
+        handle: u64,
+        view: Option<&wgpu::TextureView>,
+    ) {
+        self.texture = if view.is_some() { handle } else { 0 };
+        let sprite = view.unwrap_or(&pipelines.white_view);
+        self.render_bind_group = create_render_bind_group(device, pipelines, &self.storage, &self.params_buffer, sprite);
+        self.params.has_texture = view.is_some() as u32;
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::bytes_of(&self.params));
+    }
+
+    /// Advance the emission accumulator and upload this frame's sim params.
+    /// Returns the number of particles to spawn.
+    fn prepare_frame(&mut self, queue: &wgpu::Queue, dt: f32, camera: &ParticleCamera) -> u32 {
+        let mut spawn = std::mem::take(&mut self.pending_burst);
+        if self.active && self.params.emission_rate > 0.0 {
+            self.emit_accumulator += self.params.emission_rate * dt;
+            let whole = self.emit_accumulator.floor();
+            self.emit_accumulator -= whole;
+            spawn += whole as u32;
+        }
+        spawn = spawn.min(self.params.max_particles);
+        self.frame = self.frame.wrapping_add(1);
+
+        let sim = sim_params(&self.transform, camera, dt, spawn, self.frame, self.storage.sort_count);
+        queue.write_buffer(&self.sim_buffer, 0, bytemuck::bytes_of(&sim));
+        spawn
+    }
+
+    /// Record emission, integration, sorting and indirect-args generation.
+    /// `scene_bind_group` comes from `GPUParticlePipelines::scene_bind_group`.
+    pub fn record_simulation(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        queue: &wgpu::Queue,
+        pipelines: &GPUParticlePipelines,
+        scene_bind_group: &wgpu::BindGroup,
+        dt: f32,
+        camera: &ParticleCamera,
+    ) {
+        let spawn = self.prepare_frame(queue, dt, camera);
+        let sort_count = self.storage.sort_count;
+
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Particle Simulation"),
+            timestamp_writes: None,
+        });
+        pass.set_bind_group(0, &self.sim_bind_group, &[]);
+        pass.set_bind_group(1, &self.sort_bind_group, &[0]);
+        pass.set_bind_group(2, scene_bind_group, &[]);
+
+        pass.set_pipeline(&pipelines.clear_pipeline);
+        pass.dispatch_workgroups(sort_count.div_ceil(256), 1, 1);
+
+        if spawn > 0 {
+            pass.set_pipeline(&pipelines.emit_pipeline);
+            pass.dispatch_workgroups(spawn.div_ceil(64), 1, 1);
+        }
+
+        pass.set_pipeline(&pipelines.simulate_pipeline);
+        pass.dispatch_workgroups(self.params.max_particles.div_ceil(256), 1, 1);
+
+        // Additive blending is order-independent, so those emitters skip the sort
+        if self.params.blend_mode != ParticleBlendMode::Additive as u32 {
+            pass.set_pipeline(&pipelines.sort_pipeline);
+            for step in 0..self.storage.sort_steps {
+                let offset = (step as u64 * pipelines.sort_params_stride) as u32;
+                pass.set_bind_group(1, &self.sort_bind_group, &[offset]);
+                pass.dispatch_workgroups(sort_count.div_ceil(256), 1, 1);
+            }
+        }
+
+        pass.set_pipeline(&pipelines.indirect_pipeline);
+        pass.dispatch_workgroups(1, 1, 1);
+    }
+}
+
+/// Every (j, k) step of the bitonic network over `sort_count` keys, in dispatch order.
+fn bitonic_steps(sort_count: u32) -> Vec<ParticleSortParams> {
+    let mut steps = Vec::new();
+    let mut k = 2;
+    while k <= sort_count {
+        let mut j = k / 2;
+        while j > 0 {
+            steps.push(ParticleSortParams { j, k, _pad1: 0, _pad2: 0 });
+            j /= 2;
+        }
+        k *= 2;
+    }
+    steps
+}
+
+/// Sort steps at `stride`-aligned offsets for the dynamic uniform binding;
+/// at least one slot, since the buffer is bound even when nothing sorts.
+fn pack_sort_params(steps: &[ParticleSortParams], stride: usize) -> Vec<u8> {
+    let mut data = vec![0u8; steps.len().max(1) * stride];
+    for (slot, step) in data.chunks_mut(stride).zip(steps) {
+        slot[..std::mem::size_of::<ParticleSortParams>()].copy_from_slice(bytemuck::bytes_of(step));
+    }
+    data
+}
+
+/// Simulation inputs of frame `frame` for an emitter at `transform`.
+fn sim_params(
+    transform: &[f32; 16],
+    camera: &ParticleCamera,
+    dt: f32,
+    spawn_count: u32,
+    frame: u32,
+    sort_count: u32,
+) -> ParticleSimParams {
+    ParticleSimParams {
+        emitter_transform: glam::Mat4::from_cols_array(transform).to_cols_array_2d(),
+        view_proj: camera.view_proj.to_cols_array_2d(),
+        inv_view_proj: camera.view_proj.inverse().to_cols_array_2d(),
+        camera_pos: camera.position.extend(1.0).to_array(),
+        screen_size: camera.screen_size,
+        dt,
+        spawn_count,
+        seed: frame.wrapping_mul(0x9E37_79B9),
+        sort_count,
+        _pad1: 0,
+        _pad2: 0,
+    }
+}
+
+/// Camera inputs to a simulation step.
+pub struct ParticleCamera {
+    pub view_proj: glam::Mat4,
+    pub position: glam::Vec3,
+    /// Size of the G-buffer sampled by depth collisions.
+    pub screen_size: [f32; 2],
+}
+
+fn create_triangle_buffer(device: &wgpu::Device, triangles: &[[f32; 4]]) -> wgpu::Buffer {
+    use wgpu::util::DeviceExt;
+
+    // Storage bindings can't be empty; one zeroed triangle stands in for "no mesh"
+    let placeholder = [[0.0f32; 4]; 3];
+    let contents: &[[f32; 4]] = if triangles.is_empty() { &placeholder } else { triangles };
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Particle Emission Triangles"),
+        contents: bytemuck::cast_slice(contents),
+        usage: wgpu::BufferUsages::STORAGE,
+    })
+}
+
+fn create_sim_bind_group(
+    device: &wgpu::Device,
+    pipelines: &GPUParticlePipelines,
+    storage: &ParticleStorage,
+    params_buffer: &wgpu::Buffer,
+    sim_buffer: &wgpu::Buffer,
+    triangle_buffer: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Particle Sim BG"),
+        layout: &pipelines.sim_bgl,
+        entries: &[
+            wgpu::BindGroupEntry { binding: 0, resource: storage.particles.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: 1, resource: storage.sort_keys.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: 2, resource: storage.sort_indices.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: 3, resource: storage.counter.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: 4, resource: storage.indirect.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: 5, resource: params_buffer.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: 6, resource: sim_buffer.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: 7, resource: triangle_buffer.as_entire_binding() },
+        ],
+    })
+}
+
+fn create_sort_bind_group(
+    device: &wgpu::Device,
+    pipelines: &GPUParticlePipelines,
+    storage: &ParticleStorage,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Particle Sort BG"),
+        layout: &pipelines.sort_bgl,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                buffer: &storage.sort_params,
+                offset: 0,
+                size: wgpu::BufferSize::new(std::mem::size_of::<ParticleSortParams>() as u64),
+            }),
+        }],
+    })
+}
+
+fn create_render_bind_group(
+    device: &wgpu::Device,
+    pipelines: &GPUParticlePipelines,
+    storage: &ParticleStorage,
+    params_buffer: &wgpu::Buffer,
+    sprite_view: &wgpu::TextureView,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Particle Render BG"),
+        layout: &pipelines.render_bgl,
+        entries: &[
+            wgpu::BindGroupEntry { binding: 0, resource: storage.particles.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: 1, resource: storage.sort_indices.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: 2, resource: params_buffer.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::TextureView(sprite_view) },
+            wgpu::BindGroupEntry { binding: 4, resource: wgpu::BindingResource::Sampler(&pipelines.sprite_sampler) },
+        ],
+    })
+}
+
+/// Draw every emitter into `color_view`. `depth_view` is attached read-only,
+/// which lets the same texture be sampled for soft particles. The caller
+/// uploads `pipelines.draw_buffer` beforehand.
+pub fn render_gpu_particles<'a>(
+    encoder: &mut wgpu::CommandEncoder,
+    device: &wgpu::Device,
+    color_view: &wgpu::TextureView,
+    depth_view: &wgpu::TextureView,
+    pipelines: &GPUParticlePipelines,
+    emitters: impl Iterator<Item = &'a GPUParticleEmitter>,
+) {
+    let draw_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("GPU Particle Draw BG"),
+        layout: &pipelines.draw_bgl,
+        entries: &[
+            wgpu::BindGroupEntry { binding: 0, resource: pipelines.draw_buffer.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(depth_view) },
+        ],
+    });
+
+    let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("GPU Particle Pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: color_view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+            view: depth_view,
+            depth_ops: None, // Read-only depth
+            stencil_ops: None,
+        }),
+        ..Default::default()
+    });
+
+    pass.set_bind_group(0, &draw_bind_group, &[]);
+    for emitter in emitters {
+        let mode = (emitter.params.blend_mode as usize).min(pipelines.render_pipelines.len() - 1);
+        pass.set_pipeline(&pipelines.render_pipelines[mode]);
+        pass.set_bind_group(1, &emitter.render_bind_group, &[]);
+        pass.draw_indirect(&emitter.storage.indirect, 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::{offset_of, size_of};
+
+    #[test]
+    fn test_bitonic_schedule_covers_power_of_two_range() {
+        assert_eq!(5u32.next_power_of_two(), 8);
+        let steps: Vec<_> = bitonic_steps(8).iter().map(|s| (s.k, s.j)).collect();
+        assert_eq!(steps, [(2, 1), (4, 2), (4, 1), (8, 4), (8, 2), (8, 1)]);
+
+        // log2(n) * (log2(n) + 1) / 2 steps
+        assert_eq!(bitonic_steps(1024).len(), 55);
+        assert!(bitonic_steps(1).is_empty());
+    }
+
+    #[test]
+    fn test_sort_params_are_stride_aligned() {
+        let steps = bitonic_steps(4);
+        let data = pack_sort_params(&steps, 256);
+        assert_eq!(data.len(), 3 * 256);
+        for (i, step) in steps.iter().enumerate() {
+            let slot = &data[i * 256..(i + 1) * 256];
+            assert_eq!(&slot[..size_of::<ParticleSortParams>()], bytemuck::bytes_of(step));
+            assert!(slot[size_of::<ParticleSortParams>()..].iter().all(|&b| b == 0));
+        }
+        // Nothing to sort still leaves one bindable slot
+        assert_eq!(pack_sort_params(&[], 256).len(), 256);
+    }
+
+    #[test]
+    fn test_sim_params_match_wgsl_layout() {
+        // Offsets of `SimParams` in particle_compute.wgsl
+        assert_eq!(offset_of!(ParticleSimParams, camera_pos), 192);
+        assert_eq!(offset_of!(ParticleSimParams, screen_size), 208);
+        assert_eq!(offset_of!(ParticleSimParams, dt), 216);
+        assert_eq!(offset_of!(ParticleSimParams, sort_count), 228);
+        assert_eq!(size_of::<ParticleSimParams>(), 240);
+
+        let camera = ParticleCamera {
+            view_proj: glam::Mat4::from_scale(glam::Vec3::splat(2.0)),
+            position: glam::Vec3::new(1.0, 2.0, 3.0),
+            screen_size: [1280.0, 720.0],
+        };
+        let transform = glam::Mat4::from_translation(glam::Vec3::X).to_cols_array();
+        let sim = sim_params(&transform, &camera, 0.5, 7, 3, 64);
+        assert_eq!(sim.emitter_transform[3], [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(sim.inv_view_proj[0][0], 0.5);
+        assert_eq!(sim.camera_pos, [1.0, 2.0, 3.0, 1.0]);
+        assert_eq!((sim.dt, sim.spawn_count, sim.sort_count), (0.5, 7, 64));
+        assert_ne!(sim.seed, sim_params(&transform, &camera, 0.5, 7, 4, 64).seed);
+    }
+
+    #[test]
+    fn test_indirect_args_match_draw_indirect_layout() {
+        // `cs_write_indirect` writes six vertices per alive particle instance
+        let args = wgpu::util::DrawIndirectArgs { vertex_count: 6, instance_count: 3, first_vertex: 0, first_instance: 0 };
+        assert_eq!(args.as_bytes(), bytemuck::cast_slice::<u32, u8>(&[6, 3, 0, 0]));
+        assert_eq!(size_of::<wgpu::util::DrawIndirectArgs>(), 16);
+
+        let wgsl = shaders::PARTICLE_COMPUTE_SHADER;
+        let start = wgsl.find("struct DrawIndirectArgs {").unwrap();
+        let fields: Vec<_> = wgsl[start..wgsl[start..].find("};").unwrap() + start]
+            .lines()
+            .skip(1)
+            .filter_map(|l| l.trim().split(':').next().filter(|f| !f.is_empty()))
+            .collect();
+        assert_eq!(fields, ["vertex_count", "instance_count", "first_vertex", "first_instance"]);
+    }
+}
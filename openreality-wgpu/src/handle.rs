@@ -43,6 +43,11 @@ impl<T> HandleStore<T> {
         self.items.iter()
     }
 
+    /// Iterate mutably over all items.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&u64, &mut T)> {
+        self.items.iter_mut()
+    }
+
     /// Number of stored items.
     pub fn len(&self) -> usize {
         self.items.len()
@@ -2,6 +2,10 @@
 //! Generates a procedural sky cubemap and computes irradiance/prefilter maps.
 //! This is a simplified version — for production, accept pre-processed HDR cubemaps.
 
+use glam::Vec3;
+use openreality_gpu_shared::atmosphere::{self, AtmosphereSettings, IrradianceSH};
+use openreality_gpu_shared::display::f32_to_f16;
+
 /// IBL environment state.
 pub struct IBLEnvironment {
     pub irradiance_cubemap: wgpu::Texture,
@@ -72,12 +76,19 @@ pub fn generate_brdf_lut(device: &wgpu::Device, queue: &wgpu::Queue) -> (wgpu::T
     (texture, view)
 }
 
-/// Generate a simple procedural sky cubemap (gradient sky).
+/// Bake the atmosphere's sky for a sun of `sun_illuminance` shining from
+/// `sun_dir` into an `Rgba16Float` cubemap of `size`² faces. Mips are
+/// box-filtered down to 1x1 so specular IBL can sample blurrier mips for
+/// rougher surfaces. Also returns the sky's irradiance for diffuse IBL.
 pub fn generate_procedural_sky_cubemap(
     device: &wgpu::Device,
     queue: &wgpu::Queue,
     size: u32,
-) -> (wgpu::Texture, wgpu::TextureView) {
+    settings: &AtmosphereSettings,
+    sun_dir: Vec3,
+    sun_illuminance: Vec3,
+) -> (wgpu::Texture, wgpu::TextureView, IrradianceSH) {
+    let mip_count = size.ilog2() + 1;
     let texture = device.create_texture(&wgpu::TextureDescriptor {
         label: Some("Procedural Sky Cubemap"),
         size: wgpu::Extent3d {
@@ -85,71 +96,45 @@ pub fn generate_procedural_sky_cubemap(
             height: size,
             depth_or_array_layers: 6,
         },
-        mip_level_count: 1,
+        mip_level_count: mip_count,
         sample_count: 1,
         dimension: wgpu::TextureDimension::D2,
-        format: wgpu::TextureFormat::Rgba8Unorm,
+        format: wgpu::TextureFormat::Rgba16Float,
         usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
         view_formats: &[],
     });
 
-    // Simple gradient: sky blue at top, horizon white, ground dark
-    let mut face_data = vec![0u8; (size * size * 4) as usize];
-
-    for face in 0..6u32 {
-        for y in 0..size {
-            for x in 0..size {
-                let dir = cubemap_direction(face, x, y, size);
-                let up = dir[1]; // Y component
-
-                // Sky gradient
-                let (r, g, b) = if up > 0.0 {
-                    // Sky: lerp from horizon white to zenith blue
-                    let t = up;
-                    (
-                        lerp(0.8, 0.3, t),
-                        lerp(0.85, 0.5, t),
-                        lerp(0.9, 0.9, t),
-                    )
-                } else {
-                    // Ground: dark gray
-                    let t = (-up).min(1.0);
-                    (
-                        lerp(0.5, 0.2, t),
-                        lerp(0.5, 0.2, t),
-                        lerp(0.5, 0.2, t),
-                    )
-                };
-
-                let idx = ((y * size + x) * 4) as usize;
-                face_data[idx] = (r * 255.0) as u8;
-                face_data[idx + 1] = (g * 255.0) as u8;
-                face_data[idx + 2] = (b * 255.0) as u8;
-                face_data[idx + 3] = 255;
-            }
-        }
+    let sky = atmosphere::bake_sky_cubemap(settings, sun_dir, sun_illuminance, size);
+    let irradiance = IrradianceSH::from_cubemap(&sky, size);
 
+    let mut texels = sky;
+    for mip in 0..mip_count {
+        let mip_size = (size >> mip).max(1);
+        if mip > 0 {
+            texels = atmosphere::downsample_cubemap(&texels, (size >> (mip - 1)).max(1));
+        }
+        let data: Vec<u16> = texels
+            .iter()
+            .flat_map(|t| [t.x, t.y, t.z, 1.0])
+            .map(|v| f32_to_f16(v.min(65504.0)))
+            .collect();
         queue.write_texture(
             wgpu::ImageCopyTexture {
                 texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d {
-                    x: 0,
-                    y: 0,
-                    z: face,
-                },
+                mip_level: mip,
+                origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
             },
-            &face_data,
+            bytemuck::cast_slice(&data),
             wgpu::ImageDataLayout {
                 offset: 0,
-                bytes_per_row: Some(4 * size),
-                rows_per_image: Some(size),
+                bytes_per_row: Some(8 * mip_size),
+                rows_per_image: Some(mip_size),
             },
             wgpu::Extent3d {
-                width: size,
-                height: size,
-                depth_or_array_layers: 1,
+                width: mip_size,
+                height: mip_size,
+                depth_or_array_layers: 6,
             },
         );
     }
@@ -159,33 +144,11 @@ pub fn generate_procedural_sky_cubemap(
         ..Default::default()
     });
 
-    (texture, view)
+    (texture, view, irradiance)
 }
 
 // ---- Internal helpers ----
 
-fn lerp(a: f32, b: f32, t: f32) -> f32 {
-    a + (b - a) * t
-}
-
-/// Get the world-space direction for a cubemap texel.
-fn cubemap_direction(face: u32, x: u32, y: u32, size: u32) -> [f32; 3] {
-    let u = (x as f32 + 0.5) / size as f32 * 2.0 - 1.0;
-    let v = (y as f32 + 0.5) / size as f32 * 2.0 - 1.0;
-
-    let dir = match face {
-        0 => [1.0, -v, -u],   // +X
-        1 => [-1.0, -v, u],   // -X
-        2 => [u, 1.0, v],     // +Y
-        3 => [u, -1.0, -v],   // -Y
-        4 => [u, -v, 1.0],    // +Z
-        _ => [-u, -v, -1.0],  // -Z
-    };
-
-    let len = (dir[0] * dir[0] + dir[1] * dir[1] + dir[2] * dir[2]).sqrt();
-    [dir[0] / len, dir[1] / len, dir[2] / len]
-}
-
 /// Numerically integrate the BRDF split-sum for a given NdotV and roughness.
 fn integrate_brdf(n_dot_v: f32, roughness: f32) -> (f32, f32) {
     let v = [
@@ -0,0 +1,306 @@
+//! Instanced drawing of static meshes.
+//! Entities that share a mesh and material are batched into one draw whose
+//! matrices come from a per-instance storage buffer. With GPU culling enabled,
+//! `instance_cull.wgsl` compacts the visible instances of every batch and
+//! writes the `draw_indexed_indirect` arguments, so the CPU never sees the
+//! surviving counts.
+
+use openreality_gpu_shared::batching::InstanceBatch;
+use openreality_gpu_shared::math::BoundingSphere;
+use openreality_gpu_shared::uniforms::{CullInstance, CullParams, DrawIndexedIndirectArgs, PerObjectUniforms};
+
+/// Threads per workgroup in `instance_cull.wgsl`.
+const CULL_WORKGROUP_SIZE: u32 = 64;
+
+/// Inputs of the GPU culling pass.
+pub struct GpuCull<'a> {
+    pub pipeline: &'a wgpu::ComputePipeline,
+    pub bind_group_layout: &'a wgpu::BindGroupLayout,
+    /// Planes to test; a shadow cascade passes only its side planes.
+    pub planes: &'a [[f32; 4]],
+    /// Mesh-space bounds of every instance, in instance order.
+    pub bounds: &'a [BoundingSphere],
+    /// Index count of every batch's mesh.
+    pub index_counts: &'a [u32],
+}
+
+/// Instances of one pass, grouped into batches.
+pub struct Instances<'a> {
+    /// Per-instance uniforms, already in batch order.
+    pub objects: &'a [PerObjectUniforms],
+    pub batches: Vec<InstanceBatch>,
+}
+
+/// Buffer of `T` records, grown to the next power of two on upload.
+struct GrowableBuffer {
+    buffer: Option<wgpu::Buffer>,
+    /// Records `buffer` can hold.
+    capacity: u32,
+    label: &'static str,
+    usage: wgpu::BufferUsages,
+    stride: u64,
+}
+
+impl GrowableBuffer {
+    fn new<T: bytemuck::Pod>(label: &'static str, usage: wgpu::BufferUsages) -> Self {
+        Self {
+            buffer: None,
+            capacity: 0,
+            label,
+            usage: usage | wgpu::BufferUsages::COPY_DST,
+            stride: std::mem::size_of::<T>() as u64,
+        }
+    }
+
+    /// Make room for `count` records (at least one, as bindings cannot be empty).
+    fn reserve(&mut self, device: &wgpu::Device, count: usize) -> &wgpu::Buffer {
+        let count = (count as u32).max(1);
+        if self.buffer.is_none() || count > self.capacity {
+            self.capacity = count.next_power_of_two();
+            self.buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(self.label),
+                size: self.capacity as u64 * self.stride,
+                usage: self.usage,
+                mapped_at_creation: false,
+            }));
+        }
+        self.buffer.as_ref().unwrap()
+    }
+
+    fn upload<T: bytemuck::Pod>(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, data: &[T]) -> &wgpu::Buffer {
+        let buffer = self.reserve(device, data.len());
+        if !data.is_empty() {
+            queue.write_buffer(buffer, 0, bytemuck::cast_slice(data));
+        }
+        buffer
+    }
+}
+
+/// Buffers of one instanced pass, kept across frames and refilled with queue
+/// writes. Queue writes all land before a submission runs, so passes recorded
+/// into the same submission need separate sets.
+pub struct InstanceBuffers {
+    input: GrowableBuffer,
+    output: GrowableBuffer,
+    cull: GrowableBuffer,
+    params: GrowableBuffer,
+    indirect: GrowableBuffer,
+}
+
+impl Default for InstanceBuffers {
+    fn default() -> Self {
+        use wgpu::BufferUsages as U;
+        Self {
+            input: GrowableBuffer::new::<PerObjectUniforms>("Instance Buffer", U::STORAGE),
+            output: GrowableBuffer::new::<PerObjectUniforms>("Culled Instance Buffer", U::STORAGE),
+            cull: GrowableBuffer::new::<CullInstance>("Instance Cull Data", U::STORAGE),
+            params: GrowableBuffer::new::<CullParams>("Instance Cull Params", U::UNIFORM),
+            indirect: GrowableBuffer::new::<DrawIndexedIndirectArgs>("Instance Indirect Args", U::STORAGE | U::INDIRECT),
+        }
+    }
+}
+
+/// Instance buffer sets of passes recorded into one submission, by pass.
+#[derive(Default)]
+pub struct InstanceBufferPool {
+    sets: Vec<InstanceBuffers>,
+}
+
+impl InstanceBufferPool {
+    /// The set of pass `index`, created on first use.
+    pub fn get(&mut self, index: usize) -> &mut InstanceBuffers {
+        if self.sets.len() <= index {
+            self.sets.resize_with(index + 1, InstanceBuffers::default);
+        }
+        &mut self.sets[index]
+    }
+}
+
+/// Per-instance data of one pass, bound at the instanced pipelines' instance group.
+pub struct InstanceDraws<'a> {
+    pub bind_group: wgpu::BindGroup,
+    batches: Vec<InstanceBatch>,
+    /// One `DrawIndexedIndirectArgs` per batch when culled on the GPU.
+    indirect: Option<&'a wgpu::Buffer>,
+}
+
+impl InstanceBuffers {
+    /// Upload `instances` and record the culling dispatch into `encoder` when
+    /// `cull` is given.
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        instance_bgl: &wgpu::BindGroupLayout,
+        instances: Instances<'_>,
+        cull: Option<GpuCull<'_>>,
+    ) -> InstanceDraws<'_> {
+        let Instances { objects: instances, batches } = instances;
+        let input = self.input.upload(device, queue, instances);
+
+        let Some(cull) = cull.filter(|_| !instances.is_empty()) else {
+            let bind_group = instance_bind_group(device, instance_bgl, input);
+            return InstanceDraws { bind_group, batches, indirect: None };
+        };
+
+        let output = self.output.reserve(device, instances.len());
+        let cull_buffer = self.cull.upload(device, queue, &cull_instances(&batches, cull.bounds));
+        let params = self.params.upload(device, queue, &[cull_params(cull.planes, instances.len())]);
+        // Instance counts start at zero; the culling pass counts the survivors
+        let indirect = self.indirect.upload(device, queue, &indirect_args(&batches, cull.index_counts));
+
+        let cull_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Instance Cull BG"),
+            layout: cull.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: params.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: input.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: cull_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 3, resource: output.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 4, resource: indirect.as_entire_binding() },
+            ],
+        });
+
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Instance Cull"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(cull.pipeline);
+            pass.set_bind_group(0, &cull_bind_group, &[]);
+            pass.dispatch_workgroups((instances.len() as u32).div_ceil(CULL_WORKGROUP_SIZE), 1, 1);
+        }
+
+        let bind_group = instance_bind_group(device, instance_bgl, output);
+        InstanceDraws { bind_group, batches, indirect: Some(indirect) }
+    }
+}
+
+/// Culling parameters testing the first (up to six) `planes`.
+fn cull_params(planes: &[[f32; 4]], instance_count: usize) -> CullParams {
+    let mut padded = [[0.0; 4]; 6];
+    let plane_count = planes.len().min(6);
+    padded[..plane_count].copy_from_slice(&planes[..plane_count]);
+    CullParams {
+        planes: padded,
+        instance_count: instance_count as u32,
+        plane_count: plane_count as u32,
+        _pad1: 0,
+        _pad2: 0,
+    }
+}
+
+/// Per-instance culling records: the bounds of each instance and its batch.
+fn cull_instances(batches: &[InstanceBatch], bounds: &[BoundingSphere]) -> Vec<CullInstance> {
+    let mut cull_data = Vec::with_capacity(bounds.len());
+    for (b, batch) in batches.iter().enumerate() {
+        let range = batch.first_instance as usize..(batch.first_instance + batch.instance_count) as usize;
+        cull_data.extend(bounds[range].iter().map(|s| CullInstance {
+            bounds: [s.center.x, s.center.y, s.center.z, s.radius],
+            batch: b as u32,
+            _pad1: 0,
+            _pad2: 0,
+            _pad3: 0,
+        }));
+    }
+    cull_data
+}
+
+/// Indirect draw arguments of every batch with no instances counted yet.
+fn indirect_args(batches: &[InstanceBatch], index_counts: &[u32]) -> Vec<DrawIndexedIndirectArgs> {
+    batches
+        .iter()
+        .zip(index_counts)
+        .map(|(b, &index_count)| DrawIndexedIndirectArgs {
+            index_count,
+            instance_count: 0,
+            first_index: 0,
+            base_vertex: 0,
+            first_instance: b.first_instance,
+        })
+        .collect()
+}
+
+impl InstanceDraws<'_> {
+    /// Draw batch `batch`, whose mesh buffers are already bound.
+    pub fn draw(&self, pass: &mut wgpu::RenderPass<'_>, batch: usize, index_count: u32) {
+        match &self.indirect {
+            Some(indirect) => {
+                let offset = (batch * std::mem::size_of::<DrawIndexedIndirectArgs>()) as u64;
+                pass.draw_indexed_indirect(indirect, offset);
+            }
+            None => {
+                let b = self.batches[batch];
+                pass.draw_indexed(0..index_count, 0, b.first_instance..b.first_instance + b.instance_count);
+            }
+        }
+    }
+}
+
+fn instance_bind_group(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, buffer: &wgpu::Buffer) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Instance BG"),
+        layout,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: buffer.as_entire_binding(),
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec3;
+    use wgpu::util::DrawIndexedIndirectArgs as WgpuArgs;
+
+    const BATCHES: [InstanceBatch; 2] = [
+        InstanceBatch { first_instance: 0, instance_count: 2, representative: 0 },
+        InstanceBatch { first_instance: 2, instance_count: 1, representative: 2 },
+    ];
+
+    #[test]
+    fn test_cull_params_pad_and_truncate_planes() {
+        let planes: Vec<[f32; 4]> = (0..8).map(|i| [i as f32, 0.0, 0.0, 1.0]).collect();
+
+        let side = cull_params(&planes[..4], 7);
+        assert_eq!(side.plane_count, 4);
+        assert_eq!(side.instance_count, 7);
+        assert_eq!(side.planes[3], [3.0, 0.0, 0.0, 1.0]);
+        assert_eq!(side.planes[4], [0.0; 4]);
+        assert_eq!(side.planes[5], [0.0; 4]);
+
+        let all = cull_params(&planes, 1);
+        assert_eq!(all.plane_count, 6);
+        assert_eq!(all.planes[5], [5.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_cull_instances_record_their_batch() {
+        let bounds = [
+            BoundingSphere { center: Vec3::new(1.0, 2.0, 3.0), radius: 0.5 },
+            BoundingSphere { center: Vec3::ZERO, radius: 1.0 },
+            BoundingSphere { center: Vec3::X, radius: 2.0 },
+        ];
+        let cull = cull_instances(&BATCHES, &bounds);
+        assert_eq!(cull.iter().map(|c| c.batch).collect::<Vec<_>>(), [0, 0, 1]);
+        assert_eq!(cull[0].bounds, [1.0, 2.0, 3.0, 0.5]);
+        assert_eq!(cull[2].bounds, [1.0, 0.0, 0.0, 2.0]);
+    }
+
+    #[test]
+    fn test_indirect_args_match_wgpu_layout() {
+        let args = indirect_args(&BATCHES, &[36, 960]);
+        assert_eq!(std::mem::size_of::<DrawIndexedIndirectArgs>(), 20);
+
+        let expected: Vec<u8> = [
+            WgpuArgs { index_count: 36, instance_count: 0, first_index: 0, base_vertex: 0, first_instance: 0 },
+            WgpuArgs { index_count: 960, instance_count: 0, first_index: 0, base_vertex: 0, first_instance: 2 },
+        ]
+        .iter()
+        .flat_map(|a| a.as_bytes().to_vec())
+        .collect();
+        assert_eq!(bytemuck::cast_slice::<_, u8>(&args), expected);
+    }
+}
@@ -9,10 +9,32 @@ mod render_targets;
 mod pipeline;
 mod passes;
 mod ibl;
+mod gpu_particles;
+mod instancing;
+mod csm;
+mod exposure;
+mod local_shadows;
+mod post_process;
+mod output;
+mod frame_timer;
+mod environment;
 
 use backend::WGPUBackendState;
 use handle::HandleStore;
-use std::ffi::CString;
+use openreality_gpu_shared::batching::group_instances;
+use openreality_gpu_shared::clustering::cluster_params;
+use openreality_gpu_shared::decal::decal_bounds;
+use openreality_gpu_shared::math::{extract_frustum_planes, sphere_in_frustum};
+use openreality_gpu_shared::color_lut::CubeLut;
+use openreality_gpu_shared::display::DisplaySettings;
+use openreality_gpu_shared::resolution::ResolutionSettings;
+use openreality_gpu_shared::post_process::{
+    BloomSettings, ColorGradingSettings, DofSettings, ExposureSettings, LensSettings, MotionBlurSettings, PostEffect,
+    PostProcessStack, SsaoSettings, SsrSettings, TaaSettings, ToneMapSettings,
+};
+use openreality_gpu_shared::scene_format::MorphTargetParsed;
+use post_process::ColorLut;
+use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
 use std::sync::Mutex;
 
@@ -142,6 +164,28 @@ pub extern "C" fn or_wgpu_initialize(
     }
 }
 
+/// Initialize the WebGPU backend without a window. Frames are presented to an
+/// offscreen `Rgba16Float` target that `or_wgpu_read_output` reads back, so
+/// rendering (including the HDR output encodings) runs on machines without a
+/// display.
+///
+/// Returns a backend handle (> 0) on success, 0 on failure.
+#[no_mangle]
+pub extern "C" fn or_wgpu_initialize_headless(width: i32, height: i32) -> u64 {
+    let _ = env_logger::try_init();
+
+    match WGPUBackendState::new_headless(width.max(1) as u32, height.max(1) as u32) {
+        Ok(state) => {
+            let mut backends = BACKENDS.lock().unwrap();
+            backends.insert(state)
+        }
+        Err(e) => {
+            log::error!("WebGPU headless initialization failed: {e}");
+            0
+        }
+    }
+}
+
 /// Shutdown the backend and release all GPU resources.
 #[no_mangle]
 pub extern "C" fn or_wgpu_shutdown(backend: u64) {
@@ -210,6 +254,136 @@ pub extern "C" fn or_wgpu_upload_mesh(
     }
 }
 
+/// Upload a skinned mesh. `bone_weights` holds 4 f32 and `bone_indices` 4 u16
+/// per vertex. Returns mesh handle (> 0) or 0 on failure.
+///
+/// # Safety
+/// `positions` and `normals` must point to `num_vertices * 3` f32, `uvs`
+/// to `num_vertices * 2` f32, `bone_weights` and `bone_indices` to
+/// `num_vertices * 4` elements and `indices` to `num_indices` u32.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn or_wgpu_upload_skinned_mesh(
+    backend: u64,
+    positions: *const f32,
+    num_vertices: u32,
+    normals: *const f32,
+    uvs: *const f32,
+    bone_weights: *const f32,
+    bone_indices: *const u16,
+    indices: *const u32,
+    num_indices: u32,
+) -> u64 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        let pos_slice = unsafe { std::slice::from_raw_parts(positions, (num_vertices * 3) as usize) };
+        let norm_slice = unsafe { std::slice::from_raw_parts(normals, (num_vertices * 3) as usize) };
+        let uv_slice = unsafe { std::slice::from_raw_parts(uvs, (num_vertices * 2) as usize) };
+        let weight_slice = unsafe { std::slice::from_raw_parts(bone_weights, (num_vertices * 4) as usize) };
+        let bone_idx_slice = unsafe { std::slice::from_raw_parts(bone_indices, (num_vertices * 4) as usize) };
+        let idx_slice = unsafe { std::slice::from_raw_parts(indices, num_indices as usize) };
+        state.upload_skinned_mesh(pos_slice, norm_slice, uv_slice, weight_slice, bone_idx_slice, idx_slice)
+    } else {
+        0
+    }
+}
+
+/// Upload the bone palette for a skinned mesh: `bone_count` column-major
+/// mat4x4<f32> in mesh-local space. Returns 0 on success, -1 on failure.
+///
+/// # Safety
+/// `matrices` must point to `bone_count * 16` f32.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn or_wgpu_update_bone_matrices(backend: u64, mesh: u64, matrices: *const f32, bone_count: u32) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        let matrix_slice = unsafe { std::slice::from_raw_parts(matrices, (bone_count * 16) as usize) };
+        match state.update_bone_matrices(mesh, matrix_slice) {
+            Ok(()) => 0,
+            Err(e) => {
+                state.last_error = Some(e);
+                -1
+            }
+        }
+    } else {
+        -1
+    }
+}
+
+/// Attach morph targets to a mesh uploaded with `or_wgpu_upload_mesh`.
+/// `base_positions`/`base_normals` are the uploaded vertices (3 floats each);
+/// `position_deltas` holds `num_targets` blocks of the same size, target-major.
+/// `normal_deltas` may be null. Returns 0 on success, -1 on failure.
+///
+/// # Safety
+/// `base_positions` and `base_normals` must point to 3 f32 per mesh vertex,
+/// `position_deltas` to `num_targets` times as many, and `normal_deltas` must
+/// be null or as long as `position_deltas`.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn or_wgpu_upload_morph_targets(
+    backend: u64,
+    mesh: u64,
+    base_positions: *const f32,
+    base_normals: *const f32,
+    position_deltas: *const f32,
+    normal_deltas: *const f32,
+    num_targets: u32,
+) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        let Some(n) = state.meshes.get(mesh).map(|m| m.vertex_count as usize * 3) else {
+            state.last_error = Some("Invalid mesh handle".into());
+            return -1;
+        };
+        let pos_slice = unsafe { std::slice::from_raw_parts(base_positions, n) };
+        let norm_slice = unsafe { std::slice::from_raw_parts(base_normals, n) };
+        let targets = (0..num_targets as usize)
+            .map(|t| MorphTargetParsed {
+                position_deltas: unsafe { std::slice::from_raw_parts(position_deltas.add(t * n), n) }.to_vec(),
+                normal_deltas: if normal_deltas.is_null() {
+                    Vec::new()
+                } else {
+                    unsafe { std::slice::from_raw_parts(normal_deltas.add(t * n), n) }.to_vec()
+                },
+            })
+            .collect();
+        match state.set_morph_targets(mesh, pos_slice, norm_slice, targets) {
+            Ok(()) => 0,
+            Err(e) => {
+                state.last_error = Some(e);
+                -1
+            }
+        }
+    } else {
+        -1
+    }
+}
+
+/// Set morph target weights for a mesh and re-upload its deformed vertices.
+/// Returns 0 on success, -1 on failure.
+///
+/// # Safety
+/// `weights` must point to `count` f32.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn or_wgpu_set_morph_weights(backend: u64, mesh: u64, weights: *const f32, count: u32) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        let weight_slice = unsafe { std::slice::from_raw_parts(weights, count as usize) };
+        match state.set_morph_weights(mesh, weight_slice) {
+            Ok(()) => 0,
+            Err(e) => {
+                state.last_error = Some(e);
+                -1
+            }
+        }
+    } else {
+        -1
+    }
+}
+
 /// Destroy a mesh and free its GPU resources.
 #[no_mangle]
 pub extern "C" fn or_wgpu_destroy_mesh(backend: u64, mesh: u64) {
@@ -276,59 +450,34 @@ pub extern "C" fn or_wgpu_last_error(backend: u64) -> *const c_char {
 // FFI: Advanced resource creation (stubs for now)
 // ============================================================
 
-/// Create cascaded shadow maps. Returns CSM handle or 0.
+/// Create cascaded shadow maps: `num_cascades` (1-4) layers of
+/// `resolution`² texels splitting the view distances `near..far`. Replaces
+/// any previous maps; their settings are kept. Returns 1 on success, 0 on failure.
 #[no_mangle]
 pub extern "C" fn or_wgpu_create_csm(
     backend: u64,
     num_cascades: i32,
     resolution: i32,
-    _near: f32,
-    _far: f32,
+    near: f32,
+    far: f32,
 ) -> u64 {
     let mut backends = BACKENDS.lock().unwrap();
     if let Some(state) = backends.get_mut(backend) {
-        let res = resolution as u32;
-        let n = num_cascades as u32;
-
-        let sampler = state.device.create_sampler(&wgpu::SamplerDescriptor {
-            label: Some("Shadow Sampler"),
-            compare: Some(wgpu::CompareFunction::LessEqual),
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
-            ..Default::default()
-        });
-
-        let mut depth_textures = Vec::new();
-        let mut depth_views = Vec::new();
-
-        for i in 0..n {
-            let texture = state.device.create_texture(&wgpu::TextureDescriptor {
-                label: Some(&format!("Shadow Cascade {i}")),
-                size: wgpu::Extent3d {
-                    width: res,
-                    height: res,
-                    depth_or_array_layers: 1,
-                },
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::Depth32Float,
-                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
-                    | wgpu::TextureUsages::TEXTURE_BINDING,
-                view_formats: &[],
-            });
-            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-            depth_textures.push(texture);
-            depth_views.push(view);
+        let max_size = state.device.limits().max_texture_dimension_2d as i32;
+        if !(1..=csm::MAX_CASCADES as i32).contains(&num_cascades) || !(1..=max_size).contains(&resolution) {
+            state.last_error = Some(format!(
+                "CSM needs 1-{} cascades of 1-{max_size} texels, got {num_cascades} of {resolution}",
+                csm::MAX_CASCADES
+            ));
+            return 0;
         }
-
-        state.csm = Some(backend::CascadedShadowMap {
-            depth_textures,
-            depth_views,
-            sampler,
-            num_cascades: n,
-            resolution: res,
-        });
+        if !(near > 0.0 && far > near) {
+            state.last_error = Some(format!("CSM split range {near}..{far} must satisfy 0 < near < far"));
+            return 0;
+        }
+        let settings = state.csm.settings;
+        state.csm = csm::CascadedShadowMap::new(&state.device, num_cascades as u32, resolution as u32, near, far);
+        state.csm.set_settings(&state.device, settings);
 
         1 // Success (non-zero)
     } else {
@@ -336,20 +485,318 @@ pub extern "C" fn or_wgpu_create_csm(
     }
 }
 
-/// Create post-processing pipeline. Returns 1 on success, 0 on failure.
+/// Set the CSM filter and biases: `settings_ptr` points to a `CsmSettings`
+/// struct of `settings_size` bytes. Out-of-range values are clamped.
+/// Returns 0 on success, -1 on failure.
+///
+/// # Safety
+/// `settings_ptr` must point to `settings_size` readable bytes.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn or_wgpu_set_csm_settings(backend: u64, settings_ptr: *const u8, settings_size: u32) -> i32 {
+    use openreality_gpu_shared::uniforms::{CsmSettings, SHADOW_FILTER_EVSM};
+
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        let size = std::mem::size_of::<CsmSettings>();
+        if settings_size as usize != size {
+            state.last_error = Some(format!("CsmSettings is {size} bytes, got {settings_size}"));
+            return -1;
+        }
+        let data = unsafe { std::slice::from_raw_parts(settings_ptr, size) };
+        let settings: CsmSettings = bytemuck::pod_read_unaligned(data);
+        if settings.filter_mode > SHADOW_FILTER_EVSM {
+            state.last_error = Some(format!("Unknown shadow filter mode {}", settings.filter_mode));
+            return -1;
+        }
+        state.csm.set_settings(&state.device, settings);
+        0
+    } else {
+        -1
+    }
+}
+
+/// Configure the post-process stack: bloom threshold and intensity, gamma,
+/// tone mapping operator and whether FXAA runs. The stack follows the
+/// deferred pipeline's size, so `width` and `height` are unused.
+/// Returns 1 on success, 0 on failure.
 #[no_mangle]
 pub extern "C" fn or_wgpu_create_post_process(
-    _backend: u64,
+    backend: u64,
     _width: i32,
     _height: i32,
-    _bloom_threshold: f32,
-    _bloom_intensity: f32,
-    _gamma: f32,
-    _tone_mapping_mode: i32,
-    _fxaa_enabled: i32,
+    bloom_threshold: f32,
+    bloom_intensity: f32,
+    gamma: f32,
+    tone_mapping_mode: i32,
+    fxaa_enabled: i32,
 ) -> u64 {
-    // Post-processing is handled by the deferred pipeline
-    1
+    let result = update_post_stack(backend, |stack| {
+        let bloom = BloomSettings { threshold: bloom_threshold, intensity: bloom_intensity };
+        let tone_map = ToneMapSettings { mode: tone_mapping_mode.max(0) as u32, gamma };
+        bloom.validate()?;
+        tone_map.validate()?;
+        stack.bloom = bloom;
+        stack.tone_map = tone_map;
+        stack.set_enabled(PostEffect::Fxaa, fxaa_enabled != 0);
+        Ok(())
+    });
+    if result == 0 { 1 } else { 0 }
+}
+
+/// Apply `update` to the backend's post-process stack, recording its error.
+fn update_post_stack(backend: u64, update: impl FnOnce(&mut PostProcessStack) -> Result<(), String>) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        match update(&mut state.post_process) {
+            Ok(()) => 0,
+            Err(e) => {
+                state.last_error = Some(e);
+                -1
+            }
+        }
+    } else {
+        -1
+    }
+}
+
+/// Enable or disable one post effect (`PostEffect` id: 0 SSAO, 1 SSR, 2 TAA,
+/// 3 bloom, 4 DOF, 5 motion blur, 6 color grading, 7 FXAA).
+/// Returns 0 on success, -1 on failure.
+#[no_mangle]
+pub extern "C" fn or_wgpu_set_post_effect_enabled(backend: u64, effect: u32, enabled: i32) -> i32 {
+    update_post_stack(backend, |stack| {
+        let effect = PostEffect::from_id(effect).ok_or_else(|| format!("Unknown post effect {effect}"))?;
+        stack.set_enabled(effect, enabled != 0);
+        Ok(())
+    })
+}
+
+/// Reorder the post-process stack: `effects_ptr` holds `count` effect ids,
+/// every effect exactly once. HDR effects still run before tone mapping and
+/// display-space effects after it. Returns 0 on success, -1 on failure.
+///
+/// # Safety
+/// Unless `count` is 0, `effects_ptr` must point to `count` u32.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn or_wgpu_set_post_effect_order(backend: u64, effects_ptr: *const u32, count: u32) -> i32 {
+    let effects: &[u32] = if count == 0 { &[] } else { unsafe { std::slice::from_raw_parts(effects_ptr, count as usize) } };
+    update_post_stack(backend, |stack| stack.set_order(effects))
+}
+
+/// Bloom extraction threshold and composite intensity.
+#[no_mangle]
+pub extern "C" fn or_wgpu_set_post_bloom(backend: u64, threshold: f32, intensity: f32) -> i32 {
+    let settings = BloomSettings { threshold, intensity };
+    update_post_stack(backend, |stack| settings.validate().map(|()| stack.bloom = settings))
+}
+
+/// Tone mapping operator (0 Reinhard, 1 ACES, 2 Uncharted 2, 3 AgX, 4 Khronos
+/// PBR Neutral) and display gamma.
+#[no_mangle]
+pub extern "C" fn or_wgpu_set_post_tone_mapping(backend: u64, mode: u32, gamma: f32) -> i32 {
+    let settings = ToneMapSettings { mode, gamma };
+    update_post_stack(backend, |stack| settings.validate().map(|()| stack.tone_map = settings))
+}
+
+/// SSAO sample radius (world units), depth bias, contrast power and kernel size.
+#[no_mangle]
+pub extern "C" fn or_wgpu_set_post_ssao(backend: u64, radius: f32, bias: f32, power: f32, kernel_size: u32) -> i32 {
+    let settings = SsaoSettings { radius, bias, power, kernel_size };
+    update_post_stack(backend, |stack| settings.validate().map(|()| stack.ssao = settings))
+}
+
+/// SSR ray march steps, maximum distance and depth thickness.
+#[no_mangle]
+pub extern "C" fn or_wgpu_set_post_ssr(backend: u64, max_steps: u32, max_distance: f32, thickness: f32) -> i32 {
+    let settings = SsrSettings { max_steps, max_distance, thickness };
+    update_post_stack(backend, |stack| settings.validate().map(|()| stack.ssr = settings))
+}
+
+/// TAA history weight in [0, 1).
+#[no_mangle]
+pub extern "C" fn or_wgpu_set_post_taa(backend: u64, feedback: f32) -> i32 {
+    let settings = TaaSettings { feedback };
+    update_post_stack(backend, |stack| settings.validate().map(|()| stack.taa = settings))
+}
+
+/// Depth of field focus distance, in-focus range and blur radius in texels.
+#[no_mangle]
+pub extern "C" fn or_wgpu_set_post_dof(backend: u64, focus_distance: f32, focus_range: f32, bokeh_radius: f32) -> i32 {
+    let settings = DofSettings { focus_distance, focus_range, bokeh_radius };
+    update_post_stack(backend, |stack| settings.validate().map(|()| stack.dof = settings))
+}
+
+/// Camera motion blur strength, taps per pixel and longest blur in pixels.
+#[no_mangle]
+pub extern "C" fn or_wgpu_set_post_motion_blur(backend: u64, intensity: f32, samples: u32, max_velocity: f32) -> i32 {
+    let settings = MotionBlurSettings { intensity, samples, max_velocity };
+    update_post_stack(backend, |stack| settings.validate().map(|()| stack.motion_blur = settings))
+}
+
+/// Display-space brightness offset, contrast, saturation and the blend
+/// towards the 3D LUT graded color.
+#[no_mangle]
+pub extern "C" fn or_wgpu_set_post_color_grading(
+    backend: u64,
+    brightness: f32,
+    contrast: f32,
+    saturation: f32,
+    lut_strength: f32,
+) -> i32 {
+    let settings = ColorGradingSettings { brightness, contrast, saturation, lut_strength };
+    update_post_stack(backend, |stack| settings.validate().map(|()| stack.color_grading = settings))
+}
+
+/// Exposure mode (0 fixed, 1 manual, 2 auto), the EV100 of manual exposure
+/// and the compensation in stops every mode applies.
+#[no_mangle]
+pub extern "C" fn or_wgpu_set_post_exposure(backend: u64, mode: u32, ev100: f32, compensation: f32) -> i32 {
+    update_post_stack(backend, |stack| {
+        let settings = ExposureSettings { mode, ev100, compensation, ..stack.exposure };
+        settings.validate().map(|()| stack.exposure = settings)
+    })
+}
+
+/// EV100 range auto exposure meters and clamps to, and its adaptation rates
+/// (1/s) towards brighter and darker scenes.
+#[no_mangle]
+pub extern "C" fn or_wgpu_set_post_auto_exposure(
+    backend: u64,
+    min_ev100: f32,
+    max_ev100: f32,
+    speed_up: f32,
+    speed_down: f32,
+) -> i32 {
+    update_post_stack(backend, |stack| {
+        let settings = ExposureSettings { min_ev100, max_ev100, speed_up, speed_down, ..stack.exposure };
+        settings.validate().map(|()| stack.exposure = settings)
+    })
+}
+
+/// Chromatic aberration, vignette and film grain of the tone mapping pass;
+/// zero intensities turn them off.
+#[no_mangle]
+pub extern "C" fn or_wgpu_set_post_lens(
+    backend: u64,
+    chromatic_aberration: f32,
+    vignette_intensity: f32,
+    vignette_radius: f32,
+    vignette_softness: f32,
+    grain_intensity: f32,
+) -> i32 {
+    let settings = LensSettings {
+        chromatic_aberration,
+        vignette_intensity,
+        vignette_radius,
+        vignette_softness,
+        grain_intensity,
+    };
+    update_post_stack(backend, |stack| settings.validate().map(|()| stack.lens = settings))
+}
+
+/// Grade with the 3D LUT in `cube_text`, the contents of a `.cube` file.
+/// Returns 0 on success, -1 on failure (the previous LUT stays).
+///
+/// # Safety
+/// `cube_text` must be null or a NUL-terminated string.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn or_wgpu_set_color_lut(backend: u64, cube_text: *const c_char) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        if cube_text.is_null() {
+            state.last_error = Some("No .cube text given".into());
+            return -1;
+        }
+        let text = unsafe { CStr::from_ptr(cube_text) }.to_string_lossy();
+        match CubeLut::parse(&text) {
+            Ok(lut) => {
+                state.color_lut = ColorLut::new(&state.device, &state.queue, &lut);
+                0
+            }
+            Err(e) => {
+                state.last_error = Some(format!("Invalid .cube LUT: {e}"));
+                -1
+            }
+        }
+    } else {
+        -1
+    }
+}
+
+/// Replace the color grading LUT with the identity.
+#[no_mangle]
+pub extern "C" fn or_wgpu_clear_color_lut(backend: u64) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        state.color_lut = ColorLut::identity(&state.device, &state.queue);
+        0
+    } else {
+        -1
+    }
+}
+
+// ============================================================
+// FFI: Display output
+// ============================================================
+
+/// Select the display mode (`display::DISPLAY_*`): SDR, scRGB or HDR10.
+/// `paper_white_nits` is the luminance of diffuse white and `max_nits` the
+/// display's peak on HDR outputs. Returns the `display::OUTPUT_*` encoding
+/// the surface supports, which is an SDR one when it has no HDR format, or
+/// -1 on invalid settings.
+#[no_mangle]
+pub extern "C" fn or_wgpu_set_display_output(backend: u64, mode: u32, paper_white_nits: f32, max_nits: f32) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        match state.set_display_output(DisplaySettings { mode, paper_white_nits, max_nits }) {
+            Ok(encoding) => encoding as i32,
+            Err(e) => {
+                state.last_error = Some(e);
+                -1
+            }
+        }
+    } else {
+        -1
+    }
+}
+
+/// Copy the last presented frame of a headless backend into `out` as RGBA
+/// floats, rows from the top. `capacity` is the length of `out` in floats.
+/// Returns the number of floats written, or -1.
+///
+/// # Safety
+/// `out` must be null or valid for writes of `capacity` f32.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn or_wgpu_read_output(backend: u64, out: *mut f32, capacity: u32) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        let output::OutputTarget::Headless(target) = &state.output else {
+            state.last_error = Some("Only headless backends can read back their output".into());
+            return -1;
+        };
+        let needed = (target.width * target.height * 4) as usize;
+        if out.is_null() || (capacity as usize) < needed {
+            state.last_error = Some(format!("Output readback needs {needed} floats, got {capacity}"));
+            return -1;
+        }
+        match output::read_headless_target(&state.device, &state.queue, target) {
+            Ok(texels) => {
+                let out = unsafe { std::slice::from_raw_parts_mut(out, needed) };
+                out.copy_from_slice(&texels);
+                needed as i32
+            }
+            Err(e) => {
+                state.last_error = Some(e);
+                -1
+            }
+        }
+    } else {
+        -1
+    }
 }
 
 // ============================================================
@@ -381,7 +828,7 @@ pub extern "C" fn or_wgpu_resize_pipeline(backend: u64, width: i32, height: i32)
     let mut backends = BACKENDS.lock().unwrap();
     if let Some(state) = backends.get_mut(backend) {
         state.resize(width as u32, height as u32);
-        state.resize_deferred_pipeline(width as u32, height as u32);
+        state.resize_deferred_pipeline();
     }
 }
 
@@ -399,33 +846,83 @@ pub extern "C" fn or_wgpu_begin_frame(
 ) -> i32 {
     let mut backends = BACKENDS.lock().unwrap();
     if let Some(state) = backends.get_mut(backend) {
+        state.update_dynamic_resolution();
         let data = unsafe { std::slice::from_raw_parts(per_frame_ptr, per_frame_size as usize) };
         state.queue.write_buffer(&state.per_frame_buffer, 0, data);
+        // Keep the camera frustum for culling in the G-Buffer pass
+        let size = std::mem::size_of::<openreality_gpu_shared::uniforms::PerFrameUniforms>();
+        state.frame_camera = data.get(..size).map(|bytes| {
+            let frame: openreality_gpu_shared::uniforms::PerFrameUniforms = bytemuck::pod_read_unaligned(bytes);
+            (glam::Mat4::from_cols_array_2d(&frame.view), glam::Mat4::from_cols_array_2d(&frame.projection))
+        });
+        state.frame_frustum = state.frame_camera.map(|(view, projection)| extract_frustum_planes(&(projection * view)));
         0
     } else {
         -1
     }
 }
 
-/// Upload light data (LightUniforms struct).
+/// Upload light data: a LightUniforms struct followed by `num_point_lights`
+/// PointLightData, `num_spot_lights` SpotLightData and `num_area_lights`
+/// AreaLightData entries, in that order. Point lights have no fixed limit;
+/// they are binned into froxels by the lighting pass.
 #[no_mangle]
 pub extern "C" fn or_wgpu_upload_lights(
     backend: u64,
     light_data_ptr: *const u8,
     light_data_size: u32,
 ) -> i32 {
+    use openreality_gpu_shared::uniforms::{AreaLightData, LightUniforms, PointLightData, SpotLightData};
+
+    // Read `count` records of `T` from `data` at `*offset`, advancing it.
+    fn read_records<T: bytemuck::Pod>(data: &[u8], offset: &mut usize, count: i32) -> Option<Vec<T>> {
+        let size = std::mem::size_of::<T>();
+        let bytes = data.get(*offset..*offset + count.max(0) as usize * size)?;
+        *offset += bytes.len();
+        Some(bytes.chunks_exact(size).map(bytemuck::pod_read_unaligned).collect())
+    }
+
     let mut backends = BACKENDS.lock().unwrap();
     if let Some(state) = backends.get_mut(backend) {
         let data = unsafe { std::slice::from_raw_parts(light_data_ptr, light_data_size as usize) };
-        state.queue.write_buffer(&state.light_buffer, 0, data);
+        let header_size = std::mem::size_of::<LightUniforms>();
+        let Some(header) = data.get(..header_size) else {
+            state.last_error = Some("Light data smaller than LightUniforms".into());
+            return -1;
+        };
+        let uniforms: LightUniforms = bytemuck::pod_read_unaligned(header);
+        let mut offset = header_size;
+        let Some(point_lights) = read_records::<PointLightData>(data, &mut offset, uniforms.num_point_lights) else {
+            state.last_error = Some("Light data shorter than num_point_lights".into());
+            return -1;
+        };
+        let Some(spot_lights) = read_records::<SpotLightData>(data, &mut offset, uniforms.num_spot_lights) else {
+            state.last_error = Some("Light data shorter than num_spot_lights".into());
+            return -1;
+        };
+        let Some(area_lights) = read_records::<AreaLightData>(data, &mut offset, uniforms.num_area_lights) else {
+            state.last_error = Some("Light data shorter than num_area_lights".into());
+            return -1;
+        };
+
+        state.queue.write_buffer(&state.light_buffer, 0, header);
+        state.environment.set_sun(&uniforms.dir_lights, uniforms.num_dir_lights);
+        let clusters = &mut state.light_clusters;
+        clusters.point_lights.upload(&state.device, &state.queue, &point_lights);
+        clusters.spot_lights.upload(&state.device, &state.queue, &spot_lights);
+        clusters.area_lights.upload(&state.device, &state.queue, &area_lights);
+        clusters.point_light_data = point_lights;
+        clusters.spot_light_data = spot_lights;
         0
     } else {
         -1
     }
 }
 
-/// Shadow pass: render depth for all cascades.
-/// `cascade_matrices_ptr` points to 4 mat4x4<f32> (cascade light-space VP matrices).
+/// Shadow pass: fit up to `num_cascades` cascades to the camera of
+/// `or_wgpu_begin_frame` for the directional light shining along the vec3 at
+/// `light_dir_ptr`, and render depth for all of them (plus EVSM moments when
+/// selected). `num_cascades` = 0 turns directional shadows off.
 /// `entity_models_ptr` points to N mat4x4<f32> model matrices (one per entity).
 /// `entity_mesh_handles_ptr` points to N u64 mesh handles.
 #[no_mangle]
@@ -434,100 +931,127 @@ pub extern "C" fn or_wgpu_shadow_pass(
     entity_mesh_handles_ptr: *const u64,
     entity_models_ptr: *const f32,
     entity_count: u32,
-    cascade_matrices_ptr: *const f32,
+    light_dir_ptr: *const f32,
     num_cascades: i32,
 ) -> i32 {
     let mut backends = BACKENDS.lock().unwrap();
     if let Some(state) = backends.get_mut(backend) {
-        let dp = match state.deferred.as_ref() {
-            Some(dp) => dp,
-            None => { state.last_error = Some("Deferred pipeline not created".into()); return -1; }
-        };
-        let csm = match state.csm.as_ref() {
-            Some(csm) => csm,
-            None => { state.last_error = Some("CSM not created".into()); return -1; }
+        let (mesh_handles, model_data) = if entity_count == 0 {
+            (&[][..], &[][..])
+        } else {
+            unsafe {
+                (
+                    std::slice::from_raw_parts(entity_mesh_handles_ptr, entity_count as usize),
+                    std::slice::from_raw_parts(entity_models_ptr, (entity_count * 16) as usize),
+                )
+            }
         };
-
-        let mesh_handles = unsafe { std::slice::from_raw_parts(entity_mesh_handles_ptr, entity_count as usize) };
-        let model_data = unsafe { std::slice::from_raw_parts(entity_models_ptr, (entity_count * 16) as usize) };
-        let cascade_data = unsafe { std::slice::from_raw_parts(cascade_matrices_ptr, (num_cascades * 16) as usize) };
-
-        let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Shadow Encoder"),
-        });
-
-        for c in 0..(num_cascades as usize).min(csm.num_cascades as usize) {
-            // Upload cascade VP matrix as per-frame data for this cascade
-            let cascade_vp: [[f32; 4]; 4] = {
-                let base = c * 16;
-                [
-                    [cascade_data[base], cascade_data[base + 1], cascade_data[base + 2], cascade_data[base + 3]],
-                    [cascade_data[base + 4], cascade_data[base + 5], cascade_data[base + 6], cascade_data[base + 7]],
-                    [cascade_data[base + 8], cascade_data[base + 9], cascade_data[base + 10], cascade_data[base + 11]],
-                    [cascade_data[base + 12], cascade_data[base + 13], cascade_data[base + 14], cascade_data[base + 15]],
-                ]
-            };
-
-            // Write cascade VP to per-frame buffer (view slot = identity, projection slot = cascade VP)
-            let shadow_frame = openreality_gpu_shared::uniforms::PerFrameUniforms {
-                view: [[1.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 1.0]],
-                projection: cascade_vp,
-                inv_view_proj: [[0.0; 4]; 4],
-                camera_pos: [0.0; 4],
-                time: 0.0,
-                _pad1: 0.0,
-                _pad2: 0.0,
-                _pad3: 0.0,
-                _alignment_pad: [0.0; 8],
-            };
-            state.queue.write_buffer(&state.per_frame_buffer, 0, bytemuck::bytes_of(&shadow_frame));
-
-            let per_frame_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
-                label: Some("Shadow Per-Frame BG"),
-                layout: &state.per_frame_bind_group_layout,
-                entries: &[wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: state.per_frame_buffer.as_entire_binding(),
-                }],
-            });
-
-            // Collect entities for this cascade
-            let mut shadow_meshes = Vec::new();
-            for i in 0..entity_count as usize {
-                let mesh_handle = mesh_handles[i];
-                if let Some(mesh) = state.meshes.get(mesh_handle) {
-                    let base = i * 16;
-                    let model: [[f32; 4]; 4] = [
-                        [model_data[base], model_data[base + 1], model_data[base + 2], model_data[base + 3]],
-                        [model_data[base + 4], model_data[base + 5], model_data[base + 6], model_data[base + 7]],
-                        [model_data[base + 8], model_data[base + 9], model_data[base + 10], model_data[base + 11]],
-                        [model_data[base + 12], model_data[base + 13], model_data[base + 14], model_data[base + 15]],
-                    ];
-                    shadow_meshes.push((mesh_handle, mesh, model));
-                }
+        let light_dir = glam::Vec3::from_slice(unsafe { std::slice::from_raw_parts(light_dir_ptr, 3) });
+        match csm::render_cascaded_shadows(state, mesh_handles, model_data, light_dir, num_cascades.max(0) as u32) {
+            Ok(()) => 0,
+            Err(e) => {
+                state.last_error = Some(e);
+                -1
             }
-
-            passes::shadow::render_shadow_cascade(
-                &mut encoder,
-                csm,
-                c,
-                &dp.shadow_pipeline,
-                &per_frame_bg,
-                &dp.per_object_bgl,
-                &state.device,
-                &state.queue,
-                &state.per_object_buffer,
-                &shadow_meshes,
-            );
         }
+    } else {
+        -1
+    }
+}
 
-        state.queue.submit(std::iter::once(encoder.finish()));
+/// Create the point/spot light shadow atlas: `size`² texels with tiles of
+/// at most `max_tile`, both powers of two. Replaces any previous atlas; the
+/// shadow-casting lights are kept. Returns 0 on success, -1 on failure.
+#[no_mangle]
+pub extern "C" fn or_wgpu_create_shadow_atlas(backend: u64, size: u32, max_tile: u32) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        let max_size = state.device.limits().max_texture_dimension_2d;
+        if !size.is_power_of_two() || !max_tile.is_power_of_two() || max_tile > size || size > max_size {
+            state.last_error = Some(format!(
+                "Shadow atlas size {size} and max tile {max_tile} must be powers of two with max_tile <= size <= {max_size}"
+            ));
+            return -1;
+        }
+        let casters = std::mem::take(&mut state.shadow_atlas.casters);
+        state.shadow_atlas = local_shadows::ShadowAtlas::new(&state.device, size, max_tile);
+        state.shadow_atlas.casters = casters;
+        0
+    } else {
+        -1
+    }
+}
+
+/// Mark which lights of `light_kind` (0 = point, 1 = spot) cast shadows:
+/// `indices_ptr` holds `count` indices into that kind's records of the last
+/// `or_wgpu_upload_lights`. Replaces the previous set for that kind.
+///
+/// # Safety
+/// Unless `count` is 0, `indices_ptr` must point to `count` u32.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn or_wgpu_set_shadow_casters(backend: u64, light_kind: u32, indices_ptr: *const u32, count: u32) -> i32 {
+    use openreality_gpu_shared::shadow_atlas::{LIGHT_KIND_POINT, LIGHT_KIND_SPOT};
+
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        if light_kind != LIGHT_KIND_POINT && light_kind != LIGHT_KIND_SPOT {
+            state.last_error = Some(format!("Unknown shadow light kind {light_kind}"));
+            return -1;
+        }
+        let indices = if count == 0 {
+            Vec::new()
+        } else {
+            unsafe { std::slice::from_raw_parts(indices_ptr, count as usize) }.to_vec()
+        };
+        state.shadow_atlas.casters[light_kind as usize] = indices;
         0
     } else {
         -1
     }
 }
 
+/// Local light shadow pass: assign atlas tiles to the shadow-casting point
+/// and spot lights by screen coverage and render the tiles whose light or
+/// static casters changed. Call after `or_wgpu_upload_lights` and before the
+/// lighting pass; it fills in the lights' `shadow_index`.
+/// Same entity arrays as `or_wgpu_shadow_pass`.
+///
+/// # Safety
+/// Unless `entity_count` is 0, `entity_mesh_handles_ptr` must point to
+/// `entity_count` u64 and `entity_models_ptr` to `entity_count * 16` f32.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn or_wgpu_local_shadow_pass(
+    backend: u64,
+    entity_mesh_handles_ptr: *const u64,
+    entity_models_ptr: *const f32,
+    entity_count: u32,
+) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        let (mesh_handles, model_data) = if entity_count == 0 {
+            (&[][..], &[][..])
+        } else {
+            unsafe {
+                (
+                    std::slice::from_raw_parts(entity_mesh_handles_ptr, entity_count as usize),
+                    std::slice::from_raw_parts(entity_models_ptr, (entity_count * 16) as usize),
+                )
+            }
+        };
+        match local_shadows::render_local_shadows(state, mesh_handles, model_data) {
+            Ok(()) => 0,
+            Err(e) => {
+                state.last_error = Some(e);
+                -1
+            }
+        }
+    } else {
+        -1
+    }
+}
+
 /// G-Buffer pass: render all opaque entities.
 /// Each entity is described by: mesh_handle (u64), model_matrix (16 f32), normal_matrix (12 f32),
 /// material (MaterialUniforms bytes), texture_handles (6 u64).
@@ -558,80 +1082,89 @@ pub extern "C" fn or_wgpu_gbuffer_pass(
 
         // Parse entities from packed data
         let entities_data = unsafe { std::slice::from_raw_parts(entities_ptr, (entity_count * entity_stride) as usize) };
-        let mut gbuffer_entities = Vec::new();
+        let mut static_keys = Vec::new();
+        let mut static_entities = Vec::new();
+        let mut skinned_entities = Vec::new();
+        let mut culled = 0;
 
         for i in 0..entity_count as usize {
             let offset = i * entity_stride as usize;
             let entity_bytes = &entities_data[offset..offset + entity_stride as usize];
 
-            // Parse EntityDrawData layout:
-            // 0: mesh_handle u64 (8 bytes)
-            // 8: model mat4 (64 bytes)
-            // 72: normal_col0 vec4 (16 bytes)
-            // 88: normal_col1 vec4 (16 bytes)
-            // 104: normal_col2 vec4 (16 bytes)
-            // 120: material (MaterialUniforms, 96 bytes)
-            // 216: texture_handles [6]u64 (48 bytes)
-            // Total: 264 bytes
-
-            let mesh_handle = u64::from_le_bytes(entity_bytes[0..8].try_into().unwrap());
-            let mesh = match state.meshes.get(mesh_handle) {
-                Some(m) => m,
-                None => continue,
+            let Some((mesh_handle, tex_handles, entity)) =
+                parse_draw_entity(&state.meshes, &state.textures, entity_bytes)
+            else {
+                continue;
             };
 
-            let model: [[f32; 4]; 4] = *bytemuck::from_bytes(&entity_bytes[8..72]);
-            let nc0: [f32; 4] = *bytemuck::from_bytes(&entity_bytes[72..88]);
-            let nc1: [f32; 4] = *bytemuck::from_bytes(&entity_bytes[88..104]);
-            let nc2: [f32; 4] = *bytemuck::from_bytes(&entity_bytes[104..120]);
-            let material: openreality_gpu_shared::uniforms::MaterialUniforms =
-                *bytemuck::from_bytes(&entity_bytes[120..216]);
-
-            let tex_handles: [u64; 6] = *bytemuck::from_bytes(&entity_bytes[216..264]);
-
-            // Look up texture views
-            let mut texture_views: [Option<&wgpu::TextureView>; 6] = [None; 6];
-            for (j, &handle) in tex_handles.iter().enumerate() {
-                if handle != 0 {
-                    if let Some(tex) = state.textures.get(handle) {
-                        texture_views[j] = Some(&tex.view);
-                    }
+            let skinned = entity.mesh.skin.is_some();
+            // The GPU culls static instances itself
+            let cpu_cull = skinned || !state.gpu_culling;
+            if let Some(planes) = state.frame_frustum.as_ref().filter(|_| cpu_cull) {
+                if !entity_in_frustum(planes, &entity) {
+                    culled += 1;
+                    continue;
                 }
             }
-
-            gbuffer_entities.push(passes::gbuffer::GBufferEntity {
-                mesh,
-                per_object: openreality_gpu_shared::uniforms::PerObjectUniforms {
-                    model,
-                    normal_matrix_col0: nc0,
-                    normal_matrix_col1: nc1,
-                    normal_matrix_col2: nc2,
-                    _pad: [0.0; 4],
-                },
-                material,
-                texture_views,
-            });
+            if skinned {
+                skinned_entities.push(entity);
+            } else {
+                // Entities sharing mesh, material and textures draw as one batch
+                let material_bits: [u32; 24] = bytemuck::cast(entity.material);
+                static_keys.push((mesh_handle, material_bits, tex_handles));
+                static_entities.push(entity);
+            }
         }
 
+        state.cull_stats.gbuffer_drawn = (static_entities.len() + skinned_entities.len()) as u32;
+        state.cull_stats.gbuffer_culled = culled;
+
         let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("GBuffer Encoder"),
         });
 
-        passes::gbuffer::render_gbuffer_pass(
-            &mut encoder,
-            &dp.gbuffer,
-            &dp.gbuffer_pipeline,
-            &per_frame_bg,
-            &dp.per_object_bgl,
-            &state.material_bind_group_layout,
+        let (batches, order) = group_instances(static_keys);
+        let instances: Vec<_> = order.iter().map(|&i| static_entities[i].per_object).collect();
+        let bounds: Vec<_> = order.iter().map(|&i| static_entities[i].mesh.bounds).collect();
+        let index_counts: Vec<u32> = batches.iter().map(|b| static_entities[b.representative].mesh.index_count).collect();
+        let representatives: Vec<_> = batches.iter().map(|b| &static_entities[b.representative]).collect();
+        let gpu_cull = state.frame_frustum.as_ref().filter(|_| state.gpu_culling).map(|planes| instancing::GpuCull {
+            pipeline: &dp.instance_cull_pipeline,
+            bind_group_layout: &dp.instance_cull_bgl,
+            planes,
+            bounds: &bounds,
+            index_counts: &index_counts,
+        });
+        let instance_draws = state.gbuffer_instances.prepare(
             &state.device,
             &state.queue,
-            &state.per_object_buffer,
-            &gbuffer_entities,
-            &dp.default_texture_view,
-            &state.default_sampler,
+            &mut encoder,
+            &dp.instance_bgl,
+            instancing::Instances { objects: &instances, batches },
+            gpu_cull,
         );
 
+        let resources = passes::gbuffer::GBufferResources {
+            pipeline: &dp.gbuffer_pipeline,
+            skinned_pipeline: &dp.gbuffer_skinned_pipeline,
+            per_frame_bg: &per_frame_bg,
+            per_object_bgl: &dp.per_object_bgl,
+            material_bgl: &state.material_bind_group_layout,
+            default_texture_view: &dp.default_texture_view,
+            default_sampler: &state.default_sampler,
+        };
+        let draws = passes::gbuffer::GBufferDraws {
+            batches: &representatives,
+            instances: &instance_draws,
+            skinned: &skinned_entities,
+        };
+        passes::gbuffer::render_gbuffer_pass(&mut encoder, &state.device, &state.queue, &dp.gbuffer, &resources, &draws);
+
+        let decals = visible_decals(state, &dp.default_texture_view);
+        if !decals.is_empty() {
+            passes::decal::render_decal_pass(&mut encoder, &state.device, dp, &per_frame_bg, &decals);
+        }
+
         state.queue.submit(std::iter::once(encoder.finish()));
         0
     } else {
@@ -639,6 +1172,129 @@ pub extern "C" fn or_wgpu_gbuffer_pass(
     }
 }
 
+/// Parse one packed EntityDrawData into its mesh handle, texture handles and
+/// draw data. Returns `None` for unknown meshes.
+fn parse_draw_entity<'a>(
+    meshes: &'a HandleStore<backend::GPUMesh>,
+    textures: &'a HandleStore<backend::GPUTexture>,
+    entity_bytes: &[u8],
+) -> Option<(u64, [u64; 6], passes::gbuffer::GBufferEntity<'a>)> {
+    // Parse EntityDrawData layout:
+    // 0: mesh_handle u64 (8 bytes)
+    // 8: model mat4 (64 bytes)
+    // 72: normal_col0 vec4 (16 bytes)
+    // 88: normal_col1 vec4 (16 bytes)
+    // 104: normal_col2 vec4 (16 bytes)
+    // 120: material (MaterialUniforms, 96 bytes)
+    // 216: texture_handles [6]u64 (48 bytes)
+    // Total: 264 bytes
+
+    let mesh_handle = u64::from_le_bytes(entity_bytes[0..8].try_into().unwrap());
+    let mesh = meshes.get(mesh_handle)?;
+
+    let model: [[f32; 4]; 4] = *bytemuck::from_bytes(&entity_bytes[8..72]);
+    let nc0: [f32; 4] = *bytemuck::from_bytes(&entity_bytes[72..88]);
+    let nc1: [f32; 4] = *bytemuck::from_bytes(&entity_bytes[88..104]);
+    let nc2: [f32; 4] = *bytemuck::from_bytes(&entity_bytes[104..120]);
+    let material: openreality_gpu_shared::uniforms::MaterialUniforms =
+        *bytemuck::from_bytes(&entity_bytes[120..216]);
+
+    let tex_handles: [u64; 6] = *bytemuck::from_bytes(&entity_bytes[216..264]);
+
+    // Look up texture views
+    let mut texture_views: [Option<&wgpu::TextureView>; 6] = [None; 6];
+    for (j, &handle) in tex_handles.iter().enumerate() {
+        if handle != 0 {
+            if let Some(tex) = textures.get(handle) {
+                texture_views[j] = Some(&tex.view);
+            }
+        }
+    }
+
+    let entity = passes::gbuffer::GBufferEntity {
+        mesh,
+        per_object: openreality_gpu_shared::uniforms::PerObjectUniforms {
+            model,
+            normal_matrix_col0: nc0,
+            normal_matrix_col1: nc1,
+            normal_matrix_col2: nc2,
+            _pad: [0.0; 4],
+        },
+        material,
+        texture_views,
+    };
+    Some((mesh_handle, tex_handles, entity))
+}
+
+fn entity_in_frustum(planes: &[[f32; 4]; 6], entity: &passes::gbuffer::GBufferEntity<'_>) -> bool {
+    let model = glam::Mat4::from_cols_array_2d(&entity.per_object.model);
+    entity.mesh.cull_bounds(&model).is_none_or(|bounds| sphere_in_frustum(planes, bounds.center, bounds.radius))
+}
+
+/// Decals inside the camera frustum in handle order, with their texture
+/// views (`default_view` for missing maps).
+fn visible_decals<'a>(
+    state: &'a WGPUBackendState,
+    default_view: &'a wgpu::TextureView,
+) -> Vec<passes::decal::DecalDraw<'a>> {
+    let mut decals: Vec<_> = state.decals.iter().collect();
+    decals.sort_by_key(|(handle, _)| **handle);
+    decals
+        .into_iter()
+        .filter(|(_, decal)| {
+            let bounds = decal_bounds(&decal.transform);
+            state.frame_frustum.as_ref().is_none_or(|planes| sphere_in_frustum(planes, bounds.center, bounds.radius))
+        })
+        .map(|(_, decal)| {
+            let albedo = state.textures.get(decal.albedo_texture).map(|t| &t.view);
+            let normal = state.textures.get(decal.normal_texture).map(|t| &t.view);
+            passes::decal::DecalDraw {
+                data: openreality_gpu_shared::uniforms::DecalData::new(decal.transform, &decal.params, albedo.is_some(), normal.is_some()),
+                albedo: albedo.unwrap_or(default_view),
+                normal: normal.unwrap_or(default_view),
+            }
+        })
+        .collect()
+}
+
+/// Write `[gbuffer_drawn, gbuffer_culled, shadow_drawn, shadow_culled]` from
+/// the last G-Buffer and shadow passes to `out` (4 u32).
+///
+/// # Safety
+/// `out` must be valid for writes of 4 u32.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn or_wgpu_cull_stats(backend: u64, out: *mut u32) -> i32 {
+    let backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get(backend) {
+        let stats = state.cull_stats;
+        let values = [stats.gbuffer_drawn, stats.gbuffer_culled, stats.shadow_drawn, stats.shadow_culled];
+        unsafe { std::ptr::copy_nonoverlapping(values.as_ptr(), out, values.len()) };
+        0
+    } else {
+        -1
+    }
+}
+
+/// Enable (`enabled != 0`) or disable GPU culling of instanced draws. When
+/// enabled, static instances are culled in a compute pass and drawn with
+/// `draw_indexed_indirect`; cull stats then count them as drawn. Returns -1
+/// if the device lacks indirect first-instance support.
+#[no_mangle]
+pub extern "C" fn or_wgpu_set_gpu_culling(backend: u64, enabled: i32) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        if enabled != 0 && !state.device.features().contains(wgpu::Features::INDIRECT_FIRST_INSTANCE) {
+            state.last_error = Some("GPU culling requires INDIRECT_FIRST_INSTANCE".into());
+            return -1;
+        }
+        state.gpu_culling = enabled != 0;
+        0
+    } else {
+        -1
+    }
+}
+
 /// Deferred lighting pass: fullscreen PBR lighting.
 #[no_mangle]
 pub extern "C" fn or_wgpu_lighting_pass(backend: u64) -> i32 {
@@ -649,42 +1305,70 @@ pub extern "C" fn or_wgpu_lighting_pass(backend: u64) -> i32 {
             None => { state.last_error = Some("Deferred pipeline not created".into()); return -1; }
         };
 
-        let depth_sampler = &dp.depth_sampler;
-
-        // Use SSAO if available, else use default white texture
-        let ssao_view = &dp.ssao_targets.blur.color_view;
-        let ssr_view = &dp.ssr_target.color_view;
-
-        let lighting_bg = passes::lighting::create_lighting_bind_group(
-            &state.device,
-            &dp.lighting_bgl,
-            &state.per_frame_buffer,
-            &dp.gbuffer,
-            ssao_view,
-            ssr_view,
-            &state.default_sampler,
-            depth_sampler,
-        );
+        let lighting_bg =
+            passes::lighting::create_lighting_bind_group(&state.device, dp, &state.per_frame_buffer, &state.default_sampler);
 
+        let clusters = &state.light_clusters;
         let light_data_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Light Data BG"),
             layout: &dp.light_data_bgl,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: state.light_buffer.as_entire_binding(),
-            }],
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: state.light_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: clusters.point_lights.buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: clusters.params_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 3, resource: clusters.counts_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 4, resource: clusters.indices_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 5, resource: clusters.spot_lights.buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 6, resource: clusters.area_lights.buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 7, resource: wgpu::BindingResource::TextureView(&state.shadow_atlas.view) },
+                wgpu::BindGroupEntry { binding: 8, resource: state.shadow_atlas.records.buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 9, resource: wgpu::BindingResource::Sampler(&dp.shadow_comparison_sampler) },
+                wgpu::BindGroupEntry { binding: 10, resource: dp.shadow_uniform_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 11, resource: wgpu::BindingResource::TextureView(&state.csm.depth_view) },
+                wgpu::BindGroupEntry { binding: 12, resource: wgpu::BindingResource::TextureView(&state.csm.moments_view) },
+                wgpu::BindGroupEntry { binding: 13, resource: wgpu::BindingResource::Sampler(&dp.csm_moments_sampler) },
+                wgpu::BindGroupEntry { binding: 14, resource: wgpu::BindingResource::TextureView(&state.csm.depth_view) },
+            ],
         });
 
         let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Lighting Encoder"),
         });
 
+        if let Some((view, projection)) = &state.frame_camera {
+            let params = cluster_params(view, projection, clusters.point_lights.count);
+            passes::lighting::build_light_clusters(
+                &mut encoder,
+                &state.device,
+                &state.queue,
+                clusters,
+                &dp.light_cluster_pipeline,
+                &dp.light_cluster_bgl,
+                &params,
+            );
+        }
+
+        let environment = &mut state.environment;
+        environment.update(&state.device, &state.queue, state.frame_camera.as_ref());
+        if environment.has_fog() && state.frame_camera.is_some() {
+            passes::lighting::render_volumetric_fog(
+                &mut encoder,
+                &state.device,
+                dp,
+                environment,
+                &state.per_frame_buffer,
+                &light_data_bg,
+            );
+        }
+        let environment_bg = passes::lighting::create_environment_bind_group(&state.device, &dp.environment_bgl, environment);
+
         passes::lighting::render_lighting_pass(
             &mut encoder,
             &dp.lighting_target,
             &dp.lighting_pipeline,
             &lighting_bg,
             &light_data_bg,
+            &environment_bg,
         );
 
         state.queue.submit(std::iter::once(encoder.finish()));
@@ -694,151 +1378,75 @@ pub extern "C" fn or_wgpu_lighting_pass(backend: u64) -> i32 {
     }
 }
 
-/// SSAO pass: compute ambient occlusion from G-Buffer.
-/// `params_ptr` points to SSAOParams struct.
+/// SSAO pass: ambient occlusion from the G-Buffer for the lighting pass, with
+/// the stack's SSAO settings. Clears the AO to white when SSAO is disabled.
 #[no_mangle]
-pub extern "C" fn or_wgpu_ssao_pass(backend: u64, params_ptr: *const u8) -> i32 {
+pub extern "C" fn or_wgpu_ssao_pass(backend: u64) -> i32 {
     let mut backends = BACKENDS.lock().unwrap();
     if let Some(state) = backends.get_mut(backend) {
-        let dp = match state.deferred.as_ref() {
-            Some(dp) => dp,
-            None => { state.last_error = Some("Deferred pipeline not created".into()); return -1; }
-        };
-
-        let params_size = std::mem::size_of::<openreality_gpu_shared::uniforms::SSAOParams>();
-        let data = unsafe { std::slice::from_raw_parts(params_ptr, params_size) };
-        state.queue.write_buffer(&dp.ssao_params_buffer, 0, data);
-
-        // Create SSAO bind group (matches ssao.wgsl: uniform, depth, normal, noise, 2 samplers)
-        let ssao_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("SSAO BG"),
-            layout: &dp.ssao_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.ssao_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.gbuffer.depth_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(&dp.gbuffer.normal_roughness_view) },
-                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::TextureView(&dp.ssao_noise_view) },
-                wgpu::BindGroupEntry { binding: 4, resource: wgpu::BindingResource::Sampler(&state.default_sampler) },
-                wgpu::BindGroupEntry { binding: 5, resource: wgpu::BindingResource::Sampler(&dp.depth_sampler) },
-            ],
-        });
-
-        let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("SSAO Encoder"),
-        });
-        passes::ssao::render_ssao_pass(&mut encoder, &dp.ssao_targets.ao, &dp.ssao_pipeline, &ssao_bg);
-
-        // Blur pass
-        let blur_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("SSAO Blur BG"),
-            layout: &dp.ssao_blur_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.ssao_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.ssao_targets.ao.color_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(&state.default_sampler) },
-            ],
-        });
-        passes::ssao::render_ssao_blur(&mut encoder, &dp.ssao_targets.blur, &dp.ssao_blur_pipeline, &blur_bg);
-
-        state.queue.submit(std::iter::once(encoder.finish()));
-        0
+        match post_process::render_ssao(state) {
+            Ok(()) => 0,
+            Err(e) => {
+                state.last_error = Some(e);
+                -1
+            }
+        }
     } else {
         -1
     }
 }
 
-/// SSR pass: screen-space reflections.
+/// SSR pass: screen-space reflections of the last lit image for the lighting
+/// pass. Clears the reflections when SSR is disabled.
 #[no_mangle]
-pub extern "C" fn or_wgpu_ssr_pass(backend: u64, params_ptr: *const u8) -> i32 {
+pub extern "C" fn or_wgpu_ssr_pass(backend: u64) -> i32 {
     let mut backends = BACKENDS.lock().unwrap();
     if let Some(state) = backends.get_mut(backend) {
-        let dp = match state.deferred.as_ref() {
-            Some(dp) => dp,
-            None => { state.last_error = Some("Deferred pipeline not created".into()); return -1; }
-        };
-
-        let params_size = std::mem::size_of::<openreality_gpu_shared::uniforms::SSRParams>();
-        let data = unsafe { std::slice::from_raw_parts(params_ptr, params_size) };
-        state.queue.write_buffer(&dp.ssr_params_buffer, 0, data);
-
-        let ssr_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("SSR BG"),
-            layout: &dp.ssr_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.ssr_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.gbuffer.depth_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(&dp.gbuffer.normal_roughness_view) },
-                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::TextureView(&dp.lighting_target.color_view) },
-                wgpu::BindGroupEntry { binding: 4, resource: wgpu::BindingResource::Sampler(&state.default_sampler) },
-                wgpu::BindGroupEntry { binding: 5, resource: wgpu::BindingResource::Sampler(&dp.depth_sampler) },
-            ],
-        });
-
-        let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("SSR Encoder"),
-        });
-        passes::ssr::render_ssr_pass(&mut encoder, &dp.ssr_target, &dp.ssr_pipeline, &ssr_bg);
-        state.queue.submit(std::iter::once(encoder.finish()));
-        0
+        match post_process::render_ssr(state) {
+            Ok(()) => 0,
+            Err(e) => {
+                state.last_error = Some(e);
+                -1
+            }
+        }
     } else {
         -1
     }
 }
 
-/// TAA pass: temporal anti-aliasing.
+/// Post-process pass: the enabled stack effects (TAA, bloom, DOF, motion blur,
+/// tone mapping, color grading, FXAA) in stack order, into the target read by
+/// particles and present.
 #[no_mangle]
-pub extern "C" fn or_wgpu_taa_pass(backend: u64, params_ptr: *const u8) -> i32 {
+pub extern "C" fn or_wgpu_postprocess_pass(backend: u64) -> i32 {
     let mut backends = BACKENDS.lock().unwrap();
     if let Some(state) = backends.get_mut(backend) {
-        let dp = match state.deferred.as_mut() {
-            Some(dp) => dp,
-            None => { state.last_error = Some("Deferred pipeline not created".into()); return -1; }
-        };
-
-        let params_size = std::mem::size_of::<openreality_gpu_shared::uniforms::TAAParams>();
-        let data = unsafe { std::slice::from_raw_parts(params_ptr, params_size) };
-        state.queue.write_buffer(&dp.taa_params_buffer, 0, data);
-
-        let taa_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("TAA BG"),
-            layout: &dp.taa_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.taa_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.lighting_target.color_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(&dp.taa_targets.history_view) },
-                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::TextureView(&dp.gbuffer.depth_view) },
-                wgpu::BindGroupEntry { binding: 4, resource: wgpu::BindingResource::Sampler(&state.default_sampler) },
-                wgpu::BindGroupEntry { binding: 5, resource: wgpu::BindingResource::Sampler(&dp.depth_sampler) },
-            ],
-        });
-
-        let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("TAA Encoder"),
-        });
-        passes::taa::render_taa_pass(&mut encoder, &dp.taa_targets.current, &dp.taa_pipeline, &taa_bg);
-
-        // Copy to history
-        let w = dp.taa_targets.current.width;
-        let h = dp.taa_targets.current.height;
-        passes::taa::copy_taa_to_history(
-            &mut encoder,
-            &dp.taa_targets.current.color_texture,
-            &dp.taa_targets.history_texture,
-            w,
-            h,
-        );
-
-        state.queue.submit(std::iter::once(encoder.finish()));
-        dp.taa_first_frame = false;
-        0
+        match post_process::render_post_process(state) {
+            Ok(()) => 0,
+            Err(e) => {
+                state.last_error = Some(e);
+                -1
+            }
+        }
     } else {
         -1
     }
 }
 
-/// Post-process pass: bloom + tone mapping + FXAA.
+/// Forward pass: render transparent objects over the lit image, before
+/// post-processing. Same entity format as gbuffer_pass; entities should be
+/// sorted back-to-front. With MSAA the pass renders multisampled and resolves
+/// into the lit image.
 #[no_mangle]
-pub extern "C" fn or_wgpu_postprocess_pass(backend: u64, params_ptr: *const u8) -> i32 {
+pub extern "C" fn or_wgpu_forward_pass(
+    backend: u64,
+    entities_ptr: *const u8,
+    entity_count: u32,
+    entity_stride: u32,
+) -> i32 {
+    if entity_count == 0 {
+        return 0;
+    }
     let mut backends = BACKENDS.lock().unwrap();
     if let Some(state) = backends.get_mut(backend) {
         let dp = match state.deferred.as_ref() {
@@ -846,73 +1454,100 @@ pub extern "C" fn or_wgpu_postprocess_pass(backend: u64, params_ptr: *const u8)
             None => { state.last_error = Some("Deferred pipeline not created".into()); return -1; }
         };
 
-        let params_size = std::mem::size_of::<openreality_gpu_shared::uniforms::PostProcessParams>();
-        let data = unsafe { std::slice::from_raw_parts(params_ptr, params_size) };
-        state.queue.write_buffer(&dp.pp_params_buffer, 0, data);
-
-        let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("PostProcess Encoder"),
-        });
+        let entities_data = unsafe { std::slice::from_raw_parts(entities_ptr, (entity_count * entity_stride) as usize) };
+        let entities: Vec<_> = entities_data
+            .chunks_exact(entity_stride as usize)
+            .filter_map(|entity_bytes| parse_draw_entity(&state.meshes, &state.textures, entity_bytes))
+            .map(|(_, _, entity)| entity)
+            .filter(|entity| state.frame_frustum.as_ref().is_none_or(|planes| entity_in_frustum(planes, entity)))
+            .collect();
+        if entities.is_empty() {
+            return 0;
+        }
 
-        // Bloom extract
-        let extract_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Bloom Extract BG"),
-            layout: &dp.bloom_extract_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.pp_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.lighting_target.color_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(&state.default_sampler) },
-            ],
+        let per_frame_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Forward Per-Frame BG"),
+            layout: &state.per_frame_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: state.per_frame_buffer.as_entire_binding(),
+            }],
         });
-        passes::postprocess::render_bloom_extract(&mut encoder, &dp.bloom_targets.extract, &dp.bloom_extract_pipeline, &extract_bg);
 
-        // Bloom blur horizontal
-        let blur_h_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Bloom Blur H BG"),
-            layout: &dp.bloom_blur_bgl,
+        let clusters = &state.light_clusters;
+        let environment = &state.environment;
+        let light_shadow_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Forward Light+Shadow BG"),
+            layout: &dp.forward_light_shadow_bgl,
             entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.pp_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.bloom_targets.extract.color_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(&state.default_sampler) },
+                wgpu::BindGroupEntry { binding: 0, resource: state.light_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: dp.shadow_uniform_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(&state.csm.depth_view) },
+                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::TextureView(&state.csm.moments_view) },
+                wgpu::BindGroupEntry { binding: 4, resource: wgpu::BindingResource::Sampler(&dp.csm_moments_sampler) },
+                wgpu::BindGroupEntry { binding: 5, resource: wgpu::BindingResource::TextureView(&state.csm.depth_view) },
+                wgpu::BindGroupEntry { binding: 6, resource: wgpu::BindingResource::Sampler(&dp.shadow_comparison_sampler) },
+                wgpu::BindGroupEntry { binding: 7, resource: clusters.point_lights.buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 8, resource: clusters.params_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 9, resource: clusters.counts_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 10, resource: clusters.indices_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 11, resource: clusters.spot_lights.buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 12, resource: clusters.area_lights.buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 13, resource: wgpu::BindingResource::TextureView(&state.shadow_atlas.view) },
+                wgpu::BindGroupEntry { binding: 14, resource: state.shadow_atlas.records.buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 15, resource: environment.uniform_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 16, resource: wgpu::BindingResource::TextureView(&environment.sky_view) },
+                wgpu::BindGroupEntry { binding: 17, resource: wgpu::BindingResource::TextureView(&environment.fog_volume_view) },
+                wgpu::BindGroupEntry { binding: 18, resource: wgpu::BindingResource::Sampler(&environment.sampler) },
             ],
         });
-        passes::postprocess::render_bloom_blur(&mut encoder, &dp.bloom_targets.blur_h, &dp.bloom_blur_pipeline, &blur_h_bg, "Bloom Blur H");
 
-        // Bloom blur vertical
-        let blur_v_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Bloom Blur V BG"),
-            layout: &dp.bloom_blur_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.pp_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.bloom_targets.blur_h.color_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(&state.default_sampler) },
-            ],
-        });
-        passes::postprocess::render_bloom_blur(&mut encoder, &dp.bloom_targets.blur_v, &dp.bloom_blur_pipeline, &blur_v_bg, "Bloom Blur V");
+        let resources = passes::forward::ForwardResources {
+            pipeline: &dp.forward_pipeline,
+            skinned_pipeline: &dp.forward_skinned_pipeline,
+            per_frame_bg: &per_frame_bg,
+            light_shadow_bg: &light_shadow_bg,
+            per_object_bgl: &dp.per_object_bgl,
+            skinned_object_bgl: &dp.skinned_object_bgl,
+            material_bgl: &state.material_bind_group_layout,
+            default_texture_view: &dp.default_texture_view,
+            default_sampler: &state.default_sampler,
+        };
 
-        // Bloom composite (scene + bloom)
-        let composite_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Bloom Composite BG"),
-            layout: &dp.bloom_composite_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.pp_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.lighting_target.color_view) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(&dp.bloom_targets.blur_v.color_view) },
-                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::Sampler(&state.default_sampler) },
-            ],
+        let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Forward Encoder"),
         });
-        passes::postprocess::render_bloom_composite(&mut encoder, &dp.pp_target_a, &dp.bloom_composite_pipeline, &composite_bg);
 
-        // FXAA (no uniform buffer — just texture + sampler)
-        let fxaa_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("FXAA BG"),
-            layout: &dp.fxaa_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&dp.pp_target_a.color_view) },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::Sampler(&state.default_sampler) },
-            ],
+        let msaa = dp.msaa.as_ref().zip(dp.msaa_prime_pipeline.as_ref()).and_then(|(msaa, prime_pipeline)| {
+            Some((&msaa.scene.color_view, msaa.scene.depth_view.as_ref()?, prime_pipeline))
         });
-        passes::postprocess::render_fxaa(&mut encoder, &dp.pp_target_b, &dp.fxaa_pipeline, &fxaa_bg);
+        match msaa {
+            Some((color, depth, prime_pipeline)) => {
+                let prime_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("MSAA Prime BG"),
+                    layout: &dp.msaa_prime_bgl,
+                    entries: &[
+                        wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&dp.lighting_target.color_view) },
+                        wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.gbuffer.depth_view) },
+                    ],
+                });
+                let attachments = passes::forward::ForwardAttachments {
+                    color,
+                    resolve: Some(&dp.lighting_target.color_view),
+                    depth,
+                };
+                passes::forward::render_msaa_prime(&mut encoder, &attachments, prime_pipeline, &prime_bg);
+                passes::forward::render_forward_pass(&mut encoder, &state.device, &attachments, &resources, &entities);
+            }
+            None => {
+                let attachments = passes::forward::ForwardAttachments {
+                    color: &dp.lighting_target.color_view,
+                    resolve: None,
+                    depth: &dp.gbuffer.depth_view,
+                };
+                passes::forward::render_forward_pass(&mut encoder, &state.device, &attachments, &resources, &entities);
+            }
+        }
 
         state.queue.submit(std::iter::once(encoder.finish()));
         0
@@ -921,29 +1556,12 @@ pub extern "C" fn or_wgpu_postprocess_pass(backend: u64, params_ptr: *const u8)
     }
 }
 
-/// Forward pass: render transparent objects.
-/// Same entity format as gbuffer_pass.
-#[no_mangle]
-pub extern "C" fn or_wgpu_forward_pass(
-    backend: u64,
-    entities_ptr: *const u8,
-    entity_count: u32,
-    entity_stride: u32,
-) -> i32 {
-    let mut backends = BACKENDS.lock().unwrap();
-    if let Some(_state) = backends.get_mut(backend) {
-        // Forward pass uses same entity parsing as gbuffer_pass
-        // For now return success — will be filled in when Julia side packs data
-        let _ = (entities_ptr, entity_count, entity_stride);
-        0
-    } else {
-        -1
-    }
-}
-
-/// Particle pass: render particle billboard quads.
-/// `vertices_ptr` points to interleaved vertex data (pos3 + uv2 + color4 = 9 floats per vertex).
-/// `view_ptr` and `proj_ptr` point to mat4x4<f32>.
+/// Particle pass: render one emitter's billboard quads into the post-processed
+/// HDR target. `vertices_ptr` points to interleaved vertex data
+/// (pos3 + uv2 + color4 = 9 floats per vertex).
+/// `blend_mode`: 0 = alpha, 1 = additive, 2 = premultiplied.
+/// `soft_distance` > 0 fades quads near opaque geometry; `texture` is a sprite
+/// texture handle or 0 for the round procedural sprite.
 #[no_mangle]
 pub extern "C" fn or_wgpu_particle_pass(
     backend: u64,
@@ -951,6 +1569,9 @@ pub extern "C" fn or_wgpu_particle_pass(
     vertex_count: u32,
     view_ptr: *const f32,
     proj_ptr: *const f32,
+    blend_mode: u32,
+    soft_distance: f32,
+    texture: u64,
 ) -> i32 {
     if vertex_count == 0 {
         return 0;
@@ -979,117 +1600,725 @@ pub extern "C" fn or_wgpu_particle_pass(
 
         state.queue.write_buffer(&dp.particle_vbo, 0, bytemuck::cast_slice(vertex_data));
 
-        // Upload view + proj
-        let view_data = unsafe { std::slice::from_raw_parts(view_ptr, 16) };
-        let proj_data = unsafe { std::slice::from_raw_parts(proj_ptr, 16) };
-        let mut uniform_data = [0f32; 32];
-        uniform_data[..16].copy_from_slice(view_data);
-        uniform_data[16..32].copy_from_slice(proj_data);
-        state.queue.write_buffer(&dp.particle_uniform_buffer, 0, bytemuck::cast_slice(&uniform_data));
+        let sprite_view = match texture {
+            0 => None,
+            handle => match state.textures.get(handle) {
+                Some(t) => Some(&t.view),
+                None => { state.last_error = Some("Invalid texture handle".into()); return -1; }
+            },
+        };
+        let uniforms = particle_draw_uniforms(view_ptr, proj_ptr, blend_mode, soft_distance, sprite_view.is_some());
+        state.queue.write_buffer(&dp.particle_uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
 
         let particle_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Particle BG"),
             layout: &dp.particle_bgl,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: dp.particle_uniform_buffer.as_entire_binding(),
-            }],
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: dp.particle_uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&dp.gbuffer.depth_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(sprite_view.unwrap_or(&dp.default_texture_view)),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::Sampler(&state.default_sampler),
+                },
+            ],
         });
 
-        // Get surface texture for rendering
-        let output = match state.surface.get_current_texture() {
-            Ok(o) => o,
-            Err(e) => { state.last_error = Some(format!("Surface error: {e}")); return -1; }
-        };
-        let surface_view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
-
         let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Particle Encoder"),
         });
 
+        let mode = (blend_mode as usize).min(dp.particle_pipelines.len() - 1);
         passes::particles::render_particle_pass(
             &mut encoder,
-            &surface_view,
+            &dp.pp_target_b.color_view,
             &dp.gbuffer.depth_view,
-            &dp.particle_pipeline,
+            &dp.particle_pipelines[mode],
             &particle_bg,
             &dp.particle_vbo,
             vertex_count,
-            false, // TODO: per-emitter additive blending
         );
 
         state.queue.submit(std::iter::once(encoder.finish()));
-        output.present();
         0
     } else {
         -1
     }
 }
 
-/// UI pass: render 2D UI overlay.
-/// `vertices_ptr` points to interleaved vertex data (pos2 + uv2 + color4 = 8 floats per vertex).
-/// `draw_cmds_ptr` points to packed UIDrawCommand array.
+/// Create a GPU-simulated particle emitter. `params_ptr` points to a packed
+/// `ParticleEmitterParams` (432 bytes). Returns emitter handle (> 0) or 0 on failure.
 #[no_mangle]
-pub extern "C" fn or_wgpu_ui_pass(
-    backend: u64,
-    vertices_ptr: *const f32,
-    vertex_count: u32,
-    screen_width: f32,
-    screen_height: f32,
-) -> i32 {
+pub extern "C" fn or_wgpu_create_particle_emitter(backend: u64, params_ptr: *const u8) -> u64 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        let params = read_particle_params(params_ptr);
+        match state.create_particle_emitter(params) {
+            Ok(handle) => handle,
+            Err(e) => {
+                state.last_error = Some(e);
+                0
+            }
+        }
+    } else {
+        0
+    }
+}
+
+/// Replace an emitter's parameters. Returns 0 on success, -1 on failure.
+#[no_mangle]
+pub extern "C" fn or_wgpu_update_particle_emitter(backend: u64, emitter: u64, params_ptr: *const u8) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        let params = read_particle_params(params_ptr);
+        match state.update_particle_emitter(emitter, params) {
+            Ok(()) => 0,
+            Err(e) => {
+                state.last_error = Some(e);
+                -1
+            }
+        }
+    } else {
+        -1
+    }
+}
+
+/// Destroy a GPU particle emitter.
+#[no_mangle]
+pub extern "C" fn or_wgpu_destroy_particle_emitter(backend: u64, emitter: u64) {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        state.destroy_particle_emitter(emitter);
+    }
+}
+
+/// Place an emitter (`matrix_ptr`: column-major mat4x4<f32>) and toggle
+/// continuous emission (`active` = 0 stops spawning but lets live particles
+/// finish). Returns 0 on success, -1 on failure.
+///
+/// # Safety
+/// `matrix_ptr` must point to 16 f32.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn or_wgpu_set_particle_emitter_transform(
+    backend: u64,
+    emitter: u64,
+    matrix_ptr: *const f32,
+    active: i32,
+) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        match state.particle_emitters.get_mut(emitter) {
+            Some(e) => {
+                let m = unsafe { std::slice::from_raw_parts(matrix_ptr, 16) };
+                e.transform.copy_from_slice(m);
+                e.active = active != 0;
+                0
+            }
+            None => {
+                state.last_error = Some("Invalid particle emitter handle".into());
+                -1
+            }
+        }
+    } else {
+        -1
+    }
+}
+
+/// Emit from the surface of a triangle mesh in emitter space.
+/// `positions` is xyz-interleaved, `indices` a triangle list.
+/// Returns 0 on success, -1 on failure.
+///
+/// # Safety
+/// `positions` must point to `num_vertices * 3` f32 and `indices` to
+/// `num_indices` u32.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn or_wgpu_set_particle_emitter_mesh(
+    backend: u64,
+    emitter: u64,
+    positions: *const f32,
+    num_vertices: u32,
+    indices: *const u32,
+    num_indices: u32,
+) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        let pos = unsafe { std::slice::from_raw_parts(positions, num_vertices as usize * 3) };
+        let idx = unsafe { std::slice::from_raw_parts(indices, num_indices as usize) };
+        match state.set_particle_emitter_mesh(emitter, pos, idx) {
+            Ok(()) => 0,
+            Err(e) => {
+                state.last_error = Some(e);
+                -1
+            }
+        }
+    } else {
+        -1
+    }
+}
+
+/// Attach a sprite texture (or texture sheet) to an emitter; 0 detaches it.
+/// Returns 0 on success, -1 on failure.
+#[no_mangle]
+pub extern "C" fn or_wgpu_set_particle_emitter_texture(backend: u64, emitter: u64, texture: u64) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        match state.set_particle_emitter_texture(emitter, texture) {
+            Ok(()) => 0,
+            Err(e) => {
+                state.last_error = Some(e);
+                -1
+            }
+        }
+    } else {
+        -1
+    }
+}
+
+/// Queue `count` particles to spawn on the emitter's next simulation step.
+#[no_mangle]
+pub extern "C" fn or_wgpu_burst_particle_emitter(backend: u64, emitter: u64, count: u32) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        match state.particle_emitters.get_mut(emitter) {
+            Some(e) => {
+                e.pending_burst = e.pending_burst.saturating_add(count);
+                0
+            }
+            None => {
+                state.last_error = Some("Invalid particle emitter handle".into());
+                -1
+            }
+        }
+    } else {
+        -1
+    }
+}
+
+/// Advance every GPU emitter by `dt`: spawn, integrate, collide, sort
+/// back-to-front from the camera and write indirect draw args.
+/// `view_ptr` and `proj_ptr` point to mat4x4<f32> (the current frame's camera,
+/// matching the G-buffer used for depth collisions).
+///
+/// # Safety
+/// `view_ptr` and `proj_ptr` must each point to 16 f32.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn or_wgpu_simulate_particles(backend: u64, dt: f32, view_ptr: *const f32, proj_ptr: *const f32) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        let view = glam::Mat4::from_cols_slice(unsafe { std::slice::from_raw_parts(view_ptr, 16) });
+        let proj = glam::Mat4::from_cols_slice(unsafe { std::slice::from_raw_parts(proj_ptr, 16) });
+        state.simulate_particles(dt, view, proj);
+        0
+    } else {
+        -1
+    }
+}
+
+/// GPU particle pass: draw every emitter into the post-processed HDR target
+/// with indirect draws, depth-tested against the G-buffer.
+/// `view_ptr` and `proj_ptr` point to mat4x4<f32>.
+#[no_mangle]
+pub extern "C" fn or_wgpu_gpu_particle_pass(backend: u64, view_ptr: *const f32, proj_ptr: *const f32) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        let (Some(pipelines), Some(dp)) = (state.gpu_particles.as_ref(), state.deferred.as_ref()) else {
+            return 0; // Nothing to draw yet
+        };
+        if state.particle_emitters.len() == 0 {
+            return 0;
+        }
+
+        // Blend mode, soft distance and texture come from each emitter's params
+        let uniforms = particle_draw_uniforms(view_ptr, proj_ptr, 0, 0.0, false);
+        state.queue.write_buffer(&pipelines.draw_buffer, 0, bytemuck::bytes_of(&uniforms));
+
+        let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("GPU Particle Encoder"),
+        });
+        gpu_particles::render_gpu_particles(
+            &mut encoder,
+            &state.device,
+            &dp.pp_target_b.color_view,
+            &dp.gbuffer.depth_view,
+            pipelines,
+            state.particle_emitters.iter().map(|(_, e)| e),
+        );
+        state.queue.submit(std::iter::once(encoder.finish()));
+        0
+    } else {
+        -1
+    }
+}
+
+fn particle_draw_uniforms(
+    view_ptr: *const f32,
+    proj_ptr: *const f32,
+    blend_mode: u32,
+    soft_distance: f32,
+    has_texture: bool,
+) -> openreality_gpu_shared::uniforms::ParticleDrawUniforms {
+    let view = glam::Mat4::from_cols_slice(unsafe { std::slice::from_raw_parts(view_ptr, 16) });
+    let proj = glam::Mat4::from_cols_slice(unsafe { std::slice::from_raw_parts(proj_ptr, 16) });
+    openreality_gpu_shared::uniforms::ParticleDrawUniforms {
+        view: view.to_cols_array_2d(),
+        projection: proj.to_cols_array_2d(),
+        inv_projection: proj.inverse().to_cols_array_2d(),
+        soft_distance,
+        blend_mode,
+        has_texture: has_texture as u32,
+        _pad1: 0,
+    }
+}
+
+fn read_particle_params(params_ptr: *const u8) -> openreality_gpu_shared::uniforms::ParticleEmitterParams {
+    let size = std::mem::size_of::<openreality_gpu_shared::uniforms::ParticleEmitterParams>();
+    let bytes = unsafe { std::slice::from_raw_parts(params_ptr, size) };
+    bytemuck::pod_read_unaligned(bytes)
+}
+
+/// UI pass: upload the 2D UI overlay, drawn over the frame by `or_wgpu_present`.
+/// `vertices_ptr` points to interleaved vertex data (pos2 + uv2 + color4 = 8 floats
+/// per vertex) of solid-color triangles, in pixels with a top-left origin.
+#[no_mangle]
+pub extern "C" fn or_wgpu_ui_pass(
+    backend: u64,
+    vertices_ptr: *const f32,
+    vertex_count: u32,
+    screen_width: f32,
+    screen_height: f32,
+) -> i32 {
     if vertex_count == 0 {
         return 0;
     }
     let mut backends = BACKENDS.lock().unwrap();
     if let Some(state) = backends.get_mut(backend) {
-        let _dp = match state.deferred.as_ref() {
+        let dp = match state.deferred.as_mut() {
             Some(dp) => dp,
             None => { state.last_error = Some("Deferred pipeline not created".into()); return -1; }
         };
-        // UI rendering will be wired up with draw commands
-        let _ = (vertices_ptr, vertex_count, screen_width, screen_height);
+
+        let float_count = (vertex_count * 8) as usize;
+        let vertex_data = unsafe { std::slice::from_raw_parts(vertices_ptr, float_count) };
+        let byte_size = (float_count * 4) as u64;
+
+        // Resize VBO if needed
+        if byte_size > dp.ui_vbo_size {
+            dp.ui_vbo = state.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("UI VBO"),
+                size: byte_size,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            dp.ui_vbo_size = byte_size;
+        }
+        state.queue.write_buffer(&dp.ui_vbo, 0, bytemuck::cast_slice(vertex_data));
+
+        let projection = glam::Mat4::orthographic_rh(0.0, screen_width, screen_height, 0.0, -1.0, 1.0);
+        state.queue.write_buffer(&dp.ui_uniform_buffer, 0, bytemuck::bytes_of(&openreality_gpu_shared::uniforms::UIUniforms {
+            projection: projection.to_cols_array_2d(),
+            has_texture: 0,
+            is_font: 0,
+            _pad1: 0,
+            _pad2: 0,
+        }));
+        dp.ui_vertex_count = vertex_count;
         0
     } else {
         -1
     }
 }
 
-/// Present: blit the final post-processed result to the swapchain.
+/// Present: upscale the final post-processed result to the output size when
+/// rendering below it, encode it for the swapchain (or the headless target)
+/// and draw the UI overlay on top.
 #[no_mangle]
 pub extern "C" fn or_wgpu_present(backend: u64) -> i32 {
     let mut backends = BACKENDS.lock().unwrap();
     if let Some(state) = backends.get_mut(backend) {
-        let dp = match state.deferred.as_ref() {
+        let dp = match state.deferred.as_mut() {
             Some(dp) => dp,
             None => { state.last_error = Some("Deferred pipeline not created".into()); return -1; }
         };
+        let ui_vertex_count = std::mem::take(&mut dp.ui_vertex_count);
+        let dp = &*dp;
 
-        let output = match state.surface.get_current_texture() {
-            Ok(o) => o,
-            Err(e) => { state.last_error = Some(format!("Surface error: {e}")); return -1; }
+        let frame = match state.output.acquire() {
+            Ok(frame) => frame,
+            Err(e) => { state.last_error = Some(e); return -1; }
+        };
+
+        let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Present Encoder"),
+        });
+
+        // Upscale from the internal render resolution first
+        let scene_view = match &dp.upscale_target {
+            Some(upscale_target) => {
+                state.queue.write_buffer(&dp.upscale_params_buffer, 0, bytemuck::bytes_of(&openreality_gpu_shared::uniforms::UpscaleParams {
+                    input_size: [dp.gbuffer.width as f32, dp.gbuffer.height as f32],
+                    output_size: [state.width as f32, state.height as f32],
+                }));
+                let upscale_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Upscale BG"),
+                    layout: &dp.upscale_bgl,
+                    entries: &[
+                        wgpu::BindGroupEntry { binding: 0, resource: dp.upscale_params_buffer.as_entire_binding() },
+                        wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.pp_target_b.color_view) },
+                        wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(&state.default_sampler) },
+                    ],
+                });
+                passes::postprocess::render_fullscreen_effect(
+                    &mut encoder,
+                    upscale_target,
+                    &dp.upscale_pipeline,
+                    &upscale_bg,
+                    "FSR EASU Pass",
+                );
+                &upscale_target.color_view
+            }
+            None => &dp.pp_target_b.color_view,
         };
-        let surface_view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
 
+        state.queue.write_buffer(&dp.output_params_buffer, 0, bytemuck::bytes_of(&openreality_gpu_shared::uniforms::OutputParams {
+            encoding: state.output_encoding,
+            gamma: state.post_process.tone_map.gamma,
+            paper_white_nits: state.display.paper_white_nits,
+            max_nits: state.display.max_nits,
+            sharpness: if dp.upscale_target.is_some() { state.resolution.sharpness } else { 0.0 },
+            _pad1: 0.0,
+            _pad2: 0.0,
+            _pad3: 0.0,
+        }));
         let present_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Present BG"),
             layout: &dp.present_bgl,
             entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: dp.pp_params_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.pp_target_b.color_view) },
+                wgpu::BindGroupEntry { binding: 0, resource: dp.output_params_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(scene_view) },
                 wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(&state.default_sampler) },
             ],
         });
 
-        let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Present Encoder"),
+        let ui_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("UI BG"),
+            layout: &dp.ui_bgl,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: dp.ui_uniform_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&dp.default_texture_view) },
+                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(&state.default_sampler) },
+            ],
+        });
+        let ui = (ui_vertex_count > 0).then_some(passes::ui::UIDraw {
+            pipeline: &dp.ui_pipeline,
+            bind_group: &ui_bg,
+            vertex_buffer: &dp.ui_vbo,
+            vertex_count: ui_vertex_count,
         });
 
-        passes::present::render_present_pass(&mut encoder, &surface_view, &dp.present_pipeline, &present_bg);
+        // With MSAA the UI renders multisampled and resolves to the surface
+        let (target, resolve_target) = match &dp.msaa {
+            Some(msaa) => (&msaa.output.color_view, Some(&frame.view)),
+            None => (&frame.view, None),
+        };
+        passes::present::render_present_pass(
+            &mut encoder,
+            target,
+            resolve_target,
+            &dp.present_pipeline,
+            &present_bg,
+            ui.as_ref(),
+        );
 
+        state.frame_timer.end_frame(&mut encoder);
         state.queue.submit(std::iter::once(encoder.finish()));
-        output.present();
+        state.frame_timer.frame_submitted();
+        frame.present();
         0
     } else {
         -1
     }
 }
+
+// ============================================================
+// FFI: Resolution
+// ============================================================
+
+fn update_resolution(backend: u64, update: impl FnOnce(&mut ResolutionSettings)) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        let mut settings = state.resolution;
+        update(&mut settings);
+        match state.set_resolution(settings) {
+            Ok(()) => 0,
+            Err(e) => {
+                state.last_error = Some(e);
+                -1
+            }
+        }
+    } else {
+        -1
+    }
+}
+
+/// Render the scene at `scale` (0.25-1) times the output size, upscaled with
+/// FSR1 and RCAS sharpening of `sharpness` (0-1). Used while dynamic
+/// resolution is off. Returns 0 on success, -1 on invalid values.
+#[no_mangle]
+pub extern "C" fn or_wgpu_set_render_scale(backend: u64, scale: f32, sharpness: f32) -> i32 {
+    update_resolution(backend, |settings| {
+        settings.scale = scale;
+        settings.sharpness = sharpness;
+    })
+}
+
+/// Enable dynamic resolution: the render scale follows the GPU frame time
+/// towards `target_frame_ms`, within `[min_scale, max_scale]`.
+#[no_mangle]
+pub extern "C" fn or_wgpu_set_dynamic_resolution(
+    backend: u64,
+    enabled: i32,
+    target_frame_ms: f32,
+    min_scale: f32,
+    max_scale: f32,
+) -> i32 {
+    update_resolution(backend, |settings| {
+        settings.dynamic = enabled != 0;
+        settings.target_frame_ms = target_frame_ms;
+        settings.min_scale = min_scale;
+        settings.max_scale = max_scale;
+    })
+}
+
+/// Set the MSAA sample count (1, 2, 4 or 8) of the forward and UI passes.
+/// Returns -1 if the adapter does not support it.
+#[no_mangle]
+pub extern "C" fn or_wgpu_set_msaa_samples(backend: u64, samples: u32) -> i32 {
+    update_resolution(backend, |settings| settings.msaa_samples = samples)
+}
+
+/// The current render scale, or -1 for an invalid backend.
+#[no_mangle]
+pub extern "C" fn or_wgpu_render_scale(backend: u64) -> f32 {
+    let backends = BACKENDS.lock().unwrap();
+    backends.get(backend).map_or(-1.0, |state| state.render_scale)
+}
+
+// ============================================================
+// FFI: Sky and fog
+// ============================================================
+
+/// Read a `T` of `size` bytes from `ptr`, or describe the size mismatch.
+fn read_settings<T: bytemuck::Pod>(ptr: *const u8, size: u32, name: &str) -> Result<T, String> {
+    let expected = std::mem::size_of::<T>();
+    if size as usize != expected {
+        return Err(format!("{name} is {expected} bytes, got {size}"));
+    }
+    let data = unsafe { std::slice::from_raw_parts(ptr, expected) };
+    Ok(bytemuck::pod_read_unaligned(data))
+}
+
+/// Set the physically based sky: `settings_ptr` points to an
+/// `AtmosphereSettings` struct of `settings_size` bytes. When enabled, the
+/// sky replaces the flat background and lights the scene as IBL; it is
+/// rebaked when the settings or the first directional light change.
+/// Returns 0 on success, -1 on failure.
+#[no_mangle]
+pub extern "C" fn or_wgpu_set_atmosphere(backend: u64, settings_ptr: *const u8, settings_size: u32) -> i32 {
+    use openreality_gpu_shared::atmosphere::AtmosphereSettings;
+
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        let result = read_settings::<AtmosphereSettings>(settings_ptr, settings_size, "AtmosphereSettings")
+            .and_then(|settings| settings.validate().map(|()| settings));
+        match result {
+            Ok(settings) => {
+                state.environment.set_atmosphere(settings);
+                0
+            }
+            Err(e) => {
+                state.last_error = Some(e);
+                -1
+            }
+        }
+    } else {
+        -1
+    }
+}
+
+/// Set the volumetric fog: `settings_ptr` points to a `FogSettings` struct
+/// of `settings_size` bytes. Returns 0 on success, -1 on failure.
+#[no_mangle]
+pub extern "C" fn or_wgpu_set_fog(backend: u64, settings_ptr: *const u8, settings_size: u32) -> i32 {
+    use openreality_gpu_shared::atmosphere::FogSettings;
+
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        let result = read_settings::<FogSettings>(settings_ptr, settings_size, "FogSettings")
+            .and_then(|settings| settings.validate().map(|()| settings));
+        match result {
+            Ok(settings) => {
+                state.environment.set_fog(&state.device, settings);
+                0
+            }
+            Err(e) => {
+                state.last_error = Some(e);
+                -1
+            }
+        }
+    } else {
+        -1
+    }
+}
+
+// ============================================================
+// FFI: Decals
+// ============================================================
+
+/// Read a decal: `transform_ptr` is a column-major mat4x4<f32> mapping the
+/// unit box [-0.5, 0.5]³ to the world (projected along its -z axis) and
+/// `params_ptr` a `DecalParams` struct of `params_size` bytes.
+fn read_decal(
+    transform_ptr: *const f32,
+    params_ptr: *const u8,
+    params_size: u32,
+    albedo_texture: u64,
+    normal_texture: u64,
+) -> Result<backend::Decal, String> {
+    let params = read_settings(params_ptr, params_size, "DecalParams")?;
+    let m = unsafe { std::slice::from_raw_parts(transform_ptr, 16) };
+    Ok(backend::Decal {
+        transform: glam::Mat4::from_cols_slice(m),
+        params,
+        albedo_texture,
+        normal_texture,
+    })
+}
+
+/// Add a decal that blends into the G-Buffer albedo, normals and roughness
+/// before lighting (see `read_decal` for the box and settings). Texture
+/// handles of 0 leave out that map. Decals added later draw on top.
+/// Returns the decal handle (> 0) or 0 on failure.
+#[no_mangle]
+pub extern "C" fn or_wgpu_add_decal(
+    backend: u64,
+    transform_ptr: *const f32,
+    params_ptr: *const u8,
+    params_size: u32,
+    albedo_texture: u64,
+    normal_texture: u64,
+) -> u64 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        let result = read_decal(transform_ptr, params_ptr, params_size, albedo_texture, normal_texture)
+            .and_then(|decal| state.add_decal(decal));
+        match result {
+            Ok(handle) => handle,
+            Err(e) => {
+                state.last_error = Some(e);
+                0
+            }
+        }
+    } else {
+        0
+    }
+}
+
+/// Replace a decal's box, settings and textures. Returns 0 on success, -1 on failure.
+#[no_mangle]
+pub extern "C" fn or_wgpu_update_decal(
+    backend: u64,
+    decal: u64,
+    transform_ptr: *const f32,
+    params_ptr: *const u8,
+    params_size: u32,
+    albedo_texture: u64,
+    normal_texture: u64,
+) -> i32 {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        let result = read_decal(transform_ptr, params_ptr, params_size, albedo_texture, normal_texture)
+            .and_then(|d| state.update_decal(decal, d));
+        match result {
+            Ok(()) => 0,
+            Err(e) => {
+                state.last_error = Some(e);
+                -1
+            }
+        }
+    } else {
+        -1
+    }
+}
+
+/// Remove a decal.
+#[no_mangle]
+pub extern "C" fn or_wgpu_remove_decal(backend: u64, decal: u64) {
+    let mut backends = BACKENDS.lock().unwrap();
+    if let Some(state) = backends.get_mut(backend) {
+        state.remove_decal(decal);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openreality_gpu_shared::uniforms::PerFrameUniforms;
+
+    /// Runs on whatever adapter is available, including GL on llvmpipe.
+    #[test]
+    fn test_headless_frame_reads_back() {
+        let (width, height) = (8, 4);
+        let backend = or_wgpu_initialize_headless(width, height);
+        if backend == 0 {
+            eprintln!("skipping: no GPU adapter");
+            return;
+        }
+        let check = |status: i32, step: &str| {
+            let error = BACKENDS.lock().unwrap().get_mut(backend).and_then(|s| s.last_error.clone());
+            assert_eq!(status, 0, "{step}: {error:?}");
+        };
+        check(or_wgpu_create_deferred_pipeline(backend, width, height), "create pipeline");
+
+        let eye = glam::Vec3::new(0.0, 1.0, 5.0);
+        let view = glam::Mat4::look_at_rh(eye, glam::Vec3::ZERO, glam::Vec3::Y);
+        let projection = glam::Mat4::perspective_rh(1.0, 2.0, 0.1, 100.0);
+        let frame = PerFrameUniforms {
+            view: view.to_cols_array_2d(),
+            projection: projection.to_cols_array_2d(),
+            inv_view_proj: (projection * view).inverse().to_cols_array_2d(),
+            camera_pos: eye.extend(1.0).to_array(),
+            ..bytemuck::Zeroable::zeroed()
+        };
+        let frame = bytemuck::bytes_of(&frame);
+        check(or_wgpu_begin_frame(backend, frame.as_ptr(), frame.len() as u32), "begin frame");
+        check(or_wgpu_gbuffer_pass(backend, [0u8; 0].as_ptr(), 0, 0), "G-Buffer pass");
+        check(or_wgpu_lighting_pass(backend), "lighting pass");
+        check(or_wgpu_postprocess_pass(backend), "post-process pass");
+        check(or_wgpu_present(backend), "present");
+
+        let mut texels = vec![f32::NAN; (width * height * 4) as usize];
+        assert_eq!(or_wgpu_read_output(backend, texels.as_mut_ptr(), texels.len() as u32 - 1), -1);
+        let written = or_wgpu_read_output(backend, texels.as_mut_ptr(), texels.len() as u32);
+        or_wgpu_shutdown(backend);
+        assert_eq!(written as usize, texels.len());
+
+        // Nothing was drawn, so every pixel is the tone mapped flat gray background
+        let background = &texels[..4];
+        assert!(background[0] > 0.0 && background[0] <= 0.1, "{background:?}");
+        assert_eq!(background[..3], [background[0]; 3]);
+        assert_eq!(background[3], 1.0);
+        assert!(texels.chunks_exact(4).all(|t| t == background), "{texels:?}");
+    }
+}
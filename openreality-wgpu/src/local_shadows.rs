@@ -0,0 +1,337 @@
+//! Point and spot light shadows, rendered into tiles of one shadow atlas.
+//! Tiles are budgeted every frame by `shadow_atlas::allocate_tiles`; a tile
+//! whose light, placement and static casters are unchanged since it was last
+//! drawn keeps its depth and is not rendered again.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use glam::{Mat4, Vec3};
+use openreality_gpu_shared::batching::group_instances;
+use openreality_gpu_shared::math::{extract_frustum_planes, sphere_in_frustum, BoundingSphere};
+use openreality_gpu_shared::shadow_atlas::{
+    self, AtlasConfig, AtlasTile, ShadowRequest, CUBE_FACES, LIGHT_KIND_POINT, LIGHT_KIND_SPOT,
+};
+use openreality_gpu_shared::uniforms::{LocalShadowData, PerFrameUniforms, PerObjectUniforms};
+
+use crate::backend::{GPUMesh, LightBuffer, WGPUBackendState};
+use crate::instancing::Instances;
+use crate::passes::shadow::{render_shadow_depth, ShadowCasters, ShadowTarget};
+
+/// Smallest tile handed to a light, in texels.
+pub const MIN_TILE: u32 = 64;
+
+/// Tiles last drawn for one light, and what they were drawn from.
+struct CachedShadow {
+    light_hash: u64,
+    tiles: Vec<AtlasTile>,
+    /// Static casters of each tile, or `None` when a skinned mesh was inside
+    /// and the tile has to be drawn every frame.
+    casters_hash: Vec<Option<u64>>,
+}
+
+/// Depth atlas of the local light shadows and the records the lighting
+/// shaders read them through.
+pub struct ShadowAtlas {
+    pub view: wgpu::TextureView,
+    /// `None` for the 1x1 placeholder bound until `or_wgpu_create_shadow_atlas`.
+    pub config: Option<AtlasConfig>,
+    /// `LocalShadowData` records, indexed by the lights' `shadow_index`.
+    pub records: LightBuffer,
+    /// Indices of the shadow-casting point and spot lights, by light kind.
+    pub casters: [Vec<u32>; 2],
+    cache: HashMap<(u32, u32), CachedShadow>,
+}
+
+impl ShadowAtlas {
+    pub fn placeholder(device: &wgpu::Device) -> Self {
+        Self::create(device, 1, None)
+    }
+
+    /// Atlas of `size`² texels with tiles of up to `max_tile`; both powers of two.
+    pub fn new(device: &wgpu::Device, size: u32, max_tile: u32) -> Self {
+        let config = AtlasConfig { size, min_tile: MIN_TILE.min(max_tile), max_tile };
+        Self::create(device, size, Some(config))
+    }
+
+    fn create(device: &wgpu::Device, size: u32, config: Option<AtlasConfig>) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Shadow Atlas"),
+            size: wgpu::Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Depth32Float,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Self {
+            view,
+            config,
+            records: LightBuffer::new::<LocalShadowData>(device, "Local Shadow Records"),
+            casters: [Vec::new(), Vec::new()],
+            cache: HashMap::new(),
+        }
+    }
+}
+
+/// A shadow-casting light of this frame and the view-projection of each of
+/// its tiles, with that projection's field of view.
+struct ShadowedLight {
+    key: (u32, u32),
+    hash: u64,
+    request: ShadowRequest,
+    views: Vec<(Mat4, f32)>,
+}
+
+/// One atlas tile to draw this frame.
+struct TileDraw {
+    tile: AtlasTile,
+    view_proj: Mat4,
+    /// Indices into the frame's caster list.
+    casters: Vec<usize>,
+}
+
+fn hash_floats(values: &[f32]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for v in values {
+        v.to_bits().hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Fraction of the screen covered by a light of `range` at `position`.
+fn screen_coverage(camera: Option<(Vec3, f32)>, position: Vec3, range: f32) -> f32 {
+    let Some((eye, focal)) = camera else { return 1.0 };
+    let dist = eye.distance(position);
+    if dist <= range {
+        1.0
+    } else {
+        (range * focal / dist).min(1.0)
+    }
+}
+
+/// Mesh handle, mesh, model matrix and world bounds of a shadow caster.
+/// Skinned casters have no bounds and are never culled.
+type Caster<'a> = (u64, &'a GPUMesh, [[f32; 4]; 4], Option<BoundingSphere>);
+
+/// Assign atlas tiles to the shadow-casting lights, draw the tiles that are
+/// stale and upload the shadow records and the lights' `shadow_index`.
+/// `model_data` holds one column-major model matrix per mesh handle.
+pub fn render_local_shadows(state: &mut WGPUBackendState, mesh_handles: &[u64], model_data: &[f32]) -> Result<(), String> {
+    let dp = state.deferred.as_ref().ok_or("Deferred pipeline not created")?;
+    let atlas = &mut state.shadow_atlas;
+    let config = atlas.config.ok_or("Shadow atlas not created")?;
+    let clusters = &mut state.light_clusters;
+    let camera = state
+        .frame_camera
+        .map(|(view, projection)| (view.inverse().w_axis.truncate(), projection.y_axis.y));
+
+    let mut lights = Vec::new();
+    for &index in &atlas.casters[LIGHT_KIND_POINT as usize] {
+        let Some(light) = clusters.point_light_data.get(index as usize) else { continue };
+        let position = Vec3::from_slice(&light.position[..3]);
+        let fov = std::f32::consts::FRAC_PI_2;
+        lights.push(ShadowedLight {
+            key: (LIGHT_KIND_POINT, index),
+            hash: hash_floats(&[position.x, position.y, position.z, light.range]),
+            request: ShadowRequest {
+                coverage: screen_coverage(camera, position, light.range),
+                faces: CUBE_FACES as u32,
+            },
+            views: (0..CUBE_FACES)
+                .map(|face| (shadow_atlas::cube_face_view_proj(position, light.range, face), fov))
+                .collect(),
+        });
+    }
+    for &index in &atlas.casters[LIGHT_KIND_SPOT as usize] {
+        let Some(light) = clusters.spot_light_data.get(index as usize) else { continue };
+        let position = Vec3::from_slice(&light.position[..3]);
+        let direction = Vec3::from_slice(&light.direction[..3]);
+        let fov = (2.0 * light.outer_cos.clamp(-1.0, 1.0).acos()).clamp(0.01, 3.0);
+        let view_proj = shadow_atlas::spot_view_proj(position, direction, light.outer_cos, light.range);
+        lights.push(ShadowedLight {
+            key: (LIGHT_KIND_SPOT, index),
+            hash: hash_floats(&[
+                position.x, position.y, position.z, direction.x, direction.y, direction.z, light.outer_cos, light.range,
+            ]),
+            request: ShadowRequest {
+                coverage: screen_coverage(camera, position, light.range),
+                faces: 1,
+            },
+            views: vec![(view_proj, fov)],
+        });
+    }
+
+    let requests: Vec<ShadowRequest> = lights.iter().map(|l| l.request).collect();
+    let tiles = shadow_atlas::allocate_tiles(&requests, &config);
+
+    // World bounds of every entity with a known mesh
+    let mut entities: Vec<Caster> = Vec::new();
+    for (i, &handle) in mesh_handles.iter().enumerate() {
+        let (Some(mesh), Some(cols)) = (state.meshes.get(handle), model_data.get(i * 16..i * 16 + 16)) else {
+            continue;
+        };
+        let model = Mat4::from_cols_slice(cols);
+        entities.push((handle, mesh, model.to_cols_array_2d(), mesh.cull_bounds(&model)));
+    }
+
+    for light in &mut clusters.point_light_data {
+        light.shadow_index = -1;
+    }
+    for light in &mut clusters.spot_light_data {
+        light.shadow_index = -1;
+    }
+
+    let mut records = Vec::new();
+    let mut draws = Vec::new();
+    let mut cache = HashMap::new();
+    for (light, light_tiles) in lights.iter().zip(tiles) {
+        if light_tiles.is_empty() {
+            continue;
+        }
+        let shadow_index = records.len() as i32;
+        match light.key {
+            (LIGHT_KIND_POINT, i) => clusters.point_light_data[i as usize].shadow_index = shadow_index,
+            (_, i) => clusters.spot_light_data[i as usize].shadow_index = shadow_index,
+        }
+
+        let cached = atlas.cache.get(&light.key).filter(|c| c.light_hash == light.hash);
+        let mut casters_hash = Vec::with_capacity(light_tiles.len());
+        for (face, (tile, &(view_proj, fov))) in light_tiles.iter().zip(&light.views).enumerate() {
+            records.push(shadow_atlas::local_shadow_data(view_proj, fov, tile, config.size));
+
+            let planes = extract_frustum_planes(&view_proj);
+            let visible: Vec<usize> = (0..entities.len())
+                .filter(|&e| entities[e].3.is_none_or(|b| sphere_in_frustum(&planes, b.center, b.radius)))
+                .collect();
+            let hash = if visible.iter().any(|&e| entities[e].1.skin.is_some()) {
+                None
+            } else {
+                let mut hasher = DefaultHasher::new();
+                for &e in &visible {
+                    entities[e].0.hash(&mut hasher);
+                    hash_floats(entities[e].2.as_flattened()).hash(&mut hasher);
+                }
+                Some(hasher.finish())
+            };
+
+            let up_to_date = hash.is_some()
+                && cached.is_some_and(|c| c.tiles.get(face) == Some(tile) && c.casters_hash.get(face) == Some(&hash));
+            if !up_to_date {
+                draws.push(TileDraw { tile: *tile, view_proj, casters: visible });
+            }
+            casters_hash.push(hash);
+        }
+        cache.insert(light.key, CachedShadow { light_hash: light.hash, tiles: light_tiles, casters_hash });
+    }
+    atlas.cache = cache;
+
+    atlas.records.upload(&state.device, &state.queue, &records);
+    clusters.point_lights.upload(&state.device, &state.queue, &clusters.point_light_data);
+    clusters.spot_lights.upload(&state.device, &state.queue, &clusters.spot_light_data);
+    if draws.is_empty() {
+        return Ok(());
+    }
+
+    // One per-frame slot per tile: queue writes are staged, so tiles cannot
+    // share a buffer region within one submission.
+    let slot = std::mem::size_of::<PerFrameUniforms>() as u64;
+    let frames: Vec<PerFrameUniforms> = draws
+        .iter()
+        .map(|d| PerFrameUniforms {
+            view: Mat4::IDENTITY.to_cols_array_2d(),
+            projection: d.view_proj.to_cols_array_2d(),
+            inv_view_proj: [[0.0; 4]; 4],
+            camera_pos: [0.0; 4],
+            time: 0.0,
+            _pad1: 0.0,
+            _pad2: 0.0,
+            _pad3: 0.0,
+            _alignment_pad: [0.0; 8],
+        })
+        .collect();
+    let frame_buffer = state.device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Local Shadow Per-Frame"),
+        size: slot * frames.len() as u64,
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    state.queue.write_buffer(&frame_buffer, 0, bytemuck::cast_slice(&frames));
+
+    let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Local Shadow Encoder"),
+    });
+    for (d, draw) in draws.iter().enumerate() {
+        let per_frame_bg = state.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Local Shadow Per-Frame BG"),
+            layout: &state.per_frame_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &frame_buffer,
+                    offset: d as u64 * slot,
+                    size: wgpu::BufferSize::new(slot),
+                }),
+            }],
+        });
+
+        let mut static_handles = Vec::new();
+        let mut static_meshes = Vec::new();
+        let mut static_objects = Vec::new();
+        let mut skinned_meshes = Vec::new();
+        for &e in &draw.casters {
+            let (handle, mesh, model, _) = entities[e];
+            if mesh.skin.is_some() {
+                skinned_meshes.push((handle, mesh, model));
+            } else {
+                static_handles.push(handle);
+                static_meshes.push(mesh);
+                static_objects.push(PerObjectUniforms {
+                    model,
+                    normal_matrix_col0: [0.0; 4], // Not needed for depth-only
+                    normal_matrix_col1: [0.0; 4],
+                    normal_matrix_col2: [0.0; 4],
+                    _pad: [0.0; 4],
+                });
+            }
+        }
+        let (batches, order) = group_instances(static_handles);
+        let instances: Vec<_> = order.iter().map(|&i| static_objects[i]).collect();
+        let batch_meshes: Vec<&GPUMesh> = batches.iter().map(|b| static_meshes[b.representative]).collect();
+        let instance_draws = state.local_shadow_instances.get(d).prepare(
+            &state.device,
+            &state.queue,
+            &mut encoder,
+            &dp.instance_bgl,
+            Instances { objects: &instances, batches },
+            None,
+        );
+
+        render_shadow_depth(
+            &mut encoder,
+            &ShadowTarget {
+                label: "Local Shadow Tile",
+                view: &atlas.view,
+                tile: Some(draw.tile),
+                per_frame_bg: &per_frame_bg,
+            },
+            dp,
+            &ShadowCasters {
+                batches: &batch_meshes,
+                instances: &instance_draws,
+                skinned: &skinned_meshes,
+            },
+            &state.device,
+            &state.queue,
+        );
+    }
+    state.queue.submit(std::iter::once(encoder.finish()));
+    Ok(())
+}
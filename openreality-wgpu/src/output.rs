@@ -0,0 +1,149 @@
+//! Where the present pass draws: the window's swapchain, or an offscreen
+//! `Rgba16Float` target when the backend runs headless (tests, offscreen
+//! captures). The headless target takes every display mode, so the HDR
+//! encodings can be checked without an HDR display.
+
+use openreality_gpu_shared::display::{
+    f16_to_f32, SurfaceFormatKind, DISPLAY_HDR10, DISPLAY_HDR_SCRGB, OUTPUT_HDR10, OUTPUT_SCRGB, OUTPUT_SDR_LINEAR,
+};
+
+use crate::backend::RenderTarget;
+
+/// Format of the headless output target.
+pub const HEADLESS_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+pub enum OutputTarget {
+    Surface {
+        surface: wgpu::Surface<'static>,
+        /// Formats the surface supports, in the adapter's order of preference.
+        formats: Vec<wgpu::TextureFormat>,
+    },
+    Headless(RenderTarget),
+}
+
+/// Texture of one output frame; surface frames are shown by `present`.
+pub struct OutputFrame {
+    pub view: wgpu::TextureView,
+    surface_texture: Option<wgpu::SurfaceTexture>,
+}
+
+impl OutputFrame {
+    pub fn present(self) {
+        if let Some(texture) = self.surface_texture {
+            texture.present();
+        }
+    }
+}
+
+impl OutputTarget {
+    pub fn acquire(&self) -> Result<OutputFrame, String> {
+        match self {
+            OutputTarget::Surface { surface, .. } => {
+                let texture = surface
+                    .get_current_texture()
+                    .map_err(|e| format!("Surface texture error: {e}"))?;
+                Ok(OutputFrame {
+                    view: texture.texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                    surface_texture: Some(texture),
+                })
+            }
+            OutputTarget::Headless(target) => Ok(OutputFrame {
+                view: target.color_texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                surface_texture: None,
+            }),
+        }
+    }
+}
+
+pub fn format_kind(format: wgpu::TextureFormat) -> SurfaceFormatKind {
+    use wgpu::TextureFormat::*;
+    match format {
+        Bgra8UnormSrgb | Rgba8UnormSrgb => SurfaceFormatKind::Srgb8,
+        Bgra8Unorm | Rgba8Unorm => SurfaceFormatKind::Unorm8,
+        Rgba16Float => SurfaceFormatKind::Float16,
+        Rgb10a2Unorm => SurfaceFormatKind::Rgb10a2,
+        _ => SurfaceFormatKind::Other,
+    }
+}
+
+/// Encoding of the headless target for `mode`. It holds any of them, so HDR10
+/// is written PQ encoded rather than falling back to scRGB.
+pub fn headless_encoding(mode: u32) -> u32 {
+    match mode {
+        DISPLAY_HDR_SCRGB => OUTPUT_SCRGB,
+        DISPLAY_HDR10 => OUTPUT_HDR10,
+        _ => OUTPUT_SDR_LINEAR,
+    }
+}
+
+pub fn create_headless_target(device: &wgpu::Device, width: u32, height: u32) -> RenderTarget {
+    let color_texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Headless Output"),
+        size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: HEADLESS_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+    RenderTarget {
+        color_view: color_texture.create_view(&wgpu::TextureViewDescriptor::default()),
+        color_texture,
+        depth_texture: None,
+        depth_view: None,
+        width,
+        height,
+    }
+}
+
+/// Read the headless target back as RGBA floats, row by row from the top.
+pub fn read_headless_target(device: &wgpu::Device, queue: &wgpu::Queue, target: &RenderTarget) -> Result<Vec<f32>, String> {
+    const TEXEL_BYTES: u32 = 8;
+    let row_bytes = target.width * TEXEL_BYTES;
+    let padded_row_bytes = row_bytes.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Headless Readback"),
+        size: (padded_row_bytes * target.height) as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Headless Readback Encoder"),
+    });
+    encoder.copy_texture_to_buffer(
+        target.color_texture.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row_bytes),
+                rows_per_image: Some(target.height),
+            },
+        },
+        wgpu::Extent3d { width: target.width, height: target.height, depth_or_array_layers: 1 },
+    );
+    queue.submit(std::iter::once(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    device.poll(wgpu::Maintain::Wait);
+    receiver
+        .recv()
+        .map_err(|e| format!("Readback failed: {e}"))?
+        .map_err(|e| format!("Readback failed: {e}"))?;
+
+    let data = slice.get_mapped_range();
+    let texels = data
+        .chunks(padded_row_bytes as usize)
+        .flat_map(|row| row[..row_bytes as usize].chunks_exact(2))
+        .map(|half| f16_to_f32(u16::from_le_bytes([half[0], half[1]])))
+        .collect();
+    drop(data);
+    buffer.unmap();
+    Ok(texels)
+}
@@ -0,0 +1,108 @@
+//! Decal pass — blend decal boxes into the G-Buffer albedo, normals and
+//! roughness, between the geometry and lighting passes.
+
+use crate::backend::DeferredPipeline;
+use openreality_gpu_shared::uniforms::DecalData;
+use wgpu::util::DeviceExt;
+
+/// One decal to draw. Missing maps are bound to the default texture and
+/// disabled in `data`.
+pub struct DecalDraw<'a> {
+    pub data: DecalData,
+    pub albedo: &'a wgpu::TextureView,
+    pub normal: &'a wgpu::TextureView,
+}
+
+/// Draw `decals` in order, so later decals cover earlier ones. All albedo and
+/// normal blends run first, then the roughness blends of decals that have one.
+pub fn render_decal_pass(
+    encoder: &mut wgpu::CommandEncoder,
+    device: &wgpu::Device,
+    dp: &DeferredPipeline,
+    per_frame_bg: &wgpu::BindGroup,
+    decals: &[DecalDraw<'_>],
+) {
+    let data: Vec<DecalData> = decals.iter().map(|d| d.data).collect();
+    let decal_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Decal Storage"),
+        contents: bytemuck::cast_slice(&data),
+        usage: wgpu::BufferUsages::STORAGE,
+    });
+
+    let bind_groups: Vec<_> = decals
+        .iter()
+        .map(|decal| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Decal BG"),
+                layout: &dp.decal_bgl,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: decal_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&dp.gbuffer.depth_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::TextureView(decal.albedo),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: wgpu::BindingResource::TextureView(decal.normal),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: wgpu::BindingResource::Sampler(&dp.decal_sampler),
+                    },
+                ],
+            })
+        })
+        .collect();
+
+    let target = |view| {
+        Some(wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: wgpu::StoreOp::Store,
+            },
+        })
+    };
+    // No depth attachment: the shader reads the depth to find the surface
+    let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Decal Pass"),
+        color_attachments: &[
+            target(&dp.gbuffer.albedo_metallic_view),
+            target(&dp.gbuffer.normal_roughness_view),
+        ],
+        ..Default::default()
+    });
+    pass.set_bind_group(0, per_frame_bg, &[]);
+
+    pass.set_pipeline(&dp.decal_pipeline);
+    for (i, bg) in bind_groups.iter().enumerate() {
+        let i = i as u32;
+        pass.set_bind_group(1, bg, &[]);
+        pass.draw(0..36, i..i + 1);
+    }
+
+    pass.set_pipeline(&dp.decal_roughness_pipeline);
+    for (i, (decal, bg)) in decals.iter().zip(&bind_groups).enumerate() {
+        if decal.data.roughness_opacity <= 0.0 {
+            continue;
+        }
+        let i = i as u32;
+        // The blend constant's alpha is the roughness the decal blends in
+        pass.set_blend_constant(wgpu::Color {
+            r: 0.0,
+            g: 0.0,
+            b: 0.0,
+            a: decal.data.roughness as f64,
+        });
+        pass.set_bind_group(1, bg, &[]);
+        pass.draw(0..36, i..i + 1);
+    }
+}
@@ -1,41 +1,52 @@
 //! Forward PBR pass — render transparent objects with blending.
 
-use crate::backend::RenderTarget;
 use crate::passes::gbuffer::GBufferEntity;
-use openreality_gpu_shared::uniforms::MaterialUniforms;
+use wgpu::util::DeviceExt;
 
-/// Render transparent entities with the forward PBR pipeline.
-/// Entities should be sorted back-to-front before calling.
-pub fn render_forward_pass(
+/// Attachments of the forward pass. With MSAA `color` and `depth` are the
+/// multisampled targets (primed with `render_msaa_prime`) and `resolve` is
+/// the lighting target; without, `color` is the lighting target and `depth`
+/// the G-Buffer depth.
+pub struct ForwardAttachments<'a> {
+    pub color: &'a wgpu::TextureView,
+    pub resolve: Option<&'a wgpu::TextureView>,
+    pub depth: &'a wgpu::TextureView,
+}
+
+/// Pipeline, layouts and shared bind groups of the forward pass.
+pub struct ForwardResources<'a> {
+    pub pipeline: &'a wgpu::RenderPipeline,
+    pub skinned_pipeline: &'a wgpu::RenderPipeline,
+    pub per_frame_bg: &'a wgpu::BindGroup,
+    pub light_shadow_bg: &'a wgpu::BindGroup,
+    pub per_object_bgl: &'a wgpu::BindGroupLayout,
+    pub skinned_object_bgl: &'a wgpu::BindGroupLayout,
+    pub material_bgl: &'a wgpu::BindGroupLayout,
+    pub default_texture_view: &'a wgpu::TextureView,
+    pub default_sampler: &'a wgpu::Sampler,
+}
+
+/// Copy the lit image and G-Buffer depth into the multisampled forward targets.
+pub fn render_msaa_prime(
     encoder: &mut wgpu::CommandEncoder,
-    target: &RenderTarget,
-    depth_view: &wgpu::TextureView,
+    attachments: &ForwardAttachments<'_>,
     pipeline: &wgpu::RenderPipeline,
-    per_frame_bg: &wgpu::BindGroup,
-    light_shadow_bg: &wgpu::BindGroup,
-    per_object_bgl: &wgpu::BindGroupLayout,
-    material_bgl: &wgpu::BindGroupLayout,
-    device: &wgpu::Device,
-    queue: &wgpu::Queue,
-    per_object_buffer: &wgpu::Buffer,
-    entities: &[GBufferEntity<'_>],
-    default_texture_view: &wgpu::TextureView,
-    default_sampler: &wgpu::Sampler,
+    bind_group: &wgpu::BindGroup,
 ) {
     let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-        label: Some("Forward Transparent Pass"),
+        label: Some("MSAA Prime Pass"),
         color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-            view: &target.color_view,
+            view: attachments.color,
             resolve_target: None,
             ops: wgpu::Operations {
-                load: wgpu::LoadOp::Load, // Preserve lighting result
+                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
                 store: wgpu::StoreOp::Store,
             },
         })],
         depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-            view: depth_view,
+            view: attachments.depth,
             depth_ops: Some(wgpu::Operations {
-                load: wgpu::LoadOp::Load, // Preserve G-Buffer depth
+                load: wgpu::LoadOp::Clear(1.0),
                 store: wgpu::StoreOp::Store,
             }),
             stencil_ops: None,
@@ -44,56 +55,120 @@ pub fn render_forward_pass(
     });
 
     pass.set_pipeline(pipeline);
-    pass.set_bind_group(0, per_frame_bg, &[]);
-    pass.set_bind_group(3, light_shadow_bg, &[]);
+    pass.set_bind_group(0, bind_group, &[]);
+    pass.draw(0..3, 0..1);
+}
+
+/// Render transparent entities with the forward PBR pipeline.
+/// Entities should be sorted back-to-front before calling.
+pub fn render_forward_pass(
+    encoder: &mut wgpu::CommandEncoder,
+    device: &wgpu::Device,
+    attachments: &ForwardAttachments<'_>,
+    resources: &ForwardResources<'_>,
+    entities: &[GBufferEntity<'_>],
+) {
+    // Per-entity uniform buffers, so one pass can draw them all
+    let bind_groups: Vec<_> = entities
+        .iter()
+        .map(|entity| {
+            let obj_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Forward Per-Object UBO"),
+                contents: bytemuck::bytes_of(&entity.per_object),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+            let obj_bg = match &entity.mesh.skin {
+                Some(skin) => device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Forward Skinned Per-Object BG"),
+                    layout: resources.skinned_object_bgl,
+                    entries: &[
+                        wgpu::BindGroupEntry { binding: 0, resource: obj_buffer.as_entire_binding() },
+                        wgpu::BindGroupEntry { binding: 1, resource: skin.bone_buffer.as_entire_binding() },
+                    ],
+                }),
+                None => device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Forward Per-Object BG"),
+                    layout: resources.per_object_bgl,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: obj_buffer.as_entire_binding(),
+                    }],
+                }),
+            };
 
-    for entity in entities {
-        queue.write_buffer(per_object_buffer, 0, bytemuck::bytes_of(&entity.per_object));
+            let mat_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Forward Material UBO"),
+                contents: bytemuck::bytes_of(&entity.material),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
 
-        let obj_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Forward Per-Object BG"),
-            layout: per_object_bgl,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: per_object_buffer.as_entire_binding(),
-            }],
-        });
+            let tex_views: Vec<&wgpu::TextureView> = entity
+                .texture_views
+                .iter()
+                .map(|v| v.unwrap_or(resources.default_texture_view))
+                .collect();
 
-        let mat_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Forward Material UBO"),
-            size: std::mem::size_of::<MaterialUniforms>() as u64,
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-        queue.write_buffer(&mat_buffer, 0, bytemuck::bytes_of(&entity.material));
+            let mat_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Forward Material BG"),
+                layout: resources.material_bgl,
+                entries: &[
+                    wgpu::BindGroupEntry { binding: 0, resource: mat_buffer.as_entire_binding() },
+                    wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(tex_views[0]) },
+                    wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(tex_views[1]) },
+                    wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::TextureView(tex_views[2]) },
+                    wgpu::BindGroupEntry { binding: 4, resource: wgpu::BindingResource::TextureView(tex_views[3]) },
+                    wgpu::BindGroupEntry { binding: 5, resource: wgpu::BindingResource::TextureView(tex_views[4]) },
+                    wgpu::BindGroupEntry { binding: 6, resource: wgpu::BindingResource::TextureView(tex_views[5]) },
+                    wgpu::BindGroupEntry { binding: 7, resource: wgpu::BindingResource::Sampler(resources.default_sampler) },
+                ],
+            });
+            (mat_bg, obj_bg)
+        })
+        .collect();
 
-        let tex_views: Vec<&wgpu::TextureView> = entity
-            .texture_views
-            .iter()
-            .map(|v| v.unwrap_or(default_texture_view))
-            .collect();
+    let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Forward Transparent Pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: attachments.color,
+            resolve_target: attachments.resolve,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Load, // Preserve lighting result
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+            view: attachments.depth,
+            depth_ops: Some(wgpu::Operations {
+                load: wgpu::LoadOp::Load, // Preserve G-Buffer depth
+                store: wgpu::StoreOp::Store,
+            }),
+            stencil_ops: None,
+        }),
+        ..Default::default()
+    });
 
-        let mat_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Forward Material BG"),
-            layout: material_bgl,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: mat_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(tex_views[0]) },
-                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(tex_views[1]) },
-                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::TextureView(tex_views[2]) },
-                wgpu::BindGroupEntry { binding: 4, resource: wgpu::BindingResource::TextureView(tex_views[3]) },
-                wgpu::BindGroupEntry { binding: 5, resource: wgpu::BindingResource::TextureView(tex_views[4]) },
-                wgpu::BindGroupEntry { binding: 6, resource: wgpu::BindingResource::TextureView(tex_views[5]) },
-                wgpu::BindGroupEntry { binding: 7, resource: wgpu::BindingResource::Sampler(default_sampler) },
-            ],
-        });
+    pass.set_bind_group(0, resources.per_frame_bg, &[]);
+    pass.set_bind_group(3, resources.light_shadow_bg, &[]);
 
-        pass.set_bind_group(1, &mat_bg, &[]);
-        pass.set_bind_group(2, &obj_bg, &[]);
+    // Back-to-front order interleaves skinned and static meshes, so the
+    // pipeline switches whenever the kind changes
+    let mut bound_skinned = None;
+    for (entity, (mat_bg, obj_bg)) in entities.iter().zip(&bind_groups) {
+        let skin = entity.mesh.skin.as_ref();
+        if bound_skinned != Some(skin.is_some()) {
+            pass.set_pipeline(if skin.is_some() { resources.skinned_pipeline } else { resources.pipeline });
+            bound_skinned = Some(skin.is_some());
+        }
+        pass.set_bind_group(1, mat_bg, &[]);
+        pass.set_bind_group(2, obj_bg, &[]);
 
         pass.set_vertex_buffer(0, entity.mesh.vertex_buffer.slice(..));
         pass.set_vertex_buffer(1, entity.mesh.normal_buffer.slice(..));
         pass.set_vertex_buffer(2, entity.mesh.uv_buffer.slice(..));
+        if let Some(skin) = skin {
+            pass.set_vertex_buffer(3, skin.bone_weight_buffer.slice(..));
+            pass.set_vertex_buffer(4, skin.bone_index_buffer.slice(..));
+        }
         pass.set_index_buffer(entity.mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
         pass.draw_indexed(0..entity.mesh.index_count, 0, 0..1);
     }
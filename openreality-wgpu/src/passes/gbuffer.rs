@@ -1,22 +1,36 @@
 //! G-Buffer geometry pass — render all opaque entities to the G-Buffer MRTs.
 
 use crate::backend::{GBuffer, GPUMesh};
+use crate::instancing::InstanceDraws;
 use openreality_gpu_shared::uniforms::{MaterialUniforms, PerObjectUniforms};
 
+/// Pipelines, layouts and shared bindings of the G-Buffer pass.
+pub struct GBufferResources<'a> {
+    pub pipeline: &'a wgpu::RenderPipeline,
+    pub skinned_pipeline: &'a wgpu::RenderPipeline,
+    pub per_frame_bg: &'a wgpu::BindGroup,
+    pub per_object_bgl: &'a wgpu::BindGroupLayout,
+    pub material_bgl: &'a wgpu::BindGroupLayout,
+    pub default_texture_view: &'a wgpu::TextureView,
+    pub default_sampler: &'a wgpu::Sampler,
+}
+
+/// Opaque entities of one frame. `batches` holds one representative entity
+/// per instanced batch of `instances`; `skinned` entities are drawn individually.
+pub struct GBufferDraws<'a> {
+    pub batches: &'a [&'a GBufferEntity<'a>],
+    pub instances: &'a InstanceDraws<'a>,
+    pub skinned: &'a [GBufferEntity<'a>],
+}
+
 /// Render all opaque entities into the G-Buffer.
 pub fn render_gbuffer_pass(
     encoder: &mut wgpu::CommandEncoder,
-    gbuffer: &GBuffer,
-    pipeline: &wgpu::RenderPipeline,
-    per_frame_bg: &wgpu::BindGroup,
-    per_object_bgl: &wgpu::BindGroupLayout,
-    material_bgl: &wgpu::BindGroupLayout,
     device: &wgpu::Device,
     queue: &wgpu::Queue,
-    per_object_buffer: &wgpu::Buffer,
-    entities: &[GBufferEntity<'_>],
-    default_texture_view: &wgpu::TextureView,
-    default_sampler: &wgpu::Sampler,
+    gbuffer: &GBuffer,
+    resources: &GBufferResources<'_>,
+    draws: &GBufferDraws<'_>,
 ) {
     let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
         label: Some("G-Buffer Pass"),
@@ -75,10 +89,26 @@ pub fn render_gbuffer_pass(
         ..Default::default()
     });
 
-    pass.set_pipeline(pipeline);
-    pass.set_bind_group(0, per_frame_bg, &[]);
+    pass.set_bind_group(0, resources.per_frame_bg, &[]);
 
-    for entity in entities {
+    // Static meshes: one instanced draw per mesh/material batch
+    pass.set_pipeline(resources.pipeline);
+    pass.set_bind_group(2, &draws.instances.bind_group, &[]);
+    for (b, entity) in draws.batches.iter().enumerate() {
+        let mat_bg = material_bind_group(device, queue, resources, entity);
+        pass.set_bind_group(1, &mat_bg, &[]);
+        pass.set_vertex_buffer(0, entity.mesh.vertex_buffer.slice(..));
+        pass.set_vertex_buffer(1, entity.mesh.normal_buffer.slice(..));
+        pass.set_vertex_buffer(2, entity.mesh.uv_buffer.slice(..));
+        pass.set_index_buffer(entity.mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        draws.instances.draw(&mut pass, b, entity.mesh.index_count);
+    }
+
+    // Skinned meshes carry their own bone palette and are drawn one by one
+    if !draws.skinned.is_empty() {
+        pass.set_pipeline(resources.skinned_pipeline);
+    }
+    for entity in draws.skinned {
         // Create per-entity object buffer (can't reuse a single buffer because
         // queue.write_buffer is staged and only the last write would survive).
         let obj_buffer = device.create_buffer(&wgpu::BufferDescriptor {
@@ -91,78 +121,91 @@ pub fn render_gbuffer_pass(
 
         let obj_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("GBuffer Per-Object BG"),
-            layout: per_object_bgl,
+            layout: resources.per_object_bgl,
             entries: &[wgpu::BindGroupEntry {
                 binding: 0,
                 resource: obj_buffer.as_entire_binding(),
             }],
         });
 
-        // Create material bind group with textures
-        let mat_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Material UBO"),
-            size: std::mem::size_of::<MaterialUniforms>() as u64,
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-        queue.write_buffer(&mat_buffer, 0, bytemuck::bytes_of(&entity.material));
-
-        let tex_views: Vec<&wgpu::TextureView> = entity
-            .texture_views
-            .iter()
-            .map(|v| v.unwrap_or(default_texture_view))
-            .collect();
-
-        let mat_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("GBuffer Material BG"),
-            layout: material_bgl,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: mat_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::TextureView(tex_views[0]),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: wgpu::BindingResource::TextureView(tex_views[1]),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 3,
-                    resource: wgpu::BindingResource::TextureView(tex_views[2]),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 4,
-                    resource: wgpu::BindingResource::TextureView(tex_views[3]),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 5,
-                    resource: wgpu::BindingResource::TextureView(tex_views[4]),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 6,
-                    resource: wgpu::BindingResource::TextureView(tex_views[5]),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 7,
-                    resource: wgpu::BindingResource::Sampler(default_sampler),
-                },
-            ],
-        });
-
+        let mat_bg = material_bind_group(device, queue, resources, entity);
         pass.set_bind_group(1, &mat_bg, &[]);
         pass.set_bind_group(2, &obj_bg, &[]);
 
         pass.set_vertex_buffer(0, entity.mesh.vertex_buffer.slice(..));
         pass.set_vertex_buffer(1, entity.mesh.normal_buffer.slice(..));
         pass.set_vertex_buffer(2, entity.mesh.uv_buffer.slice(..));
+        if let Some(skin) = &entity.mesh.skin {
+            pass.set_bind_group(3, &skin.bone_bind_group, &[]);
+            pass.set_vertex_buffer(3, skin.bone_weight_buffer.slice(..));
+            pass.set_vertex_buffer(4, skin.bone_index_buffer.slice(..));
+        }
         pass.set_index_buffer(entity.mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
         pass.draw_indexed(0..entity.mesh.index_count, 0, 0..1);
     }
 }
 
+/// Material uniforms and textures of `entity` at group 1.
+fn material_bind_group(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    resources: &GBufferResources<'_>,
+    entity: &GBufferEntity<'_>,
+) -> wgpu::BindGroup {
+    let mat_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Material UBO"),
+        size: std::mem::size_of::<MaterialUniforms>() as u64,
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    queue.write_buffer(&mat_buffer, 0, bytemuck::bytes_of(&entity.material));
+
+    let tex_views: Vec<&wgpu::TextureView> = entity
+        .texture_views
+        .iter()
+        .map(|v| v.unwrap_or(resources.default_texture_view))
+        .collect();
+
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("GBuffer Material BG"),
+        layout: resources.material_bgl,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: mat_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(tex_views[0]),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::TextureView(tex_views[1]),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: wgpu::BindingResource::TextureView(tex_views[2]),
+            },
+            wgpu::BindGroupEntry {
+                binding: 4,
+                resource: wgpu::BindingResource::TextureView(tex_views[3]),
+            },
+            wgpu::BindGroupEntry {
+                binding: 5,
+                resource: wgpu::BindingResource::TextureView(tex_views[4]),
+            },
+            wgpu::BindGroupEntry {
+                binding: 6,
+                resource: wgpu::BindingResource::TextureView(tex_views[5]),
+            },
+            wgpu::BindGroupEntry {
+                binding: 7,
+                resource: wgpu::BindingResource::Sampler(resources.default_sampler),
+            },
+        ],
+    })
+}
+
 /// Data needed to render one entity in the G-Buffer pass.
 pub struct GBufferEntity<'a> {
     pub mesh: &'a GPUMesh,
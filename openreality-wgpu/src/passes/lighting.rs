@@ -1,6 +1,109 @@
 //! Deferred lighting pass — fullscreen PBR lighting with Cook-Torrance BRDF.
 
-use crate::backend::{GBuffer, RenderTarget};
+use crate::backend::{DeferredPipeline, LightClusters, RenderTarget};
+use crate::environment::{Environment, FOG_TILE};
+use openreality_gpu_shared::atmosphere::FOG_GRID;
+use openreality_gpu_shared::clustering::CLUSTER_GRID;
+use openreality_gpu_shared::uniforms::ClusterParams;
+
+/// Threads per workgroup axis in `light_cluster.wgsl`.
+const CLUSTER_WORKGROUP_SIZE: u32 = 4;
+
+/// Bin the point lights into the camera's froxels for the lighting shaders.
+pub fn build_light_clusters(
+    encoder: &mut wgpu::CommandEncoder,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    clusters: &LightClusters,
+    pipeline: &wgpu::ComputePipeline,
+    layout: &wgpu::BindGroupLayout,
+    params: &ClusterParams,
+) {
+    queue.write_buffer(&clusters.params_buffer, 0, bytemuck::bytes_of(params));
+
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Light Cluster BG"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry { binding: 0, resource: clusters.params_buffer.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: 1, resource: clusters.point_lights.buffer.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: 2, resource: clusters.counts_buffer.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: 3, resource: clusters.indices_buffer.as_entire_binding() },
+        ],
+    });
+
+    let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+        label: Some("Light Clustering"),
+        timestamp_writes: None,
+    });
+    pass.set_pipeline(pipeline);
+    pass.set_bind_group(0, &bind_group, &[]);
+    let [x, y, z] = CLUSTER_GRID.map(|n| n.div_ceil(CLUSTER_WORKGROUP_SIZE));
+    pass.dispatch_workgroups(x, y, z);
+}
+
+/// Light the fog froxels with the lighting pass's light data, then integrate
+/// them front to back into the volume the lighting and forward passes read.
+/// Needs the light clusters of this frame.
+pub fn render_volumetric_fog(
+    encoder: &mut wgpu::CommandEncoder,
+    device: &wgpu::Device,
+    dp: &DeferredPipeline,
+    environment: &Environment,
+    per_frame_buffer: &wgpu::Buffer,
+    light_data_bg: &wgpu::BindGroup,
+) {
+    let inject_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Fog Inject BG"),
+        layout: &dp.fog_inject_bgl,
+        entries: &[
+            wgpu::BindGroupEntry { binding: 0, resource: per_frame_buffer.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: 1, resource: environment.fog_buffer.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: 2, resource: environment.uniform_buffer.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::TextureView(&environment.scattering_view) },
+        ],
+    });
+    let integrate_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Fog Integrate BG"),
+        layout: &dp.fog_integrate_bgl,
+        entries: &[
+            wgpu::BindGroupEntry { binding: 0, resource: environment.fog_buffer.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&environment.scattering_view) },
+            wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(&environment.fog_volume_view) },
+        ],
+    });
+
+    let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+        label: Some("Volumetric Fog"),
+        timestamp_writes: None,
+    });
+    let [x, y, z] = FOG_GRID;
+    pass.set_pipeline(&dp.fog_inject_pipeline);
+    pass.set_bind_group(0, &inject_bg, &[]);
+    pass.set_bind_group(1, light_data_bg, &[]);
+    pass.dispatch_workgroups(x.div_ceil(FOG_TILE), y.div_ceil(FOG_TILE), z);
+    pass.set_pipeline(&dp.fog_integrate_pipeline);
+    pass.set_bind_group(0, &integrate_bg, &[]);
+    pass.dispatch_workgroups(x.div_ceil(FOG_TILE), y.div_ceil(FOG_TILE), 1);
+}
+
+/// Sky and fog of the lighting pass (bind group 2).
+pub fn create_environment_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    environment: &Environment,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Environment BG"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry { binding: 0, resource: environment.uniform_buffer.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&environment.sky_view) },
+            wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(&environment.fog_volume_view) },
+            wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::Sampler(&environment.sampler) },
+        ],
+    })
+}
 
 /// Render the deferred lighting pass into the lighting target.
 pub fn render_lighting_pass(
@@ -9,6 +112,7 @@ pub fn render_lighting_pass(
     pipeline: &wgpu::RenderPipeline,
     lighting_bg: &wgpu::BindGroup,
     light_data_bg: &wgpu::BindGroup,
+    environment_bg: &wgpu::BindGroup,
 ) {
     let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
         label: Some("Deferred Lighting Pass"),
@@ -27,25 +131,24 @@ pub fn render_lighting_pass(
     pass.set_pipeline(pipeline);
     pass.set_bind_group(0, lighting_bg, &[]);
     pass.set_bind_group(1, light_data_bg, &[]);
+    pass.set_bind_group(2, environment_bg, &[]);
 
     // Full-screen triangle via vertex index (no vertex buffer needed)
     pass.draw(0..3, 0..1);
 }
 
-/// Create the lighting bind group with G-Buffer textures and SSAO/SSR.
+/// Create the lighting bind group with the G-Buffer textures and the blurred
+/// SSAO and SSR results of `dp`.
 pub fn create_lighting_bind_group(
     device: &wgpu::Device,
-    layout: &wgpu::BindGroupLayout,
+    dp: &DeferredPipeline,
     per_frame_buffer: &wgpu::Buffer,
-    gbuffer: &GBuffer,
-    ssao_view: &wgpu::TextureView,
-    ssr_view: &wgpu::TextureView,
     gbuffer_sampler: &wgpu::Sampler,
-    depth_sampler: &wgpu::Sampler,
 ) -> wgpu::BindGroup {
+    let gbuffer = &dp.gbuffer;
     device.create_bind_group(&wgpu::BindGroupDescriptor {
         label: Some("Lighting Bind Group"),
-        layout,
+        layout: &dp.lighting_bgl,
         entries: &[
             wgpu::BindGroupEntry {
                 binding: 0,
@@ -73,11 +176,11 @@ pub fn create_lighting_bind_group(
             },
             wgpu::BindGroupEntry {
                 binding: 6,
-                resource: wgpu::BindingResource::TextureView(ssao_view),
+                resource: wgpu::BindingResource::TextureView(&dp.ssao_targets.blur.color_view),
             },
             wgpu::BindGroupEntry {
                 binding: 7,
-                resource: wgpu::BindingResource::TextureView(ssr_view),
+                resource: wgpu::BindingResource::TextureView(&dp.ssr_target.color_view),
             },
             wgpu::BindGroupEntry {
                 binding: 8,
@@ -85,7 +188,7 @@ pub fn create_lighting_bind_group(
             },
             wgpu::BindGroupEntry {
                 binding: 9,
-                resource: wgpu::BindingResource::Sampler(depth_sampler),
+                resource: wgpu::BindingResource::Sampler(&dp.depth_sampler),
             },
         ],
     })
@@ -2,6 +2,7 @@
 
 pub mod shadow;
 pub mod gbuffer;
+pub mod decal;
 pub mod lighting;
 pub mod ssao;
 pub mod ssr;
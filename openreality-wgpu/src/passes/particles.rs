@@ -2,20 +2,21 @@
 
 /// Render particles as billboard quads.
 /// `vertex_data` is interleaved: pos3 + uv2 + color4 = 9 floats per vertex.
+/// `pipeline` selects the blend mode. The depth attachment is read-only so the
+/// bind group can sample it for soft particles.
 pub fn render_particle_pass(
     encoder: &mut wgpu::CommandEncoder,
-    surface_view: &wgpu::TextureView,
+    color_view: &wgpu::TextureView,
     depth_view: &wgpu::TextureView,
     pipeline: &wgpu::RenderPipeline,
     uniforms_bg: &wgpu::BindGroup,
     vertex_buffer: &wgpu::Buffer,
     vertex_count: u32,
-    _additive: bool,
 ) {
     let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
         label: Some("Particle Pass"),
         color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-            view: surface_view,
+            view: color_view,
             resolve_target: None,
             ops: wgpu::Operations {
                 load: wgpu::LoadOp::Load, // Preserve scene
@@ -24,10 +25,7 @@ pub fn render_particle_pass(
         })],
         depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
             view: depth_view,
-            depth_ops: Some(wgpu::Operations {
-                load: wgpu::LoadOp::Load,
-                store: wgpu::StoreOp::Discard, // Read-only depth
-            }),
+            depth_ops: None, // Read-only depth
             stencil_ops: None,
         }),
         ..Default::default()
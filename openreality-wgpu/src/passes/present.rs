@@ -1,17 +1,24 @@
-//! Present pass — blit final color to swapchain surface with tone mapping.
+//! Present pass — blit final color to swapchain surface with tone mapping,
+//! then the UI overlay.
 
-/// Render the final present pass to the swapchain surface.
+use crate::passes::ui::{self, UIDraw};
+
+/// Render the final present pass to the swapchain surface. With MSAA,
+/// `target` is the multisampled output target and `resolve_target` the
+/// surface.
 pub fn render_present_pass(
     encoder: &mut wgpu::CommandEncoder,
-    surface_view: &wgpu::TextureView,
+    target: &wgpu::TextureView,
+    resolve_target: Option<&wgpu::TextureView>,
     pipeline: &wgpu::RenderPipeline,
     bind_group: &wgpu::BindGroup,
+    ui: Option<&UIDraw<'_>>,
 ) {
     let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
         label: Some("Present Pass"),
         color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-            view: surface_view,
-            resolve_target: None,
+            view: target,
+            resolve_target,
             ops: wgpu::Operations {
                 load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
                 store: wgpu::StoreOp::Store,
@@ -24,4 +31,8 @@ pub fn render_present_pass(
     pass.set_pipeline(pipeline);
     pass.set_bind_group(0, bind_group, &[]);
     pass.draw(0..3, 0..1);
+
+    if let Some(ui) = ui {
+        ui::draw_ui(&mut pass, ui);
+    }
 }
@@ -1,29 +1,47 @@
-//! Cascaded shadow map depth pass.
+//! Shadow depth passes: cascaded shadow maps and local light atlas tiles.
 
-use crate::backend::{CascadedShadowMap, GPUMesh};
+use crate::backend::{DeferredPipeline, GPUMesh};
+use crate::instancing::InstanceDraws;
+use openreality_gpu_shared::shadow_atlas::AtlasTile;
 
-/// Render shadow depth for one cascade.
-pub fn render_shadow_cascade(
+/// Depth target of one shadow view.
+pub struct ShadowTarget<'a> {
+    pub label: &'a str,
+    pub view: &'a wgpu::TextureView,
+    /// Atlas tile to render into. The rest of the atlas is kept, and the tile
+    /// is reset with the clear pipeline; without a tile the whole target is cleared.
+    pub tile: Option<AtlasTile>,
+    pub per_frame_bg: &'a wgpu::BindGroup,
+}
+
+/// Casters of one shadow view. `batches` holds the mesh of each instanced
+/// batch of `instances`; `skinned` meshes are drawn individually.
+pub struct ShadowCasters<'a> {
+    pub batches: &'a [&'a GPUMesh],
+    pub instances: &'a InstanceDraws<'a>,
+    pub skinned: &'a [(u64, &'a GPUMesh, [[f32; 4]; 4])], // (entity, mesh, model_matrix)
+}
+
+/// Render shadow depth of `casters` into `target`.
+pub fn render_shadow_depth(
     encoder: &mut wgpu::CommandEncoder,
-    csm: &CascadedShadowMap,
-    cascade_index: usize,
-    pipeline: &wgpu::RenderPipeline,
-    per_frame_bg: &wgpu::BindGroup,
-    per_object_bgl: &wgpu::BindGroupLayout,
+    target: &ShadowTarget,
+    dp: &DeferredPipeline,
+    casters: &ShadowCasters,
     device: &wgpu::Device,
     queue: &wgpu::Queue,
-    per_object_buffer: &wgpu::Buffer,
-    meshes: &[(u64, &GPUMesh, [[f32; 4]; 4])], // (entity, mesh, model_matrix)
 ) {
-    let depth_view = &csm.depth_views[cascade_index];
-
+    let load = match target.tile {
+        Some(_) => wgpu::LoadOp::Load,
+        None => wgpu::LoadOp::Clear(1.0),
+    };
     let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-        label: Some(&format!("Shadow Cascade {cascade_index}")),
+        label: Some(target.label),
         color_attachments: &[],
         depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-            view: depth_view,
+            view: target.view,
             depth_ops: Some(wgpu::Operations {
-                load: wgpu::LoadOp::Clear(1.0),
+                load,
                 store: wgpu::StoreOp::Store,
             }),
             stencil_ops: None,
@@ -31,10 +49,29 @@ pub fn render_shadow_cascade(
         ..Default::default()
     });
 
-    pass.set_pipeline(pipeline);
-    pass.set_bind_group(0, per_frame_bg, &[]);
+    if let Some(tile) = target.tile {
+        let (x, y, size) = (tile.x as f32, tile.y as f32, tile.size as f32);
+        pass.set_viewport(x, y, size, size, 0.0, 1.0);
+        pass.set_scissor_rect(tile.x, tile.y, tile.size, tile.size);
+        pass.set_pipeline(&dp.shadow_clear_pipeline);
+        pass.draw(0..3, 0..1);
+    }
+
+    pass.set_bind_group(0, target.per_frame_bg, &[]);
+
+    // Static meshes: one instanced draw per mesh
+    pass.set_pipeline(&dp.shadow_pipeline);
+    pass.set_bind_group(1, &casters.instances.bind_group, &[]);
+    for (b, mesh) in casters.batches.iter().enumerate() {
+        pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+        pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        casters.instances.draw(&mut pass, b, mesh.index_count);
+    }
 
-    for (_, mesh, model) in meshes {
+    if !casters.skinned.is_empty() {
+        pass.set_pipeline(&dp.shadow_skinned_pipeline);
+    }
+    for (_, mesh, model) in casters.skinned {
         // Create per-entity object buffer (can't reuse a single buffer because
         // queue.write_buffer is staged and only the last write would survive).
         let obj = openreality_gpu_shared::uniforms::PerObjectUniforms {
@@ -54,7 +91,7 @@ pub fn render_shadow_cascade(
 
         let obj_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Shadow Per-Object BG"),
-            layout: per_object_bgl,
+            layout: &dp.per_object_bgl,
             entries: &[wgpu::BindGroupEntry {
                 binding: 0,
                 resource: obj_buffer.as_entire_binding(),
@@ -63,6 +100,11 @@ pub fn render_shadow_cascade(
         pass.set_bind_group(1, &obj_bg, &[]);
 
         pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+        if let Some(skin) = &mesh.skin {
+            pass.set_bind_group(2, &skin.bone_bind_group, &[]);
+            pass.set_vertex_buffer(1, skin.bone_weight_buffer.slice(..));
+            pass.set_vertex_buffer(2, skin.bone_index_buffer.slice(..));
+        }
         pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
         pass.draw_indexed(0..mesh.index_count, 0, 0..1);
     }
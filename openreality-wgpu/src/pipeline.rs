@@ -2,7 +2,7 @@
 //! Each function creates a wgpu::RenderPipeline with appropriate shader, bind group layouts,
 //! and vertex buffer layouts.
 
-use crate::render_targets::{DEPTH_FORMAT, HDR_FORMAT};
+use crate::render_targets::{DEPTH_FORMAT, DEPTH_VALUES, HDR_FORMAT};
 use openreality_gpu_shared::shaders;
 
 /// Shared fullscreen quad vertex state (used by vertex-index-based full-screen triangle).
@@ -41,15 +41,196 @@ fn depth_stencil_readonly() -> wgpu::DepthStencilState {
 // G-Buffer Pipeline
 // ============================================================
 
+/// Per-vertex position/normal/uv streams (slots 0-2).
+const MESH_VERTEX_BUFFERS: [wgpu::VertexBufferLayout<'static>; 3] = [
+    // location 0: position vec3
+    wgpu::VertexBufferLayout {
+        array_stride: 12,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &[wgpu::VertexAttribute {
+            format: wgpu::VertexFormat::Float32x3,
+            offset: 0,
+            shader_location: 0,
+        }],
+    },
+    // location 1: normal vec3
+    wgpu::VertexBufferLayout {
+        array_stride: 12,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &[wgpu::VertexAttribute {
+            format: wgpu::VertexFormat::Float32x3,
+            offset: 0,
+            shader_location: 1,
+        }],
+    },
+    // location 2: uv vec2
+    wgpu::VertexBufferLayout {
+        array_stride: 8,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &[wgpu::VertexAttribute {
+            format: wgpu::VertexFormat::Float32x2,
+            offset: 0,
+            shader_location: 2,
+        }],
+    },
+];
+
+/// Bone weight (vec4 f32) and bone index (vec4 u16) streams for skinned meshes,
+/// at locations 3-4 for the G-Buffer and forward stages.
+const GBUFFER_SKIN_BUFFERS: [wgpu::VertexBufferLayout<'static>; 2] = [
+    wgpu::VertexBufferLayout {
+        array_stride: 16,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &[wgpu::VertexAttribute {
+            format: wgpu::VertexFormat::Float32x4,
+            offset: 0,
+            shader_location: 3,
+        }],
+    },
+    wgpu::VertexBufferLayout {
+        array_stride: 8,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &[wgpu::VertexAttribute {
+            format: wgpu::VertexFormat::Uint16x4,
+            offset: 0,
+            shader_location: 4,
+        }],
+    },
+];
+
+/// Same streams at locations 1-2 for the depth-only shadow stage.
+const SHADOW_SKIN_BUFFERS: [wgpu::VertexBufferLayout<'static>; 2] = [
+    wgpu::VertexBufferLayout {
+        array_stride: 16,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &[wgpu::VertexAttribute {
+            format: wgpu::VertexFormat::Float32x4,
+            offset: 0,
+            shader_location: 1,
+        }],
+    },
+    wgpu::VertexBufferLayout {
+        array_stride: 8,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &[wgpu::VertexAttribute {
+            format: wgpu::VertexFormat::Uint16x4,
+            offset: 0,
+            shader_location: 2,
+        }],
+    },
+];
+
+/// Bind group layout for a skinned mesh's bone palette (one storage buffer of mat4x4<f32>).
+pub fn create_skin_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Skin BGL"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    })
+}
+
+/// Per-object layout of the skinned forward pipeline: the `PerObject` uniform
+/// and, at binding 1, the mesh's bone palette.
+pub fn create_skinned_object_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Skinned Per-Object BGL"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+        ],
+    })
+}
+
+/// Bind group layout for the per-instance `PerObject` storage buffer read by
+/// the instanced G-Buffer and shadow vertex stages.
+pub fn create_instance_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Instance BGL"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    })
+}
+
+/// Instanced G-Buffer pipeline for static meshes; instances at group 2.
 pub fn create_gbuffer_pipeline(
+    device: &wgpu::Device,
+    per_frame_bgl: &wgpu::BindGroupLayout,
+    material_bgl: &wgpu::BindGroupLayout,
+    instance_bgl: &wgpu::BindGroupLayout,
+) -> wgpu::RenderPipeline {
+    build_gbuffer_pipeline(
+        device,
+        "GBuffer",
+        shaders::GBUFFER_VERT,
+        &[per_frame_bgl, material_bgl, instance_bgl],
+        &MESH_VERTEX_BUFFERS,
+    )
+}
+
+/// G-Buffer pipeline for skinned meshes. Adds the bone palette at group 3 and
+/// bone weights/indices at vertex slots 3-4.
+pub fn create_skinned_gbuffer_pipeline(
     device: &wgpu::Device,
     per_frame_bgl: &wgpu::BindGroupLayout,
     material_bgl: &wgpu::BindGroupLayout,
     per_object_bgl: &wgpu::BindGroupLayout,
+    skin_bgl: &wgpu::BindGroupLayout,
+) -> wgpu::RenderPipeline {
+    let [pos, normal, uv] = MESH_VERTEX_BUFFERS;
+    let [weights, indices] = GBUFFER_SKIN_BUFFERS;
+    build_gbuffer_pipeline(
+        device,
+        "GBuffer Skinned",
+        shaders::GBUFFER_SKINNED_VERT,
+        &[per_frame_bgl, material_bgl, per_object_bgl, skin_bgl],
+        &[pos, normal, uv, weights, indices],
+    )
+}
+
+fn build_gbuffer_pipeline(
+    device: &wgpu::Device,
+    label: &str,
+    vert_source: &str,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+    vertex_buffers: &[wgpu::VertexBufferLayout<'_>],
 ) -> wgpu::RenderPipeline {
     let vert_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-        label: Some("GBuffer Vertex"),
-        source: wgpu::ShaderSource::Wgsl(shaders::GBUFFER_VERT.into()),
+        label: Some(&format!("{label} Vertex")),
+        source: wgpu::ShaderSource::Wgsl(vert_source.into()),
     });
 
     let frag_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
@@ -58,50 +239,19 @@ pub fn create_gbuffer_pipeline(
     });
 
     let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-        label: Some("GBuffer Pipeline Layout"),
-        bind_group_layouts: &[per_frame_bgl, material_bgl, per_object_bgl],
+        label: Some(&format!("{label} Pipeline Layout")),
+        bind_group_layouts,
         push_constant_ranges: &[],
     });
 
     device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-        label: Some("GBuffer Pipeline"),
+        label: Some(&format!("{label} Pipeline")),
         layout: Some(&layout),
         vertex: wgpu::VertexState {
             module: &vert_module,
             entry_point: Some("vs_main"),
             compilation_options: wgpu::PipelineCompilationOptions::default(),
-            buffers: &[
-                // location 0: position vec3
-                wgpu::VertexBufferLayout {
-                    array_stride: 12,
-                    step_mode: wgpu::VertexStepMode::Vertex,
-                    attributes: &[wgpu::VertexAttribute {
-                        format: wgpu::VertexFormat::Float32x3,
-                        offset: 0,
-                        shader_location: 0,
-                    }],
-                },
-                // location 1: normal vec3
-                wgpu::VertexBufferLayout {
-                    array_stride: 12,
-                    step_mode: wgpu::VertexStepMode::Vertex,
-                    attributes: &[wgpu::VertexAttribute {
-                        format: wgpu::VertexFormat::Float32x3,
-                        offset: 0,
-                        shader_location: 1,
-                    }],
-                },
-                // location 2: uv vec2
-                wgpu::VertexBufferLayout {
-                    array_stride: 8,
-                    step_mode: wgpu::VertexStepMode::Vertex,
-                    attributes: &[wgpu::VertexAttribute {
-                        format: wgpu::VertexFormat::Float32x2,
-                        offset: 0,
-                        shader_location: 2,
-                    }],
-                },
-            ],
+            buffers: vertex_buffers,
         },
         fragment: Some(wgpu::FragmentState {
             module: &frag_module,
@@ -142,42 +292,212 @@ pub fn create_gbuffer_pipeline(
     })
 }
 
+// ============================================================
+// Decal Pipeline
+// ============================================================
+
+/// Decal storage buffer, scene depth, albedo and normal maps and their sampler.
+pub fn create_decal_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    let texture = |binding: u32, sample_type: wgpu::TextureSampleType| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            sample_type,
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+        },
+        count: None,
+    };
+    let color = wgpu::TextureSampleType::Float { filterable: true };
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Decal BGL"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            texture(1, DEPTH_VALUES),
+            texture(2, color),
+            texture(3, color),
+            wgpu::BindGroupLayoutEntry {
+                binding: 4,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+    })
+}
+
+/// Blend the decal's rgb by its alpha, keeping the target's alpha.
+const DECAL_COLOR_BLEND: wgpu::BlendState = wgpu::BlendState {
+    color: wgpu::BlendComponent {
+        src_factor: wgpu::BlendFactor::SrcAlpha,
+        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+        operation: wgpu::BlendOperation::Add,
+    },
+    alpha: wgpu::BlendComponent {
+        src_factor: wgpu::BlendFactor::Zero,
+        dst_factor: wgpu::BlendFactor::One,
+        operation: wgpu::BlendOperation::Add,
+    },
+};
+
+/// Blend the blend constant into the target's alpha by the source alpha.
+const DECAL_ROUGHNESS_BLEND: wgpu::BlendState = wgpu::BlendState {
+    color: wgpu::BlendComponent::REPLACE,
+    alpha: wgpu::BlendComponent {
+        src_factor: wgpu::BlendFactor::Constant,
+        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+        operation: wgpu::BlendOperation::Add,
+    },
+};
+
+/// Decals draw the back faces of their boxes into the albedo and normal
+/// G-Buffer targets, without a depth attachment so they can read the depth.
+/// The `roughness` variant only writes the normal target's alpha, weighting
+/// the blend constant.
+pub fn create_decal_pipeline(
+    device: &wgpu::Device,
+    per_frame_bgl: &wgpu::BindGroupLayout,
+    decal_bgl: &wgpu::BindGroupLayout,
+    roughness: bool,
+) -> wgpu::RenderPipeline {
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Decal Shader"),
+        source: wgpu::ShaderSource::Wgsl(shaders::DECAL_SHADER.into()),
+    });
+
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Decal Pipeline Layout"),
+        bind_group_layouts: &[per_frame_bgl, decal_bgl],
+        push_constant_ranges: &[],
+    });
+
+    let (label, entry_point, targets) = if roughness {
+        (
+            "Decal Roughness Pipeline",
+            "fs_roughness",
+            [
+                Some(wgpu::ColorTargetState {
+                    format: HDR_FORMAT,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::empty(),
+                }),
+                Some(wgpu::ColorTargetState {
+                    format: HDR_FORMAT,
+                    blend: Some(DECAL_ROUGHNESS_BLEND),
+                    write_mask: wgpu::ColorWrites::ALPHA,
+                }),
+            ],
+        )
+    } else {
+        let target = Some(wgpu::ColorTargetState {
+            format: HDR_FORMAT,
+            blend: Some(DECAL_COLOR_BLEND),
+            write_mask: wgpu::ColorWrites::ALL,
+        });
+        ("Decal Pipeline", "fs_main", [target.clone(), target])
+    };
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: &module,
+            entry_point: Some("vs_main"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &module,
+            entry_point: Some(entry_point),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            targets: &targets,
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            cull_mode: Some(wgpu::Face::Front),
+            ..Default::default()
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
+
 // ============================================================
 // Shadow Depth Pipeline
 // ============================================================
 
+/// Instanced shadow depth pipeline for static meshes; instances at group 1.
 pub fn create_shadow_pipeline(
+    device: &wgpu::Device,
+    per_frame_bgl: &wgpu::BindGroupLayout,
+    instance_bgl: &wgpu::BindGroupLayout,
+) -> wgpu::RenderPipeline {
+    let [pos, _, _] = MESH_VERTEX_BUFFERS;
+    build_shadow_pipeline(
+        device,
+        "Shadow",
+        shaders::SHADOW_DEPTH_VERT,
+        &[per_frame_bgl, instance_bgl],
+        &[pos],
+    )
+}
+
+/// Shadow depth pipeline for skinned meshes. Bone palette at group 2, bone
+/// weights/indices at vertex slots 1-2.
+pub fn create_skinned_shadow_pipeline(
     device: &wgpu::Device,
     per_frame_bgl: &wgpu::BindGroupLayout,
     per_object_bgl: &wgpu::BindGroupLayout,
+    skin_bgl: &wgpu::BindGroupLayout,
+) -> wgpu::RenderPipeline {
+    let [pos, _, _] = MESH_VERTEX_BUFFERS;
+    let [weights, indices] = SHADOW_SKIN_BUFFERS;
+    build_shadow_pipeline(
+        device,
+        "Shadow Skinned",
+        shaders::SHADOW_DEPTH_SKINNED_VERT,
+        &[per_frame_bgl, per_object_bgl, skin_bgl],
+        &[pos, weights, indices],
+    )
+}
+
+fn build_shadow_pipeline(
+    device: &wgpu::Device,
+    label: &str,
+    vert_source: &str,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+    vertex_buffers: &[wgpu::VertexBufferLayout<'_>],
 ) -> wgpu::RenderPipeline {
     let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-        label: Some("Shadow Depth"),
-        source: wgpu::ShaderSource::Wgsl(shaders::SHADOW_DEPTH_VERT.into()),
+        label: Some(&format!("{label} Depth")),
+        source: wgpu::ShaderSource::Wgsl(vert_source.into()),
     });
 
     let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-        label: Some("Shadow Pipeline Layout"),
-        bind_group_layouts: &[per_frame_bgl, per_object_bgl],
+        label: Some(&format!("{label} Pipeline Layout")),
+        bind_group_layouts,
         push_constant_ranges: &[],
     });
 
     device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-        label: Some("Shadow Pipeline"),
+        label: Some(&format!("{label} Pipeline")),
         layout: Some(&layout),
         vertex: wgpu::VertexState {
             module: &module,
             entry_point: Some("vs_main"),
             compilation_options: wgpu::PipelineCompilationOptions::default(),
-            buffers: &[wgpu::VertexBufferLayout {
-                array_stride: 12,
-                step_mode: wgpu::VertexStepMode::Vertex,
-                attributes: &[wgpu::VertexAttribute {
-                    format: wgpu::VertexFormat::Float32x3,
-                    offset: 0,
-                    shader_location: 0,
-                }],
-            }],
+            buffers: vertex_buffers,
         },
         fragment: None, // Depth-only
         primitive: wgpu::PrimitiveState {
@@ -202,6 +522,154 @@ pub fn create_shadow_pipeline(
     })
 }
 
+/// Resets shadow atlas tiles to the far plane: a fullscreen triangle at depth
+/// 1.0 that always passes, drawn with the viewport set to the stale tile.
+pub fn create_shadow_clear_pipeline(device: &wgpu::Device) -> wgpu::RenderPipeline {
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Shadow Clear"),
+        source: wgpu::ShaderSource::Wgsl(shaders::SHADOW_CLEAR_VERT.into()),
+    });
+
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Shadow Clear Pipeline Layout"),
+        bind_group_layouts: &[],
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Shadow Clear Pipeline"),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: &module,
+            entry_point: Some("vs_main"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            buffers: &[],
+        },
+        fragment: None,
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::Always,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
+
+// ============================================================
+// GPU Instance Culling
+// ============================================================
+
+/// Bind group layout of `instance_cull.wgsl`: params, input instances, cull
+/// data, compacted output instances and indirect draw arguments.
+pub fn create_instance_cull_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    let storage = |binding: u32, read_only: bool| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    };
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Instance Cull BGL"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            storage(1, true),
+            storage(2, true),
+            storage(3, false),
+            storage(4, false),
+        ],
+    })
+}
+
+pub fn create_instance_cull_pipeline(device: &wgpu::Device, cull_bgl: &wgpu::BindGroupLayout) -> wgpu::ComputePipeline {
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Instance Cull"),
+        source: wgpu::ShaderSource::Wgsl(shaders::INSTANCE_CULL_SHADER.into()),
+    });
+
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Instance Cull Pipeline Layout"),
+        bind_group_layouts: &[cull_bgl],
+        push_constant_ranges: &[],
+    });
+
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some("Instance Cull Pipeline"),
+        layout: Some(&layout),
+        module: &module,
+        entry_point: Some("cs_main"),
+        compilation_options: wgpu::PipelineCompilationOptions::default(),
+        cache: None,
+    })
+}
+
+// ============================================================
+// Light Clustering
+// ============================================================
+
+/// Bind group layout of `light_cluster.wgsl`: ClusterParams, point lights,
+/// then the per-cluster light counts and indices it writes.
+pub fn create_light_cluster_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    let buffer = |binding: u32, ty: wgpu::BufferBindingType| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    };
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Light Cluster BGL"),
+        entries: &[
+            buffer(0, wgpu::BufferBindingType::Uniform),
+            buffer(1, wgpu::BufferBindingType::Storage { read_only: true }),
+            buffer(2, wgpu::BufferBindingType::Storage { read_only: false }),
+            buffer(3, wgpu::BufferBindingType::Storage { read_only: false }),
+        ],
+    })
+}
+
+pub fn create_light_cluster_pipeline(device: &wgpu::Device, cluster_bgl: &wgpu::BindGroupLayout) -> wgpu::ComputePipeline {
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Light Cluster"),
+        source: wgpu::ShaderSource::Wgsl(shaders::LIGHT_CLUSTER_SHADER.into()),
+    });
+
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Light Cluster Pipeline Layout"),
+        bind_group_layouts: &[cluster_bgl],
+        push_constant_ranges: &[],
+    });
+
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some("Light Cluster Pipeline"),
+        layout: Some(&layout),
+        module: &module,
+        entry_point: Some("cs_main"),
+        compilation_options: wgpu::PipelineCompilationOptions::default(),
+        cache: None,
+    })
+}
+
 // ============================================================
 // Deferred Lighting Pipeline
 // ============================================================
@@ -267,7 +735,7 @@ pub fn create_lighting_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGro
                 binding: 5,
                 visibility: wgpu::ShaderStages::FRAGMENT,
                 ty: wgpu::BindingType::Texture {
-                    sample_type: wgpu::TextureSampleType::Depth,
+                    sample_type: DEPTH_VALUES,
                     view_dimension: wgpu::TextureViewDimension::D2,
                     multisampled: false,
                 },
@@ -313,11 +781,161 @@ pub fn create_lighting_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGro
     })
 }
 
+/// Stages that read the light data: the lighting and forward fragment
+/// shaders, and the fog inject compute pass.
+const LIGHT_DATA_STAGES: wgpu::ShaderStages = wgpu::ShaderStages::FRAGMENT.union(wgpu::ShaderStages::COMPUTE);
+
+/// Light data for the deferred lighting pass and the fog inject pass:
+/// LightData uniform, then the clustered point lights, spot and area lights
+/// (see `light_buffer_entries`), the local light shadow atlas, the shadow
+/// comparison sampler and the cascaded shadow maps (see `csm_entries`).
 pub fn create_light_data_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    let [lights, params, counts, indices, spots, areas] = light_buffer_entries(1);
+    let [atlas, shadows] = local_shadow_entries(7);
+    let [csm_uniforms, csm_depth, csm_moments, csm_sampler, csm_depth_values] = csm_entries(10);
     device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
         label: Some("Light Data BGL"),
-        entries: &[wgpu::BindGroupLayoutEntry {
-            binding: 0,
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: LIGHT_DATA_STAGES,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            lights,
+            params,
+            counts,
+            indices,
+            spots,
+            areas,
+            atlas,
+            shadows,
+            wgpu::BindGroupLayoutEntry {
+                binding: 9,
+                visibility: LIGHT_DATA_STAGES,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+                count: None,
+            },
+            csm_uniforms,
+            csm_depth,
+            csm_moments,
+            csm_sampler,
+            csm_depth_values,
+        ],
+    })
+}
+
+/// Entries for the cascaded shadow maps, starting at `first`:
+/// ShadowUniforms, the cascade depth array, the EVSM moments array, the
+/// filtering sampler the moments are read with, and the cascade depth array
+/// again as `DEPTH_VALUES` for the PCSS blocker search.
+fn csm_entries(first: u32) -> [wgpu::BindGroupLayoutEntry; 5] {
+    let texture = |binding: u32, sample_type: wgpu::TextureSampleType| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: LIGHT_DATA_STAGES,
+        ty: wgpu::BindingType::Texture {
+            sample_type,
+            view_dimension: wgpu::TextureViewDimension::D2Array,
+            multisampled: false,
+        },
+        count: None,
+    };
+    [
+        wgpu::BindGroupLayoutEntry {
+            binding: first,
+            visibility: LIGHT_DATA_STAGES,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        },
+        texture(first + 1, wgpu::TextureSampleType::Depth),
+        texture(first + 2, wgpu::TextureSampleType::Float { filterable: true }),
+        wgpu::BindGroupLayoutEntry {
+            binding: first + 3,
+            visibility: LIGHT_DATA_STAGES,
+            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+            count: None,
+        },
+        texture(first + 4, DEPTH_VALUES),
+    ]
+}
+
+/// Entries for the local light shadow atlas depth texture and
+/// its `LocalShadowData` storage buffer, starting at `first`.
+fn local_shadow_entries(first: u32) -> [wgpu::BindGroupLayoutEntry; 2] {
+    [
+        wgpu::BindGroupLayoutEntry {
+            binding: first,
+            visibility: LIGHT_DATA_STAGES,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Depth,
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        },
+        wgpu::BindGroupLayoutEntry {
+            binding: first + 1,
+            visibility: LIGHT_DATA_STAGES,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        },
+    ]
+}
+
+/// Entries for the light storage buffers, starting at `first`:
+/// point light storage, ClusterParams uniform, per-cluster light counts,
+/// per-cluster light indices, spot light storage and area light storage.
+fn light_buffer_entries(first: u32) -> [wgpu::BindGroupLayoutEntry; 6] {
+    let buffer = |binding: u32, ty: wgpu::BufferBindingType| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: LIGHT_DATA_STAGES,
+        ty: wgpu::BindingType::Buffer {
+            ty,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    };
+    let storage = wgpu::BufferBindingType::Storage { read_only: true };
+    [
+        buffer(first, storage),
+        buffer(first + 1, wgpu::BufferBindingType::Uniform),
+        buffer(first + 2, storage),
+        buffer(first + 3, storage),
+        buffer(first + 4, storage),
+        buffer(first + 5, storage),
+    ]
+}
+
+/// Fragment-stage entries for the sky and fog, starting at `first`:
+/// EnvironmentUniforms, the sky cubemap, the integrated fog volume and the
+/// linear clamp sampler both are read with.
+fn environment_entries(first: u32) -> [wgpu::BindGroupLayoutEntry; 4] {
+    let texture = |binding: u32, view_dimension: wgpu::TextureViewDimension| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            view_dimension,
+            multisampled: false,
+        },
+        count: None,
+    };
+    [
+        wgpu::BindGroupLayoutEntry {
+            binding: first,
             visibility: wgpu::ShaderStages::FRAGMENT,
             ty: wgpu::BindingType::Buffer {
                 ty: wgpu::BufferBindingType::Uniform,
@@ -325,7 +943,23 @@ pub fn create_light_data_bind_group_layout(device: &wgpu::Device) -> wgpu::BindG
                 min_binding_size: None,
             },
             count: None,
-        }],
+        },
+        texture(first + 1, wgpu::TextureViewDimension::Cube),
+        texture(first + 2, wgpu::TextureViewDimension::D3),
+        wgpu::BindGroupLayoutEntry {
+            binding: first + 3,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+            count: None,
+        },
+    ]
+}
+
+/// Sky and fog of the deferred lighting pass (bind group 2).
+pub fn create_environment_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Environment BGL"),
+        entries: &environment_entries(0),
     })
 }
 
@@ -333,6 +967,7 @@ pub fn create_lighting_pipeline(
     device: &wgpu::Device,
     lighting_bgl: &wgpu::BindGroupLayout,
     light_data_bgl: &wgpu::BindGroupLayout,
+    environment_bgl: &wgpu::BindGroupLayout,
 ) -> wgpu::RenderPipeline {
     let vert_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
         label: Some("Fullscreen Quad Vert"),
@@ -346,7 +981,7 @@ pub fn create_lighting_pipeline(
 
     let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
         label: Some("Lighting Pipeline Layout"),
-        bind_group_layouts: &[lighting_bgl, light_data_bgl],
+        bind_group_layouts: &[lighting_bgl, light_data_bgl, environment_bgl],
         push_constant_ranges: &[],
     });
 
@@ -385,6 +1020,14 @@ pub fn create_effect_bind_group_layout(
     num_textures: u32,
     _has_depth_texture: bool,
 ) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some(label),
+        entries: &effect_layout_entries(num_textures),
+    })
+}
+
+/// Params uniform, `num_textures` filterable textures, then a sampler.
+fn effect_layout_entries(num_textures: u32) -> Vec<wgpu::BindGroupLayoutEntry> {
     let mut entries = vec![
         // binding 0: params uniform
         wgpu::BindGroupLayoutEntry {
@@ -419,16 +1062,12 @@ pub fn create_effect_bind_group_layout(
         ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
         count: None,
     });
-
-    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-        label: Some(label),
-        entries: &entries,
-    })
+    entries
 }
 
 /// SSAO bind group layout — matches ssao.wgsl:
 ///   0: uniform SSAOParams
-///   1: texture_depth_2d (g_depth)
+///   1: texture_2d<f32>  (g_depth, `DEPTH_VALUES`)
 ///   2: texture_2d<f32>  (g_normal_roughness)
 ///   3: texture_2d<f32>  (noise_texture)
 ///   4: sampler           (tex_sampler)
@@ -451,7 +1090,7 @@ pub fn create_ssao_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLa
                 binding: 1,
                 visibility: wgpu::ShaderStages::FRAGMENT,
                 ty: wgpu::BindingType::Texture {
-                    sample_type: wgpu::TextureSampleType::Depth,
+                    sample_type: DEPTH_VALUES,
                     view_dimension: wgpu::TextureViewDimension::D2,
                     multisampled: false,
                 },
@@ -495,7 +1134,7 @@ pub fn create_ssao_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLa
 
 /// SSR bind group layout — matches ssr.wgsl:
 ///   0: uniform SSRParams
-///   1: texture_depth_2d (g_depth)
+///   1: texture_2d<f32>  (g_depth, `DEPTH_VALUES`)
 ///   2: texture_2d<f32>  (g_normal_roughness)
 ///   3: texture_2d<f32>  (lighting_result)
 ///   4: sampler           (tex_sampler)
@@ -518,7 +1157,7 @@ pub fn create_ssr_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLay
                 binding: 1,
                 visibility: wgpu::ShaderStages::FRAGMENT,
                 ty: wgpu::BindingType::Texture {
-                    sample_type: wgpu::TextureSampleType::Depth,
+                    sample_type: DEPTH_VALUES,
                     view_dimension: wgpu::TextureViewDimension::D2,
                     multisampled: false,
                 },
@@ -564,7 +1203,7 @@ pub fn create_ssr_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLay
 ///   0: uniform TAAParams
 ///   1: texture_2d<f32>  (current_frame)
 ///   2: texture_2d<f32>  (history_frame)
-///   3: texture_depth_2d (depth_texture)
+///   3: texture_2d<f32>  (depth_texture, `DEPTH_VALUES`)
 ///   4: sampler           (tex_sampler)
 ///   5: sampler           (depth_sampler)
 pub fn create_taa_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
@@ -605,7 +1244,7 @@ pub fn create_taa_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLay
                 binding: 3,
                 visibility: wgpu::ShaderStages::FRAGMENT,
                 ty: wgpu::BindingType::Texture {
-                    sample_type: wgpu::TextureSampleType::Depth,
+                    sample_type: DEPTH_VALUES,
                     view_dimension: wgpu::TextureViewDimension::D2,
                     multisampled: false,
                 },
@@ -654,6 +1293,300 @@ pub fn create_fxaa_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLa
     })
 }
 
+/// Depth effect bind group layout — matches the DOF CoC and motion blur
+/// velocity passes:
+///   0: uniform params
+///   1: texture_2d<f32> (depth, `DEPTH_VALUES`)
+///   2: sampler (non-filtering)
+pub fn create_depth_effect_bind_group_layout(device: &wgpu::Device, label: &str) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some(label),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: DEPTH_VALUES,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
+                count: None,
+            },
+        ],
+    })
+}
+
+/// DOF composite bind group layout — matches dof.wgsl `fs_composite` (no uniform buffer):
+///   0: texture_2d<f32>  (sharp_texture)
+///   1: texture_2d<f32>  (blurred_texture)
+///   2: texture_2d<f32>  (composite_coc_texture)
+///   3: sampler           (composite_sampler)
+pub fn create_dof_composite_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    let texture = |binding| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+        },
+        count: None,
+    };
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("DOF Composite BGL"),
+        entries: &[
+            texture(0),
+            texture(1),
+            texture(2),
+            wgpu::BindGroupLayoutEntry {
+                binding: 3,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+    })
+}
+
+/// EVSM moments bind group layout — matches evsm_moments.wgsl:
+///   0: ShadowUniforms
+///   1: texture_2d<f32>   (one cascade layer, `DEPTH_VALUES`)
+pub fn create_evsm_moments_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("EVSM Moments BGL"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: DEPTH_VALUES,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+        ],
+    })
+}
+
+/// Bind group layout of the `bloom_composite.wgsl` tone mapping pass: the
+/// effect layout with two textures, plus the exposure state at binding 4.
+pub fn create_tone_map_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    let mut entries = effect_layout_entries(2);
+    entries.push(wgpu::BindGroupLayoutEntry {
+        binding: 4,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only: true },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    });
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Tone Map BGL"),
+        entries: &entries,
+    })
+}
+
+/// Bind group layout of `color_grading.wgsl`: the effect layout with one
+/// texture, plus the unfilterable 3D LUT at binding 3.
+pub fn create_color_grading_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    let mut entries = effect_layout_entries(1);
+    entries.push(wgpu::BindGroupLayoutEntry {
+        binding: 3,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+            view_dimension: wgpu::TextureViewDimension::D3,
+            multisampled: false,
+        },
+        count: None,
+    });
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Color Grading BGL"),
+        entries: &entries,
+    })
+}
+
+/// Bind group layout of `luminance_histogram.wgsl`: ExposureParams, the HDR
+/// image, then the histogram and exposure state it writes.
+pub fn create_exposure_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    let buffer = |binding: u32, ty: wgpu::BufferBindingType| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    };
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Exposure BGL"),
+        entries: &[
+            buffer(0, wgpu::BufferBindingType::Uniform),
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            buffer(2, wgpu::BufferBindingType::Storage { read_only: false }),
+            buffer(3, wgpu::BufferBindingType::Storage { read_only: false }),
+        ],
+    })
+}
+
+/// One of the `luminance_histogram.wgsl` passes (`cs_histogram` or `cs_average`).
+pub fn create_exposure_pipeline(
+    device: &wgpu::Device,
+    exposure_bgl: &wgpu::BindGroupLayout,
+    entry_point: &str,
+) -> wgpu::ComputePipeline {
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Luminance Histogram"),
+        source: wgpu::ShaderSource::Wgsl(shaders::LUMINANCE_HISTOGRAM_SHADER.into()),
+    });
+
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Exposure Pipeline Layout"),
+        bind_group_layouts: &[exposure_bgl],
+        push_constant_ranges: &[],
+    });
+
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some(&format!("Exposure {entry_point} Pipeline")),
+        layout: Some(&layout),
+        module: &module,
+        entry_point: Some(entry_point),
+        compilation_options: wgpu::PipelineCompilationOptions::default(),
+        cache: None,
+    })
+}
+
+/// Bind group layout of `fog_inject.wgsl` (bind group 0; group 1 is the
+/// light data layout): PerFrame, FogUniforms, EnvironmentUniforms and the
+/// scattering volume it writes.
+pub fn create_fog_inject_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    let uniform = |binding: u32| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    };
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Fog Inject BGL"),
+        entries: &[uniform(0), uniform(1), uniform(2), fog_volume_storage_entry(3)],
+    })
+}
+
+/// Bind group layout of `fog_integrate.wgsl`: FogUniforms, the injected
+/// scattering volume and the integrated volume it writes.
+pub fn create_fog_integrate_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Fog Integrate BGL"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D3,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            fog_volume_storage_entry(2),
+        ],
+    })
+}
+
+fn fog_volume_storage_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::StorageTexture {
+            access: wgpu::StorageTextureAccess::WriteOnly,
+            format: wgpu::TextureFormat::Rgba16Float,
+            view_dimension: wgpu::TextureViewDimension::D3,
+        },
+        count: None,
+    }
+}
+
+/// One of the fog compute passes, `cs_main` of `source` over `bgls`.
+pub fn create_fog_pipeline(
+    device: &wgpu::Device,
+    label: &str,
+    source: &str,
+    bgls: &[&wgpu::BindGroupLayout],
+) -> wgpu::ComputePipeline {
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some(label),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+    });
+
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some(&format!("{label} Layout")),
+        bind_group_layouts: bgls,
+        push_constant_ranges: &[],
+    });
+
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some(label),
+        layout: Some(&layout),
+        module: &module,
+        entry_point: Some("cs_main"),
+        compilation_options: wgpu::PipelineCompilationOptions::default(),
+        cache: None,
+    })
+}
+
 /// Create a fullscreen effect pipeline with a given fragment shader and output format.
 pub fn create_fullscreen_effect_pipeline(
     device: &wgpu::Device,
@@ -662,6 +1595,18 @@ pub fn create_fullscreen_effect_pipeline(
     frag_entry: &str,
     bgl: &wgpu::BindGroupLayout,
     output_format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    fullscreen_pipeline(device, label, frag_source, frag_entry, bgl, output_format, 1)
+}
+
+fn fullscreen_pipeline(
+    device: &wgpu::Device,
+    label: &str,
+    frag_source: &str,
+    frag_entry: &str,
+    bgl: &wgpu::BindGroupLayout,
+    output_format: wgpu::TextureFormat,
+    sample_count: u32,
 ) -> wgpu::RenderPipeline {
     let vert_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
         label: Some("Fullscreen Vert"),
@@ -695,7 +1640,7 @@ pub fn create_fullscreen_effect_pipeline(
         }),
         primitive: wgpu::PrimitiveState::default(),
         depth_stencil: None,
-        multisample: wgpu::MultisampleState::default(),
+        multisample: wgpu::MultisampleState { count: sample_count, ..Default::default() },
         multiview: None,
         cache: None,
     })
@@ -706,6 +1651,10 @@ pub fn create_fullscreen_effect_pipeline(
 // ============================================================
 
 pub fn create_forward_light_shadow_bgl(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    let [lights, params, counts, indices, spots, areas] = light_buffer_entries(7);
+    let [atlas, shadows] = local_shadow_entries(13);
+    let [csm_uniforms, csm_depth, csm_moments, csm_sampler, csm_depth_values] = csm_entries(1);
+    let [env, sky, fog, env_sampler] = environment_entries(15);
     device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
         label: Some("Forward Light+Shadow BGL"),
         entries: &[
@@ -720,58 +1669,12 @@ pub fn create_forward_light_shadow_bgl(device: &wgpu::Device) -> wgpu::BindGroup
                 },
                 count: None,
             },
-            // 1: ShadowUniforms
-            wgpu::BindGroupLayoutEntry {
-                binding: 1,
-                visibility: wgpu::ShaderStages::FRAGMENT,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            },
-            // 2-5: shadow cascade depth textures
-            wgpu::BindGroupLayoutEntry {
-                binding: 2,
-                visibility: wgpu::ShaderStages::FRAGMENT,
-                ty: wgpu::BindingType::Texture {
-                    sample_type: wgpu::TextureSampleType::Depth,
-                    view_dimension: wgpu::TextureViewDimension::D2,
-                    multisampled: false,
-                },
-                count: None,
-            },
-            wgpu::BindGroupLayoutEntry {
-                binding: 3,
-                visibility: wgpu::ShaderStages::FRAGMENT,
-                ty: wgpu::BindingType::Texture {
-                    sample_type: wgpu::TextureSampleType::Depth,
-                    view_dimension: wgpu::TextureViewDimension::D2,
-                    multisampled: false,
-                },
-                count: None,
-            },
-            wgpu::BindGroupLayoutEntry {
-                binding: 4,
-                visibility: wgpu::ShaderStages::FRAGMENT,
-                ty: wgpu::BindingType::Texture {
-                    sample_type: wgpu::TextureSampleType::Depth,
-                    view_dimension: wgpu::TextureViewDimension::D2,
-                    multisampled: false,
-                },
-                count: None,
-            },
-            wgpu::BindGroupLayoutEntry {
-                binding: 5,
-                visibility: wgpu::ShaderStages::FRAGMENT,
-                ty: wgpu::BindingType::Texture {
-                    sample_type: wgpu::TextureSampleType::Depth,
-                    view_dimension: wgpu::TextureViewDimension::D2,
-                    multisampled: false,
-                },
-                count: None,
-            },
+            // 1-5: cascaded shadow maps
+            csm_uniforms,
+            csm_depth,
+            csm_moments,
+            csm_sampler,
+            csm_depth_values,
             // 6: shadow comparison sampler
             wgpu::BindGroupLayoutEntry {
                 binding: 6,
@@ -779,6 +1682,21 @@ pub fn create_forward_light_shadow_bgl(device: &wgpu::Device) -> wgpu::BindGroup
                 ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
                 count: None,
             },
+            // 7-10: clustered point lights, 11-12: spot and area lights
+            lights,
+            params,
+            counts,
+            indices,
+            spots,
+            areas,
+            // 13-14: local light shadow atlas and records
+            atlas,
+            shadows,
+            // 15-18: sky and fog
+            env,
+            sky,
+            fog,
+            env_sampler,
         ],
     })
 }
@@ -789,6 +1707,42 @@ pub fn create_forward_pipeline(
     material_bgl: &wgpu::BindGroupLayout,
     per_object_bgl: &wgpu::BindGroupLayout,
     light_shadow_bgl: &wgpu::BindGroupLayout,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
+    let layouts = [per_frame_bgl, material_bgl, per_object_bgl, light_shadow_bgl];
+    build_forward_pipeline(device, "Forward PBR Pipeline", "vs_main", &layouts, &MESH_VERTEX_BUFFERS, sample_count)
+}
+
+/// Forward pipeline for skinned meshes. `skinned_object_bgl` adds the bone
+/// palette to group 2, and bone weights/indices use vertex slots 3-4.
+pub fn create_skinned_forward_pipeline(
+    device: &wgpu::Device,
+    per_frame_bgl: &wgpu::BindGroupLayout,
+    material_bgl: &wgpu::BindGroupLayout,
+    skinned_object_bgl: &wgpu::BindGroupLayout,
+    light_shadow_bgl: &wgpu::BindGroupLayout,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
+    let [pos, normal, uv] = MESH_VERTEX_BUFFERS;
+    let [weights, indices] = GBUFFER_SKIN_BUFFERS;
+    let layouts = [per_frame_bgl, material_bgl, skinned_object_bgl, light_shadow_bgl];
+    build_forward_pipeline(
+        device,
+        "Forward PBR Skinned Pipeline",
+        "vs_skinned",
+        &layouts,
+        &[pos, normal, uv, weights, indices],
+        sample_count,
+    )
+}
+
+fn build_forward_pipeline(
+    device: &wgpu::Device,
+    label: &str,
+    vs_entry: &str,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+    vertex_buffers: &[wgpu::VertexBufferLayout<'_>],
+    sample_count: u32,
 ) -> wgpu::RenderPipeline {
     let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
         label: Some("Forward PBR"),
@@ -797,46 +1751,18 @@ pub fn create_forward_pipeline(
 
     let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
         label: Some("Forward Pipeline Layout"),
-        bind_group_layouts: &[per_frame_bgl, material_bgl, per_object_bgl, light_shadow_bgl],
+        bind_group_layouts,
         push_constant_ranges: &[],
     });
 
     device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-        label: Some("Forward PBR Pipeline"),
+        label: Some(label),
         layout: Some(&layout),
         vertex: wgpu::VertexState {
             module: &module,
-            entry_point: Some("vs_main"),
+            entry_point: Some(vs_entry),
             compilation_options: wgpu::PipelineCompilationOptions::default(),
-            buffers: &[
-                wgpu::VertexBufferLayout {
-                    array_stride: 12,
-                    step_mode: wgpu::VertexStepMode::Vertex,
-                    attributes: &[wgpu::VertexAttribute {
-                        format: wgpu::VertexFormat::Float32x3,
-                        offset: 0,
-                        shader_location: 0,
-                    }],
-                },
-                wgpu::VertexBufferLayout {
-                    array_stride: 12,
-                    step_mode: wgpu::VertexStepMode::Vertex,
-                    attributes: &[wgpu::VertexAttribute {
-                        format: wgpu::VertexFormat::Float32x3,
-                        offset: 0,
-                        shader_location: 1,
-                    }],
-                },
-                wgpu::VertexBufferLayout {
-                    array_stride: 8,
-                    step_mode: wgpu::VertexStepMode::Vertex,
-                    attributes: &[wgpu::VertexAttribute {
-                        format: wgpu::VertexFormat::Float32x2,
-                        offset: 0,
-                        shader_location: 2,
-                    }],
-                },
-            ],
+            buffers: vertex_buffers,
         },
         fragment: Some(wgpu::FragmentState {
             module: &module,
@@ -865,7 +1791,7 @@ pub fn create_forward_pipeline(
             ..Default::default()
         },
         depth_stencil: Some(depth_stencil_readonly()),
-        multisample: wgpu::MultisampleState::default(),
+        multisample: wgpu::MultisampleState { count: sample_count, ..Default::default() },
         multiview: None,
         cache: None,
     })
@@ -878,23 +1804,70 @@ pub fn create_forward_pipeline(
 pub fn create_particle_bgl(device: &wgpu::Device) -> wgpu::BindGroupLayout {
     device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
         label: Some("Particle BGL"),
-        entries: &[wgpu::BindGroupLayoutEntry {
-            binding: 0,
-            visibility: wgpu::ShaderStages::VERTEX,
-            ty: wgpu::BindingType::Buffer {
-                ty: wgpu::BufferBindingType::Uniform,
-                has_dynamic_offset: false,
-                min_binding_size: None,
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
             },
-            count: None,
-        }],
+            // Scene depth for soft particles
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: DEPTH_VALUES,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            // Sprite texture + sampler
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 3,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
     })
 }
 
+/// Blend state for a particle blend mode (0 alpha, 1 additive, 2 premultiplied).
+pub fn particle_blend_state(blend_mode: u32) -> wgpu::BlendState {
+    match blend_mode {
+        1 => wgpu::BlendState {
+            color: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::SrcAlpha,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Add,
+            },
+            alpha: wgpu::BlendComponent::OVER,
+        },
+        2 => wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+        _ => wgpu::BlendState::ALPHA_BLENDING,
+    }
+}
+
+/// Particles draw into the HDR post-process target, after forward rendering.
 pub fn create_particle_pipeline(
     device: &wgpu::Device,
     particle_bgl: &wgpu::BindGroupLayout,
-    surface_format: wgpu::TextureFormat,
+    blend_mode: u32,
 ) -> wgpu::RenderPipeline {
     let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
         label: Some("Particle Shader"),
@@ -944,15 +1917,8 @@ pub fn create_particle_pipeline(
             entry_point: Some("fs_main"),
             compilation_options: wgpu::PipelineCompilationOptions::default(),
             targets: &[Some(wgpu::ColorTargetState {
-                format: surface_format,
-                blend: Some(wgpu::BlendState {
-                    color: wgpu::BlendComponent {
-                        src_factor: wgpu::BlendFactor::SrcAlpha,
-                        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                        operation: wgpu::BlendOperation::Add,
-                    },
-                    alpha: wgpu::BlendComponent::OVER,
-                }),
+                format: HDR_FORMAT,
+                blend: Some(particle_blend_state(blend_mode)),
                 write_mask: wgpu::ColorWrites::ALL,
             })],
         }),
@@ -1010,6 +1976,7 @@ pub fn create_ui_pipeline(
     device: &wgpu::Device,
     ui_bgl: &wgpu::BindGroupLayout,
     surface_format: wgpu::TextureFormat,
+    sample_count: u32,
 ) -> wgpu::RenderPipeline {
     let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
         label: Some("UI Shader"),
@@ -1070,7 +2037,7 @@ pub fn create_ui_pipeline(
             ..Default::default()
         },
         depth_stencil: None,
-        multisample: wgpu::MultisampleState::default(),
+        multisample: wgpu::MultisampleState { count: sample_count, ..Default::default() },
         multiview: None,
         cache: None,
     })
@@ -1272,17 +2239,122 @@ pub fn create_present_bgl(device: &wgpu::Device) -> wgpu::BindGroupLayout {
     })
 }
 
+/// Present pipeline; multisampled like the UI pipeline, which draws in the same pass.
 pub fn create_present_pipeline(
     device: &wgpu::Device,
     present_bgl: &wgpu::BindGroupLayout,
     surface_format: wgpu::TextureFormat,
+    sample_count: u32,
 ) -> wgpu::RenderPipeline {
-    create_fullscreen_effect_pipeline(
+    fullscreen_pipeline(
         device,
         "Present Pipeline",
         shaders::PRESENT_FRAG,
         "fs_main",
         present_bgl,
         surface_format,
+        sample_count,
     )
 }
+
+// ============================================================
+// MSAA Prime Pipeline
+// ============================================================
+
+/// Lit image and G-Buffer depth, copied into the multisampled forward targets.
+pub fn create_msaa_prime_bgl(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("MSAA Prime BGL"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: DEPTH_VALUES,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+        ],
+    })
+}
+
+pub fn create_msaa_prime_pipeline(
+    device: &wgpu::Device,
+    prime_bgl: &wgpu::BindGroupLayout,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
+    let vert_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Fullscreen Vert"),
+        source: wgpu::ShaderSource::Wgsl(shaders::FULLSCREEN_QUAD_VERT.into()),
+    });
+    let frag_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("MSAA Prime"),
+        source: wgpu::ShaderSource::Wgsl(shaders::MSAA_PRIME_FRAG.into()),
+    });
+
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("MSAA Prime Pipeline Layout"),
+        bind_group_layouts: &[prime_bgl],
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("MSAA Prime Pipeline"),
+        layout: Some(&layout),
+        vertex: fullscreen_vertex_state(&vert_module),
+        fragment: Some(wgpu::FragmentState {
+            module: &frag_module,
+            entry_point: Some("fs_main"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: HDR_FORMAT,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        // Overwrite every sample's depth with the G-Buffer's
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::Always,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState { count: sample_count, ..Default::default() },
+        multiview: None,
+        cache: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skin_streams_match_upload_layout() {
+        let gbuffer_location = MESH_VERTEX_BUFFERS.len() as u32;
+        for (buffers, first_location) in [(&GBUFFER_SKIN_BUFFERS, gbuffer_location), (&SHADOW_SKIN_BUFFERS, 1)] {
+            let [weights, indices] = buffers;
+            // 4 f32 weights and 4 u16 indices per vertex, as `upload_skinned_mesh` takes them
+            assert_eq!(weights.array_stride, 4 * std::mem::size_of::<f32>() as u64);
+            assert_eq!(weights.attributes[0].format, wgpu::VertexFormat::Float32x4);
+            assert_eq!(weights.attributes[0].shader_location, first_location);
+            assert_eq!(indices.array_stride, 4 * std::mem::size_of::<u16>() as u64);
+            assert_eq!(indices.attributes[0].format, wgpu::VertexFormat::Uint16x4);
+            assert_eq!(indices.attributes[0].shader_location, first_location + 1);
+        }
+    }
+}
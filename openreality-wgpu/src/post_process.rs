@@ -0,0 +1,475 @@
+//! Runs the post-process stack over the deferred pipeline's targets. SSAO and
+//! SSR render the lighting pass inputs, or clear them to neutral values when
+//! disabled. The chain ping-pongs between `pp_target_a` and `pp_target_b` and
+//! always ends in `pp_target_b`, which particles and the present pass read, so
+//! effects can be toggled and reordered without touching any pipeline.
+
+use glam::Mat4;
+use openreality_gpu_shared::clustering::projection_depth_range;
+use openreality_gpu_shared::color_lut::CubeLut;
+use openreality_gpu_shared::post_process::{ssao_kernel, PostEffect};
+use openreality_gpu_shared::uniforms::{
+    ColorGradingParams, DOFBlurParams, DOFCoCParams, MotionBlurParams, PostProcessParams, SSAOParams, SSRParams,
+    TAAParams, VelocityParams,
+};
+
+use crate::backend::{RenderTarget, WGPUBackendState};
+use crate::passes;
+use crate::passes::postprocess::render_fullscreen_effect;
+
+/// Size of one `effect_params_buffer` slot (the uniform offset alignment).
+pub const SLOT_SIZE: u64 = 256;
+pub const NUM_SLOTS: u64 = 7;
+
+// Uniforms of passes that share a shader with different settings get their
+// own slot, since queue writes all land before the encoder runs.
+const SLOT_BLOOM_BLUR_H: u64 = 0;
+const SLOT_DOF_COC: u64 = 1;
+const SLOT_DOF_BLUR_H: u64 = 2;
+const SLOT_DOF_BLUR_V: u64 = 3;
+const SLOT_VELOCITY: u64 = 4;
+const SLOT_MOTION_BLUR: u64 = 5;
+const SLOT_COLOR_GRADING: u64 = 6;
+
+/// Fixed, so the SSAO kernel (and its noise) is the same every frame.
+const SSAO_KERNEL_SEED: u64 = 0x55A0;
+
+/// 3D LUT of the color grading pass; the identity until one is loaded. Kept
+/// on the backend state, so it survives deferred pipeline rebuilds.
+pub struct ColorLut {
+    pub view: wgpu::TextureView,
+    pub size: u32,
+    pub domain_min: [f32; 3],
+    pub domain_max: [f32; 3],
+}
+
+impl ColorLut {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, lut: &CubeLut) -> Self {
+        let extent = wgpu::Extent3d { width: lut.size, height: lut.size, depth_or_array_layers: lut.size };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(lut.title.as_deref().unwrap_or("Color LUT")),
+            size: extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D3,
+            format: wgpu::TextureFormat::Rgba32Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            bytemuck::cast_slice(&lut.to_rgba32f()),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(lut.size * 16),
+                rows_per_image: Some(lut.size),
+            },
+            extent,
+        );
+        Self {
+            view: texture.create_view(&wgpu::TextureViewDescriptor::default()),
+            size: lut.size,
+            domain_min: lut.domain_min,
+            domain_max: lut.domain_max,
+        }
+    }
+
+    pub fn identity(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        Self::new(device, queue, &CubeLut::identity(2))
+    }
+}
+
+fn slot(buffer: &wgpu::Buffer, slot: u64) -> wgpu::BindingResource<'_> {
+    wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+        buffer,
+        offset: slot * SLOT_SIZE,
+        size: wgpu::BufferSize::new(SLOT_SIZE),
+    })
+}
+
+/// Bind group whose entries are `resources` at bindings 0, 1, 2, ...
+fn bind_group(
+    device: &wgpu::Device,
+    label: &str,
+    layout: &wgpu::BindGroupLayout,
+    resources: Vec<wgpu::BindingResource>,
+) -> wgpu::BindGroup {
+    let entries: Vec<wgpu::BindGroupEntry> = resources
+        .into_iter()
+        .enumerate()
+        .map(|(i, resource)| wgpu::BindGroupEntry { binding: i as u32, resource })
+        .collect();
+    device.create_bind_group(&wgpu::BindGroupDescriptor { label: Some(label), layout, entries: &entries })
+}
+
+fn clear_target(encoder: &mut wgpu::CommandEncoder, target: &RenderTarget, color: wgpu::Color, label: &str) {
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some(label),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: &target.color_view,
+            resolve_target: None,
+            ops: wgpu::Operations { load: wgpu::LoadOp::Clear(color), store: wgpu::StoreOp::Store },
+        })],
+        ..Default::default()
+    });
+}
+
+/// SSAO from the G-Buffer into the AO input of the lighting pass; white AO
+/// when SSAO is disabled.
+pub fn render_ssao(state: &WGPUBackendState) -> Result<(), String> {
+    use wgpu::BindingResource::{Sampler, TextureView};
+
+    let dp = state.deferred.as_ref().ok_or("Deferred pipeline not created")?;
+    let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("SSAO Encoder"),
+    });
+
+    if state.post_process.is_enabled(PostEffect::Ssao) {
+        let (_, projection) = state.frame_camera.ok_or("No camera set for this frame")?;
+        let s = state.post_process.ssao;
+        let params = SSAOParams {
+            samples: ssao_kernel(s.kernel_size, SSAO_KERNEL_SEED),
+            projection: projection.to_cols_array_2d(),
+            kernel_size: s.kernel_size as i32,
+            radius: s.radius,
+            bias: s.bias,
+            power: s.power,
+            screen_width: dp.gbuffer.width as f32,
+            screen_height: dp.gbuffer.height as f32,
+            _pad1: 0.0,
+            _pad2: 0.0,
+        };
+        state.queue.write_buffer(&dp.ssao_params_buffer, 0, bytemuck::bytes_of(&params));
+
+        let ssao_bg = bind_group(&state.device, "SSAO BG", &dp.ssao_bgl, vec![
+            dp.ssao_params_buffer.as_entire_binding(),
+            TextureView(&dp.gbuffer.depth_view),
+            TextureView(&dp.gbuffer.normal_roughness_view),
+            TextureView(&dp.ssao_noise_view),
+            Sampler(&state.default_sampler),
+            Sampler(&dp.depth_sampler),
+        ]);
+        passes::ssao::render_ssao_pass(&mut encoder, &dp.ssao_targets.ao, &dp.ssao_pipeline, &ssao_bg);
+
+        let blur_bg = bind_group(&state.device, "SSAO Blur BG", &dp.ssao_blur_bgl, vec![
+            dp.ssao_params_buffer.as_entire_binding(),
+            TextureView(&dp.ssao_targets.ao.color_view),
+            Sampler(&state.default_sampler),
+        ]);
+        passes::ssao::render_ssao_blur(&mut encoder, &dp.ssao_targets.blur, &dp.ssao_blur_pipeline, &blur_bg);
+    } else {
+        clear_target(&mut encoder, &dp.ssao_targets.blur, wgpu::Color::WHITE, "SSAO Off");
+    }
+
+    state.queue.submit(std::iter::once(encoder.finish()));
+    Ok(())
+}
+
+/// Screen-space reflections of the last lit image into the SSR input of the
+/// lighting pass; no reflections (zero alpha) when SSR is disabled.
+pub fn render_ssr(state: &WGPUBackendState) -> Result<(), String> {
+    use wgpu::BindingResource::{Sampler, TextureView};
+
+    let dp = state.deferred.as_ref().ok_or("Deferred pipeline not created")?;
+    let mut encoder = state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("SSR Encoder"),
+    });
+
+    if state.post_process.is_enabled(PostEffect::Ssr) {
+        let (view, projection) = state.frame_camera.ok_or("No camera set for this frame")?;
+        let s = state.post_process.ssr;
+        let params = SSRParams {
+            projection: projection.to_cols_array_2d(),
+            view: view.to_cols_array_2d(),
+            inv_projection: projection.inverse().to_cols_array_2d(),
+            camera_pos: view.inverse().w_axis.truncate().extend(0.0).to_array(),
+            screen_size: [dp.gbuffer.width as f32, dp.gbuffer.height as f32],
+            max_steps: s.max_steps as i32,
+            max_distance: s.max_distance,
+            thickness: s.thickness,
+            _pad1: 0.0,
+            _pad2: 0.0,
+            _pad3: 0.0,
+        };
+        state.queue.write_buffer(&dp.ssr_params_buffer, 0, bytemuck::bytes_of(&params));
+
+        let ssr_bg = bind_group(&state.device, "SSR BG", &dp.ssr_bgl, vec![
+            dp.ssr_params_buffer.as_entire_binding(),
+            TextureView(&dp.gbuffer.depth_view),
+            TextureView(&dp.gbuffer.normal_roughness_view),
+            TextureView(&dp.lighting_target.color_view),
+            Sampler(&state.default_sampler),
+            Sampler(&dp.depth_sampler),
+        ]);
+        passes::ssr::render_ssr_pass(&mut encoder, &dp.ssr_target, &dp.ssr_pipeline, &ssr_bg);
+    } else {
+        clear_target(&mut encoder, &dp.ssr_target, wgpu::Color::TRANSPARENT, "SSR Off");
+    }
+
+    state.queue.submit(std::iter::once(encoder.finish()));
+    Ok(())
+}
+
+/// Run the enabled HDR effects, tone mapping and the enabled display-space
+/// effects over the lit image, in stack order, into `pp_target_b`.
+pub fn render_post_process(state: &mut WGPUBackendState) -> Result<(), String> {
+    let (view, projection) = state.frame_camera.ok_or("No camera set for this frame")?;
+    let dp = state.deferred.as_mut().ok_or("Deferred pipeline not created")?;
+    // Auto exposure meters the lit image, before any HDR effect
+    let size = (dp.lighting_target.width, dp.lighting_target.height);
+    let settings = &state.post_process.exposure;
+    dp.exposure.update(&state.device, &state.queue, &dp.lighting_target.color_view, size, settings);
+
+    record_chain(state, view, projection)?;
+
+    let taa = state.post_process.is_enabled(PostEffect::Taa);
+    if let Some(dp) = state.deferred.as_mut() {
+        dp.prev_view_proj = Some(projection * view);
+        dp.taa_first_frame &= !taa;
+        dp.frame_index = dp.frame_index.wrapping_add(1);
+    }
+    Ok(())
+}
+
+fn record_chain(state: &WGPUBackendState, view: Mat4, projection: Mat4) -> Result<(), String> {
+    use wgpu::BindingResource::{Sampler, TextureView};
+
+    let dp = state.deferred.as_ref().ok_or("Deferred pipeline not created")?;
+    let stack = &state.post_process;
+    let device = &state.device;
+    let sampler = &state.default_sampler;
+    let view_proj = projection * view;
+    let prev_view_proj = dp.prev_view_proj.unwrap_or(view_proj);
+    let (width, height) = (dp.gbuffer.width as f32, dp.gbuffer.height as f32);
+
+    // Uniforms of every pass, enabled or not; they are a few hundred bytes
+    let write_slot = |slot: u64, bytes: &[u8]| state.queue.write_buffer(&dp.effect_params_buffer, slot * SLOT_SIZE, bytes);
+    let lens = stack.lens;
+    let output_peak = state.display.output_peak(state.output_encoding);
+    let pp_params = |horizontal| PostProcessParams {
+        bloom_threshold: stack.bloom.threshold,
+        bloom_intensity: if stack.is_enabled(PostEffect::Bloom) { stack.bloom.intensity } else { 0.0 },
+        gamma: stack.tone_map.gamma,
+        tone_mapping_mode: stack.tone_map.mode as i32,
+        horizontal,
+        chromatic_aberration: lens.chromatic_aberration,
+        vignette_intensity: lens.vignette_intensity,
+        vignette_radius: lens.vignette_radius,
+        vignette_softness: lens.vignette_softness,
+        grain_intensity: lens.grain_intensity,
+        grain_seed: (dp.frame_index % 1024) as f32,
+        output_peak,
+    };
+    state.queue.write_buffer(&dp.pp_params_buffer, 0, bytemuck::bytes_of(&pp_params(0)));
+    write_slot(SLOT_BLOOM_BLUR_H, bytemuck::bytes_of(&pp_params(1)));
+
+    state.queue.write_buffer(&dp.taa_params_buffer, 0, bytemuck::bytes_of(&TAAParams {
+        prev_view_proj: prev_view_proj.to_cols_array_2d(),
+        feedback: stack.taa.feedback,
+        first_frame: (dp.taa_first_frame || dp.prev_view_proj.is_none()) as i32,
+        screen_width: width,
+        screen_height: height,
+    }));
+
+    let (near_plane, far_plane) = projection_depth_range(&projection);
+    let dof = stack.dof;
+    write_slot(SLOT_DOF_COC, bytemuck::bytes_of(&DOFCoCParams {
+        focus_distance: dof.focus_distance,
+        focus_range: dof.focus_range,
+        near_plane,
+        far_plane,
+    }));
+    for (slot, horizontal) in [(SLOT_DOF_BLUR_H, 1), (SLOT_DOF_BLUR_V, 0)] {
+        write_slot(slot, bytemuck::bytes_of(&DOFBlurParams {
+            horizontal,
+            bokeh_radius: dof.bokeh_radius,
+            _pad1: 0.0,
+            _pad2: 0.0,
+        }));
+    }
+
+    let blur = stack.motion_blur;
+    write_slot(SLOT_VELOCITY, bytemuck::bytes_of(&VelocityParams {
+        inv_view_proj: view_proj.inverse().to_cols_array_2d(),
+        prev_view_proj: prev_view_proj.to_cols_array_2d(),
+        max_velocity: blur.max_velocity,
+        _pad1: 0.0,
+        _pad2: 0.0,
+        _pad3: 0.0,
+    }));
+    write_slot(SLOT_MOTION_BLUR, bytemuck::bytes_of(&MotionBlurParams {
+        samples: blur.samples as i32,
+        intensity: blur.intensity,
+        _pad1: 0.0,
+        _pad2: 0.0,
+    }));
+
+    let grading = stack.color_grading;
+    let lut = &state.color_lut;
+    write_slot(SLOT_COLOR_GRADING, bytemuck::bytes_of(&ColorGradingParams {
+        brightness: grading.brightness,
+        contrast: grading.contrast,
+        saturation: grading.saturation,
+        lut_strength: grading.lut_strength,
+        lut_domain_min: lut.domain_min,
+        lut_size: lut.size as f32,
+        lut_domain_max: lut.domain_max,
+        output_max: output_peak.powf(1.0 / stack.tone_map.gamma),
+    }));
+
+    // Start on the ping-pong target that makes the last write land in pp_target_b
+    let hdr: Vec<PostEffect> = stack.hdr_chain().collect();
+    let display: Vec<PostEffect> = stack.display_chain().collect();
+    let ping_pong_passes = 1 + display.len() + hdr.iter().filter(|e| matches!(e, PostEffect::Dof | PostEffect::MotionBlur)).count();
+    let mut to_b = ping_pong_passes % 2 == 1;
+    let mut next_target = || {
+        let target = if to_b { &dp.pp_target_b } else { &dp.pp_target_a };
+        to_b = !to_b;
+        target
+    };
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("PostProcess Encoder"),
+    });
+    let mut input = &dp.lighting_target.color_view;
+
+    for effect in hdr {
+        match effect {
+            PostEffect::Taa => {
+                let taa_bg = bind_group(device, "TAA BG", &dp.taa_bgl, vec![
+                    dp.taa_params_buffer.as_entire_binding(),
+                    TextureView(input),
+                    TextureView(&dp.taa_targets.history_view),
+                    TextureView(&dp.gbuffer.depth_view),
+                    Sampler(sampler),
+                    Sampler(&dp.depth_sampler),
+                ]);
+                let current = &dp.taa_targets.current;
+                passes::taa::render_taa_pass(&mut encoder, current, &dp.taa_pipeline, &taa_bg);
+                passes::taa::copy_taa_to_history(
+                    &mut encoder,
+                    &current.color_texture,
+                    &dp.taa_targets.history_texture,
+                    current.width,
+                    current.height,
+                );
+                input = &current.color_view;
+            }
+            PostEffect::Bloom => {
+                // Extract and blur here; the blurred bloom is added during tone mapping
+                let targets = &dp.bloom_targets;
+                let extract_bg = bind_group(device, "Bloom Extract BG", &dp.bloom_extract_bgl, vec![
+                    dp.pp_params_buffer.as_entire_binding(),
+                    TextureView(input),
+                    Sampler(sampler),
+                ]);
+                passes::postprocess::render_bloom_extract(&mut encoder, &targets.extract, &dp.bloom_extract_pipeline, &extract_bg);
+
+                let blur_h_bg = bind_group(device, "Bloom Blur H BG", &dp.bloom_blur_bgl, vec![
+                    slot(&dp.effect_params_buffer, SLOT_BLOOM_BLUR_H),
+                    TextureView(&targets.extract.color_view),
+                    Sampler(sampler),
+                ]);
+                passes::postprocess::render_bloom_blur(&mut encoder, &targets.blur_h, &dp.bloom_blur_pipeline, &blur_h_bg, "Bloom Blur H");
+
+                let blur_v_bg = bind_group(device, "Bloom Blur V BG", &dp.bloom_blur_bgl, vec![
+                    dp.pp_params_buffer.as_entire_binding(),
+                    TextureView(&targets.blur_h.color_view),
+                    Sampler(sampler),
+                ]);
+                passes::postprocess::render_bloom_blur(&mut encoder, &targets.blur_v, &dp.bloom_blur_pipeline, &blur_v_bg, "Bloom Blur V");
+            }
+            PostEffect::Dof => {
+                let targets = &dp.dof_targets;
+                let coc_bg = bind_group(device, "DOF CoC BG", &dp.depth_effect_bgl, vec![
+                    slot(&dp.effect_params_buffer, SLOT_DOF_COC),
+                    TextureView(&dp.gbuffer.depth_view),
+                    Sampler(&dp.depth_sampler),
+                ]);
+                render_fullscreen_effect(&mut encoder, &targets.coc, &dp.dof_coc_pipeline, &coc_bg, "DOF CoC");
+
+                for (slot_index, source, target, label) in [
+                    (SLOT_DOF_BLUR_H, input, &targets.blur_h, "DOF Blur H"),
+                    (SLOT_DOF_BLUR_V, &targets.blur_h.color_view, &targets.blur_v, "DOF Blur V"),
+                ] {
+                    let blur_bg = bind_group(device, label, &dp.dof_blur_bgl, vec![
+                        slot(&dp.effect_params_buffer, slot_index),
+                        TextureView(source),
+                        TextureView(&targets.coc.color_view),
+                        Sampler(sampler),
+                    ]);
+                    render_fullscreen_effect(&mut encoder, target, &dp.dof_blur_pipeline, &blur_bg, label);
+                }
+
+                let composite_bg = bind_group(device, "DOF Composite BG", &dp.dof_composite_bgl, vec![
+                    TextureView(input),
+                    TextureView(&targets.blur_v.color_view),
+                    TextureView(&targets.coc.color_view),
+                    Sampler(sampler),
+                ]);
+                let target = next_target();
+                render_fullscreen_effect(&mut encoder, target, &dp.dof_composite_pipeline, &composite_bg, "DOF Composite");
+                input = &target.color_view;
+            }
+            PostEffect::MotionBlur => {
+                let velocity = &dp.mblur_targets.velocity;
+                let velocity_bg = bind_group(device, "Velocity BG", &dp.depth_effect_bgl, vec![
+                    slot(&dp.effect_params_buffer, SLOT_VELOCITY),
+                    TextureView(&dp.gbuffer.depth_view),
+                    Sampler(&dp.depth_sampler),
+                ]);
+                render_fullscreen_effect(&mut encoder, velocity, &dp.velocity_pipeline, &velocity_bg, "Motion Blur Velocity");
+
+                let blur_bg = bind_group(device, "Motion Blur BG", &dp.motion_blur_bgl, vec![
+                    slot(&dp.effect_params_buffer, SLOT_MOTION_BLUR),
+                    TextureView(input),
+                    TextureView(&velocity.color_view),
+                    Sampler(sampler),
+                ]);
+                let target = next_target();
+                render_fullscreen_effect(&mut encoder, target, &dp.motion_blur_pipeline, &blur_bg, "Motion Blur");
+                input = &target.color_view;
+            }
+            // The HDR chain holds no lighting inputs or display-space effects
+            PostEffect::Ssao | PostEffect::Ssr | PostEffect::ColorGrading | PostEffect::Fxaa => {}
+        }
+    }
+
+    // Exposure, tone mapping, lens effects and gamma, adding bloom (zero
+    // intensity when bloom is off)
+    let composite_bg = bind_group(device, "Bloom Composite BG", &dp.bloom_composite_bgl, vec![
+        dp.pp_params_buffer.as_entire_binding(),
+        TextureView(input),
+        TextureView(&dp.bloom_targets.blur_v.color_view),
+        Sampler(sampler),
+        dp.exposure.state_buffer.as_entire_binding(),
+    ]);
+    let target = next_target();
+    passes::postprocess::render_bloom_composite(&mut encoder, target, &dp.bloom_composite_pipeline, &composite_bg);
+    input = &target.color_view;
+
+    for effect in display {
+        let target = next_target();
+        if effect == PostEffect::ColorGrading {
+            let grading_bg = bind_group(device, "Color Grading BG", &dp.color_grading_bgl, vec![
+                slot(&dp.effect_params_buffer, SLOT_COLOR_GRADING),
+                TextureView(input),
+                Sampler(sampler),
+                TextureView(&lut.view),
+            ]);
+            render_fullscreen_effect(&mut encoder, target, &dp.color_grading_pipeline, &grading_bg, "Color Grading");
+        } else {
+            let fxaa_bg = bind_group(device, "FXAA BG", &dp.fxaa_bgl, vec![TextureView(input), Sampler(sampler)]);
+            passes::postprocess::render_fxaa(&mut encoder, target, &dp.fxaa_pipeline, &fxaa_bg);
+        }
+        input = &target.color_view;
+    }
+
+    state.queue.submit(std::iter::once(encoder.finish()));
+    Ok(())
+}
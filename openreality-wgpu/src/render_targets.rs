@@ -7,6 +7,10 @@ use crate::backend::{GBuffer, RenderTarget};
 pub const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
 /// Depth format.
 pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+/// Sample type of depth bindings read without a comparison. naga's GLSL
+/// output only has shadow samplers for depth textures, so on GL the depth
+/// values must be bound as unfilterable floats (`texture_2d<f32>` in WGSL).
+pub const DEPTH_VALUES: wgpu::TextureSampleType = wgpu::TextureSampleType::Float { filterable: false };
 /// Single-channel float format (CoC, SSAO).
 pub const R16_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R16Float;
 /// Two-channel float format (velocity buffer).
@@ -114,6 +118,19 @@ pub fn create_render_target(
     label: &str,
     format: wgpu::TextureFormat,
     with_depth: bool,
+) -> RenderTarget {
+    create_multisampled_target(device, width, height, label, format, with_depth, 1)
+}
+
+/// Create a render target with `sample_count` samples per pixel.
+pub fn create_multisampled_target(
+    device: &wgpu::Device,
+    width: u32,
+    height: u32,
+    label: &str,
+    format: wgpu::TextureFormat,
+    with_depth: bool,
+    sample_count: u32,
 ) -> RenderTarget {
     let size = wgpu::Extent3d {
         width,
@@ -125,7 +142,7 @@ pub fn create_render_target(
         label: Some(label),
         size,
         mip_level_count: 1,
-        sample_count: 1,
+        sample_count,
         dimension: wgpu::TextureDimension::D2,
         format,
         usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
@@ -138,7 +155,7 @@ pub fn create_render_target(
             label: Some(&format!("{label} Depth")),
             size,
             mip_level_count: 1,
-            sample_count: 1,
+            sample_count,
             dimension: wgpu::TextureDimension::D2,
             format: DEPTH_FORMAT,
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
@@ -274,10 +291,35 @@ pub fn create_dof_targets(device: &wgpu::Device, width: u32, height: u32) -> DOF
     }
 }
 
-/// Motion blur targets: velocity buffer (RG16F), blur output.
+/// Motion blur targets: velocity buffer (RG16F). The blurred image goes to
+/// the post-process ping-pong targets.
 pub struct MotionBlurTargets {
     pub velocity: RenderTarget,
-    pub blur: RenderTarget,
+}
+
+/// Multisampled targets of the forward pass and of the present + UI pass.
+/// The forward pass is primed with the lit image and G-Buffer depth, then
+/// resolves into the lighting target; present resolves into the frame.
+pub struct MsaaTargets {
+    /// HDR color + depth at the internal render size.
+    pub scene: RenderTarget,
+    /// Output format at the output size.
+    pub output: RenderTarget,
+}
+
+pub fn create_msaa_targets(
+    device: &wgpu::Device,
+    render_size: (u32, u32),
+    output_size: (u32, u32),
+    output_format: wgpu::TextureFormat,
+    samples: u32,
+) -> MsaaTargets {
+    let (w, h) = render_size;
+    let (out_w, out_h) = output_size;
+    MsaaTargets {
+        scene: create_multisampled_target(device, w, h, "MSAA Scene", HDR_FORMAT, true, samples),
+        output: create_multisampled_target(device, out_w, out_h, "MSAA Output", output_format, false, samples),
+    }
 }
 
 pub fn create_motion_blur_targets(
@@ -294,10 +336,10 @@ pub fn create_motion_blur_targets(
             RG16_FORMAT,
             false,
         ),
-        blur: create_hdr_target(device, width, height, "Motion Blur Output", false),
     }
 }
 
+
 /// Create a 1x1 white default texture for fallback.
 pub fn create_default_texture(device: &wgpu::Device, queue: &wgpu::Queue) -> (wgpu::Texture, wgpu::TextureView) {
     let texture = device.create_texture(&wgpu::TextureDescriptor {
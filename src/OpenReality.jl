@@ -40,6 +40,7 @@ include("math/transforms.jl")
 
 # Components
 include("components/transform.jl")
+include("components/name.jl")
 include("components/mesh.jl")
 include("components/material.jl")
 include("components/camera.jl")
@@ -59,6 +60,7 @@ include("physics/shapes.jl")
 include("components/rigidbody.jl")
 include("components/animation.jl")
 include("components/animation_blend_tree.jl")
+include("components/animation_state_machine.jl")
 include("components/audio.jl")
 include("components/skeleton.jl")
 include("components/particle_system.jl")
@@ -227,7 +229,9 @@ if any(isfile, _WEBGPU_LIB_CANDIDATES)
     include("backend/webgpu/webgpu_ffi.jl")
     include("backend/webgpu/webgpu_backend.jl")
     export WebGPUBackend, WebGPUGPUMesh, WebGPUGPUTexture, WebGPUFramebuffer,
-           WebGPUGBuffer, WebGPUGPUResourceCache, WebGPUTextureCache
+           WebGPUGBuffer, WebGPUGPUResourceCache, WebGPUTextureCache, set_shadow_config!,
+           set_post_process_config!, set_post_effect_enabled!, set_post_effect_order!,
+           set_display_output!, read_output, set_resolution_config!, render_scale
 end
 
 # Rendering pipeline (after backend — uses backend types)
@@ -253,6 +257,8 @@ const COMPONENT_TYPES = DataType[
     AnimationComponent,
     # Animation Blend Tree
     AnimationBlendTreeComponent,
+    # Animation State Machine
+    AnimationStateMachineComponent,
     # Audio
     AudioListenerComponent,
     AudioSourceComponent,
@@ -267,11 +273,16 @@ const COMPONENT_TYPES = DataType[
     # Lights
     PointLightComponent,
     DirectionalLightComponent,
+    SpotLightComponent,
+    AreaLightComponent,
     IBLComponent,
+    AtmosphereComponent,
+    VolumetricFogComponent,
     # Lod
     LODComponent,
     # Material
     MaterialComponent,
+    DecalComponent,
     # Mesh
     MeshComponent,
     # Particle System
@@ -325,11 +336,13 @@ export get_all_descendants, get_ancestors, entity_count
 
 # Export Components
 export TransformComponent, transform, with_parent
+export NameComponent
 export Vec3d, Quaterniond
-export MeshComponent
-export MaterialComponent, TextureRef
+export MeshComponent, MorphTarget
+export MaterialComponent, TextureRef, DecalComponent
 export CameraComponent
-export PointLightComponent, DirectionalLightComponent, IBLComponent
+export PointLightComponent, DirectionalLightComponent, SpotLightComponent, AreaLightComponent, IBLComponent
+export AtmosphereComponent, VolumetricFogComponent
 export cube_mesh, sphere_mesh, plane_mesh
 export PlayerComponent, create_player
 export LODComponent, LODLevel, LODTransitionMode, LOD_TRANSITION_INSTANT, LOD_TRANSITION_DITHER
@@ -367,7 +380,7 @@ export JointConstraint, JointComponent
 export BallSocketJoint, DistanceJoint, HingeJoint, FixedJoint, SliderJoint
 
 # Export Scripts
-export ScriptComponent, update_scripts!, SCRIPT_ERROR_BUDGET
+export ScriptComponent, WebScriptComponent, update_scripts!, SCRIPT_ERROR_BUDGET
 
 # Export Triggers
 export TriggerComponent
@@ -383,6 +396,12 @@ export update_animations!
 # Export Animation Blend Trees
 export BlendNode, ClipNode, Blend1DNode, Blend2DNode
 export AnimationBlendTreeComponent
+
+# Export Animation State Machines
+export AnimationStateMachineComponent, AnimParameter, AnimCondition, AnimState, AnimTransition, AnimLayer
+export AnimParamKind, ANIM_PARAM_FLOAT, ANIM_PARAM_BOOL, ANIM_PARAM_TRIGGER
+export LayerBlendMode, LAYER_OVERRIDE, LAYER_ADDITIVE
+export ConditionOp, COND_GREATER, COND_LESS, COND_EQUALS, COND_NOT_EQUALS, COND_TRUE, COND_FALSE, COND_TRIGGER
 export update_blend_tree!, transition_to_tree!
 export set_parameter!, set_bool_parameter!, fire_trigger!
 
@@ -408,6 +427,9 @@ export get_or_create_font_atlas!, reset_font_cache!
 
 # Export Particles
 export ParticleSystemComponent
+export ParticleBlendMode, PARTICLE_BLEND_ALPHA, PARTICLE_BLEND_ADDITIVE, PARTICLE_BLEND_PREMULTIPLIED
+export EmitterShape, EMIT_POINT, EMIT_SPHERE, EMIT_CONE, EMIT_BOX, EMIT_MESH
+export ParticleCollisionMode, PARTICLE_COLLIDE_NONE, PARTICLE_COLLIDE_GROUND, PARTICLE_COLLIDE_DEPTH
 export Particle, ParticlePool, PARTICLE_POOLS
 export update_particles!, reset_particle_pools!
 export init_particle_renderer!, shutdown_particle_renderer!, render_particles!, reset_particle_renderer!
@@ -418,6 +440,7 @@ export ShadowMap, create_shadow_map!, destroy_shadow_map!, compute_light_space_m
 
 # Export Cascaded Shadow Mapping
 export CascadedShadowMap, create_csm!, destroy_csm!, compute_cascade_splits
+export ShadowConfig, ShadowFilterMode, SHADOW_FILTER_HARD, SHADOW_FILTER_PCF, SHADOW_FILTER_PCSS, SHADOW_FILTER_EVSM
 export compute_cascade_light_matrix, render_csm_cascade!
 
 # Export Frustum Culling
@@ -426,7 +449,11 @@ export extract_frustum, bounding_sphere_from_mesh, is_sphere_in_frustum
 
 # Export Post-Processing
 export Framebuffer, PostProcessConfig, PostProcessPipeline
-export ToneMappingMode, TONEMAP_REINHARD, TONEMAP_ACES, TONEMAP_UNCHARTED2
+export ToneMappingMode, TONEMAP_REINHARD, TONEMAP_ACES, TONEMAP_UNCHARTED2, TONEMAP_AGX, TONEMAP_PBR_NEUTRAL
+export ExposureMode, EXPOSURE_FIXED, EXPOSURE_MANUAL, EXPOSURE_AUTO
+export DisplayMode, DISPLAY_SDR, DISPLAY_HDR_SCRGB, DISPLAY_HDR10, DisplayConfig, ResolutionConfig
+export PostEffect, POST_SSAO, POST_SSR, POST_TAA, POST_BLOOM, POST_DOF, POST_MOTION_BLUR
+export POST_COLOR_GRADING, POST_FXAA, post_effect_enabled
 export DOFPass, create_dof_pass!, destroy_dof_pass!, resize_dof_pass!, render_dof!
 export MotionBlurPass, create_motion_blur_pass!, destroy_motion_blur_pass!, resize_motion_blur_pass!, render_motion_blur!
 
@@ -177,7 +177,7 @@ function pack_lights(light_data::FrameLightData)
     MetalLightUniforms(
         point_lights,
         dir_lights,
-        Int32(length(light_data.point_positions)),
+        Int32(min(length(light_data.point_positions), METAL_MAX_POINT_LIGHTS)),
         Int32(length(light_data.dir_directions)),
         light_data.has_ibl ? Int32(1) : Int32(0),
         light_data.ibl_intensity
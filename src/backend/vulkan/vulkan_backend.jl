@@ -254,8 +254,11 @@ function initialize!(backend::VulkanBackend; width::Int=1280, height::Int=720, t
     # Create lighting UBOs and descriptor sets
     for _ in 1:VK_MAX_FRAMES_IN_FLIGHT
         # Light UBO
-        light_data = FrameLightData(Vec3f[], RGB{Float32}[], Float32[], Float32[],
-                                     Vec3f[], RGB{Float32}[], Float32[], false, "", 1.0f0)
+        light_data = FrameLightData(Vec3f[], RGB{Float32}[], Float32[], Float32[], Bool[],
+                                     Vec3f[], RGB{Float32}[], Float32[],
+                                     Vec3f[], Vec3f[], RGB{Float32}[], Float32[], Float32[], Float32[], Float32[], Bool[],
+                                     Symbol[], Vec3f[], Vec3f[], Vec3f[], RGB{Float32}[], Float32[], Float32[], Float32[],
+                                     false, "", 1.0f0)
         light_uniforms = vk_pack_lights(light_data)
         light_ubo, light_mem = vk_create_uniform_buffer(backend.device, backend.physical_device, light_uniforms)
         push!(backend.light_ubos, light_ubo)
@@ -23,13 +23,15 @@ mutable struct WebGPUBackend <: AbstractBackend
 
     # Configuration
     post_process_config::Union{PostProcessConfig, Nothing}
+    post_process_synced::Union{PostProcessConfig, Nothing}  # last config pushed to the Rust stack
+    atmosphere_synced::Union{AtmosphereComponent, Nothing}  # last sky and fog pushed to the backend
+    fog_synced::Union{VolumetricFogComponent, Nothing}
+    shadow_config::ShadowConfig
+    display_config::DisplayConfig
+    resolution_config::ResolutionConfig
     use_deferred::Bool
     width::Int
     height::Int
-
-    # TAA state tracking
-    prev_view_proj::Mat4f
-    taa_frame_index::Int
 end
 
 function WebGPUBackend()
@@ -45,54 +47,89 @@ function WebGPUBackend()
         UInt64(0),                      # csm_handle
         UInt64(0),                      # post_process_handle
         nothing,                        # post_process_config
+        nothing,                        # post_process_synced
+        nothing,                        # atmosphere_synced
+        nothing,                        # fog_synced
+        ShadowConfig(),                 # shadow_config
+        DisplayConfig(),                # display_config
+        ResolutionConfig(),             # resolution_config
         true,                           # use_deferred
         1280,                           # width
         720,                            # height
-        Mat4f(I),                       # prev_view_proj
-        0,                              # taa_frame_index
     )
 end
 
 # ---- Core lifecycle ----
 
-function initialize!(backend::WebGPUBackend; width::Int=1280, height::Int=720, title::String="OpenReality")
+"""
+    initialize!(backend::WebGPUBackend; width=1280, height=720, title="OpenReality", headless=false)
+
+Create the window and the WebGPU device. With `headless = true` no window is
+created and frames are presented to an offscreen float target, which
+`read_output` returns; this runs wherever an adapter exists, display or not.
+"""
+function initialize!(backend::WebGPUBackend; width::Int=1280, height::Int=720, title::String="OpenReality",
+                     headless::Bool=false)
     backend.width = width
     backend.height = height
 
-    # Create GLFW window with NO_API (WebGPU creates its own surface)
-    ensure_glfw_init!()
-    GLFW.WindowHint(GLFW.CLIENT_API, GLFW.NO_API)
-    backend.window = Window()
-    backend.window.handle = GLFW.CreateWindow(width, height, title)
-    if backend.window.handle == C_NULL
-        error("Failed to create GLFW window for WebGPU backend")
-    end
-
-    # Get raw window/display handles for the Rust FFI via GLFW native access
-    if Sys.islinux()
-        x11_window = ccall((:glfwGetX11Window, GLFW.libglfw), UInt64, (GLFW.Window,), backend.window.handle)
-        x11_display = ccall((:glfwGetX11Display, GLFW.libglfw), Ptr{Nothing}, ())
-        backend.backend_handle = wgpu_initialize(x11_window, x11_display, width, height)
-    elseif Sys.iswindows()
-        hwnd = ccall((:glfwGetWin32Window, GLFW.libglfw), Ptr{Nothing}, (GLFW.Window,), backend.window.handle)
-        backend.backend_handle = wgpu_initialize(UInt64(hwnd), Ptr{Nothing}(C_NULL), width, height)
+    if headless
+        backend.backend_handle = wgpu_initialize_headless(width, height)
     else
-        error("WebGPU backend not supported on this platform (use Metal on macOS)")
+        # Create GLFW window with NO_API (WebGPU creates its own surface)
+        ensure_glfw_init!()
+        GLFW.WindowHint(GLFW.CLIENT_API, GLFW.NO_API)
+        backend.window = Window()
+        backend.window.handle = GLFW.CreateWindow(width, height, title)
+        if backend.window.handle == C_NULL
+            error("Failed to create GLFW window for WebGPU backend")
+        end
+
+        # Get raw window/display handles for the Rust FFI via GLFW native access
+        if Sys.islinux()
+            x11_window = ccall((:glfwGetX11Window, GLFW.libglfw), UInt64, (GLFW.Window,), backend.window.handle)
+            x11_display = ccall((:glfwGetX11Display, GLFW.libglfw), Ptr{Nothing}, ())
+            backend.backend_handle = wgpu_initialize(x11_window, x11_display, width, height)
+        elseif Sys.iswindows()
+            hwnd = ccall((:glfwGetWin32Window, GLFW.libglfw), Ptr{Nothing}, (GLFW.Window,), backend.window.handle)
+            backend.backend_handle = wgpu_initialize(UInt64(hwnd), Ptr{Nothing}(C_NULL), width, height)
+        else
+            error("WebGPU backend not supported on this platform (use Metal on macOS)")
+        end
     end
 
     if backend.backend_handle == UInt64(0)
         error("Failed to initialize WebGPU backend: $(wgpu_last_error(UInt64(0)))")
     end
 
+    if backend.display_config.mode != DISPLAY_SDR
+        _apply_display_config!(backend, backend.display_config)
+    end
+    if backend.resolution_config != ResolutionConfig()
+        _apply_resolution_config!(backend, backend.resolution_config)
+    end
+
     # Setup input callbacks
-    setup_input_callbacks!(backend.window, backend.input)
+    backend.window !== nothing && setup_input_callbacks!(backend.window, backend.input)
 
     # Create cascaded shadow maps (4 cascades, 1024x1024)
     backend.csm_handle = wgpu_create_csm(backend.backend_handle, 4, 1024, Float32(0.1), Float32(500.0))
+    if wgpu_set_csm_settings(backend.backend_handle, backend.shadow_config) != 0
+        @warn "Failed to set CSM settings" error=wgpu_last_error(backend.backend_handle)
+    end
+
+    # Shadow atlas for point and spot lights (4096x4096, tiles up to 1024)
+    if wgpu_create_shadow_atlas(backend.backend_handle, 4096, 1024) != 0
+        @warn "Failed to create shadow atlas" error=wgpu_last_error(backend.backend_handle)
+    end
 
     # Deferred pipeline is created lazily on the first frame (window must be visible first)
     backend.deferred_initialized = false
 
+    # Particles are simulated by compute shaders on the Rust side
+    BACKEND_SIMULATES_PARTICLES[] = true
+    BACKEND_PARTICLE_DT[] = 0.0f0
+
     backend.initialized = true
     return nothing
 end
@@ -110,6 +147,8 @@ function shutdown!(backend::WebGPUBackend)
             wgpu_destroy_mesh(backend.backend_handle, mesh.handle)
         end
         empty!(backend.gpu_cache.meshes)
+        empty!(backend.gpu_cache.particle_emitters)
+        empty!(backend.gpu_cache.decals)
 
         # Shutdown the Rust backend (destroys deferred pipeline, CSM, etc.)
         wgpu_shutdown(backend.backend_handle)
@@ -121,6 +160,8 @@ function shutdown!(backend::WebGPUBackend)
         backend.window = nothing
     end
 
+    BACKEND_SIMULATES_PARTICLES[] = false
+
     backend.deferred_initialized = false
     backend.initialized = false
     return nothing
@@ -184,14 +225,22 @@ function render_frame!(backend::WebGPUBackend, scene)
     # 2. Upload lights
     light_data = _pack_lights(frame_data.lights)
     wgpu_upload_lights(backend.backend_handle, light_data)
-
-    # 3. Shadow pass (if directional light exists)
-    if frame_data.primary_light_dir !== nothing
-        mesh_handles = UInt64[]
-        model_floats = Float32[]
-        cascade_matrices = Float32[]
-
-        # Collect all opaque entities for shadow depth
+    lights = frame_data.lights
+    point_casters = UInt32[i - 1 for i in eachindex(lights.point_casts_shadow) if lights.point_casts_shadow[i]]
+    spot_casters = UInt32[i - 1 for i in eachindex(lights.spot_casts_shadow) if lights.spot_casts_shadow[i]]
+    wgpu_set_shadow_casters(backend.backend_handle, WGPU_LIGHT_KIND_POINT, point_casters)
+    wgpu_set_shadow_casters(backend.backend_handle, WGPU_LIGHT_KIND_SPOT, spot_casters)
+    _sync_environment!(backend)
+    _sync_decals!(backend)
+
+    # Skinning and morph targets are evaluated on the Rust side; push inputs once
+    _sync_deformations!(backend, frame_data.opaque_entities)
+    _sync_deformations!(backend, frame_data.transparent_entities)
+
+    # Collect all opaque entities for shadow depth
+    mesh_handles = UInt64[]
+    model_floats = Float32[]
+    if frame_data.primary_light_dir !== nothing || !isempty(point_casters) || !isempty(spot_casters)
         for erd in frame_data.opaque_entities
             gpu_mesh = _ensure_mesh_uploaded(backend, erd.entity_id, erd.mesh)
             gpu_mesh === nothing && continue
@@ -202,26 +251,18 @@ function render_frame!(backend::WebGPUBackend, scene)
                 push!(model_floats, Float32(m[row, col]))
             end
         end
+    end
 
-        # Compute 4 cascade matrices
-        num_cascades = 4
-        split_distances = compute_cascade_splits(0.1f0, 500.0f0, num_cascades)
-        for c in 1:num_cascades
-            light_matrix = compute_cascade_light_matrix(view, proj,
-                split_distances[c], split_distances[c + 1], frame_data.primary_light_dir)
-            lm = light_matrix
-            for col in 1:4, row in 1:4
-                push!(cascade_matrices, Float32(lm[row, col]))
-            end
-        end
-
-        if !isempty(mesh_handles)
-            wgpu_shadow_pass(backend.backend_handle, mesh_handles, model_floats,
-                UInt32(length(mesh_handles)), cascade_matrices, Int32(num_cascades))
-        end
+    # 3. Shadow pass: cascades are fitted on the Rust side; no directional light turns them off
+    light_dir = something(frame_data.primary_light_dir, Vec3f(0, -1, 0))
+    num_cascades = frame_data.primary_light_dir === nothing ? 0 : 4
+    wgpu_shadow_pass(backend.backend_handle, mesh_handles, model_floats,
+        UInt32(length(mesh_handles)), light_dir, Int32(num_cascades))
 
-        # Re-upload camera per-frame data (shadow pass overwrites the buffer with cascade VPs)
-        wgpu_begin_frame(backend.backend_handle, per_frame_data)
+    # Point and spot light shadows (atlas tiles; also sets the lights' shadow_index)
+    if !isempty(point_casters) || !isempty(spot_casters)
+        wgpu_local_shadow_pass(backend.backend_handle, mesh_handles, model_floats,
+            UInt32(length(mesh_handles)))
     end
 
     # 4. G-Buffer pass: pack opaque entities
@@ -242,28 +283,15 @@ function render_frame!(backend::WebGPUBackend, scene)
         end
     end
 
-    # 5. Lighting pass
-    wgpu_lighting_pass(backend.backend_handle)
-
-    # 6. SSAO pass
-    ssao_params = _pack_ssao_params(proj, backend.width, backend.height)
-    wgpu_ssao_pass(backend.backend_handle, ssao_params)
-
-    # 7. SSR pass
-    ssr_params = _pack_ssr_params(proj, view, cam_pos, backend.width, backend.height)
-    wgpu_ssr_pass(backend.backend_handle, ssr_params)
+    # 5. Lighting inputs: SSAO from this frame's G-Buffer, SSR from the last lit image
+    _sync_post_process!(backend)
+    wgpu_ssao_pass(backend.backend_handle)
+    wgpu_ssr_pass(backend.backend_handle)
 
-    # 8. TAA pass
-    taa_params = _pack_taa_params(backend, vp)
-    wgpu_taa_pass(backend.backend_handle, taa_params)
-    backend.prev_view_proj = Mat4f(vp)
-    backend.taa_frame_index += 1
-
-    # 9. Post-process pass
-    pp_params = _pack_postprocess_params(backend)
-    wgpu_postprocess_pass(backend.backend_handle, pp_params)
+    # 6. Lighting pass
+    wgpu_lighting_pass(backend.backend_handle)
 
-    # 10. Forward pass for transparent entities
+    # 7. Forward pass for transparent entities, into the lit image
     if !isempty(frame_data.transparent_entities)
         sorted_trans = sort(frame_data.transparent_entities, by=x -> -x.dist_sq)
         entity_stride = UInt32(264)
@@ -282,13 +310,19 @@ function render_frame!(backend::WebGPUBackend, scene)
         end
     end
 
-    # 11. Particle pass
+    # 8. Post-process stack (TAA, bloom, DOF, motion blur, tone mapping, color grading, FXAA)
+    if wgpu_postprocess_pass(backend.backend_handle) != 0
+        @warn "Post-process pass failed" error=wgpu_last_error(backend.backend_handle) maxlog=1
+    end
+
+    # 9. Particle passes (CPU billboards, then GPU-simulated emitters)
     _render_wgpu_particles(backend, view, proj)
+    _render_wgpu_gpu_particles(backend, view, proj)
 
-    # 12. UI pass
+    # 10. UI pass
     _render_wgpu_ui(backend)
 
-    # 13. Present
+    # 11. Present (upscaled to the window size, with the UI on top)
     wgpu_present(backend.backend_handle)
 
     return nothing
@@ -312,6 +346,119 @@ function _ensure_mesh_uploaded(backend::WebGPUBackend, entity_id::EntityID, mesh
     return backend_upload_mesh!(backend, entity_id, mesh)
 end
 
+# ---- Helper: Per-frame deformation data ----
+
+"""
+    _sync_environment!(backend)
+
+Push the scene's first `AtmosphereComponent` and `VolumetricFogComponent`
+when they changed since the last frame; a missing component disables its
+effect.
+"""
+function _sync_environment!(backend::WebGPUBackend)
+    eid = first_entity_with_component(AtmosphereComponent)
+    atmosphere = eid === nothing ? AtmosphereComponent(enabled=false) : get_component(eid, AtmosphereComponent)
+    if atmosphere != backend.atmosphere_synced
+        if wgpu_set_atmosphere(backend.backend_handle, atmosphere) != 0
+            @warn "Failed to set atmosphere" error=wgpu_last_error(backend.backend_handle)
+        end
+        backend.atmosphere_synced = atmosphere
+    end
+
+    eid = first_entity_with_component(VolumetricFogComponent)
+    fog = eid === nothing ? VolumetricFogComponent(enabled=false) : get_component(eid, VolumetricFogComponent)
+    if fog != backend.fog_synced
+        if wgpu_set_fog(backend.backend_handle, fog) != 0
+            @warn "Failed to set volumetric fog" error=wgpu_last_error(backend.backend_handle)
+        end
+        backend.fog_synced = fog
+    end
+    return nothing
+end
+
+"""
+    _sync_decals!(backend)
+
+Add, update and remove backend decals to match the `DecalComponent`s. The
+G-Buffer pass draws them in the order they were added.
+"""
+function _sync_decals!(backend::WebGPUBackend)
+    h = backend.backend_handle
+    decals = backend.gpu_cache.decals
+    seen = Set{EntityID}()
+
+    iterate_components(DecalComponent) do eid, comp
+        push!(seen, eid)
+        world = get_world_transform(eid)
+        world_mat = Float32[Float32(world[i]) for i in 1:16]
+        albedo = comp.albedo_map === nothing ? UInt64(0) : _load_and_upload_texture(backend, comp.albedo_map.path)
+        normal = comp.normal_map === nothing ? UInt64(0) : _load_and_upload_texture(backend, comp.normal_map.path)
+
+        synced = get(decals, eid, nothing)
+        if synced === nothing
+            handle = wgpu_add_decal(h, world_mat, comp, albedo, normal)
+            if handle == UInt64(0)
+                @warn "Failed to add decal" entity=eid error=wgpu_last_error(h) maxlog=1
+                return
+            end
+            decals[eid] = WebGPUDecal(handle, comp, world_mat, albedo, normal)
+        elseif (synced.decal, synced.world_mat, synced.albedo, synced.normal) != (comp, world_mat, albedo, normal)
+            if wgpu_update_decal(h, synced.handle, world_mat, comp, albedo, normal) != 0
+                @warn "Failed to update decal" entity=eid error=wgpu_last_error(h) maxlog=1
+            end
+            synced.decal, synced.world_mat, synced.albedo, synced.normal = comp, world_mat, albedo, normal
+        end
+    end
+
+    # Remove decals whose entities are gone
+    for eid in collect(keys(decals))
+        if eid ∉ seen
+            wgpu_remove_decal(h, decals[eid].handle)
+            delete!(decals, eid)
+        end
+    end
+    return nothing
+end
+
+"""
+    _pack_bone_palette(bone_matrices) -> Vector{Float32}
+
+Flatten bone matrices into the column-major `mat4x4<f32>` array read by the
+skinned vertex stages.
+"""
+function _pack_bone_palette(bone_matrices)
+    palette = Float32[]
+    for m in bone_matrices, col in 1:4, row in 1:4
+        push!(palette, m[row, col])
+    end
+    return palette
+end
+
+"""
+    _sync_deformations!(backend, entities)
+
+Upload this frame's bone palettes and morph weights for every entity that will
+be drawn. Runs once per frame, before the shadow, G-Buffer and forward passes.
+"""
+function _sync_deformations!(backend::WebGPUBackend, entities)
+    for erd in entities
+        mesh = erd.mesh
+        skin = isempty(mesh.bone_weights) ? nothing : get_component(erd.entity_id, SkinnedMeshComponent)
+        (skin === nothing && isempty(mesh.morph_targets)) && continue
+
+        gpu_mesh = _ensure_mesh_uploaded(backend, erd.entity_id, mesh)
+        gpu_mesh === nothing && continue
+
+        if skin !== nothing && !isempty(skin.bone_matrices)
+            wgpu_update_bone_matrices(backend.backend_handle, gpu_mesh.handle, _pack_bone_palette(skin.bone_matrices))
+        end
+        if !isempty(mesh.morph_targets)
+            wgpu_set_morph_weights(backend.backend_handle, gpu_mesh.handle, mesh.morph_weights)
+        end
+    end
+    return nothing
+end
+
 # ---- Helper: Ensure textures are uploaded ----
 
 """
@@ -497,157 +644,240 @@ function _pack_material_96(material)::Vector{UInt8}
     return buf
 end
 
-# ---- Helper: Pack SSAO params ----
-
-"""
-    _pack_ssao_params(proj, width, height) -> Vector{UInt8}
-
-Pack SSAO parameters matching WGPUSSAOParams layout.
-Generates a hemisphere sample kernel and packs it with the projection matrix.
-"""
-function _pack_ssao_params(proj, width::Int, height::Int)::Vector{UInt8}
-    # Generate 64 hemisphere sample directions
-    samples = Float32[]
-    for i in 1:64
-        # Uniform hemisphere sample
-        xi1 = Float32(rand())
-        xi2 = Float32(rand())
-        x = Float32(cos(2.0f0 * Float32(pi) * xi1) * sqrt(1.0f0 - xi2 * xi2))
-        y = Float32(sin(2.0f0 * Float32(pi) * xi1) * sqrt(1.0f0 - xi2 * xi2))
-        z = Float32(xi2)
-        # Scale by distance distribution (more samples closer to origin)
-        scale_val = Float32(i) / 64.0f0
-        scale_val = 0.1f0 + scale_val * scale_val * 0.9f0
-        push!(samples, x * scale_val, y * scale_val, z * scale_val, 0.0f0)
-    end
-
-    ssao = WGPUSSAOParams(
-        NTuple{256, Float32}(Tuple(samples)),
-        _mat4_to_ntuple(proj),
-        Int32(32),              # kernel_size
-        0.5f0,                  # radius
-        0.025f0,                # bias
-        2.0f0,                  # power
-        Float32(width),         # screen_width
-        Float32(height),        # screen_height
-        0.0f0,                  # _pad1
-        0.0f0,                  # _pad2
-    )
-    return _struct_to_bytes(ssao)
-end
-
-# ---- Helper: Pack SSR params ----
+# ---- Helper: Sync post-process stack ----
 
 """
-    _pack_ssr_params(proj, view, cam_pos, width, height) -> Vector{UInt8}
+    _sync_post_process!(backend)
 
-Pack SSR parameters matching WGPUSSRParams layout.
+Push `backend.post_process_config` to the Rust post-process stack when it is
+a different config from the last one pushed (e.g. after `run_render_loop!`
+assigned it). Without a config the stack keeps its defaults.
 """
-function _pack_ssr_params(proj, view, cam_pos, width::Int, height::Int)::Vector{UInt8}
-    inv_proj = inv(proj)
-    ssr = WGPUSSRParams(
-        _mat4_to_ntuple(proj),
-        _mat4_to_ntuple(view),
-        _mat4_to_ntuple(Mat4f(inv_proj)),
-        _vec3_to_ntuple4(cam_pos, 0.0f0),
-        (Float32(width), Float32(height)),
-        Int32(64),              # max_steps
-        50.0f0,                 # max_distance
-        0.1f0,                  # thickness
-        0.0f0,                  # _pad1
-        0.0f0,                  # _pad2
-        0.0f0,                  # _pad3
-    )
-    return _struct_to_bytes(ssr)
+function _sync_post_process!(backend::WebGPUBackend)
+    config = backend.post_process_config
+    (config === nothing || config === backend.post_process_synced) && return nothing
+    _apply_post_process_config!(backend, config)
+    return nothing
 end
 
-# ---- Helper: Pack TAA params ----
+# Load the .cube LUT at `path` (re-read on every apply, so edits are picked up),
+# or go back to the identity LUT.
+function _apply_color_lut!(backend::WebGPUBackend, path::Union{String, Nothing})
+    path === nothing && return wgpu_clear_color_lut(backend.backend_handle)
+    if !isfile(path)
+        @warn "Color LUT not found" path
+        return Int32(-1)
+    end
+    return wgpu_set_color_lut(backend.backend_handle, read(path, String))
+end
+
+function _apply_post_process_config!(backend::WebGPUBackend, config::PostProcessConfig)
+    h = backend.backend_handle
+    status = Int32[
+        wgpu_set_post_effect_order(h, config.effect_order),
+        wgpu_set_post_bloom(h, config.bloom_threshold, config.bloom_intensity),
+        wgpu_set_post_tone_mapping(h, config.tone_mapping, config.gamma),
+        wgpu_set_post_ssao(h, config.ssao_radius, 0.025f0, 2.0f0, config.ssao_samples),
+        wgpu_set_post_ssr(h, config.ssr_max_steps, config.ssr_max_distance, config.ssr_thickness),
+        wgpu_set_post_taa(h, config.taa_feedback),
+        wgpu_set_post_dof(h, config.dof_focus_distance, config.dof_focus_range, config.dof_bokeh_radius),
+        wgpu_set_post_motion_blur(h, config.motion_blur_intensity, config.motion_blur_samples,
+                                  config.motion_blur_max_velocity),
+        wgpu_set_post_color_grading(h, config.color_grading_brightness, config.color_grading_contrast,
+                                    config.color_grading_saturation, config.color_lut_strength),
+        wgpu_set_post_exposure(h, config.exposure_mode, config.exposure_ev100, config.exposure_compensation),
+        wgpu_set_post_auto_exposure(h, config.auto_exposure_min_ev100, config.auto_exposure_max_ev100,
+                                    config.auto_exposure_speed_up, config.auto_exposure_speed_down),
+        wgpu_set_post_lens(h, config.chromatic_aberration,
+                           config.vignette_enabled ? config.vignette_intensity : 0.0f0,
+                           config.vignette_radius, config.vignette_softness, config.film_grain),
+    ]
+    push!(status, _apply_color_lut!(backend, config.color_lut_path))
+    for effect in instances(PostEffect)
+        push!(status, wgpu_set_post_effect_enabled(h, effect, post_effect_enabled(config, effect)))
+    end
+    if any(!=(0), status)
+        @warn "Failed to apply post-process config" error=wgpu_last_error(h)
+    end
+    backend.post_process_synced = config
+    return nothing
+end
+
+# ---- Helper: Render particles ----
 
 """
-    _pack_taa_params(backend, current_vp) -> Vector{UInt8}
+    _render_wgpu_particles(backend, view, proj)
 
-Pack TAA parameters matching WGPUTAAParams layout.
+Submit each active CPU particle pool to the wgpu_particle_pass FFI call.
+Pools draw one call each so every emitter keeps its own blend mode, soft
+distance and sprite texture.
 """
-function _pack_taa_params(backend::WebGPUBackend, current_vp)::Vector{UInt8}
-    taa = WGPUTAAParams(
-        _mat4_to_ntuple(backend.prev_view_proj),
-        0.9f0,                  # feedback factor
-        Int32(backend.taa_frame_index == 0 ? 1 : 0),  # first_frame
-        Float32(backend.width),
-        Float32(backend.height),
-    )
-    return _struct_to_bytes(taa)
+function _render_wgpu_particles(backend::WebGPUBackend, view::Mat4f, proj::Mat4f)
+    isempty(PARTICLE_POOLS) && return
+
+    # Flatten view and projection matrices to Float32 arrays (column-major)
+    view_mat = Float32[Float32(view[i]) for i in 1:16]
+    proj_mat = Float32[Float32(proj[i]) for i in 1:16]
+
+    for (eid, pool) in PARTICLE_POOLS
+        pool.vertex_count <= 0 && continue
+        # Pool vertex data is interleaved: pos3 + uv2 + color4 = 9 floats per vertex
+        num_floats = pool.vertex_count * 9
+        num_floats > length(pool.vertex_data) && continue
+        comp = get_component(eid, ParticleSystemComponent)
+        comp === nothing && continue
+
+        texture = comp.texture === nothing ? UInt64(0) : _load_and_upload_texture(backend, comp.texture.path)
+        wgpu_particle_pass(backend.backend_handle, pool.vertex_data,
+            UInt32(pool.vertex_count), view_mat, proj_mat;
+            blend_mode = Int(comp.blend_mode), soft_distance = comp.soft_distance, texture = texture)
+    end
 end
 
-# ---- Helper: Pack post-process params ----
+# Bake an over-lifetime curve to 8 evenly spaced samples (the GPU lookup table)
+function _bake_particle_curve(curve, fallback)
+    ntuple(i -> begin
+        v = _sample_particle_curve(curve, Float32(i - 1) / 7.0f0)
+        v === nothing ? fallback : v
+    end, 8)
+end
 
 """
-    _pack_postprocess_params(backend) -> Vector{UInt8}
+    _pack_particle_emitter_params(comp) -> Vector{UInt8}
 
-Pack post-processing parameters matching WGPUPostProcessParams layout.
-Uses the backend's PostProcessConfig if available, otherwise defaults.
+Pack a `ParticleSystemComponent` into `WGPUParticleEmitterParams` bytes.
+Mesh triangle count and texture flag are filled in by the backend.
 """
-function _pack_postprocess_params(backend::WebGPUBackend)::Vector{UInt8}
-    config = backend.post_process_config
+function _pack_particle_emitter_params(comp::ParticleSystemComponent)
+    shape_params = if comp.shape == EMIT_SPHERE
+        (comp.shape_radius, comp.shape_surface_only ? 1.0f0 : 0.0f0, 0.0f0, 0.0f0)
+    elseif comp.shape == EMIT_CONE
+        (comp.shape_angle, comp.shape_radius, 0.0f0, 0.0f0)
+    elseif comp.shape == EMIT_BOX
+        (comp.shape_half_extents[1], comp.shape_half_extents[2], comp.shape_half_extents[3], 0.0f0)
+    else
+        (0.0f0, 0.0f0, 0.0f0, 0.0f0)
+    end
 
-    bloom_threshold = 1.0f0
-    bloom_intensity = 0.3f0
-    gamma = 2.2f0
-    tone_mapping_mode = Int32(0)  # Reinhard
-
-    if config !== nothing
-        bloom_threshold = config.bloom_threshold
-        bloom_intensity = config.bloom_intensity
-        gamma = config.gamma
-        tone_mapping_mode = Int32(config.tone_mapping)
-    end
-
-    pp = WGPUPostProcessParams(
-        bloom_threshold,
-        bloom_intensity,
-        gamma,
-        tone_mapping_mode,
-        Int32(0),               # horizontal (not used for composite)
-        0.0f0,                  # _pad1
-        0.0f0,                  # _pad2
-        0.0f0,                  # _pad3
+    curve_flags = UInt32(0)
+    isempty(comp.color_curve) || (curve_flags |= UInt32(1))
+    isempty(comp.size_curve) || (curve_flags |= UInt32(2))
+    isempty(comp.velocity_curve) || (curve_flags |= UInt32(4))
+    colors = _bake_particle_curve(comp.color_curve, RGBA{Float32}(1, 1, 1, 1))
+    sizes = _bake_particle_curve(comp.size_curve, 1.0f0)
+    velocities = _bake_particle_curve(comp.velocity_curve, Vec3f(0, 0, 0))
+
+    tiles_x = max(comp.sheet_tiles_x, 1)
+    tiles_y = max(comp.sheet_tiles_y, 1)
+    frames = comp.sheet_frame_count > 0 ? comp.sheet_frame_count : tiles_x * tiles_y
+
+    params = WGPUParticleEmitterParams(
+        (comp.velocity_min[1], comp.velocity_min[2], comp.velocity_min[3], 0.0f0),
+        (comp.velocity_max[1], comp.velocity_max[2], comp.velocity_max[3], 0.0f0),
+        (red(comp.start_color), green(comp.start_color), blue(comp.start_color), comp.start_alpha),
+        (red(comp.end_color), green(comp.end_color), blue(comp.end_color), comp.end_alpha),
+        shape_params,
+        UInt32(comp.max_particles),
+        comp.emission_rate,
+        comp.lifetime_min,
+        comp.lifetime_max,
+        comp.gravity_modifier,
+        comp.damping,
+        comp.start_size_min,
+        comp.start_size_max,
+        comp.end_size,
+        UInt32(Int(comp.blend_mode)),
+        UInt32(Int(comp.shape)),
+        UInt32(0),                      # mesh_triangle_count
+        UInt32(Int(comp.collision)),
+        comp.plane_height,
+        comp.bounce,
+        comp.friction,
+        comp.soft_distance,
+        UInt32(tiles_x),
+        UInt32(tiles_y),
+        UInt32(frames),
+        comp.sheet_fps,
+        curve_flags,
+        UInt32(0),                      # has_texture
+        0.0f0,
+        ntuple(i -> (colors[i].r, colors[i].g, colors[i].b, colors[i].alpha), 8),
+        ntuple(i -> (sizes[i], velocities[i][1], velocities[i][2], velocities[i][3]), 8),
     )
-    return _struct_to_bytes(pp)
+    return _struct_to_bytes(params)
 end
 
-# ---- Helper: Render particles ----
-
 """
-    _render_wgpu_particles(backend, view, proj)
+    _render_wgpu_gpu_particles(backend, view, proj)
 
-Collect particle vertex data from all active particle pools and submit to the
-wgpu_particle_pass FFI call.
+Sync GPU emitters with `ParticleSystemComponent`s (create, reconfigure, move,
+attach meshes/textures, burst, destroy), advance them by the accumulated
+particle time step and draw them.
 """
-function _render_wgpu_particles(backend::WebGPUBackend, view::Mat4f, proj::Mat4f)
-    isempty(PARTICLE_POOLS) && return
+function _render_wgpu_gpu_particles(backend::WebGPUBackend, view::Mat4f, proj::Mat4f)
+    h = backend.backend_handle
+    emitters = backend.gpu_cache.particle_emitters
+    seen = Set{EntityID}()
+
+    iterate_components(ParticleSystemComponent) do eid, comp
+        comp.max_particles <= 0 && return
+        push!(seen, eid)
+        params = _pack_particle_emitter_params(comp)
+
+        emitter = get(emitters, eid, nothing)
+        if emitter === nothing
+            handle = wgpu_create_particle_emitter(h, params)
+            if handle == UInt64(0)
+                @warn "Failed to create GPU particle emitter" entity=eid error=wgpu_last_error(h)
+                return
+            end
+            emitter = WebGPUParticleEmitter(handle, params)
+            emitters[eid] = emitter
+        elseif emitter.params != params
+            wgpu_update_particle_emitter(h, emitter.handle, params)
+            emitter.params = params
+        end
+
+        # Emission mesh: the entity's own mesh
+        mesh = comp.shape == EMIT_MESH ? get_component(eid, MeshComponent) : nothing
+        mesh_id = mesh === nothing ? UInt64(0) : UInt64(objectid(mesh))
+        if mesh_id != emitter.mesh_id && mesh !== nothing
+            positions = Float32[Float32(v[k]) for v in mesh.vertices for k in 1:3]
+            wgpu_set_particle_emitter_mesh(h, emitter.handle, positions, mesh.indices)
+            emitter.mesh_id = mesh_id
+        end
 
-    # Collect all particle vertex data into a single buffer
-    all_vertices = Float32[]
-    total_vertex_count = 0
+        texture = comp.texture === nothing ? UInt64(0) : _load_and_upload_texture(backend, comp.texture.path)
+        if texture != emitter.texture
+            wgpu_set_particle_emitter_texture(h, emitter.handle, texture)
+            emitter.texture = texture
+        end
 
-    for (eid, pool) in PARTICLE_POOLS
-        pool.vertex_count <= 0 && continue
-        # Pool vertex data is interleaved: pos3 + uv2 + color4 = 9 floats per vertex
-        num_floats = pool.vertex_count * 9
-        if num_floats <= length(pool.vertex_data)
-            append!(all_vertices, @view pool.vertex_data[1:num_floats])
-            total_vertex_count += pool.vertex_count
+        world = get_world_transform(eid)
+        world_mat = Float32[Float32(world[i]) for i in 1:16]
+        wgpu_set_particle_emitter_transform(h, emitter.handle, world_mat, comp._active)
+        if comp.burst_count > 0
+            wgpu_burst_particle_emitter(h, emitter.handle, comp.burst_count)
+            comp.burst_count = 0
         end
     end
 
-    total_vertex_count <= 0 && return
+    # Destroy emitters whose entities are gone
+    for eid in collect(keys(emitters))
+        if eid ∉ seen
+            wgpu_destroy_particle_emitter(h, emitters[eid].handle)
+            delete!(emitters, eid)
+        end
+    end
+
+    dt = BACKEND_PARTICLE_DT[]
+    BACKEND_PARTICLE_DT[] = 0.0f0
+    isempty(emitters) && return
 
-    # Flatten view and projection matrices to Float32 arrays (column-major)
     view_mat = Float32[Float32(view[i]) for i in 1:16]
     proj_mat = Float32[Float32(proj[i]) for i in 1:16]
-
-    wgpu_particle_pass(backend.backend_handle, all_vertices,
-        UInt32(total_vertex_count), view_mat, proj_mat)
+    wgpu_simulate_particles(h, dt, view_mat, proj_mat)
+    wgpu_gpu_particle_pass(h, view_mat, proj_mat)
 end
 
 # ---- Helper: Render UI ----
@@ -666,14 +896,29 @@ function _render_wgpu_ui(backend::WebGPUBackend)
     clear_ui!(ctx)
     _UI_CALLBACK[](ctx)
 
-    isempty(ctx.vertices) && return
+    vertices = _pack_wgpu_ui_vertices(ctx)
+    isempty(vertices) && return
 
-    # UI vertex data: pos2 + uv2 + color4 = 8 floats per vertex
-    vertex_count = length(ctx.vertices) ÷ 8
-    vertex_count <= 0 && return
+    wgpu_ui_pass(backend.backend_handle, vertices,
+        UInt32(length(vertices) ÷ 8), Float32(ctx.width), Float32(ctx.height))
+end
 
-    wgpu_ui_pass(backend.backend_handle, ctx.vertices,
-        UInt32(vertex_count), Float32(ctx.width), Float32(ctx.height))
+"""
+    _pack_wgpu_ui_vertices(ctx::UIContext) -> Vector{Float32}
+
+Vertices of the solid-color draw commands, overlay last (pos2 + uv2 + color4
+= 8 floats per vertex). Textured commands are skipped: their texture ids are
+OpenGL textures.
+"""
+function _pack_wgpu_ui_vertices(ctx::UIContext)
+    vertices = Float32[]
+    for (cmds, source) in ((ctx.draw_commands, ctx.vertices), (ctx.overlay_draw_commands, ctx.overlay_vertices))
+        for cmd in cmds
+            cmd.texture_id == UInt32(0) || continue
+            append!(vertices, view(source, cmd.vertex_offset + 1:cmd.vertex_offset + cmd.vertex_count * 8))
+        end
+    end
+    return vertices
 end
 
 # ---- Shader operations ----
@@ -735,16 +980,62 @@ function backend_upload_mesh!(backend::WebGPUBackend, entity_id, mesh)
 
     indices = UInt32.(mesh.indices)
 
-    handle = wgpu_upload_mesh(backend.backend_handle, positions, normals, uvs, indices)
+    handle = if !isempty(mesh.bone_weights) && length(mesh.bone_indices) == length(mesh.vertices)
+        # Skinned meshes are deformed in the vertex stage from a per-frame bone palette
+        bone_weights = Float32[]
+        for w in mesh.bone_weights
+            push!(bone_weights, Float32(w[1]), Float32(w[2]), Float32(w[3]), Float32(w[4]))
+        end
+        bone_indices = UInt16[]
+        for bi in mesh.bone_indices
+            push!(bone_indices, UInt16(bi[1]), UInt16(bi[2]), UInt16(bi[3]), UInt16(bi[4]))
+        end
+        wgpu_upload_skinned_mesh(backend.backend_handle, positions, normals, uvs,
+                                 bone_weights, bone_indices, indices)
+    else
+        wgpu_upload_mesh(backend.backend_handle, positions, normals, uvs, indices)
+    end
     if handle == UInt64(0)
         error("Failed to upload mesh: $(wgpu_last_error(backend.backend_handle))")
     end
 
+    if !isempty(mesh.morph_targets)
+        _upload_morph_targets(backend, handle, mesh, positions, normals)
+    end
+
     gpu_mesh = WebGPUGPUMesh(handle, Int32(length(indices)))
     backend.gpu_cache.meshes[eid] = gpu_mesh
     return gpu_mesh
 end
 
+function _upload_morph_targets(backend::WebGPUBackend, handle::UInt64, mesh,
+                               positions::Vector{Float32}, normals::Vector{Float32})
+    nv = length(mesh.vertices)
+    position_deltas = Float32[]
+    for target in mesh.morph_targets
+        for i in 1:nv
+            d = i <= length(target.position_deltas) ? target.position_deltas[i] : Vec3f(0, 0, 0)
+            push!(position_deltas, d[1], d[2], d[3])
+        end
+    end
+
+    # Normal deltas are all-or-nothing across targets on the FFI side
+    normal_deltas = nothing
+    if all(t -> length(t.normal_deltas) == nv, mesh.morph_targets)
+        normal_deltas = Float32[]
+        for target in mesh.morph_targets, d in target.normal_deltas
+            push!(normal_deltas, d[1], d[2], d[3])
+        end
+    end
+
+    if wgpu_upload_morph_targets(backend.backend_handle, handle, positions, normals,
+                                 position_deltas, normal_deltas, length(mesh.morph_targets)) != 0
+        error("Failed to upload morph targets: $(wgpu_last_error(backend.backend_handle))")
+    end
+    wgpu_set_morph_weights(backend.backend_handle, handle, mesh.morph_weights)
+    return nothing
+end
+
 function backend_draw_mesh!(backend::WebGPUBackend, gpu_mesh::WebGPUGPUMesh)
     # No-op: draw calls happen inside render_frame on the Rust side
     nothing
@@ -817,6 +1108,129 @@ function backend_create_csm!(backend::WebGPUBackend, num_cascades::Int, resoluti
     return WebGPUCascadedShadowMap(handle, num_cascades, resolution)
 end
 
+"""
+    set_shadow_config!(backend::WebGPUBackend, config::ShadowConfig)
+
+Switch the cascaded shadow filter (hard, PCF, PCSS or EVSM) and biases.
+Takes effect from the next frame's shadow pass.
+"""
+function set_shadow_config!(backend::WebGPUBackend, config::ShadowConfig)
+    backend.shadow_config = config
+    if backend.initialized && wgpu_set_csm_settings(backend.backend_handle, config) != 0
+        @warn "Failed to set CSM settings" error=wgpu_last_error(backend.backend_handle)
+    end
+    return nothing
+end
+
+"""
+    set_post_process_config!(backend::WebGPUBackend, config::PostProcessConfig)
+
+Replace the post-process settings. Effects are enabled, disabled and
+reordered in place, without recreating the deferred pipeline. Call again
+after mutating `config` to push the changes.
+"""
+function set_post_process_config!(backend::WebGPUBackend, config::PostProcessConfig)
+    backend.post_process_config = config
+    backend.initialized && _apply_post_process_config!(backend, config)
+    return nothing
+end
+
+"""
+    set_post_effect_enabled!(backend::WebGPUBackend, effect::PostEffect, enabled::Bool)
+
+Turn one post-process effect on or off.
+"""
+function set_post_effect_enabled!(backend::WebGPUBackend, effect::PostEffect, enabled::Bool)
+    if backend.initialized && wgpu_set_post_effect_enabled(backend.backend_handle, effect, enabled) != 0
+        @warn "Failed to toggle post effect" effect error=wgpu_last_error(backend.backend_handle)
+    end
+    return nothing
+end
+
+"""
+    set_post_effect_order!(backend::WebGPUBackend, order::Vector{PostEffect})
+
+Reorder the post-process effects; `order` lists every `PostEffect` once.
+"""
+function set_post_effect_order!(backend::WebGPUBackend, order::Vector{PostEffect})
+    if backend.initialized && wgpu_set_post_effect_order(backend.backend_handle, order) != 0
+        @warn "Failed to reorder post effects" error=wgpu_last_error(backend.backend_handle)
+    end
+    return nothing
+end
+
+"""
+    set_display_output!(backend::WebGPUBackend, mode::DisplayMode; paper_white_nits=200, max_nits=1000) -> DisplayMode
+
+Present to an SDR, scRGB or HDR10 swapchain. The surface is reconfigured
+with the best format it offers for `mode`, and the tone mapper targets
+`max_nits` on HDR outputs. Returns the mode in use, which is `DISPLAY_SDR`
+(or the other HDR mode) when the display lacks the requested format.
+"""
+function set_display_output!(backend::WebGPUBackend, mode::DisplayMode;
+                             paper_white_nits::Real=200, max_nits::Real=1000)
+    config = DisplayConfig(; mode, paper_white_nits, max_nits)
+    backend.display_config = config
+    backend.initialized || return mode
+    return _apply_display_config!(backend, config)
+end
+
+# Push `config` to the Rust side and map the encoding it picked back to a mode.
+function _apply_display_config!(backend::WebGPUBackend, config::DisplayConfig)
+    encoding = wgpu_set_display_output(backend.backend_handle, config)
+    if encoding < 0
+        @warn "Failed to set display output" error=wgpu_last_error(backend.backend_handle)
+        return DISPLAY_SDR
+    end
+    active = encoding == 2 ? DISPLAY_HDR_SCRGB : encoding == 3 ? DISPLAY_HDR10 : DISPLAY_SDR
+    active != config.mode && @info "Display does not support $(config.mode), using $active"
+    return active
+end
+
+"""
+    read_output(backend::WebGPUBackend) -> Matrix{Float32}
+
+Last presented frame of a headless backend, as a 4×(width·height) matrix of
+RGBA columns starting at the top-left pixel. Values are what the display
+would receive: linear 0..1 for SDR, scRGB (1.0 = 80 nits) or PQ for HDR.
+"""
+function read_output(backend::WebGPUBackend)
+    pixels = wgpu_read_output(backend.backend_handle, backend.width, backend.height)
+    pixels === nothing && error("Failed to read WebGPU output: $(wgpu_last_error(backend.backend_handle))")
+    return pixels
+end
+
+"""
+    set_resolution_config!(backend::WebGPUBackend, config::ResolutionConfig)
+
+Set the render scale, dynamic resolution and MSAA sample count. Resizes the
+render targets when the scale changes. Rejected settings, such as an MSAA
+count the device lacks, are logged.
+"""
+function set_resolution_config!(backend::WebGPUBackend, config::ResolutionConfig)
+    backend.resolution_config = config
+    backend.initialized && _apply_resolution_config!(backend, config)
+    return nothing
+end
+
+function _apply_resolution_config!(backend::WebGPUBackend, config::ResolutionConfig)
+    if wgpu_set_resolution(backend.backend_handle, config) != 0
+        @warn "Failed to set resolution config" error=wgpu_last_error(backend.backend_handle)
+    end
+    return nothing
+end
+
+"""
+    render_scale(backend::WebGPUBackend) -> Float32
+
+Fraction of the window size the scene renders at; changes over time with
+dynamic resolution.
+"""
+function render_scale(backend::WebGPUBackend)
+    backend.initialized || return backend.resolution_config.render_scale
+    return wgpu_render_scale(backend.backend_handle)
+end
+
 # ---- IBL operations ----
 
 function backend_create_ibl_environment!(backend::WebGPUBackend, path::String, intensity::Float32)
@@ -871,7 +1285,7 @@ end
 
 # ---- Windowing / event loop operations ----
 
-backend_should_close(b::WebGPUBackend) = GLFW.WindowShouldClose(b.window.handle)
+backend_should_close(b::WebGPUBackend) = b.window !== nothing && GLFW.WindowShouldClose(b.window.handle)
 
 function backend_poll_events!(b::WebGPUBackend)
     GLFW.PollEvents()
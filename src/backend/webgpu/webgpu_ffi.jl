@@ -44,6 +44,16 @@ function wgpu_initialize(window_handle::UInt64, display_handle::Ptr{Nothing}, wi
           window_handle, display_handle, Int32(width), Int32(height))
 end
 
+"""
+    wgpu_initialize_headless(width, height) -> UInt64
+
+Create a backend without a window that presents to an offscreen
+`Rgba16Float` target, read back with `wgpu_read_output`. Returns 0 on failure.
+"""
+function wgpu_initialize_headless(width::Int, height::Int)
+    ccall((:or_wgpu_initialize_headless, _webgpu_lib()), UInt64, (Int32, Int32), Int32(width), Int32(height))
+end
+
 function wgpu_shutdown(backend::UInt64)
     ccall((:or_wgpu_shutdown, _webgpu_lib()), Cvoid, (UInt64,), backend)
 end
@@ -74,6 +84,42 @@ function wgpu_upload_mesh(backend::UInt64,
           backend, positions, num_vertices, normals, uvs, indices, num_indices)
 end
 
+function wgpu_upload_skinned_mesh(backend::UInt64,
+                                  positions::Vector{Float32}, normals::Vector{Float32},
+                                  uvs::Vector{Float32}, bone_weights::Vector{Float32},
+                                  bone_indices::Vector{UInt16}, indices::Vector{UInt32})
+    num_vertices = UInt32(length(positions) ÷ 3)
+    num_indices = UInt32(length(indices))
+    ccall((:or_wgpu_upload_skinned_mesh, _webgpu_lib()), UInt64,
+          (UInt64, Ptr{Float32}, UInt32, Ptr{Float32}, Ptr{Float32}, Ptr{Float32}, Ptr{UInt16}, Ptr{UInt32}, UInt32),
+          backend, positions, num_vertices, normals, uvs, bone_weights, bone_indices, indices, num_indices)
+end
+
+function wgpu_update_bone_matrices(backend::UInt64, mesh::UInt64, matrices::Vector{Float32})
+    ccall((:or_wgpu_update_bone_matrices, _webgpu_lib()), Int32,
+          (UInt64, UInt64, Ptr{Float32}, UInt32),
+          backend, mesh, matrices, UInt32(length(matrices) ÷ 16))
+end
+
+function wgpu_upload_morph_targets(backend::UInt64, mesh::UInt64,
+                                   base_positions::Vector{Float32}, base_normals::Vector{Float32},
+                                   position_deltas::Vector{Float32},
+                                   normal_deltas::Union{Vector{Float32}, Nothing},
+                                   num_targets::Int)
+    nd_ptr = normal_deltas === nothing ? Ptr{Float32}(C_NULL) : pointer(normal_deltas)
+    GC.@preserve normal_deltas begin
+        ccall((:or_wgpu_upload_morph_targets, _webgpu_lib()), Int32,
+              (UInt64, UInt64, Ptr{Float32}, Ptr{Float32}, Ptr{Float32}, Ptr{Float32}, UInt32),
+              backend, mesh, base_positions, base_normals, position_deltas, nd_ptr, UInt32(num_targets))
+    end
+end
+
+function wgpu_set_morph_weights(backend::UInt64, mesh::UInt64, weights::Vector{Float32})
+    ccall((:or_wgpu_set_morph_weights, _webgpu_lib()), Int32,
+          (UInt64, UInt64, Ptr{Float32}, UInt32),
+          backend, mesh, weights, UInt32(length(weights)))
+end
+
 function wgpu_destroy_mesh(backend::UInt64, mesh::UInt64)
     ccall((:or_wgpu_destroy_mesh, _webgpu_lib()), Cvoid,
           (UInt64, UInt64), backend, mesh)
@@ -102,8 +148,28 @@ function wgpu_create_csm(backend::UInt64, num_cascades::Int, resolution::Int,
           backend, Int32(num_cascades), Int32(resolution), near, far)
 end
 
+"""
+    wgpu_set_csm_settings(backend, config::ShadowConfig) -> Int32
+
+Set the filter and biases of the cascaded shadow maps.
+Returns 0 on success, -1 on failure.
+"""
+function wgpu_set_csm_settings(backend::UInt64, config::ShadowConfig)
+    bytes = _struct_to_bytes(WGPUCsmSettings(config))
+    ccall((:or_wgpu_set_csm_settings, _webgpu_lib()), Int32,
+          (UInt64, Ptr{UInt8}, UInt32),
+          backend, bytes, UInt32(length(bytes)))
+end
+
 # ---- Post-processing ----
 
+"""
+    wgpu_create_post_process(backend, width, height, bloom_threshold, bloom_intensity,
+                             gamma, tone_mapping_mode, fxaa_enabled) -> UInt64
+
+Set the bloom, tone mapping and FXAA settings of the backend's post-process
+stack. Returns 1 on success, 0 on failure.
+"""
 function wgpu_create_post_process(backend::UInt64, width::Int, height::Int,
                                     bloom_threshold::Float32, bloom_intensity::Float32,
                                     gamma::Float32, tone_mapping_mode::Int,
@@ -115,6 +181,241 @@ function wgpu_create_post_process(backend::UInt64, width::Int, height::Int,
           Int32(tone_mapping_mode), Int32(fxaa_enabled ? 1 : 0))
 end
 
+"""
+    wgpu_set_post_effect_enabled(backend, effect::PostEffect, enabled) -> Int32
+
+Turn one effect of the post-process stack on or off.
+Returns 0 on success, -1 on failure.
+"""
+function wgpu_set_post_effect_enabled(backend::UInt64, effect::PostEffect, enabled::Bool)
+    ccall((:or_wgpu_set_post_effect_enabled, _webgpu_lib()), Int32,
+          (UInt64, UInt32, Int32),
+          backend, UInt32(Int(effect)), Int32(enabled))
+end
+
+"""
+    wgpu_set_post_effect_order(backend, order::Vector{PostEffect}) -> Int32
+
+Reorder the post-process stack; `order` lists every effect once.
+Returns 0 on success, -1 on failure.
+"""
+function wgpu_set_post_effect_order(backend::UInt64, order::Vector{PostEffect})
+    ids = UInt32[Int(e) for e in order]
+    ccall((:or_wgpu_set_post_effect_order, _webgpu_lib()), Int32,
+          (UInt64, Ptr{UInt32}, UInt32),
+          backend, ids, UInt32(length(ids)))
+end
+
+function wgpu_set_post_bloom(backend::UInt64, threshold::Float32, intensity::Float32)
+    ccall((:or_wgpu_set_post_bloom, _webgpu_lib()), Int32,
+          (UInt64, Float32, Float32), backend, threshold, intensity)
+end
+
+function wgpu_set_post_tone_mapping(backend::UInt64, mode::ToneMappingMode, gamma::Float32)
+    ccall((:or_wgpu_set_post_tone_mapping, _webgpu_lib()), Int32,
+          (UInt64, UInt32, Float32), backend, UInt32(Int(mode)), gamma)
+end
+
+function wgpu_set_post_ssao(backend::UInt64, radius::Float32, bias::Float32, power::Float32, kernel_size::Int)
+    ccall((:or_wgpu_set_post_ssao, _webgpu_lib()), Int32,
+          (UInt64, Float32, Float32, Float32, UInt32), backend, radius, bias, power, UInt32(kernel_size))
+end
+
+function wgpu_set_post_ssr(backend::UInt64, max_steps::Int, max_distance::Float32, thickness::Float32)
+    ccall((:or_wgpu_set_post_ssr, _webgpu_lib()), Int32,
+          (UInt64, UInt32, Float32, Float32), backend, UInt32(max_steps), max_distance, thickness)
+end
+
+function wgpu_set_post_taa(backend::UInt64, feedback::Float32)
+    ccall((:or_wgpu_set_post_taa, _webgpu_lib()), Int32, (UInt64, Float32), backend, feedback)
+end
+
+function wgpu_set_post_dof(backend::UInt64, focus_distance::Float32, focus_range::Float32, bokeh_radius::Float32)
+    ccall((:or_wgpu_set_post_dof, _webgpu_lib()), Int32,
+          (UInt64, Float32, Float32, Float32), backend, focus_distance, focus_range, bokeh_radius)
+end
+
+function wgpu_set_post_motion_blur(backend::UInt64, intensity::Float32, samples::Int, max_velocity::Float32)
+    ccall((:or_wgpu_set_post_motion_blur, _webgpu_lib()), Int32,
+          (UInt64, Float32, UInt32, Float32), backend, intensity, UInt32(samples), max_velocity)
+end
+
+function wgpu_set_post_color_grading(backend::UInt64, brightness::Float32, contrast::Float32, saturation::Float32,
+                                     lut_strength::Float32)
+    ccall((:or_wgpu_set_post_color_grading, _webgpu_lib()), Int32,
+          (UInt64, Float32, Float32, Float32, Float32), backend, brightness, contrast, saturation, lut_strength)
+end
+
+function wgpu_set_post_exposure(backend::UInt64, mode::ExposureMode, ev100::Float32, compensation::Float32)
+    ccall((:or_wgpu_set_post_exposure, _webgpu_lib()), Int32,
+          (UInt64, UInt32, Float32, Float32), backend, UInt32(Int(mode)), ev100, compensation)
+end
+
+function wgpu_set_post_auto_exposure(backend::UInt64, min_ev100::Float32, max_ev100::Float32,
+                                     speed_up::Float32, speed_down::Float32)
+    ccall((:or_wgpu_set_post_auto_exposure, _webgpu_lib()), Int32,
+          (UInt64, Float32, Float32, Float32, Float32), backend, min_ev100, max_ev100, speed_up, speed_down)
+end
+
+function wgpu_set_post_lens(backend::UInt64, chromatic_aberration::Float32, vignette_intensity::Float32,
+                            vignette_radius::Float32, vignette_softness::Float32, grain_intensity::Float32)
+    ccall((:or_wgpu_set_post_lens, _webgpu_lib()), Int32,
+          (UInt64, Float32, Float32, Float32, Float32, Float32),
+          backend, chromatic_aberration, vignette_intensity, vignette_radius, vignette_softness, grain_intensity)
+end
+
+"""
+    wgpu_set_color_lut(backend, cube_text::String) -> Int32
+
+Grade with the 3D LUT in `cube_text`, the contents of a `.cube` file.
+Returns 0 on success, -1 if it does not parse (see `wgpu_last_error`).
+"""
+function wgpu_set_color_lut(backend::UInt64, cube_text::String)
+    ccall((:or_wgpu_set_color_lut, _webgpu_lib()), Int32, (UInt64, Cstring), backend, cube_text)
+end
+
+function wgpu_clear_color_lut(backend::UInt64)
+    ccall((:or_wgpu_clear_color_lut, _webgpu_lib()), Int32, (UInt64,), backend)
+end
+
+# ---- Display output ----
+
+"""
+    wgpu_set_display_output(backend, config::DisplayConfig) -> Int32
+
+Switch the output to `config.mode`. Returns the encoding the present pass
+uses (0/1 SDR, 2 scRGB, 3 HDR10), or -1 on invalid settings.
+"""
+function wgpu_set_display_output(backend::UInt64, config::DisplayConfig)
+    ccall((:or_wgpu_set_display_output, _webgpu_lib()), Int32,
+          (UInt64, UInt32, Float32, Float32),
+          backend, UInt32(Int(config.mode)), config.paper_white_nits, config.max_nits)
+end
+
+"""
+    wgpu_read_output(backend, width, height) -> Union{Matrix{Float32}, Nothing}
+
+Last presented frame of a headless backend as a 4×(width·height) matrix of
+RGBA columns, rows from the top. `nothing` on failure.
+"""
+function wgpu_read_output(backend::UInt64, width::Int, height::Int)
+    pixels = Matrix{Float32}(undef, 4, width * height)
+    n = ccall((:or_wgpu_read_output, _webgpu_lib()), Int32,
+              (UInt64, Ptr{Float32}, UInt32), backend, pixels, UInt32(length(pixels)))
+    return n < 0 ? nothing : pixels
+end
+
+# ---- Resolution ----
+
+function wgpu_set_render_scale(backend::UInt64, scale::Float32, sharpness::Float32)
+    ccall((:or_wgpu_set_render_scale, _webgpu_lib()), Int32,
+          (UInt64, Float32, Float32), backend, scale, sharpness)
+end
+
+function wgpu_set_dynamic_resolution(backend::UInt64, enabled::Bool, target_frame_ms::Float32,
+                                     min_scale::Float32, max_scale::Float32)
+    ccall((:or_wgpu_set_dynamic_resolution, _webgpu_lib()), Int32,
+          (UInt64, Int32, Float32, Float32, Float32),
+          backend, Int32(enabled), target_frame_ms, min_scale, max_scale)
+end
+
+"""
+    wgpu_set_msaa_samples(backend, samples) -> Int32
+
+Returns -1 if the device does not support `samples` (see `wgpu_last_error`).
+"""
+function wgpu_set_msaa_samples(backend::UInt64, samples::Integer)
+    ccall((:or_wgpu_set_msaa_samples, _webgpu_lib()), Int32, (UInt64, UInt32), backend, UInt32(samples))
+end
+
+"""
+    wgpu_set_resolution(backend, config::ResolutionConfig) -> Int32
+
+Push all of `config`; returns -1 if any part is rejected.
+"""
+function wgpu_set_resolution(backend::UInt64, config::ResolutionConfig)
+    wgpu_set_render_scale(backend, config.render_scale, config.sharpness) == 0 || return Int32(-1)
+    wgpu_set_dynamic_resolution(backend, config.dynamic_resolution, config.target_frame_ms,
+                                config.min_render_scale, config.max_render_scale) == 0 || return Int32(-1)
+    return wgpu_set_msaa_samples(backend, config.msaa_samples)
+end
+
+"""
+    wgpu_render_scale(backend) -> Float32
+
+Render scale in use (the dynamic one when dynamic resolution is on).
+"""
+function wgpu_render_scale(backend::UInt64)
+    ccall((:or_wgpu_render_scale, _webgpu_lib()), Float32, (UInt64,), backend)
+end
+
+# ---- Sky and fog ----
+
+"""
+    wgpu_set_atmosphere(backend, atmosphere::AtmosphereComponent) -> Int32
+
+Set the physically based sky; it replaces the background and the scene's IBL
+while enabled. Returns 0 on success, -1 on invalid settings.
+"""
+function wgpu_set_atmosphere(backend::UInt64, atmosphere::AtmosphereComponent)
+    bytes = _struct_to_bytes(WGPUAtmosphereSettings(atmosphere))
+    ccall((:or_wgpu_set_atmosphere, _webgpu_lib()), Int32,
+          (UInt64, Ptr{UInt8}, UInt32),
+          backend, bytes, UInt32(length(bytes)))
+end
+
+"""
+    wgpu_set_fog(backend, fog::VolumetricFogComponent) -> Int32
+
+Set the volumetric fog. Returns 0 on success, -1 on invalid settings.
+"""
+function wgpu_set_fog(backend::UInt64, fog::VolumetricFogComponent)
+    bytes = _struct_to_bytes(WGPUFogSettings(fog))
+    ccall((:or_wgpu_set_fog, _webgpu_lib()), Int32,
+          (UInt64, Ptr{UInt8}, UInt32),
+          backend, bytes, UInt32(length(bytes)))
+end
+
+# ---- Decals ----
+
+"""
+    wgpu_add_decal(backend, world_mat, decal::DecalComponent, albedo, normal) -> UInt64
+
+Add a decal box placed by `world_mat` (16 Float32, column-major). `albedo` and
+`normal` are texture handles, 0 for none. Returns the decal handle, or 0 on failure.
+"""
+function wgpu_add_decal(backend::UInt64, world_mat::Vector{Float32}, decal::DecalComponent,
+                        albedo::UInt64, normal::UInt64)
+    bytes = _struct_to_bytes(WGPUDecalParams(decal))
+    ccall((:or_wgpu_add_decal, _webgpu_lib()), UInt64,
+          (UInt64, Ptr{Float32}, Ptr{UInt8}, UInt32, UInt64, UInt64),
+          backend, world_mat, bytes, UInt32(length(bytes)), albedo, normal)
+end
+
+"""
+    wgpu_update_decal(backend, handle, world_mat, decal::DecalComponent, albedo, normal) -> Int32
+
+Replace a decal's box, settings and textures. Returns 0 on success, -1 on failure.
+"""
+function wgpu_update_decal(backend::UInt64, handle::UInt64, world_mat::Vector{Float32},
+                           decal::DecalComponent, albedo::UInt64, normal::UInt64)
+    bytes = _struct_to_bytes(WGPUDecalParams(decal))
+    ccall((:or_wgpu_update_decal, _webgpu_lib()), Int32,
+          (UInt64, UInt64, Ptr{Float32}, Ptr{UInt8}, UInt32, UInt64, UInt64),
+          backend, handle, world_mat, bytes, UInt32(length(bytes)), albedo, normal)
+end
+
+"""
+    wgpu_remove_decal(backend, handle)
+
+Remove a decal.
+"""
+function wgpu_remove_decal(backend::UInt64, handle::UInt64)
+    ccall((:or_wgpu_remove_decal, _webgpu_lib()), Cvoid,
+          (UInt64, UInt64),
+          backend, handle)
+end
+
 # ---- Error handling ----
 
 function wgpu_last_error(backend::UInt64)
@@ -165,8 +466,8 @@ struct WGPUPointLightData
     color::NTuple{4, Float32}      # rgb + w padding
     intensity::Float32
     range::Float32
-    _pad1::Float32
-    _pad2::Float32
+    shadow_index::Int32            # first of six WGPULocalShadowData records, or -1
+    _pad::Float32
 end
 
 """
@@ -183,20 +484,64 @@ struct WGPUDirLightData
     _pad3::Float32
 end
 
+"""
+    WGPUSpotLightData
+
+Matches Rust `SpotLightData` (80 bytes).
+"""
+struct WGPUSpotLightData
+    position::NTuple{4, Float32}   # xyz + w padding
+    direction::NTuple{4, Float32}  # xyz + w padding
+    color::NTuple{4, Float32}      # rgb + w padding
+    intensity::Float32
+    range::Float32
+    inner_cos::Float32             # cosine of the inner cone half-angle
+    outer_cos::Float32             # cosine of the outer cone half-angle
+    shadow_index::Int32            # WGPULocalShadowData record, or -1
+    _pad1::Float32
+    _pad2::Float32
+    _pad3::Float32
+end
+
+const WGPU_LIGHT_KIND_POINT = UInt32(0)
+const WGPU_LIGHT_KIND_SPOT = UInt32(1)
+
+const WGPU_AREA_LIGHT_RECT = UInt32(0)
+const WGPU_AREA_LIGHT_TUBE = UInt32(1)
+
+"""
+    WGPUAreaLightData
+
+Matches Rust `AreaLightData` (80 bytes).
+"""
+struct WGPUAreaLightData
+    position::NTuple{4, Float32}   # center + w padding
+    axis_x::NTuple{4, Float32}     # half-extent axis + w padding
+    axis_y::NTuple{4, Float32}     # half-extent axis (zero for tubes) + w padding
+    color::NTuple{4, Float32}      # rgb + w padding
+    intensity::Float32
+    range::Float32
+    radius::Float32                # tube thickness
+    shape::UInt32                  # WGPU_AREA_LIGHT_RECT or WGPU_AREA_LIGHT_TUBE
+end
+
 """
     WGPULightUniforms
 
 Matches Rust `LightUniforms`.
-16 point lights (48 bytes each) + 4 dir lights (48 bytes each) + 4 control ints/floats.
-Total: 16*48 + 4*48 + 16 = 768 + 192 + 16 = 976 bytes.
+4 dir lights (48 bytes each) + 8 control ints/floats = 224 bytes.
+Point, spot and area lights follow it in the uploaded data, in that order.
 """
 struct WGPULightUniforms
-    point_lights::NTuple{16, WGPUPointLightData}
     dir_lights::NTuple{4, WGPUDirLightData}
     num_point_lights::Int32
     num_dir_lights::Int32
     has_ibl::Int32
     ibl_intensity::Float32
+    num_spot_lights::Int32
+    num_area_lights::Int32
+    _pad1::Int32
+    _pad2::Int32
 end
 
 """
@@ -255,106 +600,170 @@ end
 const WGPU_ENTITY_DRAW_DATA_SIZE = Int(sizeof(WGPUEntityDrawData))
 
 """
-    WGPUSSAOParams
-
-Matches Rust `SSAOParams`.
-64 hemisphere sample directions (vec4 each) + projection matrix + control params.
-Total: 64*16 + 64 + 32 = 1024 + 64 + 32 = 1120 bytes.
-"""
-struct WGPUSSAOParams
-    samples::NTuple{256, Float32}              # 64 vec4 samples = 256 floats = 1024 bytes
-    projection::NTuple{16, Float32}            # mat4 = 64 bytes
-    kernel_size::Int32                         # 4
-    radius::Float32                            # 4
-    bias::Float32                              # 4
-    power::Float32                             # 4
-    screen_width::Float32                      # 4
-    screen_height::Float32                     # 4
+    WGPUParticleEmitterParams
+
+Matches Rust `ParticleEmitterParams`.
+GPU particle emitter descriptor. Curves are baked to 8 evenly spaced samples.
+Total: 432 bytes.
+"""
+struct WGPUParticleEmitterParams
+    velocity_min::NTuple{4, Float32}           # 16
+    velocity_max::NTuple{4, Float32}           # 16
+    start_color::NTuple{4, Float32}            # 16 (rgb + start alpha)
+    end_color::NTuple{4, Float32}              # 16 (rgb + end alpha)
+    shape_params::NTuple{4, Float32}           # 16 (sphere: radius, surface; cone: angle, radius; box: half extents)
+    max_particles::UInt32                      # 4
+    emission_rate::Float32                     # 4
+    lifetime_min::Float32                      # 4
+    lifetime_max::Float32                      # 4
+    gravity_modifier::Float32                  # 4
+    damping::Float32                           # 4
+    start_size_min::Float32                    # 4
+    start_size_max::Float32                    # 4
+    end_size::Float32                          # 4
+    blend_mode::UInt32                         # 4 (0 alpha, 1 additive, 2 premultiplied)
+    shape_kind::UInt32                         # 4 (0 point, 1 sphere, 2 cone, 3 box, 4 mesh)
+    mesh_triangle_count::UInt32                # 4 (set by the backend)
+    collision_mode::UInt32                     # 4 (0 none, 1 ground plane, 2 depth buffer)
+    plane_height::Float32                      # 4
+    bounce::Float32                            # 4
+    friction::Float32                          # 4
+    soft_distance::Float32                     # 4
+    sheet_tiles_x::UInt32                      # 4
+    sheet_tiles_y::UInt32                      # 4
+    sheet_frames::UInt32                       # 4
+    sheet_fps::Float32                         # 4
+    curve_flags::UInt32                        # 4 (bit 0 color, bit 1 size, bit 2 velocity)
+    has_texture::UInt32                        # 4 (set by the backend)
     _pad1::Float32                             # 4
-    _pad2::Float32                             # 4
+    color_lut::NTuple{8, NTuple{4, Float32}}   # 128
+    size_velocity_lut::NTuple{8, NTuple{4, Float32}}  # 128 (x = size multiplier, yzw = velocity offset)
 end
 
 """
-    WGPUSSRParams
+    WGPUCascadeData
 
-Matches Rust `SSRParams`.
-3 matrices (projection, view, inv_projection) + camera_pos + control params.
-Total: 3*64 + 16 + 32 = 240 bytes.
+Matches Rust `CascadeData` (80 bytes).
+Single shadow cascade: light-space VP matrix, split depth and the world-space
+size of a texel and of the 0..1 depth range.
 """
-struct WGPUSSRParams
-    projection::NTuple{16, Float32}            # mat4 = 64 bytes
-    view::NTuple{16, Float32}                  # mat4 = 64 bytes
-    inv_projection::NTuple{16, Float32}        # mat4 = 64 bytes
-    camera_pos::NTuple{4, Float32}             # vec4 = 16 bytes
-    screen_size::NTuple{2, Float32}            # 8 bytes
-    max_steps::Int32                           # 4
-    max_distance::Float32                      # 4
-    thickness::Float32                         # 4
-    _pad1::Float32                             # 4
-    _pad2::Float32                             # 4
-    _pad3::Float32                             # 4
+struct WGPUCascadeData
+    light_view_proj::NTuple{16, Float32}       # mat4 = 64 bytes
+    split_depth::Float32                       # 4
+    texel_size::Float32                        # 4
+    depth_range::Float32                       # 4
+    _pad::Float32                              # 4
 end
 
 """
-    WGPUTAAParams
+    WGPUCsmSettings
 
-Matches Rust `TAAParams`.
-Previous frame view-projection matrix + control params.
-Total: 64 + 16 = 80 bytes.
+Matches Rust `CsmSettings` (48 bytes). Filter and bias settings of the
+cascaded shadow maps, packed from a `ShadowConfig`.
 """
-struct WGPUTAAParams
-    prev_view_proj::NTuple{16, Float32}        # mat4 = 64 bytes
-    feedback::Float32                          # 4
-    first_frame::Int32                         # 4
-    screen_width::Float32                      # 4
-    screen_height::Float32                     # 4
+struct WGPUCsmSettings
+    filter_mode::UInt32                        # 4 (ShadowFilterMode)
+    pcf_samples::UInt32                        # 4
+    pcf_radius::Float32                        # 4
+    light_size::Float32                        # 4
+    blend_band::Float32                        # 4
+    depth_bias::Float32                        # 4
+    normal_bias::Float32                       # 4
+    slope_bias::Float32                        # 4
+    evsm_exponents::NTuple{2, Float32}         # 8
+    light_bleed_reduction::Float32             # 4
+    stabilize::UInt32                          # 4
+end
+
+function WGPUCsmSettings(config::ShadowConfig)
+    WGPUCsmSettings(UInt32(Int(config.filter)), UInt32(config.pcf_samples), config.pcf_radius,
+                    config.light_size, config.blend_band, config.depth_bias, config.normal_bias,
+                    config.slope_bias, config.evsm_exponents, config.light_bleed_reduction,
+                    UInt32(config.stabilize))
 end
 
 """
-    WGPUPostProcessParams
+    WGPUShadowUniforms
 
-Matches Rust `PostProcessParams`.
-Bloom and tone mapping control params.
-Total: 32 bytes.
+Matches Rust `ShadowUniforms`.
+4 cascade data entries + control params + CSM settings.
+Total: 4*80 + 16 + 48 = 384 bytes.
 """
-struct WGPUPostProcessParams
-    bloom_threshold::Float32                   # 4
-    bloom_intensity::Float32                   # 4
-    gamma::Float32                             # 4
-    tone_mapping_mode::Int32                   # 4
-    horizontal::Int32                          # 4
+struct WGPUShadowUniforms
+    cascades::NTuple{4, WGPUCascadeData}       # 4 * 80 = 320 bytes
+    num_cascades::Int32                        # 4
     _pad1::Float32                             # 4
     _pad2::Float32                             # 4
     _pad3::Float32                             # 4
+    settings::WGPUCsmSettings                  # 48
 end
 
 """
-    WGPUCascadeData
+    WGPUAtmosphereSettings
+
+Matches Rust `AtmosphereSettings` (68 bytes), packed from an `AtmosphereComponent`.
+"""
+struct WGPUAtmosphereSettings
+    rayleigh_scattering::NTuple{3, Float32}    # 12
+    rayleigh_scale_height::Float32             # 4
+    mie_scattering::Float32                    # 4
+    mie_absorption::Float32                    # 4
+    mie_scale_height::Float32                  # 4
+    mie_anisotropy::Float32                    # 4
+    ozone_absorption::NTuple{3, Float32}       # 12
+    planet_radius::Float32                     # 4
+    atmosphere_height::Float32                 # 4
+    ground_albedo::Float32                     # 4
+    sun_angular_radius::Float32                # 4
+    intensity::Float32                         # 4
+    enabled::UInt32                            # 4
+end
+
+function WGPUAtmosphereSettings(a::AtmosphereComponent)
+    WGPUAtmosphereSettings(Tuple(a.rayleigh_scattering), a.rayleigh_scale_height, a.mie_scattering,
+                           a.mie_absorption, a.mie_scale_height, a.mie_anisotropy, Tuple(a.ozone_absorption),
+                           a.planet_radius, a.atmosphere_height, a.ground_albedo, a.sun_angular_radius,
+                           a.intensity, UInt32(a.enabled))
+end
 
-Matches Rust `CascadeData` (80 bytes).
-Single shadow cascade: light-space VP matrix + split depth.
 """
-struct WGPUCascadeData
-    light_view_proj::NTuple{16, Float32}       # mat4 = 64 bytes
-    split_depth::Float32                       # 4
-    _pad1::Float32                             # 4
-    _pad2::Float32                             # 4
-    _pad3::Float32                             # 4
+    WGPUFogSettings
+
+Matches Rust `FogSettings` (44 bytes), packed from a `VolumetricFogComponent`.
+"""
+struct WGPUFogSettings
+    albedo::NTuple{3, Float32}                 # 12
+    density::Float32                           # 4
+    height_density::Float32                    # 4
+    height_falloff::Float32                    # 4
+    base_height::Float32                       # 4
+    anisotropy::Float32                        # 4
+    max_distance::Float32                      # 4
+    ambient_intensity::Float32                 # 4
+    enabled::UInt32                            # 4
+end
+
+function WGPUFogSettings(f::VolumetricFogComponent)
+    WGPUFogSettings((f.albedo.r, f.albedo.g, f.albedo.b), f.density, f.height_density, f.height_falloff,
+                    f.base_height, f.anisotropy, f.max_distance, f.ambient_intensity, UInt32(f.enabled))
 end
 
 """
-    WGPUShadowUniforms
+    WGPUDecalParams
 
-Matches Rust `ShadowUniforms`.
-4 cascade data entries + control params.
-Total: 4*80 + 16 = 336 bytes.
+Matches Rust `DecalParams` (32 bytes), packed from a `DecalComponent`.
 """
-struct WGPUShadowUniforms
-    cascades::NTuple{4, WGPUCascadeData}       # 4 * 80 = 320 bytes
-    num_cascades::Int32                        # 4
-    shadow_bias::Float32                       # 4
-    _pad1::Float32                             # 4
-    _pad2::Float32                             # 4
+struct WGPUDecalParams
+    color::NTuple{4, Float32}                  # 16
+    normal_strength::Float32                   # 4
+    roughness::Float32                         # 4
+    roughness_opacity::Float32                 # 4
+    angle_fade::Float32                        # 4
+end
+
+function WGPUDecalParams(d::DecalComponent)
+    WGPUDecalParams((d.color.r, d.color.g, d.color.b, d.opacity), d.normal_strength, d.roughness,
+                    d.roughness_opacity, d.angle_fade)
 end
 
 # ==================================================================
@@ -404,8 +813,9 @@ end
 """
     wgpu_upload_lights(backend, light_data) -> Int32
 
-Upload light uniform data (LightUniforms struct).
-`light_data` is a Vector{UInt8} containing a packed WGPULightUniforms.
+Upload light data: a packed WGPULightUniforms followed by its
+`num_point_lights` WGPUPointLightData, `num_spot_lights` WGPUSpotLightData
+and `num_area_lights` WGPUAreaLightData entries (see `_pack_lights`).
 Returns 0 on success, -1 on failure.
 """
 function wgpu_upload_lights(backend::UInt64, light_data::Vector{UInt8})
@@ -415,26 +825,73 @@ function wgpu_upload_lights(backend::UInt64, light_data::Vector{UInt8})
 end
 
 """
-    wgpu_shadow_pass(backend, mesh_handles, models, entity_count, cascade_matrices, num_cascades) -> Int32
+    wgpu_shadow_pass(backend, mesh_handles, models, entity_count, light_dir, num_cascades) -> Int32
 
-Render depth for all shadow cascades.
+Fit the shadow cascades to the camera of `wgpu_begin_frame` and render depth
+for all of them.
 - `mesh_handles`: Vector{UInt64} of mesh handles (one per entity)
 - `models`: Vector{Float32} of flattened model matrices (16 floats per entity)
 - `entity_count`: number of entities
-- `cascade_matrices`: Vector{Float32} of flattened cascade light-space VP matrices (16 floats per cascade)
-- `num_cascades`: number of cascades (typically 4)
+- `light_dir`: direction the shadow-casting directional light shines along
+- `num_cascades`: number of cascades (at most those of `wgpu_create_csm`; 0 turns shadows off)
 Returns 0 on success, -1 on failure.
 """
 function wgpu_shadow_pass(backend::UInt64,
                            mesh_handles::Vector{UInt64},
                            models::Vector{Float32},
                            entity_count::Integer,
-                           cascade_matrices::Vector{Float32},
+                           light_dir::Vec3f,
                            num_cascades::Integer)
+    dir = Float32[light_dir[1], light_dir[2], light_dir[3]]
     ccall((:or_wgpu_shadow_pass, _webgpu_lib()), Int32,
           (UInt64, Ptr{UInt64}, Ptr{Float32}, UInt32, Ptr{Float32}, Int32),
           backend, mesh_handles, models, UInt32(entity_count),
-          cascade_matrices, Int32(num_cascades))
+          dir, Int32(num_cascades))
+end
+
+"""
+    wgpu_create_shadow_atlas(backend, size, max_tile) -> Int32
+
+Create the point/spot light shadow atlas: a `size`x`size` depth texture whose
+tiles are at most `max_tile` texels wide (both powers of two).
+Returns 0 on success, -1 on failure.
+"""
+function wgpu_create_shadow_atlas(backend::UInt64, size::Integer, max_tile::Integer)
+    ccall((:or_wgpu_create_shadow_atlas, _webgpu_lib()), Int32,
+          (UInt64, UInt32, UInt32),
+          backend, UInt32(size), UInt32(max_tile))
+end
+
+"""
+    wgpu_set_shadow_casters(backend, light_kind, indices) -> Int32
+
+Mark which lights cast shadows. `light_kind` is `WGPU_LIGHT_KIND_POINT` or
+`WGPU_LIGHT_KIND_SPOT`; `indices` are 0-based positions of those lights in the
+last `wgpu_upload_lights` data. Replaces the previous set for that kind.
+Returns 0 on success, -1 on failure.
+"""
+function wgpu_set_shadow_casters(backend::UInt64, light_kind::UInt32, indices::Vector{UInt32})
+    ccall((:or_wgpu_set_shadow_casters, _webgpu_lib()), Int32,
+          (UInt64, UInt32, Ptr{UInt32}, UInt32),
+          backend, light_kind, indices, UInt32(length(indices)))
+end
+
+"""
+    wgpu_local_shadow_pass(backend, mesh_handles, models, entity_count) -> Int32
+
+Render shadows of the shadow-casting point and spot lights into the shadow
+atlas. Tiles are budgeted by the lights' screen coverage, and tiles whose
+light and static casters did not move are kept from earlier frames.
+Same entity arrays as `wgpu_shadow_pass`. Call after `wgpu_upload_lights` and
+before the lighting pass. Returns 0 on success, -1 on failure.
+"""
+function wgpu_local_shadow_pass(backend::UInt64,
+                                 mesh_handles::Vector{UInt64},
+                                 models::Vector{Float32},
+                                 entity_count::Integer)
+    ccall((:or_wgpu_local_shadow_pass, _webgpu_lib()), Int32,
+          (UInt64, Ptr{UInt64}, Ptr{Float32}, UInt32),
+          backend, mesh_handles, models, UInt32(entity_count))
 end
 
 """
@@ -455,6 +912,32 @@ function wgpu_gbuffer_pass(backend::UInt64,
           backend, entities_data, UInt32(entity_count), UInt32(entity_stride))
 end
 
+"""
+    wgpu_cull_stats(backend) -> NTuple{4, UInt32}
+
+Draws kept and frustum-culled by the last passes:
+`(gbuffer_drawn, gbuffer_culled, shadow_drawn, shadow_culled)`.
+"""
+function wgpu_cull_stats(backend::UInt64)
+    out = zeros(UInt32, 4)
+    ccall((:or_wgpu_cull_stats, _webgpu_lib()), Int32,
+          (UInt64, Ptr{UInt32}),
+          backend, out)
+    return (out[1], out[2], out[3], out[4])
+end
+
+"""
+    wgpu_set_gpu_culling(backend, enabled::Bool) -> Int32
+
+Cull instanced draws in a compute pass and issue them with indirect draws.
+Returns -1 if the adapter does not support indirect first-instance draws.
+"""
+function wgpu_set_gpu_culling(backend::UInt64, enabled::Bool)
+    ccall((:or_wgpu_set_gpu_culling, _webgpu_lib()), Int32,
+          (UInt64, Int32),
+          backend, Int32(enabled))
+end
+
 """
     wgpu_lighting_pass(backend) -> Int32
 
@@ -468,62 +951,44 @@ function wgpu_lighting_pass(backend::UInt64)
 end
 
 """
-    wgpu_ssao_pass(backend, params) -> Int32
+    wgpu_ssao_pass(backend) -> Int32
 
-Compute screen-space ambient occlusion from G-Buffer.
-`params` is a Vector{UInt8} containing a packed WGPUSSAOParams.
+Compute screen-space ambient occlusion from the G-Buffer for the lighting pass,
+with the post-process stack's SSAO settings (white AO when SSAO is disabled).
 Returns 0 on success, -1 on failure.
 """
-function wgpu_ssao_pass(backend::UInt64, params::Vector{UInt8})
-    ccall((:or_wgpu_ssao_pass, _webgpu_lib()), Int32,
-          (UInt64, Ptr{UInt8}),
-          backend, params)
+function wgpu_ssao_pass(backend::UInt64)
+    ccall((:or_wgpu_ssao_pass, _webgpu_lib()), Int32, (UInt64,), backend)
 end
 
 """
-    wgpu_ssr_pass(backend, params) -> Int32
+    wgpu_ssr_pass(backend) -> Int32
 
-Screen-space reflections pass.
-`params` is a Vector{UInt8} containing a packed WGPUSSRParams.
+Screen-space reflections of the last lit image for the lighting pass, with the
+post-process stack's SSR settings (none when SSR is disabled).
 Returns 0 on success, -1 on failure.
 """
-function wgpu_ssr_pass(backend::UInt64, params::Vector{UInt8})
-    ccall((:or_wgpu_ssr_pass, _webgpu_lib()), Int32,
-          (UInt64, Ptr{UInt8}),
-          backend, params)
+function wgpu_ssr_pass(backend::UInt64)
+    ccall((:or_wgpu_ssr_pass, _webgpu_lib()), Int32, (UInt64,), backend)
 end
 
 """
-    wgpu_taa_pass(backend, params) -> Int32
+    wgpu_postprocess_pass(backend) -> Int32
 
-Temporal anti-aliasing pass.
-`params` is a Vector{UInt8} containing a packed WGPUTAAParams.
+Run the enabled post-process stack effects (TAA, bloom, DOF, motion blur,
+tone mapping, color grading, FXAA) in stack order.
 Returns 0 on success, -1 on failure.
 """
-function wgpu_taa_pass(backend::UInt64, params::Vector{UInt8})
-    ccall((:or_wgpu_taa_pass, _webgpu_lib()), Int32,
-          (UInt64, Ptr{UInt8}),
-          backend, params)
-end
-
-"""
-    wgpu_postprocess_pass(backend, params) -> Int32
-
-Post-processing pass: bloom extraction, blur, composite, FXAA.
-`params` is a Vector{UInt8} containing a packed WGPUPostProcessParams.
-Returns 0 on success, -1 on failure.
-"""
-function wgpu_postprocess_pass(backend::UInt64, params::Vector{UInt8})
-    ccall((:or_wgpu_postprocess_pass, _webgpu_lib()), Int32,
-          (UInt64, Ptr{UInt8}),
-          backend, params)
+function wgpu_postprocess_pass(backend::UInt64)
+    ccall((:or_wgpu_postprocess_pass, _webgpu_lib()), Int32, (UInt64,), backend)
 end
 
 """
     wgpu_forward_pass(backend, entities_data, entity_count, entity_stride) -> Int32
 
-Render transparent objects (forward pass with blending).
-Same packed entity format as gbuffer_pass.
+Render transparent objects (forward pass with blending) into the lit image;
+call before the post-process pass. Same packed entity format as gbuffer_pass,
+sorted back-to-front.
 Returns 0 on success, -1 on failure.
 """
 function wgpu_forward_pass(backend::UInt64,
@@ -536,30 +1001,154 @@ function wgpu_forward_pass(backend::UInt64,
 end
 
 """
-    wgpu_particle_pass(backend, vertices, vertex_count, view_mat, proj_mat) -> Int32
+    wgpu_particle_pass(backend, vertices, vertex_count, view_mat, proj_mat;
+                       blend_mode=0, soft_distance=0, texture=0) -> Int32
 
-Render particle billboard quads.
+Render one emitter's particle billboard quads into the HDR target.
 - `vertices`: Vector{Float32} of interleaved vertex data (pos3 + uv2 + color4 = 9 floats/vertex)
 - `vertex_count`: number of vertices
 - `view_mat`: Vector{Float32} of 16 floats (column-major mat4 view matrix)
 - `proj_mat`: Vector{Float32} of 16 floats (column-major mat4 projection matrix)
+- `blend_mode`: 0 = alpha, 1 = additive, 2 = premultiplied
+- `soft_distance`: fade distance near opaque geometry (0 = off)
+- `texture`: sprite texture handle (0 = round procedural sprite)
 Returns 0 on success, -1 on failure.
 """
 function wgpu_particle_pass(backend::UInt64,
                              vertices::Vector{Float32},
                              vertex_count::Integer,
                              view_mat::Vector{Float32},
-                             proj_mat::Vector{Float32})
+                             proj_mat::Vector{Float32};
+                             blend_mode::Integer = 0,
+                             soft_distance::Real = 0.0f0,
+                             texture::UInt64 = UInt64(0))
     ccall((:or_wgpu_particle_pass, _webgpu_lib()), Int32,
-          (UInt64, Ptr{Float32}, UInt32, Ptr{Float32}, Ptr{Float32}),
-          backend, vertices, UInt32(vertex_count), view_mat, proj_mat)
+          (UInt64, Ptr{Float32}, UInt32, Ptr{Float32}, Ptr{Float32}, UInt32, Float32, UInt64),
+          backend, vertices, UInt32(vertex_count), view_mat, proj_mat,
+          UInt32(blend_mode), Float32(soft_distance), texture)
+end
+
+"""
+    wgpu_create_particle_emitter(backend, params) -> UInt64
+
+Create a GPU-simulated particle emitter from packed `WGPUParticleEmitterParams`.
+Returns emitter handle (> 0) or 0 on failure.
+"""
+function wgpu_create_particle_emitter(backend::UInt64, params::Vector{UInt8})
+    ccall((:or_wgpu_create_particle_emitter, _webgpu_lib()), UInt64,
+          (UInt64, Ptr{UInt8}),
+          backend, params)
+end
+
+"""
+    wgpu_update_particle_emitter(backend, emitter, params) -> Int32
+
+Replace an emitter's parameters. Changing `max_particles` clears live particles.
+Returns 0 on success, -1 on failure.
+"""
+function wgpu_update_particle_emitter(backend::UInt64, emitter::UInt64, params::Vector{UInt8})
+    ccall((:or_wgpu_update_particle_emitter, _webgpu_lib()), Int32,
+          (UInt64, UInt64, Ptr{UInt8}),
+          backend, emitter, params)
+end
+
+"""
+    wgpu_destroy_particle_emitter(backend, emitter)
+
+Destroy a GPU particle emitter.
+"""
+function wgpu_destroy_particle_emitter(backend::UInt64, emitter::UInt64)
+    ccall((:or_wgpu_destroy_particle_emitter, _webgpu_lib()), Cvoid,
+          (UInt64, UInt64),
+          backend, emitter)
+end
+
+"""
+    wgpu_set_particle_emitter_transform(backend, emitter, world_mat, active) -> Int32
+
+Place an emitter and enable/disable continuous emission.
+- `world_mat`: Vector{Float32} of 16 floats (column-major world transform)
+Returns 0 on success, -1 on failure.
+"""
+function wgpu_set_particle_emitter_transform(backend::UInt64, emitter::UInt64,
+                                             world_mat::Vector{Float32}, active::Bool)
+    ccall((:or_wgpu_set_particle_emitter_transform, _webgpu_lib()), Int32,
+          (UInt64, UInt64, Ptr{Float32}, Int32),
+          backend, emitter, world_mat, Int32(active))
+end
+
+"""
+    wgpu_set_particle_emitter_mesh(backend, emitter, positions, indices) -> Int32
+
+Emit from the surface of a triangle mesh (emitter space).
+- `positions`: Vector{Float32} of xyz-interleaved vertex positions
+- `indices`: Vector{UInt32} triangle list (0-based)
+Returns 0 on success, -1 on failure.
+"""
+function wgpu_set_particle_emitter_mesh(backend::UInt64, emitter::UInt64,
+                                        positions::Vector{Float32}, indices::Vector{UInt32})
+    ccall((:or_wgpu_set_particle_emitter_mesh, _webgpu_lib()), Int32,
+          (UInt64, UInt64, Ptr{Float32}, UInt32, Ptr{UInt32}, UInt32),
+          backend, emitter, positions, UInt32(length(positions) ÷ 3), indices, UInt32(length(indices)))
+end
+
+"""
+    wgpu_set_particle_emitter_texture(backend, emitter, texture) -> Int32
+
+Attach a sprite texture or texture sheet to an emitter (0 detaches it).
+Returns 0 on success, -1 on failure.
+"""
+function wgpu_set_particle_emitter_texture(backend::UInt64, emitter::UInt64, texture::UInt64)
+    ccall((:or_wgpu_set_particle_emitter_texture, _webgpu_lib()), Int32,
+          (UInt64, UInt64, UInt64),
+          backend, emitter, texture)
+end
+
+"""
+    wgpu_burst_particle_emitter(backend, emitter, count) -> Int32
+
+Spawn `count` particles on the emitter's next simulation step.
+Returns 0 on success, -1 on failure.
+"""
+function wgpu_burst_particle_emitter(backend::UInt64, emitter::UInt64, count::Integer)
+    ccall((:or_wgpu_burst_particle_emitter, _webgpu_lib()), Int32,
+          (UInt64, UInt64, UInt32),
+          backend, emitter, UInt32(count))
+end
+
+"""
+    wgpu_simulate_particles(backend, dt, view_mat, proj_mat) -> Int32
+
+Advance every GPU emitter by `dt` (spawn, integrate, collide, depth sort,
+indirect args). Depth-buffer collisions read this frame's G-buffer.
+- `view_mat`, `proj_mat`: Vector{Float32} of 16 floats (column-major)
+Returns 0 on success, -1 on failure.
+"""
+function wgpu_simulate_particles(backend::UInt64, dt::Real,
+                                 view_mat::Vector{Float32}, proj_mat::Vector{Float32})
+    ccall((:or_wgpu_simulate_particles, _webgpu_lib()), Int32,
+          (UInt64, Float32, Ptr{Float32}, Ptr{Float32}),
+          backend, Float32(dt), view_mat, proj_mat)
+end
+
+"""
+    wgpu_gpu_particle_pass(backend, view_mat, proj_mat) -> Int32
+
+Draw all GPU particle emitters with indirect draws.
+- `view_mat`, `proj_mat`: Vector{Float32} of 16 floats (column-major)
+Returns 0 on success, -1 on failure.
+"""
+function wgpu_gpu_particle_pass(backend::UInt64, view_mat::Vector{Float32}, proj_mat::Vector{Float32})
+    ccall((:or_wgpu_gpu_particle_pass, _webgpu_lib()), Int32,
+          (UInt64, Ptr{Float32}, Ptr{Float32}),
+          backend, view_mat, proj_mat)
 end
 
 """
     wgpu_ui_pass(backend, vertices, vertex_count, screen_width, screen_height) -> Int32
 
-Render 2D UI overlay.
-- `vertices`: Vector{Float32} of interleaved vertex data (pos2 + uv2 + color4 = 8 floats/vertex)
+Upload the 2D UI overlay, drawn over the frame by `wgpu_present`.
+- `vertices`: Vector{Float32} of interleaved solid-color vertex data (pos2 + uv2 + color4 = 8 floats/vertex)
 - `vertex_count`: number of vertices
 - `screen_width`, `screen_height`: viewport dimensions for orthographic projection
 Returns 0 on success, -1 on failure.
@@ -662,34 +1251,17 @@ end
 """
     _pack_lights(frame_light_data) -> Vector{UInt8}
 
-Pack a FrameLightData into a byte buffer matching WGPULightUniforms.
+Pack a FrameLightData into a WGPULightUniforms header followed by every
+point, spot and area light as WGPUPointLightData, WGPUSpotLightData and
+WGPUAreaLightData.
 
-Converts up to 16 point lights and 4 directional lights from the engine's
-FrameLightData struct into the packed GPU format.
+Point lights are not capped: the backend bins them into view-space clusters,
+so shading cost depends on the lights near each pixel rather than the total.
 """
 function _pack_lights(fld)::Vector{UInt8}
-    # Pack point lights (up to 16)
-    num_point = min(length(fld.point_positions), 16)
-    point_lights = ntuple(16) do i
-        if i <= num_point
-            pos = fld.point_positions[i]
-            col = fld.point_colors[i]
-            WGPUPointLightData(
-                (Float32(pos[1]), Float32(pos[2]), Float32(pos[3]), 0.0f0),
-                (Float32(col.r), Float32(col.g), Float32(col.b), 1.0f0),
-                Float32(fld.point_intensities[i]),
-                Float32(fld.point_ranges[i]),
-                0.0f0,
-                0.0f0,
-            )
-        else
-            WGPUPointLightData(
-                (0.0f0, 0.0f0, 0.0f0, 0.0f0),
-                (0.0f0, 0.0f0, 0.0f0, 0.0f0),
-                0.0f0, 0.0f0, 0.0f0, 0.0f0,
-            )
-        end
-    end
+    num_point = length(fld.point_positions)
+    num_spot = length(fld.spot_positions)
+    num_area = length(fld.area_positions)
 
     # Pack directional lights (up to 4)
     num_dir = min(length(fld.dir_directions), 4)
@@ -713,14 +1285,65 @@ function _pack_lights(fld)::Vector{UInt8}
     end
 
     lu = WGPULightUniforms(
-        point_lights,
         dir_lights,
         Int32(num_point),
         Int32(num_dir),
         Int32(fld.has_ibl ? 1 : 0),
         Float32(fld.ibl_intensity),
+        Int32(num_spot),
+        Int32(num_area),
+        Int32(0),
+        Int32(0),
     )
-    return _struct_to_bytes(lu)
+    buf = _struct_to_bytes(lu)
+
+    for i in 1:num_point
+        pos = fld.point_positions[i]
+        col = fld.point_colors[i]
+        append!(buf, _struct_to_bytes(WGPUPointLightData(
+            (Float32(pos[1]), Float32(pos[2]), Float32(pos[3]), 0.0f0),
+            (Float32(col.r), Float32(col.g), Float32(col.b), 1.0f0),
+            Float32(fld.point_intensities[i]),
+            Float32(fld.point_ranges[i]),
+            Int32(-1),  # filled in by the local shadow pass
+            0.0f0,
+        )))
+    end
+
+    for i in 1:num_spot
+        pos = fld.spot_positions[i]
+        dir = fld.spot_directions[i]
+        col = fld.spot_colors[i]
+        append!(buf, _struct_to_bytes(WGPUSpotLightData(
+            (Float32(pos[1]), Float32(pos[2]), Float32(pos[3]), 1.0f0),
+            (Float32(dir[1]), Float32(dir[2]), Float32(dir[3]), 0.0f0),
+            (Float32(col.r), Float32(col.g), Float32(col.b), 1.0f0),
+            Float32(fld.spot_intensities[i]),
+            Float32(fld.spot_ranges[i]),
+            Float32(cos(fld.spot_inner_angles[i])),
+            Float32(cos(fld.spot_outer_angles[i])),
+            Int32(-1),  # filled in by the local shadow pass
+            0.0f0, 0.0f0, 0.0f0,
+        )))
+    end
+
+    for i in 1:num_area
+        pos = fld.area_positions[i]
+        ax = fld.area_axes_x[i]
+        ay = fld.area_axes_y[i]
+        col = fld.area_colors[i]
+        append!(buf, _struct_to_bytes(WGPUAreaLightData(
+            (Float32(pos[1]), Float32(pos[2]), Float32(pos[3]), 1.0f0),
+            (Float32(ax[1]), Float32(ax[2]), Float32(ax[3]), 0.0f0),
+            (Float32(ay[1]), Float32(ay[2]), Float32(ay[3]), 0.0f0),
+            (Float32(col.r), Float32(col.g), Float32(col.b), 1.0f0),
+            Float32(fld.area_intensities[i]),
+            Float32(fld.area_ranges[i]),
+            Float32(fld.area_radii[i]),
+            fld.area_shapes[i] == :tube ? WGPU_AREA_LIGHT_TUBE : WGPU_AREA_LIGHT_RECT,
+        )))
+    end
+    return buf
 end
 
 # ==================================================================
@@ -141,16 +141,51 @@ end
 # Resource caches (Julia-side, map EntityID to handle)
 # ==================================================================
 
+"""
+    WebGPUParticleEmitter
+
+GPU-simulated particle emitter. `params` holds the last uploaded
+`WGPUParticleEmitterParams` bytes so changes can be detected; `texture` and
+`mesh_id` (`objectid` of the emission mesh, 0 = none) track what is attached.
+"""
+mutable struct WebGPUParticleEmitter
+    handle::UInt64
+    params::Vector{UInt8}
+    texture::UInt64
+    mesh_id::UInt64
+end
+
+WebGPUParticleEmitter(handle::UInt64, params::Vector{UInt8}) =
+    WebGPUParticleEmitter(handle, params, UInt64(0), UInt64(0))
+
+"""
+    WebGPUDecal
+
+Decal added to the backend, with the component, world transform and texture
+handles it was last uploaded with so changes can be detected.
+"""
+mutable struct WebGPUDecal
+    handle::UInt64
+    decal::DecalComponent
+    world_mat::Vector{Float32}
+    albedo::UInt64
+    normal::UInt64
+end
+
 """
     WebGPUGPUResourceCache
 
-Cache of uploaded GPU meshes, keyed by EntityID.
+Cache of uploaded GPU meshes, particle emitters and decals, keyed by EntityID.
 """
 mutable struct WebGPUGPUResourceCache
     meshes::Dict{EntityID, WebGPUGPUMesh}
+    particle_emitters::Dict{EntityID, WebGPUParticleEmitter}
+    decals::Dict{EntityID, WebGPUDecal}
 end
 
-WebGPUGPUResourceCache() = WebGPUGPUResourceCache(Dict{EntityID, WebGPUGPUMesh}())
+WebGPUGPUResourceCache() = WebGPUGPUResourceCache(Dict{EntityID, WebGPUGPUMesh}(),
+                                                  Dict{EntityID, WebGPUParticleEmitter}(),
+                                                  Dict{EntityID, WebGPUDecal}())
 
 """
     WebGPUTextureCache
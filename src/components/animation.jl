@@ -14,9 +14,9 @@ A single animated property targeting one entity's transform.
 """
 struct AnimationChannel
     target_entity::EntityID
-    target_property::Symbol    # :position, :rotation, or :scale
+    target_property::Symbol    # :position, :rotation, :scale or :weights
     times::Vector{Float32}     # keyframe timestamps (sorted ascending)
-    values::Vector{Any}        # Vec3d for position/scale, Quaterniond for rotation
+    values::Vector{Any}        # Vec3d for position/scale, Quaterniond for rotation, Vector{Float32} for weights
     interpolation::InterpolationMode
 end
 
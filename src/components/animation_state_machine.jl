@@ -0,0 +1,111 @@
+# Animation state machine component: layered states, transitions and parameters
+
+"""
+    AnimParamKind
+
+Type of an animation state machine parameter.
+"""
+@enum AnimParamKind ANIM_PARAM_FLOAT ANIM_PARAM_BOOL ANIM_PARAM_TRIGGER
+
+"""
+    LayerBlendMode
+
+How a layer combines with the layers below it. `LAYER_OVERRIDE` replaces the
+underlying pose (scaled by layer weight), `LAYER_ADDITIVE` adds the layer's
+difference from its clip's first frame.
+"""
+@enum LayerBlendMode LAYER_OVERRIDE LAYER_ADDITIVE
+
+"""
+    ConditionOp
+
+Comparison used by a transition condition. `COND_TRUE`/`COND_FALSE` test bool
+parameters, `COND_TRIGGER` fires once and consumes the trigger.
+"""
+@enum ConditionOp COND_GREATER COND_LESS COND_EQUALS COND_NOT_EQUALS COND_TRUE COND_FALSE COND_TRIGGER
+
+struct AnimParameter
+    name::String
+    kind::AnimParamKind
+    default::Float32
+end
+
+struct AnimCondition
+    parameter::String
+    op::ConditionOp
+    threshold::Float32
+end
+
+AnimCondition(parameter::String, op::ConditionOp) = AnimCondition(parameter, op, 0.0f0)
+
+"""
+    AnimState
+
+A state plays one clip of the entity's `AnimationComponent` (1-based index).
+"""
+struct AnimState
+    name::String
+    clip::Int
+    speed::Float32
+    looping::Bool
+end
+
+AnimState(name::String, clip::Int; speed::Float32 = 1.0f0, looping::Bool = true) =
+    AnimState(name, clip, speed, looping)
+
+"""
+    AnimTransition
+
+Cross-fade from state `from` (or any state when `nothing`) to `to` once all
+conditions hold and, if `exit_time >= 0`, the source state has played that
+fraction of its clip.
+"""
+struct AnimTransition
+    from::Union{String, Nothing}
+    to::String
+    duration::Float32
+    exit_time::Float32
+    conditions::Vector{AnimCondition}
+end
+
+AnimTransition(from, to::String; duration::Float32 = 0.2f0, exit_time::Float32 = -1.0f0,
+               conditions::Vector{AnimCondition} = AnimCondition[]) =
+    AnimTransition(from, to, duration, exit_time, conditions)
+
+"""
+    AnimLayer
+
+One layer of a state machine. An empty `mask` affects every animated entity;
+otherwise only the listed (bone) entities are touched.
+"""
+struct AnimLayer
+    name::String
+    weight::Float32
+    blend_mode::LayerBlendMode
+    mask::Vector{EntityID}
+    states::Vector{AnimState}
+    default_state::String
+    transitions::Vector{AnimTransition}
+end
+
+AnimLayer(name::String, states::Vector{AnimState}, default_state::String;
+          weight::Float32 = 1.0f0, blend_mode::LayerBlendMode = LAYER_OVERRIDE,
+          mask::Vector{EntityID} = EntityID[],
+          transitions::Vector{AnimTransition} = AnimTransition[]) =
+    AnimLayer(name, weight, blend_mode, mask, states, default_state, transitions)
+
+"""
+    AnimationStateMachineComponent <: Component
+
+State machine asset driving the `AnimationComponent` on the same entity.
+Exported to ORSB and evaluated by the web runtime.
+"""
+struct AnimationStateMachineComponent <: Component
+    parameters::Vector{AnimParameter}
+    layers::Vector{AnimLayer}
+
+    AnimationStateMachineComponent(;
+        parameters::Vector{AnimParameter} = AnimParameter[],
+        layers::Vector{AnimLayer} = AnimLayer[]
+    ) = new(parameters, layers)
+end
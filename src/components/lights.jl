@@ -4,17 +4,21 @@
     PointLightComponent <: Component
 
 A point light that emits light in all directions.
+With `casts_shadow = true` the WebGPU backend renders an omnidirectional
+(cube) shadow for it into the shadow atlas.
 """
 struct PointLightComponent <: Component
     color::RGB{Float32}
     intensity::Float32
     range::Float32
+    casts_shadow::Bool
 
     PointLightComponent(;
         color::RGB{Float32} = RGB{Float32}(1.0, 1.0, 1.0),
         intensity::Float32 = 1.0f0,
-        range::Float32 = 10.0f0
-    ) = new(color, intensity, range)
+        range::Float32 = 10.0f0,
+        casts_shadow::Bool = false
+    ) = new(color, intensity, range, casts_shadow)
 end
 
 """
@@ -34,6 +38,72 @@ struct DirectionalLightComponent <: Component
     ) = new(color, intensity, direction)
 end
 
+"""
+    SpotLightComponent <: Component
+
+A cone-shaped light emitting along `direction` from the entity's position.
+`inner_angle` and `outer_angle` are cone half-angles in radians: full
+intensity inside the inner cone, fading to zero at the outer cone.
+"""
+struct SpotLightComponent <: Component
+    color::RGB{Float32}
+    intensity::Float32
+    range::Float32
+    direction::Vec3f
+    inner_angle::Float32
+    outer_angle::Float32
+    casts_shadow::Bool
+
+    SpotLightComponent(;
+        color::RGB{Float32} = RGB{Float32}(1.0, 1.0, 1.0),
+        intensity::Float32 = 1.0f0,
+        range::Float32 = 10.0f0,
+        direction::Vec3f = Vec3f(0, -1, 0),
+        inner_angle::Float32 = Float32(deg2rad(20)),
+        outer_angle::Float32 = Float32(deg2rad(30)),
+        casts_shadow::Bool = false
+    ) = new(color, intensity, range, direction, inner_angle, max(outer_angle, inner_angle), casts_shadow)
+end
+
+"""
+    AreaLightComponent <: Component
+
+A rectangular or tube-shaped light centered on the entity's position.
+
+- `shape = :rect`: a `width` x `height` panel spanned by `tangent` and
+  `direction × tangent`, emitting along `direction` only.
+  `color * intensity` is the panel's surface radiance.
+- `shape = :tube`: a `width`-long segment along `tangent` with thickness
+  `radius`, emitting in all directions. `color * intensity` is its total
+  intensity, as for a point light.
+"""
+struct AreaLightComponent <: Component
+    shape::Symbol
+    color::RGB{Float32}
+    intensity::Float32
+    range::Float32
+    direction::Vec3f
+    tangent::Vec3f
+    width::Float32
+    height::Float32
+    radius::Float32
+
+    function AreaLightComponent(;
+        shape::Symbol = :rect,
+        color::RGB{Float32} = RGB{Float32}(1.0, 1.0, 1.0),
+        intensity::Float32 = 1.0f0,
+        range::Float32 = 10.0f0,
+        direction::Vec3f = Vec3f(0, -1, 0),
+        tangent::Vec3f = Vec3f(1, 0, 0),
+        width::Float32 = 1.0f0,
+        height::Float32 = 1.0f0,
+        radius::Float32 = 0.0f0
+    )
+        shape in (:rect, :tube) || throw(ArgumentError("AreaLightComponent shape must be :rect or :tube, got :$shape"))
+        new(shape, color, intensity, range, direction, tangent, width, height, radius)
+    end
+end
+
 """
     IBLComponent <: Component
 
@@ -52,3 +122,109 @@ struct IBLComponent <: Component
         enabled::Bool = true
     ) = new(environment_path, intensity, enabled)
 end
+
+"""
+    AtmosphereComponent <: Component
+
+Physically based sky: single-scattered sunlight through a Rayleigh, Mie and
+ozone atmosphere, lit by the first directional light. It is drawn as the
+background and also provides diffuse and specular image-based lighting, so
+the scene needs no `IBLComponent`.
+Distances are in kilometers and coefficients are per kilometer. The defaults
+describe Earth. Only one atmosphere should be active in a scene at a time.
+"""
+struct AtmosphereComponent <: Component
+    rayleigh_scattering::Vec3f
+    rayleigh_scale_height::Float32
+    mie_scattering::Float32
+    mie_absorption::Float32
+    mie_scale_height::Float32
+    mie_anisotropy::Float32
+    ozone_absorption::Vec3f
+    planet_radius::Float32
+    atmosphere_height::Float32
+    ground_albedo::Float32
+    sun_angular_radius::Float32   # Radians; 0 hides the sun disk
+    intensity::Float32
+    enabled::Bool
+
+    function AtmosphereComponent(;
+        rayleigh_scattering::Vec3f = Vec3f(5.802f-3, 13.558f-3, 33.1f-3),
+        rayleigh_scale_height::Float32 = 8.0f0,
+        mie_scattering::Float32 = 3.996f-3,
+        mie_absorption::Float32 = 4.4f-3,
+        mie_scale_height::Float32 = 1.2f0,
+        mie_anisotropy::Float32 = 0.8f0,
+        ozone_absorption::Vec3f = Vec3f(0.650f-3, 1.881f-3, 0.085f-3),
+        planet_radius::Float32 = 6360.0f0,
+        atmosphere_height::Float32 = 100.0f0,
+        ground_albedo::Float32 = 0.3f0,
+        sun_angular_radius::Float32 = 0.01f0,
+        intensity::Float32 = 1.0f0,
+        enabled::Bool = true
+    )
+        all(>=(0), (rayleigh_scattering..., ozone_absorption..., mie_scattering, mie_absorption)) ||
+            throw(ArgumentError("AtmosphereComponent scattering and absorption must be >= 0"))
+        (rayleigh_scale_height > 0 && mie_scale_height > 0) ||
+            throw(ArgumentError("AtmosphereComponent scale heights must be > 0"))
+        (planet_radius > 0 && atmosphere_height > 0) ||
+            throw(ArgumentError("AtmosphereComponent planet_radius and atmosphere_height must be > 0"))
+        -1 < mie_anisotropy < 1 ||
+            throw(ArgumentError("AtmosphereComponent mie_anisotropy must be in (-1, 1), got $mie_anisotropy"))
+        0 <= ground_albedo <= 1 ||
+            throw(ArgumentError("AtmosphereComponent ground_albedo must be in [0, 1], got $ground_albedo"))
+        0 <= sun_angular_radius < 0.5 ||
+            throw(ArgumentError("AtmosphereComponent sun_angular_radius must be in [0, 0.5), got $sun_angular_radius"))
+        intensity >= 0 || throw(ArgumentError("AtmosphereComponent intensity must be >= 0, got $intensity"))
+        new(rayleigh_scattering, rayleigh_scale_height, mie_scattering, mie_absorption, mie_scale_height,
+            mie_anisotropy, ozone_absorption, planet_radius, atmosphere_height, ground_albedo,
+            sun_angular_radius, intensity, enabled)
+    end
+end
+
+"""
+    VolumetricFogComponent <: Component
+
+Froxel-based volumetric fog lit by the scene's directional, point and spot
+lights (with their shadows) and by the sky's ambient light.
+The extinction at height `y` is
+`density + height_density * exp(-height_falloff * (y - base_height))`, with
+the height term held constant below `base_height`. Surfaces farther than
+`max_distance` receive the fog accumulated up to it.
+Only one fog component should be active in a scene at a time.
+"""
+struct VolumetricFogComponent <: Component
+    albedo::RGB{Float32}
+    density::Float32
+    height_density::Float32
+    height_falloff::Float32
+    base_height::Float32
+    anisotropy::Float32       # Henyey-Greenstein g; positive scatters forward
+    max_distance::Float32
+    ambient_intensity::Float32
+    enabled::Bool
+
+    function VolumetricFogComponent(;
+        albedo::RGB{Float32} = RGB{Float32}(0.9, 0.9, 0.9),
+        density::Float32 = 0.01f0,
+        height_density::Float32 = 0.05f0,
+        height_falloff::Float32 = 0.2f0,
+        base_height::Float32 = 0.0f0,
+        anisotropy::Float32 = 0.6f0,
+        max_distance::Float32 = 100.0f0,
+        ambient_intensity::Float32 = 1.0f0,
+        enabled::Bool = true
+    )
+        all(c -> 0 <= c <= 1, (albedo.r, albedo.g, albedo.b)) ||
+            throw(ArgumentError("VolumetricFogComponent albedo must be in [0, 1]"))
+        (density >= 0 && height_density >= 0 && height_falloff >= 0) ||
+            throw(ArgumentError("VolumetricFogComponent density, height_density and height_falloff must be >= 0"))
+        -1 < anisotropy < 1 ||
+            throw(ArgumentError("VolumetricFogComponent anisotropy must be in (-1, 1), got $anisotropy"))
+        max_distance > 0 || throw(ArgumentError("VolumetricFogComponent max_distance must be > 0, got $max_distance"))
+        ambient_intensity >= 0 ||
+            throw(ArgumentError("VolumetricFogComponent ambient_intensity must be >= 0, got $ambient_intensity"))
+        new(albedo, density, height_density, height_falloff, base_height, anisotropy, max_distance,
+            ambient_intensity, enabled)
+    end
+end
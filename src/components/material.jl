@@ -72,3 +72,48 @@ struct MaterialComponent <: Component
             height_map, parallax_height_scale,
             subsurface, subsurface_color)
 end
+
+"""
+    DecalComponent <: Component
+
+Deferred decal (bullet holes, signage, dirt). The entity's transform places a
+unit box centered on the entity; the decal is projected along the box's local
+-Z axis onto whatever opaque surface lies inside it, blending into the
+surface's albedo, normal and roughness before lighting. The image's U runs
+along local +X and V down local +Y.
+
+`opacity` scales the albedo blend (times the albedo map's alpha),
+`normal_strength` the normal map's and `roughness_opacity` how much of the
+surface roughness is replaced by `roughness`. Surfaces turned more than
+`angle_fade` radians away from the box's +Z axis are not covered.
+Decals are drawn in creation order, so later decals cover earlier ones.
+"""
+struct DecalComponent <: Component
+    color::RGB{Float32}
+    opacity::Float32
+    albedo_map::Union{TextureRef, Nothing}
+    normal_map::Union{TextureRef, Nothing}
+    normal_strength::Float32
+    roughness::Float32
+    roughness_opacity::Float32
+    angle_fade::Float32
+
+    function DecalComponent(;
+        color::RGB{Float32} = RGB{Float32}(1.0, 1.0, 1.0),
+        opacity::Float32 = 1.0f0,
+        albedo_map::Union{TextureRef, Nothing} = nothing,
+        normal_map::Union{TextureRef, Nothing} = nothing,
+        normal_strength::Float32 = 1.0f0,
+        roughness::Float32 = 0.5f0,
+        roughness_opacity::Float32 = 0.0f0,
+        angle_fade::Float32 = Float32(π / 3)
+    )
+        all(>=(0), (color.r, color.g, color.b)) || throw(ArgumentError("DecalComponent color must be >= 0"))
+        0 <= opacity <= 1 || throw(ArgumentError("DecalComponent opacity must be in [0, 1], got $opacity"))
+        all(v -> 0 <= v <= 1, (normal_strength, roughness, roughness_opacity)) ||
+            throw(ArgumentError("DecalComponent normal_strength, roughness and roughness_opacity must be in [0, 1]"))
+        0 < angle_fade <= π / 2 ||
+            throw(ArgumentError("DecalComponent angle_fade must be in (0, π/2], got $angle_fade"))
+        new(color, opacity, albedo_map, normal_map, normal_strength, roughness, roughness_opacity, angle_fade)
+    end
+end
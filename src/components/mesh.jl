@@ -3,11 +3,24 @@
 # Type alias for bone indices (4 bone indices per vertex)
 const BoneIndices4 = NTuple{4, UInt16}
 
+"""
+    MorphTarget
+
+Per-vertex deltas for one blend shape. `normal_deltas` is empty when the
+target does not affect normals.
+"""
+struct MorphTarget
+    position_deltas::Vector{Vec3f}
+    normal_deltas::Vector{Vec3f}
+end
+
 """
     MeshComponent <: Component
 
 Represents a 3D mesh with vertices, indices, normals, UV coordinates,
-and optional skeletal animation data (bone weights and indices).
+optional skeletal animation data (bone weights and indices) and optional
+morph targets. `morph_weights` holds the current weight of each target and
+is updated in place by `:weights` animation channels.
 """
 struct MeshComponent <: Component
     vertices::Vector{Point3f}
@@ -16,6 +29,8 @@ struct MeshComponent <: Component
     uvs::Vector{Vec2f}
     bone_weights::Vector{Vec4f}
     bone_indices::Vector{BoneIndices4}
+    morph_targets::Vector{MorphTarget}
+    morph_weights::Vector{Float32}
 
     MeshComponent(;
         vertices::Vector{Point3f} = Point3f[],
@@ -23,6 +38,8 @@ struct MeshComponent <: Component
         normals::Vector{Vec3f} = Vec3f[],
         uvs::Vector{Vec2f} = Vec2f[],
         bone_weights::Vector{Vec4f} = Vec4f[],
-        bone_indices::Vector{BoneIndices4} = BoneIndices4[]
-    ) = new(vertices, indices, normals, uvs, bone_weights, bone_indices)
+        bone_indices::Vector{BoneIndices4} = BoneIndices4[],
+        morph_targets::Vector{MorphTarget} = MorphTarget[],
+        morph_weights::Vector{Float32} = zeros(Float32, length(morph_targets))
+    ) = new(vertices, indices, normals, uvs, bone_weights, bone_indices, morph_targets, morph_weights)
 end
@@ -0,0 +1,11 @@
+# Name component
+
+"""
+    NameComponent <: Component
+
+Human-readable entity name. Exported to ORSB so the web runtime can look
+entities up by name.
+"""
+struct NameComponent <: Component
+    name::String
+end
@@ -1,10 +1,43 @@
 # Particle system component
 
+"""
+    ParticleBlendMode
+
+How particle quads composite over the scene. Premultiplied expects colors
+(and sprite textures) already multiplied by alpha.
+"""
+@enum ParticleBlendMode PARTICLE_BLEND_ALPHA PARTICLE_BLEND_ADDITIVE PARTICLE_BLEND_PREMULTIPLIED
+
+"""
+    EmitterShape
+
+Volume particles spawn from. `EMIT_MESH` emits from the surface of the
+emitter entity's own `MeshComponent`.
+"""
+@enum EmitterShape EMIT_POINT EMIT_SPHERE EMIT_CONE EMIT_BOX EMIT_MESH
+
+"""
+    ParticleCollisionMode
+
+`PARTICLE_COLLIDE_GROUND` bounces off the horizontal plane `y = plane_height`.
+`PARTICLE_COLLIDE_DEPTH` bounces off visible geometry using the G-buffer depth
+and normals. Only the WebGPU backend, which simulates emitters in compute
+shaders, can evaluate it; CPU simulation (`update_particles!` and the web
+runtime) only knows the ground plane, so depth-colliding particles pass
+through geometry there.
+"""
+@enum ParticleCollisionMode PARTICLE_COLLIDE_NONE PARTICLE_COLLIDE_GROUND PARTICLE_COLLIDE_DEPTH
+
 """
     ParticleSystemComponent <: Component
 
 Configures a particle emitter. Attach to an entity with a TransformComponent
-to emit particles from that entity's world position.
+to emit particles from that entity's world transform.
+
+Over-lifetime curves are `time => value` keys with `time` in [0, 1] (0 = born,
+1 = dying), sampled piecewise-linearly. An empty curve falls back to the
+start/end lerp. The size curve multiplies each particle's start size; the
+velocity curve is added on top of the simulated velocity.
 """
 mutable struct ParticleSystemComponent <: Component
     # Emission
@@ -16,7 +49,8 @@ mutable struct ParticleSystemComponent <: Component
     lifetime_min::Float32
     lifetime_max::Float32
 
-    # Velocity (randomized per-component in this range, local space)
+    # Velocity (randomized per-component in this range, in the shape's local
+    # frame: +Y points along the emission direction)
     velocity_min::Vec3f
     velocity_max::Vec3f
 
@@ -36,7 +70,33 @@ mutable struct ParticleSystemComponent <: Component
     end_alpha::Float32
 
     # Blending
-    additive::Bool                  # additive vs alpha blend
+    blend_mode::ParticleBlendMode
+    soft_distance::Float32          # fade distance near opaque geometry (0 = hard edges)
+
+    # Emitter shape
+    shape::EmitterShape
+    shape_radius::Float32           # sphere / cone base radius
+    shape_angle::Float32            # cone half-angle (radians)
+    shape_half_extents::Vec3f       # box
+    shape_surface_only::Bool        # sphere: emit from the surface only
+
+    # Over-lifetime curves
+    color_curve::Vector{Pair{Float32, RGBA{Float32}}}
+    size_curve::Vector{Pair{Float32, Float32}}
+    velocity_curve::Vector{Pair{Float32, Vec3f}}
+
+    # Texture sheet (flipbook); tiles run left-to-right, top-to-bottom
+    texture::Union{TextureRef, Nothing}
+    sheet_tiles_x::Int
+    sheet_tiles_y::Int
+    sheet_frame_count::Int          # 0 = every tile
+    sheet_fps::Float32              # 0 = stretch the frames over the lifetime
+
+    # Collisions
+    collision::ParticleCollisionMode
+    plane_height::Float32
+    bounce::Float32                 # restitution along the surface normal
+    friction::Float32               # tangential velocity lost per bounce (0..1)
 
     # Internal state
     _emit_accumulator::Float32
@@ -60,6 +120,25 @@ mutable struct ParticleSystemComponent <: Component
         start_alpha::Float32 = 1.0f0,
         end_alpha::Float32 = 0.0f0,
         additive::Bool = false,
+        blend_mode::ParticleBlendMode = additive ? PARTICLE_BLEND_ADDITIVE : PARTICLE_BLEND_ALPHA,
+        soft_distance::Float32 = 0.0f0,
+        shape::EmitterShape = EMIT_POINT,
+        shape_radius::Float32 = 1.0f0,
+        shape_angle::Float32 = Float32(π / 6),
+        shape_half_extents::Vec3f = Vec3f(0.5f0, 0.5f0, 0.5f0),
+        shape_surface_only::Bool = false,
+        color_curve::Vector{Pair{Float32, RGBA{Float32}}} = Pair{Float32, RGBA{Float32}}[],
+        size_curve::Vector{Pair{Float32, Float32}} = Pair{Float32, Float32}[],
+        velocity_curve::Vector{Pair{Float32, Vec3f}} = Pair{Float32, Vec3f}[],
+        texture::Union{TextureRef, Nothing} = nothing,
+        sheet_tiles_x::Int = 1,
+        sheet_tiles_y::Int = 1,
+        sheet_frame_count::Int = 0,
+        sheet_fps::Float32 = 0.0f0,
+        collision::ParticleCollisionMode = PARTICLE_COLLIDE_NONE,
+        plane_height::Float32 = 0.0f0,
+        bounce::Float32 = 0.5f0,
+        friction::Float32 = 0.1f0,
         _emit_accumulator::Float32 = 0.0f0,
         _active::Bool = true
     )
@@ -69,7 +148,24 @@ mutable struct ParticleSystemComponent <: Component
             gravity_modifier, damping,
             start_size_min, start_size_max, end_size,
             start_color, end_color, start_alpha, end_alpha,
-            additive,
+            blend_mode, soft_distance,
+            shape, shape_radius, shape_angle, shape_half_extents, shape_surface_only,
+            color_curve, size_curve, velocity_curve,
+            texture, sheet_tiles_x, sheet_tiles_y, sheet_frame_count, sheet_fps,
+            collision, plane_height, bounce, friction,
             _emit_accumulator, _active)
     end
 end
+
+# `additive` predates `blend_mode` and is kept as a view onto it.
+function Base.getproperty(comp::ParticleSystemComponent, name::Symbol)
+    name === :additive && return getfield(comp, :blend_mode) == PARTICLE_BLEND_ADDITIVE
+    return getfield(comp, name)
+end
+
+function Base.setproperty!(comp::ParticleSystemComponent, name::Symbol, value)
+    if name === :additive
+        return setfield!(comp, :blend_mode, value ? PARTICLE_BLEND_ADDITIVE : PARTICLE_BLEND_ALPHA)
+    end
+    return setfield!(comp, name, convert(fieldtype(ParticleSystemComponent, name), value))
+end
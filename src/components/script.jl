@@ -30,3 +30,38 @@ mutable struct ScriptComponent <: Component
         on_destroy::Union{Function, Nothing} = nothing
     ) = new(on_start, on_update, on_destroy, false, 0, false)
 end
+
+"""
+    WebScriptComponent <: Component
+
+Game logic written in the small embedded script language run by the web
+runtime. Unlike `ScriptComponent` closures, the source text is exported to
+ORSB, so the behavior ships inside the bundle. `params` are bound as numeric
+globals of the entity's script instance.
+
+Scripts define `on_start()`, `on_update(dt)`, `on_destroy()`,
+`on_collision_enter(other)`, `on_collision_exit(other)` and
+`on_animation_finished(clip)` as needed; see `openreality-gpu-shared/src/script.rs`
+for the language and `openreality-web/src/scripting.rs` for the bindings.
+
+```julia
+WebScriptComponent(\"\"\"
+    let t = 0.0;
+    fn on_update(dt) {
+        t = t + dt;
+        let p = position(self);
+        p.y = base_y + 0.5 * sin(3.0 * t);
+        set_position(self, p);
+    }
+\"\"\"; name="bob", params=Dict("base_y" => 1.5))
+```
+"""
+struct WebScriptComponent <: Component
+    source::String
+    name::String
+    params::Dict{String, Float64}
+
+    WebScriptComponent(source::AbstractString; name::AbstractString = "script",
+                       params::AbstractDict = Dict{String, Float64}()) =
+        new(String(source), String(name), Dict{String, Float64}(String(k) => Float64(v) for (k, v) in params))
+end
@@ -3,7 +3,7 @@
 # Exports a Julia Scene to a binary .orsb file that the Rust WASM runtime can load.
 
 const ORSB_MAGIC = UInt8['O', 'R', 'S', 'B']
-const ORSB_VERSION = UInt32(1)
+const ORSB_VERSION = UInt32(3)
 
 # Section type IDs
 const SECTION_ENTITY_GRAPH = UInt32(1)
@@ -35,9 +35,11 @@ const CMASK_PARTICLE     = UInt64(1) << 10
 const CMASK_AUDIO_SRC    = UInt64(1) << 11
 const CMASK_AUDIO_LIST   = UInt64(1) << 12
 const CMASK_IBL          = UInt64(1) << 13
+const CMASK_SPOT_LIGHT   = UInt64(1) << 14
+const CMASK_AREA_LIGHT   = UInt64(1) << 15
 
 """
-    export_scene(scene::Scene, path::String; physics_config, compress_textures)
+    export_scene(scene::Scene, path::String; physics_config, compress_textures, prefabs)
 
 Export a Scene to the ORSB binary format for loading in the WASM web runtime.
 
@@ -46,12 +48,28 @@ Export a Scene to the ORSB binary format for loading in the WASM web runtime.
 - `path`: Output .orsb file path
 - `physics_config`: Physics world configuration to include
 - `compress_textures`: Whether to compress textures (PNG) in the output
+- `prefabs`: Named subtree roots to hold back as prefabs. Their subtrees are not
+  part of the loaded scene; the web runtime instantiates copies on demand.
 """
 function export_scene(scene::Scene, path::String;
                        physics_config::PhysicsWorldConfig = PhysicsWorldConfig(),
-                       compress_textures::Bool = true)
+                       compress_textures::Bool = true,
+                       prefabs::AbstractDict{String, EntityID} = Dict{String, EntityID}())
+    stats = open(io -> _write_orsb(io, scene, physics_config, compress_textures, prefabs), path, "w")
+    @info "Exported scene to $path ($(stats.num_entities) entities, $(stats.num_meshes) meshes, $(stats.num_textures) textures)"
+    return nothing
+end
 
-    entities = scene.entities
+"""
+    _write_orsb(io, scene, physics_config, compress_textures, prefabs)
+
+Write the ORSB bundle of `scene` to `io` (see `export_scene`). Returns the
+entity, mesh and texture counts and `sections`, the byte range of every
+section (`:header`, `:entity_graph`, ... `:decals`) within the bundle.
+"""
+function _write_orsb(io::IO, scene::Scene, physics_config::PhysicsWorldConfig,
+                     compress_textures::Bool, prefabs::AbstractDict{String, EntityID})
+    entities, prefab_ranges = _prefab_order(scene, prefabs)
     num_entities = length(entities)
 
     # Build entity index map (EntityID → array index)
@@ -85,6 +103,15 @@ function export_scene(scene::Scene, path::String;
                 push!(unique_meshes, mesh)
             end
         end
+        if has_component(eid, LODComponent)
+            for level in get_component(eid, LODComponent).levels
+                h = objectid(level.mesh)
+                if !haskey(mesh_index_map, h)
+                    mesh_index_map[h] = UInt32(length(unique_meshes))
+                    push!(unique_meshes, level.mesh)
+                end
+            end
+        end
         if has_component(eid, MaterialComponent)
             mat = get_component(eid, MaterialComponent)
             h = objectid(mat)
@@ -106,10 +133,33 @@ function export_scene(scene::Scene, path::String;
                 end
             end
         end
+        if has_component(eid, ParticleSystemComponent)
+            tex_ref = get_component(eid, ParticleSystemComponent).texture
+            if tex_ref !== nothing && tex_ref.path != "" && !haskey(texture_index_map, tex_ref.path)
+                texture_index_map[tex_ref.path] = Int32(length(unique_textures))
+                push!(unique_textures, tex_ref.path)
+            end
+        end
+        if has_component(eid, DecalComponent)
+            decal = get_component(eid, DecalComponent)
+            for tex_ref in (decal.albedo_map, decal.normal_map)
+                if tex_ref !== nothing && tex_ref.path != "" && !haskey(texture_index_map, tex_ref.path)
+                    texture_index_map[tex_ref.path] = Int32(length(unique_textures))
+                    push!(unique_textures, tex_ref.path)
+                end
+            end
+        end
     end
 
-    open(path, "w") do io
-        # Write header (32 bytes)
+    sections = Dict{Symbol, UnitRange{Int}}()
+    base = position(io)
+    function section(write_section, name)
+        start = position(io)
+        write_section()
+        sections[name] = (start - base + 1):(position(io) - base)
+    end
+
+    section(:header) do
         write(io, ORSB_MAGIC...)
         write(io, ORSB_VERSION)
         write(io, UInt32(0))  # flags
@@ -118,50 +168,114 @@ function export_scene(scene::Scene, path::String;
         write(io, UInt32(length(unique_textures)))
         write(io, UInt32(length(unique_materials)))
         write(io, UInt32(0))  # num_animations (populated below)
-
-        # ---- Entity Graph Section ----
+    end
+    section(:entity_graph) do
         _write_entity_graph(io, entities, entity_index, parent_map,
                             mesh_index_map, material_index_map)
-
-        # ---- Transforms Section ----
+    end
+    section(:transforms) do
         _write_transforms(io, entities)
-
-        # ---- Meshes Section ----
+    end
+    section(:meshes) do
         _write_meshes(io, unique_meshes)
-
-        # ---- Materials Section ----
+    end
+    section(:materials) do
         _write_materials(io, unique_materials, texture_index_map)
-
-        # ---- Textures Section ----
+    end
+    section(:textures) do
         _write_textures(io, unique_textures, compress_textures)
-
-        # ---- Lights Section ----
+    end
+    section(:lights) do
         _write_lights(io, entities)
-
-        # ---- Cameras Section ----
+    end
+    section(:cameras) do
         _write_cameras(io, entities)
-
-        # ---- Colliders Section ----
+    end
+    section(:colliders) do
         _write_colliders(io, entities)
-
-        # ---- RigidBodies Section ----
+    end
+    section(:rigidbodies) do
         _write_rigidbodies(io, entities)
-
-        # ---- Animations Section ----
+    end
+    section(:animations) do
         _write_animations(io, entities, entity_index)
-
-        # ---- Physics Config Section ----
+    end
+    section(:physics_config) do
         _write_physics_config(io, physics_config)
     end
+    section(:state_machines) do
+        _write_state_machines(io, entities, entity_index)
+    end
+    section(:particles) do
+        _write_particles(io, entities, mesh_index_map, texture_index_map)
+    end
+    section(:entity_names) do
+        _write_entity_names(io, entities)
+    end
+    section(:scripts) do
+        _write_scripts(io, entities)
+    end
+    section(:prefabs) do
+        _write_prefabs(io, prefab_ranges)
+    end
+    section(:lods) do
+        _write_lods(io, entities, mesh_index_map)
+    end
+    section(:environment) do
+        _write_environment(io, entities)
+    end
+    section(:decals) do
+        _write_decals(io, entities, texture_index_map)
+    end
 
-    @info "Exported scene to $path ($(num_entities) entities, $(length(unique_meshes)) meshes, $(length(unique_textures)) textures)"
-    return nothing
+    return (; sections, num_entities, num_meshes = length(unique_meshes), num_textures = length(unique_textures))
 end
 
 # ---- Internal serialization helpers ----
 
+# Scene entities with each prefab's subtree moved to the end as a contiguous,
+# parent-first range. Returns the ordered entities and (name, first index,
+# count) per prefab.
+function _prefab_order(scene::Scene, prefabs)
+    ranges = Tuple{String, Int, Int}[]
+    isempty(prefabs) && return scene.entities, ranges
+    held = Set{EntityID}()
+    subtrees = Pair{String, Vector{EntityID}}[]
+    for name in sort!(collect(keys(prefabs)))
+        root = prefabs[name]
+        root in scene.entities || throw(ArgumentError("Prefab root for '$name' is not in the scene"))
+        tree = EntityID[]
+        stack = [root]
+        while !isempty(stack)
+            eid = pop!(stack)
+            eid in held && throw(ArgumentError("Prefab '$name' overlaps another prefab"))
+            push!(held, eid)
+            push!(tree, eid)
+            append!(stack, reverse(get_children(scene, eid)))
+        end
+        push!(subtrees, name => tree)
+    end
+    ordered = [eid for eid in scene.entities if !(eid in held)]
+    for (name, tree) in subtrees
+        push!(ranges, (name, length(ordered), length(tree)))
+        append!(ordered, tree)
+    end
+    return ordered, ranges
+end
+
 function _write_entity_graph(io, entities, entity_index, parent_map,
                               mesh_index_map, material_index_map)
+    camera_index      = _component_ordinals(entities, CameraComponent)
+    point_light_index = _component_ordinals(entities, PointLightComponent)
+    dir_light_index   = _component_ordinals(entities, DirectionalLightComponent)
+    spot_light_index  = _component_ordinals(entities, SpotLightComponent)
+    area_light_index  = _component_ordinals(entities, AreaLightComponent)
+    collider_index    = _component_ordinals(entities, ColliderComponent)
+    rigidbody_index   = _component_ordinals(entities, RigidBodyComponent)
+    animation_index   = _component_ordinals(entities, AnimationComponent)
+    skeleton_index    = _component_ordinals(entities, SkinnedMeshComponent)
+    particle_index    = _component_ordinals(entities, ParticleSystemComponent)
+
     for eid in entities
 
         # TODO: do not use internals of Ark
@@ -179,6 +293,8 @@ function _write_entity_graph(io, entities, entity_index, parent_map,
         has_component(eid, CameraComponent)         && (mask |= CMASK_CAMERA)
         has_component(eid, PointLightComponent)     && (mask |= CMASK_POINT_LIGHT)
         has_component(eid, DirectionalLightComponent) && (mask |= CMASK_DIR_LIGHT)
+        has_component(eid, SpotLightComponent)      && (mask |= CMASK_SPOT_LIGHT)
+        has_component(eid, AreaLightComponent)      && (mask |= CMASK_AREA_LIGHT)
         has_component(eid, ColliderComponent)       && (mask |= CMASK_COLLIDER)
         has_component(eid, RigidBodyComponent)      && (mask |= CMASK_RIGIDBODY)
         has_component(eid, AnimationComponent)      && (mask |= CMASK_ANIMATION)
@@ -204,7 +320,30 @@ function _write_entity_graph(io, entities, entity_index, parent_map,
         else
             write(io, NO_IDX)
         end
+
+        # Per-component section indices (position within the matching section)
+        write(io, get(camera_index, eid, NO_IDX))
+        light_idx = get(point_light_index, eid, get(dir_light_index, eid,
+                        get(spot_light_index, eid, get(area_light_index, eid, NO_IDX))))
+        write(io, light_idx)
+        write(io, get(collider_index, eid, NO_IDX))
+        write(io, get(rigidbody_index, eid, NO_IDX))
+        write(io, get(animation_index, eid, NO_IDX))
+        write(io, get(skeleton_index, eid, NO_IDX))
+        write(io, get(particle_index, eid, NO_IDX))
+    end
+end
+
+# Map each entity owning component `T` to its position among all such entities.
+# Sections like lights/cameras/colliders are written in this same order.
+function _component_ordinals(entities, ::Type{T}) where T
+    ordinals = Dict{EntityID, UInt32}()
+    for eid in entities
+        if has_component(eid, T)
+            ordinals[eid] = UInt32(length(ordinals))
+        end
     end
+    return ordinals
 end
 
 function _write_transforms(io, entities)
@@ -235,7 +374,7 @@ function _write_meshes(io, meshes)
         write(io, UInt32(nv))
         write(io, UInt32(ni))
         write(io, UInt32(has_bones ? 1 : 0))
-        write(io, UInt32(0))  # padding
+        write(io, UInt32(length(mesh.morph_targets)))
 
         # Positions
         for v in mesh.vertices
@@ -266,6 +405,21 @@ function _write_meshes(io, meshes)
                 write(io, UInt16(bi[1]), UInt16(bi[2]), UInt16(bi[3]), UInt16(bi[4]))
             end
         end
+
+        # Morph targets: has_normals flag, position deltas, normal deltas
+        for target in mesh.morph_targets
+            has_normals = length(target.normal_deltas) == nv
+            write(io, UInt8(has_normals ? 1 : 0))
+            for i in 1:nv
+                d = i <= length(target.position_deltas) ? target.position_deltas[i] : Vec3f(0, 0, 0)
+                write(io, Float32(d[1]), Float32(d[2]), Float32(d[3]))
+            end
+            if has_normals
+                for d in target.normal_deltas
+                    write(io, Float32(d[1]), Float32(d[2]), Float32(d[3]))
+                end
+            end
+        end
     end
 end
 
@@ -327,9 +481,13 @@ function _write_lights(io, entities)
     # Point lights
     point_lights = EntityID[]
     dir_lights = EntityID[]
+    spot_lights = EntityID[]
+    area_lights = EntityID[]
     for eid in entities
         has_component(eid, PointLightComponent) && push!(point_lights, eid)
         has_component(eid, DirectionalLightComponent) && push!(dir_lights, eid)
+        has_component(eid, SpotLightComponent) && push!(spot_lights, eid)
+        has_component(eid, AreaLightComponent) && push!(area_lights, eid)
     end
 
     write(io, UInt32(length(point_lights)))
@@ -350,6 +508,33 @@ function _write_lights(io, entities)
         write(io, Float32(light.intensity))
         write(io, Float32(0))  # padding
     end
+
+    # Spot lights (version 3+)
+    write(io, UInt32(length(spot_lights)))
+    for eid in spot_lights
+        light = get_component(eid, SpotLightComponent)
+        pos = has_component(eid, TransformComponent) ? get_component(eid, TransformComponent).position[] : (0.0, 0.0, 0.0)
+        write(io, Float32(pos[1]), Float32(pos[2]), Float32(pos[3]))
+        write(io, Float32(light.direction[1]), Float32(light.direction[2]), Float32(light.direction[3]))
+        write(io, Float32(light.color.r), Float32(light.color.g), Float32(light.color.b))
+        write(io, Float32(light.intensity), Float32(light.range))
+        write(io, Float32(light.inner_angle), Float32(light.outer_angle))
+        write(io, UInt8(light.casts_shadow ? 1 : 0), UInt8(0), UInt8(0), UInt8(0))  # casts_shadow + padding
+    end
+
+    # Area lights (version 3+)
+    write(io, UInt32(length(area_lights)))
+    for eid in area_lights
+        light = get_component(eid, AreaLightComponent)
+        pos = has_component(eid, TransformComponent) ? get_component(eid, TransformComponent).position[] : (0.0, 0.0, 0.0)
+        write(io, UInt8(light.shape == :tube ? 1 : 0), UInt8(0), UInt8(0), UInt8(0))  # shape + padding
+        write(io, Float32(pos[1]), Float32(pos[2]), Float32(pos[3]))
+        write(io, Float32(light.direction[1]), Float32(light.direction[2]), Float32(light.direction[3]))
+        write(io, Float32(light.tangent[1]), Float32(light.tangent[2]), Float32(light.tangent[3]))
+        write(io, Float32(light.color.r), Float32(light.color.g), Float32(light.color.b))
+        write(io, Float32(light.intensity), Float32(light.range))
+        write(io, Float32(light.width), Float32(light.height), Float32(light.radius))
+    end
 end
 
 function _write_cameras(io, entities)
@@ -362,6 +547,7 @@ function _write_cameras(io, entities)
     for eid in cameras
         cam = get_component(eid, CameraComponent)
         write(io, Float32(cam.fov), Float32(cam.near), Float32(cam.far), Float32(cam.aspect))
+        write(io, UInt8(cam.active ? 1 : 0), UInt8(0), UInt8(0), UInt8(0))  # active + padding
     end
 end
 
@@ -440,12 +626,13 @@ function _write_animations(io, entities, entity_index)
 
             for channel in clip.channels
                 # Target entity index
-                target_idx = haskey(entity_index, channel.target) ? entity_index[channel.target] : typemax(UInt32)
+                target_idx = get(entity_index, channel.target_entity, typemax(UInt32))
                 write(io, target_idx)
 
                 # Target property
-                prop = channel.property == :position ? UInt8(0) :
-                       channel.property == :rotation ? UInt8(1) : UInt8(2)
+                prop = channel.target_property == :position ? UInt8(0) :
+                       channel.target_property == :rotation ? UInt8(1) :
+                       channel.target_property == :weights ? UInt8(3) : UInt8(2)
                 write(io, prop)
 
                 # Interpolation mode
@@ -455,13 +642,20 @@ function _write_animations(io, entities, entity_index)
 
                 # Keyframes
                 write(io, UInt32(length(channel.times)))
+                if channel.target_property == :weights
+                    write(io, UInt32(isempty(channel.values) ? 0 : length(first(channel.values))))
+                end
                 for t in channel.times
                     write(io, Float32(t))
                 end
                 for v in channel.values
-                    if channel.property == :rotation
+                    if channel.target_property == :rotation
                         # Quaternion: w, x, y, z
                         write(io, Float64(v.s), Float64(v.v1), Float64(v.v2), Float64(v.v3))
+                    elseif channel.target_property == :weights
+                        for w in v
+                            write(io, Float64(w))
+                        end
                     else
                         # Vec3
                         write(io, Float64(v[1]), Float64(v[2]), Float64(v[3]))
@@ -470,7 +664,7 @@ function _write_animations(io, entities, entity_index)
             end
         end
 
-        write(io, Int32(anim.active_clip))
+        write(io, Int32(anim.active_clip - 1))  # 1-based in Julia, -1 = none
         write(io, UInt8(anim.playing ? 1 : 0))
         write(io, UInt8(anim.looping ? 1 : 0))
         write(io, Float32(anim.speed))
@@ -485,3 +679,240 @@ function _write_physics_config(io, config)
     write(io, Float32(config.position_correction))
     write(io, Float32(config.slop))
 end
+
+function _write_name(io, name::AbstractString)
+    bytes = Vector{UInt8}(name)
+    write(io, UInt16(length(bytes)))
+    write(io, bytes...)
+end
+
+function _write_state_machines(io, entities, entity_index)
+    animation_index = _component_ordinals(entities, AnimationComponent)
+    machines = EntityID[]
+    for eid in entities
+        has_component(eid, AnimationStateMachineComponent) && haskey(animation_index, eid) &&
+            push!(machines, eid)
+    end
+
+    write(io, UInt32(length(machines)))
+    for eid in machines
+        sm = get_component(eid, AnimationStateMachineComponent)
+        write(io, UInt32(animation_index[eid]))
+
+        param_index = Dict(p.name => i - 1 for (i, p) in enumerate(sm.parameters))
+        write(io, UInt16(length(sm.parameters)))
+        for p in sm.parameters
+            _write_name(io, p.name)
+            write(io, UInt8(Int(p.kind)))
+            write(io, Float32(p.default))
+        end
+
+        write(io, UInt16(length(sm.layers)))
+        for layer in sm.layers
+            _write_name(io, layer.name)
+            write(io, Float32(layer.weight))
+            write(io, UInt8(Int(layer.blend_mode)))
+
+            mask = [entity_index[m] for m in layer.mask if haskey(entity_index, m)]
+            write(io, UInt32(length(mask)))
+            for m in mask
+                write(io, UInt32(m))
+            end
+
+            state_index = Dict(s.name => i - 1 for (i, s) in enumerate(layer.states))
+            write(io, UInt16(length(layer.states)))
+            for st in layer.states
+                _write_name(io, st.name)
+                write(io, UInt32(st.clip - 1))
+                write(io, Float32(st.speed))
+                write(io, UInt8(st.looping ? 1 : 0))
+            end
+            write(io, UInt16(get(state_index, layer.default_state, 0)))
+
+            # Transitions naming unknown states are dropped; a `nothing` source
+            # is the any-state sentinel
+            transitions = [t for t in layer.transitions
+                           if haskey(state_index, t.to) && (t.from === nothing || haskey(state_index, t.from))]
+            write(io, UInt16(length(transitions)))
+            for t in transitions
+                from = t.from === nothing ? typemax(UInt16) : UInt16(state_index[t.from])
+                write(io, from)
+                write(io, UInt16(state_index[t.to]))
+                write(io, Float32(t.duration))
+                write(io, Float32(t.exit_time))
+
+                conditions = [c for c in t.conditions if haskey(param_index, c.parameter)]
+                write(io, UInt16(length(conditions)))
+                for c in conditions
+                    write(io, UInt16(param_index[c.parameter]))
+                    write(io, UInt8(Int(c.op)))
+                    write(io, Float32(c.threshold))
+                end
+            end
+        end
+    end
+end
+
+function _write_vec3f(io, v)
+    write(io, Float32(v[1]), Float32(v[2]), Float32(v[3]))
+end
+
+# One record per ParticleSystemComponent, in `_component_ordinals` order
+function _write_particles(io, entities, mesh_index_map, texture_index_map)
+    emitters = [eid for eid in entities if has_component(eid, ParticleSystemComponent)]
+    write(io, UInt32(length(emitters)))
+    for eid in emitters
+        comp = get_component(eid, ParticleSystemComponent)
+
+        write(io, UInt32(comp.max_particles))
+        write(io, Float32(comp.emission_rate))
+        write(io, UInt32(comp.burst_count))
+        write(io, Float32(comp.lifetime_min), Float32(comp.lifetime_max))
+        _write_vec3f(io, comp.velocity_min)
+        _write_vec3f(io, comp.velocity_max)
+        write(io, Float32(comp.gravity_modifier), Float32(comp.damping))
+        write(io, Float32(comp.start_size_min), Float32(comp.start_size_max), Float32(comp.end_size))
+        c0, c1 = comp.start_color, comp.end_color
+        write(io, Float32(c0.r), Float32(c0.g), Float32(c0.b), Float32(comp.start_alpha))
+        write(io, Float32(c1.r), Float32(c1.g), Float32(c1.b), Float32(comp.end_alpha))
+
+        # Blend, shape kind, collision, shape flags (bit 0 = sphere surface only)
+        write(io, UInt8(Int(comp.blend_mode)), UInt8(Int(comp.shape)),
+              UInt8(Int(comp.collision)), UInt8(comp.shape_surface_only ? 1 : 0))
+        shape_params = if comp.shape == EMIT_SPHERE
+            (comp.shape_radius, 0.0f0, 0.0f0)
+        elseif comp.shape == EMIT_CONE
+            (comp.shape_angle, comp.shape_radius, 0.0f0)
+        elseif comp.shape == EMIT_BOX
+            Tuple(comp.shape_half_extents)
+        else
+            (0.0f0, 0.0f0, 0.0f0)
+        end
+        write(io, Float32.(shape_params)..., 0.0f0)
+        mesh = comp.shape == EMIT_MESH ? get_component(eid, MeshComponent) : nothing
+        write(io, mesh === nothing ? typemax(UInt32) : mesh_index_map[objectid(mesh)])
+
+        write(io, UInt16(length(comp.color_curve)))
+        for (t, c) in comp.color_curve
+            write(io, Float32(t), Float32(c.r), Float32(c.g), Float32(c.b), Float32(c.alpha))
+        end
+        write(io, UInt16(length(comp.size_curve)))
+        for (t, v) in comp.size_curve
+            write(io, Float32(t), Float32(v))
+        end
+        write(io, UInt16(length(comp.velocity_curve)))
+        for (t, v) in comp.velocity_curve
+            write(io, Float32(t))
+            _write_vec3f(io, v)
+        end
+
+        # Texture sheet: texture index (-1 = none), tiles, frame count, fps
+        tex = comp.texture
+        write(io, tex === nothing ? Int32(-1) : get(texture_index_map, tex.path, Int32(-1)))
+        tiles_x, tiles_y = max(comp.sheet_tiles_x, 1), max(comp.sheet_tiles_y, 1)
+        frames = comp.sheet_frame_count > 0 ? comp.sheet_frame_count : tiles_x * tiles_y
+        write(io, UInt16(tiles_x), UInt16(tiles_y), UInt16(frames), UInt16(0))
+        write(io, Float32(comp.sheet_fps))
+
+        write(io, Float32(comp.soft_distance), Float32(comp.plane_height),
+              Float32(comp.bounce), Float32(comp.friction))
+    end
+end
+
+# (entity index, name) for every entity with a NameComponent
+function _write_entity_names(io, entities)
+    named = [(i, eid) for (i, eid) in enumerate(entities) if has_component(eid, NameComponent)]
+    write(io, UInt32(length(named)))
+    for (i, eid) in named
+        write(io, UInt32(i - 1))
+        _write_name(io, get_component(eid, NameComponent).name)
+    end
+end
+
+# Deduplicated script sources, then (entity index, script index, params) per
+# entity with a WebScriptComponent
+function _write_scripts(io, entities)
+    scripted = [(i, get_component(eid, WebScriptComponent)) for (i, eid) in enumerate(entities)
+                if has_component(eid, WebScriptComponent)]
+    sources = unique((comp.name, comp.source) for (_, comp) in scripted)
+    write(io, UInt32(length(sources)))
+    for (name, source) in sources
+        _write_name(io, name)
+        bytes = Vector{UInt8}(source)
+        write(io, UInt32(length(bytes)))
+        write(io, bytes)
+    end
+    write(io, UInt32(length(scripted)))
+    for (i, comp) in scripted
+        write(io, UInt32(i - 1))
+        write(io, UInt32(findfirst(==((comp.name, comp.source)), sources) - 1))
+        write(io, UInt16(length(comp.params)))
+        for (key, value) in sort!(collect(comp.params))
+            _write_name(io, key)
+            write(io, Float64(value))
+        end
+    end
+end
+
+# (name, first entity index, entity count) per prefab
+function _write_prefabs(io, prefab_ranges)
+    write(io, UInt32(length(prefab_ranges)))
+    for (name, first, count) in prefab_ranges
+        _write_name(io, name)
+        write(io, UInt32(first), UInt32(count))
+    end
+end
+
+# (entity index, hysteresis, levels of (mesh index, max distance)) per entity
+# with a LODComponent; crossfade settings are desktop-only
+function _write_lods(io, entities, mesh_index_map)
+    lods = [(i, get_component(eid, LODComponent)) for (i, eid) in enumerate(entities)
+            if has_component(eid, LODComponent)]
+    write(io, UInt32(length(lods)))
+    for (i, lod) in lods
+        write(io, UInt32(i - 1), Float32(lod.hysteresis), UInt16(length(lod.levels)))
+        for level in lod.levels
+            write(io, mesh_index_map[objectid(level.mesh)], Float32(level.max_distance))
+        end
+    end
+end
+
+# Flags (bit 0 atmosphere, bit 1 fog), then the first AtmosphereComponent and
+# VolumetricFogComponent, each laid out as the renderer's settings struct
+function _write_environment(io, entities)
+    atmosphere_eid = findfirst(eid -> has_component(eid, AtmosphereComponent), entities)
+    fog_eid = findfirst(eid -> has_component(eid, VolumetricFogComponent), entities)
+    write(io, UInt32((atmosphere_eid !== nothing) | (fog_eid !== nothing) << 1))
+    if atmosphere_eid !== nothing
+        a = get_component(entities[atmosphere_eid], AtmosphereComponent)
+        _write_vec3f(io, a.rayleigh_scattering)
+        write(io, Float32(a.rayleigh_scale_height), Float32(a.mie_scattering), Float32(a.mie_absorption),
+              Float32(a.mie_scale_height), Float32(a.mie_anisotropy))
+        _write_vec3f(io, a.ozone_absorption)
+        write(io, Float32(a.planet_radius), Float32(a.atmosphere_height), Float32(a.ground_albedo),
+              Float32(a.sun_angular_radius), Float32(a.intensity), UInt32(a.enabled))
+    end
+    if fog_eid !== nothing
+        f = get_component(entities[fog_eid], VolumetricFogComponent)
+        write(io, Float32(f.albedo.r), Float32(f.albedo.g), Float32(f.albedo.b),
+              Float32(f.density), Float32(f.height_density), Float32(f.height_falloff),
+              Float32(f.base_height), Float32(f.anisotropy), Float32(f.max_distance),
+              Float32(f.ambient_intensity), UInt32(f.enabled))
+    end
+end
+
+# (entity index, albedo and normal texture indices (-1 = none), color and
+# opacity, normal strength, roughness, roughness opacity, angle fade) per
+# entity with a DecalComponent; the entity transform places the decal box
+function _write_decals(io, entities, texture_index_map)
+    decals = [(i, get_component(eid, DecalComponent)) for (i, eid) in enumerate(entities)
+              if has_component(eid, DecalComponent)]
+    write(io, UInt32(length(decals)))
+    texture_index(tex) = tex === nothing ? Int32(-1) : get(texture_index_map, tex.path, Int32(-1))
+    for (i, d) in decals
+        write(io, UInt32(i - 1), texture_index(d.albedo_map), texture_index(d.normal_map))
+        write(io, Float32(d.color.r), Float32(d.color.g), Float32(d.color.b), Float32(d.opacity),
+              Float32(d.normal_strength), Float32(d.roughness), Float32(d.roughness_opacity),
+              Float32(d.angle_fade))
+    end
+end
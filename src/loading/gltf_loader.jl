@@ -524,8 +524,29 @@ function _extract_gltf_mesh(gltf::GLTFLib.Object, prim::GLTFLib.Primitive, buffe
         normals = _compute_averaged_normals(positions, indices)
     end
 
+    # Morph targets — POSITION/NORMAL deltas per target
+    morph_targets = MorphTarget[]
+    if hasproperty(prim, :targets) && prim.targets !== nothing
+        for target in prim.targets
+            pos_deltas = zeros(Vec3f, length(positions))
+            if haskey(target, "POSITION")
+                d = _read_accessor_data(gltf, target["POSITION"], buffers_data)
+                for (k, i) in enumerate(1:3:min(length(d), 3 * length(positions)))
+                    pos_deltas[k] = Vec3f(d[i], d[i+1], d[i+2])
+                end
+            end
+            norm_deltas = Vec3f[]
+            if haskey(target, "NORMAL")
+                d = _read_accessor_data(gltf, target["NORMAL"], buffers_data)
+                norm_deltas = [Vec3f(d[i], d[i+1], d[i+2]) for i in 1:3:(length(d) - 2)]
+            end
+            push!(morph_targets, MorphTarget(pos_deltas, norm_deltas))
+        end
+    end
+
     return MeshComponent(vertices=positions, indices=indices, normals=normals, uvs=uvs,
-                         bone_weights=bone_weights, bone_indices=bone_indices)
+                         bone_weights=bone_weights, bone_indices=bone_indices,
+                         morph_targets=morph_targets)
 end
 
 # ---- Material extraction ----
@@ -675,6 +696,7 @@ const GLTF_PATH_MAP = Dict(
     "translation" => :position,
     "rotation" => :rotation,
     "scale" => :scale,
+    "weights" => :weights,
 )
 
 const GLTF_INTERP_MAP = Dict(
@@ -725,6 +747,14 @@ function _extract_gltf_animations(gltf::GLTFLib.Object, buffers_data::Vector{Vec
                     i + 2 > length(values_raw) && break
                     push!(values, Vec3d(Float64(values_raw[i]), Float64(values_raw[i+1]), Float64(values_raw[i+2])))
                 end
+            elseif target_prop == :weights
+                # One weight per morph target; cubic splines store 3 entries per key
+                per_key = interp == INTERP_CUBICSPLINE ? 3 : 1
+                n = isempty(times) ? 0 : length(values_raw) ÷ (length(times) * per_key)
+                n == 0 && continue
+                for i in 1:n:(length(values_raw) - n + 1)
+                    push!(values, Float32.(values_raw[i:i+n-1]))
+                end
             elseif target_prop == :rotation
                 for i in 1:4:length(values_raw)
                     i + 3 > length(values_raw) && break
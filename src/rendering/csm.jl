@@ -112,3 +112,56 @@ function compute_cascade_light_matrix(view::Mat4f, proj::Mat4f, near::Float32, f
 
     return light_proj * light_view
 end
+
+"""
+    ShadowFilterMode
+
+Filter of directional light (cascaded) shadows:
+- `SHADOW_FILTER_HARD`: one depth comparison
+- `SHADOW_FILTER_PCF`: rotated Poisson disk of `pcf_samples` comparisons
+- `SHADOW_FILTER_PCSS`: PCF widened with the blocker distance (contact-hardening)
+- `SHADOW_FILTER_EVSM`: exponential variance shadow maps
+"""
+@enum ShadowFilterMode SHADOW_FILTER_HARD SHADOW_FILTER_PCF SHADOW_FILTER_PCSS SHADOW_FILTER_EVSM
+
+"""
+    ShadowConfig
+
+User-facing configuration for cascaded shadow filtering. Biases and `pcf_radius`
+are in shadow map texels; `blend_band` is the fraction of each cascade
+cross-faded into the next; `light_size` scales the PCSS penumbra with the
+blocker-receiver distance. `stabilize` snaps cascades to whole texels so
+shadows do not shimmer as the camera moves.
+"""
+mutable struct ShadowConfig
+    filter::ShadowFilterMode
+    pcf_samples::Int
+    pcf_radius::Float32
+    light_size::Float32
+    blend_band::Float32
+    depth_bias::Float32
+    normal_bias::Float32
+    slope_bias::Float32
+    evsm_exponents::NTuple{2, Float32}
+    light_bleed_reduction::Float32
+    stabilize::Bool
+
+    function ShadowConfig(;
+        filter::ShadowFilterMode = SHADOW_FILTER_PCF,
+        pcf_samples::Int = 16,
+        pcf_radius::Float32 = 1.5f0,
+        light_size::Float32 = 0.02f0,
+        blend_band::Float32 = 0.1f0,
+        depth_bias::Float32 = 1.0f0,
+        normal_bias::Float32 = 1.0f0,
+        slope_bias::Float32 = 1.0f0,
+        evsm_exponents::NTuple{2, Float32} = (5.0f0, 5.0f0),
+        light_bleed_reduction::Float32 = 0.2f0,
+        stabilize::Bool = true
+    )
+        1 <= pcf_samples <= 32 || throw(ArgumentError("pcf_samples must be in 1:32, got $pcf_samples"))
+        0.0f0 <= blend_band <= 1.0f0 || throw(ArgumentError("blend_band must be in [0, 1], got $blend_band"))
+        new(filter, pcf_samples, pcf_radius, light_size, blend_band, depth_bias, normal_bias,
+            slope_bias, evsm_exponents, light_bleed_reduction, stabilize)
+    end
+end
@@ -12,12 +12,33 @@ struct FrameLightData
     point_colors::Vector{RGB{Float32}}
     point_intensities::Vector{Float32}
     point_ranges::Vector{Float32}
+    point_casts_shadow::Vector{Bool}
 
     # Directional lights
     dir_directions::Vector{Vec3f}
     dir_colors::Vector{RGB{Float32}}
     dir_intensities::Vector{Float32}
 
+    # Spot lights (angles are cone half-angles in radians)
+    spot_positions::Vector{Vec3f}
+    spot_directions::Vector{Vec3f}
+    spot_colors::Vector{RGB{Float32}}
+    spot_intensities::Vector{Float32}
+    spot_ranges::Vector{Float32}
+    spot_inner_angles::Vector{Float32}
+    spot_outer_angles::Vector{Float32}
+    spot_casts_shadow::Vector{Bool}
+
+    # Area lights: center and world-space half-extent axes (axis_y is zero for tubes)
+    area_shapes::Vector{Symbol}
+    area_positions::Vector{Vec3f}
+    area_axes_x::Vector{Vec3f}
+    area_axes_y::Vector{Vec3f}
+    area_colors::Vector{RGB{Float32}}
+    area_intensities::Vector{Float32}
+    area_ranges::Vector{Float32}
+    area_radii::Vector{Float32}
+
     # IBL
     has_ibl::Bool
     ibl_path::String
@@ -84,13 +105,14 @@ end
 Query all light components from the ECS and return structured data.
 """
 function collect_lights()
-    # Point lights
+    # Point lights (uncapped; backends with fixed-size light arrays cap when packing)
     point_entities = entities_with_component(PointLightComponent)
-    num_point = min(length(point_entities), 16)
+    num_point = length(point_entities)
     point_positions = Vec3f[]
     point_colors = RGB{Float32}[]
     point_intensities = Float32[]
     point_ranges = Float32[]
+    point_casts_shadow = Bool[]
 
     for i in 1:num_point
         eid = point_entities[i]
@@ -101,6 +123,7 @@ function collect_lights()
         push!(point_colors, light.color)
         push!(point_intensities, light.intensity)
         push!(point_ranges, light.range)
+        push!(point_casts_shadow, light.casts_shadow)
     end
 
     # Directional lights
@@ -118,6 +141,53 @@ function collect_lights()
         push!(dir_intensities, light.intensity)
     end
 
+    # Spot lights
+    spot_positions = Vec3f[]
+    spot_directions = Vec3f[]
+    spot_colors = RGB{Float32}[]
+    spot_intensities = Float32[]
+    spot_ranges = Float32[]
+    spot_inner_angles = Float32[]
+    spot_outer_angles = Float32[]
+    spot_casts_shadow = Bool[]
+
+    for eid in entities_with_component(SpotLightComponent)
+        light = get_component(eid, SpotLightComponent)
+        world = get_world_transform(eid)
+        push!(spot_positions, Vec3f(Float32(world[1, 4]), Float32(world[2, 4]), Float32(world[3, 4])))
+        push!(spot_directions, normalize(light.direction))
+        push!(spot_colors, light.color)
+        push!(spot_intensities, light.intensity)
+        push!(spot_ranges, light.range)
+        push!(spot_inner_angles, light.inner_angle)
+        push!(spot_outer_angles, light.outer_angle)
+        push!(spot_casts_shadow, light.casts_shadow)
+    end
+
+    # Area lights
+    area_shapes = Symbol[]
+    area_positions = Vec3f[]
+    area_axes_x = Vec3f[]
+    area_axes_y = Vec3f[]
+    area_colors = RGB{Float32}[]
+    area_intensities = Float32[]
+    area_ranges = Float32[]
+    area_radii = Float32[]
+
+    for eid in entities_with_component(AreaLightComponent)
+        light = get_component(eid, AreaLightComponent)
+        world = get_world_transform(eid)
+        axis_x, axis_y = area_light_axes(light)
+        push!(area_shapes, light.shape)
+        push!(area_positions, Vec3f(Float32(world[1, 4]), Float32(world[2, 4]), Float32(world[3, 4])))
+        push!(area_axes_x, axis_x)
+        push!(area_axes_y, axis_y)
+        push!(area_colors, light.color)
+        push!(area_intensities, light.intensity)
+        push!(area_ranges, light.range)
+        push!(area_radii, light.radius)
+    end
+
     # IBL
     has_ibl = false
     ibl_path = ""
@@ -133,12 +203,33 @@ function collect_lights()
     end
 
     return FrameLightData(
-        point_positions, point_colors, point_intensities, point_ranges,
+        point_positions, point_colors, point_intensities, point_ranges, point_casts_shadow,
         dir_directions, dir_colors, dir_intensities,
+        spot_positions, spot_directions, spot_colors, spot_intensities,
+        spot_ranges, spot_inner_angles, spot_outer_angles, spot_casts_shadow,
+        area_shapes, area_positions, area_axes_x, area_axes_y,
+        area_colors, area_intensities, area_ranges, area_radii,
         has_ibl, ibl_path, ibl_intensity
     )
 end
 
+"""
+    area_light_axes(light::AreaLightComponent) -> (axis_x, axis_y)
+
+Half-extent axes of an area light. A rect spans `center ± axis_x ± axis_y`
+with `cross(axis_x, axis_y)` pointing along its direction; a tube runs along
+`center ± axis_x` and has a zero `axis_y`.
+"""
+function area_light_axes(light::AreaLightComponent)
+    dir = normalize(light.direction)
+    if light.shape == :tube
+        return normalize(light.tangent) * (light.width / 2), Vec3f(0, 0, 0)
+    end
+    tangent = normalize(light.tangent - dir * dot(light.tangent, dir))
+    bitangent = cross(dir, tangent)
+    return tangent * (light.width / 2), bitangent * (light.height / 2)
+end
+
 """
     prepare_frame(scene::Scene, bounds_cache::Dict{EntityID, BoundingSphere}) -> Union{FrameData, Nothing}
 
@@ -4,9 +4,31 @@
 """
     ToneMappingMode
 
-Selectable tone mapping operator.
+Selectable tone mapping operator. `TONEMAP_AGX` and `TONEMAP_PBR_NEUTRAL`
+(Khronos PBR Neutral) are WebGPU only; other backends use Uncharted 2 instead.
 """
-@enum ToneMappingMode TONEMAP_REINHARD TONEMAP_ACES TONEMAP_UNCHARTED2
+@enum ToneMappingMode TONEMAP_REINHARD TONEMAP_ACES TONEMAP_UNCHARTED2 TONEMAP_AGX TONEMAP_PBR_NEUTRAL
+
+"""
+    ExposureMode
+
+How the HDR image is exposed before tone mapping (WebGPU):
+- `EXPOSURE_FIXED` — scaled by `2^exposure_compensation` only
+- `EXPOSURE_MANUAL` — exposed for `exposure_ev100`, like a physical camera
+- `EXPOSURE_AUTO` — EV100 metered from a luminance histogram each frame and
+  adapted over time
+"""
+@enum ExposureMode EXPOSURE_FIXED EXPOSURE_MANUAL EXPOSURE_AUTO
+
+"""
+    PostEffect
+
+Effects of the post-process stack, as ordered by `PostProcessConfig.effect_order`.
+SSAO and SSR feed the lighting pass; the HDR effects (TAA, bloom, DOF, motion
+blur) run before tone mapping and the display-space effects (color grading,
+FXAA) after it, each group in `effect_order`.
+"""
+@enum PostEffect POST_SSAO POST_SSR POST_TAA POST_BLOOM POST_DOF POST_MOTION_BLUR POST_COLOR_GRADING POST_FXAA
 
 """
     PostProcessConfig
@@ -24,6 +46,16 @@ mutable struct PostProcessConfig
     fxaa_enabled::Bool
     gamma::Float32
 
+    # Screen-space reflections
+    ssr_enabled::Bool
+    ssr_max_steps::Int
+    ssr_max_distance::Float32
+    ssr_thickness::Float32
+
+    # Temporal anti-aliasing
+    taa_enabled::Bool
+    taa_feedback::Float32
+
     # Depth of Field
     dof_enabled::Bool
     dof_focus_distance::Float32
@@ -47,8 +79,26 @@ mutable struct PostProcessConfig
     color_grading_brightness::Float32
     color_grading_contrast::Float32
     color_grading_saturation::Float32
+    color_lut_path::Union{String, Nothing}  # .cube 3D LUT, applied before the adjustments above
+    color_lut_strength::Float32
+
+    # Exposure
+    exposure_mode::ExposureMode
+    exposure_ev100::Float32
+    exposure_compensation::Float32          # stops, in every mode
+    auto_exposure_min_ev100::Float32
+    auto_exposure_max_ev100::Float32
+    auto_exposure_speed_up::Float32         # adaptation rate (1/s) towards a brighter scene
+    auto_exposure_speed_down::Float32
+
+    # Lens and film (0 = off)
+    chromatic_aberration::Float32
+    film_grain::Float32
 
-    PostProcessConfig(;
+    # Run order of the effects (every PostEffect once)
+    effect_order::Vector{PostEffect}
+
+    function PostProcessConfig(;
         bloom_enabled::Bool = false,
         bloom_threshold::Float32 = 1.0f0,
         bloom_intensity::Float32 = 0.3f0,
@@ -58,6 +108,12 @@ mutable struct PostProcessConfig
         tone_mapping::ToneMappingMode = TONEMAP_REINHARD,
         fxaa_enabled::Bool = false,
         gamma::Float32 = 2.2f0,
+        ssr_enabled::Bool = false,
+        ssr_max_steps::Int = 64,
+        ssr_max_distance::Float32 = 50.0f0,
+        ssr_thickness::Float32 = 0.1f0,
+        taa_enabled::Bool = false,
+        taa_feedback::Float32 = 0.9f0,
         dof_enabled::Bool = false,
         dof_focus_distance::Float32 = 10.0f0,
         dof_focus_range::Float32 = 5.0f0,
@@ -73,12 +129,136 @@ mutable struct PostProcessConfig
         color_grading_enabled::Bool = false,
         color_grading_brightness::Float32 = 0.0f0,
         color_grading_contrast::Float32 = 1.0f0,
-        color_grading_saturation::Float32 = 1.0f0
-    ) = new(bloom_enabled, bloom_threshold, bloom_intensity,
+        color_grading_saturation::Float32 = 1.0f0,
+        color_lut_path::Union{String, Nothing} = nothing,
+        color_lut_strength::Float32 = 1.0f0,
+        exposure_mode::ExposureMode = EXPOSURE_FIXED,
+        exposure_ev100::Float32 = 0.0f0,
+        exposure_compensation::Float32 = 0.0f0,
+        auto_exposure_min_ev100::Float32 = -4.0f0,
+        auto_exposure_max_ev100::Float32 = 16.0f0,
+        auto_exposure_speed_up::Float32 = 3.0f0,
+        auto_exposure_speed_down::Float32 = 1.0f0,
+        chromatic_aberration::Float32 = 0.0f0,
+        film_grain::Float32 = 0.0f0,
+        effect_order::Vector{PostEffect} = collect(instances(PostEffect))
+    )
+        sort(effect_order) == collect(instances(PostEffect)) ||
+            throw(ArgumentError("effect_order must list every PostEffect exactly once"))
+        0.0f0 <= taa_feedback < 1.0f0 || throw(ArgumentError("taa_feedback must be in [0, 1)"))
+        0.0f0 <= color_lut_strength <= 1.0f0 || throw(ArgumentError("color_lut_strength must be in [0, 1]"))
+        auto_exposure_min_ev100 < auto_exposure_max_ev100 ||
+            throw(ArgumentError("auto_exposure_min_ev100 must be below auto_exposure_max_ev100"))
+        auto_exposure_speed_up > 0 && auto_exposure_speed_down > 0 ||
+            throw(ArgumentError("auto exposure speeds must be > 0"))
+        chromatic_aberration >= 0 && film_grain >= 0 ||
+            throw(ArgumentError("chromatic_aberration and film_grain must be >= 0"))
+        new(bloom_enabled, bloom_threshold, bloom_intensity,
             ssao_enabled, ssao_radius, ssao_samples,
             tone_mapping, fxaa_enabled, gamma,
+            ssr_enabled, ssr_max_steps, ssr_max_distance, ssr_thickness,
+            taa_enabled, taa_feedback,
             dof_enabled, dof_focus_distance, dof_focus_range, dof_bokeh_radius,
             motion_blur_enabled, motion_blur_intensity, motion_blur_samples, motion_blur_max_velocity,
             vignette_enabled, vignette_intensity, vignette_radius, vignette_softness,
-            color_grading_enabled, color_grading_brightness, color_grading_contrast, color_grading_saturation)
+            color_grading_enabled, color_grading_brightness, color_grading_contrast, color_grading_saturation,
+            color_lut_path, color_lut_strength,
+            exposure_mode, exposure_ev100, exposure_compensation,
+            auto_exposure_min_ev100, auto_exposure_max_ev100, auto_exposure_speed_up, auto_exposure_speed_down,
+            chromatic_aberration, film_grain,
+            effect_order)
+    end
+end
+
+"""
+    post_effect_enabled(config::PostProcessConfig, effect::PostEffect) -> Bool
+
+Whether `config` turns `effect` on. A color LUT turns color grading on.
+"""
+function post_effect_enabled(config::PostProcessConfig, effect::PostEffect)
+    effect == POST_SSAO && return config.ssao_enabled
+    effect == POST_SSR && return config.ssr_enabled
+    effect == POST_TAA && return config.taa_enabled
+    effect == POST_BLOOM && return config.bloom_enabled
+    effect == POST_DOF && return config.dof_enabled
+    effect == POST_MOTION_BLUR && return config.motion_blur_enabled
+    effect == POST_COLOR_GRADING && return (config.color_grading_enabled || config.color_lut_path !== nothing)
+    return config.fxaa_enabled
+end
+
+"""
+    DisplayMode
+
+Output the WebGPU backend presents to:
+- `DISPLAY_SDR` — 8-bit sRGB swapchain
+- `DISPLAY_HDR_SCRGB` — `Rgba16Float` swapchain in extended linear sRGB
+- `DISPLAY_HDR10` — 10-bit swapchain with Rec.2020 primaries and the PQ curve
+
+HDR modes fall back to the other HDR format, then to SDR, when the surface
+does not offer theirs.
+"""
+@enum DisplayMode DISPLAY_SDR DISPLAY_HDR_SCRGB DISPLAY_HDR10
+
+"""
+    DisplayConfig
+
+Display output of the WebGPU backend. On HDR outputs a tone mapped 1.0 is
+shown at `paper_white_nits`, and the tone mapper compresses highlights into
+`max_nits` instead of 1.0.
+"""
+struct DisplayConfig
+    mode::DisplayMode
+    paper_white_nits::Float32
+    max_nits::Float32
+
+    function DisplayConfig(;
+        mode::DisplayMode = DISPLAY_SDR,
+        paper_white_nits::Real = 200,
+        max_nits::Real = 1000
+    )
+        paper_white_nits > 0 || throw(ArgumentError("paper_white_nits must be > 0, got $paper_white_nits"))
+        paper_white_nits <= max_nits <= 10000 ||
+            throw(ArgumentError("max_nits must be in [paper_white_nits, 10000], got $max_nits"))
+        new(mode, Float32(paper_white_nits), Float32(max_nits))
+    end
+end
+
+"""
+    ResolutionConfig
+
+Internal render resolution and multisampling of the WebGPU backend. The scene
+renders at `render_scale` times the window size and is upscaled with FSR1
+(EASU), then sharpened with RCAS by `sharpness`. With `dynamic_resolution`
+the scale instead follows the GPU frame time towards `target_frame_ms`,
+within `[min_render_scale, max_render_scale]`. `msaa_samples` (1, 2, 4 or 8)
+antialiases the forward (transparent) and UI passes.
+"""
+struct ResolutionConfig
+    render_scale::Float32
+    dynamic_resolution::Bool
+    target_frame_ms::Float32
+    min_render_scale::Float32
+    max_render_scale::Float32
+    sharpness::Float32
+    msaa_samples::Int
+
+    function ResolutionConfig(;
+        render_scale::Real = 1.0,
+        dynamic_resolution::Bool = false,
+        target_frame_ms::Real = 16.6,
+        min_render_scale::Real = 0.5,
+        max_render_scale::Real = 1.0,
+        sharpness::Real = 0.8,
+        msaa_samples::Integer = 1
+    )
+        0.25 <= render_scale <= 1 || throw(ArgumentError("render_scale must be in [0.25, 1], got $render_scale"))
+        0.25 <= min_render_scale <= max_render_scale <= 1 ||
+            throw(ArgumentError("render scale range must lie within [0.25, 1], got [$min_render_scale, $max_render_scale]"))
+        (isfinite(target_frame_ms) && target_frame_ms > 0) ||
+            throw(ArgumentError("target_frame_ms must be finite and > 0, got $target_frame_ms"))
+        0 <= sharpness <= 1 || throw(ArgumentError("sharpness must be in [0, 1], got $sharpness"))
+        msaa_samples in (1, 2, 4, 8) || throw(ArgumentError("msaa_samples must be 1, 2, 4 or 8, got $msaa_samples"))
+        new(Float32(render_scale), dynamic_resolution, Float32(target_frame_ms),
+            Float32(min_render_scale), Float32(max_render_scale), Float32(sharpness), Int(msaa_samples))
+    end
 end
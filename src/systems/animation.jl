@@ -41,11 +41,16 @@ end
 function _apply_channel!(channel::AnimationChannel, t::Float32)
     isempty(channel.times) && return
 
+    idx_a, idx_b, lerp_t = _find_keyframe_pair(channel.times, t)
+
+    if channel.target_property == :weights
+        _apply_morph_weights!(channel, idx_a, idx_b, lerp_t)
+        return
+    end
+
     tc = get_component(channel.target_entity, TransformComponent)
     tc === nothing && return
 
-    idx_a, idx_b, lerp_t = _find_keyframe_pair(channel.times, t)
-
     if channel.target_property == :position
         va = channel.values[idx_a]::Vec3d
         vb = channel.values[idx_b]::Vec3d
@@ -72,3 +77,20 @@ function _apply_channel!(channel::AnimationChannel, t::Float32)
         end
     end
 end
+
+function _apply_morph_weights!(channel::AnimationChannel, idx_a::Int, idx_b::Int, lerp_t::Float32)
+    mesh = get_component(channel.target_entity, MeshComponent)
+    mesh === nothing && return
+
+    # Cubic-spline channels store [in_tangent, value, out_tangent] per key
+    if channel.interpolation == INTERP_CUBICSPLINE
+        idx_a, idx_b = 3 * idx_a - 1, 3 * idx_b - 1
+    end
+    wa = channel.values[idx_a]::Vector{Float32}
+    wb = channel.values[idx_b]::Vector{Float32}
+    n = min(length(mesh.morph_weights), length(wa), length(wb))
+    for i in 1:n
+        mesh.morph_weights[i] = channel.interpolation == INTERP_STEP ? wa[i] :
+                                wa[i] + (wb[i] - wa[i]) * lerp_t
+    end
+end
@@ -17,24 +17,43 @@ Particle() = Particle(Vec3f(0,0,0), Vec3f(0,0,0), 0.0f0, 1.0f0, 0.1f0, false)
 
 # ---- ParticlePool ----
 
+"""
+    MeshEmissionSurface
+
+Triangles of an emitter mesh with their cumulative area, so that surface
+points can be picked uniformly by area.
+"""
+struct MeshEmissionSurface
+    triangles::Vector{NTuple{3, Vec3f}}
+    cdf::Vector{Float32}            # normalized cumulative area, last = 1
+end
+
 mutable struct ParticlePool
     particles::Vector{Particle}
     alive_count::Int
-    # Per-particle vertex data (rebuilt each frame): 6 verts * 8 floats (pos3 + uv2 + rgba3)
+    # Per-particle vertex data (rebuilt each frame): 6 verts * 9 floats (pos3 + uv2 + color4)
     vertex_data::Vector{Float32}
     vertex_count::Int
+    # Built on first use by EMIT_MESH emitters
+    mesh_surface::Union{MeshEmissionSurface, Nothing}
 end
 
 function ParticlePool(max_particles::Int)
     particles = [Particle() for _ in 1:max_particles]
     # 6 vertices per particle, 9 floats each (pos3 + uv2 + color4)
     vertex_data = zeros(Float32, max_particles * 6 * 9)
-    ParticlePool(particles, 0, vertex_data, 0)
+    ParticlePool(particles, 0, vertex_data, 0, nothing)
 end
 
 # Global pool storage
 const PARTICLE_POOLS = Dict{EntityID, ParticlePool}()
 
+# Set by backends that simulate particles on the device (WebGPU). The CPU
+# system then only accumulates the time step, which the backend consumes in
+# render_frame!.
+const BACKEND_SIMULATES_PARTICLES = Ref(false)
+const BACKEND_PARTICLE_DT = Ref(0.0f0)
+
 """
     reset_particle_pools!()
 
@@ -60,18 +79,154 @@ end
     a + (b - a) * t
 end
 
+# ---- Emitter shapes and curves ----
+# These mirror openreality-gpu-shared/src/particles.rs and particle_compute.wgsl
+# so every runtime emits and ages particles the same way.
+
+"""
+    MeshEmissionSurface(mesh::MeshComponent)
+
+Returns `nothing` for meshes without triangle area.
+"""
+function MeshEmissionSurface(mesh::MeshComponent)
+    triangles = NTuple{3, Vec3f}[]
+    areas = Float32[]
+    verts = mesh.vertices
+    for i in 1:3:(length(mesh.indices) - 2)
+        a, b, c = (Vec3f(verts[Int(mesh.indices[i + k]) + 1]) for k in 0:2)
+        area = 0.5f0 * Float32(norm(cross(b - a, c - a)))
+        area > 0.0f0 || continue
+        push!(triangles, (a, b, c))
+        push!(areas, area)
+    end
+    isempty(triangles) && return nothing
+    cdf = cumsum(areas)
+    cdf ./= cdf[end]
+    return MeshEmissionSurface(triangles, cdf)
+end
+
+function _sample_mesh_surface(surface::MeshEmissionSurface)
+    i = min(searchsortedfirst(surface.cdf, rand(Float32)), length(surface.triangles))
+    a, b, c = surface.triangles[i]
+    s = sqrt(rand(Float32))
+    v = rand(Float32)
+    pos = a * (1.0f0 - s) + b * (s * (1.0f0 - v)) + c * (s * v)
+    return pos, Vec3f(normalize(cross(b - a, c - a)))
+end
+
+"""
+    _sample_emitter_shape(comp, surface) -> (offset, direction)
+
+Spawn offset in emitter space and the direction local +Y velocity is rotated onto.
+"""
+function _sample_emitter_shape(comp::ParticleSystemComponent,
+                               surface::Union{MeshEmissionSurface, Nothing})
+    up = Vec3f(0, 1, 0)
+    if comp.shape == EMIT_SPHERE
+        z = 2.0f0 * rand(Float32) - 1.0f0
+        phi = 2.0f0 * Float32(π) * rand(Float32)
+        r = sqrt(max(1.0f0 - z * z, 0.0f0))
+        dir = Vec3f(r * cos(phi), z, r * sin(phi))
+        dist = comp.shape_surface_only ? comp.shape_radius : comp.shape_radius * cbrt(rand(Float32))
+        return dir * dist, dir
+    elseif comp.shape == EMIT_CONE
+        cos_theta = 1.0f0 - rand(Float32) * (1.0f0 - cos(comp.shape_angle))
+        sin_theta = sqrt(max(1.0f0 - cos_theta * cos_theta, 0.0f0))
+        phi = 2.0f0 * Float32(π) * rand(Float32)
+        dir = Vec3f(sin_theta * cos(phi), cos_theta, sin_theta * sin(phi))
+        offset = Vec3f(cos(phi), 0, sin(phi)) * (comp.shape_radius * sqrt(rand(Float32)))
+        return offset, dir
+    elseif comp.shape == EMIT_BOX
+        u = Vec3f(rand(Float32), rand(Float32), rand(Float32))
+        return (u * 2.0f0 .- 1.0f0) .* comp.shape_half_extents, up
+    elseif comp.shape == EMIT_MESH && surface !== nothing
+        return _sample_mesh_surface(surface)
+    end
+    return Vec3f(0, 0, 0), up
+end
+
+# Rotate `v` by the shortest arc taking +Y onto `dir` (Rodrigues)
+function _orient_velocity(v::Vec3f, dir::Vec3f)::Vec3f
+    c = dir[2]
+    c > 0.99999f0 && return v
+    c < -0.99999f0 && return Vec3f(v[1], -v[2], -v[3])
+    k = Vec3f(normalize(Vec3f(dir[3], 0, -dir[1])))
+    s = sqrt(1.0f0 - c * c)
+    return v * c + cross(k, v) * s + k * (dot(k, v) * (1.0f0 - c))
+end
+
+# Piecewise-linear lookup; `nothing` for an empty curve
+function _sample_particle_curve(curve::Vector{Pair{Float32, T}}, t::Float32) where T
+    isempty(curve) && return nothing
+    t <= first(curve).first && return first(curve).second
+    t >= last(curve).first && return last(curve).second
+    i = findlast(k -> k.first <= t, curve)
+    (t0, v0), (t1, v1) = curve[i], curve[i + 1]
+    f = t1 > t0 ? (t - t0) / (t1 - t0) : 0.0f0
+    return _curve_lerp(v0, v1, f)
+end
+
+_curve_lerp(a::Float32, b::Float32, f::Float32) = _lerp(a, b, f)
+_curve_lerp(a::Vec3f, b::Vec3f, f::Float32) = a + (b - a) * f
+_curve_lerp(a::RGBA{Float32}, b::RGBA{Float32}, f::Float32) =
+    RGBA{Float32}(_lerp(a.r, b.r, f), _lerp(a.g, b.g, f), _lerp(a.b, b.b, f), _lerp(a.alpha, b.alpha, f))
+
+"""
+    _sheet_frame_uv(comp, age, t) -> (u0, v0, su, sv)
+
+Atlas offset and scale of the current texture-sheet frame.
+"""
+function _sheet_frame_uv(comp::ParticleSystemComponent, age::Float32, t::Float32)
+    tiles_x = max(comp.sheet_tiles_x, 1)
+    tiles_y = max(comp.sheet_tiles_y, 1)
+    total = tiles_x * tiles_y
+    frames = comp.sheet_frame_count > 0 ? min(comp.sheet_frame_count, total) : total
+    frame = if comp.sheet_fps > 0.0f0
+        mod(floor(Int, age * comp.sheet_fps), frames)
+    else
+        min(floor(Int, t * frames), frames - 1)
+    end
+    su = 1.0f0 / tiles_x
+    sv = 1.0f0 / tiles_y
+    return Float32(frame % tiles_x) * su, Float32(frame ÷ tiles_x) * sv, su, sv
+end
+
+function _collide_ground_plane!(p::Particle, comp::ParticleSystemComponent)
+    (p.position[2] >= comp.plane_height || p.velocity[2] > 0.0f0) && return false
+    keep = 1.0f0 - clamp(comp.friction, 0.0f0, 1.0f0)
+    p.position = Vec3f(p.position[1], comp.plane_height, p.position[3])
+    p.velocity = Vec3f(p.velocity[1] * keep, -p.velocity[2] * comp.bounce, p.velocity[3] * keep)
+    return true
+end
+
 # ---- Core simulation ----
 
 """
     _emit_particle!(pool, comp, origin)
+    _emit_particle!(pool, comp, world::Mat4d)
 
-Find a dead particle and initialize it.
+Find a dead particle and initialize it at a point sampled from the emitter
+shape. Shapes scale with the world transform; velocities only rotate.
 """
 function _emit_particle!(pool::ParticlePool, comp::ParticleSystemComponent, origin::Vec3f)
+    axes = (Vec3f(1, 0, 0), Vec3f(0, 1, 0), Vec3f(0, 0, 1))
+    return _emit_particle!(pool, comp, origin, axes)
+end
+
+function _emit_particle!(pool::ParticlePool, comp::ParticleSystemComponent, world::AbstractMatrix)
+    origin = Vec3f(world[1, 4], world[2, 4], world[3, 4])
+    axes = ntuple(j -> Vec3f(world[1, j], world[2, j], world[3, j]), 3)
+    return _emit_particle!(pool, comp, origin, axes)
+end
+
+function _emit_particle!(pool::ParticlePool, comp::ParticleSystemComponent, origin::Vec3f,
+                         axes::NTuple{3, Vec3f})
     for p in pool.particles
         if !p.alive
-            p.position = origin
-            p.velocity = _rand_range_vec3(comp.velocity_min, comp.velocity_max)
+            offset, dir = _sample_emitter_shape(comp, pool.mesh_surface)
+            local_velocity = _orient_velocity(_rand_range_vec3(comp.velocity_min, comp.velocity_max), dir)
+            p.position = origin + axes[1] * offset[1] + axes[2] * offset[2] + axes[3] * offset[3]
+            p.velocity = sum(Vec3f(normalize(axes[i])) * local_velocity[i] for i in 1:3)
             p.max_lifetime = _rand_range(comp.lifetime_min, comp.lifetime_max)
             p.lifetime = p.max_lifetime
             p.size = _rand_range(comp.start_size_min, comp.start_size_max)
@@ -103,7 +258,16 @@ function _simulate_particles!(pool::ParticlePool, comp::ParticleSystemComponent,
         if comp.damping > 0.0f0
             p.velocity = p.velocity * (1.0f0 - comp.damping * dt)
         end
-        p.position = p.position + p.velocity * dt
+        step_velocity = p.velocity
+        if !isempty(comp.velocity_curve)
+            t = 1.0f0 - clamp(p.lifetime / p.max_lifetime, 0.0f0, 1.0f0)
+            step_velocity += _sample_particle_curve(comp.velocity_curve, t)
+        end
+        p.position = p.position + step_velocity * dt
+        # Depth-buffer collisions need the GPU path; the CPU only knows the ground plane
+        if comp.collision == PARTICLE_COLLIDE_GROUND
+            _collide_ground_plane!(p, comp)
+        end
         alive += 1
     end
     pool.alive_count = alive
@@ -161,15 +325,26 @@ function _build_billboard_vertices!(pool::ParticlePool, comp::ParticleSystemComp
         # Lifetime fraction (0 = just born, 1 = about to die)
         t = 1.0f0 - clamp(p.lifetime / p.max_lifetime, 0.0f0, 1.0f0)
 
-        # Interpolated size
-        size = _lerp(p.size, comp.end_size, t)
+        # Size: curve scales the start size, otherwise lerp to end_size
+        size_scale = _sample_particle_curve(comp.size_curve, t)
+        size = size_scale === nothing ? _lerp(p.size, comp.end_size, t) : p.size * size_scale
         half = size * 0.5f0
 
-        # Interpolated color + alpha
-        r = _lerp(comp.start_color.r, comp.end_color.r, t)
-        g = _lerp(comp.start_color.g, comp.end_color.g, t)
-        b = _lerp(comp.start_color.b, comp.end_color.b, t)
-        a = _lerp(comp.start_alpha, comp.end_alpha, t)
+        # Color + alpha: curve, otherwise start/end lerp
+        curve_color = _sample_particle_curve(comp.color_curve, t)
+        if curve_color === nothing
+            r = _lerp(comp.start_color.r, comp.end_color.r, t)
+            g = _lerp(comp.start_color.g, comp.end_color.g, t)
+            b = _lerp(comp.start_color.b, comp.end_color.b, t)
+            a = _lerp(comp.start_alpha, comp.end_alpha, t)
+        else
+            r, g, b, a = curve_color.r, curve_color.g, curve_color.b, curve_color.alpha
+        end
+
+        # Texture-sheet frame (texture rows run top-down)
+        u0, v0, su, sv = _sheet_frame_uv(comp, p.max_lifetime - p.lifetime, t)
+        u1 = u0 + su
+        v1 = v0 + sv
 
         # Billboard corners
         right = cam_right * half
@@ -192,14 +367,14 @@ function _build_billboard_vertices!(pool::ParticlePool, comp::ParticleSystemComp
         end
 
         # Triangle 1: bl, br, tr
-        _write_particle_vertex!(data, idx, bl, 0f0, 0f0, r, g, b, a); idx += 9
-        _write_particle_vertex!(data, idx, br, 1f0, 0f0, r, g, b, a); idx += 9
-        _write_particle_vertex!(data, idx, tr, 1f0, 1f0, r, g, b, a); idx += 9
+        _write_particle_vertex!(data, idx, bl, u0, v1, r, g, b, a); idx += 9
+        _write_particle_vertex!(data, idx, br, u1, v1, r, g, b, a); idx += 9
+        _write_particle_vertex!(data, idx, tr, u1, v0, r, g, b, a); idx += 9
 
         # Triangle 2: bl, tr, tl
-        _write_particle_vertex!(data, idx, bl, 0f0, 0f0, r, g, b, a); idx += 9
-        _write_particle_vertex!(data, idx, tr, 1f0, 1f0, r, g, b, a); idx += 9
-        _write_particle_vertex!(data, idx, tl, 0f0, 1f0, r, g, b, a); idx += 9
+        _write_particle_vertex!(data, idx, bl, u0, v1, r, g, b, a); idx += 9
+        _write_particle_vertex!(data, idx, tr, u1, v0, r, g, b, a); idx += 9
+        _write_particle_vertex!(data, idx, tl, u0, v0, r, g, b, a); idx += 9
 
         vert_count += 6
     end
@@ -232,6 +407,12 @@ Call once per frame before rendering.
 Uses GPU compute path on OpenGL 4.3+, CPU fallback otherwise.
 """
 function update_particles!(dt::Float32, cam_pos::Vec3f, cam_right::Vec3f, cam_up::Vec3f)
+    # Backend-owned simulation (WebGPU compute emitters)
+    if BACKEND_SIMULATES_PARTICLES[]
+        BACKEND_PARTICLE_DT[] += dt
+        return
+    end
+
     # GPU path: dispatch compute shaders for simulation
     if has_gpu_particles()
         update_gpu_particles!(dt, cam_pos, cam_right, cam_up)
@@ -259,14 +440,19 @@ function update_particles!(dt::Float32, cam_pos::Vec3f, cam_right::Vec3f, cam_up
             resize!(pool.vertex_data, comp.max_particles * 6 * 9)
         end
 
-        # World position of emitter
+        # Mesh-surface emitters sample the entity's own mesh
+        if comp.shape == EMIT_MESH && pool.mesh_surface === nothing
+            mesh = get_component(eid, MeshComponent)
+            mesh !== nothing && (pool.mesh_surface = MeshEmissionSurface(mesh))
+        end
+
+        # World transform of emitter
         world = get_world_transform(eid)
-        origin = Vec3f(Float32(world[1, 4]), Float32(world[2, 4]), Float32(world[3, 4]))
 
         # Handle burst emission
         if comp.burst_count > 0
             for _ in 1:comp.burst_count
-                _emit_particle!(pool, comp, origin) || break
+                _emit_particle!(pool, comp, world) || break
             end
             comp.burst_count = 0
         end
@@ -275,7 +461,7 @@ function update_particles!(dt::Float32, cam_pos::Vec3f, cam_right::Vec3f, cam_up
         if comp.emission_rate > 0.0f0
             comp._emit_accumulator += comp.emission_rate * dt
             while comp._emit_accumulator >= 1.0f0
-                _emit_particle!(pool, comp, origin) || break
+                _emit_particle!(pool, comp, world) || break
                 comp._emit_accumulator -= 1.0f0
             end
         end
@@ -516,9 +516,28 @@ using StaticArrays
         @testset "Light components" begin
             pl = PointLightComponent()
             @test pl.intensity == 1.0f0
+            @test !pl.casts_shadow
+            @test PointLightComponent(casts_shadow=true).casts_shadow
 
             dl = DirectionalLightComponent()
             @test dl.direction == Vec3f(0, -1, 0)
+
+            sl = SpotLightComponent(inner_angle=0.5f0, outer_angle=0.3f0)
+            @test sl.direction == Vec3f(0, -1, 0)
+            @test sl.outer_angle == sl.inner_angle  # outer cone never narrower than inner
+            @test !sl.casts_shadow
+
+            rect = AreaLightComponent(width=2.0f0, height=1.0f0)
+            ax, ay = OpenReality.area_light_axes(rect)
+            @test ax ≈ Vec3f(1, 0, 0)
+            @test ay ≈ Vec3f(0, 0, 0.5)
+            @test normalize(cross(ax, ay)) ≈ rect.direction
+
+            tube = AreaLightComponent(shape=:tube, width=4.0f0, radius=0.1f0)
+            ax, ay = OpenReality.area_light_axes(tube)
+            @test ax ≈ Vec3f(2, 0, 0)
+            @test ay == Vec3f(0, 0, 0)
+            @test_throws ArgumentError AreaLightComponent(shape=:disk)
         end
 
         @testset "ColliderComponent" begin
@@ -2088,6 +2107,112 @@ using StaticArrays
             @test TONEMAP_UNCHARTED2 isa ToneMappingMode
         end
 
+        @testset "Effect stack" begin
+            @test Int.(instances(PostEffect)) == collect(0:7)
+            config = PostProcessConfig()
+            @test config.ssr_enabled == false
+            @test config.taa_enabled == false
+            @test config.taa_feedback == 0.9f0
+            @test config.effect_order == collect(instances(PostEffect))
+            @test post_effect_enabled(config, POST_BLOOM) == false
+
+            config = PostProcessConfig(bloom_enabled=true, dof_enabled=true)
+            @test post_effect_enabled(config, POST_BLOOM)
+            @test post_effect_enabled(config, POST_DOF)
+            @test !post_effect_enabled(config, POST_MOTION_BLUR)
+
+            reordered = [POST_DOF, POST_BLOOM, POST_SSAO, POST_SSR, POST_TAA,
+                         POST_MOTION_BLUR, POST_FXAA, POST_COLOR_GRADING]
+            @test PostProcessConfig(effect_order=reordered).effect_order == reordered
+            @test_throws ArgumentError PostProcessConfig(effect_order=[POST_BLOOM, POST_FXAA])
+            @test_throws ArgumentError PostProcessConfig(effect_order=fill(POST_BLOOM, 8))
+            @test_throws ArgumentError PostProcessConfig(taa_feedback=1.0f0)
+        end
+
+        @testset "Exposure, LUT and lens settings" begin
+            @test Int(TONEMAP_AGX) == 3 && Int(TONEMAP_PBR_NEUTRAL) == 4
+            @test Int.(instances(ExposureMode)) == [0, 1, 2]
+            config = PostProcessConfig()
+            @test config.exposure_mode == EXPOSURE_FIXED
+            @test config.exposure_compensation == 0.0f0
+            @test config.color_lut_path === nothing
+            @test config.chromatic_aberration == 0.0f0 && config.film_grain == 0.0f0
+
+            # A LUT turns color grading on
+            @test !post_effect_enabled(config, POST_COLOR_GRADING)
+            graded = PostProcessConfig(color_lut_path="grades/warm.cube", exposure_mode=EXPOSURE_AUTO)
+            @test post_effect_enabled(graded, POST_COLOR_GRADING)
+            @test graded.exposure_mode == EXPOSURE_AUTO
+
+            @test_throws ArgumentError PostProcessConfig(color_lut_strength=1.5f0)
+            @test_throws ArgumentError PostProcessConfig(auto_exposure_min_ev100=10.0f0, auto_exposure_max_ev100=5.0f0)
+            @test_throws ArgumentError PostProcessConfig(auto_exposure_speed_down=0.0f0)
+            @test_throws ArgumentError PostProcessConfig(film_grain=-0.1f0)
+        end
+
+        @testset "Display output" begin
+            @test Int.(instances(DisplayMode)) == [0, 1, 2]
+            config = DisplayConfig()
+            @test config.mode == DISPLAY_SDR
+            @test config.paper_white_nits == 200.0f0 && config.max_nits == 1000.0f0
+            hdr = DisplayConfig(mode=DISPLAY_HDR10, paper_white_nits=250, max_nits=4000)
+            @test hdr.max_nits / hdr.paper_white_nits == 16.0f0
+            @test_throws ArgumentError DisplayConfig(paper_white_nits=0)
+            @test_throws ArgumentError DisplayConfig(paper_white_nits=300, max_nits=200)
+            @test_throws ArgumentError DisplayConfig(max_nits=20000)
+
+            # Headless float target: needs the wgpu library and a GPU adapter
+            if isdefined(OpenReality, :WebGPUBackend)
+                backend = WebGPUBackend()
+                started = try
+                    initialize!(backend; width=4, height=2, headless=true)
+                    true
+                catch e
+                    @info "No WebGPU adapter, skipping headless output test" exception=e
+                    false
+                end
+                if started
+                    @test set_display_output!(backend, DISPLAY_HDR_SCRGB; paper_white_nits=160) == DISPLAY_HDR_SCRGB
+                    @test OpenReality.wgpu_render_clear(backend.backend_handle, 0.5, 2.0, 0.0) == 0
+                    pixels = read_output(backend)
+                    @test size(pixels) == (4, 8)
+                    # Cleared values land unencoded, above 1 included
+                    @test all(pixels[1:3, :] .== [0.5f0, 2.0f0, 0.0f0])
+                    shutdown!(backend)
+                end
+            end
+        end
+
+        @testset "Resolution config" begin
+            config = ResolutionConfig()
+            @test config.render_scale == 1.0f0 && !config.dynamic_resolution
+            @test config.msaa_samples == 1
+            dynamic = ResolutionConfig(dynamic_resolution=true, target_frame_ms=8.3, min_render_scale=0.25, msaa_samples=4)
+            @test dynamic.min_render_scale == 0.25f0 && dynamic.target_frame_ms == 8.3f0
+            @test_throws ArgumentError ResolutionConfig(render_scale=0.2)
+            @test_throws ArgumentError ResolutionConfig(render_scale=1.5)
+            @test_throws ArgumentError ResolutionConfig(min_render_scale=0.8, max_render_scale=0.6)
+            @test_throws ArgumentError ResolutionConfig(target_frame_ms=0)
+            @test_throws ArgumentError ResolutionConfig(target_frame_ms=NaN)
+            @test_throws ArgumentError ResolutionConfig(target_frame_ms=Inf)
+            @test_throws ArgumentError ResolutionConfig(sharpness=2)
+            @test_throws ArgumentError ResolutionConfig(msaa_samples=3)
+
+            if isdefined(OpenReality, :WebGPUBackend)
+                # Only solid-color UI commands reach the wgpu UI pass, overlay last
+                ctx = UIContext()
+                ui_rect(ctx, x=0, y=0, width=10, height=10, color=RGB{Float32}(1, 0, 0))
+                push!(ctx.draw_commands, UIDrawCommand(length(ctx.vertices), 6, UInt32(7), true))
+                append!(ctx.vertices, zeros(Float32, 48))
+                append!(ctx.overlay_vertices, ones(Float32, 48))
+                push!(ctx.overlay_draw_commands, UIDrawCommand(0, 6, UInt32(0), false))
+                vertices = OpenReality._pack_wgpu_ui_vertices(ctx)
+                @test length(vertices) == 96
+                @test vertices[1:48] == ctx.vertices[1:48]
+                @test all(==(1.0f0), vertices[49:96])
+            end
+        end
+
         @testset "Framebuffer struct" begin
             fb = Framebuffer()
             @test fb.fbo == UInt32(0)
@@ -2105,6 +2230,37 @@ using StaticArrays
         end
     end
 
+    @testset "Shadow filtering" begin
+        @testset "ShadowConfig defaults" begin
+            config = ShadowConfig()
+            @test config.filter == SHADOW_FILTER_PCF
+            @test config.pcf_samples == 16
+            @test config.pcf_radius == 1.5f0
+            @test config.blend_band == 0.1f0
+            @test config.evsm_exponents == (5.0f0, 5.0f0)
+            @test config.stabilize == true
+        end
+
+        @testset "ShadowConfig custom" begin
+            config = ShadowConfig(filter=SHADOW_FILTER_PCSS, pcf_samples=32, light_size=0.05f0,
+                                  blend_band=0.2f0, stabilize=false)
+            @test config.filter == SHADOW_FILTER_PCSS
+            @test config.pcf_samples == 32
+            @test config.light_size == 0.05f0
+            @test config.stabilize == false
+            @test_throws ArgumentError ShadowConfig(pcf_samples=0)
+            @test_throws ArgumentError ShadowConfig(pcf_samples=64)
+            @test_throws ArgumentError ShadowConfig(blend_band=1.5f0)
+        end
+
+        @testset "ShadowFilterMode matches the WebGPU filter ids" begin
+            @test Int(SHADOW_FILTER_HARD) == 0
+            @test Int(SHADOW_FILTER_PCF) == 1
+            @test Int(SHADOW_FILTER_PCSS) == 2
+            @test Int(SHADOW_FILTER_EVSM) == 3
+        end
+    end
+
     @testset "Windowing" begin
         win = Window()
         @test win.width == 1280
@@ -3452,6 +3608,96 @@ using StaticArrays
             @test alive_positions[2] >= alive_positions[3]
         end
 
+        @testset "Emitter shapes" begin
+            comp = ParticleSystemComponent(max_particles=200, shape=EMIT_SPHERE, shape_radius=2.0f0,
+                                           lifetime_min=10.0f0, lifetime_max=10.0f0)
+            pool = ParticlePool(200)
+            for _ in 1:200
+                OpenReality._emit_particle!(pool, comp, Vec3f(0, 5, 0))
+            end
+            @test all(p -> norm(p.position - Vec3f(0, 5, 0)) <= 2.0f0 + 1f-4, pool.particles)
+
+            comp.shape_surface_only = true
+            pool = ParticlePool(20)
+            for _ in 1:20
+                OpenReality._emit_particle!(pool, comp, Vec3f(0, 0, 0))
+            end
+            @test all(p -> isapprox(norm(p.position), 2.0f0; atol=1f-4), pool.particles)
+
+            # Box extents scale with the emitter transform
+            comp = ParticleSystemComponent(max_particles=50, shape=EMIT_BOX,
+                                           shape_half_extents=Vec3f(1, 0, 1),
+                                           lifetime_min=10.0f0, lifetime_max=10.0f0)
+            pool = ParticlePool(50)
+            world = Mat4d(2, 0, 0, 0,  0, 1, 0, 0,  0, 0, 2, 0,  0, 0, 0, 1)
+            for _ in 1:50
+                OpenReality._emit_particle!(pool, comp, world)
+            end
+            @test all(p -> abs(p.position[1]) <= 2.0f0 && p.position[2] == 0.0f0, pool.particles)
+            @test any(p -> abs(p.position[1]) > 1.0f0, pool.particles)
+
+            # Cone: +Y velocity is rotated within the half-angle
+            comp = ParticleSystemComponent(max_particles=50, shape=EMIT_CONE, shape_angle=0.3f0,
+                                           shape_radius=0.0f0,
+                                           velocity_min=Vec3f(0, 1, 0), velocity_max=Vec3f(0, 1, 0),
+                                           lifetime_min=10.0f0, lifetime_max=10.0f0)
+            pool = ParticlePool(50)
+            for _ in 1:50
+                OpenReality._emit_particle!(pool, comp, Vec3f(0, 0, 0))
+            end
+            @test all(p -> p.velocity[2] >= cos(0.3f0) - 1f-4, pool.particles)
+
+            # Mesh surface: points land on the triangle
+            mesh = MeshComponent(vertices=[Point3f(0, 0, 0), Point3f(1, 0, 0), Point3f(0, 0, 1)],
+                                 indices=UInt32[0, 1, 2])
+            comp = ParticleSystemComponent(max_particles=20, shape=EMIT_MESH,
+                                           lifetime_min=10.0f0, lifetime_max=10.0f0)
+            pool = ParticlePool(20)
+            pool.mesh_surface = OpenReality.MeshEmissionSurface(mesh)
+            for _ in 1:20
+                OpenReality._emit_particle!(pool, comp, Vec3f(0, 0, 0))
+            end
+            @test all(p -> p.position[2] == 0.0f0 && p.position[1] + p.position[3] <= 1.0f0 + 1f-5,
+                      pool.particles)
+            @test OpenReality.MeshEmissionSurface(MeshComponent()) === nothing
+        end
+
+        @testset "Particle curves and texture sheets" begin
+            curve = [0.0f0 => 1.0f0, 0.5f0 => 3.0f0, 1.0f0 => 0.0f0]
+            @test OpenReality._sample_particle_curve(curve, 0.25f0) ≈ 2.0f0
+            @test OpenReality._sample_particle_curve(curve, 2.0f0) == 0.0f0
+            @test OpenReality._sample_particle_curve(Pair{Float32, Float32}[], 0.5f0) === nothing
+
+            comp = ParticleSystemComponent(sheet_tiles_x=4, sheet_tiles_y=2)
+            @test all(OpenReality._sheet_frame_uv(comp, 0.0f0, 0.7f0) .≈ (0.25f0, 0.5f0, 0.25f0, 0.5f0))
+            comp.sheet_fps = 10.0f0
+            # 9.5 frames in at 10 fps wraps to frame 1 of 8
+            @test OpenReality._sheet_frame_uv(comp, 0.95f0, 0.0f0)[1:2] == (0.25f0, 0.0f0)
+        end
+
+        @testset "Ground plane collision" begin
+            comp = ParticleSystemComponent(max_particles=1, gravity_modifier=0.0f0,
+                                           velocity_min=Vec3f(1, -4, 0), velocity_max=Vec3f(1, -4, 0),
+                                           lifetime_min=10.0f0, lifetime_max=10.0f0,
+                                           collision=PARTICLE_COLLIDE_GROUND, plane_height=0.0f0,
+                                           bounce=0.5f0, friction=0.0f0)
+            pool = ParticlePool(1)
+            OpenReality._emit_particle!(pool, comp, Vec3f(0, 1, 0))
+            OpenReality._simulate_particles!(pool, comp, 0.5f0)
+            p = pool.particles[1]
+            @test p.position[2] == 0.0f0
+            @test p.velocity ≈ Vec3f(1, 2, 0)
+        end
+
+        @testset "Particle blend mode" begin
+            comp = ParticleSystemComponent(additive=true)
+            @test comp.blend_mode == PARTICLE_BLEND_ADDITIVE
+            comp.blend_mode = PARTICLE_BLEND_PREMULTIPLIED
+            @test comp.additive == false
+            comp.additive = true
+            @test comp.blend_mode == PARTICLE_BLEND_ADDITIVE
+        end
+
         @testset "update_particles! with no emitters" begin
             
             reset_component_stores!()
@@ -3537,9 +3783,18 @@ using StaticArrays
     end
 
     @testset "ORSB Scene Export" begin
+        # Export to memory; returns the bundle and its sections keyed by name
+        function export_sections(s; physics_config=PhysicsWorldConfig(), compress_textures=true,
+                                 prefabs=Dict{String, EntityID}())
+            io = IOBuffer()
+            stats = OpenReality._write_orsb(io, s, physics_config, compress_textures, prefabs)
+            data = take!(io)
+            return data, Dict(name => data[range] for (name, range) in stats.sections)
+        end
+
         @testset "Export constants" begin
             @test OpenReality.ORSB_MAGIC == UInt8['O', 'R', 'S', 'B']
-            @test OpenReality.ORSB_VERSION == UInt32(1)
+            @test OpenReality.ORSB_VERSION == UInt32(3)
         end
 
         @testset "Empty scene export roundtrip" begin
@@ -3557,7 +3812,7 @@ using StaticArrays
                 # Check magic
                 @test data[1:4] == UInt8['O', 'R', 'S', 'B']
                 # Version
-                @test reinterpret(UInt32, data[5:8])[1] == UInt32(1)
+                @test reinterpret(UInt32, data[5:8])[1] == UInt32(3)
                 # 0 entities
                 @test reinterpret(UInt32, data[13:16])[1] == UInt32(0)
             finally
@@ -3676,6 +3931,340 @@ using StaticArrays
                 isfile(tmp) && rm(tmp)
             end
         end
+
+        @testset "Entity with spot and area lights" begin
+
+            reset_component_stores!()
+
+            eid1 = create_entity!(World())
+            add_component!(eid1, transform(position=Vec3d(0.0, 4.0, 0.0)))
+            add_component!(eid1, SpotLightComponent(intensity=20.0f0, casts_shadow=true))
+
+            eid2 = create_entity!(World())
+            add_component!(eid2, transform())
+            add_component!(eid2, AreaLightComponent(shape=:tube, width=2.0f0, radius=0.05f0))
+
+            data, sections = export_sections(add_entity(add_entity(scene(), eid1), eid2))
+            @test reinterpret(UInt32, data[13:16])[1] == UInt32(2)
+            # No point or dir lights, then one spot (56 bytes) and one area (72 bytes) record
+            lights = sections[:lights]
+            @test reinterpret(UInt32, lights[1:12]) == UInt32[0, 0, 1]
+            spot = lights[13:68]
+            @test reinterpret(Float32, spot[37:40])[1] == 20.0f0
+            @test spot[53] == 0x01
+            @test reinterpret(UInt32, lights[69:72])[1] == UInt32(1)
+            area = lights[73:end]
+            @test length(area) == 72
+            @test area[1] == 0x01
+            @test reinterpret(Float32, area[69:72])[1] == 0.05f0
+        end
+
+        @testset "Animation state machine export" begin
+
+            reset_component_stores!()
+
+            eid = create_entity!(World())
+            add_component!(eid, transform())
+            idle = AnimationClip("idle", AnimationChannel[], 1.0f0)
+            walk = AnimationClip("walk", AnimationChannel[], 1.0f0)
+            add_component!(eid, AnimationComponent(clips=[idle, walk], active_clip=1, playing=true))
+            add_component!(eid, AnimationStateMachineComponent(
+                parameters=[AnimParameter("speed", ANIM_PARAM_FLOAT, 0.0f0)],
+                layers=[AnimLayer("base", [AnimState("idle", 1), AnimState("walk", 2)], "idle";
+                    transitions=[AnimTransition("idle", "walk";
+                                         conditions=[AnimCondition("speed", COND_GREATER, 0.1f0)]),
+                                 AnimTransition("run", "idle"),
+                                 AnimTransition(nothing, "idle"; duration=0.5f0)])]
+            ))
+
+            _, sections = export_sections(add_entity(scene(), eid))
+            io = IOBuffer(sections[:state_machines])
+            read_name(io) = String(read(io, read(io, UInt16)))
+            @test read(io, UInt32) == UInt32(1)
+            @test read(io, UInt32) == UInt32(0)  # animation index
+            @test read(io, UInt16) == UInt16(1)
+            @test read_name(io) == "speed"
+            @test read(io, UInt8) == UInt8(Int(ANIM_PARAM_FLOAT))
+            @test read(io, Float32) == 0.0f0
+            @test read(io, UInt16) == UInt16(1)
+            @test read_name(io) == "base"
+            @test read(io, Float32) == 1.0f0
+            read(io, UInt8)  # blend mode
+            @test read(io, UInt32) == UInt32(0)  # no mask
+            @test read(io, UInt16) == UInt16(2)
+            for (name, clip) in (("idle", 0), ("walk", 1))
+                @test read_name(io) == name
+                @test read(io, UInt32) == UInt32(clip)
+                @test read(io, Float32) == 1.0f0
+                @test read(io, UInt8) == 0x01
+            end
+            @test read(io, UInt16) == UInt16(0)  # default state
+            # The transition from the unknown "run" state is dropped
+            @test read(io, UInt16) == UInt16(2)
+            @test (read(io, UInt16), read(io, UInt16)) == (UInt16(0), UInt16(1))
+            read(io, Float32); read(io, Float32)
+            @test read(io, UInt16) == UInt16(1)
+            @test read(io, UInt16) == UInt16(0)
+            @test read(io, UInt8) == UInt8(Int(COND_GREATER))
+            @test read(io, Float32) == 0.1f0
+            @test (read(io, UInt16), read(io, UInt16)) == (typemax(UInt16), UInt16(0))
+            @test read(io, Float32) == 0.5f0
+            read(io, Float32)
+            @test read(io, UInt16) == UInt16(0)
+            @test eof(io)
+
+            reset_component_stores!()
+            _, sections = export_sections(scene())
+            @test sections[:state_machines] == reinterpret(UInt8, [UInt32(0)])
+        end
+
+        @testset "Morph target export" begin
+            verts = [Point3f(0, 0, 0), Point3f(1, 0, 0), Point3f(0, 1, 0)]
+            function export_size(targets)
+                reset_component_stores!()
+                eid = create_entity!(World())
+                add_component!(eid, transform())
+                add_component!(eid, MeshComponent(vertices=verts, indices=UInt32[0, 1, 2],
+                                                  morph_targets=targets))
+                tmp = tempname() * ".orsb"
+                try
+                    export_scene(add_entity(scene(), eid), tmp)
+                    return filesize(tmp)
+                finally
+                    isfile(tmp) && rm(tmp)
+                end
+            end
+
+            base = export_size(MorphTarget[])
+            target = MorphTarget(fill(Vec3f(0, 0, 1), 3), Vec3f[])
+            # has_normals flag + 3 position deltas
+            @test export_size([target]) == base + 1 + 3 * 12
+            with_normals = MorphTarget(fill(Vec3f(0, 0, 1), 3), fill(Vec3f(0, 1, 0), 3))
+            @test export_size([with_normals]) == base + 1 + 6 * 12
+
+            mesh = MeshComponent(vertices=verts, morph_targets=[target, target])
+            @test mesh.morph_weights == Float32[0, 0]
+        end
+
+        @testset "Morph weight animation export" begin
+            reset_component_stores!()
+            eid = create_entity!(World())
+            add_component!(eid, transform())
+            channel = AnimationChannel(eid, :weights, Float32[0.0, 1.0],
+                                       Any[Float32[0.0, 1.0], Float32[0.5, 0.25]], INTERP_STEP)
+            add_component!(eid, AnimationComponent(clips=[AnimationClip("blink", [channel], 1.0f0)]))
+
+            _, sections = export_sections(add_entity(scene(), eid))
+            io = IOBuffer(sections[:animations])
+            @test read(io, UInt32) == UInt32(1)
+            @test read(io, UInt32) == UInt32(1)
+            @test String(read(io, read(io, UInt16))) == "blink"
+            @test read(io, UInt32) == UInt32(1)
+            @test read(io, Float32) == 1.0f0
+            # Target entity, property 3 (weights), step interpolation
+            @test read(io, UInt32) == UInt32(0)
+            @test read(io, UInt8) == 0x03
+            @test read(io, UInt8) == 0x00
+            # Keyframe count, then weights per keyframe
+            @test read(io, UInt32) == UInt32(2)
+            @test read(io, UInt32) == UInt32(2)
+            @test [read(io, Float32) for _ in 1:2] == Float32[0.0, 1.0]
+            @test [read(io, Float64) for _ in 1:4] == [0.0, 1.0, 0.5, 0.25]
+        end
+
+        @testset "Particle emitter export" begin
+            function export_bytes(comp)
+                reset_component_stores!()
+                eid = create_entity!(World())
+                add_component!(eid, transform())
+                add_component!(eid, comp)
+                tmp = tempname() * ".orsb"
+                try
+                    export_scene(add_entity(scene(), eid), tmp)
+                    return read(tmp)
+                finally
+                    isfile(tmp) && rm(tmp)
+                end
+            end
+
+            plain = export_bytes(ParticleSystemComponent(friction=0.25f0))
+            # Records end with soft distance, plane height, bounce, friction
+            reset_component_stores!()
+            eid = create_entity!(World())
+            add_component!(eid, transform())
+            add_component!(eid, ParticleSystemComponent(friction=0.25f0))
+            _, sections = export_sections(add_entity(scene(), eid))
+            particles = sections[:particles]
+            @test reinterpret(UInt32, particles[1:4])[1] == UInt32(1)
+            @test reinterpret(Float32, particles[end-3:end])[1] == 0.25f0
+
+            curved = export_bytes(ParticleSystemComponent(
+                friction=0.25f0,
+                color_curve=[0.0f0 => RGBA{Float32}(1, 0, 0, 1), 1.0f0 => RGBA{Float32}(0, 0, 1, 0)],
+                size_curve=[0.5f0 => 2.0f0]))
+            # Color keys are t + rgba, size keys t + value
+            @test length(curved) == length(plain) + 2 * 20 + 8
+
+            # Sprite textures join the texture table
+            sheet = export_bytes(ParticleSystemComponent(texture=TextureRef("missing_sprite.png"),
+                                                         sheet_tiles_x=4, sheet_tiles_y=4))
+            @test reinterpret(UInt32, sheet[21:24])[1] == UInt32(1)
+        end
+
+        @testset "Entity name export" begin
+            reset_component_stores!()
+            s = scene()
+            for name in (nothing, "door")
+                eid = create_entity!(World())
+                add_component!(eid, transform())
+                name === nothing || add_component!(eid, NameComponent(name))
+                s = add_entity(s, eid)
+            end
+            _, sections = export_sections(s)
+            # Count, entity index, u16 length, bytes
+            names = sections[:entity_names]
+            @test reinterpret(UInt32, names[1:8]) == UInt32[1, 1]
+            @test reinterpret(UInt16, names[9:10])[1] == UInt16(4)
+            @test String(names[11:end]) == "door"
+        end
+
+        @testset "Web script export" begin
+            source = "fn on_update(dt) { }"
+            comp = WebScriptComponent(source; name="bob", params=Dict("speed" => 2))
+            @test comp.params == Dict("speed" => 2.0)
+
+            reset_component_stores!()
+            s = scene()
+            for _ in 1:2
+                eid = create_entity!(World())
+                add_component!(eid, transform())
+                add_component!(eid, comp)
+                s = add_entity(s, eid)
+            end
+            _, sections = export_sections(s)
+            # One shared source (name, u32 length, bytes), then two attachments
+            # of (entity, script, 1 param)
+            scripts = sections[:scripts]
+            @test reinterpret(UInt32, scripts[1:4])[1] == UInt32(1)
+            @test String(scripts[7:9]) == "bob"
+            @test reinterpret(UInt32, scripts[10:13])[1] == UInt32(length(source))
+            @test String(scripts[14:13+length(source)]) == source
+            attachments = scripts[14+length(source):end]
+            attachment = 4 + 4 + 2 + (2 + length("speed")) + 8
+            @test length(attachments) == 4 + 2 * attachment
+            @test reinterpret(UInt32, attachments[1:4])[1] == UInt32(2)
+            second = attachments[5+attachment:end]
+            @test reinterpret(UInt32, second[1:8]) == UInt32[1, 0]
+            @test String(second[13:17]) == "speed"
+            @test reinterpret(Float64, second[18:25])[1] == 2.0
+        end
+
+        @testset "Prefab export" begin
+            reset_component_stores!()
+            s = scene()
+            ids = EntityID[]
+            for _ in 1:3
+                eid = create_entity!(World())
+                add_component!(eid, transform())
+                push!(ids, eid)
+            end
+            s = add_entity(s, ids[1])
+            s = add_entity(s, ids[2])
+            s = add_entity(s, ids[3], ids[2])
+            _, sections = export_sections(s; prefabs=Dict("bullet" => ids[2]))
+            # One prefab: name, then the subtree moved to entities 1..2
+            prefab = sections[:prefabs]
+            @test reinterpret(UInt32, prefab[1:4])[1] == UInt32(1)
+            @test String(prefab[7:12]) == "bullet"
+            @test reinterpret(UInt32, prefab[13:end]) == UInt32[1, 2]
+
+            tmp = tempname() * ".orsb"
+            try
+                @test_throws ArgumentError export_scene(s, tmp; prefabs=Dict("a" => ids[2], "b" => ids[3]))
+            finally
+                isfile(tmp) && rm(tmp)
+            end
+        end
+
+        @testset "LOD export" begin
+            reset_component_stores!()
+            high = MeshComponent(vertices=[Point3f(0, 0, 0), Point3f(1, 0, 0), Point3f(0, 1, 0)], indices=UInt32[0, 1, 2])
+            low = MeshComponent(vertices=[Point3f(0, 0, 0), Point3f(1, 0, 0), Point3f(0, 1, 0)], indices=UInt32[0, 1, 2])
+            eid = create_entity!(World())
+            add_component!(eid, transform())
+            add_component!(eid, high)
+            add_component!(eid, LODComponent(levels=[LODLevel(mesh=high, max_distance=15.0f0),
+                                                     LODLevel(mesh=low, max_distance=30.0f0)],
+                                             hysteresis=1.25f0))
+            data, sections = export_sections(add_entity(scene(), eid))
+            # The coarse level's mesh joins the mesh table
+            @test reinterpret(UInt32, data[17:20])[1] == UInt32(2)
+            # Count, entity 0, hysteresis, 2 levels of (mesh, max distance)
+            lods = sections[:lods]
+            @test length(lods) == 4 + 4 + 4 + 2 + 2 * 8
+            @test reinterpret(UInt32, lods[1:8]) == UInt32[1, 0]
+            @test reinterpret(Float32, lods[9:12])[1] == 1.25f0
+            @test reinterpret(UInt16, lods[13:14])[1] == UInt16(2)
+            @test reinterpret(UInt32, lods[15:18])[1] == UInt32(0)
+            @test reinterpret(UInt32, lods[23:26])[1] == UInt32(1)
+            @test reinterpret(Float32, lods[27:30])[1] == 30.0f0
+        end
+
+        @testset "Environment export" begin
+            @test_throws ArgumentError AtmosphereComponent(mie_anisotropy=1.0f0)
+            @test_throws ArgumentError AtmosphereComponent(planet_radius=0.0f0)
+            @test_throws ArgumentError VolumetricFogComponent(density=-1.0f0)
+            @test_throws ArgumentError VolumetricFogComponent(albedo=RGB{Float32}(2, 0, 0))
+
+            reset_component_stores!()
+            eid = create_entity!(World())
+            add_component!(eid, transform())
+            add_component!(eid, AtmosphereComponent(intensity=2.0f0))
+            add_component!(eid, VolumetricFogComponent(density=0.02f0, max_distance=80.0f0))
+            _, sections = export_sections(add_entity(scene(), eid))
+            # Flags, the 68-byte atmosphere and the 44-byte fog settings
+            environment = sections[:environment]
+            @test length(environment) == 4 + 68 + 44
+            atmosphere = environment[5:72]
+            fog = environment[73:116]
+            @test reinterpret(UInt32, environment[1:4])[1] == UInt32(3)
+            @test reinterpret(Float32, atmosphere[1:12]) == Float32[5.802f-3, 13.558f-3, 33.1f-3]
+            @test reinterpret(Float32, atmosphere[61:64])[1] == 2.0f0
+            @test reinterpret(UInt32, atmosphere[65:68])[1] == UInt32(1)
+            @test reinterpret(Float32, fog[13:16])[1] == 0.02f0
+            @test reinterpret(Float32, fog[33:36])[1] == 80.0f0
+            @test reinterpret(UInt32, fog[41:44])[1] == UInt32(1)
+        end
+
+        @testset "Decal export" begin
+            @test_throws ArgumentError DecalComponent(opacity=1.5f0)
+            @test_throws ArgumentError DecalComponent(roughness_opacity=-0.1f0)
+            @test_throws ArgumentError DecalComponent(angle_fade=0.0f0)
+
+            reset_component_stores!()
+            s = scene()
+            ids = EntityID[]
+            for decal in (DecalComponent(opacity=0.5f0),
+                          DecalComponent(roughness=0.9f0, roughness_opacity=0.75f0, angle_fade=1.0f0))
+                eid = create_entity!(World())
+                add_component!(eid, transform())
+                add_component!(eid, decal)
+                s = add_entity(s, eid)
+            end
+            _, sections = export_sections(s)
+            # Count, then (entity, albedo texture, normal texture, 8 floats) per decal
+            decals = sections[:decals]
+            decal = 4 + 4 + 4 + 8 * 4
+            @test length(decals) == 4 + 2 * decal
+            @test reinterpret(UInt32, decals[1:4])[1] == UInt32(2)
+            first_decal = decals[5:4+decal]
+            second_decal = decals[5+decal:end]
+            @test reinterpret(UInt32, first_decal[1:4])[1] == UInt32(0)
+            @test reinterpret(Int32, first_decal[5:12]) == Int32[-1, -1]
+            @test reinterpret(Float32, first_decal[13:28]) == Float32[1, 1, 1, 0.5]
+            @test reinterpret(UInt32, second_decal[1:4])[1] == UInt32(1)
+            @test reinterpret(Float32, second_decal[33:44]) == Float32[0.9, 0.75, 1.0]
+        end
     end
 
     @testset "WebGPU Backend Types" begin
@@ -3703,6 +4292,20 @@ using StaticArrays
             @test t.handle == UInt64(2)
             @test t.width == 256
         end
+
+        if isdefined(OpenReality, :WebGPUBackend)
+            @testset "Bone palette packing" begin
+                translate = Mat4f(1, 0, 0, 0,  0, 1, 0, 0,  0, 0, 1, 0,  2, 3, 4, 1)
+                scale = Mat4f(2, 0, 0, 0,  0, 2, 0, 0,  0, 0, 2, 0,  0, 0, 0, 1)
+                palette = OpenReality._pack_bone_palette([translate, scale])
+                @test palette isa Vector{Float32}
+                @test length(palette) == 32
+                # Column-major, one mat4x4<f32> per bone: translation lands in floats 13-15
+                @test palette[13:16] == Float32[2, 3, 4, 1]
+                @test palette[17] == 2.0f0 && palette[22] == 2.0f0
+                @test isempty(OpenReality._pack_bone_palette(Mat4f[]))
+            end
+        end
     end
 
     @testset "Engine Reset" begin